@@ -0,0 +1,251 @@
+use browser::css;
+use browser::css::selector::CompiledSelector;
+use browser::html;
+use browser::html::arena::Arena;
+use browser::html::parser::{Parser, ParserOptions};
+use browser::html::tokenizer::Tokenizer;
+use criterion::{black_box, criterion_group, criterion_main, BatchSize, Criterion};
+
+// 200KBくらいの記事
+fn article() -> String {
+    let mut html = "<!DOCTYPE html><html><head><title>article</title></head><body>".to_string();
+    let mut i = 0;
+    while html.len() < 200 * 1024 {
+        html += &format!(
+            "<h2 id=\"section{}\">Section {}</h2>\n\
+             <p class=\"lead\">Lorem ipsum dolor sit amet, consectetur adipiscing elit, \
+             sed do eiusmod tempor incididunt ut labore et dolore magna aliqua. \
+             <a href=\"https://example.com/{}\">Ut enim</a> ad minim veniam, \
+             quis nostrud <em>exercitation</em> ullamco laboris.</p>\n",
+            i, i, i
+        );
+        i += 1;
+    }
+    html += "</body></html>";
+    return html;
+}
+
+fn deeply_nested() -> String {
+    let depth = 500;
+    let mut html = "".to_string();
+    for _ in 0..depth {
+        html += "<div class=\"level\">";
+    }
+    html += "bottom";
+    for _ in 0..depth {
+        html += "</div>";
+    }
+    return html;
+}
+
+fn attribute_heavy() -> String {
+    let mut html = "<html><body><form action=\"/submit\" method=\"post\">".to_string();
+    for i in 0..500 {
+        html += &format!(
+            "<input type=\"text\" id=\"field{}\" name=\"field{}\" value=\"value {}\" \
+             placeholder=\"enter a value\" class=\"form control\" autocomplete=\"off\"/>",
+            i, i, i
+        );
+    }
+    html += "</form><table>";
+    for i in 0..500 {
+        html += &format!(
+            "<tr class=\"row\" data_index=\"{}\"><td class=\"cell\" title=\"a\">{}</td>\
+             <td class=\"cell\" title=\"b\">{}</td></tr>",
+            i, i, i
+        );
+    }
+    html += "</table></body></html>";
+    return html;
+}
+
+// 属性が50個ある要素を並べる (属性の索引が効くところ)
+fn fifty_attributes() -> String {
+    let mut html = "<html><body>".to_string();
+    for i in 0..200 {
+        html += "<div";
+        for j in 0..50 {
+            html += &format!(" data_k{}=\"{}\"", j, i);
+        }
+        html += "></div>";
+    }
+    html += "</body></html>";
+    return html;
+}
+
+fn bench_attr_lookup(c: &mut Criterion, name: &str, html: &str) {
+    let doc = html::parse(html).unwrap();
+    let elements: Vec<_> = doc.query_selector_all("div").unwrap();
+    let mut group = c.benchmark_group(name);
+    // 最後の属性と、ない属性を名前で引く
+    group.bench_function("attr lookup", |b| {
+        b.iter(|| {
+            elements
+                .iter()
+                .filter(|el| el.attr(black_box("DATA_K49")).is_some())
+                .filter(|el| el.attr(black_box("missing")).is_none())
+                .count()
+        })
+    });
+    group.finish();
+}
+
+fn comment_heavy() -> String {
+    let mut html = "<html><body>".to_string();
+    for i in 0..2000 {
+        html += &format!("<!-- comment {} -- with - hyphens --><p>{}</p>", i, i);
+    }
+    html += "</body></html>";
+    return html;
+}
+
+fn bench_fixture(c: &mut Criterion, name: &str, html: &str) {
+    let mut group = c.benchmark_group(name);
+
+    group.bench_function("tokenize+parse", |b| {
+        b.iter(|| {
+            let mut tokenizer = Tokenizer::new(black_box(html));
+            let mut parser = Parser::new();
+            parser.parse(tokenizer.tokenize())
+        })
+    });
+
+    group.bench_function("pipelined", |b| {
+        b.iter(|| html::parse_pipelined(black_box(html)))
+    });
+
+    group.bench_function("parse", |b| {
+        b.iter_batched(
+            || Tokenizer::new(html).tokenize(),
+            |tokens| Parser::new().parse(tokens),
+            BatchSize::LargeInput,
+        )
+    });
+
+    group.finish();
+}
+
+// 木を作って捨てるまで。Box<Node>の木とArenaを比べる
+fn bench_build_drop(c: &mut Criterion, name: &str, html: &str) {
+    let mut group = c.benchmark_group(name);
+
+    group.bench_function("build+drop boxed", |b| {
+        b.iter_batched(
+            || Tokenizer::new(html).tokenize(),
+            |tokens| drop(Parser::new().parse(tokens)),
+            BatchSize::LargeInput,
+        )
+    });
+
+    group.bench_function("build+drop arena", |b| {
+        b.iter_batched(
+            || Tokenizer::new(html).tokenize(),
+            |tokens| {
+                let mut arena = Arena::new();
+                let roots = Parser::new().parse_in(&mut arena, tokens);
+                drop(arena);
+                roots
+            },
+            BatchSize::LargeInput,
+        )
+    });
+
+    group.finish();
+}
+
+// id, class, 要素名のruleを混ぜた300個ほどのstylesheet
+fn stylesheet() -> String {
+    let mut css = "* { box-sizing: border-box }\nbody p a { color: blue }\n".to_string();
+    for i in 0..100 {
+        css += &format!(
+            "#section{} {{ margin: 0 }}\n.lead.c{} {{ color: red }}\nh2 + p em:nth-child({}) {{ font-style: italic }}\n",
+            i, i, i
+        );
+    }
+    return css;
+}
+
+fn bench_match_rules(c: &mut Criterion, name: &str, html: &str) {
+    let doc = html::parse(html).unwrap();
+    let sheets = vec![css::parse_stylesheet(&stylesheet()).unwrap()];
+    let mut group = c.benchmark_group(name);
+    group.bench_function("match_rules", |b| {
+        b.iter(|| css::match_rules(black_box(&doc), black_box(&sheets)))
+    });
+    group.finish();
+}
+
+// 同じselectorを何度も使う。文字列のままだと毎回parseし直す
+fn bench_select(c: &mut Criterion, name: &str, html: &str) {
+    let doc = html::parse(html).unwrap();
+    let selectors: Vec<String> = (0..30)
+        .map(|i| format!("#section{} h2, article p.lead em, li.c{} > a", i, i))
+        .collect();
+    let compiled: Vec<CompiledSelector> = selectors
+        .iter()
+        .map(|s| CompiledSelector::compile(s).unwrap())
+        .collect();
+    let mut group = c.benchmark_group(name);
+    group.bench_function("select_string", |b| {
+        b.iter(|| {
+            for s in &selectors {
+                black_box(doc.query_selector_all(s).unwrap());
+            }
+        })
+    });
+    group.bench_function("select_compiled", |b| {
+        b.iter(|| {
+            for selector in &compiled {
+                black_box(doc.select(selector));
+            }
+        })
+    });
+    group.finish();
+}
+
+// 小さな文書をまとめて読む。parallelを付けて動かすと、coreの数に応じて速くなる
+fn bench_parse_many(c: &mut Criterion, name: &str) {
+    let inputs: Vec<String> = (0..200)
+        .map(|i| {
+            let mut html = format!("<!DOCTYPE html><html><body><h1>page {}</h1>", i);
+            for j in 0..50 {
+                html += &format!("<p class=\"c{}\">paragraph {} of page {}</p>", j, j, i);
+            }
+            html += "</body></html>";
+            return html;
+        })
+        .collect();
+    let options = ParserOptions::default();
+    let mut group = c.benchmark_group(name);
+    group.bench_function("one_by_one", |b| {
+        b.iter(|| {
+            for html in &inputs {
+                black_box(html::parse_with_options(html, &options).unwrap());
+            }
+        })
+    });
+    group.bench_function("parse_many", |b| {
+        b.iter_batched(
+            || inputs.clone(),
+            |inputs| black_box(html::parse_many(inputs, &options)),
+            BatchSize::SmallInput,
+        )
+    });
+    group.finish();
+}
+
+fn parser_benchmark(c: &mut Criterion) {
+    bench_fixture(c, "article", &article());
+    bench_fixture(c, "deeply_nested", &deeply_nested());
+    bench_fixture(c, "attribute_heavy", &attribute_heavy());
+    bench_fixture(c, "comment_heavy", &comment_heavy());
+    bench_fixture(c, "fifty_attributes", &fifty_attributes());
+    bench_attr_lookup(c, "fifty_attributes", &fifty_attributes());
+    bench_build_drop(c, "article", &article());
+    bench_match_rules(c, "article", &article());
+    bench_select(c, "article", &article());
+    bench_parse_many(c, "two_hundred_pages");
+}
+
+criterion_group!(benches, parser_benchmark);
+criterion_main!(benches);
@@ -0,0 +1,57 @@
+use browser::css::stylesheet::CssFormat;
+use browser::html;
+use browser::html::errors::LoadError;
+use browser::html::loader::ResourceLoader;
+use std::env;
+use std::fs;
+use std::process;
+
+// hrefをfilesystemのpathとして読む
+struct FileLoader;
+
+impl ResourceLoader for FileLoader {
+    fn load(&self, url: &str) -> Result<String, LoadError> {
+        return fs::read_to_string(url).map_err(|err| LoadError::Failed {
+            url: url.to_string(),
+            detail: err.to_string(),
+        });
+    }
+}
+
+// cargo run --example load_stylesheets -- path/to/index.html
+fn main() {
+    let path = match env::args().nth(1) {
+        Some(path) => path,
+        None => {
+            eprintln!("usage: load_stylesheets <file.html>");
+            process::exit(2);
+        }
+    };
+    let source = match fs::read_to_string(&path) {
+        Ok(source) => source,
+        Err(err) => {
+            eprintln!("{}: {}", path, err);
+            process::exit(1);
+        }
+    };
+    let doc = match html::parse(&source) {
+        Ok(doc) => doc,
+        Err(err) => {
+            eprintln!("{}: {}", path, err);
+            process::exit(1);
+        }
+    };
+
+    for (url, result) in doc.load_stylesheets(&path, &FileLoader) {
+        match result {
+            Ok(sheet) => {
+                println!("/* {} */", url);
+                print!("{}", sheet.to_css(CssFormat::Pretty));
+                for err in &sheet.errors {
+                    eprintln!("{}:{}: {}", url, err.position().line_no, err);
+                }
+            }
+            Err(err) => eprintln!("{}: {}", url, err),
+        }
+    }
+}
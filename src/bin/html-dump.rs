@@ -0,0 +1,151 @@
+// parserの動きを目で見るための道具
+//   html-dump tree [file]    木を表示する
+//   html-dump tokens [file]  tokenを1行に1つ、位置と一緒に表示する
+//   html-dump check [file]   エラーを元の行と一緒に表示し、あれば1で終わる
+// fileがなければ標準入力を読む。--lenientなら閉じタグの食い違いなどを直して読み、checkはそれをwarningとして出す
+use browser::html::parser::{ParseWarning, Parser};
+use browser::html::serializer::document_ascii_tree;
+use browser::html::tokenizer::Tokenizer;
+use browser::Document;
+use std::io::{self, Read};
+use std::process::ExitCode;
+
+const USAGE: &str = "usage: html-dump <tree|tokens|check> [--strict|--lenient] [file]";
+
+enum Command {
+    Tree,
+    Tokens,
+    Check,
+}
+
+fn main() -> ExitCode {
+    let mut command = None;
+    let mut file = None;
+    let mut lenient = false;
+    for arg in std::env::args().skip(1) {
+        match arg.as_str() {
+            "tree" if command.is_none() => command = Some(Command::Tree),
+            "tokens" if command.is_none() => command = Some(Command::Tokens),
+            "check" if command.is_none() => command = Some(Command::Check),
+            // 既定はstrict。後に書いたほうが勝つ
+            "--strict" => lenient = false,
+            "--lenient" => lenient = true,
+            "-h" | "--help" => {
+                println!("{}", USAGE);
+                return ExitCode::SUCCESS;
+            }
+            _ if !arg.starts_with('-') && command.is_some() && file.is_none() => file = Some(arg),
+            _ => {
+                eprintln!("{}", USAGE);
+                return ExitCode::from(2);
+            }
+        }
+    }
+    let command = match command {
+        Some(command) => command,
+        None => {
+            eprintln!("{}", USAGE);
+            return ExitCode::from(2);
+        }
+    };
+
+    let read = match &file {
+        Some(path) => std::fs::read_to_string(path),
+        None => {
+            let mut input = String::new();
+            io::stdin().read_to_string(&mut input).map(|_| input)
+        }
+    };
+    let source = match read {
+        Ok(source) => source,
+        Err(err) => {
+            eprintln!(
+                "html-dump: {}: {}",
+                file.as_deref().unwrap_or("<stdin>"),
+                err
+            );
+            return ExitCode::from(2);
+        }
+    };
+    let name = file.as_deref().unwrap_or("<stdin>");
+    let mut builder = Parser::builder().collect_warnings(true);
+    if lenient {
+        builder = builder.lenient();
+    }
+    let mut parser = match builder.build() {
+        Ok(parser) => parser,
+        Err(err) => {
+            eprintln!("html-dump: {}", err);
+            return ExitCode::from(2);
+        }
+    };
+
+    match command {
+        Command::Tokens => {
+            print!("{}", dump_tokens(&source));
+            return ExitCode::SUCCESS;
+        }
+        Command::Tree => match parser.parse_str(&source) {
+            Ok(nodes) => {
+                let doc = Document::new(nodes.unwrap_or_default());
+                print!("{}", document_ascii_tree(&doc));
+                return ExitCode::SUCCESS;
+            }
+            Err(err) => {
+                eprint!("{}", err.render(name, &source));
+                return ExitCode::FAILURE;
+            }
+        },
+        Command::Check => {
+            // strictなら最初のエラーで止まる。lenientなら直したところがwarningになり、
+            // 直しきれないエラー (深すぎる入れ子やエラーの数の上限) があれば最後に1つ出る
+            let result = parser.parse_str(&source);
+            for warning in parser.warnings() {
+                print!("{}", render_warning(warning, name));
+            }
+            match result {
+                Ok(_) if parser.warnings().is_empty() => {
+                    println!("{}: ok", name);
+                    return ExitCode::SUCCESS;
+                }
+                Ok(_) => {
+                    println!("{}: {} warnings", name, parser.warnings().len());
+                    return ExitCode::SUCCESS;
+                }
+                Err(err) => {
+                    print!("{}", err.render(name, &source));
+                    return ExitCode::FAILURE;
+                }
+            }
+        }
+    }
+}
+
+// warning: element not closed before the end; closed it
+//  --> page.html:3:7
+fn render_warning(warning: &ParseWarning, name: &str) -> String {
+    return format!(
+        "warning: {}\n --> {}:{}:{}\n",
+        warning.message,
+        name,
+        warning.pos.line_no,
+        warning.pos.at_line.max(1)
+    );
+}
+
+// 1:0 TagBegin "<"
+fn dump_tokens(source: &str) -> String {
+    let mut out = String::new();
+    let head = Tokenizer::new(source).tokenize();
+    let mut tok = head.as_deref();
+    while let Some(t) = tok {
+        let line = format!("{}:{}\t{:?}", t.pos.line_no, t.pos.at_line, t.kind);
+        if t.s.is_empty() {
+            out += &format!("{}\n", line);
+        } else {
+            out += &format!("{}\t{:?}\n", line, t.s.as_str());
+        }
+        tok = t.next.as_deref();
+    }
+    return out;
+}
@@ -0,0 +1,67 @@
+pub mod cascade;
+pub mod computed;
+pub mod errors;
+pub mod media;
+pub mod parser;
+pub mod sanitize;
+pub mod selector;
+pub mod shorthand;
+pub mod stylesheet;
+pub mod tokenizer;
+pub mod ua;
+pub mod values;
+
+use crate::css::cascade::StyleIndex;
+use crate::css::computed::ComputedStyles;
+use crate::css::errors::CssParseError;
+use crate::css::media::MediaEnv;
+use crate::css::parser::CssParser;
+use crate::css::stylesheet::{Declaration, Stylesheet};
+use crate::html::document::Document;
+
+// 最初のエラーで失敗する
+pub fn parse_stylesheet(css: &str) -> Result<Stylesheet, CssParseError> {
+    let mut sheet = parse_stylesheet_recovering(css);
+    if sheet.errors.is_empty() {
+        return Ok(sheet);
+    }
+    return Err(sheet.errors.remove(0));
+}
+
+// 読めなかった部分を飛ばして最後まで読む。エラーはStylesheet::errorsに残る
+pub fn parse_stylesheet_recovering(css: &str) -> Stylesheet {
+    return CssParser::new(css).parse_stylesheet();
+}
+
+// style属性の中身を読む。最初のエラーで失敗する
+pub fn parse_declarations(css: &str) -> Result<Vec<Declaration>, CssParseError> {
+    let (declarations, mut errors) = parse_declarations_recovering(css);
+    if errors.is_empty() {
+        return Ok(declarations);
+    }
+    return Err(errors.remove(0));
+}
+
+// 読めなかった宣言は飛ばす
+pub fn parse_declarations_recovering(css: &str) -> (Vec<Declaration>, Vec<CssParseError>) {
+    return CssParser::new(css).parse_declaration_list();
+}
+
+// 要素ごとに、合ったstyle ruleを集める。@mediaは既定の画面の大きさで評価する
+pub fn match_rules<'a>(doc: &'a Document, sheets: &'a [Stylesheet]) -> StyleIndex<'a> {
+    return StyleIndex::build(doc, sheets, &MediaEnv::default());
+}
+
+// 要素ごとに、cascadeと継承で値を決める
+pub fn compute_styles(doc: &Document, sheets: &[Stylesheet]) -> ComputedStyles {
+    return ComputedStyles::compute(doc, sheets, &MediaEnv::default());
+}
+
+// viewportの大きさやmedia typeを指定して@mediaを評価する
+pub fn compute_styles_for_media(
+    doc: &Document,
+    sheets: &[Stylesheet],
+    env: &MediaEnv,
+) -> ComputedStyles {
+    return ComputedStyles::compute(doc, sheets, env);
+}
@@ -0,0 +1,420 @@
+use crate::css;
+use crate::css::media::MediaEnv;
+use crate::css::selector::{Selector, SimpleSelector, Specificity};
+use crate::css::stylesheet::{Declaration, StyleRule, Stylesheet};
+use crate::html::atom::Atom;
+use crate::html::document::{Document, ElementRef};
+use crate::html::parser::Node;
+use std::collections::HashMap;
+use std::ops::ControlFlow;
+
+// 要素に合ったrule
+#[derive(Debug, Clone, Copy)]
+pub struct MatchedRule<'a> {
+    pub rule: &'a StyleRule,
+    // ruleのselectorのうち、合ったものの中で一番強いもの
+    pub specificity: Specificity,
+    // 全部のstylesheetを通してのruleの順番
+    pub order: usize,
+}
+
+// 宣言の強さ。!important, style属性かどうか, specificity, ruleの順, rule内の順 の順に比べる
+// 今はどれもauthor (文書) の宣言なので、originは比べない
+type Precedence = (bool, bool, Specificity, usize, usize);
+
+// 要素ごとに勝った宣言。propertyから値を引く
+#[derive(Debug, Clone, Default)]
+pub struct ComputedDeclarations<'s> {
+    values: HashMap<&'s str, &'s Declaration>,
+}
+
+impl<'s> ComputedDeclarations<'s> {
+    pub fn get(&self, property: &str) -> Option<&'s str> {
+        return self.values.get(property).map(|d| d.value.as_str());
+    }
+
+    pub fn declaration(&self, property: &str) -> Option<&'s Declaration> {
+        return self.values.get(property).copied();
+    }
+
+    pub fn len(&self) -> usize {
+        return self.values.len();
+    }
+
+    pub fn is_empty(&self) -> bool {
+        return self.values.is_empty();
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&'s str, &'s str)> + '_ {
+        return self
+            .values
+            .iter()
+            .map(|(name, d)| (*name, d.value.as_str()));
+    }
+}
+
+#[derive(Clone, Copy)]
+struct Candidate<'a> {
+    selector: &'a Selector,
+    rule: &'a StyleRule,
+    order: usize,
+}
+
+// selectorを右端のcompoundのid, class, 要素名で分けておき、要素ごとに合う見込みのあるものだけを調べる
+#[derive(Default)]
+struct RuleBuckets<'a> {
+    ids: HashMap<&'a str, Vec<Candidate<'a>>>,
+    classes: HashMap<&'a str, Vec<Candidate<'a>>>,
+    tags: HashMap<&'a Atom, Vec<Candidate<'a>>>,
+    universal: Vec<Candidate<'a>>,
+}
+
+impl<'a> RuleBuckets<'a> {
+    fn insert(&mut self, candidate: Candidate<'a>) {
+        let rightmost = match candidate.selector.compounds.last() {
+            Some(compound) => &compound.simple,
+            None => return,
+        };
+        let id = rightmost.iter().find_map(|simple| match simple {
+            SimpleSelector::Id(id) => Some(id.as_str()),
+            _ => None,
+        });
+        let class = rightmost.iter().find_map(|simple| match simple {
+            SimpleSelector::Class(class) => Some(class.as_str()),
+            _ => None,
+        });
+        let tag = rightmost.iter().find_map(|simple| match simple {
+            SimpleSelector::Type(name) => Some(name),
+            _ => None,
+        });
+
+        if let Some(id) = id {
+            self.ids.entry(id).or_default().push(candidate);
+        } else if let Some(class) = class {
+            self.classes.entry(class).or_default().push(candidate);
+        } else if let Some(tag) = tag {
+            self.tags.entry(tag).or_default().push(candidate);
+        } else {
+            self.universal.push(candidate);
+        }
+    }
+
+    fn collect(&self, el: &ElementRef, found: &mut Vec<MatchedRule<'a>>) {
+        let node = el.node();
+        let mut check = |candidates: Option<&Vec<Candidate<'a>>>| {
+            for candidate in candidates.into_iter().flatten() {
+                if candidate.selector.matches(el) {
+                    found.push(MatchedRule {
+                        rule: candidate.rule,
+                        specificity: candidate.selector.specificity(),
+                        order: candidate.order,
+                    });
+                }
+            }
+        };
+
+        if let Some(id) = node.attr("id") {
+            check(self.ids.get(id.as_str()));
+        }
+        if let Some(classes) = node.attr("class") {
+            for class in classes.split_ascii_whitespace() {
+                check(self.classes.get(class));
+            }
+        }
+        if let Some(name) = node.name() {
+            check(self.tags.get(name));
+        }
+        check(Some(&self.universal));
+    }
+}
+
+// 要素ごとの合ったruleとstyle属性の宣言。ここからcascadeで値を決める
+pub struct StyleIndex<'a> {
+    matched: HashMap<*const Node, Vec<MatchedRule<'a>>>,
+    // style属性は読めた宣言だけを持つ
+    inline: HashMap<*const Node, Vec<Declaration>>,
+}
+
+impl<'a> StyleIndex<'a> {
+    // envに合わない@mediaの中のruleは使わない
+    pub fn build(doc: &'a Document, sheets: &'a [Stylesheet], env: &MediaEnv) -> StyleIndex<'a> {
+        let mut buckets = RuleBuckets::default();
+        let mut order = 0;
+        for sheet in sheets {
+            for rule in sheet.style_rules_for(env) {
+                for selector in &rule.selectors.selectors {
+                    // 疑似要素のselectorは要素そのものには合わない
+                    if selector.pseudo_element.is_none() {
+                        buckets.insert(Candidate {
+                            selector,
+                            rule,
+                            order,
+                        });
+                    }
+                }
+                order += 1;
+            }
+        }
+
+        let mut matched = HashMap::new();
+        let mut inline = HashMap::new();
+        let mut found = Vec::new();
+        doc.walk_elements(|el| {
+            if let Some(style) = el.node().attr("style") {
+                let (declarations, _) = css::parse_declarations_recovering(style);
+                inline.insert(el.node() as *const Node, declarations);
+            }
+
+            buckets.collect(&el, &mut found);
+            if found.is_empty() {
+                return ControlFlow::Continue(());
+            }
+            // 複数のselectorやclassで同じruleに合ったら、一番強いものだけを残す
+            found.sort_by_key(|m| m.order);
+            found.dedup_by(|later, earlier| {
+                if later.order != earlier.order {
+                    return false;
+                }
+                earlier.specificity = earlier.specificity.max(later.specificity);
+                return true;
+            });
+            matched.insert(el.node() as *const Node, std::mem::take(&mut found));
+            return ControlFlow::Continue(());
+        });
+
+        return StyleIndex { matched, inline };
+    }
+
+    // stylesheetでの順に並んでいる。nodeはbuildに渡したDocumentの中のもの
+    pub fn rules_for(&self, node: &Node) -> &[MatchedRule<'a>] {
+        return match self.matched.get(&(node as *const Node)) {
+            Some(rules) => rules,
+            None => &[],
+        };
+    }
+
+    pub fn inline_declarations(&self, node: &Node) -> &[Declaration] {
+        return match self.inline.get(&(node as *const Node)) {
+            Some(declarations) => declarations,
+            None => &[],
+        };
+    }
+
+    // nodeに当たる宣言を全部、強さと一緒に渡す
+    fn each_declaration<'s>(&'s self, node: &Node, mut f: impl FnMut(&'s Declaration, Precedence)) {
+        for matched in self.rules_for(node) {
+            for (i, declaration) in matched.rule.declarations.iter().enumerate() {
+                f(
+                    declaration,
+                    (
+                        declaration.important,
+                        false,
+                        matched.specificity,
+                        matched.order,
+                        i,
+                    ),
+                );
+            }
+        }
+        // style属性はどのselectorよりも強い。ただしstylesheetの!importantには負ける
+        for (i, declaration) in self.inline_declarations(node).iter().enumerate() {
+            f(
+                declaration,
+                (declaration.important, true, Specificity::default(), 0, i),
+            );
+        }
+    }
+
+    // propertyについてcascadeで勝った値
+    pub fn declared_value(&self, node: &Node, property: &str) -> Option<&str> {
+        let mut best: Option<(&Declaration, Precedence)> = None;
+        self.each_declaration(node, |declaration, precedence| {
+            if declaration.name != property {
+                return;
+            }
+            if best.is_none_or(|(_, current)| precedence > current) {
+                best = Some((declaration, precedence));
+            }
+        });
+        return best.map(|(declaration, _)| declaration.value.as_str());
+    }
+
+    pub fn computed_declarations(&self, node: &Node) -> ComputedDeclarations<'_> {
+        let mut best: HashMap<&str, (&Declaration, Precedence)> = HashMap::new();
+        self.each_declaration(node, |declaration, precedence| {
+            let entry = best
+                .entry(declaration.name.as_str())
+                .or_insert((declaration, precedence));
+            if precedence > entry.1 {
+                *entry = (declaration, precedence);
+            }
+        });
+        return ComputedDeclarations {
+            values: best
+                .into_iter()
+                .map(|(name, (declaration, _))| (name, declaration))
+                .collect(),
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::css;
+    use crate::css::selector::Specificity;
+    use crate::html;
+
+    const HTML: &str = "<html><body>\
+        <div id=\"main\" class=\"page\">\
+        <p class=\"lead intro\">one</p><p>two</p><a href=\"/x\" class=\"lead\">link</a>\
+        </div><p id=\"foot\">three</p>\
+        </body></html>";
+
+    const CSS: &str = "
+        p { color: black }
+        .lead { font-weight: bold }
+        #main p, #foot { margin: 0 }
+        div > .lead.intro { color: red }
+        * { box-sizing: border-box }
+        a:link, .lead { color: blue }
+        p::first-line { color: green }
+        span { color: gray }
+    ";
+
+    #[test]
+    fn rules_for_elements() {
+        let doc = html::parse(HTML).unwrap();
+        let sheets = vec![css::parse_stylesheet(CSS).unwrap()];
+        let index = css::match_rules(&doc, &sheets);
+
+        let selectors = |selector: &str| -> Vec<(String, Specificity, usize)> {
+            let node = doc.query_selector(selector).unwrap().unwrap();
+            return index
+                .rules_for(&node)
+                .iter()
+                .map(|m| (m.rule.selectors.to_string(), m.specificity, m.order))
+                .collect();
+        };
+
+        assert_eq!(
+            selectors(".intro"),
+            vec![
+                ("p".to_string(), Specificity(0, 0, 1), 0),
+                (".lead".to_string(), Specificity(0, 1, 0), 1),
+                ("#main p, #foot".to_string(), Specificity(1, 0, 1), 2),
+                ("div > .lead.intro".to_string(), Specificity(0, 2, 1), 3),
+                ("*".to_string(), Specificity(0, 0, 0), 4),
+                ("a:link, .lead".to_string(), Specificity(0, 1, 0), 5),
+            ]
+        );
+        // a:link と .lead の両方に合うときは強い方
+        assert_eq!(
+            selectors("a"),
+            vec![
+                (".lead".to_string(), Specificity(0, 1, 0), 1),
+                ("*".to_string(), Specificity(0, 0, 0), 4),
+                ("a:link, .lead".to_string(), Specificity(0, 1, 1), 5),
+            ]
+        );
+        assert_eq!(
+            selectors("#foot"),
+            vec![
+                ("p".to_string(), Specificity(0, 0, 1), 0),
+                ("#main p, #foot".to_string(), Specificity(1, 0, 0), 2),
+                ("*".to_string(), Specificity(0, 0, 0), 4),
+            ]
+        );
+        assert_eq!(
+            selectors("#main"),
+            vec![("*".to_string(), Specificity(0, 0, 0), 4)]
+        );
+    }
+
+    #[test]
+    fn cascade_picks_the_winner() {
+        // (css, 要素のstyle属性, 勝つcolor)
+        let table = [
+            // specificityで決まる
+            ("#x { color: red } p { color: blue }", "", "red"),
+            ("p.a { color: red } .a { color: blue }", "", "red"),
+            ("div p { color: red } p { color: blue }", "", "red"),
+            ("p:not(#x) { color: red } p.a.b { color: blue }", "", "blue"),
+            // 同じspecificityなら後のもの
+            (".a { color: red } .b { color: blue }", "", "blue"),
+            (".b { color: red } .a { color: blue }", "", "blue"),
+            ("p { color: red; color: green }", "", "green"),
+            // !importantは逆転させる
+            ("p { color: red !important } #x { color: blue }", "", "red"),
+            (
+                "#x { color: blue !important } p { color: red !important }",
+                "",
+                "blue",
+            ),
+            ("p { color: red !important; color: green }", "", "red"),
+            // style属性はselectorより強いが、!importantには負ける
+            ("#x.a.b { color: red }", "color: blue", "blue"),
+            ("p { color: red !important }", "color: blue", "red"),
+            (
+                "p { color: red !important }",
+                "color: blue !important",
+                "blue",
+            ),
+            ("", "color: blue; color: green", "green"),
+        ];
+
+        for (sheet, style, expected) in table {
+            let html = format!(
+                "<div><p id=\"x\" class=\"a b\" style=\"{}\">text</p></div>",
+                style
+            );
+            let doc = html::parse(&html).unwrap();
+            let sheets = vec![css::parse_stylesheet(sheet).unwrap()];
+            let index = css::match_rules(&doc, &sheets);
+            let p = doc.query_selector("p").unwrap().unwrap();
+            assert_eq!(
+                index.declared_value(&p, "color"),
+                Some(expected),
+                "{:?} / {:?}",
+                sheet,
+                style
+            );
+            assert_eq!(index.computed_declarations(&p).get("color"), Some(expected));
+        }
+    }
+
+    #[test]
+    fn computed_declarations_per_element() {
+        let doc = html::parse(HTML).unwrap();
+        let sheets = vec![css::parse_stylesheet(CSS).unwrap()];
+        let index = css::match_rules(&doc, &sheets);
+
+        let intro = doc.query_selector(".intro").unwrap().unwrap();
+        let declarations = index.computed_declarations(&intro);
+        // margin は4つのlonghandになっている
+        assert_eq!(declarations.len(), 7);
+        assert_eq!(declarations.get("color"), Some("red"));
+        assert_eq!(declarations.get("font-weight"), Some("bold"));
+        assert_eq!(declarations.get("margin-left"), Some("0"));
+        assert_eq!(declarations.get("box-sizing"), Some("border-box"));
+
+        let main = doc.query_selector("#main").unwrap().unwrap();
+        assert_eq!(index.declared_value(&main, "color"), None);
+    }
+
+    #[test]
+    fn order_continues_across_sheets() {
+        let doc = html::parse("<p class=\"x\">a</p>").unwrap();
+        let sheets = vec![
+            css::parse_stylesheet("p { color: red } .x { color: blue }").unwrap(),
+            css::parse_stylesheet("@media print { p { color: black } } .x { margin: 0 }").unwrap(),
+        ];
+        let index = css::match_rules(&doc, &sheets);
+        let p = doc.query_selector("p").unwrap().unwrap();
+        let orders: Vec<usize> = index.rules_for(&p).iter().map(|m| m.order).collect();
+        assert_eq!(orders, vec![0, 1, 2]);
+
+        // 別の文書のnodeには何も合っていない
+        let other = html::parse("<p class=\"x\">a</p>").unwrap();
+        assert!(index.rules_for(&other.children[0]).is_empty());
+    }
+}
@@ -0,0 +1,656 @@
+use crate::css::cascade::StyleIndex;
+use crate::css::media::MediaEnv;
+use crate::css::stylesheet::Stylesheet;
+use crate::css::values::{
+    parse_component_list, Color, Component, Length, LengthContext, LengthUnit,
+};
+use crate::html::document::Document;
+use crate::html::parser::Node;
+use std::collections::HashMap;
+use std::ops::ControlFlow;
+
+// font-sizeの初期値 (medium)
+pub const DEFAULT_FONT_SIZE: f64 = 16.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Display {
+    Inline,
+    Block,
+    InlineBlock,
+    ListItem,
+    Flex,
+    InlineFlex,
+    Grid,
+    InlineGrid,
+    Table,
+    TableRow,
+    TableCell,
+    Contents,
+    None,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum FontStyle {
+    Normal,
+    Italic,
+    Oblique,
+}
+
+// text-decoration-line。全部falseならnone
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct TextDecoration {
+    pub underline: bool,
+    pub overline: bool,
+    pub line_through: bool,
+}
+
+// em, remなどはpxにしてある。%は何に対するかがlayoutで決まるのでそのまま
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ComputedLength {
+    Px(f64),
+    Percent(f64),
+    Auto,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Sides<T> {
+    pub top: T,
+    pub right: T,
+    pub bottom: T,
+    pub left: T,
+}
+
+impl<T: Copy> Sides<T> {
+    pub fn all(value: T) -> Sides<T> {
+        return Sides {
+            top: value,
+            right: value,
+            bottom: value,
+            left: value,
+        };
+    }
+
+    fn get(&self, side: &str) -> Option<T> {
+        return match side {
+            "top" => Some(self.top),
+            "right" => Some(self.right),
+            "bottom" => Some(self.bottom),
+            "left" => Some(self.left),
+            _ => None,
+        };
+    }
+}
+
+// ComputedStyles::getで返す値
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ComputedValue {
+    Color(Color),
+    Length(ComputedLength),
+    FontWeight(u16),
+    FontStyle(FontStyle),
+    TextDecoration(TextDecoration),
+    Display(Display),
+}
+
+// 今扱っているpropertyの値
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ComputedStyle {
+    // currentColorは解決してある
+    pub color: Color,
+    pub background_color: Color,
+    // px
+    pub font_size: f64,
+    pub font_weight: u16,
+    pub font_style: FontStyle,
+    pub text_decoration: TextDecoration,
+    pub display: Display,
+    pub margin: Sides<ComputedLength>,
+    pub padding: Sides<ComputedLength>,
+}
+
+impl Default for ComputedStyle {
+    fn default() -> ComputedStyle {
+        return ComputedStyle::initial();
+    }
+}
+
+impl ComputedStyle {
+    // 何も宣言がなく、継承もしないときの値
+    pub fn initial() -> ComputedStyle {
+        return ComputedStyle {
+            color: Color::rgb(0, 0, 0),
+            background_color: Color::TRANSPARENT,
+            font_size: DEFAULT_FONT_SIZE,
+            font_weight: 400,
+            font_style: FontStyle::Normal,
+            text_decoration: TextDecoration::default(),
+            display: Display::Inline,
+            margin: Sides::all(ComputedLength::Px(0.0)),
+            padding: Sides::all(ComputedLength::Px(0.0)),
+        };
+    }
+
+    // 継承するpropertyだけ親から引き継ぐ
+    fn inherit_from(parent: &ComputedStyle) -> ComputedStyle {
+        return ComputedStyle {
+            color: parent.color,
+            font_size: parent.font_size,
+            font_weight: parent.font_weight,
+            font_style: parent.font_style,
+            ..ComputedStyle::initial()
+        };
+    }
+
+    pub fn get(&self, property: &str) -> Option<ComputedValue> {
+        let value = match property {
+            "color" => ComputedValue::Color(self.color),
+            "background-color" => ComputedValue::Color(self.background_color),
+            "font-size" => ComputedValue::Length(ComputedLength::Px(self.font_size)),
+            "font-weight" => ComputedValue::FontWeight(self.font_weight),
+            "font-style" => ComputedValue::FontStyle(self.font_style),
+            "text-decoration" | "text-decoration-line" => {
+                ComputedValue::TextDecoration(self.text_decoration)
+            }
+            "display" => ComputedValue::Display(self.display),
+            _ => {
+                if let Some(side) = property.strip_prefix("margin-") {
+                    return self.margin.get(side).map(ComputedValue::Length);
+                }
+                if let Some(side) = property.strip_prefix("padding-") {
+                    return self.padding.get(side).map(ComputedValue::Length);
+                }
+                return None;
+            }
+        };
+        return Some(value);
+    }
+}
+
+// 要素ごとのComputedStyle
+pub struct ComputedStyles {
+    styles: HashMap<*const Node, ComputedStyle>,
+}
+
+impl ComputedStyles {
+    pub fn compute(doc: &Document, sheets: &[Stylesheet], env: &MediaEnv) -> ComputedStyles {
+        let index = StyleIndex::build(doc, sheets, env);
+        let initial = ComputedStyle::initial();
+        let mut styles: HashMap<*const Node, ComputedStyle> = HashMap::new();
+        // remは文書の最上位の要素のfont-sizeに対する
+        let mut root_font_size = DEFAULT_FONT_SIZE;
+        // 親は子より先に辿られるので、もう計算してある
+        doc.walk_elements(|el| {
+            let parent = match el.parent_element() {
+                Some(parent) => styles[&(parent.node() as *const Node)],
+                None => initial,
+            };
+            let declared = index.computed_declarations(el.node());
+            let mut cx = Context {
+                parent: &parent,
+                lengths: LengthContext {
+                    font_size: parent.font_size,
+                    root_font_size: if el.depth() == 0 {
+                        parent.font_size
+                    } else {
+                        root_font_size
+                    },
+                    viewport_width: env.width,
+                    viewport_height: env.height,
+                },
+            };
+            let style = cx.compute(|property| declared.get(property));
+            if el.depth() == 0 {
+                root_font_size = style.font_size;
+            }
+            styles.insert(el.node() as *const Node, style);
+            return ControlFlow::Continue(());
+        });
+        return ComputedStyles { styles };
+    }
+
+    // nodeはcomputeに渡したDocumentの中の要素
+    pub fn style(&self, node: &Node) -> Option<&ComputedStyle> {
+        return self.styles.get(&(node as *const Node));
+    }
+
+    pub fn get(&self, node: &Node, property: &str) -> Option<ComputedValue> {
+        return self.style(node)?.get(property);
+    }
+}
+
+// inherit, initial, unset
+#[derive(Clone, Copy)]
+enum CssWide {
+    Inherit,
+    Initial,
+    Unset,
+}
+
+fn css_wide(value: &str) -> Option<CssWide> {
+    let value = value.trim();
+    if value.eq_ignore_ascii_case("inherit") {
+        return Some(CssWide::Inherit);
+    }
+    if value.eq_ignore_ascii_case("initial") {
+        return Some(CssWide::Initial);
+    }
+    if value.eq_ignore_ascii_case("unset") {
+        return Some(CssWide::Unset);
+    }
+    return None;
+}
+
+// 要素一つ分の計算に使う値
+struct Context<'p> {
+    parent: &'p ComputedStyle,
+    // font_sizeは要素自身のもの。font-sizeを決めたあとはemの基準になる
+    lengths: LengthContext,
+}
+
+impl Context<'_> {
+    fn compute<'v>(&mut self, declared: impl Fn(&str) -> Option<&'v str>) -> ComputedStyle {
+        let parent = *self.parent;
+        let initial = ComputedStyle::initial();
+        let mut style = ComputedStyle::inherit_from(&parent);
+
+        // 他のemはfont-sizeに対するので先に決める
+        style.font_size = resolve(
+            declared("font-size"),
+            style.font_size,
+            parent.font_size,
+            initial.font_size,
+            |value| self.font_size(value),
+        );
+        self.lengths.font_size = style.font_size;
+
+        style.color = resolve(
+            declared("color"),
+            style.color,
+            parent.color,
+            initial.color,
+            |value| match Color::parse(value) {
+                Ok(Color::CurrentColor) => Some(parent.color),
+                Ok(color) => Some(color),
+                Err(_) => None,
+            },
+        );
+        let color = style.color;
+        style.background_color = resolve(
+            declared("background-color"),
+            style.background_color,
+            parent.background_color,
+            initial.background_color,
+            |value| match Color::parse(value) {
+                Ok(Color::CurrentColor) => Some(color),
+                Ok(color) => Some(color),
+                Err(_) => None,
+            },
+        );
+        style.font_weight = resolve(
+            declared("font-weight"),
+            style.font_weight,
+            parent.font_weight,
+            initial.font_weight,
+            |value| font_weight(value, parent.font_weight),
+        );
+        style.font_style = resolve(
+            declared("font-style"),
+            style.font_style,
+            parent.font_style,
+            initial.font_style,
+            font_style,
+        );
+        style.text_decoration = resolve(
+            declared("text-decoration-line"),
+            style.text_decoration,
+            parent.text_decoration,
+            initial.text_decoration,
+            text_decoration,
+        );
+        style.display = resolve(
+            declared("display"),
+            style.display,
+            parent.display,
+            initial.display,
+            display,
+        );
+
+        for (property, sides, parent_sides, allow_auto) in [
+            ("margin", &mut style.margin, &parent.margin, true),
+            ("padding", &mut style.padding, &parent.padding, false),
+        ] {
+            for (side, value, parent_value) in [
+                ("top", &mut sides.top, parent_sides.top),
+                ("right", &mut sides.right, parent_sides.right),
+                ("bottom", &mut sides.bottom, parent_sides.bottom),
+                ("left", &mut sides.left, parent_sides.left),
+            ] {
+                *value = resolve(
+                    declared(&format!("{}-{}", property, side)),
+                    *value,
+                    parent_value,
+                    ComputedLength::Px(0.0),
+                    |value| self.box_length(value, allow_auto),
+                );
+            }
+        }
+
+        return style;
+    }
+
+    fn font_size(&self, value: &str) -> Option<f64> {
+        let parent = self.parent.font_size;
+        let keyword = value.trim().to_ascii_lowercase();
+        let size = match keyword.as_str() {
+            "xx-small" => 9.0,
+            "x-small" => 10.0,
+            "small" => 13.0,
+            "medium" => 16.0,
+            "large" => 18.0,
+            "x-large" => 24.0,
+            "xx-large" => 32.0,
+            "xxx-large" => 48.0,
+            "larger" => parent * 1.2,
+            "smaller" => parent / 1.2,
+            _ => {
+                let length = single_length(value)?;
+                // font-sizeのemと%は親のfont-sizeに対する
+                let size = match length.unit {
+                    LengthUnit::Percent => parent * length.value / 100.0,
+                    LengthUnit::Em => parent * length.value,
+                    LengthUnit::Ex | LengthUnit::Ch => parent * length.value / 2.0,
+                    _ => length.to_px(&self.lengths)?,
+                };
+                if size < 0.0 {
+                    return None;
+                }
+                size
+            }
+        };
+        return Some(size);
+    }
+
+    fn box_length(&self, value: &str, allow_auto: bool) -> Option<ComputedLength> {
+        if allow_auto && value.trim().eq_ignore_ascii_case("auto") {
+            return Some(ComputedLength::Auto);
+        }
+        let length = single_length(value)?;
+        // paddingは負にできない
+        if !allow_auto && length.value < 0.0 {
+            return None;
+        }
+        if length.unit == LengthUnit::Percent {
+            return Some(ComputedLength::Percent(length.value));
+        }
+        return length.to_px(&self.lengths).map(ComputedLength::Px);
+    }
+}
+
+// 宣言された値から計算した値を決める。宣言がないか読めないときはdefault (継承した値か初期値)
+fn resolve<T: Copy>(
+    declared: Option<&str>,
+    default: T,
+    parent: T,
+    initial: T,
+    parse: impl FnOnce(&str) -> Option<T>,
+) -> T {
+    let value = match declared {
+        Some(value) => value,
+        None => return default,
+    };
+    return match css_wide(value) {
+        Some(CssWide::Inherit) => parent,
+        Some(CssWide::Initial) => initial,
+        Some(CssWide::Unset) => default,
+        None => parse(value).unwrap_or(default),
+    };
+}
+
+fn single_length(value: &str) -> Option<Length> {
+    let components = parse_component_list(value).ok()?;
+    return match components.as_slice() {
+        [component] => component.as_length(),
+        _ => None,
+    };
+}
+
+fn single_keyword(value: &str) -> Option<String> {
+    let components = parse_component_list(value).ok()?;
+    return match components.as_slice() {
+        [Component::Keyword(keyword)] => Some(keyword.clone()),
+        _ => None,
+    };
+}
+
+fn font_weight(value: &str, parent: u16) -> Option<u16> {
+    let components = parse_component_list(value).ok()?;
+    let weight = match components.as_slice() {
+        [Component::Number(weight)] if (1.0..=1000.0).contains(weight) => weight.round() as u16,
+        [Component::Keyword(keyword)] => match keyword.as_str() {
+            "normal" => 400,
+            "bold" => 700,
+            // 親の太さから一段階変える
+            "bolder" => match parent {
+                0..=349 => 400,
+                350..=549 => 700,
+                _ => 900,
+            },
+            "lighter" => match parent {
+                0..=99 => parent,
+                100..=549 => 100,
+                550..=749 => 400,
+                _ => 700,
+            },
+            _ => return None,
+        },
+        _ => return None,
+    };
+    return Some(weight);
+}
+
+fn font_style(value: &str) -> Option<FontStyle> {
+    let components = parse_component_list(value).ok()?;
+    return match components.as_slice() {
+        [Component::Keyword(keyword)] if keyword == "normal" => Some(FontStyle::Normal),
+        [Component::Keyword(keyword)] if keyword == "italic" => Some(FontStyle::Italic),
+        // oblique 10deg のように角度がついてもよい
+        [Component::Keyword(keyword), ..] if keyword == "oblique" => Some(FontStyle::Oblique),
+        _ => None,
+    };
+}
+
+// underline overline のように並べられる
+fn text_decoration(value: &str) -> Option<TextDecoration> {
+    let mut decoration = TextDecoration::default();
+    for component in parse_component_list(value).ok()? {
+        match component {
+            Component::Keyword(keyword) => match keyword.as_str() {
+                "none" => {}
+                "underline" => decoration.underline = true,
+                "overline" => decoration.overline = true,
+                "line-through" => decoration.line_through = true,
+                // 点滅はさせない
+                "blink" => {}
+                _ => return None,
+            },
+            _ => return None,
+        }
+    }
+    return Some(decoration);
+}
+
+fn display(value: &str) -> Option<Display> {
+    let display = match single_keyword(value)?.as_str() {
+        "inline" => Display::Inline,
+        "block" => Display::Block,
+        "inline-block" => Display::InlineBlock,
+        "list-item" => Display::ListItem,
+        "flex" => Display::Flex,
+        "inline-flex" => Display::InlineFlex,
+        "grid" => Display::Grid,
+        "inline-grid" => Display::InlineGrid,
+        "table" => Display::Table,
+        "table-row" => Display::TableRow,
+        "table-cell" => Display::TableCell,
+        "contents" => Display::Contents,
+        "none" => Display::None,
+        _ => return None,
+    };
+    return Some(display);
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::css;
+    use crate::css::computed::{ComputedLength, ComputedValue, Display, FontStyle};
+    use crate::css::media::MediaEnv;
+    use crate::css::values::Color;
+    use crate::html;
+
+    const HTML: &str = "<html><body>\
+        <div id=\"outer\" style=\"margin-left: auto\">\
+        <p id=\"child\">text <em id=\"em\">emphasis</em></p>\
+        <p id=\"reset\">reset</p>\
+        </div>\
+        </body></html>";
+
+    const CSS: &str = "
+        html { font-size: 20px }
+        #outer {
+            color: navy;
+            background-color: yellow;
+            font-size: 1.5em;
+            font-weight: bold;
+            display: block;
+            margin-top: 2em;
+            padding-top: 10%;
+            text-decoration: underline;
+        }
+        #child { font-size: 0.5em; margin-bottom: 1rem; padding-left: -1px }
+        #em { font-style: italic; font-weight: bolder; background-color: currentColor }
+        #reset { color: initial; font-size: larger; display: inherit; margin-top: inherit }
+    ";
+
+    #[test]
+    fn inherit_and_resolve() {
+        let doc = html::parse(HTML).unwrap();
+        let sheets = vec![css::parse_stylesheet(CSS).unwrap()];
+        let styles = css::compute_styles(&doc, &sheets);
+        let get = |selector: &str, property: &str| {
+            let node = doc.query_selector(selector).unwrap().unwrap();
+            return styles.get(&node, property).unwrap();
+        };
+        let px = |value: f64| ComputedValue::Length(ComputedLength::Px(value));
+        let navy = ComputedValue::Color(Color::rgb(0, 0, 128));
+
+        assert_eq!(get("html", "font-size"), px(20.0));
+        assert_eq!(get("body", "font-size"), px(20.0));
+        assert_eq!(get("#outer", "font-size"), px(30.0));
+        assert_eq!(get("#outer", "margin-top"), px(60.0));
+        assert_eq!(
+            get("#outer", "padding-top"),
+            ComputedValue::Length(ComputedLength::Percent(10.0))
+        );
+        // style属性
+        assert_eq!(
+            get("#outer", "margin-left"),
+            ComputedValue::Length(ComputedLength::Auto)
+        );
+
+        // colorとfont-*は継承し、emは親のfont-sizeに対する
+        assert_eq!(get("#child", "color"), navy);
+        assert_eq!(get("#child", "font-weight"), ComputedValue::FontWeight(700));
+        assert_eq!(get("#child", "font-size"), px(15.0));
+        // remは最上位の要素に対する
+        assert_eq!(get("#child", "margin-bottom"), px(20.0));
+        // 負のpaddingは無視する
+        assert_eq!(get("#child", "padding-left"), px(0.0));
+
+        // 継承しないpropertyは初期値に戻る
+        assert_eq!(
+            get("#child", "background-color"),
+            ComputedValue::Color(Color::TRANSPARENT)
+        );
+        assert_eq!(
+            get("#child", "display"),
+            ComputedValue::Display(Display::Inline)
+        );
+        assert_eq!(get("#child", "margin-top"), px(0.0));
+        assert_eq!(get("#child", "padding-top"), px(0.0));
+        assert_eq!(
+            get("#child", "text-decoration"),
+            get("body", "text-decoration")
+        );
+
+        assert_eq!(get("#em", "font-size"), px(15.0));
+        assert_eq!(
+            get("#em", "font-style"),
+            ComputedValue::FontStyle(FontStyle::Italic)
+        );
+        assert_eq!(get("#em", "font-weight"), ComputedValue::FontWeight(900));
+        assert_eq!(get("#em", "background-color"), navy);
+
+        // inherit, initial
+        assert_eq!(
+            get("#reset", "color"),
+            ComputedValue::Color(Color::rgb(0, 0, 0))
+        );
+        assert_eq!(get("#reset", "font-size"), px(36.0));
+        assert_eq!(
+            get("#reset", "display"),
+            ComputedValue::Display(Display::Block)
+        );
+        assert_eq!(get("#reset", "margin-top"), px(60.0));
+
+        let outer = doc.query_selector("#outer").unwrap().unwrap();
+        assert!(styles.style(&outer).unwrap().text_decoration.underline);
+        assert_eq!(styles.get(&outer, "float"), None);
+    }
+
+    #[test]
+    fn media_blocks_follow_env() {
+        let doc = html::parse("<p>a</p>").unwrap();
+        let sheets = vec![css::parse_stylesheet(
+            "p { color: black; margin-left: 10vw }
+             @media (max-width: 600px) { p { color: red } }
+             @media print { p { color: gray } }",
+        )
+        .unwrap()];
+        let p = doc.query_selector("p").unwrap().unwrap();
+        let color = |env: &MediaEnv| {
+            let styles = css::compute_styles_for_media(&doc, &sheets, env);
+            return styles.style(&p).unwrap().color;
+        };
+        assert_eq!(
+            color(&MediaEnv::screen(600.0, 800.0)),
+            Color::rgb(255, 0, 0)
+        );
+        assert_eq!(color(&MediaEnv::screen(601.0, 800.0)), Color::rgb(0, 0, 0));
+        assert_eq!(
+            color(&MediaEnv::print(800.0, 600.0)),
+            Color::rgb(128, 128, 128)
+        );
+
+        // 既定の画面は1024pxの幅
+        let styles = css::compute_styles(&doc, &sheets);
+        assert_eq!(
+            styles.get(&p, "margin-left"),
+            Some(ComputedValue::Length(ComputedLength::Px(102.4)))
+        );
+    }
+
+    #[test]
+    fn initial_values_without_stylesheets() {
+        let doc = html::parse("<p>a</p>").unwrap();
+        let styles = css::compute_styles(&doc, &[]);
+        let p = doc.query_selector("p").unwrap().unwrap();
+        assert_eq!(
+            styles.style(&p),
+            Some(&css::computed::ComputedStyle::initial())
+        );
+
+        // 別の文書のnodeには何もない
+        let other = html::parse("<p>a</p>").unwrap();
+        assert_eq!(styles.style(&other.children[0]), None);
+    }
+}
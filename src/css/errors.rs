@@ -0,0 +1,153 @@
+use crate::html::errors::LoadError;
+use crate::html::position::Position;
+use thiserror::Error;
+
+static START: Position = Position {
+    line_no: 1,
+    at_line: 0,
+    at_whole: 0,
+};
+
+// どれもtokenizerは読み進められる。仕様どおりのtokenに直したうえで記録する
+#[derive(Debug, Clone, PartialEq, Error)]
+pub enum CssTokenizeError {
+    #[error("unterminated string (at {pos:?})")]
+    UnterminatedString { pos: Position },
+    #[error("newline in string (at {pos:?})")]
+    NewlineInString { pos: Position },
+    #[error("unterminated comment (at {pos:?})")]
+    UnterminatedComment { pos: Position },
+    #[error("invalid escape (at {pos:?})")]
+    InvalidEscape { pos: Position },
+    #[error("bad url (at {pos:?})")]
+    BadUrl { pos: Position },
+}
+
+impl CssTokenizeError {
+    pub fn position(&self) -> &Position {
+        return match self {
+            CssTokenizeError::UnterminatedString { pos } => pos,
+            CssTokenizeError::NewlineInString { pos } => pos,
+            CssTokenizeError::UnterminatedComment { pos } => pos,
+            CssTokenizeError::InvalidEscape { pos } => pos,
+            CssTokenizeError::BadUrl { pos } => pos,
+        };
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Error)]
+pub enum SelectorParseError {
+    #[error(transparent)]
+    Tokenize(#[from] CssTokenizeError),
+    #[error("empty selector (at {pos:?})")]
+    Empty { pos: Position },
+    #[error("combinator without a selector after it (at {pos:?})")]
+    DanglingCombinator { pos: Position },
+    #[error("unexpected token in selector: {found} (at {pos:?})")]
+    UnexpectedToken { found: String, pos: Position },
+    #[error("unknown pseudo-class: {name} (at {pos:?})")]
+    UnknownPseudoClass { name: String, pos: Position },
+}
+
+impl SelectorParseError {
+    pub fn position(&self) -> &Position {
+        return match self {
+            SelectorParseError::Tokenize(err) => err.position(),
+            SelectorParseError::Empty { pos } => pos,
+            SelectorParseError::DanglingCombinator { pos } => pos,
+            SelectorParseError::UnexpectedToken { pos, .. } => pos,
+            SelectorParseError::UnknownPseudoClass { pos, .. } => pos,
+        };
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Error)]
+pub enum MediaQueryParseError {
+    #[error(transparent)]
+    Tokenize(#[from] CssTokenizeError),
+    #[error("empty media query (at {pos:?})")]
+    Empty { pos: Position },
+    #[error("unexpected token in media query: {found} (at {pos:?})")]
+    UnexpectedToken { found: String, pos: Position },
+}
+
+impl MediaQueryParseError {
+    pub fn position(&self) -> &Position {
+        return match self {
+            MediaQueryParseError::Tokenize(err) => err.position(),
+            MediaQueryParseError::Empty { pos } => pos,
+            MediaQueryParseError::UnexpectedToken { pos, .. } => pos,
+        };
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Error)]
+pub enum CssParseError {
+    #[error(transparent)]
+    Tokenize(#[from] CssTokenizeError),
+    #[error("expected '{{' after prelude (at {pos:?})")]
+    MissingBlock { pos: Position },
+    #[error("unclosed block (at {pos:?})")]
+    UnclosedBlock { pos: Position },
+    #[error("unmatched '}}' (at {pos:?})")]
+    UnmatchedBrace { pos: Position },
+    #[error("invalid declaration (at {pos:?})")]
+    InvalidDeclaration { pos: Position },
+    // selectorが読めないruleは丸ごと捨てる
+    #[error(transparent)]
+    InvalidSelector(#[from] SelectorParseError),
+    // @mediaの条件が読めなければ中のruleごと捨てる
+    #[error(transparent)]
+    InvalidMediaQuery(#[from] MediaQueryParseError),
+    // <link> の先を読めなかった
+    #[error(transparent)]
+    Load(#[from] LoadError),
+}
+
+impl CssParseError {
+    pub fn position(&self) -> &Position {
+        return match self {
+            CssParseError::Tokenize(err) => err.position(),
+            CssParseError::MissingBlock { pos } => pos,
+            CssParseError::UnclosedBlock { pos } => pos,
+            CssParseError::UnmatchedBrace { pos } => pos,
+            CssParseError::InvalidDeclaration { pos } => pos,
+            CssParseError::InvalidSelector(err) => err.position(),
+            CssParseError::InvalidMediaQuery(err) => err.position(),
+            // 位置を持たないので文書の先頭を指す
+            CssParseError::Load(_) => &START,
+        };
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Error)]
+pub enum ColorParseError {
+    #[error(transparent)]
+    Tokenize(#[from] CssTokenizeError),
+    #[error("empty color")]
+    Empty,
+    #[error("unknown color name: {0}")]
+    UnknownName(String),
+    #[error("invalid hex color: #{0}")]
+    InvalidHex(String),
+    #[error("unknown color function: {0}()")]
+    UnknownFunction(String),
+    #[error("invalid arguments to {0}()")]
+    InvalidArguments(String),
+    #[error("unexpected token in color: {0}")]
+    UnexpectedToken(String),
+}
+
+#[derive(Debug, Clone, PartialEq, Error)]
+pub enum LengthParseError {
+    #[error(transparent)]
+    Tokenize(#[from] CssTokenizeError),
+    #[error("empty length")]
+    Empty,
+    #[error("unknown length unit: {0}")]
+    UnknownUnit(String),
+    #[error("length without a unit: {0}")]
+    MissingUnit(f64),
+    #[error("unexpected token in length: {0}")]
+    UnexpectedToken(String),
+}
@@ -0,0 +1,615 @@
+use crate::css::computed::DEFAULT_FONT_SIZE;
+use crate::css::errors::MediaQueryParseError;
+use crate::css::tokenizer::{write_ident, CssToken, CssTokenKind, CssTokenizer};
+use crate::css::values::{Length, LengthContext};
+use crate::html::position::Position;
+use std::fmt;
+
+// @mediaを評価するときの環境。viewportの大きさはpx
+#[derive(Debug, Clone, PartialEq)]
+pub struct MediaEnv {
+    pub media_type: MediaType,
+    pub width: f64,
+    pub height: f64,
+}
+
+impl Default for MediaEnv {
+    // よくある画面の大きさ
+    fn default() -> MediaEnv {
+        return MediaEnv::screen(1024.0, 768.0);
+    }
+}
+
+impl MediaEnv {
+    pub fn screen(width: f64, height: f64) -> MediaEnv {
+        return MediaEnv {
+            media_type: MediaType::Screen,
+            width,
+            height,
+        };
+    }
+
+    pub fn print(width: f64, height: f64) -> MediaEnv {
+        return MediaEnv {
+            media_type: MediaType::Print,
+            width,
+            height,
+        };
+    }
+
+    // media queryの中のemは初期のfont-sizeに対する
+    pub fn length_context(&self) -> LengthContext {
+        return LengthContext {
+            font_size: DEFAULT_FONT_SIZE,
+            root_font_size: DEFAULT_FONT_SIZE,
+            viewport_width: self.width,
+            viewport_height: self.height,
+        };
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MediaType {
+    All,
+    Screen,
+    Print,
+    // tv などの古い種類。どの環境にも合わない
+    Other(String),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Qualifier {
+    Not,
+    Only,
+}
+
+// (min-width: 600px) は Min、(width >= 600px) は Ge のように書き方ごとに持つ
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Comparison {
+    // (width: 600px)
+    Eq,
+    Min,
+    Max,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum MediaValue {
+    Length(Length),
+    Number(f64),
+    // 16/9
+    Ratio(f64, f64),
+    Ident(String),
+}
+
+// (name) か (name: value)
+#[derive(Debug, Clone, PartialEq)]
+pub struct MediaFeature {
+    // min-, max- は取り除いて小文字にしてある
+    pub name: String,
+    pub value: Option<(Comparison, MediaValue)>,
+}
+
+// not screen and (max-width: 600px)
+#[derive(Debug, Clone, PartialEq)]
+pub struct MediaQuery {
+    pub qualifier: Option<Qualifier>,
+    pub media_type: MediaType,
+    // 全部 and でつながっている
+    pub features: Vec<MediaFeature>,
+}
+
+// カンマで区切ったquery。どれかが合えば合う。空なら常に合う
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct MediaQueryList {
+    pub queries: Vec<MediaQuery>,
+}
+
+impl MediaQueryList {
+    pub fn parse(queries: &str) -> Result<MediaQueryList, MediaQueryParseError> {
+        let tokens = CssTokenizer::new(queries).tokenize()?;
+        let tokens: Vec<CssToken> = tokens
+            .into_iter()
+            .filter(|tok| !matches!(tok.kind, CssTokenKind::Comment(_)))
+            .collect();
+        return match tokens.split_last() {
+            Some((eof, body)) => parse_tokens(body, &eof.pos),
+            None => Ok(MediaQueryList::default()),
+        };
+    }
+
+    pub fn evaluate(&self, env: &MediaEnv) -> bool {
+        return self.queries.is_empty() || self.queries.iter().any(|query| query.evaluate(env));
+    }
+}
+
+impl MediaQuery {
+    pub fn evaluate(&self, env: &MediaEnv) -> bool {
+        let type_matches = match &self.media_type {
+            MediaType::All => true,
+            media_type => *media_type == env.media_type,
+        };
+        let matches = type_matches && self.features.iter().all(|feature| feature.evaluate(env));
+        return match self.qualifier {
+            Some(Qualifier::Not) => !matches,
+            _ => matches,
+        };
+    }
+}
+
+impl MediaFeature {
+    // 知らないfeatureや値の種類が合わないものは合わない
+    pub fn evaluate(&self, env: &MediaEnv) -> bool {
+        let (comparison, value) = match &self.value {
+            Some(value) => value,
+            // (width) のように値がなければ、0やnone以外かどうか
+            None => {
+                return match self.name.as_str() {
+                    "width" | "device-width" => env.width > 0.0,
+                    "height" | "device-height" => env.height > 0.0,
+                    "aspect-ratio" | "orientation" => true,
+                    _ => false,
+                };
+            }
+        };
+        let actual = match self.name.as_str() {
+            "width" | "device-width" => env.width,
+            "height" | "device-height" => env.height,
+            "aspect-ratio" => env.width / env.height,
+            "orientation" => {
+                let portrait = env.height >= env.width;
+                return *comparison == Comparison::Eq
+                    && match value {
+                        MediaValue::Ident(ident) if ident == "portrait" => portrait,
+                        MediaValue::Ident(ident) if ident == "landscape" => !portrait,
+                        _ => false,
+                    };
+            }
+            _ => return false,
+        };
+        let expected = match (self.name.as_str(), value) {
+            ("aspect-ratio", MediaValue::Ratio(w, h)) => w / h,
+            ("aspect-ratio", MediaValue::Number(ratio)) => *ratio,
+            ("aspect-ratio", _) => return false,
+            (_, MediaValue::Length(length)) => match length.to_px(&env.length_context()) {
+                Some(px) => px,
+                None => return false,
+            },
+            _ => return false,
+        };
+        return match comparison {
+            Comparison::Eq => actual == expected,
+            Comparison::Min | Comparison::Ge => actual >= expected,
+            Comparison::Max | Comparison::Le => actual <= expected,
+            Comparison::Lt => actual < expected,
+            Comparison::Gt => actual > expected,
+        };
+    }
+}
+
+// @mediaのpreludeから読む。endは最後のtokenの次の位置
+pub(crate) fn parse_tokens(
+    tokens: &[CssToken],
+    end: &Position,
+) -> Result<MediaQueryList, MediaQueryParseError> {
+    let mut queries = Vec::new();
+    let mut start = 0;
+    let mut depth = 0;
+    for (i, tok) in tokens.iter().enumerate() {
+        match tok.kind {
+            CssTokenKind::Function(_) | CssTokenKind::OpenParen => depth += 1,
+            CssTokenKind::CloseParen if depth > 0 => depth -= 1,
+            CssTokenKind::Comma if depth == 0 => {
+                queries.push(MediaQueryParser::new(&tokens[start..i], &tok.pos).query()?);
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    // @media { ... } のように何もなければ全部に合う
+    if start == 0
+        && tokens
+            .iter()
+            .all(|tok| tok.kind == CssTokenKind::Whitespace)
+    {
+        return Ok(MediaQueryList::default());
+    }
+    queries.push(MediaQueryParser::new(&tokens[start..], end).query()?);
+    return Ok(MediaQueryList { queries });
+}
+
+struct MediaQueryParser<'t> {
+    tokens: &'t [CssToken],
+    at: usize,
+    end: &'t Position,
+}
+
+impl<'t> MediaQueryParser<'t> {
+    fn new(tokens: &'t [CssToken], end: &'t Position) -> MediaQueryParser<'t> {
+        return MediaQueryParser { tokens, at: 0, end };
+    }
+
+    // 空白は読み飛ばす
+    fn peek(&mut self) -> Option<&'t CssTokenKind> {
+        while self
+            .tokens
+            .get(self.at)
+            .is_some_and(|tok| tok.kind == CssTokenKind::Whitespace)
+        {
+            self.at += 1;
+        }
+        return self.tokens.get(self.at).map(|tok| &tok.kind);
+    }
+
+    fn pos(&self) -> Position {
+        return match self.tokens.get(self.at) {
+            Some(tok) => tok.pos.clone(),
+            None => self.end.clone(),
+        };
+    }
+
+    fn unexpected(&mut self) -> MediaQueryParseError {
+        let found = match self.peek() {
+            Some(kind) => kind.to_string(),
+            None => "end of media query".to_string(),
+        };
+        return MediaQueryParseError::UnexpectedToken {
+            found,
+            pos: self.pos(),
+        };
+    }
+
+    // 次が識別子kならそれを読む
+    fn eat_ident(&mut self, keyword: &str) -> bool {
+        if let Some(CssTokenKind::Ident(ident)) = self.peek() {
+            if ident.eq_ignore_ascii_case(keyword) {
+                self.at += 1;
+                return true;
+            }
+        }
+        return false;
+    }
+
+    fn query(&mut self) -> Result<MediaQuery, MediaQueryParseError> {
+        if self.peek().is_none() {
+            return Err(MediaQueryParseError::Empty { pos: self.pos() });
+        }
+
+        let qualifier = if self.eat_ident("not") {
+            Some(Qualifier::Not)
+        } else if self.eat_ident("only") {
+            Some(Qualifier::Only)
+        } else {
+            None
+        };
+
+        let mut features = Vec::new();
+        let media_type = match self.peek() {
+            Some(CssTokenKind::Ident(ident)) if !ident.eq_ignore_ascii_case("and") => {
+                self.at += 1;
+                match ident.to_ascii_lowercase().as_str() {
+                    "all" => MediaType::All,
+                    "screen" => MediaType::Screen,
+                    "print" => MediaType::Print,
+                    other => MediaType::Other(other.to_string()),
+                }
+            }
+            // (max-width: 600px) のように種類を省いたとき
+            Some(CssTokenKind::OpenParen) if qualifier != Some(Qualifier::Only) => {
+                features.push(self.feature()?);
+                MediaType::All
+            }
+            _ => return Err(self.unexpected()),
+        };
+
+        loop {
+            if self.peek().is_none() {
+                break;
+            }
+            if !self.eat_ident("and") {
+                return Err(self.unexpected());
+            }
+            if self.peek() != Some(&CssTokenKind::OpenParen) {
+                return Err(self.unexpected());
+            }
+            features.push(self.feature()?);
+        }
+
+        return Ok(MediaQuery {
+            qualifier,
+            media_type,
+            features,
+        });
+    }
+
+    // ( name ), ( name: value ), ( name op value )
+    fn feature(&mut self) -> Result<MediaFeature, MediaQueryParseError> {
+        // ( は呼ぶ側で確かめてある
+        self.at += 1;
+        let name = match self.peek() {
+            Some(CssTokenKind::Ident(name)) => name.to_ascii_lowercase(),
+            _ => return Err(self.unexpected()),
+        };
+        self.at += 1;
+
+        let value = match self.peek() {
+            Some(CssTokenKind::CloseParen) => None,
+            Some(CssTokenKind::Colon) => {
+                self.at += 1;
+                let (name, comparison) = if let Some(name) = name.strip_prefix("min-") {
+                    (name.to_string(), Comparison::Min)
+                } else if let Some(name) = name.strip_prefix("max-") {
+                    (name.to_string(), Comparison::Max)
+                } else {
+                    (name, Comparison::Eq)
+                };
+                let value = self.value()?;
+                self.close()?;
+                return Ok(MediaFeature {
+                    name,
+                    value: Some((comparison, value)),
+                });
+            }
+            Some(CssTokenKind::Delim(op)) => {
+                let op = *op;
+                self.at += 1;
+                // <= と >= は二つのtokenになる。間に空白は置けない
+                let or_equal = self.tokens.get(self.at).map(|tok| &tok.kind)
+                    == Some(&CssTokenKind::Delim('='));
+                if or_equal && op != '=' {
+                    self.at += 1;
+                }
+                let comparison = match (op, or_equal) {
+                    ('=', _) => Comparison::Eq,
+                    ('<', false) => Comparison::Lt,
+                    ('<', true) => Comparison::Le,
+                    ('>', false) => Comparison::Gt,
+                    ('>', true) => Comparison::Ge,
+                    _ => {
+                        self.at -= 1;
+                        return Err(self.unexpected());
+                    }
+                };
+                Some((comparison, self.value()?))
+            }
+            _ => return Err(self.unexpected()),
+        };
+        self.close()?;
+        return Ok(MediaFeature { name, value });
+    }
+
+    fn close(&mut self) -> Result<(), MediaQueryParseError> {
+        if self.peek() != Some(&CssTokenKind::CloseParen) {
+            return Err(self.unexpected());
+        }
+        self.at += 1;
+        return Ok(());
+    }
+
+    fn value(&mut self) -> Result<MediaValue, MediaQueryParseError> {
+        let kind = match self.peek() {
+            Some(kind) => kind,
+            None => return Err(self.unexpected()),
+        };
+        let value = match kind {
+            CssTokenKind::Dimension { .. } | CssTokenKind::Percentage(_) => {
+                match Length::from_token(kind) {
+                    Ok(length) => MediaValue::Length(length),
+                    Err(_) => return Err(self.unexpected()),
+                }
+            }
+            CssTokenKind::Number { value, .. } => {
+                let value = *value;
+                self.at += 1;
+                // 16/9
+                if self.peek() == Some(&CssTokenKind::Delim('/')) {
+                    self.at += 1;
+                    return match self.peek() {
+                        Some(CssTokenKind::Number { value: h, .. }) => {
+                            let h = *h;
+                            self.at += 1;
+                            Ok(MediaValue::Ratio(value, h))
+                        }
+                        _ => Err(self.unexpected()),
+                    };
+                }
+                return Ok(MediaValue::Number(value));
+            }
+            CssTokenKind::Ident(ident) => MediaValue::Ident(ident.to_ascii_lowercase()),
+            _ => return Err(self.unexpected()),
+        };
+        self.at += 1;
+        return Ok(value);
+    }
+}
+
+impl fmt::Display for MediaValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        return match self {
+            MediaValue::Length(length) => write!(f, "{}", length),
+            MediaValue::Number(value) => write!(f, "{}", value),
+            MediaValue::Ratio(w, h) => write!(f, "{}/{}", w, h),
+            MediaValue::Ident(ident) => write_ident(f, ident),
+        };
+    }
+}
+
+// {:#} で書くと、: や比較の周りの空白を詰める
+impl fmt::Display for MediaFeature {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (comparison, value) = match &self.value {
+            Some(value) => value,
+            None => return write!(f, "({})", self.name),
+        };
+        let (prefix, op) = match comparison {
+            Comparison::Eq => ("", ":"),
+            Comparison::Min => ("min-", ":"),
+            Comparison::Max => ("max-", ":"),
+            Comparison::Lt => ("", "<"),
+            Comparison::Le => ("", "<="),
+            Comparison::Gt => ("", ">"),
+            Comparison::Ge => ("", ">="),
+        };
+        if f.alternate() {
+            return write!(f, "({}{}{}{})", prefix, self.name, op, value);
+        }
+        let space = if op == ":" { "" } else { " " };
+        return write!(f, "({}{}{}{} {})", prefix, self.name, space, op, value);
+    }
+}
+
+impl fmt::Display for MediaQuery {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut parts = Vec::new();
+        match self.qualifier {
+            Some(Qualifier::Not) => parts.push("not".to_string()),
+            Some(Qualifier::Only) => parts.push("only".to_string()),
+            None => {}
+        }
+        // 種類を省いて書けるのは、allで条件があるときだけ
+        match &self.media_type {
+            MediaType::All if !self.features.is_empty() && self.qualifier.is_none() => {}
+            MediaType::All => parts.push("all".to_string()),
+            MediaType::Screen => parts.push("screen".to_string()),
+            MediaType::Print => parts.push("print".to_string()),
+            MediaType::Other(name) => parts.push(name.clone()),
+        }
+        for feature in &self.features {
+            if !parts.is_empty() {
+                parts.push("and".to_string());
+            }
+            if f.alternate() {
+                parts.push(format!("{:#}", feature));
+            } else {
+                parts.push(feature.to_string());
+            }
+        }
+        return write!(f, "{}", parts.join(" "));
+    }
+}
+
+impl fmt::Display for MediaQueryList {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, query) in self.queries.iter().enumerate() {
+            if i > 0 {
+                write!(f, "{}", if f.alternate() { "," } else { ", " })?;
+            }
+            fmt::Display::fmt(query, f)?;
+        }
+        return Ok(());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::css::errors::MediaQueryParseError;
+    use crate::css::media::{MediaEnv, MediaQueryList};
+
+    #[test]
+    fn parse_and_reserialize() {
+        // (入力, 正規化した形)
+        let table = [
+            ("screen", "screen"),
+            ("ALL", "all"),
+            ("(max-width: 600px)", "(max-width: 600px)"),
+            (
+                "only screen and (min-width:40em) AND (orientation: Landscape)",
+                "only screen and (min-width: 40em) and (orientation: landscape)",
+            ),
+            ("not print", "not print"),
+            ("not (color)", "not all and (color)"),
+            (
+                "(width >= 600px) and (height<400px)",
+                "(width >= 600px) and (height < 400px)",
+            ),
+            ("(aspect-ratio: 16 / 9)", "(aspect-ratio: 16/9)"),
+            (
+                "screen, print and (min-resolution: 2)",
+                "screen, print and (min-resolution: 2)",
+            ),
+            ("", ""),
+        ];
+        for (input, expected) in table {
+            let list = MediaQueryList::parse(input).unwrap();
+            assert_eq!(list.to_string(), expected, "input: {:?}", input);
+            // 正規化した形は読み直しても同じ
+            assert_eq!(MediaQueryList::parse(expected).unwrap(), list);
+        }
+
+        for input in [
+            "screen and",
+            "screen (max-width: 1px)",
+            "(max-width 1px)",
+            "(max-width: )",
+            "screen,",
+            "only (color)",
+            "(width => 1px)",
+        ] {
+            assert!(MediaQueryList::parse(input).is_err(), "input: {:?}", input);
+        }
+        assert!(matches!(
+            MediaQueryList::parse("screen, , print"),
+            Err(MediaQueryParseError::Empty { .. })
+        ));
+    }
+
+    #[test]
+    fn evaluate_against_env() {
+        let phone = MediaEnv::screen(600.0, 800.0);
+        let desktop = MediaEnv::screen(1280.0, 720.0);
+        let paper = MediaEnv::print(600.0, 800.0);
+
+        // (query, phone, desktop, paper)
+        let table = [
+            ("all", true, true, true),
+            ("screen", true, true, false),
+            ("print", false, false, true),
+            ("not print", true, true, false),
+            ("tv", false, false, false),
+            // 境界の値は min と max の両方に合う
+            ("(max-width: 600px)", true, false, true),
+            ("(max-width: 599.9px)", false, false, false),
+            ("(min-width: 600px)", true, true, true),
+            ("(min-width: 601px)", false, true, false),
+            ("(width: 600px)", true, false, true),
+            ("(width < 600px)", false, false, false),
+            ("(width <= 600px)", true, false, true),
+            ("(width > 600px)", false, true, false),
+            ("(max-width: 37.5em)", true, false, true),
+            ("(min-width: 50vw)", true, true, true),
+            ("screen and (max-width: 600px)", true, false, false),
+            ("only screen and (min-width: 601px)", false, true, false),
+            ("not screen and (max-width: 600px)", false, true, true),
+            ("(orientation: portrait)", true, false, true),
+            ("(orientation: landscape)", false, true, false),
+            ("(min-aspect-ratio: 16/9)", false, true, false),
+            (
+                "(max-height: 720px) and (min-width: 1000px)",
+                false,
+                true,
+                false,
+            ),
+            ("print, (min-width: 1000px)", false, true, true),
+            // 知らないfeatureは合わない
+            ("(min-resolution: 2)", false, false, false),
+            ("(hover)", false, false, false),
+            ("not (hover)", true, true, true),
+            ("(max-width: 10%)", false, false, false),
+            ("", true, true, true),
+        ];
+        for (query, on_phone, on_desktop, on_paper) in table {
+            let list = MediaQueryList::parse(query).unwrap();
+            assert_eq!(list.evaluate(&phone), on_phone, "{:?} on phone", query);
+            assert_eq!(
+                list.evaluate(&desktop),
+                on_desktop,
+                "{:?} on desktop",
+                query
+            );
+            assert_eq!(list.evaluate(&paper), on_paper, "{:?} on paper", query);
+        }
+    }
+}
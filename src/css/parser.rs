@@ -0,0 +1,721 @@
+use crate::css::errors::CssParseError;
+use crate::css::media;
+use crate::css::selector;
+use crate::css::shorthand;
+use crate::css::stylesheet::{AtRule, Declaration, MediaRule, Rule, StyleRule, Stylesheet};
+use crate::css::tokenizer::{CssToken, CssTokenKind, CssTokenizer};
+use std::ops::Range;
+
+// 空白は一つにまとめ、前後の空白は落として文字列に戻す
+pub(crate) fn serialize_tokens<'t>(tokens: impl IntoIterator<Item = &'t CssToken>) -> String {
+    let mut out = String::new();
+    let mut space = false;
+    for tok in tokens {
+        if tok.kind == CssTokenKind::Whitespace {
+            space = !out.is_empty();
+            continue;
+        }
+        if space {
+            out.push(' ');
+            space = false;
+        }
+        out.push_str(&tok.kind.to_string());
+    }
+    return out;
+}
+
+// CSS Syntax Level 3 の "consume a list of rules" などに沿って読む
+pub struct CssParser<'a> {
+    source: &'a str,
+    // commentは取り除いてある。最後は必ずEof
+    tokens: Vec<CssToken>,
+    at: usize,
+    // ここから先はEofとして扱う。@mediaのblockの中を読むときに狭める
+    end: usize,
+    errors: Vec<CssParseError>,
+    // falseならshorthandを書かれたまま残す
+    expand_shorthands: bool,
+}
+
+impl<'a> CssParser<'a> {
+    pub fn new(source: &'a str) -> CssParser<'a> {
+        let (tokens, errors) = CssTokenizer::new(source).tokenize_recovering();
+        let tokens = tokens
+            .into_iter()
+            .filter(|tok| !matches!(tok.kind, CssTokenKind::Comment(_)))
+            .collect::<Vec<CssToken>>();
+        return CssParser {
+            source,
+            end: tokens.len() - 1,
+            tokens,
+            at: 0,
+            errors: errors.into_iter().map(CssParseError::from).collect(),
+            expand_shorthands: true,
+        };
+    }
+
+    // 書き直す道具などのために、shorthandをlonghandに分けずに読む
+    pub fn keep_shorthands(mut self) -> CssParser<'a> {
+        self.expand_shorthands = false;
+        return self;
+    }
+
+    pub fn parse_stylesheet(mut self) -> Stylesheet {
+        let rules = self.consume_rules();
+        return Stylesheet {
+            rules,
+            errors: self.errors,
+        };
+    }
+
+    // style="" の中身のように、{} のない宣言の並び
+    pub fn parse_declaration_list(mut self) -> (Vec<Declaration>, Vec<CssParseError>) {
+        let eof = self.tokens.len() - 1;
+        let declarations = self.consume_declarations(0..eof);
+        return (declarations, self.errors);
+    }
+
+    fn peek(&self) -> &CssTokenKind {
+        if self.at >= self.end {
+            return &self.tokens[self.tokens.len() - 1].kind;
+        }
+        return &self.tokens[self.at].kind;
+    }
+
+    // Eofより先には進まない
+    fn bump(&mut self) {
+        if self.at < self.end {
+            self.at += 1;
+        }
+    }
+
+    fn consume_rules(&mut self) -> Vec<Rule> {
+        let mut rules = Vec::new();
+        loop {
+            match self.peek() {
+                CssTokenKind::Eof => return rules,
+                CssTokenKind::Whitespace | CssTokenKind::Cdo | CssTokenKind::Cdc => self.bump(),
+                CssTokenKind::AtKeyword(_) => {
+                    if let Some(rule) = self.consume_at_rule() {
+                        rules.push(rule);
+                    }
+                }
+                // 余った } は次のruleを巻き込まないように、それだけ捨てる
+                CssTokenKind::CloseCurly => {
+                    self.errors.push(CssParseError::UnmatchedBrace {
+                        pos: self.tokens[self.at].pos.clone(),
+                    });
+                    self.bump();
+                }
+                _ => {
+                    if let Some(rule) = self.consume_style_rule() {
+                        rules.push(Rule::Style(rule));
+                    }
+                }
+            }
+        }
+    }
+
+    // 括弧で始まるものは閉じるまでを一つとして読み飛ばす
+    fn skip_component(&mut self) {
+        let mut depth = 0;
+        loop {
+            match self.peek() {
+                CssTokenKind::Eof => return,
+                CssTokenKind::OpenParen
+                | CssTokenKind::Function(_)
+                | CssTokenKind::OpenSquare
+                | CssTokenKind::OpenCurly => depth += 1,
+                CssTokenKind::CloseParen | CssTokenKind::CloseSquare | CssTokenKind::CloseCurly
+                    if depth > 0 =>
+                {
+                    depth -= 1
+                }
+                _ => {}
+            }
+            self.bump();
+            if depth == 0 {
+                return;
+            }
+        }
+    }
+
+    // { の手前まで。at-ruleは ; でも終わる
+    fn consume_prelude(&mut self, at_rule: bool) -> Range<usize> {
+        let start = self.at;
+        loop {
+            match self.peek() {
+                CssTokenKind::Eof | CssTokenKind::OpenCurly => break,
+                CssTokenKind::Semicolon if at_rule => break,
+                _ => self.skip_component(),
+            }
+        }
+        return start..self.at;
+    }
+
+    // { から対応する } までを読み、中身の範囲を返す
+    fn consume_block(&mut self) -> Range<usize> {
+        let open = self.tokens[self.at].pos.clone();
+        self.bump();
+        let start = self.at;
+        loop {
+            match self.peek() {
+                CssTokenKind::Eof => {
+                    self.errors.push(CssParseError::UnclosedBlock { pos: open });
+                    return start..self.at;
+                }
+                CssTokenKind::CloseCurly => {
+                    let end = self.at;
+                    self.bump();
+                    return start..end;
+                }
+                _ => self.skip_component(),
+            }
+        }
+    }
+
+    // tokenの範囲をsourceの文字列のまま取り出す
+    fn raw_text(&self, range: Range<usize>) -> String {
+        let byte_at =
+            |tok: &CssToken| match self.source.char_indices().nth(tok.pos.at_whole as usize) {
+                Some((i, _)) => i,
+                None => self.source.len(),
+            };
+        let start = byte_at(&self.tokens[range.start]);
+        let end = byte_at(&self.tokens[range.end]);
+        return self.source[start..end].trim().to_string();
+    }
+
+    fn consume_at_rule(&mut self) -> Option<Rule> {
+        let name = match self.peek() {
+            CssTokenKind::AtKeyword(name) => name.to_ascii_lowercase(),
+            _ => String::new(),
+        };
+        self.bump();
+        let prelude = self.consume_prelude(true);
+        if name == "media" && *self.peek() == CssTokenKind::OpenCurly {
+            let block_pos = self.tokens[self.at].pos.clone();
+            let block = self.consume_block();
+            // 条件が読めなければblockごと捨てる
+            let queries = match media::parse_tokens(&self.tokens[prelude], &block_pos) {
+                Ok(queries) => queries,
+                Err(err) => {
+                    self.errors.push(err.into());
+                    return None;
+                }
+            };
+            let rules = self.consume_nested_rules(block);
+            return Some(Rule::Media(MediaRule { queries, rules }));
+        }
+
+        let block = match self.peek() {
+            CssTokenKind::OpenCurly => {
+                let range = self.consume_block();
+                Some(self.raw_text(range))
+            }
+            CssTokenKind::Semicolon => {
+                self.bump();
+                None
+            }
+            _ => None,
+        };
+        return Some(Rule::At(AtRule {
+            name,
+            prelude: serialize_tokens(&self.tokens[prelude]),
+            block,
+        }));
+    }
+
+    // blockの中をstylesheetと同じように読む
+    fn consume_nested_rules(&mut self, block: Range<usize>) -> Vec<Rule> {
+        let (after_block, end) = (self.at, self.end);
+        self.at = block.start;
+        self.end = block.end;
+        let rules = self.consume_rules();
+        self.at = after_block;
+        self.end = end;
+        return rules;
+    }
+
+    fn consume_style_rule(&mut self) -> Option<StyleRule> {
+        let pos = self.tokens[self.at].pos.clone();
+        let prelude = self.consume_prelude(false);
+        if *self.peek() == CssTokenKind::Eof {
+            self.errors.push(CssParseError::MissingBlock { pos });
+            return None;
+        }
+        let block_pos = self.tokens[self.at].pos.clone();
+        let block = self.consume_block();
+        // selectorが読めなければblockごと捨てる
+        let selectors = match selector::parse_tokens(&self.tokens[prelude], &block_pos) {
+            Ok(selectors) => selectors,
+            Err(err) => {
+                self.errors.push(err.into());
+                return None;
+            }
+        };
+        let declarations = self.consume_declarations(block);
+        return Some(StyleRule {
+            selectors,
+            declarations,
+        });
+    }
+
+    // blockの中を ; で区切って読む
+    fn consume_declarations(&mut self, block: Range<usize>) -> Vec<Declaration> {
+        let after_block = self.at;
+        self.at = block.start;
+
+        let mut declarations = Vec::new();
+        while self.at < block.end {
+            match self.peek() {
+                CssTokenKind::Whitespace | CssTokenKind::Semicolon => self.bump(),
+                // 宣言の中のat-ruleは扱わない。; か {} の終わりまでを捨てる
+                CssTokenKind::AtKeyword(_) => {
+                    self.errors.push(CssParseError::InvalidDeclaration {
+                        pos: self.tokens[self.at].pos.clone(),
+                    });
+                    while self.at < block.end {
+                        match self.peek() {
+                            CssTokenKind::Semicolon => break,
+                            CssTokenKind::OpenCurly => {
+                                self.skip_component();
+                                break;
+                            }
+                            _ => self.skip_component(),
+                        }
+                    }
+                }
+                _ => {
+                    let start = self.at;
+                    while self.at < block.end && *self.peek() != CssTokenKind::Semicolon {
+                        self.skip_component();
+                    }
+                    match self.declaration(start..self.at) {
+                        // shorthandはここでlonghandに分けておく
+                        Some(declaration) if !self.expand_shorthands => {
+                            declarations.push(declaration)
+                        }
+                        Some(declaration) => {
+                            for mut longhand in
+                                shorthand::expand(&declaration.name, &declaration.value)
+                            {
+                                longhand.important = declaration.important;
+                                declarations.push(longhand);
+                            }
+                        }
+                        None => self.errors.push(CssParseError::InvalidDeclaration {
+                            pos: self.tokens[start].pos.clone(),
+                        }),
+                    }
+                }
+            }
+        }
+
+        self.at = after_block;
+        return declarations;
+    }
+
+    // name: value !important
+    fn declaration(&self, range: Range<usize>) -> Option<Declaration> {
+        let tokens = &self.tokens[range];
+        let name = match &tokens[0].kind {
+            CssTokenKind::Ident(name) => name,
+            _ => return None,
+        };
+        let mut rest = tokens[1..]
+            .iter()
+            .skip_while(|tok| tok.kind == CssTokenKind::Whitespace);
+        match rest.next() {
+            Some(tok) if tok.kind == CssTokenKind::Colon => {}
+            _ => return None,
+        }
+
+        let mut value: Vec<&CssToken> = rest.collect();
+        // 壊れた文字列やurl、対応しない閉じ括弧を含む値は捨てる
+        let mut depth = 0;
+        for tok in &value {
+            match tok.kind {
+                CssTokenKind::BadString | CssTokenKind::BadUrl => return None,
+                CssTokenKind::Function(_)
+                | CssTokenKind::OpenParen
+                | CssTokenKind::OpenSquare
+                | CssTokenKind::OpenCurly => depth += 1,
+                CssTokenKind::CloseParen | CssTokenKind::CloseSquare | CssTokenKind::CloseCurly => {
+                    if depth == 0 {
+                        return None;
+                    }
+                    depth -= 1;
+                }
+                _ => {}
+            }
+        }
+        let trim_end = |value: &mut Vec<&CssToken>| {
+            while matches!(value.last(), Some(tok) if tok.kind == CssTokenKind::Whitespace) {
+                value.pop();
+            }
+        };
+        trim_end(&mut value);
+
+        let mut important = false;
+        if let Some(CssTokenKind::Ident(last)) = value.last().map(|tok| &tok.kind) {
+            if last.eq_ignore_ascii_case("important") {
+                let mut bang = value.len() - 1;
+                while bang > 0 && value[bang - 1].kind == CssTokenKind::Whitespace {
+                    bang -= 1;
+                }
+                if bang > 0 && value[bang - 1].kind == CssTokenKind::Delim('!') {
+                    important = true;
+                    value.truncate(bang - 1);
+                    trim_end(&mut value);
+                }
+            }
+        }
+
+        // custom propertyだけは空の値を許す
+        let custom = name.starts_with("--");
+        if value.is_empty() && !custom {
+            return None;
+        }
+        return Some(Declaration {
+            name: if custom {
+                name.clone()
+            } else {
+                name.to_ascii_lowercase()
+            },
+            value: serialize_tokens(value),
+            important,
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::css;
+    use crate::css::errors::CssParseError;
+    use crate::css::media::MediaEnv;
+    use crate::css::stylesheet::{AtRule, Declaration, Rule};
+    use crate::html::position::Position;
+
+    fn decl(name: &str, value: &str, important: bool) -> Declaration {
+        return Declaration {
+            name: name.to_string(),
+            value: value.to_string(),
+            important,
+        };
+    }
+
+    const SHEET: &str = r#"
+/* reset */
+@charset "utf-8";
+@import url(base.css) screen;
+
+html, body {
+    margin: 0;
+    padding: 0;
+}
+
+a.nav > li:hover,
+  #main   .item {
+    color: #333 !important;
+    background: url(bg.png) no-repeat;
+    font-family: "Helvetica Neue", Arial, sans-serif; /* fallback */
+    --Accent: rgb(0, 0, 255);
+}
+
+@media (max-width: 600px) {
+    .item { display: none; }
+}
+"#;
+
+    #[test]
+    fn parse_realistic_stylesheet() {
+        let sheet = css::parse_stylesheet(SHEET).unwrap();
+        assert_eq!(sheet.rules.len(), 5);
+
+        assert_eq!(
+            sheet.rules[0],
+            Rule::At(AtRule {
+                name: "charset".to_string(),
+                prelude: "\"utf-8\"".to_string(),
+                block: None,
+            })
+        );
+        assert_eq!(
+            sheet.rules[1],
+            Rule::At(AtRule {
+                name: "import".to_string(),
+                prelude: "url(base.css) screen".to_string(),
+                block: None,
+            })
+        );
+
+        let rules: Vec<_> = sheet.style_rules().collect();
+        assert_eq!(rules.len(), 2);
+        assert_eq!(rules[0].selectors.to_string(), "html, body");
+        let names: Vec<&str> = rules[0]
+            .declarations
+            .iter()
+            .map(|d| d.name.as_str())
+            .collect();
+        assert_eq!(
+            names,
+            vec![
+                "margin-top",
+                "margin-right",
+                "margin-bottom",
+                "margin-left",
+                "padding-top",
+                "padding-right",
+                "padding-bottom",
+                "padding-left",
+            ]
+        );
+        assert_eq!(
+            rules[1].selectors.to_string(),
+            "a.nav > li:hover, #main .item"
+        );
+        assert_eq!(
+            rules[1].declarations,
+            vec![
+                decl("color", "#333", true),
+                decl("background-color", "transparent", false),
+                decl("background-image", "url(bg.png)", false),
+                decl("background-repeat", "no-repeat", false),
+                decl("background-attachment", "scroll", false),
+                decl("background-position", "0% 0%", false),
+                decl(
+                    "font-family",
+                    "\"Helvetica Neue\", Arial, sans-serif",
+                    false
+                ),
+                decl("--Accent", "rgb(0, 0, 255)", false),
+            ]
+        );
+
+        let media = match &sheet.rules[4] {
+            Rule::Media(media) => media,
+            rule => panic!("not a media rule: {:?}", rule),
+        };
+        assert_eq!(media.queries.to_string(), "(max-width: 600px)");
+        assert_eq!(media.rules.len(), 1);
+        let nested: Vec<_> = sheet.style_rules_for(&MediaEnv::screen(600.0, 800.0));
+        assert_eq!(nested.len(), 3);
+        assert_eq!(nested[2].declarations, vec![decl("display", "none", false)]);
+        assert_eq!(sheet.style_rules_for(&MediaEnv::default()).len(), 2);
+    }
+
+    #[test]
+    fn recover_from_typos_mid_file() {
+        let sheet = css::parse_stylesheet_recovering(
+            "h1 { color: red }
+p { colr red; *zoom: 1; -webkit-box-shadow: none; content: \"abc
+; width: calc(1px)); margin-top: 0 }
+div, { color: blue }
+@charset; em { font-style: italic; @nested x { y: 1 } font-weight: bold }
+a:hovr { color: green }
+ul { list-style: none }",
+        );
+        let rules: Vec<(String, Vec<String>)> = sheet
+            .style_rules()
+            .map(|rule| {
+                let declarations = rule
+                    .declarations
+                    .iter()
+                    .map(|d| format!("{}: {}", d.name, d.value))
+                    .collect();
+                (rule.selectors.to_string(), declarations)
+            })
+            .collect();
+        let expected: Vec<(String, Vec<String>)> = vec![
+            ("h1", vec!["color: red"]),
+            ("p", vec!["-webkit-box-shadow: none", "margin-top: 0"]),
+            ("em", vec!["font-style: italic", "font-weight: bold"]),
+            ("ul", vec!["list-style: none"]),
+        ]
+        .into_iter()
+        .map(|(selector, declarations)| {
+            (
+                selector.to_string(),
+                declarations.into_iter().map(String::from).collect(),
+            )
+        })
+        .collect();
+        assert_eq!(rules, expected);
+
+        // 捨てたものは位置と一緒に残る
+        let errors: Vec<(&str, u32)> = sheet
+            .errors
+            .iter()
+            .map(|err| {
+                let kind = match err {
+                    CssParseError::Tokenize(_) => "tokenize",
+                    CssParseError::InvalidDeclaration { .. } => "declaration",
+                    CssParseError::InvalidSelector(_) => "selector",
+                    _ => "other",
+                };
+                (kind, err.position().line_no)
+            })
+            .collect();
+        assert_eq!(
+            errors,
+            vec![
+                ("tokenize", 2),
+                ("declaration", 2),
+                ("declaration", 2),
+                ("declaration", 2),
+                ("declaration", 3),
+                ("selector", 4),
+                ("declaration", 5),
+                ("selector", 6),
+            ]
+        );
+    }
+
+    #[test]
+    fn recover_from_mismatched_braces() {
+        let selectors = |css: &str| -> (Vec<String>, usize) {
+            let sheet = css::parse_stylesheet_recovering(css);
+            let selectors = sheet
+                .style_rules()
+                .map(|rule| rule.selectors.to_string())
+                .collect();
+            return (selectors, sheet.errors.len());
+        };
+
+        // 余った } はそれだけ捨てる
+        assert_eq!(
+            selectors("a { color: red } } b { color: blue } }} c { }"),
+            (vec!["a".to_string(), "b".to_string(), "c".to_string()], 3)
+        );
+        assert_eq!(
+            selectors("@media screen { a { } } } b { }"),
+            (vec!["b".to_string()], 1)
+        );
+        // 宣言の中の {} は対応する } までを一つとして読み飛ばす
+        assert_eq!(
+            selectors("a { color: red; { } margin: 0 } b { color: blue }"),
+            (vec!["a".to_string(), "b".to_string()], 1)
+        );
+        // 閉じていないblockは最後まで続く
+        assert_eq!(
+            selectors("a { color: red b { color: blue }"),
+            (vec!["a".to_string()], 1)
+        );
+
+        let err = css::parse_stylesheet("a { } }").unwrap_err();
+        assert_eq!(
+            err,
+            CssParseError::UnmatchedBrace {
+                pos: Position::new(1, 6, 6)
+            }
+        );
+
+        // style属性でも同じように読む
+        let (declarations, errors) =
+            css::parse_declarations_recovering("color: red; width: 1px); @x; margin-top: 0");
+        assert_eq!(
+            declarations,
+            vec![decl("color", "red", false), decl("margin-top", "0", false)]
+        );
+        assert_eq!(errors.len(), 2);
+    }
+
+    #[test]
+    fn parse_nested_media_rules() {
+        let sheet = css::parse_stylesheet_recovering(
+            "@media screen { a { color: red } @media (min-width: 800px) { b { color: blue } } c }
+             @media screen and foo { d { color: green } }
+             @media print, (max-width: 300px) { e { color: black } }
+             f { color: gray }",
+        );
+        let names = |env: &MediaEnv| -> Vec<String> {
+            return sheet
+                .style_rules_for(env)
+                .iter()
+                .map(|rule| rule.selectors.to_string())
+                .collect();
+        };
+        assert_eq!(names(&MediaEnv::screen(1024.0, 768.0)), vec!["a", "b", "f"]);
+        assert_eq!(names(&MediaEnv::screen(300.0, 768.0)), vec!["a", "e", "f"]);
+        assert_eq!(names(&MediaEnv::print(1024.0, 768.0)), vec!["e", "f"]);
+
+        // blockの中の c は { がないので捨て、読めない条件の@mediaは中身ごと捨てる
+        assert_eq!(sheet.rules.len(), 3);
+        assert_eq!(sheet.errors.len(), 2);
+        assert!(matches!(
+            sheet.errors[0],
+            CssParseError::MissingBlock { .. }
+        ));
+        assert!(matches!(
+            sheet.errors[1],
+            CssParseError::InvalidMediaQuery(_)
+        ));
+    }
+
+    #[test]
+    fn parse_declaration_details() {
+        let sheet =
+            css::parse_stylesheet("p{COLOR:Red!IMPORTANT;;margin : 0 auto ; --empty:;}").unwrap();
+        let rule = sheet.style_rules().next().unwrap();
+        assert_eq!(
+            rule.declarations,
+            vec![
+                decl("color", "Red", true),
+                decl("margin-top", "0", false),
+                decl("margin-right", "auto", false),
+                decl("margin-bottom", "0", false),
+                decl("margin-left", "auto", false),
+                decl("--empty", "", false),
+            ]
+        );
+
+        // ; は括弧の中では区切りにならない
+        let sheet =
+            css::parse_stylesheet("p { background-image: url(a;b.png); content: fn(a; b) }")
+                .unwrap();
+        let rule = sheet.style_rules().next().unwrap();
+        assert_eq!(rule.declarations[0].value, "url(a;b.png)");
+        assert_eq!(rule.declarations[1].value, "fn(a; b)");
+    }
+
+    #[test]
+    fn parse_inline_declarations() {
+        let declarations = css::parse_declarations(" color: red; width:0 !important ").unwrap();
+        assert_eq!(
+            declarations,
+            vec![decl("color", "red", false), decl("width", "0", true)]
+        );
+        assert_eq!(css::parse_declarations("").unwrap(), vec![]);
+
+        let (declarations, errors) = css::parse_declarations_recovering("color; }x: 1; width: 2px");
+        assert_eq!(declarations, vec![decl("width", "2px", false)]);
+        assert_eq!(errors.len(), 2);
+    }
+
+    #[test]
+    fn parse_errors_are_collected() {
+        let err = css::parse_stylesheet("p { color red; width: 0 }").unwrap_err();
+        assert_eq!(
+            err,
+            CssParseError::InvalidDeclaration {
+                pos: Position::new(1, 4, 4)
+            }
+        );
+
+        let sheet = css::parse_stylesheet_recovering("p { color red; width: 0 } div { color: \"x");
+        assert_eq!(sheet.rules.len(), 2);
+        let rules: Vec<_> = sheet.style_rules().collect();
+        assert_eq!(rules[0].declarations, vec![decl("width", "0", false)]);
+        assert_eq!(rules[1].declarations, vec![decl("color", "\"x\"", false)]);
+        // 壊れた宣言, 閉じていない文字列, 閉じていないblock
+        assert_eq!(sheet.errors.len(), 3);
+
+        let sheet = css::parse_stylesheet_recovering("p { color: red } h1");
+        assert_eq!(sheet.rules.len(), 1);
+        assert!(matches!(
+            sheet.errors[0],
+            CssParseError::MissingBlock { .. }
+        ));
+    }
+}
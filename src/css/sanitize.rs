@@ -0,0 +1,352 @@
+use crate::css::parser::CssParser;
+use crate::css::stylesheet::{CssFormat, Declaration, Rule, Stylesheet};
+use crate::css::tokenizer::{CssTokenKind, CssTokenizer};
+use std::collections::HashSet;
+
+// 既定で残すproperty。shorthandも書かれたまま調べる
+const DEFAULT_PROPERTIES: [&str; 75] = [
+    "background",
+    "background-attachment",
+    "background-color",
+    "background-image",
+    "background-position",
+    "background-repeat",
+    "border",
+    "border-bottom",
+    "border-bottom-color",
+    "border-bottom-style",
+    "border-bottom-width",
+    "border-collapse",
+    "border-color",
+    "border-left",
+    "border-left-color",
+    "border-left-style",
+    "border-left-width",
+    "border-radius",
+    "border-right",
+    "border-right-color",
+    "border-right-style",
+    "border-right-width",
+    "border-spacing",
+    "border-style",
+    "border-top",
+    "border-top-color",
+    "border-top-style",
+    "border-top-width",
+    "border-width",
+    "clear",
+    "color",
+    "direction",
+    "display",
+    "float",
+    "font",
+    "font-family",
+    "font-size",
+    "font-stretch",
+    "font-style",
+    "font-variant",
+    "font-weight",
+    "height",
+    "letter-spacing",
+    "line-height",
+    "list-style",
+    "list-style-position",
+    "list-style-type",
+    "margin",
+    "margin-bottom",
+    "margin-left",
+    "margin-right",
+    "margin-top",
+    "max-height",
+    "max-width",
+    "min-height",
+    "min-width",
+    "overflow",
+    "padding",
+    "padding-bottom",
+    "padding-left",
+    "padding-right",
+    "padding-top",
+    "table-layout",
+    "text-align",
+    "text-decoration",
+    "text-decoration-color",
+    "text-decoration-line",
+    "text-decoration-style",
+    "text-indent",
+    "text-transform",
+    "vertical-align",
+    "visibility",
+    "white-space",
+    "width",
+    "word-spacing",
+];
+
+// allow-listにあっても残さないもの。どれも要素にコードを結びつけられる
+const DANGEROUS_PROPERTIES: [&str; 2] = ["-moz-binding", "behavior"];
+
+// url() で読ませてはいけないscheme
+const DANGEROUS_SCHEMES: [&str; 3] = ["javascript:", "vbscript:", "livescript:"];
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct CssSanitizePolicy {
+    // 残すproperty。小文字で書く
+    pub allowed_properties: HashSet<String>,
+    // falseならurl() を含む値を全部捨てる。trueでもjavascript: などは捨てる
+    pub allow_urls: bool,
+}
+
+impl Default for CssSanitizePolicy {
+    fn default() -> CssSanitizePolicy {
+        return CssSanitizePolicy {
+            allowed_properties: DEFAULT_PROPERTIES.iter().map(|p| p.to_string()).collect(),
+            allow_urls: true,
+        };
+    }
+}
+
+impl CssSanitizePolicy {
+    pub fn allows(&self, declaration: &Declaration) -> bool {
+        let name = declaration.name.as_str();
+        if DANGEROUS_PROPERTIES.contains(&name) || !self.allowed_properties.contains(name) {
+            return false;
+        }
+        return self.allows_value(&declaration.value)
+            && !closes_style_element(&declaration.to_css(CssFormat::Compact));
+    }
+
+    fn allows_value(&self, value: &str) -> bool {
+        let (tokens, errors) = CssTokenizer::new(value).tokenize_recovering();
+        if !errors.is_empty() {
+            return false;
+        }
+        // 開いている括弧ごとに、中の文字列がURLかどうか
+        let mut url_strings: Vec<bool> = Vec::new();
+        for tok in &tokens {
+            match &tok.kind {
+                // IEの expression() はscriptを動かす
+                CssTokenKind::Function(name) if name.eq_ignore_ascii_case("expression") => {
+                    return false
+                }
+                CssTokenKind::Function(name) if name.eq_ignore_ascii_case("url") => {
+                    if !self.allow_urls {
+                        return false;
+                    }
+                    url_strings.push(true);
+                }
+                // image-set("a.png" 1x) の文字列もURLとして読まれる
+                CssTokenKind::Function(name)
+                    if name.eq_ignore_ascii_case("image-set")
+                        || name.eq_ignore_ascii_case("-webkit-image-set") =>
+                {
+                    url_strings.push(true);
+                }
+                CssTokenKind::Function(_) | CssTokenKind::OpenParen => url_strings.push(false),
+                CssTokenKind::Url(url) if !self.allow_urls || is_dangerous_url(url) => {
+                    return false
+                }
+                // url("...") や image-set("...") の中の文字列
+                CssTokenKind::String(url)
+                    if url_strings.last() == Some(&true) && is_dangerous_url(url) =>
+                {
+                    return false
+                }
+                CssTokenKind::CloseParen => {
+                    url_strings.pop();
+                }
+                // @import などは値の中に来ない
+                CssTokenKind::AtKeyword(_) | CssTokenKind::BadUrl | CssTokenKind::BadString => {
+                    return false
+                }
+                _ => {}
+            }
+        }
+        return true;
+    }
+}
+
+fn is_dangerous_url(url: &str) -> bool {
+    // java\tscript: のように空白や制御文字を挟んでも同じschemeとして扱われる
+    let url: String = url
+        .chars()
+        .filter(|c| !c.is_ascii_whitespace() && !c.is_control())
+        .collect::<String>()
+        .to_ascii_lowercase();
+    return DANGEROUS_SCHEMES
+        .iter()
+        .any(|scheme| url.starts_with(scheme));
+}
+
+// <style> の中に書くと、HTMLのparserはそこで <style> を閉じて続きをHTMLとして読む
+// 値はそのまま、selectorの文字列はエスケープを戻して書くので、書き出したものを調べる
+fn closes_style_element(css: &str) -> bool {
+    return css.to_ascii_lowercase().contains("</style");
+}
+
+// style属性の中身を調べ、残った宣言だけを書き直す
+pub fn sanitize_declarations(style: &str, policy: &CssSanitizePolicy) -> String {
+    let (declarations, _) = CssParser::new(style)
+        .keep_shorthands()
+        .parse_declaration_list();
+    return declarations
+        .iter()
+        .filter(|declaration| policy.allows(declaration))
+        .map(|declaration| declaration.to_css(CssFormat::Compact))
+        .collect::<Vec<String>>()
+        .join(";");
+}
+
+// <style> の中身を調べ、残ったruleだけを書き直す
+pub fn sanitize_stylesheet(css: &str, policy: &CssSanitizePolicy) -> String {
+    let sheet = CssParser::new(css).keep_shorthands().parse_stylesheet();
+    let sheet = Stylesheet {
+        rules: sanitize_rules(sheet.rules, policy),
+        errors: Vec::new(),
+    };
+    return sheet.to_css(CssFormat::Compact);
+}
+
+fn sanitize_rules(rules: Vec<Rule>, policy: &CssSanitizePolicy) -> Vec<Rule> {
+    let mut kept = Vec::new();
+    for rule in rules {
+        match rule {
+            Rule::Style(mut style) => {
+                if closes_style_element(&format!("{:#}", style.selectors)) {
+                    continue;
+                }
+                style
+                    .declarations
+                    .retain(|declaration| policy.allows(declaration));
+                if !style.declarations.is_empty() {
+                    kept.push(Rule::Style(style));
+                }
+            }
+            Rule::Media(mut media) => {
+                if closes_style_element(&format!("{:#}", media.queries)) {
+                    continue;
+                }
+                media.rules = sanitize_rules(media.rules, policy);
+                if !media.rules.is_empty() {
+                    kept.push(Rule::Media(media));
+                }
+            }
+            // 中身を調べられないので、@import, @font-face なども含めて全部捨てる
+            Rule::At(_) => {}
+        }
+    }
+    return kept;
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::css::sanitize::{sanitize_declarations, sanitize_stylesheet, CssSanitizePolicy};
+
+    #[test]
+    fn sanitize_style_attributes() {
+        let policy = CssSanitizePolicy::default();
+        let table = [
+            ("background:url(javascript:alert(1));color:red", "color:red"),
+            (
+                "background: url('JavaScript:alert(1)'); color: red",
+                "color:red",
+            ),
+            ("background-image: url(\"java\\9script:x\")", ""),
+            ("background-image: url(\" vbscript:x\")", ""),
+            (
+                "background-image: image-set(\"javascript:x\" 1x, \"a.png\" 2x)",
+                "",
+            ),
+            (
+                "background-image: image-set(url(a.png) 1x, \"JavaScript:x\" 2x)",
+                "",
+            ),
+            (
+                "background-image: image-set(\"a.png\" 1x)",
+                "background-image:image-set(\"a.png\" 1x)",
+            ),
+            ("font-family: \"</style><script>alert(1)</script>\"", ""),
+            ("font-family: \"</STYLE \"; color: red", "color:red"),
+            ("width: expression(alert(1)); height: 1px", "height:1px"),
+            ("width: EXPR\\45 SSION(alert(1))", ""),
+            ("-moz-binding: url(x.xml#xss); color: blue", "color:blue"),
+            ("behavior: url(x.htc)", ""),
+            ("position: fixed; top: 0; margin: 0 auto", "margin:0 auto"),
+            ("color: red !important; --x: 1", "color:red!important"),
+            (
+                "background: url(a.png) no-repeat",
+                "background:url(a.png) no-repeat",
+            ),
+            ("color red; font-weight: bold", "font-weight:bold"),
+            ("", ""),
+        ];
+        for (style, expected) in table {
+            assert_eq!(
+                sanitize_declarations(style, &policy),
+                expected,
+                "{:?}",
+                style
+            );
+        }
+
+        let no_urls = CssSanitizePolicy {
+            allow_urls: false,
+            ..CssSanitizePolicy::default()
+        };
+        assert_eq!(
+            sanitize_declarations("background: url(a.png) red; color: red", &no_urls),
+            "color:red"
+        );
+
+        let colors_only = CssSanitizePolicy {
+            allowed_properties: ["color".to_string()].into_iter().collect(),
+            ..CssSanitizePolicy::default()
+        };
+        assert_eq!(
+            sanitize_declarations("color: red; margin: 0", &colors_only),
+            "color:red"
+        );
+    }
+
+    #[test]
+    fn sanitize_style_elements() {
+        let policy = CssSanitizePolicy::default();
+        assert_eq!(
+            sanitize_stylesheet(
+                "@import url(evil.css); p { color: red } h1 { font-size: 2em }",
+                &policy
+            ),
+            "p{color:red}h1{font-size:2em}"
+        );
+        assert_eq!(
+            sanitize_stylesheet(
+                "@font-face { src: url(x.woff) }
+                 a { color: blue; background: url(javascript:x) }
+                 div { position: absolute }
+                 @media print { @import \"x.css\"; p { display: none; -moz-binding: url(x) } }
+                 @media screen { div { z-index: 9 } }",
+                &policy
+            ),
+            "a{color:blue}@media print{p{display:none}}"
+        );
+    }
+
+    #[test]
+    fn style_element_breakouts_are_dropped() {
+        let policy = CssSanitizePolicy::default();
+        for css in [
+            "a{font-family:\"</style><script>alert(1)</script>\"}",
+            "a[title=\"</style><script>alert(1)</script>\"]{color:red}",
+            // エスケープしても、selectorの文字列は戻してから書く
+            "a[title=\"\\3c /style>\"]{color:red}",
+            "@media print{a[title=\"</style>\"]{color:red}}",
+        ] {
+            let out = sanitize_stylesheet(&format!("{} p{{color:red}}", css), &policy);
+            assert_eq!(out, "p{color:red}", "{}", css);
+        }
+        // <style> を閉じない "<" はそのまま
+        assert_eq!(
+            sanitize_stylesheet("a[title=\"a<b\"]{font-family:\"x</y\"}", &policy),
+            "a[title=\"a<b\"]{font-family:\"x</y\"}"
+        );
+    }
+}
@@ -0,0 +1,1225 @@
+use crate::css::errors::SelectorParseError;
+use crate::css::tokenizer::{write_ident, write_string, CssToken, CssTokenKind, CssTokenizer};
+use crate::html::atom::Atom;
+use crate::html::document::ElementRef;
+use crate::html::parser::Node;
+use crate::html::position::Position;
+use std::fmt;
+use std::sync::Arc;
+
+// 一つのコロンでも書ける古い疑似要素
+const LEGACY_PSEUDO_ELEMENTS: [&str; 4] = ["before", "after", "first-line", "first-letter"];
+
+// 利用者の操作で変わる状態。静的な文書では合うことがない
+const STATE_PSEUDO_CLASSES: [&str; 8] = [
+    "active",
+    "focus",
+    "focus-visible",
+    "focus-within",
+    "hover",
+    "target",
+    "visited",
+    "placeholder-shown",
+];
+
+// disabledを持てる要素
+const FORM_ELEMENTS: [&str; 7] = [
+    "button", "fieldset", "input", "optgroup", "option", "select", "textarea",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Combinator {
+    // a b
+    Descendant,
+    // a > b
+    Child,
+    // a + b
+    NextSibling,
+    // a ~ b
+    SubsequentSibling,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AttrOp {
+    // [a=v]
+    Equals,
+    // [a~=v] 空白で区切った中のどれか
+    Includes,
+    // [a|=v] v か v- で始まる
+    DashMatch,
+    // [a^=v]
+    Prefix,
+    // [a$=v]
+    Suffix,
+    // [a*=v]
+    Substring,
+}
+
+// :nth-child(an+b)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Nth {
+    pub a: i32,
+    pub b: i32,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum PseudoClass {
+    Root,
+    Empty,
+    FirstChild,
+    LastChild,
+    OnlyChild,
+    FirstOfType,
+    LastOfType,
+    OnlyOfType,
+    NthChild(Nth),
+    NthLastChild(Nth),
+    NthOfType(Nth),
+    NthLastOfType(Nth),
+    Not(SelectorList),
+    Link,
+    Checked,
+    Disabled,
+    Enabled,
+    // :hover など
+    State(String),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum SimpleSelector {
+    Type(Atom),
+    Universal,
+    Id(String),
+    Class(String),
+    Attribute {
+        // 小文字にしてある
+        name: String,
+        op: Option<(AttrOp, String)>,
+        case_insensitive: bool,
+    },
+    PseudoClass(PseudoClass),
+}
+
+// a.nav:hover のように、空白なしで並んだもの
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Compound {
+    pub simple: Vec<SimpleSelector>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Selector {
+    // 左から順。combinators[i] が compounds[i] と compounds[i + 1] をつなぐ
+    pub compounds: Vec<Compound>,
+    pub combinators: Vec<Combinator>,
+    // ::before など。要素そのものには合わない
+    pub pseudo_element: Option<String>,
+}
+
+// (idの数, classと属性と疑似クラスの数, 要素名と疑似要素の数)。大きいほど優先される
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Specificity(pub u32, pub u32, pub u32);
+
+// a, b
+#[derive(Debug, Clone, PartialEq)]
+pub struct SelectorList {
+    pub selectors: Vec<Selector>,
+}
+
+pub fn parse(selectors: &str) -> Result<SelectorList, SelectorParseError> {
+    let tokens = CssTokenizer::new(selectors).tokenize()?;
+    let tokens: Vec<CssToken> = tokens
+        .into_iter()
+        .filter(|tok| !matches!(tok.kind, CssTokenKind::Comment(_)))
+        .collect();
+    return match tokens.split_last() {
+        Some((eof, body)) => parse_tokens(body, &eof.pos),
+        None => Err(SelectorParseError::Empty {
+            pos: Position::new(1, 0, 0),
+        }),
+    };
+}
+
+// stylesheetのpreludeから読む。endは最後のtokenの次の位置
+pub(crate) fn parse_tokens(
+    tokens: &[CssToken],
+    end: &Position,
+) -> Result<SelectorList, SelectorParseError> {
+    let mut selectors = Vec::new();
+    let mut start = 0;
+    let mut depth = 0;
+    for (i, tok) in tokens.iter().enumerate() {
+        match tok.kind {
+            CssTokenKind::Function(_) | CssTokenKind::OpenParen | CssTokenKind::OpenSquare => {
+                depth += 1
+            }
+            CssTokenKind::CloseParen | CssTokenKind::CloseSquare if depth > 0 => depth -= 1,
+            CssTokenKind::Comma if depth == 0 => {
+                match SelectorParser::new(&tokens[start..i], &tok.pos).selector() {
+                    Ok(selector) => selectors.push(selector),
+                    Err(err) => return Err(err),
+                }
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    match SelectorParser::new(&tokens[start..], end).selector() {
+        Ok(selector) => selectors.push(selector),
+        Err(err) => return Err(err),
+    }
+    return Ok(SelectorList { selectors });
+}
+
+struct SelectorParser<'t> {
+    tokens: &'t [CssToken],
+    at: usize,
+    end: &'t Position,
+}
+
+impl<'t> SelectorParser<'t> {
+    fn new(tokens: &'t [CssToken], end: &'t Position) -> SelectorParser<'t> {
+        return SelectorParser { tokens, at: 0, end };
+    }
+
+    fn peek(&self) -> Option<&'t CssTokenKind> {
+        return self.tokens.get(self.at).map(|tok| &tok.kind);
+    }
+
+    fn pos(&self) -> Position {
+        return match self.tokens.get(self.at) {
+            Some(tok) => tok.pos.clone(),
+            None => self.end.clone(),
+        };
+    }
+
+    fn unexpected(&self) -> SelectorParseError {
+        let found = match self.peek() {
+            Some(kind) => kind.to_string(),
+            None => "end of selector".to_string(),
+        };
+        return SelectorParseError::UnexpectedToken {
+            found,
+            pos: self.pos(),
+        };
+    }
+
+    fn skip_ws(&mut self) -> bool {
+        let start = self.at;
+        while self.peek() == Some(&CssTokenKind::Whitespace) {
+            self.at += 1;
+        }
+        return self.at > start;
+    }
+
+    fn selector(&mut self) -> Result<Selector, SelectorParseError> {
+        self.skip_ws();
+        if self.peek().is_none() {
+            return Err(SelectorParseError::Empty { pos: self.pos() });
+        }
+
+        let mut compounds = Vec::new();
+        let mut combinators = Vec::new();
+        loop {
+            let (compound, pseudo_element) = self.compound()?;
+            compounds.push(compound);
+            if pseudo_element.is_some() {
+                // 疑似要素は最後にしか書けない
+                self.skip_ws();
+                if self.peek().is_some() {
+                    return Err(self.unexpected());
+                }
+                return Ok(Selector {
+                    compounds,
+                    combinators,
+                    pseudo_element,
+                });
+            }
+
+            let ws = self.skip_ws();
+            let combinator = match self.peek() {
+                None => break,
+                Some(CssTokenKind::Delim('>')) => Combinator::Child,
+                Some(CssTokenKind::Delim('+')) => Combinator::NextSibling,
+                Some(CssTokenKind::Delim('~')) => Combinator::SubsequentSibling,
+                Some(_) if ws => Combinator::Descendant,
+                Some(_) => return Err(self.unexpected()),
+            };
+            if combinator != Combinator::Descendant {
+                self.at += 1;
+                self.skip_ws();
+                if self.peek().is_none() {
+                    return Err(SelectorParseError::DanglingCombinator { pos: self.pos() });
+                }
+            }
+            combinators.push(combinator);
+        }
+
+        return Ok(Selector {
+            compounds,
+            combinators,
+            pseudo_element: None,
+        });
+    }
+
+    fn compound(&mut self) -> Result<(Compound, Option<String>), SelectorParseError> {
+        let mut simple = Vec::new();
+        match self.peek() {
+            Some(CssTokenKind::Ident(name)) => {
+                simple.push(SimpleSelector::Type(Atom::new(name)));
+                self.at += 1;
+            }
+            Some(CssTokenKind::Delim('*')) => {
+                simple.push(SimpleSelector::Universal);
+                self.at += 1;
+            }
+            _ => {}
+        }
+
+        loop {
+            match self.peek() {
+                Some(CssTokenKind::Hash { value, is_id: true }) => {
+                    simple.push(SimpleSelector::Id(value.clone()));
+                    self.at += 1;
+                }
+                Some(CssTokenKind::Delim('.')) => {
+                    self.at += 1;
+                    match self.peek() {
+                        Some(CssTokenKind::Ident(class)) => {
+                            simple.push(SimpleSelector::Class(class.clone()));
+                            self.at += 1;
+                        }
+                        _ => return Err(self.unexpected()),
+                    }
+                }
+                Some(CssTokenKind::OpenSquare) => match self.attribute() {
+                    Ok(attr) => simple.push(attr),
+                    Err(err) => return Err(err),
+                },
+                Some(CssTokenKind::Colon) => {
+                    let pos = self.pos();
+                    self.at += 1;
+                    let element = self.peek() == Some(&CssTokenKind::Colon);
+                    if element {
+                        self.at += 1;
+                    }
+                    match self.peek() {
+                        Some(CssTokenKind::Ident(name)) => {
+                            self.at += 1;
+                            let name = name.to_ascii_lowercase();
+                            if element || LEGACY_PSEUDO_ELEMENTS.contains(&name.as_str()) {
+                                return Ok((Compound { simple }, Some(name)));
+                            }
+                            match pseudo_class(&name, pos) {
+                                Ok(pseudo) => simple.push(SimpleSelector::PseudoClass(pseudo)),
+                                Err(err) => return Err(err),
+                            }
+                        }
+                        Some(CssTokenKind::Function(name)) if !element => {
+                            self.at += 1;
+                            let name = name.to_ascii_lowercase();
+                            let pseudo = match self.function_args() {
+                                Ok((args, close)) => {
+                                    functional_pseudo_class(&name, args, close, pos)
+                                }
+                                Err(err) => return Err(err),
+                            };
+                            match pseudo {
+                                Ok(pseudo) => simple.push(SimpleSelector::PseudoClass(pseudo)),
+                                Err(err) => return Err(err),
+                            }
+                        }
+                        _ => return Err(self.unexpected()),
+                    }
+                }
+                _ => break,
+            }
+        }
+
+        if simple.is_empty() {
+            return Err(self.unexpected());
+        }
+        // *.a は .a と同じ
+        if simple.len() > 1 && simple[0] == SimpleSelector::Universal {
+            simple.remove(0);
+        }
+        return Ok((Compound { simple }, None));
+    }
+
+    // [name], [name=value i]
+    fn attribute(&mut self) -> Result<SimpleSelector, SelectorParseError> {
+        self.at += 1;
+        self.skip_ws();
+        let name = match self.peek() {
+            Some(CssTokenKind::Ident(name)) => name.to_ascii_lowercase(),
+            _ => return Err(self.unexpected()),
+        };
+        self.at += 1;
+        self.skip_ws();
+
+        let op = match self.peek() {
+            Some(CssTokenKind::CloseSquare) => {
+                self.at += 1;
+                return Ok(SimpleSelector::Attribute {
+                    name,
+                    op: None,
+                    case_insensitive: false,
+                });
+            }
+            Some(CssTokenKind::Delim('=')) => AttrOp::Equals,
+            Some(CssTokenKind::Delim(c)) => {
+                let op = match c {
+                    '~' => AttrOp::Includes,
+                    '|' => AttrOp::DashMatch,
+                    '^' => AttrOp::Prefix,
+                    '$' => AttrOp::Suffix,
+                    '*' => AttrOp::Substring,
+                    _ => return Err(self.unexpected()),
+                };
+                self.at += 1;
+                if self.peek() != Some(&CssTokenKind::Delim('=')) {
+                    return Err(self.unexpected());
+                }
+                op
+            }
+            _ => return Err(self.unexpected()),
+        };
+        self.at += 1;
+        self.skip_ws();
+
+        let value = match self.peek() {
+            Some(CssTokenKind::Ident(value)) | Some(CssTokenKind::String(value)) => value.clone(),
+            _ => return Err(self.unexpected()),
+        };
+        self.at += 1;
+        self.skip_ws();
+
+        let mut case_insensitive = false;
+        if let Some(CssTokenKind::Ident(flag)) = self.peek() {
+            if flag.eq_ignore_ascii_case("i") {
+                case_insensitive = true;
+            } else if !flag.eq_ignore_ascii_case("s") {
+                return Err(self.unexpected());
+            }
+            self.at += 1;
+            self.skip_ws();
+        }
+
+        if self.peek() != Some(&CssTokenKind::CloseSquare) {
+            return Err(self.unexpected());
+        }
+        self.at += 1;
+        return Ok(SimpleSelector::Attribute {
+            name,
+            op: Some((op, value)),
+            case_insensitive,
+        });
+    }
+
+    // 関数名のあとから対応する ) まで。 ) の位置も返す
+    fn function_args(&mut self) -> Result<(&'t [CssToken], Position), SelectorParseError> {
+        let start = self.at;
+        let mut depth = 0;
+        loop {
+            match self.peek() {
+                None => return Err(self.unexpected()),
+                Some(CssTokenKind::Function(_)) | Some(CssTokenKind::OpenParen) => depth += 1,
+                Some(CssTokenKind::CloseParen) if depth == 0 => {
+                    let close = self.pos();
+                    let args = &self.tokens[start..self.at];
+                    self.at += 1;
+                    return Ok((args, close));
+                }
+                Some(CssTokenKind::CloseParen) => depth -= 1,
+                _ => {}
+            }
+            self.at += 1;
+        }
+    }
+}
+
+fn pseudo_class(name: &str, pos: Position) -> Result<PseudoClass, SelectorParseError> {
+    let pseudo = match name {
+        "root" => PseudoClass::Root,
+        "empty" => PseudoClass::Empty,
+        "first-child" => PseudoClass::FirstChild,
+        "last-child" => PseudoClass::LastChild,
+        "only-child" => PseudoClass::OnlyChild,
+        "first-of-type" => PseudoClass::FirstOfType,
+        "last-of-type" => PseudoClass::LastOfType,
+        "only-of-type" => PseudoClass::OnlyOfType,
+        "link" | "any-link" => PseudoClass::Link,
+        "checked" => PseudoClass::Checked,
+        "disabled" => PseudoClass::Disabled,
+        "enabled" => PseudoClass::Enabled,
+        name if STATE_PSEUDO_CLASSES.contains(&name) => PseudoClass::State(name.to_string()),
+        _ => {
+            return Err(SelectorParseError::UnknownPseudoClass {
+                name: name.to_string(),
+                pos,
+            })
+        }
+    };
+    return Ok(pseudo);
+}
+
+fn functional_pseudo_class(
+    name: &str,
+    args: &[CssToken],
+    close: Position,
+    pos: Position,
+) -> Result<PseudoClass, SelectorParseError> {
+    if name == "not" {
+        let list = parse_tokens(args, &close)?;
+        if let Some(selector) = list.selectors.iter().find(|s| s.pseudo_element.is_some()) {
+            return Err(SelectorParseError::UnexpectedToken {
+                found: format!(
+                    "::{}",
+                    selector.pseudo_element.as_deref().unwrap_or_default()
+                ),
+                pos,
+            });
+        }
+        return Ok(PseudoClass::Not(list));
+    }
+
+    let wrap: fn(Nth) -> PseudoClass = match name {
+        "nth-child" => PseudoClass::NthChild,
+        "nth-last-child" => PseudoClass::NthLastChild,
+        "nth-of-type" => PseudoClass::NthOfType,
+        "nth-last-of-type" => PseudoClass::NthLastOfType,
+        _ => {
+            return Err(SelectorParseError::UnknownPseudoClass {
+                name: name.to_string(),
+                pos,
+            })
+        }
+    };
+    return match parse_nth(args) {
+        Some(nth) => Ok(wrap(nth)),
+        None => Err(SelectorParseError::UnexpectedToken {
+            found: args.iter().map(|tok| tok.kind.to_string()).collect(),
+            pos,
+        }),
+    };
+}
+
+// odd, even, 3, 2n+1, -n+3
+fn parse_nth(args: &[CssToken]) -> Option<Nth> {
+    let mut text = String::new();
+    for tok in args {
+        match &tok.kind {
+            CssTokenKind::Whitespace => {}
+            // 2n+1 の +1 は符号つきの数として読まれるので、符号を戻す
+            CssTokenKind::Number { value, .. }
+                if *value >= 0.0 && !text.is_empty() && !text.ends_with(['+', '-']) =>
+            {
+                text.push('+');
+                text.push_str(&tok.kind.to_string());
+            }
+            kind => text.push_str(&kind.to_string()),
+        }
+    }
+
+    let text = text.to_ascii_lowercase();
+    return match text.as_str() {
+        "odd" => Some(Nth { a: 2, b: 1 }),
+        "even" => Some(Nth { a: 2, b: 0 }),
+        _ => match text.split_once('n') {
+            Some((a, b)) => {
+                let a = match a {
+                    "" | "+" => 1,
+                    "-" => -1,
+                    a => a.parse().ok()?,
+                };
+                let b = match b {
+                    "" => 0,
+                    b if b.starts_with(['+', '-']) => b.parse().ok()?,
+                    _ => return None,
+                };
+                Some(Nth { a, b })
+            }
+            None => text.parse().ok().map(|b| Nth { a: 0, b }),
+        },
+    };
+}
+
+impl Nth {
+    // positionは1から数える
+    pub fn matches(&self, position: i32) -> bool {
+        if self.a == 0 {
+            return position == self.b;
+        }
+        let diff = position - self.b;
+        return diff % self.a == 0 && diff / self.a >= 0;
+    }
+}
+
+// 兄弟の中で何番目か (1から)。of_typeなら同じ名前の要素だけを数える
+fn element_position(el: &ElementRef, of_type: bool, from_end: bool) -> i32 {
+    let siblings = el.siblings();
+    let same = |nd: &Node| nd.is_element() && (!of_type || nd.name() == el.node().name());
+    let count = if from_end {
+        siblings[el.index() + 1..]
+            .iter()
+            .filter(|nd| same(nd))
+            .count()
+    } else {
+        siblings[..el.index()].iter().filter(|nd| same(nd)).count()
+    };
+    return count as i32 + 1;
+}
+
+impl PseudoClass {
+    pub fn matches(&self, el: &ElementRef) -> bool {
+        let node = el.node();
+        return match self {
+            PseudoClass::Root => el.parent_element().is_none(),
+            // commentだけなら空とみなす
+            PseudoClass::Empty => node
+                .children()
+                .iter()
+                .all(|child| !child.is_element() && !child.is_text()),
+            PseudoClass::FirstChild => element_position(el, false, false) == 1,
+            PseudoClass::LastChild => element_position(el, false, true) == 1,
+            PseudoClass::OnlyChild => {
+                element_position(el, false, false) == 1 && element_position(el, false, true) == 1
+            }
+            PseudoClass::FirstOfType => element_position(el, true, false) == 1,
+            PseudoClass::LastOfType => element_position(el, true, true) == 1,
+            PseudoClass::OnlyOfType => {
+                element_position(el, true, false) == 1 && element_position(el, true, true) == 1
+            }
+            PseudoClass::NthChild(nth) => nth.matches(element_position(el, false, false)),
+            PseudoClass::NthLastChild(nth) => nth.matches(element_position(el, false, true)),
+            PseudoClass::NthOfType(nth) => nth.matches(element_position(el, true, false)),
+            PseudoClass::NthLastOfType(nth) => nth.matches(element_position(el, true, true)),
+            PseudoClass::Not(list) => !list.matches(el),
+            PseudoClass::Link => {
+                node.name()
+                    .is_some_and(|name| *name == "a" || *name == "area" || *name == "link")
+                    && node.attr("href").is_some()
+            }
+            PseudoClass::Checked => {
+                let is = |tag: &str| node.name().is_some_and(|name| *name == tag);
+                (is("input") && node.attr("checked").is_some())
+                    || (is("option") && node.attr("selected").is_some())
+            }
+            PseudoClass::Disabled => is_form_element(node) && node.attr("disabled").is_some(),
+            PseudoClass::Enabled => is_form_element(node) && node.attr("disabled").is_none(),
+            PseudoClass::State(_) => false,
+        };
+    }
+}
+
+fn is_form_element(node: &Node) -> bool {
+    return node
+        .name()
+        .is_some_and(|name| FORM_ELEMENTS.contains(&name.as_str()));
+}
+
+impl SimpleSelector {
+    pub fn matches(&self, el: &ElementRef) -> bool {
+        let node = el.node();
+        return match self {
+            SimpleSelector::Type(name) => node.name() == Some(name),
+            SimpleSelector::Universal => true,
+            SimpleSelector::Id(id) => node.attr("id").is_some_and(|value| value == id),
+            SimpleSelector::Class(class) => node
+                .attr("class")
+                .is_some_and(|value| value.split_ascii_whitespace().any(|c| c == class)),
+            SimpleSelector::Attribute {
+                name,
+                op,
+                case_insensitive,
+            } => {
+                let actual = match node.attr(name) {
+                    Some(actual) => actual,
+                    None => return false,
+                };
+                let (op, expected) = match op {
+                    Some(op) => op,
+                    None => return true,
+                };
+                let (actual, expected) = if *case_insensitive {
+                    (
+                        actual.to_ascii_lowercase().to_string(),
+                        expected.to_ascii_lowercase(),
+                    )
+                } else {
+                    (actual.to_string(), expected.clone())
+                };
+                match op {
+                    AttrOp::Equals => actual == expected,
+                    AttrOp::Includes => {
+                        actual.split_ascii_whitespace().any(|word| word == expected)
+                    }
+                    AttrOp::DashMatch => {
+                        actual == expected
+                            || (actual.starts_with(expected.as_str())
+                                && actual[expected.len()..].starts_with('-'))
+                    }
+                    // 空の値には何も合わない
+                    AttrOp::Prefix => !expected.is_empty() && actual.starts_with(expected.as_str()),
+                    AttrOp::Suffix => !expected.is_empty() && actual.ends_with(expected.as_str()),
+                    AttrOp::Substring => !expected.is_empty() && actual.contains(expected.as_str()),
+                }
+            }
+            SimpleSelector::PseudoClass(pseudo) => pseudo.matches(el),
+        };
+    }
+}
+
+impl Compound {
+    pub fn matches(&self, el: &ElementRef) -> bool {
+        return self.simple.iter().all(|simple| simple.matches(el));
+    }
+}
+
+impl Specificity {
+    fn add(self, other: Specificity) -> Specificity {
+        return Specificity(self.0 + other.0, self.1 + other.1, self.2 + other.2);
+    }
+}
+
+impl SimpleSelector {
+    pub fn specificity(&self) -> Specificity {
+        return match self {
+            SimpleSelector::Id(_) => Specificity(1, 0, 0),
+            SimpleSelector::Class(_) | SimpleSelector::Attribute { .. } => Specificity(0, 1, 0),
+            SimpleSelector::Type(_) => Specificity(0, 0, 1),
+            SimpleSelector::Universal => Specificity(0, 0, 0),
+            // :not() は中で一番強いselectorの分だけ数える
+            SimpleSelector::PseudoClass(PseudoClass::Not(list)) => list.specificity(),
+            SimpleSelector::PseudoClass(_) => Specificity(0, 1, 0),
+        };
+    }
+}
+
+impl Selector {
+    pub fn specificity(&self) -> Specificity {
+        let mut specificity = Specificity::default();
+        for compound in &self.compounds {
+            for simple in &compound.simple {
+                specificity = specificity.add(simple.specificity());
+            }
+        }
+        if self.pseudo_element.is_some() {
+            specificity = specificity.add(Specificity(0, 0, 1));
+        }
+        return specificity;
+    }
+
+    // 右端のcompoundから左へ、祖先や兄弟を辿って合わせる
+    pub fn matches(&self, el: &ElementRef) -> bool {
+        if self.pseudo_element.is_some() || self.compounds.is_empty() {
+            return false;
+        }
+        return self.matches_from(self.compounds.len() - 1, el);
+    }
+
+    fn matches_from(&self, i: usize, el: &ElementRef) -> bool {
+        if !self.compounds[i].matches(el) {
+            return false;
+        }
+        if i == 0 {
+            return true;
+        }
+        return match self.combinators[i - 1] {
+            Combinator::Child => el
+                .parent_element()
+                .is_some_and(|parent| self.matches_from(i - 1, &parent)),
+            Combinator::Descendant => {
+                let mut ancestor = el.parent_element();
+                while let Some(cur) = ancestor {
+                    if self.matches_from(i - 1, &cur) {
+                        return true;
+                    }
+                    ancestor = cur.parent_element();
+                }
+                false
+            }
+            Combinator::NextSibling => el
+                .prev_element()
+                .is_some_and(|prev| self.matches_from(i - 1, &prev)),
+            Combinator::SubsequentSibling => {
+                let mut sibling = el.prev_element();
+                while let Some(cur) = sibling {
+                    if self.matches_from(i - 1, &cur) {
+                        return true;
+                    }
+                    sibling = cur.prev_element();
+                }
+                false
+            }
+        };
+    }
+}
+
+impl SelectorList {
+    // 一番強いselectorのもの
+    pub fn specificity(&self) -> Specificity {
+        return self
+            .selectors
+            .iter()
+            .map(|selector| selector.specificity())
+            .max()
+            .unwrap_or_default();
+    }
+
+    pub fn matches(&self, el: &ElementRef) -> bool {
+        return self.selectors.iter().any(|selector| selector.matches(el));
+    }
+}
+
+// 何度も使うselector。文書ごとにparseし直さず、cloneしても中身は共有する
+// selectorごとに右端のcompoundから要るidやclassを1つ取り出しておき、合わない要素はすぐ外す
+#[derive(Debug, Clone)]
+pub struct CompiledSelector {
+    inner: Arc<Compiled>,
+}
+
+#[derive(Debug)]
+struct Compiled {
+    list: SelectorList,
+    // list.selectorsと同じ順
+    keys: Vec<RightmostKey>,
+}
+
+// 右端の要素が必ず持っているもの。id, class, 要素名の順に絞れるものを選ぶ
+#[derive(Debug)]
+enum RightmostKey {
+    Id(String),
+    Class(String),
+    Type(Atom),
+    Any,
+}
+
+impl RightmostKey {
+    fn of(selector: &Selector) -> RightmostKey {
+        let simple = match selector.compounds.last() {
+            Some(compound) => &compound.simple,
+            None => return RightmostKey::Any,
+        };
+        let mut key = RightmostKey::Any;
+        for s in simple {
+            match s {
+                SimpleSelector::Id(id) => return RightmostKey::Id(id.clone()),
+                SimpleSelector::Class(class) if !matches!(key, RightmostKey::Class(_)) => {
+                    key = RightmostKey::Class(class.clone());
+                }
+                SimpleSelector::Type(name) if matches!(key, RightmostKey::Any) => {
+                    key = RightmostKey::Type(name.clone());
+                }
+                _ => {}
+            }
+        }
+        return key;
+    }
+
+    fn accepts(&self, node: &Node) -> bool {
+        return match self {
+            RightmostKey::Id(id) => node.attr("id").is_some_and(|value| value == id),
+            RightmostKey::Class(class) => node
+                .attr("class")
+                .is_some_and(|value| value.split_ascii_whitespace().any(|c| c == class)),
+            RightmostKey::Type(name) => node.name() == Some(name),
+            RightmostKey::Any => true,
+        };
+    }
+}
+
+impl CompiledSelector {
+    pub fn compile(selectors: &str) -> Result<CompiledSelector, SelectorParseError> {
+        return Ok(CompiledSelector::from(parse(selectors)?));
+    }
+
+    pub fn list(&self) -> &SelectorList {
+        return &self.inner.list;
+    }
+
+    pub fn matches(&self, el: &ElementRef) -> bool {
+        let node = el.node();
+        return self
+            .inner
+            .list
+            .selectors
+            .iter()
+            .zip(&self.inner.keys)
+            .any(|(selector, key)| key.accepts(node) && selector.matches(el));
+    }
+}
+
+impl From<SelectorList> for CompiledSelector {
+    fn from(list: SelectorList) -> CompiledSelector {
+        let keys = list.selectors.iter().map(RightmostKey::of).collect();
+        return CompiledSelector {
+            inner: Arc::new(Compiled { list, keys }),
+        };
+    }
+}
+
+impl fmt::Display for CompiledSelector {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        return fmt::Display::fmt(&self.inner.list, f);
+    }
+}
+
+impl fmt::Display for Nth {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.a {
+            0 => return write!(f, "{}", self.b),
+            1 => write!(f, "n")?,
+            -1 => write!(f, "-n")?,
+            a => write!(f, "{}n", a)?,
+        }
+        if self.b > 0 {
+            return write!(f, "+{}", self.b);
+        }
+        if self.b < 0 {
+            return write!(f, "{}", self.b);
+        }
+        return Ok(());
+    }
+}
+
+impl fmt::Display for PseudoClass {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        return match self {
+            PseudoClass::Root => write!(f, ":root"),
+            PseudoClass::Empty => write!(f, ":empty"),
+            PseudoClass::FirstChild => write!(f, ":first-child"),
+            PseudoClass::LastChild => write!(f, ":last-child"),
+            PseudoClass::OnlyChild => write!(f, ":only-child"),
+            PseudoClass::FirstOfType => write!(f, ":first-of-type"),
+            PseudoClass::LastOfType => write!(f, ":last-of-type"),
+            PseudoClass::OnlyOfType => write!(f, ":only-of-type"),
+            PseudoClass::NthChild(nth) => write!(f, ":nth-child({})", nth),
+            PseudoClass::NthLastChild(nth) => write!(f, ":nth-last-child({})", nth),
+            PseudoClass::NthOfType(nth) => write!(f, ":nth-of-type({})", nth),
+            PseudoClass::NthLastOfType(nth) => write!(f, ":nth-last-of-type({})", nth),
+            PseudoClass::Not(list) => {
+                write!(f, ":not(")?;
+                fmt::Display::fmt(list, f)?;
+                write!(f, ")")
+            }
+            PseudoClass::Link => write!(f, ":link"),
+            PseudoClass::Checked => write!(f, ":checked"),
+            PseudoClass::Disabled => write!(f, ":disabled"),
+            PseudoClass::Enabled => write!(f, ":enabled"),
+            PseudoClass::State(name) => write!(f, ":{}", name),
+        };
+    }
+}
+
+impl fmt::Display for SimpleSelector {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        return match self {
+            SimpleSelector::Type(name) => write_ident(f, name.as_str()),
+            SimpleSelector::Universal => write!(f, "*"),
+            SimpleSelector::Id(id) => {
+                write!(f, "#")?;
+                write_ident(f, id)
+            }
+            SimpleSelector::Class(class) => {
+                write!(f, ".")?;
+                write_ident(f, class)
+            }
+            SimpleSelector::Attribute {
+                name,
+                op,
+                case_insensitive,
+            } => {
+                write!(f, "[")?;
+                write_ident(f, name)?;
+                if let Some((op, value)) = op {
+                    let op = match op {
+                        AttrOp::Equals => "=",
+                        AttrOp::Includes => "~=",
+                        AttrOp::DashMatch => "|=",
+                        AttrOp::Prefix => "^=",
+                        AttrOp::Suffix => "$=",
+                        AttrOp::Substring => "*=",
+                    };
+                    write!(f, "{}", op)?;
+                    write_string(f, value)?;
+                    if *case_insensitive {
+                        write!(f, " i")?;
+                    }
+                }
+                write!(f, "]")
+            }
+            // {:#} をそのまま渡す
+            SimpleSelector::PseudoClass(pseudo) => fmt::Display::fmt(pseudo, f),
+        };
+    }
+}
+
+impl fmt::Display for Compound {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for simple in &self.simple {
+            fmt::Display::fmt(simple, f)?;
+        }
+        return Ok(());
+    }
+}
+
+// {:#} で書くと、combinatorやカンマの周りの空白を詰める
+impl fmt::Display for Selector {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, compound) in self.compounds.iter().enumerate() {
+            if i > 0 {
+                let (spaced, compact) = match self.combinators[i - 1] {
+                    Combinator::Descendant => (" ", " "),
+                    Combinator::Child => (" > ", ">"),
+                    Combinator::NextSibling => (" + ", "+"),
+                    Combinator::SubsequentSibling => (" ~ ", "~"),
+                };
+                write!(f, "{}", if f.alternate() { compact } else { spaced })?;
+            }
+            fmt::Display::fmt(compound, f)?;
+        }
+        if let Some(pseudo_element) = &self.pseudo_element {
+            write!(f, "::")?;
+            write_ident(f, pseudo_element)?;
+        }
+        return Ok(());
+    }
+}
+
+impl fmt::Display for SelectorList {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, selector) in self.selectors.iter().enumerate() {
+            if i > 0 {
+                write!(f, "{}", if f.alternate() { "," } else { ", " })?;
+            }
+            fmt::Display::fmt(selector, f)?;
+        }
+        return Ok(());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::css::errors::SelectorParseError;
+    use crate::css::selector;
+    use crate::css::selector::CompiledSelector;
+    use crate::html;
+    use crate::html::document::ElementRef;
+    use std::ops::ControlFlow;
+
+    #[test]
+    fn parse_and_reserialize() {
+        let table = [
+            ("div", "div"),
+            ("DIV.Note", "div.Note"),
+            ("*", "*"),
+            ("*.a", ".a"),
+            ("#main  .item", "#main .item"),
+            ("ul>li+li~li", "ul > li + li ~ li"),
+            ("a[href]", "a[href]"),
+            ("a[HREF ^= 'http' i]", "a[href^=\"http\" i]"),
+            (
+                "[lang|=en], [class~=x], [a$=b s], [a*=\"c\"]",
+                "[lang|=\"en\"], [class~=\"x\"], [a$=\"b\"], [a*=\"c\"]",
+            ),
+            ("li:nth-child(odd)", "li:nth-child(2n+1)"),
+            ("li:nth-child( 2n + 1 )", "li:nth-child(2n+1)"),
+            ("li:nth-last-child(-n+3)", "li:nth-last-child(-n+3)"),
+            ("tr:nth-of-type(even)", "tr:nth-of-type(2n)"),
+            ("p:nth-child(2n-1)", "p:nth-child(2n-1)"),
+            ("p:nth-child(5)", "p:nth-child(5)"),
+            ("p:first-child:HOVER", "p:first-child:hover"),
+            (
+                "input:not([type=hidden], .x)",
+                "input:not([type=\"hidden\"], .x)",
+            ),
+            ("p::before", "p::before"),
+            ("p:after", "p::after"),
+            ("a /* c */ b", "a b"),
+            ("\\31 23", "\\31 23"),
+            (".a\\.b", ".a\\.b"),
+        ];
+        for (input, expected) in table {
+            let list = selector::parse(input).unwrap();
+            assert_eq!(list.to_string(), expected, "input: {:?}", input);
+            // 書き出したものを読み直しても同じになる
+            assert_eq!(
+                selector::parse(expected).unwrap(),
+                list,
+                "input: {:?}",
+                input
+            );
+        }
+    }
+
+    #[test]
+    fn parse_errors() {
+        let table = [
+            "",
+            "   ",
+            "a,",
+            ",a",
+            "a >",
+            "a + ",
+            "> a",
+            "a..b",
+            "a[",
+            "a[x=]",
+            "p:unknown",
+            "p::before a",
+            "a:nth-child(x)",
+            "ns|a",
+        ];
+        for input in table {
+            assert!(selector::parse(input).is_err(), "input: {:?}", input);
+        }
+
+        assert!(matches!(
+            selector::parse(""),
+            Err(SelectorParseError::Empty { .. })
+        ));
+        assert!(matches!(
+            selector::parse("a, "),
+            Err(SelectorParseError::Empty { .. })
+        ));
+        assert!(matches!(
+            selector::parse("a > "),
+            Err(SelectorParseError::DanglingCombinator { .. })
+        ));
+        match selector::parse("p:bogus") {
+            Err(SelectorParseError::UnknownPseudoClass { name, pos }) => {
+                assert_eq!(name, "bogus");
+                assert_eq!(pos.at_whole, 1);
+            }
+            other => panic!("{:?}", other),
+        }
+    }
+
+    const FIXTURE: &str = "<html><body>\
+        <div id=\"main\" class=\"page wide\">\
+        <ul><li class=\"item\">one</li><li class=\"item sel\">two</li><li>three</li><li><a href=\"/x\" lang=\"en-US\">four</a></li></ul>\
+        <p>first</p><!-- c --><p class=\"item\">second</p><p></p>\
+        </div><input disabled=\"\"/><input type=\"checkbox\" checked=\"\"/>\
+        </body></html>";
+
+    fn select(selectors: &str) -> Vec<String> {
+        let doc = html::parse(FIXTURE).unwrap();
+        return doc
+            .query_selector_all(selectors)
+            .unwrap()
+            .iter()
+            .map(|el| {
+                let text = el.children().first().and_then(|child| child.text_value());
+                match text {
+                    Some(text) => format!("{}:{}", el.name(), text),
+                    None => el.name().to_string(),
+                }
+            })
+            .collect();
+    }
+
+    #[test]
+    fn query_selector_all() {
+        assert_eq!(select("li.item"), vec!["li:one", "li:two"]);
+        assert_eq!(select(".item.sel"), vec!["li:two"]);
+        assert_eq!(select("#main > p"), vec!["p:first", "p:second", "p"]);
+        assert_eq!(
+            select("body p.item, ul li:last-child a"),
+            vec!["a:four", "p:second"]
+        );
+        assert_eq!(select("li + li"), vec!["li:two", "li:three", "li"]);
+        assert_eq!(select("ul ~ p:not(.item)"), vec!["p:first", "p"]);
+        assert_eq!(select("p + p"), vec!["p:second", "p"]);
+        assert_eq!(select("li:nth-child(2n+1)"), vec!["li:one", "li:three"]);
+        assert_eq!(select("li:nth-last-child(1)"), vec!["li"]);
+        assert_eq!(select("p:first-of-type, p:empty"), vec!["p:first", "p"]);
+        assert_eq!(select("[class~=wide]"), vec!["div"]);
+        assert_eq!(select("a[lang|=en]"), vec!["a:four"]);
+        assert_eq!(select("[href^='/']:link"), vec!["a:four"]);
+        assert_eq!(select(":root"), vec!["html"]);
+        assert_eq!(select("input:disabled, :checked"), vec!["input", "input"]);
+        assert_eq!(select("input:enabled"), vec!["input"]);
+        assert!(select("li:hover").is_empty());
+        assert!(select("p::before").is_empty());
+
+        let doc = html::parse(FIXTURE).unwrap();
+        let first = doc.query_selector("li").unwrap().unwrap();
+        assert_eq!(first.attr("class").unwrap(), "item");
+        assert!(doc.query_selector("table").unwrap().is_none());
+        assert!(doc.query_selector("a >").is_err());
+    }
+
+    #[test]
+    fn compiled_and_string_paths_agree() {
+        let doc = html::parse(FIXTURE).unwrap();
+        for selectors in [
+            "li.item",
+            "#main > p",
+            "div#main ul li",
+            ".sel.item, p.item",
+            "ul ~ p:not(.item)",
+            "li + li a[href]",
+            "*",
+            "p::before",
+        ] {
+            let compiled = CompiledSelector::compile(selectors).unwrap();
+            let by_string = doc.query_selector_all(selectors).unwrap();
+            let by_compiled = doc.select(&compiled.clone());
+            assert_eq!(by_compiled.len(), by_string.len(), "{}", selectors);
+            let paths = |found: &[ElementRef]| found.iter().map(|el| el.path()).collect::<Vec<_>>();
+            assert_eq!(paths(&by_compiled), paths(&by_string), "{}", selectors);
+            assert_eq!(
+                doc.select_first(&compiled).map(|el| el.path()),
+                by_string.first().map(|el| el.path())
+            );
+
+            let mut matched = 0;
+            doc.walk_elements(|el| {
+                if el.matches_compiled(&compiled) {
+                    matched += 1;
+                }
+                return ControlFlow::Continue(());
+            });
+            assert_eq!(matched, by_string.len(), "{}", selectors);
+            assert_eq!(
+                compiled.to_string(),
+                selector::parse(selectors).unwrap().to_string()
+            );
+        }
+        assert!(CompiledSelector::compile("a >").is_err());
+    }
+
+    #[test]
+    fn select_iter_stops_early() {
+        let mut html = "<html><body><ul>".to_string();
+        for i in 0..1000 {
+            html += &format!("<li class=\"item\"><a href=\"/{}\">{}</a></li>", i, i);
+        }
+        html += "</ul></body></html>";
+        let doc = html::parse(&html).unwrap();
+        let selector = CompiledSelector::compile("li.item > a").unwrap();
+
+        let mut iter = doc.select_iter(&selector);
+        let first: Vec<_> = iter.by_ref().take(3).collect();
+        // html, body, ul と li, a が3組
+        assert_eq!(iter.visited(), 9);
+        let all = doc.select(&selector);
+        assert_eq!(all.len(), 1000);
+        assert!(first.iter().zip(&all).all(|(a, b)| a.path() == b.path()));
+
+        let mut walked = Vec::new();
+        doc.walk_elements(|el| {
+            if el.matches_compiled(&selector) {
+                walked.push(el.path());
+            }
+            return ControlFlow::Continue(());
+        });
+        assert!(walked.iter().zip(&all).all(|(a, b)| *a == b.path()));
+
+        // 合うものがなければ全部を辿る
+        let none = CompiledSelector::compile("table").unwrap();
+        let mut iter = doc.select_iter(&none);
+        assert!(iter.next().is_none());
+        assert_eq!(iter.visited(), 2003);
+    }
+}
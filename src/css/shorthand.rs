@@ -0,0 +1,606 @@
+use crate::css::parser::serialize_tokens;
+use crate::css::stylesheet::Declaration;
+use crate::css::tokenizer::{CssToken, CssTokenKind};
+use crate::css::values::{parse_component_list, value_tokens, Color, Component};
+
+const SIDES: [&str; 4] = ["top", "right", "bottom", "left"];
+
+const BORDER_STYLES: [&str; 10] = [
+    "none", "hidden", "dotted", "dashed", "solid", "double", "groove", "ridge", "inset", "outset",
+];
+
+// shorthandをlonghandに分ける。知らないpropertyや読めない値はそのまま返す
+pub fn expand(property: &str, value: &str) -> Vec<Declaration> {
+    let names = longhands(property);
+    let values = match values(property, value) {
+        Some(values) if !names.is_empty() && values.len() == names.len() => values,
+        _ => {
+            return vec![Declaration {
+                name: property.to_string(),
+                value: value.to_string(),
+                important: false,
+            }]
+        }
+    };
+    return names
+        .into_iter()
+        .zip(values)
+        .map(|(name, value)| Declaration {
+            name,
+            value,
+            important: false,
+        })
+        .collect();
+}
+
+// shorthandを構成するlonghandの名前。valuesはこの順に値を返す
+fn longhands(property: &str) -> Vec<String> {
+    let names: Vec<String> = match property {
+        "margin" | "padding" => SIDES
+            .iter()
+            .map(|side| format!("{}-{}", property, side))
+            .collect(),
+        "border-width" | "border-style" | "border-color" => {
+            let part = &property["border-".len()..];
+            SIDES
+                .iter()
+                .map(|side| format!("border-{}-{}", side, part))
+                .collect()
+        }
+        "border-top" | "border-right" | "border-bottom" | "border-left" => {
+            ["width", "style", "color"]
+                .iter()
+                .map(|part| format!("{}-{}", property, part))
+                .collect()
+        }
+        "border" => SIDES
+            .iter()
+            .flat_map(|side| longhands(&format!("border-{}", side)))
+            .collect(),
+        "background" => vec![
+            "background-color",
+            "background-image",
+            "background-repeat",
+            "background-attachment",
+            "background-position",
+        ]
+        .into_iter()
+        .map(String::from)
+        .collect(),
+        "font" => vec![
+            "font-style",
+            "font-variant",
+            "font-weight",
+            "font-stretch",
+            "font-size",
+            "line-height",
+            "font-family",
+        ]
+        .into_iter()
+        .map(String::from)
+        .collect(),
+        "text-decoration" => vec![
+            "text-decoration-line",
+            "text-decoration-style",
+            "text-decoration-color",
+        ]
+        .into_iter()
+        .map(String::from)
+        .collect(),
+        _ => Vec::new(),
+    };
+    return names;
+}
+
+fn values(property: &str, value: &str) -> Option<Vec<String>> {
+    let pieces = pieces(value)?;
+    // inherit などはlonghandの全部に同じ値を入れる
+    if let [piece] = pieces.as_slice() {
+        if ["inherit", "initial", "unset"]
+            .iter()
+            .any(|keyword| piece.eq_ignore_ascii_case(keyword))
+        {
+            return Some(vec![piece.clone(); longhands(property).len()]);
+        }
+    }
+
+    return match property {
+        "margin" | "padding" | "border-width" | "border-style" | "border-color" => {
+            four_sides(&pieces)
+        }
+        "border-top" | "border-right" | "border-bottom" | "border-left" => border_side(&pieces),
+        "border" => {
+            let side = border_side(&pieces)?;
+            Some(SIDES.iter().flat_map(|_| side.clone()).collect())
+        }
+        "background" => background(&pieces),
+        "font" => font(&pieces),
+        "text-decoration" => text_decoration(&pieces),
+        _ => None,
+    };
+}
+
+// 括弧の外の空白で分ける。, と / はそれだけで一つにする
+// var() は置き換えるまで分けられないのでNone
+fn pieces(value: &str) -> Option<Vec<String>> {
+    let tokens = value_tokens(value).ok()?;
+    let mut pieces = Vec::new();
+    let mut current: Vec<&CssToken> = Vec::new();
+    let mut depth = 0;
+    let flush = |current: &mut Vec<&CssToken>, pieces: &mut Vec<String>| {
+        if !current.is_empty() {
+            pieces.push(serialize_tokens(current.drain(..)));
+        }
+    };
+    for tok in &tokens {
+        match &tok.kind {
+            CssTokenKind::Function(name) if name.eq_ignore_ascii_case("var") => return None,
+            CssTokenKind::Function(_)
+            | CssTokenKind::OpenParen
+            | CssTokenKind::OpenSquare
+            | CssTokenKind::OpenCurly => depth += 1,
+            CssTokenKind::CloseParen | CssTokenKind::CloseSquare | CssTokenKind::CloseCurly => {
+                depth -= 1
+            }
+            CssTokenKind::Whitespace if depth == 0 => {
+                flush(&mut current, &mut pieces);
+                continue;
+            }
+            CssTokenKind::Comma | CssTokenKind::Delim('/') if depth == 0 => {
+                flush(&mut current, &mut pieces);
+                pieces.push(tok.kind.to_string());
+                continue;
+            }
+            _ => {}
+        }
+        current.push(tok);
+    }
+    flush(&mut current, &mut pieces);
+    return Some(pieces);
+}
+
+fn is_keyword(piece: &str, keywords: &[&str]) -> bool {
+    return keywords.iter().any(|k| piece.eq_ignore_ascii_case(k));
+}
+
+fn is_length(piece: &str) -> bool {
+    return match parse_component_list(piece) {
+        Ok(components) => matches!(components.as_slice(), [c] if c.as_length().is_some()),
+        Err(_) => false,
+    };
+}
+
+fn is_color(piece: &str) -> bool {
+    return Color::parse(piece).is_ok();
+}
+
+// 1つなら全部, 2つなら 上下 左右, 3つなら 上 左右 下, 4つなら 上 右 下 左
+fn four_sides(pieces: &[String]) -> Option<Vec<String>> {
+    if pieces.iter().any(|piece| piece == "," || piece == "/") {
+        return None;
+    }
+    let (top, right, bottom, left) = match pieces {
+        [all] => (all, all, all, all),
+        [vertical, horizontal] => (vertical, horizontal, vertical, horizontal),
+        [top, horizontal, bottom] => (top, horizontal, bottom, horizontal),
+        [top, right, bottom, left] => (top, right, bottom, left),
+        _ => return None,
+    };
+    return Some(vec![
+        top.clone(),
+        right.clone(),
+        bottom.clone(),
+        left.clone(),
+    ]);
+}
+
+// 1px solid red。順番は自由で、省いたものは初期値になる
+fn border_side(pieces: &[String]) -> Option<Vec<String>> {
+    let mut width = None;
+    let mut style = None;
+    let mut color = None;
+    for piece in pieces {
+        let slot = if is_keyword(piece, &BORDER_STYLES) {
+            &mut style
+        } else if is_keyword(piece, &["thin", "medium", "thick"]) || is_length(piece) {
+            &mut width
+        } else if is_color(piece) {
+            &mut color
+        } else {
+            return None;
+        };
+        if slot.replace(piece.clone()).is_some() {
+            return None;
+        }
+    }
+    return Some(vec![
+        width.unwrap_or("medium".to_string()),
+        style.unwrap_or("none".to_string()),
+        color.unwrap_or("currentcolor".to_string()),
+    ]);
+}
+
+// 一層だけ扱う。background-sizeの / も分けない
+fn background(pieces: &[String]) -> Option<Vec<String>> {
+    let mut color = None;
+    let mut image = None;
+    let mut repeat: Vec<&str> = Vec::new();
+    let mut attachment = None;
+    let mut position: Vec<&str> = Vec::new();
+    for piece in pieces {
+        let is_image = match parse_component_list(piece) {
+            Ok(components) => match components.as_slice() {
+                [Component::Url(_)] => true,
+                [Component::Keyword(keyword)] => keyword == "none",
+                [Component::Other(function)] => function.to_ascii_lowercase().contains("gradient("),
+                _ => false,
+            },
+            Err(_) => false,
+        };
+        let slot = if is_image {
+            &mut image
+        } else if is_keyword(
+            piece,
+            &[
+                "repeat",
+                "repeat-x",
+                "repeat-y",
+                "no-repeat",
+                "space",
+                "round",
+            ],
+        ) {
+            repeat.push(piece);
+            continue;
+        } else if is_keyword(piece, &["scroll", "fixed", "local"]) {
+            &mut attachment
+        } else if is_keyword(piece, &["left", "right", "top", "bottom", "center"])
+            || is_length(piece)
+        {
+            position.push(piece);
+            continue;
+        } else if is_color(piece) {
+            &mut color
+        } else {
+            return None;
+        };
+        if slot.replace(piece.clone()).is_some() {
+            return None;
+        }
+    }
+    if repeat.len() > 2 || position.len() > 4 {
+        return None;
+    }
+    return Some(vec![
+        color.unwrap_or("transparent".to_string()),
+        image.unwrap_or("none".to_string()),
+        if repeat.is_empty() {
+            "repeat".to_string()
+        } else {
+            repeat.join(" ")
+        },
+        attachment.unwrap_or("scroll".to_string()),
+        if position.is_empty() {
+            "0% 0%".to_string()
+        } else {
+            position.join(" ")
+        },
+    ]);
+}
+
+// [style || variant || weight || stretch]? size [/ line-height]? family
+// caption などのsystem fontは分けない
+fn font(pieces: &[String]) -> Option<Vec<String>> {
+    let mut style = None;
+    let mut variant = None;
+    let mut weight = None;
+    let mut stretch = None;
+    let mut at = 0;
+    // sizeの前に来るもの
+    while at < pieces.len() && at < 4 {
+        let piece = &pieces[at];
+        let slot = if is_keyword(piece, &["normal"]) {
+            at += 1;
+            continue;
+        } else if is_keyword(piece, &["italic", "oblique"]) {
+            &mut style
+        } else if is_keyword(piece, &["small-caps"]) {
+            &mut variant
+        } else if is_keyword(piece, &["bold", "bolder", "lighter"])
+            || piece
+                .parse::<f64>()
+                .is_ok_and(|weight| (1.0..=1000.0).contains(&weight))
+        {
+            &mut weight
+        } else if is_keyword(
+            piece,
+            &[
+                "ultra-condensed",
+                "extra-condensed",
+                "condensed",
+                "semi-condensed",
+                "semi-expanded",
+                "expanded",
+                "extra-expanded",
+                "ultra-expanded",
+            ],
+        ) {
+            &mut stretch
+        } else {
+            break;
+        };
+        if slot.replace(piece.clone()).is_some() {
+            return None;
+        }
+        at += 1;
+    }
+
+    let size = pieces.get(at)?;
+    let size_keywords = [
+        "xx-small",
+        "x-small",
+        "small",
+        "medium",
+        "large",
+        "x-large",
+        "xx-large",
+        "xxx-large",
+        "larger",
+        "smaller",
+    ];
+    if !is_keyword(size, &size_keywords) && !is_length(size) {
+        return None;
+    }
+    at += 1;
+
+    let mut line_height = "normal".to_string();
+    if pieces.get(at).is_some_and(|piece| piece == "/") {
+        line_height = pieces.get(at + 1)?.clone();
+        at += 2;
+    }
+
+    // "Helvetica Neue", serif
+    let mut family = String::new();
+    for piece in &pieces[at..] {
+        if piece == "/" {
+            return None;
+        }
+        if piece != "," && !family.is_empty() {
+            family.push(' ');
+        }
+        family.push_str(piece);
+    }
+    if family.is_empty() {
+        return None;
+    }
+
+    let normal = || "normal".to_string();
+    return Some(vec![
+        style.unwrap_or_else(normal),
+        variant.unwrap_or_else(normal),
+        weight.unwrap_or_else(normal),
+        stretch.unwrap_or_else(normal),
+        size.clone(),
+        line_height,
+        family,
+    ]);
+}
+
+// underline overline dotted red
+fn text_decoration(pieces: &[String]) -> Option<Vec<String>> {
+    let mut line: Vec<&str> = Vec::new();
+    let mut style = None;
+    let mut color = None;
+    for piece in pieces {
+        let slot = if is_keyword(
+            piece,
+            &["none", "underline", "overline", "line-through", "blink"],
+        ) {
+            line.push(piece);
+            continue;
+        } else if is_keyword(piece, &["solid", "double", "dotted", "dashed", "wavy"]) {
+            &mut style
+        } else if is_color(piece) {
+            &mut color
+        } else {
+            return None;
+        };
+        if slot.replace(piece.clone()).is_some() {
+            return None;
+        }
+    }
+    return Some(vec![
+        if line.is_empty() {
+            "none".to_string()
+        } else {
+            line.join(" ")
+        },
+        style.unwrap_or("solid".to_string()),
+        color.unwrap_or("currentcolor".to_string()),
+    ]);
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::css;
+    use crate::css::shorthand::expand;
+
+    fn expanded(property: &str, value: &str) -> Vec<(String, String)> {
+        return expand(property, value)
+            .into_iter()
+            .map(|d| (d.name, d.value))
+            .collect();
+    }
+
+    fn pairs(expected: &[(&str, &str)]) -> Vec<(String, String)> {
+        return expected
+            .iter()
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .collect();
+    }
+
+    #[test]
+    fn expand_box_sides() {
+        // (値, 上 右 下 左)
+        let table = [
+            ("0", ["0", "0", "0", "0"]),
+            ("0 auto", ["0", "auto", "0", "auto"]),
+            ("1px 2px 3px", ["1px", "2px", "3px", "2px"]),
+            ("1px 2px 3px 4px", ["1px", "2px", "3px", "4px"]),
+            ("  -1em\t5%  ", ["-1em", "5%", "-1em", "5%"]),
+            (
+                "calc(1px + 2px) 0",
+                ["calc(1px + 2px)", "0", "calc(1px + 2px)", "0"],
+            ),
+            ("inherit", ["inherit", "inherit", "inherit", "inherit"]),
+        ];
+        for (value, [top, right, bottom, left]) in table {
+            for property in ["margin", "padding"] {
+                assert_eq!(
+                    expanded(property, value),
+                    pairs(&[
+                        (&format!("{}-top", property), top),
+                        (&format!("{}-right", property), right),
+                        (&format!("{}-bottom", property), bottom),
+                        (&format!("{}-left", property), left),
+                    ]),
+                    "{}: {}",
+                    property,
+                    value
+                );
+            }
+        }
+
+        // 読めない値はそのまま
+        for value in ["1px 2px 3px 4px 5px", "1px, 2px", "var(--gap) 0"] {
+            assert_eq!(expanded("margin", value), pairs(&[("margin", value)]));
+        }
+        assert_eq!(
+            expanded("border-width", "thin thick"),
+            pairs(&[
+                ("border-top-width", "thin"),
+                ("border-right-width", "thick"),
+                ("border-bottom-width", "thin"),
+                ("border-left-width", "thick"),
+            ])
+        );
+    }
+
+    #[test]
+    fn expand_border() {
+        assert_eq!(
+            expanded("border-top", "1px solid red"),
+            pairs(&[
+                ("border-top-width", "1px"),
+                ("border-top-style", "solid"),
+                ("border-top-color", "red"),
+            ])
+        );
+        // 順番は自由で、省いたものは初期値
+        assert_eq!(
+            expanded("border-left", "DASHED #00f"),
+            pairs(&[
+                ("border-left-width", "medium"),
+                ("border-left-style", "DASHED"),
+                ("border-left-color", "#00f"),
+            ])
+        );
+
+        let border = expanded("border", "rgb(0, 0, 0) 2px dotted");
+        assert_eq!(border.len(), 12);
+        assert_eq!(
+            border[..3],
+            pairs(&[
+                ("border-top-width", "2px"),
+                ("border-top-style", "dotted"),
+                ("border-top-color", "rgb(0, 0, 0)"),
+            ])
+        );
+        assert_eq!(border[9].0, "border-left-width");
+        assert_eq!(border[11].1, "rgb(0, 0, 0)");
+
+        for value in ["1px 2px solid", "solid dashed", "1px solid nocolor"] {
+            assert_eq!(expanded("border", value), pairs(&[("border", value)]));
+        }
+    }
+
+    #[test]
+    fn expand_background_and_font() {
+        assert_eq!(
+            expanded("background", "url(bg.png) no-repeat #fff center top"),
+            pairs(&[
+                ("background-color", "#fff"),
+                ("background-image", "url(bg.png)"),
+                ("background-repeat", "no-repeat"),
+                ("background-attachment", "scroll"),
+                ("background-position", "center top"),
+            ])
+        );
+        assert_eq!(
+            expanded("background", "red")[0],
+            ("background-color".to_string(), "red".to_string())
+        );
+        assert_eq!(
+            expanded("background", "url(a.png), url(b.png)"),
+            pairs(&[("background", "url(a.png), url(b.png)")])
+        );
+
+        assert_eq!(
+            expanded("font", "italic bold 12px/1.4 \"Helvetica Neue\", serif"),
+            pairs(&[
+                ("font-style", "italic"),
+                ("font-variant", "normal"),
+                ("font-weight", "bold"),
+                ("font-stretch", "normal"),
+                ("font-size", "12px"),
+                ("line-height", "1.4"),
+                ("font-family", "\"Helvetica Neue\", serif"),
+            ])
+        );
+        assert_eq!(
+            expanded("font", "600 small-caps 2em sans-serif")[2..5],
+            pairs(&[
+                ("font-weight", "600"),
+                ("font-stretch", "normal"),
+                ("font-size", "2em"),
+            ])
+        );
+        // sizeとfamilyは省けない
+        for value in ["bold serif", "12px", "caption"] {
+            assert_eq!(expanded("font", value), pairs(&[("font", value)]));
+        }
+    }
+
+    #[test]
+    fn unknown_properties_pass_through() {
+        assert_eq!(expanded("color", "red"), pairs(&[("color", "red")]));
+        assert_eq!(expanded("color", "inherit"), pairs(&[("color", "inherit")]));
+        assert_eq!(expanded("--gap", "0 auto"), pairs(&[("--gap", "0 auto")]));
+        assert_eq!(
+            expanded("text-decoration", "underline wavy red"),
+            pairs(&[
+                ("text-decoration-line", "underline"),
+                ("text-decoration-style", "wavy"),
+                ("text-decoration-color", "red"),
+            ])
+        );
+
+        // 宣言を読むときに分けられ、!importantは全部に付く
+        let declarations =
+            css::parse_declarations("margin: 0 auto !important; color: red").unwrap();
+        let names: Vec<(&str, &str, bool)> = declarations
+            .iter()
+            .map(|d| (d.name.as_str(), d.value.as_str(), d.important))
+            .collect();
+        assert_eq!(
+            names,
+            vec![
+                ("margin-top", "0", true),
+                ("margin-right", "auto", true),
+                ("margin-bottom", "0", true),
+                ("margin-left", "auto", true),
+                ("color", "red", false),
+            ]
+        );
+    }
+}
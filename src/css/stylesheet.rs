@@ -0,0 +1,295 @@
+use crate::css::errors::CssParseError;
+use crate::css::media::{MediaEnv, MediaQueryList};
+use crate::css::selector::SelectorList;
+use crate::css::tokenizer::CssTokenKind;
+
+// parseしたstylesheet。読めなかった部分はerrorsに残して飛ばす
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Stylesheet {
+    pub rules: Vec<Rule>,
+    pub errors: Vec<CssParseError>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Rule {
+    Style(StyleRule),
+    Media(MediaRule),
+    At(AtRule),
+}
+
+// a.nav > li { color: red }
+#[derive(Debug, Clone, PartialEq)]
+pub struct StyleRule {
+    pub selectors: SelectorList,
+    pub declarations: Vec<Declaration>,
+}
+
+// @media screen { p { ... } }
+#[derive(Debug, Clone, PartialEq)]
+pub struct MediaRule {
+    pub queries: MediaQueryList,
+    pub rules: Vec<Rule>,
+}
+
+// @font-face { ... } / @import "a.css";
+#[derive(Debug, Clone, PartialEq)]
+pub struct AtRule {
+    pub name: String,
+    pub prelude: String,
+    // {} の中身をそのまま持つ。; で終わるものはNone
+    pub block: Option<String>,
+}
+
+// to_cssの書き方
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum CssFormat {
+    // 空白を詰め、最後の ; も省く
+    #[default]
+    Compact,
+    // 宣言を一行ずつ書き、blockを字下げする
+    Pretty,
+}
+
+const INDENT: &str = "    ";
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Declaration {
+    // --で始まるもの以外は小文字にする
+    pub name: String,
+    pub value: String,
+    pub important: bool,
+}
+
+impl Stylesheet {
+    // 一番外側のstyle ruleだけ。@mediaの中は見ない
+    pub fn style_rules(&self) -> impl Iterator<Item = &StyleRule> {
+        return self.rules.iter().filter_map(|rule| match rule {
+            Rule::Style(style) => Some(style),
+            Rule::Media(_) | Rule::At(_) => None,
+        });
+    }
+
+    // errorsは書かない
+    pub fn to_css(&self, format: CssFormat) -> String {
+        let mut out = String::new();
+        write_rules(&mut out, &self.rules, format, 0);
+        if format == CssFormat::Pretty && !out.is_empty() {
+            out.push('\n');
+        }
+        return out;
+    }
+
+    // envに合う@mediaの中も含めて、書かれた順に並べる
+    pub fn style_rules_for(&self, env: &MediaEnv) -> Vec<&StyleRule> {
+        let mut found = Vec::new();
+        collect_style_rules(&self.rules, env, &mut found);
+        return found;
+    }
+}
+
+fn collect_style_rules<'s>(rules: &'s [Rule], env: &MediaEnv, found: &mut Vec<&'s StyleRule>) {
+    for rule in rules {
+        match rule {
+            Rule::Style(style) => found.push(style),
+            Rule::Media(media) if media.queries.evaluate(env) => {
+                collect_style_rules(&media.rules, env, found)
+            }
+            Rule::Media(_) | Rule::At(_) => {}
+        }
+    }
+}
+
+impl Declaration {
+    pub fn to_css(&self, format: CssFormat) -> String {
+        // 名前はエスケープが要るものがあるのでidentとして書き直す
+        let name = CssTokenKind::Ident(self.name.clone()).to_string();
+        let important = match (self.important, format) {
+            (false, _) => "",
+            (true, CssFormat::Compact) => "!important",
+            (true, CssFormat::Pretty) => " !important",
+        };
+        return match format {
+            CssFormat::Compact => format!("{}:{}{}", name, self.value, important),
+            CssFormat::Pretty if self.value.is_empty() => format!("{}:{}", name, important),
+            CssFormat::Pretty => format!("{}: {}{}", name, self.value, important),
+        };
+    }
+}
+
+fn write_rules(out: &mut String, rules: &[Rule], format: CssFormat, depth: usize) {
+    for (i, rule) in rules.iter().enumerate() {
+        if format == CssFormat::Pretty && i > 0 {
+            // 一番外側のruleの間は一行空ける
+            out.push_str(if depth == 0 { "\n\n" } else { "\n" });
+        }
+        write_rule(out, rule, format, depth);
+    }
+}
+
+fn write_rule(out: &mut String, rule: &Rule, format: CssFormat, depth: usize) {
+    let indent = INDENT.repeat(depth);
+    if format == CssFormat::Pretty {
+        out.push_str(&indent);
+    }
+    match rule {
+        Rule::Style(style) => {
+            match format {
+                CssFormat::Compact => out.push_str(&format!("{:#}{{", style.selectors)),
+                CssFormat::Pretty => out.push_str(&format!("{} {{", style.selectors)),
+            }
+            let declarations: Vec<String> = style
+                .declarations
+                .iter()
+                .map(|declaration| declaration.to_css(format))
+                .collect();
+            match format {
+                CssFormat::Compact => out.push_str(&declarations.join(";")),
+                CssFormat::Pretty => {
+                    for declaration in &declarations {
+                        out.push_str(&format!("\n{}{}{};", indent, INDENT, declaration));
+                    }
+                    if !declarations.is_empty() {
+                        out.push('\n');
+                        out.push_str(&indent);
+                    }
+                }
+            }
+            out.push('}');
+        }
+        Rule::Media(media) => {
+            let queries = match format {
+                CssFormat::Compact => format!("{:#}", media.queries),
+                CssFormat::Pretty => media.queries.to_string(),
+            };
+            out.push_str("@media");
+            if !queries.is_empty() {
+                out.push(' ');
+                out.push_str(&queries);
+            }
+            match format {
+                CssFormat::Compact => {
+                    out.push('{');
+                    write_rules(out, &media.rules, format, depth + 1);
+                }
+                CssFormat::Pretty => {
+                    out.push_str(" {");
+                    if !media.rules.is_empty() {
+                        out.push('\n');
+                        write_rules(out, &media.rules, format, depth + 1);
+                        out.push('\n');
+                        out.push_str(&indent);
+                    }
+                }
+            }
+            out.push('}');
+        }
+        Rule::At(at) => {
+            out.push_str(&CssTokenKind::AtKeyword(at.name.clone()).to_string());
+            if !at.prelude.is_empty() {
+                out.push(' ');
+                out.push_str(&at.prelude);
+            }
+            // 中身はsourceのまま持っているので書き換えない
+            match (&at.block, format) {
+                (None, _) => out.push(';'),
+                (Some(block), CssFormat::Compact) => out.push_str(&format!("{{{}}}", block)),
+                (Some(block), CssFormat::Pretty) if block.is_empty() => out.push_str(" {}"),
+                (Some(block), CssFormat::Pretty) => {
+                    out.push_str(&format!(" {{\n{}{}{}\n{}}}", indent, INDENT, block, indent))
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::css;
+    use crate::css::stylesheet::{CssFormat, Declaration};
+
+    const SHEETS: [&str; 5] = [
+        "a.nav > li:hover, #main .item { color: #333 !important; margin: 0 auto }",
+        "@charset \"utf-8\"; @import url(base.css) screen; html { font: italic bold 12px/1.4 \"Helvetica Neue\", serif }",
+        "@media screen and (max-width: 600px), print { .item { display: none } @media (orientation: portrait) { p { --gap: 4px; --empty:; } } } @media { }",
+        "@font-face { font-family: X; src: url(x.woff) } ul ~ li + li:not(.a, [data-x=\"a > b\"])::before { content: \"\\201C\" }",
+        "p {} div>p{width:calc(100% - 2*1em);background:url(\"a b.png\") no-repeat}",
+    ];
+
+    #[test]
+    fn round_trip_through_css_text() {
+        for source in SHEETS {
+            let sheet = css::parse_stylesheet(source).unwrap();
+            for format in [CssFormat::Compact, CssFormat::Pretty] {
+                let text = sheet.to_css(format);
+                let reparsed = css::parse_stylesheet(&text).unwrap();
+                assert_eq!(reparsed, sheet, "{:?} -> {}", format, text);
+                // 二回目からは変わらない
+                assert_eq!(reparsed.to_css(format), text);
+            }
+        }
+    }
+
+    #[test]
+    fn compact_and_pretty_output() {
+        let sheet = css::parse_stylesheet(
+            "@import \"a.css\";\
+             ul > li, .a   .b { color : red ; background-color: rgb(0, 0, 255) !important; }\
+             @media screen and (min-width: 40em) { p { width: 1px } }\
+             @page { margin: 1in }",
+        )
+        .unwrap();
+        assert_eq!(
+            sheet.to_css(CssFormat::Compact),
+            "@import \"a.css\";\
+             ul>li,.a .b{color:red;background-color:rgb(0, 0, 255)!important}\
+             @media screen and (min-width:40em){p{width:1px}}\
+             @page{margin: 1in}"
+        );
+        assert_eq!(
+            sheet.to_css(CssFormat::Pretty),
+            r#"@import "a.css";
+
+ul > li, .a .b {
+    color: red;
+    background-color: rgb(0, 0, 255) !important;
+}
+
+@media screen and (min-width: 40em) {
+    p {
+        width: 1px;
+    }
+}
+
+@page {
+    margin: 1in
+}
+"#
+        );
+    }
+
+    #[test]
+    fn declaration_to_css() {
+        let declaration = Declaration {
+            name: "color".to_string(),
+            value: "red".to_string(),
+            important: true,
+        };
+        assert_eq!(
+            declaration.to_css(CssFormat::Compact),
+            "color:red!important"
+        );
+        assert_eq!(
+            declaration.to_css(CssFormat::Pretty),
+            "color: red !important"
+        );
+
+        let custom = Declaration {
+            name: "--my gap".to_string(),
+            value: String::new(),
+            important: false,
+        };
+        assert_eq!(custom.to_css(CssFormat::Compact), "--my\\ gap:");
+        let reparsed = css::parse_declarations(&custom.to_css(CssFormat::Pretty)).unwrap();
+        assert_eq!(reparsed, vec![custom]);
+    }
+}
@@ -0,0 +1,892 @@
+use crate::css::errors::CssTokenizeError;
+use crate::html::position::Position;
+use std::fmt;
+
+// CSS Syntax Level 3 の tokenizer に沿ったtoken
+#[derive(Debug, Clone, PartialEq)]
+pub enum CssTokenKind {
+    Ident(String),
+    Function(String),
+    AtKeyword(String),
+    // is_idは #の後ろがidentとして読めるとき
+    Hash {
+        value: String,
+        is_id: bool,
+    },
+    String(String),
+    BadString,
+    Url(String),
+    BadUrl,
+    Delim(char),
+    Number {
+        value: f64,
+        is_integer: bool,
+    },
+    Percentage(f64),
+    Dimension {
+        value: f64,
+        is_integer: bool,
+        unit: String,
+    },
+    Whitespace,
+    Comment(String),
+    Cdo,
+    Cdc,
+    Colon,
+    Semicolon,
+    Comma,
+    OpenSquare,
+    CloseSquare,
+    OpenParen,
+    CloseParen,
+    OpenCurly,
+    CloseCurly,
+    Eof,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct CssToken {
+    pub kind: CssTokenKind,
+    // tokenの始まりの位置
+    pub pos: Position,
+}
+
+// tokenとして読み直したときに同じidentになるように書く
+pub(crate) fn write_ident(f: &mut fmt::Formatter<'_>, ident: &str) -> fmt::Result {
+    let chars: Vec<char> = ident.chars().collect();
+    for (i, c) in chars.iter().copied().enumerate() {
+        let digit_at_start = c.is_ascii_digit() && (i == 0 || (i == 1 && chars[0] == '-'));
+        if c == '\x00' {
+            write!(f, "\u{fffd}")?;
+        } else if is_non_printable(c) || digit_at_start {
+            write!(f, "\\{:x} ", c as u32)?;
+        } else if (c == '-' && chars.len() == 1) || !is_ident_char(c) {
+            write!(f, "\\{}", c)?;
+        } else {
+            write!(f, "{}", c)?;
+        }
+    }
+    return Ok(());
+}
+
+pub(crate) fn write_string(f: &mut fmt::Formatter<'_>, s: &str) -> fmt::Result {
+    write!(f, "\"")?;
+    for c in s.chars() {
+        match c {
+            '"' | '\\' => write!(f, "\\{}", c)?,
+            c if is_non_printable(c) || is_newline(c) => write!(f, "\\{:x} ", c as u32)?,
+            c => write!(f, "{}", c)?,
+        }
+    }
+    return write!(f, "\"");
+}
+
+// 10e3 のような整数でない数は .0 を付けて区別を残す
+fn write_number(f: &mut fmt::Formatter<'_>, value: f64, is_integer: bool) -> fmt::Result {
+    if !is_integer && value.fract() == 0.0 {
+        return write!(f, "{}.0", value);
+    }
+    return write!(f, "{}", value);
+}
+
+// CSSの文字列に戻す
+impl fmt::Display for CssTokenKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        return match self {
+            CssTokenKind::Ident(s) => write_ident(f, s),
+            CssTokenKind::Function(s) => {
+                write_ident(f, s)?;
+                write!(f, "(")
+            }
+            CssTokenKind::AtKeyword(s) => {
+                write!(f, "@")?;
+                write_ident(f, s)
+            }
+            CssTokenKind::Hash { value, is_id } => {
+                write!(f, "#")?;
+                if *is_id {
+                    return write_ident(f, value);
+                }
+                // #123 のように数字で始まるものはそのまま
+                for c in value.chars() {
+                    if is_ident_char(c) {
+                        write!(f, "{}", c)?;
+                    } else {
+                        write!(f, "\\{}", c)?;
+                    }
+                }
+                Ok(())
+            }
+            CssTokenKind::String(s) => write_string(f, s),
+            CssTokenKind::BadString => write!(f, "\"\""),
+            CssTokenKind::Url(s) => {
+                write!(f, "url(")?;
+                for c in s.chars() {
+                    if is_ws(c) || is_non_printable(c) || matches!(c, '"' | '\'' | '(' | ')' | '\\')
+                    {
+                        write!(f, "\\{:x} ", c as u32)?;
+                    } else {
+                        write!(f, "{}", c)?;
+                    }
+                }
+                write!(f, ")")
+            }
+            CssTokenKind::BadUrl => write!(f, "url()"),
+            CssTokenKind::Delim(c) => write!(f, "{}", c),
+            CssTokenKind::Number { value, is_integer } => write_number(f, *value, *is_integer),
+            CssTokenKind::Percentage(value) => write!(f, "{}%", value),
+            CssTokenKind::Dimension {
+                value,
+                is_integer,
+                unit,
+            } => {
+                write_number(f, *value, *is_integer)?;
+                // 1e3px のように読まれないようにする
+                let rest: Vec<char> = unit.chars().skip(1).take(2).collect();
+                let exponent_like = match rest.as_slice() {
+                    [d, ..] if d.is_ascii_digit() => true,
+                    ['+' | '-', d] => d.is_ascii_digit(),
+                    _ => false,
+                };
+                if unit.starts_with(['e', 'E']) && exponent_like {
+                    write!(f, "\\{:x} ", unit.chars().next().unwrap_or('e') as u32)?;
+                    return write_ident(f, &unit[1..]);
+                }
+                write_ident(f, unit)
+            }
+            CssTokenKind::Whitespace => write!(f, " "),
+            CssTokenKind::Comment(s) => write!(f, "/*{}*/", s),
+            CssTokenKind::Cdo => write!(f, "<!--"),
+            CssTokenKind::Cdc => write!(f, "-->"),
+            CssTokenKind::Colon => write!(f, ":"),
+            CssTokenKind::Semicolon => write!(f, ";"),
+            CssTokenKind::Comma => write!(f, ","),
+            CssTokenKind::OpenSquare => write!(f, "["),
+            CssTokenKind::CloseSquare => write!(f, "]"),
+            CssTokenKind::OpenParen => write!(f, "("),
+            CssTokenKind::CloseParen => write!(f, ")"),
+            CssTokenKind::OpenCurly => write!(f, "{{"),
+            CssTokenKind::CloseCurly => write!(f, "}}"),
+            CssTokenKind::Eof => Ok(()),
+        };
+    }
+}
+
+fn is_newline(c: char) -> bool {
+    return c == '\n' || c == '\r' || c == '\x0c';
+}
+
+fn is_ws(c: char) -> bool {
+    return is_newline(c) || c == ' ' || c == '\t';
+}
+
+fn is_ident_start(c: char) -> bool {
+    return c.is_ascii_alphabetic() || c == '_' || !c.is_ascii();
+}
+
+fn is_ident_char(c: char) -> bool {
+    return is_ident_start(c) || c.is_ascii_digit() || c == '-';
+}
+
+fn is_non_printable(c: char) -> bool {
+    return matches!(c, '\x00'..='\x08' | '\x0b' | '\x0e'..='\x1f' | '\x7f');
+}
+
+pub struct CssTokenizer {
+    chars: Vec<char>,
+    at: usize,
+    pos: Position,
+    errors: Vec<CssTokenizeError>,
+}
+
+impl CssTokenizer {
+    pub fn new(target: &str) -> CssTokenizer {
+        return CssTokenizer {
+            chars: target.chars().collect(),
+            at: 0,
+            pos: Position::new(1, 0, 0),
+            errors: Vec::new(),
+        };
+    }
+
+    // 最初のエラーで止める
+    pub fn tokenize(&mut self) -> Result<Vec<CssToken>, CssTokenizeError> {
+        let (tokens, mut errors) = self.tokenize_recovering();
+        if errors.is_empty() {
+            return Ok(tokens);
+        }
+        return Err(errors.remove(0));
+    }
+
+    // エラーは仕様どおりに直して読み進め、まとめて返す (stylesheetのparserはこちらを使う)
+    pub fn tokenize_recovering(&mut self) -> (Vec<CssToken>, Vec<CssTokenizeError>) {
+        let mut tokens = Vec::new();
+        loop {
+            let tok = self.next_token();
+            let eof = tok.kind == CssTokenKind::Eof;
+            tokens.push(tok);
+            if eof {
+                break;
+            }
+        }
+        return (tokens, std::mem::take(&mut self.errors));
+    }
+
+    fn peek(&self, n: usize) -> Option<char> {
+        return self.chars.get(self.at + n).copied();
+    }
+
+    fn advance(&mut self) -> Option<char> {
+        let c = match self.chars.get(self.at) {
+            Some(c) => *c,
+            None => return None,
+        };
+        self.at += 1;
+        self.pos.at_whole += 1;
+        // \r\n は一つの改行として数える
+        if c == '\n' || c == '\x0c' || (c == '\r' && self.peek(0) != Some('\n')) {
+            self.pos.line_no += 1;
+            self.pos.at_line = 0;
+        } else {
+            self.pos.at_line += 1;
+        }
+        return Some(c);
+    }
+
+    fn error(&mut self, err: CssTokenizeError) {
+        self.errors.push(err);
+    }
+
+    // \ のあとが改行でもEofでもない
+    fn is_valid_escape(&self, n: usize) -> bool {
+        return self.peek(n) == Some('\\')
+            && match self.peek(n + 1) {
+                Some(c) => !is_newline(c),
+                None => false,
+            };
+    }
+
+    fn starts_ident(&self, n: usize) -> bool {
+        return match self.peek(n) {
+            Some('-') => match self.peek(n + 1) {
+                Some(c) if is_ident_start(c) || c == '-' => true,
+                _ => self.is_valid_escape(n + 1),
+            },
+            Some('\\') => self.is_valid_escape(n),
+            Some(c) => is_ident_start(c),
+            None => false,
+        };
+    }
+
+    fn starts_number(&self, n: usize) -> bool {
+        let digit = |c: Option<char>| matches!(c, Some('0'..='9'));
+        return match self.peek(n) {
+            Some('+') | Some('-') => {
+                digit(self.peek(n + 1))
+                    || (self.peek(n + 1) == Some('.') && digit(self.peek(n + 2)))
+            }
+            Some('.') => digit(self.peek(n + 1)),
+            c => digit(c),
+        };
+    }
+
+    // \ は読んだあと
+    fn consume_escape(&mut self) -> char {
+        let start = self.pos.clone();
+        let c = match self.advance() {
+            Some(c) => c,
+            None => {
+                self.error(CssTokenizeError::InvalidEscape { pos: start });
+                return '\u{fffd}';
+            }
+        };
+        if !c.is_ascii_hexdigit() {
+            return c;
+        }
+
+        let mut hex = c.to_string();
+        while hex.len() < 6 {
+            match self.peek(0) {
+                Some(h) if h.is_ascii_hexdigit() => {
+                    hex.push(h);
+                    self.advance();
+                }
+                _ => break,
+            }
+        }
+        // 16進のあとの空白1つは区切り
+        if let Some(c) = self.peek(0) {
+            if is_ws(c) {
+                self.advance();
+                if c == '\r' && self.peek(0) == Some('\n') {
+                    self.advance();
+                }
+            }
+        }
+
+        return match u32::from_str_radix(&hex, 16) {
+            Ok(0) | Err(_) => '\u{fffd}',
+            Ok(n) => char::from_u32(n).unwrap_or('\u{fffd}'),
+        };
+    }
+
+    fn consume_name(&mut self) -> String {
+        let mut name = String::new();
+        loop {
+            match self.peek(0) {
+                Some(c) if is_ident_char(c) => {
+                    name.push(c);
+                    self.advance();
+                }
+                Some('\\') if self.is_valid_escape(0) => {
+                    self.advance();
+                    name.push(self.consume_escape());
+                }
+                _ => return name,
+            }
+        }
+    }
+
+    fn consume_number(&mut self) -> (f64, bool) {
+        let mut repr = String::new();
+        let mut is_integer = true;
+
+        if let Some(c @ ('+' | '-')) = self.peek(0) {
+            repr.push(c);
+            self.advance();
+        }
+        self.consume_digits(&mut repr);
+        if self.peek(0) == Some('.') && matches!(self.peek(1), Some('0'..='9')) {
+            is_integer = false;
+            repr.push('.');
+            self.advance();
+            self.consume_digits(&mut repr);
+        }
+        // 1e3, 1e-3
+        if let Some(e @ ('e' | 'E')) = self.peek(0) {
+            let signed = matches!(self.peek(1), Some('+' | '-'));
+            let digit_at = if signed { 2 } else { 1 };
+            if matches!(self.peek(digit_at), Some('0'..='9')) {
+                is_integer = false;
+                repr.push(e);
+                self.advance();
+                if signed {
+                    if let Some(sign) = self.advance() {
+                        repr.push(sign);
+                    }
+                }
+                self.consume_digits(&mut repr);
+            }
+        }
+
+        let value = repr.parse::<f64>().unwrap_or(0.0);
+        return (value, is_integer);
+    }
+
+    fn consume_digits(&mut self, repr: &mut String) {
+        while let Some(c @ '0'..='9') = self.peek(0) {
+            repr.push(c);
+            self.advance();
+        }
+    }
+
+    fn consume_numeric(&mut self) -> CssTokenKind {
+        let (value, is_integer) = self.consume_number();
+        if self.starts_ident(0) {
+            let unit = self.consume_name();
+            return CssTokenKind::Dimension {
+                value,
+                is_integer,
+                unit,
+            };
+        }
+        if self.peek(0) == Some('%') {
+            self.advance();
+            return CssTokenKind::Percentage(value);
+        }
+        return CssTokenKind::Number { value, is_integer };
+    }
+
+    fn consume_string(&mut self, quote: char) -> CssTokenKind {
+        let start = self.pos.clone();
+        self.advance();
+        let mut s = String::new();
+        loop {
+            match self.peek(0) {
+                None => {
+                    self.error(CssTokenizeError::UnterminatedString { pos: start });
+                    return CssTokenKind::String(s);
+                }
+                Some(c) if c == quote => {
+                    self.advance();
+                    return CssTokenKind::String(s);
+                }
+                // 改行は読まずに残す
+                Some(c) if is_newline(c) => {
+                    self.error(CssTokenizeError::NewlineInString {
+                        pos: self.pos.clone(),
+                    });
+                    return CssTokenKind::BadString;
+                }
+                Some('\\') => {
+                    self.advance();
+                    match self.peek(0) {
+                        None => {}
+                        // 行の継続
+                        Some(c) if is_newline(c) => {
+                            self.advance();
+                            if c == '\r' && self.peek(0) == Some('\n') {
+                                self.advance();
+                            }
+                        }
+                        Some(_) => s.push(self.consume_escape()),
+                    }
+                }
+                Some(c) => {
+                    s.push(c);
+                    self.advance();
+                }
+            }
+        }
+    }
+
+    fn consume_comment(&mut self) -> CssTokenKind {
+        let start = self.pos.clone();
+        self.advance();
+        self.advance();
+        let mut s = String::new();
+        loop {
+            match self.peek(0) {
+                None => {
+                    self.error(CssTokenizeError::UnterminatedComment { pos: start });
+                    return CssTokenKind::Comment(s);
+                }
+                Some('*') if self.peek(1) == Some('/') => {
+                    self.advance();
+                    self.advance();
+                    return CssTokenKind::Comment(s);
+                }
+                Some(c) => {
+                    s.push(c);
+                    self.advance();
+                }
+            }
+        }
+    }
+
+    fn consume_ws(&mut self) {
+        while let Some(c) = self.peek(0) {
+            if !is_ws(c) {
+                break;
+            }
+            self.advance();
+        }
+    }
+
+    // url( のあと。引用符なしのurl
+    fn consume_url(&mut self, start: Position) -> CssTokenKind {
+        let mut url = String::new();
+        self.consume_ws();
+        loop {
+            match self.peek(0) {
+                None => {
+                    self.error(CssTokenizeError::BadUrl { pos: start });
+                    return CssTokenKind::Url(url);
+                }
+                Some(')') => {
+                    self.advance();
+                    return CssTokenKind::Url(url);
+                }
+                Some(c) if is_ws(c) => {
+                    self.consume_ws();
+                    match self.peek(0) {
+                        Some(')') => {
+                            self.advance();
+                            return CssTokenKind::Url(url);
+                        }
+                        None => {
+                            self.error(CssTokenizeError::BadUrl { pos: start });
+                            return CssTokenKind::Url(url);
+                        }
+                        Some(_) => return self.consume_bad_url(start),
+                    }
+                }
+                Some(c) if c == '"' || c == '\'' || c == '(' || is_non_printable(c) => {
+                    return self.consume_bad_url(start);
+                }
+                Some('\\') => {
+                    if !self.is_valid_escape(0) {
+                        return self.consume_bad_url(start);
+                    }
+                    self.advance();
+                    url.push(self.consume_escape());
+                }
+                Some(c) => {
+                    url.push(c);
+                    self.advance();
+                }
+            }
+        }
+    }
+
+    // 壊れたurlは ")" まで読み飛ばす
+    fn consume_bad_url(&mut self, start: Position) -> CssTokenKind {
+        self.error(CssTokenizeError::BadUrl { pos: start });
+        loop {
+            match self.peek(0) {
+                None => break,
+                Some(')') => {
+                    self.advance();
+                    break;
+                }
+                Some('\\') if self.is_valid_escape(0) => {
+                    self.advance();
+                    self.consume_escape();
+                }
+                Some(_) => {
+                    self.advance();
+                }
+            }
+        }
+        return CssTokenKind::BadUrl;
+    }
+
+    fn consume_ident_like(&mut self, start: Position) -> CssTokenKind {
+        let name = self.consume_name();
+        if self.peek(0) != Some('(') {
+            return CssTokenKind::Ident(name);
+        }
+        self.advance();
+
+        if !name.eq_ignore_ascii_case("url") {
+            return CssTokenKind::Function(name);
+        }
+        // url("...") は普通の関数として読む
+        let mut n = 0;
+        while matches!(self.peek(n), Some(c) if is_ws(c)) {
+            n += 1;
+        }
+        if matches!(self.peek(n), Some('"' | '\'')) {
+            return CssTokenKind::Function(name);
+        }
+        return self.consume_url(start);
+    }
+
+    pub fn next_token(&mut self) -> CssToken {
+        let start = self.pos.clone();
+        let kind = self.next_kind(start.clone());
+        return CssToken { kind, pos: start };
+    }
+
+    fn next_kind(&mut self, start: Position) -> CssTokenKind {
+        let c = match self.peek(0) {
+            Some(c) => c,
+            None => return CssTokenKind::Eof,
+        };
+
+        if c == '/' && self.peek(1) == Some('*') {
+            return self.consume_comment();
+        }
+        if is_ws(c) {
+            self.consume_ws();
+            return CssTokenKind::Whitespace;
+        }
+        if c == '"' || c == '\'' {
+            return self.consume_string(c);
+        }
+        if c.is_ascii_digit() {
+            return self.consume_numeric();
+        }
+        if is_ident_start(c) {
+            return self.consume_ident_like(start);
+        }
+
+        match c {
+            '#' if matches!(self.peek(1), Some(n) if is_ident_char(n))
+                || self.is_valid_escape(1) =>
+            {
+                self.advance();
+                let is_id = self.starts_ident(0);
+                let value = self.consume_name();
+                return CssTokenKind::Hash { value, is_id };
+            }
+            '+' | '.' if self.starts_number(0) => {
+                return self.consume_numeric();
+            }
+            '-' => {
+                if self.starts_number(0) {
+                    return self.consume_numeric();
+                }
+                if self.peek(1) == Some('-') && self.peek(2) == Some('>') {
+                    self.advance();
+                    self.advance();
+                    self.advance();
+                    return CssTokenKind::Cdc;
+                }
+                if self.starts_ident(0) {
+                    return self.consume_ident_like(start);
+                }
+            }
+            '<' if self.peek(1) == Some('!')
+                && self.peek(2) == Some('-')
+                && self.peek(3) == Some('-') =>
+            {
+                for _ in 0..4 {
+                    self.advance();
+                }
+                return CssTokenKind::Cdo;
+            }
+            '@' if self.starts_ident(1) => {
+                self.advance();
+                return CssTokenKind::AtKeyword(self.consume_name());
+            }
+            '\\' => {
+                if self.is_valid_escape(0) {
+                    return self.consume_ident_like(start);
+                }
+                self.error(CssTokenizeError::InvalidEscape { pos: start });
+            }
+            _ => {}
+        }
+
+        self.advance();
+        return match c {
+            '(' => CssTokenKind::OpenParen,
+            ')' => CssTokenKind::CloseParen,
+            '[' => CssTokenKind::OpenSquare,
+            ']' => CssTokenKind::CloseSquare,
+            '{' => CssTokenKind::OpenCurly,
+            '}' => CssTokenKind::CloseCurly,
+            ',' => CssTokenKind::Comma,
+            ':' => CssTokenKind::Colon,
+            ';' => CssTokenKind::Semicolon,
+            c => CssTokenKind::Delim(c),
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::css::errors::CssTokenizeError;
+    use crate::css::tokenizer::{CssTokenKind, CssTokenizer};
+    use crate::html::position::Position;
+    use CssTokenKind::*;
+
+    fn kinds(input: &str) -> Vec<CssTokenKind> {
+        let mut tokenizer = CssTokenizer::new(input);
+        let (tokens, _) = tokenizer.tokenize_recovering();
+        return tokens.into_iter().map(|tok| tok.kind).collect();
+    }
+
+    fn ident(s: &str) -> CssTokenKind {
+        return Ident(s.to_string());
+    }
+
+    fn num(value: f64) -> CssTokenKind {
+        return Number {
+            value,
+            is_integer: value.fract() == 0.0,
+        };
+    }
+
+    fn dim(value: f64, is_integer: bool, unit: &str) -> CssTokenKind {
+        return Dimension {
+            value,
+            is_integer,
+            unit: unit.to_string(),
+        };
+    }
+
+    #[test]
+    fn tokenize_each_kind() {
+        let table: Vec<(&str, Vec<CssTokenKind>)> = vec![
+            ("color", vec![ident("color")]),
+            ("-webkit-box", vec![ident("-webkit-box")]),
+            ("--main-color", vec![ident("--main-color")]),
+            ("rgb(", vec![Function("rgb".to_string())]),
+            ("@media", vec![AtKeyword("media".to_string())]),
+            (
+                "#fff",
+                vec![Hash {
+                    value: "fff".to_string(),
+                    is_id: true,
+                }],
+            ),
+            (
+                "#123",
+                vec![Hash {
+                    value: "123".to_string(),
+                    is_id: false,
+                }],
+            ),
+            ("\"a b\"", vec![String("a b".to_string())]),
+            (
+                "'it''s'",
+                vec![String("it".to_string()), String("s".to_string())],
+            ),
+            ("url(a.png)", vec![Url("a.png".to_string())]),
+            ("url( a.png )", vec![Url("a.png".to_string())]),
+            (
+                "url(\"a.png\")",
+                vec![
+                    Function("url".to_string()),
+                    String("a.png".to_string()),
+                    CloseParen,
+                ],
+            ),
+            ("url(a b)", vec![BadUrl]),
+            ("12", vec![num(12.0)]),
+            ("-4.5", vec![num(-4.5)]),
+            (".5", vec![num(0.5)]),
+            (
+                "+1e3",
+                vec![Number {
+                    value: 1000.0,
+                    is_integer: false,
+                }],
+            ),
+            ("50%", vec![Percentage(50.0)]),
+            ("12px", vec![dim(12.0, true, "px")]),
+            (".5em", vec![dim(0.5, false, "em")]),
+            ("1e", vec![dim(1.0, true, "e")]),
+            ("  \n\t", vec![Whitespace]),
+            ("/* note */", vec![Comment(" note ".to_string())]),
+            ("<!-- -->", vec![Cdo, Whitespace, Cdc]),
+            (":;,", vec![Colon, Semicolon, Comma]),
+            (
+                "[](){}",
+                vec![
+                    OpenSquare,
+                    CloseSquare,
+                    OpenParen,
+                    CloseParen,
+                    OpenCurly,
+                    CloseCurly,
+                ],
+            ),
+            (
+                "> + ~ * .",
+                vec![
+                    Delim('>'),
+                    Whitespace,
+                    Delim('+'),
+                    Whitespace,
+                    Delim('~'),
+                    Whitespace,
+                    Delim('*'),
+                    Whitespace,
+                    Delim('.'),
+                ],
+            ),
+            ("a>b", vec![ident("a"), Delim('>'), ident("b")]),
+        ];
+
+        for (input, mut expected) in table {
+            expected.push(Eof);
+            assert_eq!(kinds(input), expected, "input: {:?}", input);
+        }
+    }
+
+    #[test]
+    fn tokenize_escapes() {
+        let table: Vec<(&str, Vec<CssTokenKind>)> = vec![
+            // \26 は & 。16進のあとの空白1つは区切りとして消える
+            ("\\26 b", vec![ident("&b")]),
+            ("\\26  b", vec![ident("&"), Whitespace, ident("b")]),
+            // 6桁まで
+            ("\\00004Bx", vec![ident("Kx")]),
+            ("\\000041B", vec![ident("AB")]),
+            // 0やサロゲート、範囲外はU+FFFD
+            ("\\0 x", vec![ident("\u{fffd}x")]),
+            ("\\d800 x", vec![ident("\u{fffd}x")]),
+            ("\\110000 x", vec![ident("\u{fffd}x")]),
+            // 16進でない文字はそのまま
+            ("\\.nav", vec![ident(".nav")]),
+            (
+                "#\\31 23",
+                vec![Hash {
+                    value: "123".to_string(),
+                    is_id: true,
+                }],
+            ),
+            ("\"a\\\"b\"", vec![String("a\"b".to_string())]),
+            // 文字列中の \改行 は行の継続
+            ("\"a\\\nb\"", vec![String("ab".to_string())]),
+            ("url(a\\)b)", vec![Url("a)b".to_string())]),
+        ];
+
+        for (input, mut expected) in table {
+            expected.push(Eof);
+            assert_eq!(kinds(input), expected, "input: {:?}", input);
+        }
+    }
+
+    #[test]
+    fn tokenize_errors() {
+        // 閉じていない文字列は最後までを文字列にする
+        let mut tokenizer = CssTokenizer::new("a { content: \"abc");
+        assert_eq!(
+            tokenizer.tokenize(),
+            Err(CssTokenizeError::UnterminatedString {
+                pos: Position::new(1, 13, 13)
+            })
+        );
+        assert_eq!(kinds("\"abc")[0], String("abc".to_string()));
+
+        // 改行を含む文字列はBadStringになり、改行から読み直す
+        assert_eq!(
+            kinds("'a\nb'"),
+            vec![
+                BadString,
+                Whitespace,
+                ident("b"),
+                String("".to_string()),
+                Eof
+            ]
+        );
+
+        let mut tokenizer = CssTokenizer::new("a /* b");
+        let (tokens, errors) = tokenizer.tokenize_recovering();
+        assert_eq!(tokens[2].kind, Comment(" b".to_string()));
+        assert_eq!(
+            errors,
+            vec![CssTokenizeError::UnterminatedComment {
+                pos: Position::new(1, 2, 2)
+            }]
+        );
+
+        let mut tokenizer = CssTokenizer::new("a\\\nb");
+        let (tokens, errors) = tokenizer.tokenize_recovering();
+        assert_eq!(tokens[1].kind, Delim('\\'));
+        assert_eq!(errors.len(), 1);
+    }
+
+    #[test]
+    fn display_round_trip() {
+        let inputs = [
+            "a.nav > li:hover { color: #333 !important }",
+            "\\31 23 .\\.x #-\\30 a",
+            "url(a\\)b) url(\"q\") \"a\\\"b\\\\\"",
+            "1.5em 1\\65 3px -4px 50% +.5 10e3",
+            "@media <!-- --> [x=\"y\"]",
+        ];
+        for input in inputs {
+            let tokens = kinds(input);
+            let css: std::string::String = tokens.iter().map(|kind| kind.to_string()).collect();
+            assert_eq!(kinds(&css), tokens, "{:?} -> {:?}", input, css);
+        }
+    }
+
+    #[test]
+    fn tokenize_positions() {
+        let mut tokenizer = CssTokenizer::new("a {\r\n  color: red;\n}");
+        let tokens = tokenizer.tokenize().unwrap();
+        let color = tokens
+            .iter()
+            .find(|tok| tok.kind == ident("color"))
+            .unwrap();
+        assert_eq!(color.pos, Position::new(2, 2, 7));
+        let close = tokens.iter().find(|tok| tok.kind == CloseCurly).unwrap();
+        assert_eq!(close.pos, Position::new(3, 0, 19));
+    }
+}
@@ -0,0 +1,177 @@
+use crate::css;
+use crate::css::computed::Display;
+use crate::css::stylesheet::Stylesheet;
+
+// HTML Standard 15章 (Rendering) のうち、よく使うものだけ
+
+// 表示しない要素
+const HIDDEN_ELEMENTS: [&str; 13] = [
+    "area", "base", "datalist", "head", "link", "meta", "noscript", "param", "rp", "script",
+    "style", "template", "title",
+];
+
+const BLOCK_ELEMENTS: [&str; 37] = [
+    "address",
+    "article",
+    "aside",
+    "blockquote",
+    "body",
+    "center",
+    "dd",
+    "details",
+    "dialog",
+    "dir",
+    "div",
+    "dl",
+    "dt",
+    "fieldset",
+    "figcaption",
+    "figure",
+    "footer",
+    "form",
+    "h1",
+    "h2",
+    "h3",
+    "h4",
+    "h5",
+    "h6",
+    "header",
+    "hgroup",
+    "hr",
+    "html",
+    "legend",
+    "main",
+    "menu",
+    "nav",
+    "ol",
+    "p",
+    "pre",
+    "section",
+    "ul",
+];
+
+const TABLE_ELEMENTS: [(&str, Display); 7] = [
+    ("table", Display::Table),
+    ("tr", Display::TableRow),
+    ("td", Display::TableCell),
+    ("th", Display::TableCell),
+    // 行のまとまりはblockとして扱う
+    ("thead", Display::Block),
+    ("tbody", Display::Block),
+    ("tfoot", Display::Block),
+];
+
+// displayとfont以外の既定の見た目
+const PRESENTATIONAL_CSS: &str = "
+    h1 { font-size: 2em; font-weight: bold; margin: 0.67em 0 }
+    h2 { font-size: 1.5em; font-weight: bold; margin: 0.83em 0 }
+    h3 { font-size: 1.17em; font-weight: bold; margin: 1em 0 }
+    h4 { font-weight: bold; margin: 1.33em 0 }
+    h5 { font-size: 0.83em; font-weight: bold; margin: 1.67em 0 }
+    h6 { font-size: 0.67em; font-weight: bold; margin: 2.33em 0 }
+    body { margin: 8px }
+    p, blockquote, dl, figure, pre, ul, ol { margin: 1em 0 }
+    blockquote, figure { margin-left: 40px; margin-right: 40px }
+    ul, ol { padding-left: 40px }
+    ul ul, ul ol, ol ul, ol ol { margin-top: 0; margin-bottom: 0 }
+    dd { margin-left: 40px }
+    b, strong, th { font-weight: bold }
+    i, em, cite, dfn, var, address { font-style: italic }
+    u, ins { text-decoration: underline }
+    s, strike, del { text-decoration: line-through }
+    small { font-size: 0.83em }
+    a[href] { color: #0000ee; text-decoration: underline }
+";
+
+// stylesheetがないときの要素のdisplay。知らない要素はinline
+pub fn default_display(name: &str) -> Display {
+    if HIDDEN_ELEMENTS.contains(&name) {
+        return Display::None;
+    }
+    if BLOCK_ELEMENTS.contains(&name) {
+        return Display::Block;
+    }
+    if name == "li" {
+        return Display::ListItem;
+    }
+    return match TABLE_ELEMENTS.iter().find(|(element, _)| *element == name) {
+        Some((_, display)) => *display,
+        None => Display::Inline,
+    };
+}
+
+// author stylesheetより前に置いて使う。originを区別しないので、同じ詳細度ならauthorが勝つ
+pub fn stylesheet() -> Stylesheet {
+    let mut css = format!(
+        "{} {{ display: none }}\n{} {{ display: block }}\nli {{ display: list-item }}\n",
+        HIDDEN_ELEMENTS.join(", "),
+        BLOCK_ELEMENTS.join(", "),
+    );
+    for (name, display) in TABLE_ELEMENTS {
+        let display = match display {
+            Display::Table => "table",
+            Display::TableRow => "table-row",
+            Display::TableCell => "table-cell",
+            _ => "block",
+        };
+        css += &format!("{} {{ display: {} }}\n", name, display);
+    }
+    css += PRESENTATIONAL_CSS;
+    return css::parse_stylesheet_recovering(&css);
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::css;
+    use crate::css::computed::{ComputedValue, Display};
+    use crate::css::ua;
+    use crate::html;
+
+    #[test]
+    fn user_agent_stylesheet() {
+        let sheet = ua::stylesheet();
+        assert!(sheet.errors.is_empty(), "{:?}", sheet.errors);
+
+        let doc = html::parse(
+            "<html><head><title>t</title></head><body>\
+             <h1>a</h1><ul><li><a href=\"/\">b</a></li></ul><table><tr><td>c</td></tr></table>\
+             <span class=\"x\">d</span></body></html>",
+        )
+        .unwrap();
+        let author =
+            css::parse_stylesheet("span.x { display: block } h1 { display: inline }").unwrap();
+        let styles = css::compute_styles(&doc, &[sheet, author]);
+        let display = |selector: &str| {
+            let node = doc.query_selector(selector).unwrap().unwrap();
+            let name = node.name().to_string();
+            let computed = match styles.get(&node, "display") {
+                Some(ComputedValue::Display(display)) => display,
+                other => panic!("{:?}", other),
+            };
+            return (computed, ua::default_display(&name));
+        };
+
+        // authorのほうが後なので勝つ
+        assert_eq!(display("h1"), (Display::Inline, Display::Block));
+        assert_eq!(display("span"), (Display::Block, Display::Inline));
+        for (selector, expected) in [
+            ("head", Display::None),
+            ("title", Display::None),
+            ("body", Display::Block),
+            ("ul", Display::Block),
+            ("li", Display::ListItem),
+            ("a", Display::Inline),
+            ("table", Display::Table),
+            ("tr", Display::TableRow),
+            ("td", Display::TableCell),
+        ] {
+            assert_eq!(display(selector), (expected, expected), "{}", selector);
+        }
+
+        let h1 = doc.query_selector("h1").unwrap().unwrap();
+        assert_eq!(
+            styles.get(&h1, "font-weight"),
+            Some(ComputedValue::FontWeight(700))
+        );
+    }
+}
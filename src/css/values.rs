@@ -0,0 +1,874 @@
+use crate::css::errors::{ColorParseError, CssTokenizeError, LengthParseError};
+use crate::css::parser::serialize_tokens;
+use crate::css::tokenizer::{CssToken, CssTokenKind, CssTokenizer};
+use std::fmt;
+
+// 名前のついた色。二分探索するので名前の順に並べておく
+static NAMED_COLORS: [(&str, u32); 148] = [
+    ("aliceblue", 0xf0f8ff),
+    ("antiquewhite", 0xfaebd7),
+    ("aqua", 0x00ffff),
+    ("aquamarine", 0x7fffd4),
+    ("azure", 0xf0ffff),
+    ("beige", 0xf5f5dc),
+    ("bisque", 0xffe4c4),
+    ("black", 0x000000),
+    ("blanchedalmond", 0xffebcd),
+    ("blue", 0x0000ff),
+    ("blueviolet", 0x8a2be2),
+    ("brown", 0xa52a2a),
+    ("burlywood", 0xdeb887),
+    ("cadetblue", 0x5f9ea0),
+    ("chartreuse", 0x7fff00),
+    ("chocolate", 0xd2691e),
+    ("coral", 0xff7f50),
+    ("cornflowerblue", 0x6495ed),
+    ("cornsilk", 0xfff8dc),
+    ("crimson", 0xdc143c),
+    ("cyan", 0x00ffff),
+    ("darkblue", 0x00008b),
+    ("darkcyan", 0x008b8b),
+    ("darkgoldenrod", 0xb8860b),
+    ("darkgray", 0xa9a9a9),
+    ("darkgreen", 0x006400),
+    ("darkgrey", 0xa9a9a9),
+    ("darkkhaki", 0xbdb76b),
+    ("darkmagenta", 0x8b008b),
+    ("darkolivegreen", 0x556b2f),
+    ("darkorange", 0xff8c00),
+    ("darkorchid", 0x9932cc),
+    ("darkred", 0x8b0000),
+    ("darksalmon", 0xe9967a),
+    ("darkseagreen", 0x8fbc8f),
+    ("darkslateblue", 0x483d8b),
+    ("darkslategray", 0x2f4f4f),
+    ("darkslategrey", 0x2f4f4f),
+    ("darkturquoise", 0x00ced1),
+    ("darkviolet", 0x9400d3),
+    ("deeppink", 0xff1493),
+    ("deepskyblue", 0x00bfff),
+    ("dimgray", 0x696969),
+    ("dimgrey", 0x696969),
+    ("dodgerblue", 0x1e90ff),
+    ("firebrick", 0xb22222),
+    ("floralwhite", 0xfffaf0),
+    ("forestgreen", 0x228b22),
+    ("fuchsia", 0xff00ff),
+    ("gainsboro", 0xdcdcdc),
+    ("ghostwhite", 0xf8f8ff),
+    ("gold", 0xffd700),
+    ("goldenrod", 0xdaa520),
+    ("gray", 0x808080),
+    ("green", 0x008000),
+    ("greenyellow", 0xadff2f),
+    ("grey", 0x808080),
+    ("honeydew", 0xf0fff0),
+    ("hotpink", 0xff69b4),
+    ("indianred", 0xcd5c5c),
+    ("indigo", 0x4b0082),
+    ("ivory", 0xfffff0),
+    ("khaki", 0xf0e68c),
+    ("lavender", 0xe6e6fa),
+    ("lavenderblush", 0xfff0f5),
+    ("lawngreen", 0x7cfc00),
+    ("lemonchiffon", 0xfffacd),
+    ("lightblue", 0xadd8e6),
+    ("lightcoral", 0xf08080),
+    ("lightcyan", 0xe0ffff),
+    ("lightgoldenrodyellow", 0xfafad2),
+    ("lightgray", 0xd3d3d3),
+    ("lightgreen", 0x90ee90),
+    ("lightgrey", 0xd3d3d3),
+    ("lightpink", 0xffb6c1),
+    ("lightsalmon", 0xffa07a),
+    ("lightseagreen", 0x20b2aa),
+    ("lightskyblue", 0x87cefa),
+    ("lightslategray", 0x778899),
+    ("lightslategrey", 0x778899),
+    ("lightsteelblue", 0xb0c4de),
+    ("lightyellow", 0xffffe0),
+    ("lime", 0x00ff00),
+    ("limegreen", 0x32cd32),
+    ("linen", 0xfaf0e6),
+    ("magenta", 0xff00ff),
+    ("maroon", 0x800000),
+    ("mediumaquamarine", 0x66cdaa),
+    ("mediumblue", 0x0000cd),
+    ("mediumorchid", 0xba55d3),
+    ("mediumpurple", 0x9370db),
+    ("mediumseagreen", 0x3cb371),
+    ("mediumslateblue", 0x7b68ee),
+    ("mediumspringgreen", 0x00fa9a),
+    ("mediumturquoise", 0x48d1cc),
+    ("mediumvioletred", 0xc71585),
+    ("midnightblue", 0x191970),
+    ("mintcream", 0xf5fffa),
+    ("mistyrose", 0xffe4e1),
+    ("moccasin", 0xffe4b5),
+    ("navajowhite", 0xffdead),
+    ("navy", 0x000080),
+    ("oldlace", 0xfdf5e6),
+    ("olive", 0x808000),
+    ("olivedrab", 0x6b8e23),
+    ("orange", 0xffa500),
+    ("orangered", 0xff4500),
+    ("orchid", 0xda70d6),
+    ("palegoldenrod", 0xeee8aa),
+    ("palegreen", 0x98fb98),
+    ("paleturquoise", 0xafeeee),
+    ("palevioletred", 0xdb7093),
+    ("papayawhip", 0xffefd5),
+    ("peachpuff", 0xffdab9),
+    ("peru", 0xcd853f),
+    ("pink", 0xffc0cb),
+    ("plum", 0xdda0dd),
+    ("powderblue", 0xb0e0e6),
+    ("purple", 0x800080),
+    ("rebeccapurple", 0x663399),
+    ("red", 0xff0000),
+    ("rosybrown", 0xbc8f8f),
+    ("royalblue", 0x4169e1),
+    ("saddlebrown", 0x8b4513),
+    ("salmon", 0xfa8072),
+    ("sandybrown", 0xf4a460),
+    ("seagreen", 0x2e8b57),
+    ("seashell", 0xfff5ee),
+    ("sienna", 0xa0522d),
+    ("silver", 0xc0c0c0),
+    ("skyblue", 0x87ceeb),
+    ("slateblue", 0x6a5acd),
+    ("slategray", 0x708090),
+    ("slategrey", 0x708090),
+    ("snow", 0xfffafa),
+    ("springgreen", 0x00ff7f),
+    ("steelblue", 0x4682b4),
+    ("tan", 0xd2b48c),
+    ("teal", 0x008080),
+    ("thistle", 0xd8bfd8),
+    ("tomato", 0xff6347),
+    ("turquoise", 0x40e0d0),
+    ("violet", 0xee82ee),
+    ("wheat", 0xf5deb3),
+    ("white", 0xffffff),
+    ("whitesmoke", 0xf5f5f5),
+    ("yellow", 0xffff00),
+    ("yellowgreen", 0x9acd32),
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Color {
+    Rgba { r: u8, g: u8, b: u8, a: u8 },
+    // 使う側でcolor propertyの値に置き換える
+    CurrentColor,
+}
+
+// 値の文字列をtokenにする。commentは除き、前後の空白とEofは落とす
+pub(crate) fn value_tokens(value: &str) -> Result<Vec<CssToken>, CssTokenizeError> {
+    let mut tokens: Vec<CssToken> = CssTokenizer::new(value)
+        .tokenize()?
+        .into_iter()
+        .filter(|tok| !matches!(tok.kind, CssTokenKind::Comment(_) | CssTokenKind::Eof))
+        .collect();
+    while tokens
+        .last()
+        .is_some_and(|tok| tok.kind == CssTokenKind::Whitespace)
+    {
+        tokens.pop();
+    }
+    let leading = tokens
+        .iter()
+        .take_while(|tok| tok.kind == CssTokenKind::Whitespace)
+        .count();
+    tokens.drain(..leading);
+    return Ok(tokens);
+}
+
+fn clamp_u8(value: f64) -> u8 {
+    return value.round().clamp(0.0, 255.0) as u8;
+}
+
+impl Color {
+    pub const TRANSPARENT: Color = Color::Rgba {
+        r: 0,
+        g: 0,
+        b: 0,
+        a: 0,
+    };
+
+    pub fn rgb(r: u8, g: u8, b: u8) -> Color {
+        return Color::Rgba { r, g, b, a: 255 };
+    }
+
+    pub fn rgba(r: u8, g: u8, b: u8, a: u8) -> Color {
+        return Color::Rgba { r, g, b, a };
+    }
+
+    pub fn parse(value: &str) -> Result<Color, ColorParseError> {
+        let tokens = value_tokens(value)?;
+        return Color::from_tokens(&tokens);
+    }
+
+    pub(crate) fn from_tokens(tokens: &[CssToken]) -> Result<Color, ColorParseError> {
+        let first = match tokens.first() {
+            Some(tok) => &tok.kind,
+            None => return Err(ColorParseError::Empty),
+        };
+        if let CssTokenKind::Function(name) = first {
+            // 関数は最後の ) までで終わっていなければならない
+            let name = name.to_ascii_lowercase();
+            let args = match tokens.split_last() {
+                Some((last, _)) if last.kind == CssTokenKind::CloseParen && tokens.len() >= 2 => {
+                    &tokens[1..tokens.len() - 1]
+                }
+                _ => return Err(ColorParseError::InvalidArguments(name)),
+            };
+            return match name.as_str() {
+                "rgb" | "rgba" => parse_rgb(&name, args),
+                "hsl" | "hsla" => parse_hsl(&name, args),
+                _ => Err(ColorParseError::UnknownFunction(name)),
+            };
+        }
+        if tokens.len() > 1 {
+            return Err(ColorParseError::UnexpectedToken(tokens[1].kind.to_string()));
+        }
+
+        return match first {
+            CssTokenKind::Hash { value, .. } => parse_hex(value),
+            CssTokenKind::Ident(name) => {
+                if name.eq_ignore_ascii_case("transparent") {
+                    return Ok(Color::TRANSPARENT);
+                }
+                if name.eq_ignore_ascii_case("currentcolor") {
+                    return Ok(Color::CurrentColor);
+                }
+                let found = NAMED_COLORS.binary_search_by(|(table, _)| {
+                    let lhs = table.bytes();
+                    let rhs = name.bytes().map(|b| b.to_ascii_lowercase());
+                    lhs.cmp(rhs)
+                });
+                match found {
+                    Ok(i) => {
+                        let rgb = NAMED_COLORS[i].1;
+                        Ok(Color::rgb((rgb >> 16) as u8, (rgb >> 8) as u8, rgb as u8))
+                    }
+                    Err(_) => Err(ColorParseError::UnknownName(name.clone())),
+                }
+            }
+            kind => Err(ColorParseError::UnexpectedToken(kind.to_string())),
+        };
+    }
+}
+
+// #rgb, #rgba, #rrggbb, #rrggbbaa
+fn parse_hex(hex: &str) -> Result<Color, ColorParseError> {
+    let invalid = || ColorParseError::InvalidHex(hex.to_string());
+    if !hex.bytes().all(|b| b.is_ascii_hexdigit()) {
+        return Err(invalid());
+    }
+    let digit = |i: usize| u8::from_str_radix(&hex[i..i + 1], 16).unwrap_or(0);
+    let pair = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).unwrap_or(0);
+    return match hex.len() {
+        3 | 4 => {
+            let alpha = if hex.len() == 4 { digit(3) * 17 } else { 255 };
+            Ok(Color::rgba(
+                digit(0) * 17,
+                digit(1) * 17,
+                digit(2) * 17,
+                alpha,
+            ))
+        }
+        6 | 8 => {
+            let alpha = if hex.len() == 8 { pair(6) } else { 255 };
+            Ok(Color::rgba(pair(0), pair(2), pair(4), alpha))
+        }
+        _ => Err(invalid()),
+    };
+}
+
+// 関数の引数を、色の成分と透明度に分ける
+// 古い書き方 rgb(1, 2, 3, 0.5) と、新しい書き方 rgb(1 2 3 / 0.5) のどちらか
+fn split_args<'t>(
+    name: &str,
+    args: &'t [CssToken],
+) -> Result<(Vec<&'t CssTokenKind>, Option<&'t CssTokenKind>, bool), ColorParseError> {
+    let invalid = || ColorParseError::InvalidArguments(name.to_string());
+    let kinds: Vec<&CssTokenKind> = args
+        .iter()
+        .map(|tok| &tok.kind)
+        .filter(|kind| **kind != CssTokenKind::Whitespace)
+        .collect();
+    let legacy = kinds.contains(&&CssTokenKind::Comma);
+
+    let mut values = Vec::new();
+    let mut alpha = None;
+    if legacy {
+        for (i, kind) in kinds.iter().enumerate() {
+            if (i % 2 == 1) != (**kind == CssTokenKind::Comma) {
+                return Err(invalid());
+            }
+            if i % 2 == 0 {
+                values.push(*kind);
+            }
+        }
+        if kinds.len().is_multiple_of(2) {
+            return Err(invalid());
+        }
+        if values.len() == 4 {
+            alpha = values.pop();
+        }
+    } else {
+        let slash = kinds
+            .iter()
+            .position(|kind| **kind == CssTokenKind::Delim('/'));
+        match slash {
+            Some(i) if i + 2 == kinds.len() => {
+                alpha = Some(kinds[i + 1]);
+                values.extend(&kinds[..i]);
+            }
+            Some(_) => return Err(invalid()),
+            None => values.extend(&kinds),
+        }
+    }
+    if values.len() != 3 {
+        return Err(invalid());
+    }
+    return Ok((values, alpha, legacy));
+}
+
+// 0..1 の透明度。範囲の外は丸める
+fn parse_alpha(name: &str, alpha: Option<&CssTokenKind>) -> Result<u8, ColorParseError> {
+    return match alpha {
+        None => Ok(255),
+        Some(CssTokenKind::Number { value, .. }) => Ok(clamp_u8(value.clamp(0.0, 1.0) * 255.0)),
+        Some(CssTokenKind::Percentage(value)) => {
+            Ok(clamp_u8(value.clamp(0.0, 100.0) / 100.0 * 255.0))
+        }
+        Some(_) => Err(ColorParseError::InvalidArguments(name.to_string())),
+    };
+}
+
+fn parse_rgb(name: &str, args: &[CssToken]) -> Result<Color, ColorParseError> {
+    let invalid = || ColorParseError::InvalidArguments(name.to_string());
+    // 数と%は、古い書き方でも混ぜてよい。%は 100% を255として丸める
+    let (values, alpha, _) = split_args(name, args)?;
+
+    let mut rgb = [0u8; 3];
+    for (i, kind) in values.iter().enumerate() {
+        rgb[i] = match kind {
+            CssTokenKind::Number { value, .. } => clamp_u8(*value),
+            CssTokenKind::Percentage(value) => clamp_u8(value / 100.0 * 255.0),
+            _ => return Err(invalid()),
+        };
+    }
+    let a = parse_alpha(name, alpha)?;
+    return Ok(Color::rgba(rgb[0], rgb[1], rgb[2], a));
+}
+
+fn parse_hsl(name: &str, args: &[CssToken]) -> Result<Color, ColorParseError> {
+    let invalid = || ColorParseError::InvalidArguments(name.to_string());
+    let (values, alpha, legacy) = split_args(name, args)?;
+
+    let degrees = match values[0] {
+        CssTokenKind::Number { value, .. } => *value,
+        CssTokenKind::Dimension { value, unit, .. } => match unit.to_ascii_lowercase().as_str() {
+            "deg" => *value,
+            "grad" => value * 360.0 / 400.0,
+            "rad" => value.to_degrees(),
+            "turn" => value * 360.0,
+            _ => return Err(invalid()),
+        },
+        _ => return Err(invalid()),
+    };
+    // 新しい書き方では%を省いた数も書ける
+    let percent = |kind: &CssTokenKind| match kind {
+        CssTokenKind::Percentage(value) => Some(value.clamp(0.0, 100.0) / 100.0),
+        CssTokenKind::Number { value, .. } if !legacy => Some(value.clamp(0.0, 100.0) / 100.0),
+        _ => None,
+    };
+    let (s, l) = match (percent(values[1]), percent(values[2])) {
+        (Some(s), Some(l)) => (s, l),
+        _ => return Err(invalid()),
+    };
+
+    let h = degrees.rem_euclid(360.0) / 360.0;
+    let (r, g, b) = hsl_to_rgb(h, s, l);
+    let a = parse_alpha(name, alpha)?;
+    return Ok(Color::rgba(
+        clamp_u8(r * 255.0),
+        clamp_u8(g * 255.0),
+        clamp_u8(b * 255.0),
+        a,
+    ));
+}
+
+// CSS Color 4 の hslToRgb。どれも 0..1
+fn hsl_to_rgb(h: f64, s: f64, l: f64) -> (f64, f64, f64) {
+    if s == 0.0 {
+        return (l, l, l);
+    }
+    let q = if l < 0.5 {
+        l * (1.0 + s)
+    } else {
+        l + s - l * s
+    };
+    let p = 2.0 * l - q;
+    let hue = |t: f64| {
+        let t = t.rem_euclid(1.0);
+        if t < 1.0 / 6.0 {
+            return p + (q - p) * 6.0 * t;
+        }
+        if t < 0.5 {
+            return q;
+        }
+        if t < 2.0 / 3.0 {
+            return p + (q - p) * (2.0 / 3.0 - t) * 6.0;
+        }
+        return p;
+    };
+    return (hue(h + 1.0 / 3.0), hue(h), hue(h - 1.0 / 3.0));
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum LengthUnit {
+    Px,
+    Em,
+    Rem,
+    Ex,
+    Ch,
+    Vw,
+    Vh,
+    Vmin,
+    Vmax,
+    Cm,
+    Mm,
+    Q,
+    In,
+    Pt,
+    Pc,
+    // 何に対する割合かはpropertyによる
+    Percent,
+}
+
+static LENGTH_UNITS: [(&str, LengthUnit); 15] = [
+    ("px", LengthUnit::Px),
+    ("em", LengthUnit::Em),
+    ("rem", LengthUnit::Rem),
+    ("ex", LengthUnit::Ex),
+    ("ch", LengthUnit::Ch),
+    ("vw", LengthUnit::Vw),
+    ("vh", LengthUnit::Vh),
+    ("vmin", LengthUnit::Vmin),
+    ("vmax", LengthUnit::Vmax),
+    ("cm", LengthUnit::Cm),
+    ("mm", LengthUnit::Mm),
+    ("q", LengthUnit::Q),
+    ("in", LengthUnit::In),
+    ("pt", LengthUnit::Pt),
+    ("pc", LengthUnit::Pc),
+];
+
+impl LengthUnit {
+    pub fn from_unit(unit: &str) -> Option<LengthUnit> {
+        return LENGTH_UNITS
+            .iter()
+            .find(|(name, _)| unit.eq_ignore_ascii_case(name))
+            .map(|(_, unit)| *unit);
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        if *self == LengthUnit::Percent {
+            return "%";
+        }
+        return match LENGTH_UNITS.iter().find(|(_, unit)| unit == self) {
+            Some((name, _)) => name,
+            None => "",
+        };
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Length {
+    pub value: f64,
+    pub unit: LengthUnit,
+}
+
+// 相対的な長さをpxにするときの基準
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LengthContext {
+    pub font_size: f64,
+    pub root_font_size: f64,
+    pub viewport_width: f64,
+    pub viewport_height: f64,
+}
+
+impl Length {
+    pub fn new(value: f64, unit: LengthUnit) -> Length {
+        return Length { value, unit };
+    }
+
+    pub fn px(value: f64) -> Length {
+        return Length::new(value, LengthUnit::Px);
+    }
+
+    pub fn is_zero(&self) -> bool {
+        return self.value == 0.0;
+    }
+
+    // 12px, 1.5em, 100%, 0
+    pub fn parse(value: &str) -> Result<Length, LengthParseError> {
+        let tokens = value_tokens(value)?;
+        return match tokens.as_slice() {
+            [] => Err(LengthParseError::Empty),
+            [tok] => Length::from_token(&tok.kind),
+            [_, extra, ..] => Err(LengthParseError::UnexpectedToken(extra.kind.to_string())),
+        };
+    }
+
+    pub(crate) fn from_token(kind: &CssTokenKind) -> Result<Length, LengthParseError> {
+        return match kind {
+            CssTokenKind::Dimension { value, unit, .. } => match LengthUnit::from_unit(unit) {
+                Some(unit) => Ok(Length::new(*value, unit)),
+                None => Err(LengthParseError::UnknownUnit(unit.clone())),
+            },
+            CssTokenKind::Percentage(value) => Ok(Length::new(*value, LengthUnit::Percent)),
+            // 単位を省けるのは0だけ
+            CssTokenKind::Number { value, .. } if *value == 0.0 => Ok(Length::px(0.0)),
+            CssTokenKind::Number { value, .. } => Err(LengthParseError::MissingUnit(*value)),
+            kind => Err(LengthParseError::UnexpectedToken(kind.to_string())),
+        };
+    }
+
+    // %は何に対するかがpropertyによるのでNone
+    pub fn to_px(&self, cx: &LengthContext) -> Option<f64> {
+        let value = self.value;
+        let px = match self.unit {
+            LengthUnit::Px => value,
+            LengthUnit::Em => value * cx.font_size,
+            LengthUnit::Rem => value * cx.root_font_size,
+            // フォントの寸法はまだ分からないので、よくある比率で近似する
+            LengthUnit::Ex | LengthUnit::Ch => value * cx.font_size / 2.0,
+            LengthUnit::Vw => value * cx.viewport_width / 100.0,
+            LengthUnit::Vh => value * cx.viewport_height / 100.0,
+            LengthUnit::Vmin => value * cx.viewport_width.min(cx.viewport_height) / 100.0,
+            LengthUnit::Vmax => value * cx.viewport_width.max(cx.viewport_height) / 100.0,
+            LengthUnit::In => value * 96.0,
+            LengthUnit::Cm => value * 96.0 / 2.54,
+            LengthUnit::Mm => value * 96.0 / 25.4,
+            LengthUnit::Q => value * 96.0 / 101.6,
+            LengthUnit::Pt => value * 96.0 / 72.0,
+            LengthUnit::Pc => value * 16.0,
+            LengthUnit::Percent => return None,
+        };
+        return Some(px);
+    }
+}
+
+impl fmt::Display for Length {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_zero() && self.unit != LengthUnit::Percent {
+            return write!(f, "0");
+        }
+        return write!(f, "{}{}", self.value, self.unit.as_str());
+    }
+}
+
+// 空白やカンマで区切った値の一つ
+#[derive(Debug, Clone, PartialEq)]
+pub enum Component {
+    Length(Length),
+    Number(f64),
+    Color(Color),
+    // 小文字にしてある
+    Keyword(String),
+    String(String),
+    Url(String),
+    Comma,
+    // font: 12px/1.4 の /
+    Slash,
+    // 他の単位や関数など。CSSの文字列のまま持つ
+    Other(String),
+}
+
+impl Component {
+    // 単位のない0も長さとして読む
+    pub fn as_length(&self) -> Option<Length> {
+        return match self {
+            Component::Length(length) => Some(*length),
+            Component::Number(value) if *value == 0.0 => Some(Length::px(0.0)),
+            _ => None,
+        };
+    }
+
+    pub fn is_keyword(&self, keyword: &str) -> bool {
+        return matches!(self, Component::Keyword(k) if k == keyword);
+    }
+}
+
+// 0 auto 2px -> [Number(0), Keyword(auto), Length(2px)]
+pub fn parse_component_list(value: &str) -> Result<Vec<Component>, CssTokenizeError> {
+    let tokens = value_tokens(value)?;
+    let mut components = Vec::new();
+    let mut at = 0;
+    while at < tokens.len() {
+        let start = at;
+        at += 1;
+        let kind = &tokens[start].kind;
+        let component = match kind {
+            CssTokenKind::Whitespace => continue,
+            CssTokenKind::Comma => Component::Comma,
+            CssTokenKind::Delim('/') => Component::Slash,
+            CssTokenKind::Number { value, .. } => Component::Number(*value),
+            CssTokenKind::Dimension { .. } | CssTokenKind::Percentage(_) => {
+                match Length::from_token(kind) {
+                    Ok(length) => Component::Length(length),
+                    Err(_) => Component::Other(kind.to_string()),
+                }
+            }
+            CssTokenKind::String(s) => Component::String(s.clone()),
+            CssTokenKind::Url(url) => Component::Url(url.clone()),
+            CssTokenKind::Ident(_) | CssTokenKind::Hash { .. } => {
+                match Color::from_tokens(&tokens[start..at]) {
+                    Ok(color) => Component::Color(color),
+                    Err(_) => match kind {
+                        CssTokenKind::Ident(name) => Component::Keyword(name.to_ascii_lowercase()),
+                        kind => Component::Other(kind.to_string()),
+                    },
+                }
+            }
+            CssTokenKind::Function(_) => {
+                // 対応する ) までを一つにする
+                let mut depth = 1;
+                while at < tokens.len() && depth > 0 {
+                    match tokens[at].kind {
+                        CssTokenKind::Function(_) | CssTokenKind::OpenParen => depth += 1,
+                        CssTokenKind::CloseParen => depth -= 1,
+                        _ => {}
+                    }
+                    at += 1;
+                }
+                match Color::from_tokens(&tokens[start..at]) {
+                    Ok(color) => Component::Color(color),
+                    Err(_) => Component::Other(serialize_tokens(&tokens[start..at])),
+                }
+            }
+            kind => Component::Other(kind.to_string()),
+        };
+        components.push(component);
+    }
+    return Ok(components);
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::css::errors::{ColorParseError, LengthParseError};
+    use crate::css::values::{
+        parse_component_list, Color, Component, Length, LengthUnit, NAMED_COLORS,
+    };
+
+    #[test]
+    fn named_colors_are_sorted() {
+        for pair in NAMED_COLORS.windows(2) {
+            assert!(pair[0].0 < pair[1].0, "{:?}", pair);
+        }
+    }
+
+    #[test]
+    fn parse_colors() {
+        let table = [
+            ("#f00", Color::rgb(255, 0, 0)),
+            ("#F0A8", Color::rgba(255, 0, 170, 136)),
+            ("#1e90ff", Color::rgb(30, 144, 255)),
+            ("#1E90FF80", Color::rgba(30, 144, 255, 128)),
+            ("  #000  ", Color::rgb(0, 0, 0)),
+            ("red", Color::rgb(255, 0, 0)),
+            ("RebeccaPurple", Color::rgb(102, 51, 153)),
+            ("lightgoldenrodyellow", Color::rgb(250, 250, 210)),
+            ("grey", Color::rgb(128, 128, 128)),
+            ("transparent", Color::TRANSPARENT),
+            ("currentColor", Color::CurrentColor),
+            ("rgb(255, 128, 0)", Color::rgb(255, 128, 0)),
+            ("rgb(255 128 0)", Color::rgb(255, 128, 0)),
+            ("RGB( 1 ,2, 3 )", Color::rgb(1, 2, 3)),
+            ("rgba(255, 0, 0, 0.5)", Color::rgba(255, 0, 0, 128)),
+            ("rgb(255 0 0 / 50%)", Color::rgba(255, 0, 0, 128)),
+            ("rgba(0 0 0 / .25)", Color::rgba(0, 0, 0, 64)),
+            ("rgb(100%, 50%, 0%)", Color::rgb(255, 128, 0)),
+            ("rgb(100% 0 50)", Color::rgb(255, 0, 50)),
+            ("rgb(10.6, 0, 0)", Color::rgb(11, 0, 0)),
+            ("rgb(/* c */ 1 2 3)", Color::rgb(1, 2, 3)),
+            // 範囲の外は丸める
+            ("rgb(300, -20, 0)", Color::rgb(255, 0, 0)),
+            ("rgb(150%, 0%, -5%)", Color::rgb(255, 0, 0)),
+            ("rgb(50%, 0, 0)", Color::rgb(128, 0, 0)),
+            ("rgb(100%, 0, 0)", Color::rgb(255, 0, 0)),
+            ("rgba(10%, 128, 33.3%, 0.5)", Color::rgba(26, 128, 85, 128)),
+            ("rgb(120%, -10, 300)", Color::rgb(255, 0, 255)),
+            ("rgb(-50% 256 0.4%)", Color::rgb(0, 255, 1)),
+            ("rgba(0, 0, 0, 2)", Color::rgb(0, 0, 0)),
+            ("rgba(0, 0, 0, -1)", Color::TRANSPARENT),
+            ("hsl(0, 100%, 50%)", Color::rgb(255, 0, 0)),
+            ("hsl(120 100% 25%)", Color::rgb(0, 128, 0)),
+            ("hsl(240deg, 100%, 50%)", Color::rgb(0, 0, 255)),
+            ("hsl(0.5turn 100% 50%)", Color::rgb(0, 255, 255)),
+            ("hsl(-120, 100%, 50%)", Color::rgb(0, 0, 255)),
+            ("hsl(480, 100%, 50%)", Color::rgb(0, 255, 0)),
+            ("hsl(0, 0%, 50%)", Color::rgb(128, 128, 128)),
+            ("hsla(0, 100%, 50%, 0.2)", Color::rgba(255, 0, 0, 51)),
+            ("hsl(210 100 40 / 100%)", Color::rgb(0, 102, 204)),
+            ("hsl(0, 150%, 120%)", Color::rgb(255, 255, 255)),
+        ];
+        for (input, expected) in table {
+            assert_eq!(Color::parse(input), Ok(expected), "input: {:?}", input);
+        }
+    }
+
+    #[test]
+    fn reject_malformed_colors() {
+        let table = [
+            "",
+            "   ",
+            "#",
+            "#ff",
+            "#12345",
+            "#1234567",
+            "#ggg",
+            "blurple",
+            "red blue",
+            "rgb(1, 2)",
+            "rgb(1, 2, 3, 4, 5)",
+            "rgb(1 2 3 4)",
+            "rgb(1, 2 3)",
+            "rgb(1,, 2, 3)",
+            "rgb(1, 2, 3,)",
+            "rgb(1 2 / 3)",
+            "rgb(1 2 3 /)",
+            "rgb(1px, 2, 3)",
+            "rgb(1, 2, 3",
+            "rgb(1, 2, 3) x",
+            "rgb(\"1\", 2, 3)",
+            "hsl(0, 100, 50)",
+            "hsl(0px, 100%, 50%)",
+            "hsl(0%, 100%, 50%)",
+            "cmyk(0, 0, 0, 0)",
+            "12px",
+            "\"red\"",
+        ];
+        for input in table {
+            assert!(Color::parse(input).is_err(), "input: {:?}", input);
+        }
+
+        assert_eq!(Color::parse(""), Err(ColorParseError::Empty));
+        assert_eq!(
+            Color::parse("blurple"),
+            Err(ColorParseError::UnknownName("blurple".to_string()))
+        );
+        assert_eq!(
+            Color::parse("#12345"),
+            Err(ColorParseError::InvalidHex("12345".to_string()))
+        );
+        assert_eq!(
+            Color::parse("lab(1 2 3)"),
+            Err(ColorParseError::UnknownFunction("lab".to_string()))
+        );
+    }
+
+    #[test]
+    fn parse_lengths() {
+        let table = [
+            ("12px", Length::new(12.0, LengthUnit::Px)),
+            ("1.5em", Length::new(1.5, LengthUnit::Em)),
+            (".5em", Length::new(0.5, LengthUnit::Em)),
+            ("-4px", Length::new(-4.0, LengthUnit::Px)),
+            ("+3PX", Length::new(3.0, LengthUnit::Px)),
+            ("-.25rem", Length::new(-0.25, LengthUnit::Rem)),
+            ("100%", Length::new(100.0, LengthUnit::Percent)),
+            ("0", Length::px(0.0)),
+            ("-0", Length::px(0.0)),
+            ("0.0", Length::px(0.0)),
+            ("2rem", Length::new(2.0, LengthUnit::Rem)),
+            ("3vh", Length::new(3.0, LengthUnit::Vh)),
+            ("50vmin", Length::new(50.0, LengthUnit::Vmin)),
+            ("1e2px", Length::new(100.0, LengthUnit::Px)),
+            ("10Q", Length::new(10.0, LengthUnit::Q)),
+            ("  12pt\t", Length::new(12.0, LengthUnit::Pt)),
+            ("/* x */ 2in", Length::new(2.0, LengthUnit::In)),
+        ];
+        for (input, expected) in table {
+            assert_eq!(Length::parse(input), Ok(expected), "input: {:?}", input);
+        }
+
+        assert_eq!(Length::parse(""), Err(LengthParseError::Empty));
+        assert_eq!(Length::parse(" "), Err(LengthParseError::Empty));
+        assert_eq!(
+            Length::parse("12furlongs"),
+            Err(LengthParseError::UnknownUnit("furlongs".to_string()))
+        );
+        assert_eq!(
+            Length::parse("12"),
+            Err(LengthParseError::MissingUnit(12.0))
+        );
+        assert!(matches!(
+            Length::parse("12 px"),
+            Err(LengthParseError::UnexpectedToken(_))
+        ));
+        assert!(matches!(
+            Length::parse("auto"),
+            Err(LengthParseError::UnexpectedToken(_))
+        ));
+        assert!(Length::parse("- 4px").is_err());
+        assert!(Length::parse("4px;").is_err());
+
+        assert_eq!(Length::parse("-4px").unwrap().to_string(), "-4px");
+        assert_eq!(Length::parse("50%").unwrap().to_string(), "50%");
+        assert_eq!(Length::parse("0em").unwrap().to_string(), "0");
+    }
+
+    #[test]
+    fn parse_components() {
+        assert_eq!(
+            parse_component_list("0 auto 2px").unwrap(),
+            vec![
+                Component::Number(0.0),
+                Component::Keyword("auto".to_string()),
+                Component::Length(Length::px(2.0)),
+            ]
+        );
+        assert_eq!(
+            parse_component_list("  1px  SOLID\n rgb(0, 0, 255) ").unwrap(),
+            vec![
+                Component::Length(Length::px(1.0)),
+                Component::Keyword("solid".to_string()),
+                Component::Color(Color::rgb(0, 0, 255)),
+            ]
+        );
+        assert_eq!(
+            parse_component_list("italic bold 12px/1.4 \"Helvetica Neue\", serif").unwrap(),
+            vec![
+                Component::Keyword("italic".to_string()),
+                Component::Keyword("bold".to_string()),
+                Component::Length(Length::px(12.0)),
+                Component::Slash,
+                Component::Number(1.4),
+                Component::String("Helvetica Neue".to_string()),
+                Component::Comma,
+                Component::Keyword("serif".to_string()),
+            ]
+        );
+        assert_eq!(
+            parse_component_list("url(a.png) #fff no-repeat calc(1px + 2%) 2s").unwrap(),
+            vec![
+                Component::Url("a.png".to_string()),
+                Component::Color(Color::rgb(255, 255, 255)),
+                Component::Keyword("no-repeat".to_string()),
+                Component::Other("calc(1px + 2%)".to_string()),
+                Component::Other("2s".to_string()),
+            ]
+        );
+        assert_eq!(parse_component_list("").unwrap(), vec![]);
+        assert_eq!(
+            parse_component_list("0").unwrap()[0].as_length(),
+            Some(Length::px(0.0))
+        );
+        assert!(parse_component_list("\"open").is_err());
+    }
+}
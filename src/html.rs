@@ -1,4 +1,602 @@
-mod errors;
-mod parser;
-mod position;
-mod tokenizer;
+// parser featureがなければtokenizerとそれが使う型だけになる
+// std featureがなければ (no_std + alloc)、tokenizerとparserだけになる
+#[cfg(feature = "parser")]
+pub mod arena;
+pub mod atom;
+#[cfg(feature = "parser")]
+pub mod attributes;
+#[cfg(feature = "parser")]
+pub mod compare;
+#[cfg(all(feature = "parser", feature = "std"))]
+pub mod diff;
+#[cfg(all(feature = "parser", feature = "std"))]
+pub mod document;
+#[cfg(all(feature = "parser", feature = "std"))]
+pub mod encoding;
+#[cfg(feature = "parser")]
+pub mod entity;
+#[cfg(feature = "parser")]
+pub mod errors;
+#[cfg(all(feature = "parser", feature = "std"))]
+pub mod fetch;
+#[cfg(all(feature = "parser", feature = "std"))]
+pub mod file;
+#[cfg(feature = "parser")]
+pub mod footprint;
+#[cfg(all(feature = "parser", feature = "std"))]
+pub mod form;
+#[cfg(all(feature = "parser", feature = "std"))]
+pub mod incremental;
+#[cfg(all(feature = "parser", feature = "std"))]
+pub mod loader;
+#[cfg(feature = "parser")]
+pub mod lsp;
+#[cfg(all(feature = "parser", feature = "std"))]
+pub mod meta;
+#[cfg(all(feature = "parser", feature = "std"))]
+pub mod normalize;
+#[cfg(feature = "parser")]
+pub mod parser;
+#[cfg(all(feature = "parser", feature = "std"))]
+pub mod path;
+pub mod position;
+#[cfg(all(feature = "parser", feature = "std"))]
+pub mod script;
+#[cfg(all(feature = "parser", feature = "std"))]
+pub mod serializer;
+pub mod shared_str;
+#[cfg(all(test, feature = "parser", feature = "std"))]
+mod snapshot;
+#[cfg(all(feature = "parser", feature = "std"))]
+pub mod stats;
+pub mod tokenizer;
+mod trace;
+#[cfg(all(feature = "parser", feature = "std"))]
+pub mod url;
+
+#[cfg(all(feature = "parser", feature = "std"))]
+use crate::html::diff::Change;
+#[cfg(all(feature = "parser", feature = "std"))]
+use crate::html::document::Document;
+#[cfg(all(feature = "parser", feature = "std"))]
+use crate::html::encoding::{Decoded, Encoding};
+#[cfg(feature = "parser")]
+use crate::html::errors::ParseError;
+#[cfg(all(feature = "parser", feature = "std"))]
+use crate::html::errors::{BytesParseError, FetchParseError, FileParseError, OptionsError};
+#[cfg(all(feature = "parser", feature = "std"))]
+use crate::html::fetch::FetchOptions;
+#[cfg(all(feature = "parser", feature = "std"))]
+use crate::html::file::FileOptions;
+#[cfg(all(feature = "parser", feature = "std"))]
+use crate::html::parser::ParserOptions;
+#[cfg(feature = "parser")]
+use crate::html::parser::{Node, Parser, StreamStats, StreamTarget};
+#[cfg(all(feature = "parser", feature = "std"))]
+use crate::html::position::Position;
+#[cfg(feature = "parser")]
+use crate::html::tokenizer::Tokenizer;
+#[cfg(all(feature = "parser", feature = "std"))]
+use crate::net::Transport;
+#[cfg(feature = "parser")]
+use core::ops::ControlFlow;
+#[cfg(all(feature = "parser", feature = "std"))]
+use std::fs;
+#[cfg(all(feature = "parser", feature = "std"))]
+use std::path::Path;
+#[cfg(all(feature = "parser", feature = "parallel"))]
+use std::sync::atomic::{AtomicUsize, Ordering};
+#[cfg(all(feature = "parser", feature = "std"))]
+use std::sync::mpsc;
+#[cfg(all(feature = "parser", feature = "std"))]
+use std::sync::Arc;
+#[cfg(all(feature = "parser", feature = "std"))]
+use std::thread;
+
+// これより小さい文書はthreadを立てるほうが高くつく
+#[cfg(all(feature = "parser", feature = "std"))]
+const PIPELINE_MIN_BYTES: usize = 64 * 1024;
+#[cfg(all(feature = "parser", feature = "std"))]
+const PIPELINE_BATCH_LEN: usize = 4096;
+// tokenizerが先に進みすぎないように、送ったまま読まれていない列はこれだけにする
+#[cfg(all(feature = "parser", feature = "std"))]
+const PIPELINE_QUEUE_LEN: usize = 4;
+
+#[cfg(all(feature = "parser", feature = "std"))]
+pub fn parse(html: &str) -> Result<Document, ParseError> {
+    let mut tokenizer = Tokenizer::new(html);
+    let mut parser = Parser::new();
+    let result = parser.parse(tokenizer.tokenize());
+    return to_document(&parser, result);
+}
+
+// 2つの木の違い。同じページを2回読んで、何が変わったか見るときに使う
+#[cfg(all(feature = "parser", feature = "std"))]
+pub fn diff<'a>(old: &'a Node, new: &'a Node) -> Vec<Change<'a>> {
+    return diff::diff_nodes(old, new);
+}
+
+// optionsに合わせて読む。collect_metricsならDocument::metricsに数字が残る
+#[cfg(all(feature = "parser", feature = "std"))]
+pub fn parse_with_options(html: &str, options: &ParserOptions) -> Result<Document, ParseError> {
+    let mut parser = Parser::builder().options(options.clone()).build()?;
+    let result = parser.parse_str(html);
+    return to_document(&parser, result);
+}
+
+// tokenizerを別のthreadで動かし、できたtoken列から順にparseする
+// 結果はparseと同じ
+#[cfg(all(feature = "parser", feature = "std"))]
+pub fn parse_pipelined(html: &str) -> Result<Document, ParseError> {
+    // coreが1つなら重ならないので、threadを渡す分だけ遅くなる
+    let cores = match thread::available_parallelism() {
+        Ok(n) => n.get(),
+        Err(_) => 1,
+    };
+    if html.len() < PIPELINE_MIN_BYTES || cores < 2 {
+        return parse(html);
+    }
+    return parse_pipelined_with(html, PIPELINE_BATCH_LEN);
+}
+
+#[cfg(all(feature = "parser", feature = "std"))]
+fn parse_pipelined_with(html: &str, batch_len: usize) -> Result<Document, ParseError> {
+    let (tx, rx) = mpsc::sync_channel(PIPELINE_QUEUE_LEN);
+    let target: Arc<str> = Arc::from(html);
+    let handle = thread::spawn(move || {
+        let mut tokenizer = Tokenizer::from_shared(target);
+        tokenizer.tokenize_in_batches(batch_len, |batch| match tx.send(batch) {
+            Ok(_) => ControlFlow::Continue(()),
+            // parserがエラーで先に終わった
+            Err(_) => ControlFlow::Break(()),
+        });
+    });
+
+    let mut parser = Parser::new();
+    let result = parser.parse_batches(rx);
+    // tokenizerが落ちていたら、途中で切れたtoken列を読んだ結果は信用できない
+    if handle.join().is_err() {
+        return Err(ParseError::Internal {
+            detail: "tokenizer thread panicked",
+            pos: Position::new(1, 0, 0),
+        });
+    }
+
+    return to_document(&parser, result);
+}
+
+// 最上位のdoctypeはDocument::doctypeに移し、parserが覚えた位置を付ける
+#[cfg(all(feature = "parser", feature = "std"))]
+fn to_document(
+    parser: &Parser,
+    result: Result<Option<Vec<Node>>, ParseError>,
+) -> Result<Document, ParseError> {
+    let document = match result {
+        Ok(Some(nodes)) => Document::from_parsed(nodes, parser.doctype_span()),
+        Ok(None) => Document::default(),
+        Err(err) => return Err(err),
+    };
+    return Ok(document.with_metrics(parser.metrics().cloned()));
+}
+
+// 分けて届く入力を、つなげずにparseする。結果はつなげてparseしたものと同じ
+#[cfg(all(feature = "parser", feature = "std"))]
+pub fn parse_chunks<I>(chunks: I) -> Result<Document, ParseError>
+where
+    I: IntoIterator,
+    I::Item: AsRef<str>,
+{
+    let mut tokenizer = Tokenizer::from_chunks(chunks);
+    let mut parser = Parser::new();
+    let result = parser.parse(tokenizer.tokenize());
+    return to_document(&parser, result);
+}
+
+// 文書ごとにparse_with_optionsで読んだ結果を、inputsの順に返す
+// parallelなら、coreの数だけthreadを立てて分ける。どちらでも結果は同じ
+#[cfg(all(feature = "parser", feature = "std"))]
+pub fn parse_many<I: IntoIterator<Item = String>>(
+    inputs: I,
+    options: &ParserOptions,
+) -> Vec<Result<Document, ParseError>> {
+    let inputs: Vec<String> = inputs.into_iter().collect();
+    #[cfg(feature = "parallel")]
+    {
+        let workers = thread::available_parallelism().map_or(1, |n| n.get());
+        return parse_many_parallel(&inputs, options, workers);
+    }
+    #[cfg(not(feature = "parallel"))]
+    return parse_each(inputs.iter(), options);
+}
+
+// 一つのParserで順に読む
+#[cfg(all(feature = "parser", feature = "std"))]
+fn parse_each<'a>(
+    inputs: impl Iterator<Item = &'a String>,
+    options: &ParserOptions,
+) -> Vec<Result<Document, ParseError>> {
+    let mut parser = Parser::builder().options(options.clone()).build();
+    return inputs
+        .map(|html| parse_reusing(&mut parser, html))
+        .collect();
+}
+
+// optionsが使えなければ、どの文書もそのエラーにする
+#[cfg(all(feature = "parser", feature = "std"))]
+fn parse_reusing(
+    parser: &mut Result<Parser, OptionsError>,
+    html: &str,
+) -> Result<Document, ParseError> {
+    return match parser {
+        Ok(parser) => {
+            let result = parser.parse_str(html);
+            to_document(parser, result)
+        }
+        Err(err) => Err(err.clone().into()),
+    };
+}
+
+// threadは次に読む文書の番号を順に取り合う。読んだ結果は番号の順に並べ直す
+#[cfg(all(feature = "parser", feature = "parallel"))]
+fn parse_many_parallel(
+    inputs: &[String],
+    options: &ParserOptions,
+    workers: usize,
+) -> Vec<Result<Document, ParseError>> {
+    let workers = workers.min(inputs.len());
+    if workers < 2 {
+        return parse_each(inputs.iter(), options);
+    }
+    let next = AtomicUsize::new(0);
+    let claim = || {
+        let i = next.fetch_add(1, Ordering::Relaxed);
+        return inputs.get(i).map(|html| (i, html));
+    };
+    let mut parsed: Vec<(usize, Result<Document, ParseError>)> = thread::scope(|scope| {
+        let handles: Vec<_> = (0..workers)
+            .map(|_| {
+                scope.spawn(|| {
+                    let mut parser = Parser::builder().options(options.clone()).build();
+                    let mut parsed = Vec::new();
+                    while let Some((i, html)) = claim() {
+                        parsed.push((i, parse_reusing(&mut parser, html)));
+                    }
+                    return parsed;
+                })
+            })
+            .collect();
+        let mut parsed = Vec::with_capacity(inputs.len());
+        for handle in handles {
+            match handle.join() {
+                Ok(part) => parsed.extend(part),
+                // 一つずつ読んだときと同じく、panicは呼んだ側に返す
+                Err(panic) => std::panic::resume_unwind(panic),
+            }
+        }
+        return parsed;
+    });
+    parsed.sort_by_key(|(i, _)| *i);
+    return parsed.into_iter().map(|(_, result)| result).collect();
+}
+
+#[cfg(feature = "parser")]
+pub fn parse_streaming<F: FnMut(Node) -> ControlFlow<()>>(
+    html: &str,
+    target: &StreamTarget,
+    f: F,
+) -> Result<StreamStats, ParseError> {
+    let mut tokenizer = Tokenizer::new(html);
+    let mut parser = Parser::new();
+    return parser.parse_streaming(tokenizer.tokenize(), target, f);
+}
+
+// urlを取ってきて、charsetを決めてdecodeしてからparseする
+#[cfg(all(feature = "parser", feature = "std"))]
+pub fn fetch_and_parse(
+    url: &str,
+    transport: &impl Transport,
+    options: &FetchOptions,
+) -> Result<Document, FetchParseError> {
+    let response = fetch::fetch(url, transport, options)?;
+    let charset = response
+        .content_type()
+        .and_then(encoding::charset_from_content_type);
+    let decoded = parse_bytes_with_encoding(&response.body, charset)?;
+    return Ok(decoded.document);
+}
+
+// fileを読み、parse_bytes_with_encodingと同じくBOMやmetaからencodingを決めてparseする
+#[cfg(all(feature = "parser", feature = "std"))]
+pub fn parse_file(
+    path: impl AsRef<Path>,
+    options: &FileOptions,
+) -> Result<Document, FileParseError> {
+    let path = path.as_ref();
+    let bytes = match fs::read(path) {
+        Ok(bytes) => bytes,
+        Err(source) => {
+            return Err(FileParseError::Io {
+                path: path.to_path_buf(),
+                source,
+            })
+        }
+    };
+    return match parse_bytes_with_encoding(&bytes, options.charset.as_deref()) {
+        Ok(decoded) => Ok(decoded.document),
+        Err(BytesParseError::UnsupportedEncoding { label }) => {
+            Err(FileParseError::UnsupportedEncoding {
+                path: path.to_path_buf(),
+                label,
+            })
+        }
+        Err(BytesParseError::Parse(source)) => Err(FileParseError::Parse {
+            path: path.to_path_buf(),
+            source,
+        }),
+    };
+}
+
+// transportのcharsetがないときのparse_bytes_with_encoding。文書だけを返す
+#[cfg(all(feature = "parser", feature = "std"))]
+pub fn parse_bytes(bytes: &[u8]) -> Result<Document, BytesParseError> {
+    return Ok(parse_bytes_with_encoding(bytes, None)?.document);
+}
+
+// byte列のencodingを決めてからparseする。決め方はencoding::sniff
+// 手がかりがなくUTF-8で読んだときは、先頭1024byteより後ろのmetaを見て、食い違えば読み直す
+// (そのmetaのlabelが読めなければ無視する)
+#[cfg(all(feature = "parser", feature = "std"))]
+pub fn parse_bytes_with_encoding(
+    bytes: &[u8],
+    transport_charset: Option<&str>,
+) -> Result<Decoded, BytesParseError> {
+    let sniffed = encoding::sniff(bytes, transport_charset)?;
+    let body = &bytes[sniffed.bom_len..];
+    let document = parse(&sniffed.encoding.decode(body))?;
+    let late = match document.meta_charset() {
+        Some(label) if sniffed.tentative => Encoding::for_label(label).map(encoding::from_meta),
+        _ => None,
+    };
+    if let Some(encoding) = late.filter(|late| *late != sniffed.encoding) {
+        return Ok(Decoded {
+            document: parse(&encoding.decode(body))?,
+            encoding,
+            redecoded: true,
+        });
+    }
+    return Ok(Decoded {
+        document,
+        encoding: sniffed.encoding,
+        redecoded: false,
+    });
+}
+
+#[cfg(all(test, feature = "parser", feature = "std"))]
+mod tests {
+    use crate::html;
+    use crate::html::document::Document;
+    use crate::html::errors::{OptionsError, ParseError};
+    use crate::html::parser::{Parser, ParserOptions};
+    use crate::html::serializer::document_ascii_tree;
+    use crate::html::snapshot::assert_snapshot;
+    use crate::html::tokenizer::Tokenizer;
+    use std::fs;
+    use std::time::{Duration, Instant};
+
+    const FIXTURE: &str = "<!DOCTYPE html>\n\
+        <html lang=\"en\">\n\
+        <head><title>example</title></head>\n\
+        <body>\n\
+        <h1 class=\"title\">Lorem ipsum dolor sit amet, consectetur adipiscing elit</h1>\n\
+        <p>Sed_do_eiusmod_tempor incididunt ut labore et dolore magna aliqua.</p>\n\
+        <img src=\"https://example.com/image.png\" alt=\"an example image\"/>\n\
+        </body>\n\
+        </html>";
+
+    // tokenizer/parserの本体はpanicしてはいけない
+    fn assert_no_unwrap(name: &str, src: &str) {
+        let test_mod = src
+            .find("#[cfg(test)]")
+            .or_else(|| src.find("#[cfg(all(test"));
+        let body = match test_mod {
+            Some(i) => &src[..i],
+            None => src,
+        };
+        for (i, line) in body.lines().enumerate() {
+            assert!(
+                !line.contains(".unwrap()") && !line.contains(".expect("),
+                "{}:{} must not unwrap: {}",
+                name,
+                i + 1,
+                line.trim()
+            );
+        }
+    }
+
+    #[test]
+    fn no_unwrap_in_tokenizer_and_parser() {
+        assert_no_unwrap("tokenizer.rs", include_str!("html/tokenizer.rs"));
+        assert_no_unwrap("parser.rs", include_str!("html/parser.rs"));
+    }
+
+    // Debugは要約しか出さないので、木を比べるときはdebug_fullにする
+    fn full(result: &Result<Document, ParseError>) -> String {
+        return match result {
+            Ok(doc) => {
+                let children: String = doc.children.iter().map(|nd| nd.debug_full()).collect();
+                format!("{:?}\n{}", doc.doctype, children)
+            }
+            Err(err) => format!("{:?}", err),
+        };
+    }
+
+    #[test]
+    fn pipelined_matches_single_threaded() {
+        let mut corpus: Vec<String> = vec![
+            FIXTURE,
+            "<!DOCTYPE html> <html>\n<body>\n<h1 class=\"a\" id='b'>hello, world</h1>\n<!-- c -- d --></body>\n</html>",
+            "<p>a</p></div><p>x</p>",
+            "<div><p>unclosed</div>",
+            "<div><p>x</p>",
+            "<div foo=\"a\" \"b\"></div>",
+            "<p>a = b</p>",
+            "<x y=\"1\"",
+            "text only",
+            "",
+        ]
+        .into_iter()
+        .map(|s| s.to_string())
+        .collect();
+        corpus.push("<ul><li class=\"item\">one <b>two</b></li>\n</ul>".repeat(5000));
+
+        for html in &corpus {
+            let expected = full(&html::parse(html));
+            // 列の切れ目がどこに来ても同じになる
+            for batch_len in [1, 2, 3, 64, 4096] {
+                let pipelined = full(&html::parse_pipelined_with(html, batch_len));
+                assert_eq!(
+                    pipelined, expected,
+                    "batch_len {} for {:?}",
+                    batch_len, html
+                );
+            }
+        }
+        let large = &corpus[corpus.len() - 1];
+        assert!(large.len() > super::PIPELINE_MIN_BYTES);
+        assert_eq!(
+            full(&html::parse_pipelined(large)),
+            full(&html::parse(large))
+        );
+    }
+
+    // parallelでもなくても同じ結果になる (gatesでは両方で動かす)
+    #[test]
+    fn parse_many_keeps_input_order() {
+        let inputs: Vec<String> = (0..200)
+            .map(|i| match i % 4 {
+                0 => format!("<!DOCTYPE html><p id=\"p{}\">doc {}</p>", i, i),
+                1 => format!("<ul>{}</ul>", "<li>x</li>".repeat(i)),
+                // 閉じタグが食い違うのでエラー
+                2 => format!("<div><p>{}</div>", i),
+                _ => format!("<section><h1>{}</h1><!-- {} --></section>", i, i),
+            })
+            .collect();
+        for options in [
+            ParserOptions::default(),
+            ParserOptions {
+                lenient: true,
+                ..ParserOptions::default()
+            },
+        ] {
+            let many = html::parse_many(inputs.clone(), &options);
+            assert_eq!(many.len(), inputs.len());
+            for (html, result) in inputs.iter().zip(&many) {
+                let expected = html::parse_with_options(html, &options);
+                assert_eq!(full(result), full(&expected), "{}", html);
+            }
+            assert_eq!(
+                many.iter().filter(|result| result.is_err()).count(),
+                if options.lenient { 0 } else { 50 }
+            );
+            // coreが1つでもthreadに分けて確かめる
+            #[cfg(feature = "parallel")]
+            for workers in [2, 3, 8] {
+                let parallel = super::parse_many_parallel(&inputs, &options, workers);
+                let parallel: Vec<String> = parallel.iter().map(full).collect();
+                let many: Vec<String> = many.iter().map(full).collect();
+                assert_eq!(parallel, many, "{} workers", workers);
+            }
+        }
+
+        let zero = ParserOptions {
+            max_depth: Some(0),
+            ..ParserOptions::default()
+        };
+        let many = html::parse_many(inputs.clone(), &zero);
+        assert!(many.iter().all(|result| matches!(
+            result,
+            Err(ParseError::InvalidOptions(OptionsError::ZeroMaxDepth))
+        )));
+    }
+
+    #[test]
+    fn parse_chunks_matches_parse() {
+        for name in ["article", "broken", "dashboard", "login"] {
+            let path = format!(
+                "{}/tests/fixtures/{}.html",
+                env!("CARGO_MANIFEST_DIR"),
+                name
+            );
+            let html = fs::read_to_string(path).unwrap();
+            let expected = full(&html::parse(&html));
+            for len in [1, 2, 7, 64, 4096] {
+                let chars: Vec<char> = html.chars().collect();
+                let chunks = chars.chunks(len).map(|c| c.iter().collect::<String>());
+                assert_eq!(
+                    full(&html::parse_chunks(chunks)),
+                    expected,
+                    "{} in chunks of {}",
+                    name,
+                    len
+                );
+            }
+            // タグ、属性の文字列、&の途中で切る
+            let cuts: Vec<usize> = html
+                .match_indices(['<', '"', '&'])
+                .map(|(i, _)| i + 1)
+                .collect();
+            let mut chunks = Vec::new();
+            let mut from = 0;
+            for at in cuts {
+                chunks.push(&html[from..at]);
+                from = at;
+            }
+            chunks.push(&html[from..]);
+            assert_eq!(full(&html::parse_chunks(chunks)), expected);
+        }
+    }
+
+    // 二乗のオーダーに戻ってしまったときに気づくための目安
+    #[test]
+    fn parse_large_document_in_time() {
+        let mut html = "<!DOCTYPE html><html><body>".to_string();
+        let mut i = 0;
+        while html.len() < 200 * 1024 {
+            html += &format!(
+                "<p class=\"lead\">Lorem ipsum dolor sit amet, <a href=\"/{}\">consectetur</a> \
+                 adipiscing elit, sed do eiusmod tempor incididunt.</p>\n",
+                i
+            );
+            i += 1;
+        }
+        html += "</body></html>";
+
+        let start = Instant::now();
+        let mut tokenizer = Tokenizer::new(&html);
+        let mut parser = Parser::new();
+        let nodes = parser.parse(tokenizer.tokenize());
+        let elapsed = start.elapsed();
+
+        assert!(nodes.is_ok());
+        assert!(
+            elapsed < Duration::from_secs(10),
+            "parsing 200KB took {:?}",
+            elapsed
+        );
+    }
+
+    // tests/fixtures の実際にありそうな文書を、木かエラーの表示にしてsnapshotと比べる
+    #[test]
+    fn parse_fixture_snapshots() {
+        for name in ["article", "login", "dashboard", "broken"] {
+            let file = format!("{}.html", name);
+            let path = format!("{}/tests/fixtures/{}", env!("CARGO_MANIFEST_DIR"), file);
+            let html = fs::read_to_string(&path).unwrap();
+            let out = match html::parse(&html) {
+                Ok(doc) => document_ascii_tree(&doc),
+                Err(err) => err.render(&file, &html),
+            };
+            assert_snapshot(&format!("fixture__{}", name), &out);
+        }
+    }
+}
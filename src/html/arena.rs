@@ -0,0 +1,542 @@
+use crate::html::atom::Atom;
+use crate::html::attributes::Attributes;
+use crate::html::errors::ArenaError;
+use crate::html::footprint::{FootprintCounter, MemoryFootprint};
+use crate::html::parser::{Attribute, Item, NodeKind, Sink};
+use crate::html::shared_str::SharedStr;
+use alloc::vec::Vec;
+use core::mem::size_of;
+use core::ops::{ControlFlow, Index, Range};
+
+// Arenaの中のnodeの番号
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct NodeId(usize);
+
+impl NodeId {
+    pub fn index(self) -> usize {
+        return self.0;
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ArenaNode {
+    pub kind: NodeKind,
+    pub s: SharedStr,
+    parent: Option<NodeId>,
+    first_child: Option<NodeId>,
+    last_child: Option<NodeId>,
+    next_sibling: Option<NodeId>,
+    attrs: Range<usize>,
+}
+
+// 全部のnodeと属性を大きなVecに並べて持つ。親子は番号で辿る
+// 捨てるときはVecを数個解放するだけで、木を辿らない
+#[derive(Debug, Clone, Default)]
+pub struct Arena {
+    nodes: Vec<ArenaNode>,
+    attrs: Vec<Attribute>,
+}
+
+impl Arena {
+    pub fn new() -> Arena {
+        return Arena::default();
+    }
+
+    pub fn len(&self) -> usize {
+        return self.nodes.len();
+    }
+
+    pub fn is_empty(&self) -> bool {
+        return self.nodes.is_empty();
+    }
+
+    // 確保した領域は残して、次の文書に使い回す
+    pub fn clear(&mut self) {
+        self.nodes.clear();
+        self.attrs.clear();
+    }
+
+    // 木を辿らずに、並んでいるnodeと属性を順に見るだけ
+    pub fn memory_footprint(&self) -> MemoryFootprint {
+        let mut counter = FootprintCounter::default();
+        counter.heap(self.nodes.capacity() * size_of::<ArenaNode>());
+        counter.heap(self.attrs.capacity() * size_of::<Attribute>());
+        for nd in &self.nodes {
+            counter.node();
+            counter.string(&nd.s);
+            if nd.kind == NodeKind::Text {
+                counter.text(&nd.s);
+            }
+        }
+        for attr in &self.attrs {
+            counter.string(&attr.name);
+            counter.string(&attr.value);
+            counter.attribute(&attr.value);
+        }
+        return counter.finish();
+    }
+
+    pub fn get(&self, id: NodeId) -> Option<&ArenaNode> {
+        return self.nodes.get(id.0);
+    }
+
+    pub fn parent(&self, id: NodeId) -> Option<NodeId> {
+        return self.nodes[id.0].parent;
+    }
+
+    pub fn children(&self, id: NodeId) -> Children<'_> {
+        return Children {
+            arena: self,
+            next: self.nodes[id.0].first_child,
+        };
+    }
+
+    pub fn attributes(&self, id: NodeId) -> &[Attribute] {
+        return &self.attrs[self.nodes[id.0].attrs.clone()];
+    }
+
+    // nodeの場所にreplacementを入れ、外したnodeを返す。nodeの子はnodeに付いたまま
+    // replacementは親のないnode (parse_inで同じarenaに読んだ最上位のものなど)
+    pub fn replace_with(
+        &mut self,
+        node: NodeId,
+        replacement: NodeId,
+    ) -> Result<NodeId, ArenaError> {
+        self.check_insert(node, replacement)?;
+        self.replace_link(node, replacement);
+        return Ok(node);
+    }
+
+    // nodeの場所にwrapperを入れ、nodeをwrapperの最後の子にする
+    pub fn wrap(&mut self, node: NodeId, wrapper: NodeId) -> Result<(), ArenaError> {
+        if self.nodes[wrapper.0].kind != NodeKind::Element {
+            return Err(ArenaError::NotElement(wrapper));
+        }
+        self.check_insert(node, wrapper)?;
+        self.replace_link(node, wrapper);
+        self.append_child(wrapper, node);
+        return Ok(());
+    }
+
+    // nodeを外し、その子をnodeのあった場所に並べる。<font> などを消すのに使う
+    pub fn unwrap(&mut self, node: NodeId) -> Result<(), ArenaError> {
+        if self.nodes[node.0].kind != NodeKind::Element {
+            return Err(ArenaError::NotElement(node));
+        }
+        let parent = match self.nodes[node.0].parent {
+            Some(parent) => parent,
+            None => return Err(ArenaError::Detached(node)),
+        };
+        let children: Vec<NodeId> = self.children(node).collect();
+        let (first, last) = match (children.first(), children.last()) {
+            (Some(first), Some(last)) => (*first, *last),
+            _ => {
+                self.remove_link(node);
+                return Ok(());
+            }
+        };
+        for child in &children {
+            self.nodes[child.0].parent = Some(parent);
+        }
+        let next = self.nodes[node.0].next_sibling;
+        self.nodes[last.0].next_sibling = next;
+        if next.is_none() {
+            self.nodes[parent.0].last_child = Some(last);
+        }
+        match self.previous_sibling(node) {
+            Some(prev) => self.nodes[prev.0].next_sibling = Some(first),
+            None => self.nodes[parent.0].first_child = Some(first),
+        }
+        let nd = &mut self.nodes[node.0];
+        nd.parent = None;
+        nd.next_sibling = None;
+        nd.first_child = None;
+        nd.last_child = None;
+        return Ok(());
+    }
+
+    // nodeの場所にinsertedを入れられるか
+    fn check_insert(&self, node: NodeId, inserted: NodeId) -> Result<(), ArenaError> {
+        if self.nodes[node.0].parent.is_none() {
+            return Err(ArenaError::Detached(node));
+        }
+        if self.nodes[inserted.0].parent.is_some() {
+            return Err(ArenaError::Attached(inserted));
+        }
+        // insertedがnodeの先祖なら、自分の下に入ることになる
+        let mut cur = Some(node);
+        while let Some(id) = cur {
+            if id == inserted {
+                return Err(ArenaError::Cycle(inserted));
+            }
+            cur = self.nodes[id.0].parent;
+        }
+        return Ok(());
+    }
+
+    // 兄弟は次しか持たないので、親の子を先頭から辿る
+    fn previous_sibling(&self, id: NodeId) -> Option<NodeId> {
+        let parent = self.nodes[id.0].parent?;
+        let mut prev = None;
+        for child in self.children(parent) {
+            if child == id {
+                return prev;
+            }
+            prev = Some(child);
+        }
+        return None;
+    }
+
+    // oldの親と兄弟のつながりを、そのままnewに付け替える
+    fn replace_link(&mut self, old: NodeId, new: NodeId) {
+        let parent = self.nodes[old.0].parent;
+        let next = self.nodes[old.0].next_sibling;
+        if let Some(parent) = parent {
+            match self.previous_sibling(old) {
+                Some(prev) => self.nodes[prev.0].next_sibling = Some(new),
+                None => self.nodes[parent.0].first_child = Some(new),
+            }
+            if next.is_none() {
+                self.nodes[parent.0].last_child = Some(new);
+            }
+        }
+        self.nodes[new.0].parent = parent;
+        self.nodes[new.0].next_sibling = next;
+        self.nodes[old.0].parent = None;
+        self.nodes[old.0].next_sibling = None;
+    }
+
+    fn remove_link(&mut self, id: NodeId) {
+        let parent = match self.nodes[id.0].parent {
+            Some(parent) => parent,
+            None => return,
+        };
+        let prev = self.previous_sibling(id);
+        let next = self.nodes[id.0].next_sibling;
+        match prev {
+            Some(prev) => self.nodes[prev.0].next_sibling = next,
+            None => self.nodes[parent.0].first_child = next,
+        }
+        if next.is_none() {
+            self.nodes[parent.0].last_child = prev;
+        }
+        self.nodes[id.0].parent = None;
+        self.nodes[id.0].next_sibling = None;
+    }
+
+    fn push(&mut self, kind: NodeKind, s: SharedStr, attrs: Attributes) -> NodeId {
+        let start = self.attrs.len();
+        self.attrs.extend(attrs);
+        self.nodes.push(ArenaNode {
+            kind,
+            s,
+            parent: None,
+            first_child: None,
+            last_child: None,
+            next_sibling: None,
+            attrs: start..self.attrs.len(),
+        });
+        return NodeId(self.nodes.len() - 1);
+    }
+
+    fn append_child(&mut self, parent: NodeId, child: NodeId) {
+        match self.nodes[parent.0].last_child {
+            Some(last) => self.nodes[last.0].next_sibling = Some(child),
+            None => self.nodes[parent.0].first_child = Some(child),
+        }
+        self.nodes[parent.0].last_child = Some(child);
+        self.nodes[child.0].parent = Some(parent);
+    }
+}
+
+impl Index<NodeId> for Arena {
+    type Output = ArenaNode;
+
+    fn index(&self, id: NodeId) -> &ArenaNode {
+        return &self.nodes[id.0];
+    }
+}
+
+pub struct Children<'a> {
+    arena: &'a Arena,
+    next: Option<NodeId>,
+}
+
+impl<'a> Iterator for Children<'a> {
+    type Item = NodeId;
+
+    fn next(&mut self) -> Option<NodeId> {
+        let id = self.next?;
+        self.next = self.arena.nodes[id.0].next_sibling;
+        return Some(id);
+    }
+}
+
+// Parser::parse_inで使う。最上位のnodeを覚えておき、失敗したら作ったnodeを消す
+pub(crate) struct ArenaBuilder<'a> {
+    arena: &'a mut Arena,
+    roots: Vec<NodeId>,
+    nodes_mark: usize,
+    attrs_mark: usize,
+}
+
+impl<'a> ArenaBuilder<'a> {
+    pub(crate) fn new(arena: &'a mut Arena) -> ArenaBuilder<'a> {
+        let nodes_mark = arena.nodes.len();
+        let attrs_mark = arena.attrs.len();
+        return ArenaBuilder {
+            arena,
+            roots: Vec::new(),
+            nodes_mark,
+            attrs_mark,
+        };
+    }
+
+    pub(crate) fn finish(self) -> Vec<NodeId> {
+        return self.roots;
+    }
+
+    pub(crate) fn rollback(self) {
+        self.arena.nodes.truncate(self.nodes_mark);
+        self.arena.attrs.truncate(self.attrs_mark);
+    }
+}
+
+impl<'a> Sink for ArenaBuilder<'a> {
+    type Handle = NodeId;
+
+    fn create(&mut self, item: Item) -> NodeId {
+        return match item {
+            Item::Open(name, attrs, _) | Item::Solo(name, attrs, _) => {
+                self.arena.push(NodeKind::Element, name.to_shared(), attrs)
+            }
+            Item::Text(s) => self.arena.push(NodeKind::Text, s, Attributes::new()),
+            Item::Comment(s) => self.arena.push(NodeKind::Comment, s, Attributes::new()),
+            Item::Doctype(s) => self.arena.push(NodeKind::Doctype, s, Attributes::new()),
+            Item::Template(s) => self.arena.push(NodeKind::Template, s, Attributes::new()),
+        };
+    }
+
+    fn append(&mut self, open_tags: &mut [(Atom, NodeId)], child: NodeId) -> ControlFlow<()> {
+        match open_tags.last() {
+            Some((_, parent)) => self.arena.append_child(*parent, child),
+            None => self.roots.push(child),
+        }
+        return ControlFlow::Continue(());
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use crate::html;
+    use crate::html::arena::{Arena, NodeId};
+    use crate::html::errors::ArenaError;
+    use crate::html::parser::{NodeKind, Parser};
+    use crate::html::tokenizer::Tokenizer;
+
+    fn parse_in(arena: &mut Arena, html: &str) -> NodeId {
+        let roots = Parser::new()
+            .parse_in(arena, Tokenizer::new(html).tokenize())
+            .unwrap();
+        return roots[0];
+    }
+
+    // textは空白を挟まずにつなげる。親と最後の子のつながりも確かめる
+    fn html(arena: &Arena, id: NodeId) -> String {
+        let mut out = String::new();
+        // (node, 閉じタグを書くところか)
+        let mut stack = vec![(id, false)];
+        while let Some((id, close)) = stack.pop() {
+            let nd = &arena[id];
+            if close {
+                out += &format!("</{}>", nd.s);
+                continue;
+            }
+            if nd.kind != NodeKind::Element {
+                out += &nd.s;
+                continue;
+            }
+            out += &format!("<{}", nd.s);
+            for attr in arena.attributes(id) {
+                out += &format!(" {}=\"{}\"", attr.name, attr.value);
+            }
+            out += ">";
+            let children: Vec<NodeId> = arena.children(id).collect();
+            assert_eq!(nd.last_child, children.last().copied());
+            assert!(children
+                .iter()
+                .all(|child| arena.parent(*child) == Some(id)));
+            stack.push((id, true));
+            stack.extend(children.into_iter().rev().map(|child| (child, false)));
+        }
+        return out;
+    }
+
+    #[test]
+    fn parse_into_arena() {
+        let html = "<!DOCTYPE html><html><body><h1>hello world</h1><img src=\"a.png\" alt=\"a\"/></body></html>";
+        let mut arena = Arena::new();
+        let roots = Parser::new()
+            .parse_in(&mut arena, Tokenizer::new(html).tokenize())
+            .unwrap();
+
+        assert_eq!(roots.len(), 2);
+        assert!(arena[roots[0]].kind == NodeKind::Doctype);
+        assert_eq!(arena[roots[1]].s, "html");
+
+        let body = arena.children(roots[1]).next().unwrap();
+        let children: Vec<_> = arena.children(body).collect();
+        assert_eq!(arena[children[0]].s, "h1");
+        assert_eq!(arena.parent(children[0]), Some(body));
+        let text = arena.children(children[0]).next().unwrap();
+        assert_eq!(arena[text].s, "hello");
+
+        let img = children[1];
+        assert!(arena[img].kind == NodeKind::Element);
+        let attrs: Vec<_> = arena
+            .attributes(img)
+            .iter()
+            .map(|a| (a.name.to_string(), a.value.to_string()))
+            .collect();
+        assert_eq!(
+            attrs,
+            vec![
+                ("src".to_string(), "a.png".to_string()),
+                ("alt".to_string(), "a".to_string())
+            ]
+        );
+        assert_eq!(arena.len(), 7);
+    }
+
+    #[test]
+    fn arena_footprint_matches_boxed_counts() {
+        let html =
+            "<ul><li class=\"item\">one</li><li class=\"item\">two three</li></ul><!-- end -->";
+        let mut arena = Arena::new();
+        Parser::new()
+            .parse_in(&mut arena, Tokenizer::new(html).tokenize())
+            .unwrap();
+
+        let boxed = html::parse(html).unwrap().memory_footprint();
+        let footprint = arena.memory_footprint();
+        assert_eq!(footprint.nodes, boxed.nodes);
+        assert_eq!(footprint.text_bytes, boxed.text_bytes);
+        assert_eq!(footprint.attribute_bytes, boxed.attribute_bytes);
+        assert!(footprint.heap_bytes > 0);
+    }
+
+    #[test]
+    fn parse_in_rolls_back_on_error() {
+        let mut arena = Arena::new();
+        let first = Parser::new()
+            .parse_in(&mut arena, Tokenizer::new("<p a=\"1\">x</p>").tokenize())
+            .unwrap();
+        let len = arena.len();
+
+        let err = Parser::new().parse_in(
+            &mut arena,
+            Tokenizer::new("<div b=\"2\"><p>y</div>").tokenize(),
+        );
+        assert!(err.is_err());
+        assert_eq!(arena.len(), len);
+        assert_eq!(arena.attributes(first[0]).len(), 1);
+
+        // 同じarenaに続けて入れられる
+        let second = Parser::new()
+            .parse_in(&mut arena, Tokenizer::new("<p>z</p>").tokenize())
+            .unwrap();
+        assert_eq!(second[0].index(), len);
+    }
+
+    #[test]
+    fn drop_deep_arena_on_small_stack() {
+        let handle = std::thread::Builder::new()
+            .stack_size(256 * 1024)
+            .spawn(|| {
+                let depth = 100_000;
+                let html = "<div>".repeat(depth) + &"</div>".repeat(depth);
+                let mut arena = Arena::new();
+                let roots = Parser::new()
+                    .parse_in(&mut arena, Tokenizer::new(&html).tokenize())
+                    .unwrap();
+                assert_eq!(roots.len(), 1);
+                assert_eq!(arena.len(), depth);
+                drop(arena);
+            })
+            .unwrap();
+        handle.join().unwrap();
+    }
+
+    #[test]
+    fn wrap_then_unwrap_round_trip() {
+        let source = "<div><p>one</p><p>two</p><p>three</p></div>";
+        let mut arena = Arena::new();
+        let root = parse_in(&mut arena, source);
+        let section = parse_in(&mut arena, "<section class=\"box\"></section>");
+        let second = arena.children(root).nth(1).unwrap();
+
+        arena.wrap(second, section).unwrap();
+        assert_eq!(
+            html(&arena, root),
+            "<div><p>one</p><section class=\"box\"><p>two</p></section><p>three</p></div>"
+        );
+        assert_eq!(arena.parent(second), Some(section));
+
+        arena.unwrap(section).unwrap();
+        assert_eq!(html(&arena, root), source);
+        assert_eq!(arena.parent(section), None);
+        assert_eq!(arena.children(section).count(), 0);
+
+        // 最後の子を包んで外しても、last_childが戻る
+        let third = arena.children(root).nth(2).unwrap();
+        arena.wrap(third, section).unwrap();
+        arena.unwrap(section).unwrap();
+        assert_eq!(html(&arena, root), source);
+    }
+
+    #[test]
+    fn unwrap_promotes_children() {
+        let mut arena = Arena::new();
+        let root = parse_in(&mut arena, "<p>a<font>b<b>c</b></font><font></font></p>");
+        let fonts: Vec<NodeId> = arena.children(root).skip(1).collect();
+        arena.unwrap(fonts[0]).unwrap();
+        assert_eq!(html(&arena, root), "<p>ab<b>c</b><font></font></p>");
+        // 子のない要素はただ外す
+        arena.unwrap(fonts[1]).unwrap();
+        assert_eq!(html(&arena, root), "<p>ab<b>c</b></p>");
+    }
+
+    #[test]
+    fn replace_with_returns_old_subtree() {
+        let mut arena = Arena::new();
+        let root = parse_in(&mut arena, "<ul><li>one</li><li>two</li></ul>");
+        let replacement = parse_in(&mut arena, "<li><em>new</em></li>");
+        let first = arena.children(root).next().unwrap();
+
+        let old = arena.replace_with(first, replacement).unwrap();
+        assert_eq!(
+            html(&arena, root),
+            "<ul><li><em>new</em></li><li>two</li></ul>"
+        );
+        assert_eq!(html(&arena, old), "<li>one</li>");
+        assert_eq!(arena.parent(old), None);
+    }
+
+    #[test]
+    fn reject_nonsensical_tree_operations() {
+        let mut arena = Arena::new();
+        let root = parse_in(&mut arena, "<div><p>text</p></div>");
+        let wrapper = parse_in(&mut arena, "<span></span>");
+        let p = arena.children(root).next().unwrap();
+        let text = arena.children(p).next().unwrap();
+
+        assert_eq!(arena.wrap(root, wrapper), Err(ArenaError::Detached(root)));
+        assert_eq!(arena.unwrap(text), Err(ArenaError::NotElement(text)));
+        assert_eq!(arena.unwrap(root), Err(ArenaError::Detached(root)));
+        assert_eq!(arena.replace_with(p, text), Err(ArenaError::Attached(text)));
+        assert_eq!(arena.wrap(p, text), Err(ArenaError::NotElement(text)));
+        // 最上位のdivでpを包むと、divが自分の下に入る
+        assert_eq!(arena.wrap(p, root), Err(ArenaError::Cycle(root)));
+        assert_eq!(html(&arena, root), "<div><p>text</p></div>");
+    }
+}
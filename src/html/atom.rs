@@ -0,0 +1,517 @@
+use crate::html::shared_str::SharedStr;
+use alloc::string::ToString;
+use core::cmp::Ordering;
+use core::fmt;
+use core::hash::{Hash, Hasher};
+
+const ELEMENT: u8 = 1;
+const ATTRIBUTE: u8 = 1 << 1;
+const VOID: u8 = 1 << 2;
+const RAW_TEXT: u8 = 1 << 3;
+const ESCAPABLE_RAW_TEXT: u8 = 1 << 4;
+
+// 既知の要素名と属性名。二分探索するのでバイト順に並べておく
+static NAMES: [(&str, u8); 246] = [
+    ("a", ELEMENT),
+    ("abbr", ELEMENT),
+    ("accept", ATTRIBUTE),
+    ("accept-charset", ATTRIBUTE),
+    ("accesskey", ATTRIBUTE),
+    ("acronym", ELEMENT),
+    ("action", ATTRIBUTE),
+    ("address", ELEMENT),
+    ("align", ATTRIBUTE),
+    ("allow", ATTRIBUTE),
+    ("alt", ATTRIBUTE),
+    ("applet", ELEMENT),
+    ("area", ELEMENT | VOID),
+    ("article", ELEMENT),
+    ("aside", ELEMENT),
+    ("async", ATTRIBUTE),
+    ("audio", ELEMENT),
+    ("autocapitalize", ATTRIBUTE),
+    ("autocomplete", ATTRIBUTE),
+    ("autofocus", ATTRIBUTE),
+    ("autoplay", ATTRIBUTE),
+    ("b", ELEMENT),
+    ("background", ATTRIBUTE),
+    ("base", ELEMENT | VOID),
+    ("basefont", ELEMENT | VOID),
+    ("bdi", ELEMENT),
+    ("bdo", ELEMENT),
+    ("bgcolor", ATTRIBUTE),
+    ("bgsound", ELEMENT | VOID),
+    ("big", ELEMENT),
+    ("blockquote", ELEMENT),
+    ("body", ELEMENT),
+    ("border", ATTRIBUTE),
+    ("br", ELEMENT | VOID),
+    ("button", ELEMENT),
+    ("canvas", ELEMENT),
+    ("caption", ELEMENT),
+    ("center", ELEMENT),
+    ("charset", ATTRIBUTE),
+    ("checked", ATTRIBUTE),
+    ("cite", ELEMENT | ATTRIBUTE),
+    ("class", ATTRIBUTE),
+    ("code", ELEMENT),
+    ("col", ELEMENT | VOID),
+    ("colgroup", ELEMENT),
+    ("color", ATTRIBUTE),
+    ("cols", ATTRIBUTE),
+    ("colspan", ATTRIBUTE),
+    ("content", ATTRIBUTE),
+    ("contenteditable", ATTRIBUTE),
+    ("controls", ATTRIBUTE),
+    ("coords", ATTRIBUTE),
+    ("crossorigin", ATTRIBUTE),
+    ("data", ELEMENT | ATTRIBUTE),
+    ("datalist", ELEMENT),
+    ("datetime", ATTRIBUTE),
+    ("dd", ELEMENT),
+    ("decoding", ATTRIBUTE),
+    ("default", ATTRIBUTE),
+    ("defer", ATTRIBUTE),
+    ("del", ELEMENT),
+    ("details", ELEMENT),
+    ("dfn", ELEMENT),
+    ("dialog", ELEMENT),
+    ("dir", ELEMENT | ATTRIBUTE),
+    ("dirname", ATTRIBUTE),
+    ("disabled", ATTRIBUTE),
+    ("div", ELEMENT),
+    ("dl", ELEMENT),
+    ("download", ATTRIBUTE),
+    ("draggable", ATTRIBUTE),
+    ("dt", ELEMENT),
+    ("em", ELEMENT),
+    ("embed", ELEMENT | VOID),
+    ("enctype", ATTRIBUTE),
+    ("enterkeyhint", ATTRIBUTE),
+    ("fieldset", ELEMENT),
+    ("figcaption", ELEMENT),
+    ("figure", ELEMENT),
+    ("font", ELEMENT),
+    ("footer", ELEMENT),
+    ("for", ATTRIBUTE),
+    ("form", ELEMENT | ATTRIBUTE),
+    ("formaction", ATTRIBUTE),
+    ("formenctype", ATTRIBUTE),
+    ("formmethod", ATTRIBUTE),
+    ("formnovalidate", ATTRIBUTE),
+    ("formtarget", ATTRIBUTE),
+    ("frame", ELEMENT | VOID),
+    ("frameset", ELEMENT),
+    ("h1", ELEMENT),
+    ("h2", ELEMENT),
+    ("h3", ELEMENT),
+    ("h4", ELEMENT),
+    ("h5", ELEMENT),
+    ("h6", ELEMENT),
+    ("head", ELEMENT),
+    ("header", ELEMENT),
+    ("headers", ATTRIBUTE),
+    ("height", ATTRIBUTE),
+    ("hgroup", ELEMENT),
+    ("hidden", ATTRIBUTE),
+    ("high", ATTRIBUTE),
+    ("hr", ELEMENT | VOID),
+    ("href", ATTRIBUTE),
+    ("hreflang", ATTRIBUTE),
+    ("html", ELEMENT),
+    ("http-equiv", ATTRIBUTE),
+    ("i", ELEMENT),
+    ("id", ATTRIBUTE),
+    ("iframe", ELEMENT | RAW_TEXT),
+    ("image", ELEMENT),
+    ("img", ELEMENT | VOID),
+    ("inert", ATTRIBUTE),
+    ("input", ELEMENT | VOID),
+    ("inputmode", ATTRIBUTE),
+    ("ins", ELEMENT),
+    ("integrity", ATTRIBUTE),
+    ("is", ATTRIBUTE),
+    ("ismap", ATTRIBUTE),
+    ("itemprop", ATTRIBUTE),
+    ("kbd", ELEMENT),
+    ("keygen", ELEMENT | VOID),
+    ("kind", ATTRIBUTE),
+    ("label", ELEMENT | ATTRIBUTE),
+    ("lang", ATTRIBUTE),
+    ("language", ATTRIBUTE),
+    ("legend", ELEMENT),
+    ("li", ELEMENT),
+    ("link", ELEMENT | VOID),
+    ("list", ATTRIBUTE),
+    ("loading", ATTRIBUTE),
+    ("loop", ATTRIBUTE),
+    ("low", ATTRIBUTE),
+    ("main", ELEMENT),
+    ("map", ELEMENT),
+    ("mark", ELEMENT),
+    ("marquee", ELEMENT),
+    ("math", ELEMENT),
+    ("max", ATTRIBUTE),
+    ("maxlength", ATTRIBUTE),
+    ("media", ATTRIBUTE),
+    ("menu", ELEMENT),
+    ("meta", ELEMENT | VOID),
+    ("meter", ELEMENT),
+    ("method", ATTRIBUTE),
+    ("min", ATTRIBUTE),
+    ("minlength", ATTRIBUTE),
+    ("multiple", ATTRIBUTE),
+    ("muted", ATTRIBUTE),
+    ("name", ATTRIBUTE),
+    ("nav", ELEMENT),
+    ("nobr", ELEMENT),
+    ("noembed", ELEMENT | RAW_TEXT),
+    ("noframes", ELEMENT | RAW_TEXT),
+    ("nonce", ATTRIBUTE),
+    ("noscript", ELEMENT),
+    ("novalidate", ATTRIBUTE),
+    ("object", ELEMENT),
+    ("ol", ELEMENT),
+    ("open", ATTRIBUTE),
+    ("optgroup", ELEMENT),
+    ("optimum", ATTRIBUTE),
+    ("option", ELEMENT),
+    ("output", ELEMENT),
+    ("p", ELEMENT),
+    ("param", ELEMENT | VOID),
+    ("pattern", ATTRIBUTE),
+    ("picture", ELEMENT),
+    ("ping", ATTRIBUTE),
+    ("placeholder", ATTRIBUTE),
+    ("plaintext", ELEMENT),
+    ("playsinline", ATTRIBUTE),
+    ("poster", ATTRIBUTE),
+    ("pre", ELEMENT),
+    ("preload", ATTRIBUTE),
+    ("progress", ELEMENT),
+    ("q", ELEMENT),
+    ("readonly", ATTRIBUTE),
+    ("referrerpolicy", ATTRIBUTE),
+    ("rel", ATTRIBUTE),
+    ("required", ATTRIBUTE),
+    ("reversed", ATTRIBUTE),
+    ("role", ATTRIBUTE),
+    ("rows", ATTRIBUTE),
+    ("rowspan", ATTRIBUTE),
+    ("rp", ELEMENT),
+    ("rt", ELEMENT),
+    ("ruby", ELEMENT),
+    ("s", ELEMENT),
+    ("samp", ELEMENT),
+    ("sandbox", ATTRIBUTE),
+    ("scope", ATTRIBUTE),
+    ("script", ELEMENT | RAW_TEXT),
+    ("search", ELEMENT),
+    ("section", ELEMENT),
+    ("select", ELEMENT),
+    ("selected", ATTRIBUTE),
+    ("shape", ATTRIBUTE),
+    ("size", ATTRIBUTE),
+    ("sizes", ATTRIBUTE),
+    ("slot", ELEMENT | ATTRIBUTE),
+    ("small", ELEMENT),
+    ("source", ELEMENT | VOID),
+    ("span", ELEMENT | ATTRIBUTE),
+    ("spellcheck", ATTRIBUTE),
+    ("src", ATTRIBUTE),
+    ("srcdoc", ATTRIBUTE),
+    ("srclang", ATTRIBUTE),
+    ("srcset", ATTRIBUTE),
+    ("start", ATTRIBUTE),
+    ("step", ATTRIBUTE),
+    ("strike", ELEMENT),
+    ("strong", ELEMENT),
+    ("style", ELEMENT | ATTRIBUTE | RAW_TEXT),
+    ("sub", ELEMENT),
+    ("summary", ELEMENT | ATTRIBUTE),
+    ("sup", ELEMENT),
+    ("svg", ELEMENT),
+    ("tabindex", ATTRIBUTE),
+    ("table", ELEMENT),
+    ("target", ATTRIBUTE),
+    ("tbody", ELEMENT),
+    ("td", ELEMENT),
+    ("template", ELEMENT),
+    ("textarea", ELEMENT | ESCAPABLE_RAW_TEXT),
+    ("tfoot", ELEMENT),
+    ("th", ELEMENT),
+    ("thead", ELEMENT),
+    ("time", ELEMENT),
+    ("title", ELEMENT | ATTRIBUTE | ESCAPABLE_RAW_TEXT),
+    ("tr", ELEMENT),
+    ("track", ELEMENT | VOID),
+    ("translate", ATTRIBUTE),
+    ("tt", ELEMENT),
+    ("type", ATTRIBUTE),
+    ("u", ELEMENT),
+    ("ul", ELEMENT),
+    ("usemap", ATTRIBUTE),
+    ("value", ATTRIBUTE),
+    ("var", ELEMENT),
+    ("video", ELEMENT),
+    ("wbr", ELEMENT | VOID),
+    ("width", ATTRIBUTE),
+    ("wrap", ATTRIBUTE),
+    ("xmp", ELEMENT | RAW_TEXT),
+];
+
+// 自作の要素には使えない名前 (SVGとMathMLにもとからある)
+const RESERVED_CUSTOM_NAMES: [&str; 8] = [
+    "annotation-xml",
+    "color-profile",
+    "font-face",
+    "font-face-format",
+    "font-face-name",
+    "font-face-src",
+    "font-face-uri",
+    "missing-glyph",
+];
+
+// 大文字小文字を無視して比べる (nameは任意, 表の方は小文字)
+fn cmp_ignore_ascii_case(table: &str, name: &str) -> Ordering {
+    let lhs = table.bytes();
+    let rhs = name.bytes().map(|b| b.to_ascii_lowercase());
+    return lhs.cmp(rhs);
+}
+
+fn lookup(name: &str) -> Option<usize> {
+    return NAMES
+        .binary_search_by(|(table, _)| cmp_ignore_ascii_case(table, name))
+        .ok();
+}
+
+#[derive(Clone)]
+enum Repr {
+    Known(u16),
+    // 表にない名前。小文字にしたものを持つ (from_shared_exactで作ったものは書かれたまま)
+    Unknown(SharedStr),
+}
+
+// タグ名と属性名。既知の名前は表の番号だけを持つので比較はO(1)
+#[derive(Clone)]
+pub struct Atom {
+    repr: Repr,
+}
+
+impl Atom {
+    pub fn new(name: &str) -> Atom {
+        return match lookup(name) {
+            Some(i) => Atom {
+                repr: Repr::Known(i as u16),
+            },
+            None => {
+                let mut owned = name.to_string();
+                owned.make_ascii_lowercase();
+                Atom {
+                    repr: Repr::Unknown(SharedStr::from(owned)),
+                }
+            }
+        };
+    }
+
+    // tokenの文字列から作る。表にない名前でも小文字ならsourceを指したままにする
+    pub fn from_shared(name: &SharedStr) -> Atom {
+        return match lookup(name) {
+            Some(i) => Atom {
+                repr: Repr::Known(i as u16),
+            },
+            None => Atom {
+                repr: Repr::Unknown(name.to_ascii_lowercase()),
+            },
+        };
+    }
+
+    // <svg> の中の viewBox や linearGradient のように、大文字小文字を区別する名前
+    // 大文字を含む名前は表の名前と等しくならない
+    pub fn from_shared_exact(name: &SharedStr) -> Atom {
+        if !name.bytes().any(|b| b.is_ascii_uppercase()) {
+            return Atom::from_shared(name);
+        }
+        return Atom {
+            repr: Repr::Unknown(name.clone()),
+        };
+    }
+
+    pub fn as_str(&self) -> &str {
+        return match &self.repr {
+            Repr::Known(i) => NAMES[*i as usize].0,
+            Repr::Unknown(s) => s.as_str(),
+        };
+    }
+
+    // 既知の名前なら確保せずに済む
+    pub fn to_shared(&self) -> SharedStr {
+        return match &self.repr {
+            Repr::Known(i) => SharedStr::from_static(NAMES[*i as usize].0),
+            Repr::Unknown(s) => s.clone(),
+        };
+    }
+
+    fn flags(&self) -> u8 {
+        return match &self.repr {
+            Repr::Known(i) => NAMES[*i as usize].1,
+            Repr::Unknown(_) => 0,
+        };
+    }
+
+    pub fn is_known(&self) -> bool {
+        return matches!(self.repr, Repr::Known(_));
+    }
+
+    pub fn is_element(&self) -> bool {
+        return self.flags() & ELEMENT != 0;
+    }
+
+    pub fn is_attribute(&self) -> bool {
+        return self.flags() & ATTRIBUTE != 0;
+    }
+
+    // <br> のように閉じタグを持たない要素
+    pub fn is_void(&self) -> bool {
+        return self.flags() & VOID != 0;
+    }
+
+    // 中身をタグとして読まない要素 (<script>, <style> など)
+    pub fn is_raw_text(&self) -> bool {
+        return self.flags() & RAW_TEXT != 0;
+    }
+
+    // 中身をタグとして読まないが、文字参照は読む要素 (<textarea>, <title>)
+    pub fn is_escapable_raw_text(&self) -> bool {
+        return self.flags() & ESCAPABLE_RAW_TEXT != 0;
+    }
+
+    // <my-widget> のような自作の要素の名前。小文字で始まり - を含む
+    // SVGとMathMLの - の入った名前は使えない
+    pub fn is_custom_element(&self) -> bool {
+        let name = self.as_str();
+        return name.starts_with(|c: char| c.is_ascii_lowercase())
+            && name.contains('-')
+            && !RESERVED_CUSTOM_NAMES.contains(&name);
+    }
+}
+
+impl PartialEq for Atom {
+    fn eq(&self, other: &Atom) -> bool {
+        return match (&self.repr, &other.repr) {
+            (Repr::Known(a), Repr::Known(b)) => a == b,
+            (Repr::Unknown(a), Repr::Unknown(b)) => a == b,
+            // 表にない名前は既知の名前と等しくなることはない
+            _ => false,
+        };
+    }
+}
+
+impl Eq for Atom {}
+
+impl PartialEq<str> for Atom {
+    fn eq(&self, other: &str) -> bool {
+        return self.as_str() == other;
+    }
+}
+
+impl PartialEq<&str> for Atom {
+    fn eq(&self, other: &&str) -> bool {
+        return self.as_str() == *other;
+    }
+}
+
+impl Hash for Atom {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.as_str().hash(state);
+    }
+}
+
+impl From<&str> for Atom {
+    fn from(name: &str) -> Atom {
+        return Atom::new(name);
+    }
+}
+
+impl fmt::Debug for Atom {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        return fmt::Debug::fmt(self.as_str(), f);
+    }
+}
+
+impl fmt::Display for Atom {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        return fmt::Display::fmt(self.as_str(), f);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::html::atom::{Atom, NAMES};
+    use crate::html::shared_str::SharedStr;
+
+    #[test]
+    fn names_are_sorted_and_lowercase() {
+        for pair in NAMES.windows(2) {
+            assert!(pair[0].0 < pair[1].0, "{} >= {}", pair[0].0, pair[1].0);
+        }
+        for (name, _) in NAMES.iter() {
+            assert_eq!(*name, name.to_ascii_lowercase());
+        }
+    }
+
+    #[test]
+    fn known_names() {
+        let div = Atom::new("div");
+        assert!(div.is_known());
+        assert!(div.is_element());
+        assert!(!div.is_void());
+        assert_eq!(div, Atom::new("div"));
+        assert_ne!(div, Atom::new("span"));
+
+        assert!(Atom::new("img").is_void());
+        assert!(Atom::new("script").is_raw_text());
+        assert!(Atom::new("title").is_escapable_raw_text());
+
+        let href = Atom::new("href");
+        assert!(href.is_attribute());
+        assert!(!href.is_element());
+    }
+
+    #[test]
+    fn unknown_names() {
+        let widget = Atom::new("my-widget");
+        assert!(!widget.is_known());
+        assert!(!widget.is_void());
+        assert!(!widget.is_raw_text());
+        assert_eq!(widget, Atom::new("my-widget"));
+        assert_ne!(widget, Atom::new("div"));
+        assert_eq!(widget.as_str(), "my-widget");
+        assert!(widget.is_custom_element());
+        assert!(!Atom::new("div").is_custom_element());
+        assert!(!Atom::new("font-face").is_custom_element());
+        assert!(!Atom::new("-x").is_custom_element());
+    }
+
+    #[test]
+    fn case_variants() {
+        assert_eq!(Atom::new("DIV"), Atom::new("div"));
+        assert_eq!(Atom::new("Div").as_str(), "div");
+        assert!(Atom::new("IMG").is_void());
+        assert_eq!(Atom::new("My-Widget"), Atom::new("my-widget"));
+        assert_eq!(Atom::new("My-Widget").as_str(), "my-widget");
+        assert_eq!(
+            Atom::from_shared(&SharedStr::from("MyWidget")),
+            Atom::new("mywidget")
+        );
+        assert_eq!(Atom::from_shared(&SharedStr::from("DIV")), Atom::new("div"));
+        // foreignの名前は書かれたまま
+        let gradient = Atom::from_shared_exact(&SharedStr::from("linearGradient"));
+        assert_eq!(gradient.as_str(), "linearGradient");
+        assert_ne!(gradient, Atom::new("lineargradient"));
+        assert_eq!(
+            Atom::from_shared_exact(&SharedStr::from("svg")),
+            Atom::new("svg")
+        );
+    }
+}
@@ -0,0 +1,365 @@
+// 要素の属性。書かれた順に並べ、多いときだけ名前の索引を持つ
+// serializerは順に、selectorやNode::attrは名前で引くので、両方を速くする
+use crate::html::parser::Attribute;
+use crate::html::shared_str::SharedStr;
+use alloc::boxed::Box;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::cmp::Ordering;
+use core::fmt;
+use core::ops::Deref;
+
+// これより多くなったら索引を作る。少なければ並びを端から見るほうが速い
+pub const INDEX_THRESHOLD: usize = 8;
+
+// 属性が1つだけの要素が多いので、smallvec featureでは1つ目をNodeの中に持つ
+// (Attributeは大きいので、たくさん持つとテキストなど全部のnodeが大きくなる)
+#[cfg(feature = "smallvec")]
+type Entries = smallvec::SmallVec<[Attribute; 1]>;
+#[cfg(not(feature = "smallvec"))]
+type Entries = Vec<Attribute>;
+
+// (小文字にした名前, entriesの位置) を名前、位置の順に並べる
+// 同じ名前が2つあれば、前にあるものが先
+type NameIndex = Vec<(String, usize)>;
+
+#[derive(Clone, Default)]
+pub struct Attributes {
+    repr: Repr,
+}
+
+// 索引は並びと一緒にBoxに入れる (Nodeが大きくならないように、ポインタを1つ足さない)
+#[derive(Clone)]
+enum Repr {
+    Plain(Entries),
+    // INDEX_THRESHOLDを超えたときに作り、そのあとは足すたびに直す
+    Indexed(Box<Indexed>),
+}
+
+#[derive(Clone)]
+struct Indexed {
+    entries: Entries,
+    index: NameIndex,
+}
+
+impl Default for Repr {
+    fn default() -> Repr {
+        return Repr::Plain(Entries::new());
+    }
+}
+
+impl Attributes {
+    pub fn new() -> Attributes {
+        return Attributes::default();
+    }
+
+    fn from_entries(entries: Entries) -> Attributes {
+        if entries.len() <= INDEX_THRESHOLD {
+            return Attributes {
+                repr: Repr::Plain(entries),
+            };
+        }
+        let index = build_index(&entries);
+        return Attributes {
+            repr: Repr::Indexed(Box::new(Indexed { entries, index })),
+        };
+    }
+
+    fn entries(&self) -> &Entries {
+        return match &self.repr {
+            Repr::Plain(entries) => entries,
+            Repr::Indexed(indexed) => &indexed.entries,
+        };
+    }
+
+    fn entries_mut(&mut self) -> &mut Entries {
+        return match &mut self.repr {
+            Repr::Plain(entries) => entries,
+            Repr::Indexed(indexed) => &mut indexed.entries,
+        };
+    }
+
+    // 書かれた順
+    pub fn iter(&self) -> core::slice::Iter<'_, Attribute> {
+        return self.entries().iter();
+    }
+
+    // 名前は変えられない (変えると索引が合わなくなる)
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = (&SharedStr, &mut SharedStr)> {
+        return self
+            .entries_mut()
+            .iter_mut()
+            .map(|attr| (&attr.name, &mut attr.value));
+    }
+
+    // 大文字小文字も合わせる
+    pub fn get(&self, name: &str) -> Option<&SharedStr> {
+        let i = self.find(name, |attr| attr.name.as_str() == name)?;
+        return Some(&self.entries()[i].value);
+    }
+
+    // HTMLの属性名は大文字小文字を区別しない
+    pub fn get_ignore_case(&self, name: &str) -> Option<&SharedStr> {
+        let i = self.find(name, |attr| attr.name.eq_ignore_ascii_case(name))?;
+        return Some(&self.entries()[i].value);
+    }
+
+    // 同じ名前があっても後ろに足す (parserは書かれたまま残す)
+    pub fn push(&mut self, attr: Attribute) {
+        match &mut self.repr {
+            Repr::Plain(entries) => {
+                entries.push(attr);
+                if entries.len() > INDEX_THRESHOLD {
+                    *self = Attributes::from_entries(core::mem::take(entries));
+                }
+            }
+            Repr::Indexed(indexed) => {
+                let position = indexed.entries.len();
+                insert_into_index(&mut indexed.index, attr.name.as_str(), position);
+                indexed.entries.push(attr);
+            }
+        }
+    }
+
+    // 同じ名前 (大文字小文字を区別しない) があれば、その場所のまま値を替えて前の値を返す
+    // なければ後ろに足す
+    pub fn insert(&mut self, name: SharedStr, value: SharedStr) -> Option<SharedStr> {
+        let found = self.find(name.as_str(), |attr| attr.name.eq_ignore_ascii_case(&name));
+        return match found {
+            Some(i) => Some(core::mem::replace(&mut self.entries_mut()[i].value, value)),
+            None => {
+                self.push(Attribute { name, value });
+                None
+            }
+        };
+    }
+
+    // 名前の合う最初の属性を外す。後ろの属性の順は変えない
+    pub fn remove(&mut self, name: &str) -> Option<Attribute> {
+        let i = self.find(name, |attr| attr.name.eq_ignore_ascii_case(name))?;
+        let mut entries = core::mem::take(self.entries_mut());
+        let attr = entries.remove(i);
+        // 位置がずれるので索引は作り直す
+        *self = Attributes::from_entries(entries);
+        return Some(attr);
+    }
+
+    // Nodeの外に確保している分
+    pub fn heap_bytes(&self) -> usize {
+        return match &self.repr {
+            Repr::Plain(entries) => entries_heap_bytes(entries),
+            Repr::Indexed(indexed) => {
+                core::mem::size_of::<Indexed>()
+                    + entries_heap_bytes(&indexed.entries)
+                    + indexed.index.capacity() * core::mem::size_of::<(String, usize)>()
+                    + indexed
+                        .index
+                        .iter()
+                        .map(|(name, _)| name.capacity())
+                        .sum::<usize>()
+            }
+        };
+    }
+
+    // 名前が大文字小文字を除いて合うもののうち、matchesに合う最初のもの
+    fn find(&self, name: &str, matches: impl Fn(&Attribute) -> bool) -> Option<usize> {
+        let indexed = match &self.repr {
+            Repr::Plain(entries) => return entries.iter().position(matches),
+            Repr::Indexed(indexed) => indexed,
+        };
+        let index = &indexed.index;
+        let start = index.partition_point(|(key, _)| cmp_lowercase(key, name) == Ordering::Less);
+        return index[start..]
+            .iter()
+            .take_while(|(key, _)| cmp_lowercase(key, name) == Ordering::Equal)
+            .map(|(_, i)| *i)
+            .find(|i| matches(&indexed.entries[*i]));
+    }
+}
+
+#[cfg(not(feature = "smallvec"))]
+fn entries_heap_bytes(entries: &Entries) -> usize {
+    return entries.capacity() * core::mem::size_of::<Attribute>();
+}
+
+#[cfg(feature = "smallvec")]
+fn entries_heap_bytes(entries: &Entries) -> usize {
+    if !entries.spilled() {
+        return 0;
+    }
+    return entries.capacity() * core::mem::size_of::<Attribute>();
+}
+
+fn build_index(entries: &[Attribute]) -> NameIndex {
+    let mut index: NameIndex = entries
+        .iter()
+        .enumerate()
+        .map(|(i, attr)| (attr.name.as_str().to_ascii_lowercase(), i))
+        .collect();
+    index.sort();
+    return index;
+}
+
+// 後ろに足した属性は同じ名前の中でいちばん後ろになる
+fn insert_into_index(index: &mut NameIndex, name: &str, position: usize) {
+    let at = index.partition_point(|(key, _)| cmp_lowercase(key, name) != Ordering::Greater);
+    index.insert(at, (name.to_ascii_lowercase(), position));
+}
+
+// keyは小文字にしてある。nameは比べながら小文字にする (探すたびにコピーしない)
+fn cmp_lowercase(key: &str, name: &str) -> Ordering {
+    return key
+        .bytes()
+        .cmp(name.bytes().map(|b| b.to_ascii_lowercase()));
+}
+
+// 並んだ属性としてそのまま読める (len, is_empty, [i] など)
+impl Deref for Attributes {
+    type Target = [Attribute];
+
+    fn deref(&self) -> &[Attribute] {
+        return self.entries();
+    }
+}
+
+// 索引は並びから決まるので、比べるのは並びだけ
+impl PartialEq for Attributes {
+    fn eq(&self, other: &Attributes) -> bool {
+        return self.entries()[..] == other.entries()[..];
+    }
+}
+
+impl fmt::Debug for Attributes {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        return f.debug_list().entries(self.iter()).finish();
+    }
+}
+
+impl FromIterator<Attribute> for Attributes {
+    fn from_iter<I: IntoIterator<Item = Attribute>>(iter: I) -> Attributes {
+        return Attributes::from_entries(iter.into_iter().collect());
+    }
+}
+
+impl From<Vec<Attribute>> for Attributes {
+    fn from(attrs: Vec<Attribute>) -> Attributes {
+        return attrs.into_iter().collect();
+    }
+}
+
+impl IntoIterator for Attributes {
+    type Item = Attribute;
+    type IntoIter = <Entries as IntoIterator>::IntoIter;
+
+    fn into_iter(mut self) -> Self::IntoIter {
+        return core::mem::take(self.entries_mut()).into_iter();
+    }
+}
+
+impl<'a> IntoIterator for &'a Attributes {
+    type Item = &'a Attribute;
+    type IntoIter = core::slice::Iter<'a, Attribute>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        return self.iter();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::html::attributes::{Attributes, INDEX_THRESHOLD};
+    use crate::html::parser::Attribute;
+    use crate::html::shared_str::SharedStr;
+    use alloc::format;
+    use alloc::string::String;
+    use alloc::vec::Vec;
+
+    fn attr(name: &str, value: &str) -> Attribute {
+        return Attribute {
+            name: SharedStr::from(name),
+            value: SharedStr::from(value),
+        };
+    }
+
+    fn names(attrs: &Attributes) -> Vec<&str> {
+        return attrs.iter().map(|attr| attr.name.as_str()).collect();
+    }
+
+    // 索引のあるときとないときで同じ答えになるか
+    fn sizes() -> [usize; 3] {
+        return [2, INDEX_THRESHOLD, 50];
+    }
+
+    fn filled(n: usize) -> Attributes {
+        let mut attrs: Attributes = (0..n)
+            .map(|i| attr(&format!("data-k{}", i), &format!("{}", i)))
+            .collect();
+        attrs.push(attr("Title", "first"));
+        attrs.push(attr("title", "second"));
+        return attrs;
+    }
+
+    #[test]
+    fn lookup_is_case_insensitive() {
+        for n in sizes() {
+            let attrs = filled(n);
+            assert_eq!(attrs.get_ignore_case("TITLE").unwrap(), "first", "{}", n);
+            assert_eq!(attrs.get_ignore_case("title").unwrap(), "first", "{}", n);
+            // 大文字小文字も合わせるなら、後ろのもの
+            assert_eq!(attrs.get("title").unwrap(), "second", "{}", n);
+            assert_eq!(attrs.get("TITLE"), None, "{}", n);
+            assert_eq!(attrs.get_ignore_case("data-K0").unwrap(), "0", "{}", n);
+            assert_eq!(attrs.get_ignore_case("data-k"), None, "{}", n);
+            assert_eq!(attrs.get_ignore_case("title-x"), None, "{}", n);
+        }
+    }
+
+    #[test]
+    fn insert_keeps_position_and_remove_keeps_order() {
+        for n in sizes() {
+            let mut attrs = filled(n);
+            let before: Vec<String> = names(&attrs).iter().map(|s| String::from(*s)).collect();
+            // もうある名前は、その場所で値だけ替える
+            let old = attrs.insert(SharedStr::from("TITLE"), SharedStr::from("new"));
+            assert_eq!(old.unwrap(), "first");
+            assert_eq!(names(&attrs), before);
+            assert_eq!(attrs.get("Title").unwrap(), "new");
+
+            // 外して足し直すと後ろにまわる
+            let removed = attrs.remove("data-k0").unwrap();
+            assert_eq!(removed.value, "0");
+            assert_eq!(attrs.insert(removed.name, removed.value), None);
+            assert_eq!(names(&attrs).last(), Some(&"data-k0"));
+            assert_eq!(attrs.get("data-k0").unwrap(), "0");
+            assert_eq!(attrs.len(), before.len());
+
+            // 同じ名前のうち前のものだけを外す
+            assert_eq!(attrs.remove("title").unwrap().value, "new");
+            assert_eq!(attrs.get_ignore_case("title").unwrap(), "second");
+            assert_eq!(attrs.remove("missing"), None);
+        }
+    }
+
+    #[test]
+    fn values_can_be_changed_in_place() {
+        let mut attrs = filled(20);
+        for (name, value) in attrs.iter_mut() {
+            if name.as_str() == "data-k3" {
+                *value = SharedStr::from("three");
+            }
+        }
+        assert_eq!(attrs.get("data-k3").unwrap(), "three");
+        assert_eq!(
+            attrs,
+            filled(20)
+                .into_iter()
+                .map(|mut a| {
+                    if a.name.as_str() == "data-k3" {
+                        a.value = SharedStr::from("three");
+                    }
+                    a
+                })
+                .collect()
+        );
+    }
+}
@@ -0,0 +1,220 @@
+// 木の形を比べる。テストで、空白やコメントの有無まで書かずに済むように
+use crate::html::parser::{Node, NodeData, NodeKind};
+use alloc::vec;
+use alloc::vec::Vec;
+use core::slice;
+
+// 既定ではどれも無視せず、すべて同じときだけ等しい
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CompareOptions {
+    // 空白だけのtext (preserve_whitespaceで残した改行や字下げ) を無いものとする
+    pub ignore_whitespace_text: bool,
+    pub ignore_comments: bool,
+    // 属性を名前で合わせる。既定では書かれた順も比べる
+    pub ignore_attribute_order: bool,
+    // textとコメントの中身を、大文字小文字を区別せずに比べる
+    pub ignore_text_case: bool,
+}
+
+impl Node {
+    // 比べ方はoptsで決める。<template> の中身も比べる
+    pub fn equals_ignoring(&self, other: &Node, opts: CompareOptions) -> bool {
+        return lists_equal(slice::from_ref(self), slice::from_ref(other), opts);
+    }
+}
+
+fn skipped(nd: &Node, opts: CompareOptions) -> bool {
+    return (opts.ignore_whitespace_text && nd.is_blank())
+        || (opts.ignore_comments && nd.kind() == NodeKind::Comment);
+}
+
+fn text_equal(a: &str, b: &str, opts: CompareOptions) -> bool {
+    if !opts.ignore_text_case {
+        return a == b;
+    }
+    return a
+        .chars()
+        .flat_map(char::to_lowercase)
+        .eq(b.chars().flat_map(char::to_lowercase));
+}
+
+// 子は見ない
+fn shallow_equal(a: &Node, b: &Node, opts: CompareOptions) -> bool {
+    return match (&a.data, &b.data) {
+        (
+            NodeData::Element {
+                name: x,
+                namespace: xn,
+                ..
+            },
+            NodeData::Element {
+                name: y,
+                namespace: yn,
+                ..
+            },
+        ) => {
+            let (xs, ys) = (a.attrs(), b.attrs());
+            let attrs_equal = if opts.ignore_attribute_order {
+                xs.len() == ys.len() && xs.iter().all(|attr| ys.contains(attr))
+            } else {
+                xs == ys
+            };
+            x == y
+                && xn == yn
+                && attrs_equal
+                && a.template_content().is_some() == b.template_content().is_some()
+        }
+        (NodeData::Text(x), NodeData::Text(y)) | (NodeData::Comment(x), NodeData::Comment(y)) => {
+            text_equal(x, y, opts)
+        }
+        (NodeData::Doctype(x), NodeData::Doctype(y)) => x == y,
+        (NodeData::Template(x), NodeData::Template(y)) => x == y,
+        _ => false,
+    };
+}
+
+fn child_nodes(nd: &Node) -> &[Node] {
+    return nd.template_content().unwrap_or(nd.children());
+}
+
+// 深い木でも再帰しないように、比べる兄弟の列を自前のスタックに積む
+pub(crate) fn lists_equal(a: &[Node], b: &[Node], opts: CompareOptions) -> bool {
+    let mut stack: Vec<(&[Node], &[Node])> = vec![(a, b)];
+    while let Some((a, b)) = stack.pop() {
+        let mut a = a.iter().filter(|nd| !skipped(nd, opts));
+        let mut b = b.iter().filter(|nd| !skipped(nd, opts));
+        loop {
+            match (a.next(), b.next()) {
+                (None, None) => break,
+                (Some(x), Some(y)) => {
+                    if !shallow_equal(x, y, opts) {
+                        return false;
+                    }
+                    stack.push((child_nodes(x), child_nodes(y)));
+                }
+                _ => return false,
+            }
+        }
+    }
+    return true;
+}
+
+// assert_tree_eq!(nodes, expected) のように書く。&[Node] になるものなら何でもよい
+// 3つ目にCompareOptionsを渡せる。違えば両方の木を出す
+#[cfg(all(test, feature = "std"))]
+macro_rules! assert_tree_eq {
+    ($left:expr, $right:expr $(,)?) => {
+        $crate::html::compare::assert_tree_eq!(
+            $left,
+            $right,
+            $crate::html::compare::CompareOptions::default()
+        )
+    };
+    ($left:expr, $right:expr, $opts:expr $(,)?) => {
+        match (&$left, &$right) {
+            (left, right) => {
+                let left: &[$crate::html::parser::Node] = ::core::convert::AsRef::as_ref(left);
+                let right: &[$crate::html::parser::Node] = ::core::convert::AsRef::as_ref(right);
+                if !$crate::html::compare::lists_equal(left, right, $opts) {
+                    panic!(
+                        "trees are not equal\n left:\n{}right:\n{}",
+                        $crate::html::serializer::to_ascii_tree(left),
+                        $crate::html::serializer::to_ascii_tree(right)
+                    );
+                }
+            }
+        }
+    };
+}
+
+#[cfg(all(test, feature = "std"))]
+pub(crate) use assert_tree_eq;
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::CompareOptions;
+    use crate::html::parser::{Node, Parser};
+
+    fn parse(html: &str) -> Vec<Node> {
+        return Parser::builder()
+            .preserve_whitespace()
+            .parse(html)
+            .unwrap()
+            .unwrap();
+    }
+
+    #[test]
+    fn options_toggle_each_difference() {
+        let cases = [
+            (
+                "<ul>\n  <li>a</li>\n</ul>",
+                "<ul><li>a</li></ul>",
+                CompareOptions {
+                    ignore_whitespace_text: true,
+                    ..CompareOptions::default()
+                },
+            ),
+            (
+                "<p>a<!-- note --></p><!-- end -->",
+                "<p>a</p>",
+                CompareOptions {
+                    ignore_comments: true,
+                    ..CompareOptions::default()
+                },
+            ),
+            (
+                "<a href=\"/\" class=\"x\">a</a>",
+                "<a class=\"x\" href=\"/\">a</a>",
+                CompareOptions {
+                    ignore_attribute_order: true,
+                    ..CompareOptions::default()
+                },
+            ),
+            (
+                "<p>Hello World</p>",
+                "<p>hello world</p>",
+                CompareOptions {
+                    ignore_text_case: true,
+                    ..CompareOptions::default()
+                },
+            ),
+        ];
+        for (left, right, opts) in cases {
+            let (left, right) = (parse(left), parse(right));
+            assert!(
+                !left[0].equals_ignoring(&right[0], CompareOptions::default()),
+                "{:?}",
+                opts
+            );
+            assert_tree_eq!(left, right, opts);
+        }
+    }
+
+    #[test]
+    fn real_differences_are_not_ignored() {
+        let all = CompareOptions {
+            ignore_whitespace_text: true,
+            ignore_comments: true,
+            ignore_attribute_order: true,
+            ignore_text_case: true,
+        };
+        let base = &parse("<div id=\"a\"><p>x</p></div>")[0];
+        for other in [
+            "<div id=\"b\"><p>x</p></div>",
+            "<div id=\"a\" class=\"c\"><p>x</p></div>",
+            "<div id=\"a\"><p>y</p></div>",
+            "<div id=\"a\"><span>x</span></div>",
+            "<div id=\"a\"><p>x</p><p>x</p></div>",
+            "<div id=\"a\"><p>x y</p></div>",
+        ] {
+            assert!(!base.equals_ignoring(&parse(other)[0], all), "{}", other);
+        }
+        assert!(base.equals_ignoring(&parse("<DIV ID=\"a\"> <p>X</p> </DIV>")[0], all));
+    }
+
+    #[test]
+    #[should_panic(expected = "trees are not equal")]
+    fn assert_tree_eq_shows_both_trees() {
+        assert_tree_eq!(parse("<p>a</p>"), parse("<p>b</p>"));
+    }
+}
@@ -0,0 +1,333 @@
+// 2つの木を比べて、変わったところを並べる
+// 子は前と後ろから同じ種類のnodeを合わせ、残った真ん中を位置で合わせる
+// (一番短い編集の列は求めない。前後と名前の違う要素を間に足したなら、足したことだけが出る)
+use crate::html::parser::{Node, NodeData};
+use crate::html::path::NodePath;
+use crate::html::shared_str::SharedStr;
+
+// pathは比べ始めたnodeからの子の番号 (空の列が比べ始めたnode)
+// Removedは古い木の、ほかは新しい木の番号
+#[derive(Debug, Clone)]
+pub enum Change<'a> {
+    Inserted {
+        path: NodePath,
+        node: &'a Node,
+    },
+    Removed {
+        path: NodePath,
+        node: &'a Node,
+    },
+    // 要素の名前やnodeの種類が違う
+    Replaced {
+        path: NodePath,
+        old: &'a Node,
+        new: &'a Node,
+    },
+    // テキストとコメントの中身
+    TextChanged {
+        path: NodePath,
+        old: &'a SharedStr,
+        new: &'a SharedStr,
+    },
+    AttributeAdded {
+        path: NodePath,
+        name: &'a SharedStr,
+        value: &'a SharedStr,
+    },
+    AttributeRemoved {
+        path: NodePath,
+        name: &'a SharedStr,
+        value: &'a SharedStr,
+    },
+    AttributeChanged {
+        path: NodePath,
+        name: &'a SharedStr,
+        old: &'a SharedStr,
+        new: &'a SharedStr,
+    },
+}
+
+impl Change<'_> {
+    pub fn path(&self) -> &NodePath {
+        return match self {
+            Change::Inserted { path, .. }
+            | Change::Removed { path, .. }
+            | Change::Replaced { path, .. }
+            | Change::TextChanged { path, .. }
+            | Change::AttributeAdded { path, .. }
+            | Change::AttributeRemoved { path, .. }
+            | Change::AttributeChanged { path, .. } => path,
+        };
+    }
+}
+
+// 同じnodeとして中を比べてよいか。テキストは中身も見る (違えば前後を合わせるのに使えない)
+fn same_shape(old: &Node, new: &Node) -> bool {
+    return match (&old.data, &new.data) {
+        (
+            NodeData::Element {
+                name: a,
+                namespace: x,
+                ..
+            },
+            NodeData::Element {
+                name: b,
+                namespace: y,
+                ..
+            },
+        ) => a == b && x == y,
+        (NodeData::Text(a), NodeData::Text(b)) => a == b,
+        (NodeData::Comment(a), NodeData::Comment(b)) => a == b,
+        (NodeData::Doctype(a), NodeData::Doctype(b)) => a == b,
+        (NodeData::Template(a), NodeData::Template(b)) => a == b,
+        _ => false,
+    };
+}
+
+// templateの中身も子と同じく比べる
+fn child_nodes(nd: &Node) -> &[Node] {
+    return nd.template_content().unwrap_or(nd.children());
+}
+
+fn diff_attributes<'a>(
+    old: &'a Node,
+    new: &'a Node,
+    path: &NodePath,
+    changes: &mut Vec<Change<'a>>,
+) {
+    for attr in old.attrs() {
+        match new.attrs().iter().find(|a| a.name == attr.name) {
+            None => changes.push(Change::AttributeRemoved {
+                path: path.clone(),
+                name: &attr.name,
+                value: &attr.value,
+            }),
+            Some(a) if a.value != attr.value => changes.push(Change::AttributeChanged {
+                path: path.clone(),
+                name: &attr.name,
+                old: &attr.value,
+                new: &a.value,
+            }),
+            Some(_) => {}
+        }
+    }
+    for attr in new.attrs() {
+        if !old.attrs().iter().any(|a| a.name == attr.name) {
+            changes.push(Change::AttributeAdded {
+                path: path.clone(),
+                name: &attr.name,
+                value: &attr.value,
+            });
+        }
+    }
+}
+
+// 深い木でも再帰しないように、比べる組を自前のスタックに積む
+// 親の変化は子より先に出る。同じ親の中では、足したり除いたりしたnodeが先
+pub(crate) fn diff_nodes<'a>(old: &'a Node, new: &'a Node) -> Vec<Change<'a>> {
+    let mut changes = Vec::new();
+    let mut stack: Vec<(&Node, &Node, NodePath)> = vec![(old, new, NodePath::default())];
+    while let Some((old, new, path)) = stack.pop() {
+        if let (NodeData::Text(a), NodeData::Text(b))
+        | (NodeData::Comment(a), NodeData::Comment(b)) = (&old.data, &new.data)
+        {
+            if a != b {
+                changes.push(Change::TextChanged {
+                    path,
+                    old: a,
+                    new: b,
+                });
+            }
+            continue;
+        }
+        if !same_shape(old, new) {
+            changes.push(Change::Replaced { path, old, new });
+            continue;
+        }
+        diff_attributes(old, new, &path, &mut changes);
+
+        let (old_children, new_children) = (child_nodes(old), child_nodes(new));
+        let prefix = old_children
+            .iter()
+            .zip(new_children)
+            .take_while(|(a, b)| same_shape(a, b))
+            .count();
+        let rest = old_children.len().min(new_children.len()) - prefix;
+        let suffix = old_children
+            .iter()
+            .rev()
+            .zip(new_children.iter().rev())
+            .take(rest)
+            .take_while(|(a, b)| same_shape(a, b))
+            .count();
+        let old_mid = prefix..old_children.len() - suffix;
+        let new_mid = prefix..new_children.len() - suffix;
+        let paired = old_mid.len().min(new_mid.len());
+
+        // 先に出すものを後から積む
+        let mut pairs: Vec<(&Node, &Node, NodePath)> = Vec::new();
+        for i in 0..prefix + paired {
+            pairs.push((&old_children[i], &new_children[i], path.child(i)));
+        }
+        let mut edits = Vec::new();
+        for (i, node) in old_children[..old_mid.end]
+            .iter()
+            .enumerate()
+            .skip(old_mid.start + paired)
+        {
+            edits.push(Change::Removed {
+                path: path.child(i),
+                node,
+            });
+        }
+        for (i, node) in new_children[..new_mid.end]
+            .iter()
+            .enumerate()
+            .skip(new_mid.start + paired)
+        {
+            edits.push(Change::Inserted {
+                path: path.child(i),
+                node,
+            });
+        }
+        for i in 0..suffix {
+            let (o, n) = (
+                old_children.len() - suffix + i,
+                new_children.len() - suffix + i,
+            );
+            pairs.push((&old_children[o], &new_children[n], path.child(n)));
+        }
+        changes.extend(edits);
+        stack.extend(pairs.into_iter().rev());
+    }
+    return changes;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Change;
+    use crate::html;
+    use crate::html::path::NodePath;
+
+    fn changes(old: &str, new: &str) -> Vec<String> {
+        let (old, new) = (html::parse(old).unwrap(), html::parse(new).unwrap());
+        return html::diff(&old.children[0], &new.children[0])
+            .iter()
+            .map(|change| match change {
+                Change::Inserted { path, node } => format!("{} inserted {:?}", path, node),
+                Change::Removed { path, node } => format!("{} removed {:?}", path, node),
+                Change::Replaced { path, old, new } => {
+                    format!("{} replaced {:?} with {:?}", path, old, new)
+                }
+                Change::TextChanged { path, old, new } => {
+                    format!("{} text {} -> {}", path, old, new)
+                }
+                Change::AttributeAdded { path, name, value } => {
+                    format!("{} +{}={}", path, name, value)
+                }
+                Change::AttributeRemoved { path, name, value } => {
+                    format!("{} -{}={}", path, name, value)
+                }
+                Change::AttributeChanged {
+                    path,
+                    name,
+                    old,
+                    new,
+                } => format!("{} {}: {} -> {}", path, name, old, new),
+            })
+            .collect();
+    }
+
+    #[test]
+    fn same_tree_has_no_changes() {
+        let html = "<ul class=\"a\"><li>one</li><li>two<!--x--></li></ul>";
+        assert!(changes(html, html).is_empty());
+    }
+
+    #[test]
+    fn text_edit() {
+        assert_eq!(
+            changes(
+                "<div><p>hello</p><p>world</p></div>",
+                "<div><p>hello</p><p>there</p></div>"
+            ),
+            vec!["1.0 text world -> there"]
+        );
+    }
+
+    #[test]
+    fn attribute_changes() {
+        assert_eq!(
+            changes(
+                "<div><a href=\"/a\" title=\"t\">x</a></div>",
+                "<div><a href=\"/b\" rel=\"next\">x</a></div>"
+            ),
+            vec!["0 href: /a -> /b", "0 -title=t", "0 +rel=next"]
+        );
+    }
+
+    #[test]
+    fn inserted_sibling() {
+        // 間に足した <img> だけが出て、後ろの <p> はずれた位置で比べる
+        let found = changes(
+            "<div><h1>t</h1><p>a</p><p>b</p></div>",
+            "<div><h1>t</h1><img src=\"x.png\" /><p>a</p><p>b</p></div>",
+        );
+        assert_eq!(found.len(), 1);
+        assert!(
+            found[0].starts_with("1 inserted Element(\"img\""),
+            "{:?}",
+            found
+        );
+
+        let found = changes("<ul><li>a</li><li>b</li></ul>", "<ul><li>a</li></ul>");
+        assert_eq!(found.len(), 1);
+        assert!(found[0].starts_with("1 removed"), "{:?}", found);
+    }
+
+    #[test]
+    fn replaced_elements_are_not_compared_inside() {
+        assert_eq!(
+            changes("<div><p>a</p></div>", "<div><section>a</section></div>").len(),
+            1
+        );
+        let (old, new) = (
+            html::parse("<div><p>a</p></div>").unwrap(),
+            html::parse("<div><section>b</section></div>").unwrap(),
+        );
+        let found = html::diff(&old.children[0], &new.children[0]);
+        assert!(
+            matches!(&found[..], [Change::Replaced { path, .. }] if *path == NodePath(vec![0]))
+        );
+    }
+
+    #[test]
+    fn trees_of_different_shapes() {
+        // 片方だけ深い木でも、積むのは組になった子だけ
+        let depth = 20_000;
+        let deep = format!(
+            "<div>{}x{}</div>",
+            "<b>".repeat(depth),
+            "</b>".repeat(depth)
+        );
+        let flat = format!("<div>{}</div>", "<i>y</i>".repeat(depth));
+        let found = changes(&deep, &flat);
+        assert_eq!(found.len(), depth);
+        assert!(found[..depth - 1]
+            .iter()
+            .all(|change| change.contains(" inserted ")));
+        assert!(
+            found[depth - 1].starts_with("0 replaced"),
+            "{:?}",
+            found.last()
+        );
+
+        let mut deeper = "<div>".repeat(depth);
+        deeper.push_str("old");
+        deeper.push_str(&"</div>".repeat(depth));
+        let changed = deeper.replace("old", "new");
+        let found = changes(&deeper, &changed);
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].split(' ').nth(1), Some("text"));
+    }
+}
@@ -0,0 +1,1368 @@
+use crate::css;
+use crate::css::errors::{CssParseError, SelectorParseError};
+use crate::css::media::MediaQueryList;
+use crate::css::selector::CompiledSelector;
+use crate::css::stylesheet::{MediaRule, Rule, Stylesheet};
+use crate::html;
+use crate::html::atom::Atom;
+use crate::html::encoding;
+use crate::html::errors::{NotElementError, ParseError, ReparseError, RetokenizeError};
+use crate::html::footprint::{FootprintCounter, MemoryFootprint};
+use crate::html::form::FormData;
+use crate::html::incremental::{self, Edit, ReparseResult, SourceSpans};
+use crate::html::loader::ResourceLoader;
+use crate::html::meta::{self, MetaRefresh};
+use crate::html::normalize;
+use crate::html::parser::{
+    debug_tree, Doctype, Node, NodeData, ParseMetrics, Parser, ParserOptions,
+};
+use crate::html::path::{self, NodePath};
+use crate::html::position::Position;
+use crate::html::script::{self, ScriptData};
+use crate::html::serializer::{inner_html, is_preformatted};
+use crate::html::shared_str::SharedStr;
+use crate::html::stats::{self, DocumentStats};
+use crate::html::tokenizer::Token;
+use crate::html::url;
+use std::fmt;
+use std::mem::size_of;
+use std::ops::{ControlFlow, Deref};
+use std::slice;
+use std::str::FromStr;
+use std::sync::Arc;
+
+// parseした結果。最上位のnodeを持つ
+// 最上位の最初のdoctypeはchildrenに入れず、doctypeに持つ
+#[derive(Clone, Default)]
+pub struct Document {
+    pub doctype: Option<Doctype>,
+    pub children: Vec<Node>,
+    doctype_span: Option<DoctypeSpan>,
+    metrics: Option<ParseMetrics>,
+    // reparse_editで最後に読んだsourceでの要素の範囲。次の書き換えで使い回す
+    source_spans: Option<SourceSpans>,
+}
+
+// doctypeがsourceのどこにあったか
+#[derive(Debug, Clone, PartialEq)]
+pub struct DoctypeSpan {
+    // "<" と ">" のtokenの位置。parserを通さずに作った文書では分からない
+    pub range: Option<(Position, Position)>,
+    // 最上位のnodeの中で何番目だったか (前のコメントなども数える)
+    pub index: usize,
+}
+
+// {:?} は最上位のnodeの要約を並べ、{:#?} は木にする (NodeのDebugと同じ)
+impl fmt::Debug for Document {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // doctypeはchildrenの前に並べる
+        let doctype = self
+            .doctype
+            .as_ref()
+            .map(|doctype| format!("Doctype({:?})", doctype.name.as_str()));
+        if f.alternate() {
+            let mut tree = doctype.unwrap_or_default();
+            if !tree.is_empty() && !self.children.is_empty() {
+                tree.push('\n');
+            }
+            tree += &debug_tree(&self.children);
+            return write!(f, "Document\n{}", indent_tree(&tree));
+        }
+        let summaries: Vec<String> = doctype
+            .into_iter()
+            .chain(self.children.iter().map(Node::debug_summary))
+            .collect();
+        return write!(f, "Document [{}]", summaries.join(", "));
+    }
+}
+
+fn indent_tree(tree: &str) -> String {
+    return tree
+        .lines()
+        .map(|line| format!("  {}", line))
+        .collect::<Vec<String>>()
+        .join("\n");
+}
+
+// 要素と、そこに至るまでの祖先。Nodeは親を持たないので、selectorで親や兄弟を辿るときに使う
+// 要素でないnodeを指すことはない。Nodeのメソッドはderefで使える
+#[derive(Clone)]
+pub struct ElementRef<'a, 'p> {
+    node: &'a Node,
+    // 親のchildrenの中の位置
+    index: usize,
+    // 最上位から親までの (node, 親のchildrenの中の位置)
+    ancestors: Ancestors<'a, 'p>,
+    roots: &'a [Node],
+}
+
+#[derive(Clone)]
+enum Ancestors<'a, 'p> {
+    // 木を辿っている途中のpathを借りる
+    Borrowed(&'p [(&'a Node, usize)]),
+    // 検索の結果は辿り終えた後も使うので、写して持つ。親に上がるときは使う長さを減らすだけ
+    Shared(Arc<[(&'a Node, usize)]>, usize),
+}
+
+impl<'a, 'p> Ancestors<'a, 'p> {
+    fn as_slice(&self) -> &[(&'a Node, usize)] {
+        return match self {
+            Ancestors::Borrowed(path) => path,
+            Ancestors::Shared(path, len) => &path[..*len],
+        };
+    }
+
+    // 最後の1つを除いたもの
+    fn parent(&self) -> Ancestors<'a, 'p> {
+        return match self {
+            Ancestors::Borrowed(path) => Ancestors::Borrowed(&path[..path.len().saturating_sub(1)]),
+            Ancestors::Shared(path, len) => Ancestors::Shared(path.clone(), len.saturating_sub(1)),
+        };
+    }
+}
+
+impl<'a, 'p> ElementRef<'a, 'p> {
+    pub fn node(&self) -> &'a Node {
+        return self.node;
+    }
+
+    pub fn index(&self) -> usize {
+        return self.index;
+    }
+
+    pub fn depth(&self) -> usize {
+        return self.ancestors.as_slice().len();
+    }
+
+    // 祖先の位置を持っているので、木を辿り直さずに作れる
+    pub fn path(&self) -> NodePath {
+        let mut indices: Vec<usize> = self
+            .ancestors
+            .as_slice()
+            .iter()
+            .map(|(_, index)| *index)
+            .collect();
+        indices.push(self.index);
+        return NodePath(indices);
+    }
+
+    pub fn name(&self) -> &'a Atom {
+        return match &self.node.data {
+            NodeData::Element { name, .. } => name,
+            _ => unreachable!("ElementRef always points at an element"),
+        };
+    }
+
+    // 属性名は大文字小文字を区別しない
+    pub fn attr(&self, name: &str) -> Option<&'a SharedStr> {
+        return self.node.attr(name);
+    }
+
+    pub fn id(&self) -> Option<&'a SharedStr> {
+        return self.node.attr("id");
+    }
+
+    // class属性を空白で区切ったもの。同じものが2度あればそのまま2度返す
+    pub fn classes(&self) -> impl Iterator<Item = &'a str> {
+        let class = self.node.attr("class").map_or("", |class| class.as_str());
+        return class.split_ascii_whitespace();
+    }
+
+    // 子孫のtextを、空白を1つにまとめてつないだもの
+    pub fn text(&self) -> String {
+        return self.node.text_content_normalized();
+    }
+
+    // 自分のタグを含めない、子のHTML
+    pub fn inner_html(&self) -> String {
+        return inner_html(self.node);
+    }
+
+    pub fn parent_element(&self) -> Option<ElementRef<'a, 'p>> {
+        let (node, index) = *self.ancestors.as_slice().last()?;
+        return Some(ElementRef {
+            node,
+            index,
+            ancestors: self.ancestors.parent(),
+            roots: self.roots,
+        });
+    }
+
+    // 自分も含めた兄弟。最上位ならDocument::children
+    // <template> の中身の要素なら、中身の並び
+    pub fn siblings(&self) -> &'a [Node] {
+        let parent = match self.ancestors.as_slice().last() {
+            Some((parent, _)) => parent,
+            None => return self.roots,
+        };
+        let children = parent.children();
+        if children
+            .get(self.index)
+            .is_some_and(|nd| core::ptr::eq(nd, self.node))
+        {
+            return children;
+        }
+        return parent.template_content().unwrap_or(children);
+    }
+
+    // Nodeは親を持たないので、祖先や兄弟を辿れるここで合わせる
+    pub fn matches_compiled(&self, selector: &CompiledSelector) -> bool {
+        return selector.matches(self);
+    }
+
+    // 子孫のうち合うもの。祖先は文書のものを見るので、"div p" の div は外側にあってもよい
+    pub fn select<'s>(&self, selector: &'s CompiledSelector) -> SelectIter<'a, 's> {
+        let mut path = self.ancestors.as_slice().to_vec();
+        path.push((self.node, self.index));
+        let mut iter = SelectIter {
+            selector,
+            roots: self.roots,
+            path,
+            stack: Vec::new(),
+            visited: 0,
+            template_content: false,
+        };
+        let depth = iter.path.len();
+        iter.push_children(self.node.children(), depth);
+        return iter;
+    }
+
+    // 直前の兄弟の要素 (textやcommentは飛ばす)
+    pub fn prev_element(&self) -> Option<ElementRef<'a, 'p>> {
+        let siblings = self.siblings();
+        let index = siblings[..self.index]
+            .iter()
+            .rposition(|nd| nd.is_element())?;
+        return Some(ElementRef {
+            node: &siblings[index],
+            index,
+            ancestors: self.ancestors.clone(),
+            roots: self.roots,
+        });
+    }
+}
+
+impl<'a, 'p> Deref for ElementRef<'a, 'p> {
+    type Target = Node;
+
+    fn deref(&self) -> &Node {
+        return self.node;
+    }
+}
+
+impl<'a, 'p> fmt::Debug for ElementRef<'a, 'p> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        return f
+            .debug_struct("ElementRef")
+            .field("name", self.name())
+            .field("path", &self.path())
+            .finish();
+    }
+}
+
+// 祖先が分からないので、最上位にあるものとして扱う (parent_elementはNone)
+impl<'a> TryFrom<&'a Node> for ElementRef<'a, 'a> {
+    type Error = NotElementError;
+
+    fn try_from(node: &'a Node) -> Result<ElementRef<'a, 'a>, NotElementError> {
+        if !node.is_element() {
+            return Err(NotElementError { kind: node.kind() });
+        }
+        return Ok(ElementRef {
+            node,
+            index: 0,
+            ancestors: Ancestors::Borrowed(&[]),
+            roots: slice::from_ref(node),
+        });
+    }
+}
+
+// Document::select_iter と ElementRef::select。walk_elementsと同じ順に辿る
+pub struct SelectIter<'a, 's> {
+    selector: &'s CompiledSelector,
+    roots: &'a [Node],
+    path: Vec<(&'a Node, usize)>,
+    // (node, 親の中の位置, 深さ)
+    stack: Vec<(&'a Node, usize, usize)>,
+    visited: usize,
+    // <template> の中身にも入るか
+    template_content: bool,
+}
+
+impl<'a, 's> SelectIter<'a, 's> {
+    // ここまでに合うか確かめた要素の数
+    pub fn visited(&self) -> usize {
+        return self.visited;
+    }
+
+    // 既定では <template> の中身は文書の一部ではないので辿らない。これを呼ぶと中身も探す
+    // 中身の要素のpathは、Document::node_atでは辿れない
+    pub fn include_template_content(mut self) -> SelectIter<'a, 's> {
+        self.template_content = true;
+        return self;
+    }
+
+    fn push_children(&mut self, children: &'a [Node], depth: usize) {
+        for (i, child) in children.iter().enumerate().rev() {
+            if child.is_element() {
+                self.stack.push((child, i, depth));
+            }
+        }
+    }
+}
+
+impl<'a, 's> Iterator for SelectIter<'a, 's> {
+    type Item = ElementRef<'a, 'a>;
+
+    fn next(&mut self) -> Option<ElementRef<'a, 'a>> {
+        while let Some((nd, index, depth)) = self.stack.pop() {
+            self.path.truncate(depth);
+            self.visited += 1;
+            let el = ElementRef {
+                node: nd,
+                index,
+                ancestors: Ancestors::Borrowed(&self.path),
+                roots: self.roots,
+            };
+            // 合ったときだけ祖先を写す
+            let found = match self.selector.matches(&el) {
+                true => Some(ElementRef {
+                    node: nd,
+                    index,
+                    ancestors: Ancestors::Shared(Arc::from(self.path.as_slice()), depth),
+                    roots: self.roots,
+                }),
+                false => None,
+            };
+            self.path.push((nd, index));
+            // 中身は子の後ろに辿る
+            if let Some(content) = nd.template_content().filter(|_| self.template_content) {
+                self.push_children(content, depth + 1);
+            }
+            self.push_children(nd.children(), depth + 1);
+            if found.is_some() {
+                return found;
+            }
+        }
+        return None;
+    }
+}
+
+impl Node {
+    // <iframe srcdoc="..."> の中身を、外の木とは別の文書として読む。iframeでないかsrcdocがなければNone
+    // 値は属性を読んだときに文字参照を戻してある。エラーの位置は値の中の位置
+    pub fn srcdoc_document(&self, options: &ParserOptions) -> Option<Result<Document, ParseError>> {
+        if !self.name().is_some_and(|name| *name == "iframe") {
+            return None;
+        }
+        let srcdoc = self.attr("srcdoc")?;
+        let mut parser = match Parser::builder().options(options.clone()).build() {
+            Ok(parser) => parser,
+            Err(err) => return Some(Err(err.into())),
+        };
+        return match parser.parse_str(srcdoc) {
+            Ok(nodes) => Some(Ok(Document::from_parsed(
+                nodes.unwrap_or_default(),
+                parser.doctype_span(),
+            )
+            .with_metrics(parser.metrics().cloned()))),
+            Err(err) => Some(Err(ParseError::InAttribute {
+                element: "iframe",
+                attribute: "srcdoc",
+                error: Box::new(err),
+            })),
+        };
+    }
+}
+
+impl Document {
+    // 最上位にdoctypeがあれば、最初のものをchildrenから外してdoctypeに移す
+    pub fn new(children: Vec<Node>) -> Document {
+        return Document::from_parsed(children, None);
+    }
+
+    pub(crate) fn from_parsed(
+        mut children: Vec<Node>,
+        range: Option<(Position, Position)>,
+    ) -> Document {
+        let found = children
+            .iter()
+            .enumerate()
+            .find_map(|(index, nd)| match &nd.data {
+                NodeData::Doctype(doctype) => Some((index, doctype.clone())),
+                _ => None,
+            });
+        let (index, doctype) = match found {
+            Some(found) => found,
+            None => {
+                return Document {
+                    doctype: None,
+                    children,
+                    doctype_span: None,
+                    metrics: None,
+                    source_spans: None,
+                }
+            }
+        };
+        children.remove(index);
+        return Document {
+            doctype: Some(doctype),
+            children,
+            doctype_span: Some(DoctypeSpan { range, index }),
+            metrics: None,
+            source_spans: None,
+        };
+    }
+
+    // doctypeがあった場所。診断の表示に使う
+    pub fn doctype_node_span(&self) -> Option<&DoctypeSpan> {
+        return self.doctype_span.as_ref();
+    }
+
+    pub(crate) fn with_metrics(mut self, metrics: Option<ParseMetrics>) -> Document {
+        self.metrics = metrics;
+        return self;
+    }
+
+    // collect_metricsを付けて読んだときの数字。parseで読んだ文書や手で作った文書ではNone
+    pub fn metrics(&self) -> Option<&ParseMetrics> {
+        return self.metrics.as_ref();
+    }
+
+    // 木を一度辿って数える
+    pub fn memory_footprint(&self) -> MemoryFootprint {
+        let mut counter = FootprintCounter::default();
+        counter.heap(self.children.capacity() * size_of::<Node>());
+        if let Some(doctype) = &self.doctype {
+            counter.string(&doctype.name);
+        }
+
+        let mut stack: Vec<&Node> = self.children.iter().collect();
+        while let Some(nd) = stack.pop() {
+            counter.node();
+            match &nd.data {
+                NodeData::Element {
+                    name,
+                    attrs,
+                    children,
+                    ..
+                } => {
+                    counter.string(&name.to_shared());
+                    counter.heap(attrs.heap_bytes());
+                    for attr in attrs.iter() {
+                        counter.string(&attr.name);
+                        counter.string(&attr.value);
+                        counter.attribute(&attr.value);
+                    }
+                    counter.heap(children.capacity() * size_of::<Node>());
+                    stack.extend(children.iter());
+                }
+                NodeData::Text(s) => {
+                    counter.string(s);
+                    counter.text(s);
+                }
+                NodeData::Comment(s) | NodeData::Template(s) => counter.string(s),
+                NodeData::Doctype(doctype) => counter.string(&doctype.name),
+            }
+        }
+
+        return counter.finish();
+    }
+
+    // nodeの種類ごとの数や入れ子の深さなど。木を一度辿って数える
+    pub fn stats(&self) -> DocumentStats {
+        return stats::collect(self.doctype.is_some(), &self.children);
+    }
+
+    // 要素を文書の順に辿る。Breakを返すとそこで止める
+    pub fn walk_elements<'a, F>(&'a self, mut f: F)
+    where
+        F: FnMut(ElementRef<'a, '_>) -> ControlFlow<()>,
+    {
+        let mut path: Vec<(&'a Node, usize)> = Vec::new();
+        // (node, 親の中の位置, 深さ)
+        let mut stack: Vec<(&'a Node, usize, usize)> = Vec::new();
+        let push_children =
+            |stack: &mut Vec<(&'a Node, usize, usize)>, children: &'a [Node], depth: usize| {
+                for (i, child) in children.iter().enumerate().rev() {
+                    if child.is_element() {
+                        stack.push((child, i, depth));
+                    }
+                }
+            };
+
+        push_children(&mut stack, &self.children, 0);
+        while let Some((nd, index, depth)) = stack.pop() {
+            path.truncate(depth);
+            let el = ElementRef {
+                node: nd,
+                index,
+                ancestors: Ancestors::Borrowed(&path),
+                roots: &self.children,
+            };
+            if f(el).is_break() {
+                return;
+            }
+            path.push((nd, index));
+            push_children(&mut stack, nd.children(), depth + 1);
+        }
+    }
+
+    pub fn query_selector(
+        &self,
+        selectors: &str,
+    ) -> Result<Option<ElementRef<'_, '_>>, SelectorParseError> {
+        let selector = CompiledSelector::compile(selectors)?;
+        return Ok(self.select_first(&selector));
+    }
+
+    pub fn query_selector_all(
+        &self,
+        selectors: &str,
+    ) -> Result<Vec<ElementRef<'_, '_>>, SelectorParseError> {
+        let selector = CompiledSelector::compile(selectors)?;
+        return Ok(self.select(&selector));
+    }
+
+    // 同じselectorを多くの文書に使うときは、compileしたものを渡す
+    pub fn select(&self, selector: &CompiledSelector) -> Vec<ElementRef<'_, '_>> {
+        return self.select_iter(selector).collect();
+    }
+
+    pub fn select_first(&self, selector: &CompiledSelector) -> Option<ElementRef<'_, '_>> {
+        return self.select_iter(selector).next();
+    }
+
+    // 合う要素を文書の順に、次を求められたときに探す。take(3) なら3つ目の後ろは辿らない
+    pub fn select_iter<'a, 's>(&'a self, selector: &'s CompiledSelector) -> SelectIter<'a, 's> {
+        let mut iter = SelectIter {
+            selector,
+            roots: &self.children,
+            path: Vec::new(),
+            stack: Vec::new(),
+            visited: 0,
+            template_content: false,
+        };
+        iter.push_children(&self.children, 0);
+        return iter;
+    }
+
+    // <style> の中身を文書の順にparseする。読めなかった部分は各Stylesheet::errorsに残る
+    pub fn stylesheets(&self) -> Vec<Stylesheet> {
+        let mut sheets = Vec::new();
+        let mut stack: Vec<&Node> = self.children.iter().rev().collect();
+        while let Some(nd) = stack.pop() {
+            if nd.name().is_some_and(|name| *name == "style") {
+                // type="text/css" 以外は (空も含めて) CSSではない
+                let is_css = match nd.attr("type") {
+                    Some(ty) => ty.is_empty() || ty.eq_ignore_ascii_case("text/css"),
+                    None => true,
+                };
+                if is_css {
+                    // raw textなので中身は一つのtextにまとまっている
+                    let text = match nd.children().first().and_then(|child| child.text_value()) {
+                        Some(text) => text.as_str(),
+                        None => "",
+                    };
+                    sheets.push(css::parse_stylesheet_recovering(text));
+                }
+                continue;
+            }
+            stack.extend(nd.children().iter().rev());
+        }
+        return sheets;
+    }
+
+    // 最初の <base href> をbaseから解決したもの。なければbaseのまま
+    pub fn base_url(&self, base: &str) -> String {
+        let mut stack: Vec<&Node> = self.children.iter().rev().collect();
+        while let Some(nd) = stack.pop() {
+            if nd.name().is_some_and(|name| *name == "base") {
+                if let Some(href) = nd.attr("href") {
+                    return url::resolve(base, href);
+                }
+            }
+            stack.extend(nd.children().iter().rev());
+        }
+        return base.to_string();
+    }
+
+    // 最初に読める <meta http-equiv="refresh">
+    pub fn meta_refresh(&self) -> Option<MetaRefresh> {
+        let mut found = None;
+        self.walk_elements(|el| {
+            let nd = el.node();
+            if let Some(content) = http_equiv(nd, "refresh") {
+                found = meta::parse_refresh(content);
+                if found.is_some() {
+                    return ControlFlow::Break(());
+                }
+            }
+            return ControlFlow::Continue(());
+        });
+        return found;
+    }
+
+    // 最初の <meta charset> か <meta http-equiv="Content-Type" content="...; charset=..."> のlabel
+    pub fn meta_charset(&self) -> Option<&str> {
+        let mut found = None;
+        self.walk_elements(|el| {
+            let nd = el.node();
+            if let Some(charset) = nd.attr("charset").filter(|_| is_meta(nd)) {
+                found = Some(charset.trim());
+            } else if let Some(content) = http_equiv(nd, "content-type") {
+                found = encoding::charset_from_content_type(content);
+            }
+            if found.is_some() {
+                return ControlFlow::Break(());
+            }
+            return ControlFlow::Continue(());
+        });
+        return found;
+    }
+
+    // input typeやlink relなど、決まった語から選ぶ属性の値をそろえる。書き換えた数を返す
+    pub fn normalize_attributes(&mut self) -> usize {
+        return normalize::normalize_attributes(&mut self.children);
+    }
+
+    // preserve_whitespaceで読んだ文書から、空白だけのtextを消す。消した数を返す
+    // preやtextareaなどの中の空白は中身なので残す
+    pub fn strip_blank_text(&mut self) -> usize {
+        let before = self.children.len();
+        self.children.retain(|nd| !nd.is_blank());
+        let mut removed = before - self.children.len();
+        let mut stack: Vec<&mut Node> = self.children.iter_mut().collect();
+        while let Some(nd) = stack.pop() {
+            if is_preformatted(nd) {
+                continue;
+            }
+            if let Some(children) = nd.children_mut() {
+                let before = children.len();
+                children.retain(|child| !child.is_blank());
+                removed += before - children.len();
+                stack.extend(children.iter_mut());
+            }
+        }
+        return removed;
+    }
+
+    // nodeはこの文書の中のもの (参照が同じもの) に限る
+    pub fn path_to(&self, node: &Node) -> Option<NodePath> {
+        return path::path_to(&self.children, node);
+    }
+
+    // 木を変えたあとの古いpathなど、辿れなければNone
+    pub fn node_at(&self, path: &NodePath) -> Option<&Node> {
+        return path::node_at(&self.children, path);
+    }
+
+    // sourceは書き換える前の文書。書き換えを含むいちばん内側の要素だけを読み直し、
+    // 要素の範囲が変わりうるときは全体を読み直す。全体が読めなければ木はそのまま
+    pub fn reparse_edit(
+        &mut self,
+        edit: Edit,
+        source: &str,
+    ) -> Result<ReparseResult, ReparseError> {
+        return incremental::reparse_edit(self, &edit, source, &ParserOptions::default());
+    }
+
+    // optionsはこの文書を読んだときのもの。読み直すところも同じoptionsで読む
+    pub fn reparse_edit_with_options(
+        &mut self,
+        edit: Edit,
+        source: &str,
+        options: &ParserOptions,
+    ) -> Result<ReparseResult, ReparseError> {
+        return incremental::reparse_edit(self, &edit, source, options);
+    }
+
+    pub(crate) fn source_spans(&self) -> Option<&SourceSpans> {
+        return self.source_spans.as_ref();
+    }
+
+    pub(crate) fn take_source_spans(&mut self) -> Option<SourceSpans> {
+        return self.source_spans.take();
+    }
+
+    pub(crate) fn set_source_spans(&mut self, spans: SourceSpans) {
+        self.source_spans = Some(spans);
+    }
+
+    // sourceはこの文書を読んだ文字列。ndの開きタグから閉じタグまでを読み直したtoken列を返す
+    // tokenの位置はsourceの先頭から数える。TokenizerOptionsはdefault
+    pub fn retokenize(
+        &self,
+        nd: &Node,
+        source: &str,
+    ) -> Result<Option<Box<Token>>, RetokenizeError> {
+        return incremental::retokenize(self, nd, source);
+    }
+
+    // <meta http-equiv="content-security-policy"> のcontentを書かれたまま、文書の順に
+    // 複数あればどれも守る
+    // type="application/json" やimportmapなど、実行しないscriptの中身を文書の順に
+    pub fn script_data(&self) -> Vec<ScriptData> {
+        let mut scripts = Vec::new();
+        self.walk_elements(|el| {
+            let nd = el.node();
+            if !nd.name().is_some_and(|name| *name == "script") {
+                return ControlFlow::Continue(());
+            }
+            let mime_type = match nd.attr("type") {
+                Some(mime_type) if script::is_data_type(mime_type) => mime_type,
+                _ => return ControlFlow::Continue(()),
+            };
+            let text: String = nd
+                .children()
+                .iter()
+                .filter_map(|child| match &child.data {
+                    NodeData::Text(s) => Some(s.as_str()),
+                    _ => None,
+                })
+                .collect();
+            scripts.push(ScriptData {
+                id: nd.attr("id").map(|id| id.to_string()),
+                mime_type: mime_type.to_string(),
+                text,
+                path: el.path(),
+            });
+            return ControlFlow::Continue(());
+        });
+        return scripts;
+    }
+
+    pub fn content_security_policies(&self) -> Vec<&str> {
+        let mut policies = Vec::new();
+        self.walk_elements(|el| {
+            if let Some(content) = http_equiv(el.node(), "content-security-policy") {
+                policies.push(content);
+            }
+            return ControlFlow::Continue(());
+        });
+        return policies;
+    }
+
+    // 文書の中の <form> を順に
+    pub fn forms(&self) -> Vec<FormData> {
+        let mut forms = Vec::new();
+        self.walk_elements(|el| {
+            let nd = el.node();
+            if nd.name().is_some_and(|name| *name == "form") {
+                forms.push(FormData::from_form(nd));
+            }
+            return ControlFlow::Continue(());
+        });
+        return forms;
+    }
+
+    // <link rel="stylesheet"> の先を文書の順に読む。同じURLは一度だけ読む
+    // media属性があれば、中のruleを@mediaで包んで返す
+    pub fn load_stylesheets(
+        &self,
+        base: &str,
+        loader: &impl ResourceLoader,
+    ) -> Vec<(String, Result<Stylesheet, CssParseError>)> {
+        let base = self.base_url(base);
+        let mut loaded: Vec<(String, Result<Stylesheet, CssParseError>)> = Vec::new();
+        let mut stack: Vec<&Node> = self.children.iter().rev().collect();
+        while let Some(nd) = stack.pop() {
+            stack.extend(nd.children().iter().rev());
+            if !nd.name().is_some_and(|name| *name == "link") || !is_stylesheet_link(nd) {
+                continue;
+            }
+            let href = match nd.attr("href") {
+                Some(href) if !href.trim().is_empty() => href,
+                _ => continue,
+            };
+            let url = url::resolve(&base, href);
+            if loaded.iter().any(|(seen, _)| *seen == url) {
+                continue;
+            }
+
+            let queries = match nd.attr("media") {
+                Some(media) if !media.trim().is_empty() => match MediaQueryList::parse(media) {
+                    Ok(queries) => Some(queries),
+                    // 読めないmediaはどの環境にも合わないので、取りにも行かない
+                    Err(err) => {
+                        loaded.push((url, Err(err.into())));
+                        continue;
+                    }
+                },
+                _ => None,
+            };
+            let result = loader.load(&url).map(|text| {
+                let mut sheet = css::parse_stylesheet_recovering(&text);
+                if let Some(queries) = queries {
+                    sheet.rules = vec![Rule::Media(MediaRule {
+                        queries,
+                        rules: sheet.rules,
+                    })];
+                }
+                return sheet;
+            });
+            loaded.push((url, result.map_err(CssParseError::from)));
+        }
+        return loaded;
+    }
+}
+
+/// html::parse と同じく、閉じタグが合わなければエラーにする。
+/// 直しながら読むなら、lenientを付けて html::parse_with_options を使う
+///
+/// ```
+/// use browser::Document;
+///
+/// fn title(html: &str) -> Result<usize, browser::ParseError> {
+///     let doc: Document = html.parse()?;
+///     return Ok(doc.query_selector_all("h1").unwrap().len());
+/// }
+///
+/// assert_eq!(title("<h1>a</h1><h1>b</h1>").unwrap(), 2);
+/// assert!(title("<h1>a</p>").is_err());
+/// ```
+impl FromStr for Document {
+    type Err = ParseError;
+
+    fn from_str(html: &str) -> Result<Document, ParseError> {
+        return html::parse(html);
+    }
+}
+
+/// ```
+/// use browser::Document;
+///
+/// fn count(html: &str) -> Result<usize, browser::ParseError> {
+///     let doc = Document::try_from(html)?;
+///     return Ok(doc.children.len());
+/// }
+///
+/// assert_eq!(count("<p>a</p><p>b</p>").unwrap(), 2);
+/// ```
+impl TryFrom<&str> for Document {
+    type Error = ParseError;
+
+    fn try_from(html: &str) -> Result<Document, ParseError> {
+        return html::parse(html);
+    }
+}
+
+// <meta http-equiv=name content=...> のcontent
+fn is_meta(nd: &Node) -> bool {
+    return nd.name().is_some_and(|tag| *tag == "meta");
+}
+
+fn http_equiv<'a>(nd: &'a Node, name: &str) -> Option<&'a str> {
+    if !is_meta(nd) {
+        return None;
+    }
+    let equiv = nd.attr("http-equiv")?;
+    if !equiv.trim().eq_ignore_ascii_case(name) {
+        return None;
+    }
+    return nd.attr("content").map(|content| content.as_str());
+}
+
+// rel="stylesheet" のうち、alternateは選ばれるまで使わないので除く
+// typeが書かれていればtext/cssだけ
+fn is_stylesheet_link(nd: &Node) -> bool {
+    let rel = match nd.attr("rel") {
+        Some(rel) => rel,
+        None => return false,
+    };
+    let mut is_stylesheet = false;
+    for keyword in rel.split_ascii_whitespace() {
+        if keyword.eq_ignore_ascii_case("alternate") {
+            return false;
+        }
+        if keyword.eq_ignore_ascii_case("stylesheet") {
+            is_stylesheet = true;
+        }
+    }
+    return match nd.attr("type") {
+        Some(ty) => is_stylesheet && (ty.is_empty() || ty.eq_ignore_ascii_case("text/css")),
+        None => is_stylesheet,
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::css::errors::CssParseError;
+    use crate::css::media::MediaEnv;
+    use crate::css::selector::CompiledSelector;
+    use crate::css::stylesheet::CssFormat;
+    use crate::html;
+    use crate::html::document::{Document, ElementRef};
+    use crate::html::errors::{LoadError, NotElementError};
+    use crate::html::loader::MapLoader;
+    use crate::html::parser::{NodeKind, Parser, ParserOptions};
+    use crate::html::path::NodePath;
+    use crate::html::serializer::{
+        document_html, document_html_with, document_tree_test_format, inner_html, SerializeOptions,
+    };
+    use std::time::Duration;
+
+    #[test]
+    fn footprint_of_empty_document() {
+        let doc = html::parse("").unwrap();
+        let footprint = doc.memory_footprint();
+        assert_eq!(footprint.nodes, 0);
+        assert_eq!(footprint.text_bytes, 0);
+        assert_eq!(footprint.attribute_bytes, 0);
+        assert_eq!(footprint.heap_bytes, 0);
+    }
+
+    #[test]
+    fn footprint_counts_text_and_attributes() {
+        let doc = html::parse("<p class=\"lead\">hello world</p><!-- c -->").unwrap();
+        let footprint = doc.memory_footprint();
+        // p, hello, world, comment
+        assert_eq!(footprint.nodes, 4);
+        assert_eq!(footprint.text_bytes, 10);
+        assert_eq!(footprint.attribute_bytes, 4);
+        assert!(footprint.heap_bytes > 0);
+    }
+
+    #[test]
+    fn large_script_is_not_duplicated() {
+        let script = "if (a < b) { x = \"<p>\"; }\n".repeat(41_000);
+        assert!(script.len() > 1024 * 1024);
+        let html = format!(
+            "<html><head><script>{}</script></head><body></body></html>",
+            script
+        );
+
+        let doc = html::parse(&html).unwrap();
+        let text = &doc.children[0].children()[0].children()[0].children()[0];
+        assert_eq!(text.text_value().unwrap(), script.as_str());
+
+        // 中身はsourceの一部を指すだけなので、source一つ分しか使わない
+        let footprint = doc.memory_footprint();
+        assert_eq!(footprint.text_bytes, script.len());
+        assert!(
+            footprint.heap_bytes < html.len() + 4096,
+            "{} bytes for a {} byte document",
+            footprint.heap_bytes,
+            html.len()
+        );
+    }
+
+    #[test]
+    fn stylesheets_in_document_order() {
+        let doc = html::parse(
+            "<html><head><style>p > a { color: red; }</style>\
+             <style type=\"text/less\">@x: 1;</style></head>\
+             <body><style>h1 { margin: 0 } p { color blue; font-size: 2em }</style></body></html>",
+        )
+        .unwrap();
+        let sheets = doc.stylesheets();
+        assert_eq!(sheets.len(), 2);
+
+        assert!(sheets[0].errors.is_empty());
+        let rule = sheets[0].style_rules().next().unwrap();
+        assert_eq!(rule.selectors.to_string(), "p > a");
+
+        // 壊れた宣言があっても残りは読む
+        assert_eq!(sheets[1].errors.len(), 1);
+        let rules: Vec<_> = sheets[1].style_rules().collect();
+        assert_eq!(rules.len(), 2);
+        assert_eq!(rules[1].declarations[0].name, "font-size");
+    }
+
+    #[test]
+    fn load_linked_stylesheets() {
+        let doc = html::parse(
+            "<html><head>\
+             <link rel=\"stylesheet\" href=\"css/base.css\" />\
+             <link rel=\"alternate stylesheet\" href=\"css/dark.css\" title=\"dark\" />\
+             <link rel=\"icon\" href=\"favicon.ico\" />\
+             <link rel=\"Stylesheet\" href=\"/print.css\" media=\"print\" />\
+             <link rel=\"stylesheet\" href=\"./css/../css/base.css\" />\
+             <link rel=\"stylesheet\" href=\"missing.css\" />\
+             <link rel=\"stylesheet\" href=\"bad-media.css\" media=\"screen and foo\" />\
+             <link rel=\"stylesheet\" href=\"x.less\" type=\"text/less\" />\
+             </head><body></body></html>",
+        )
+        .unwrap();
+        let mut loader = MapLoader::new();
+        loader.insert("https://example.com/docs/css/base.css", "p { color: red }");
+        loader.insert(
+            "https://example.com/docs/css/dark.css",
+            "p { color: white }",
+        );
+        loader.insert("https://example.com/print.css", "p { display: none }");
+
+        let sheets = doc.load_stylesheets("https://example.com/docs/index.html", &loader);
+        let urls: Vec<&str> = sheets.iter().map(|(url, _)| url.as_str()).collect();
+        assert_eq!(
+            urls,
+            vec![
+                "https://example.com/docs/css/base.css",
+                "https://example.com/print.css",
+                "https://example.com/docs/missing.css",
+                "https://example.com/docs/bad-media.css",
+            ]
+        );
+
+        let base = sheets[0].1.as_ref().unwrap();
+        assert_eq!(base.to_css(CssFormat::Compact), "p{color:red}");
+        // media属性は@mediaとして残るので、環境ごとに評価される
+        let print = sheets[1].1.as_ref().unwrap();
+        assert_eq!(
+            print.to_css(CssFormat::Compact),
+            "@media print{p{display:none}}"
+        );
+        assert_eq!(print.style_rules_for(&MediaEnv::default()).len(), 0);
+        assert_eq!(
+            print.style_rules_for(&MediaEnv::print(800.0, 600.0)).len(),
+            1
+        );
+
+        assert!(matches!(
+            sheets[2].1,
+            Err(CssParseError::Load(LoadError::NotFound { .. }))
+        ));
+        assert!(matches!(
+            sheets[3].1,
+            Err(CssParseError::InvalidMediaQuery(_))
+        ));
+    }
+
+    #[test]
+    fn base_element_changes_base_url() {
+        let doc = html::parse(
+            "<html><head><base href=\"/static/\" />\
+             <link rel=\"stylesheet\" href=\"a.css\" /></head></html>",
+        )
+        .unwrap();
+        assert_eq!(
+            doc.base_url("https://example.com/docs/index.html"),
+            "https://example.com/static/"
+        );
+        let sheets = doc.load_stylesheets("https://example.com/docs/index.html", &MapLoader::new());
+        assert_eq!(sheets[0].0, "https://example.com/static/a.css");
+    }
+
+    #[test]
+    fn meta_refresh_and_csp() {
+        let doc = html::parse(
+            "<html><head><base href=\"https://example.com/app/\" />\
+             <meta http-equiv=\"Refresh\" content=\"soon\" />\
+             <meta http-equiv=\"refresh\" content=\"0;URL='next.html'\" />\
+             <meta http-equiv=\"refresh\" content=\"5\" />\
+             <meta http-equiv=\"Content-Security-Policy\" content=\"default-src 'self'\" />\
+             <meta name=\"content-security-policy\" content=\"ignored\" />\
+             <meta http-equiv=\"content-security-policy\" content=\"img-src *\" />\
+             </head></html>",
+        )
+        .unwrap();
+        // 読めないcontentは飛ばす
+        let refresh = doc.meta_refresh().unwrap();
+        assert_eq!(refresh.delay, 0);
+        assert_eq!(refresh.url.as_deref(), Some("next.html"));
+        assert_eq!(
+            refresh.target(&doc.base_url("https://example.com/index.html")),
+            "https://example.com/app/next.html"
+        );
+        assert_eq!(
+            doc.content_security_policies(),
+            vec!["default-src 'self'", "img-src *"]
+        );
+
+        let doc = html::parse("<p>no refresh</p>").unwrap();
+        assert_eq!(doc.meta_refresh(), None);
+        assert!(doc.content_security_policies().is_empty());
+    }
+
+    #[test]
+    fn strip_blank_text_keeps_preformatted() {
+        let html =
+            "<ul>\n  <li>one</li>\n  <li> two  words </li>\n</ul>\n<pre>\n<b>x</b>  y\n</pre>\n";
+        let nodes = Parser::builder()
+            .preserve_whitespace()
+            .parse(html)
+            .unwrap()
+            .unwrap();
+        let mut doc = Document::new(nodes);
+        let ul = &doc.children[0];
+        assert_eq!(ul.children().len(), 5);
+        assert!(ul.children()[0].is_blank());
+        assert!(!ul.children()[1].is_blank());
+        assert_eq!(ul.element_children().count(), 2);
+        assert_eq!(ul.text_content_normalized(), "one two words");
+
+        // ulの中の3つと、最上位の2つ。preの中の "\n" は残す
+        assert_eq!(doc.strip_blank_text(), 5);
+        assert_eq!(doc.children.len(), 2);
+        assert_eq!(doc.children[0].children().len(), 2);
+        let pre = &doc.children[1];
+        assert!(pre.children()[0].is_blank());
+        assert_eq!(pre.children().len(), 3);
+        assert_eq!(pre.text_content_normalized(), "x y");
+        assert_eq!(doc.strip_blank_text(), 0);
+    }
+
+    #[test]
+    fn srcdoc_is_parsed_as_its_own_document() {
+        let html = "<div><iframe srcdoc=\"&lt;!DOCTYPE html&gt;&lt;p class=&quot;a&quot; title='x &amp;amp; y'&gt;hi&lt;/p&gt;\"></iframe></div>";
+        let doc = html::parse(html).unwrap();
+        let iframe = doc.query_selector("iframe").unwrap().unwrap();
+        let inner = iframe
+            .srcdoc_document(&ParserOptions::default())
+            .unwrap()
+            .unwrap();
+        assert_eq!(inner.doctype.as_ref().unwrap().name, "html");
+        let p = inner.query_selector("p.a").unwrap().unwrap();
+        // 外で1回戻した "&amp;" は、中の文書でもう1回戻る
+        assert_eq!(p.attr("title").unwrap(), "x & y");
+        // 外の木には入らない
+        assert!(doc.query_selector("p").unwrap().is_none());
+        assert_eq!(iframe.children().len(), 0);
+
+        // iframeでなければNone
+        let div = doc.query_selector("div").unwrap().unwrap();
+        assert!(div.srcdoc_document(&ParserOptions::default()).is_none());
+        let doc = html::parse("<iframe src=\"a.html\"></iframe>").unwrap();
+        assert!(doc.children[0]
+            .srcdoc_document(&ParserOptions::default())
+            .is_none());
+    }
+
+    #[test]
+    fn srcdoc_errors_point_into_the_attribute() {
+        let html = "<p>outer</p>\n<iframe srcdoc=\"&lt;p&gt;x&lt;/p&gt;&lt;b =&gt;\"></iframe>";
+        let doc = html::parse(html).unwrap();
+        let iframe = doc.query_selector("iframe").unwrap().unwrap();
+        let err = iframe
+            .srcdoc_document(&ParserOptions::default())
+            .unwrap()
+            .unwrap_err();
+        // 値 "<p>x</p><b =>" の "="
+        let pos = err.position().unwrap();
+        assert_eq!((pos.line_no, pos.at_line), (1, 12));
+        assert!(err
+            .to_string()
+            .ends_with("(in the srcdoc attribute of <iframe>)"));
+        let srcdoc = iframe.attr("srcdoc").unwrap();
+        assert!(err.render("srcdoc", srcdoc).contains("1 | <p>x</p><b =>"));
+    }
+
+    #[test]
+    fn footprint_grows_linearly() {
+        let item = "<li class=\"item\"><a href=\"/x\">link</a> some text</li>";
+        let small = html::parse(&item.repeat(100)).unwrap().memory_footprint();
+        let large = html::parse(&item.repeat(200)).unwrap().memory_footprint();
+
+        assert_eq!(large.nodes, small.nodes * 2);
+        assert_eq!(large.text_bytes, small.text_bytes * 2);
+        assert_eq!(large.attribute_bytes, small.attribute_bytes * 2);
+        // Vecの伸び方の分だけずれる
+        let ratio = large.heap_bytes as f64 / small.heap_bytes as f64;
+        assert!(1.8 < ratio && ratio < 2.2, "{:?} -> {:?}", small, large);
+    }
+
+    #[test]
+    fn metrics_count_small_document() {
+        let html = "<div><p>a b</p><!-- c --></div><span>";
+        let options = ParserOptions {
+            lenient: true,
+            collect_metrics: true,
+            ..ParserOptions::default()
+        };
+        let doc = html::parse_with_options(html, &options).unwrap();
+        let metrics = doc.metrics().unwrap();
+        assert_eq!(metrics.input_bytes, html.len());
+        // Eofまで
+        assert_eq!(metrics.token_count, 31);
+        // div, p, "a", "b", コメント, span
+        assert_eq!(metrics.node_count, 6);
+        assert_eq!(metrics.max_depth, 2);
+        // spanを閉じた
+        assert_eq!(metrics.warning_count, 1);
+
+        // 付けなければ数えない
+        assert!(html::parse("<p>a</p>").unwrap().metrics().is_none());
+        let doc = html::parse_with_options("<p>a</p>", &ParserOptions::default()).unwrap();
+        assert!(doc.metrics().is_none());
+    }
+
+    #[test]
+    fn metrics_time_large_document() {
+        let html = "<div class=\"row\"><p>some text here</p></div>".repeat(5000);
+        let options = ParserOptions {
+            collect_metrics: true,
+            ..ParserOptions::default()
+        };
+        let doc = html::parse_with_options(&html, &options).unwrap();
+        let metrics = doc.metrics().unwrap();
+        assert!(metrics.tokenize_duration > Duration::ZERO, "{:?}", metrics);
+        assert!(metrics.parse_duration > Duration::ZERO, "{:?}", metrics);
+        assert_eq!(metrics.node_count, 5000 * 5);
+    }
+
+    #[test]
+    fn element_ref_conveniences() {
+        let html = "<div id=\"main\" class=\"a  b\"><p class=\"lead\">hello big world</p><p>x <b>bold</b></p></div>";
+        let doc = html::parse(html).unwrap();
+        let p = doc.query_selector("p.lead").unwrap().unwrap();
+        assert_eq!(p.name().as_str(), "p");
+        assert_eq!(p.attr("class").unwrap(), "lead");
+        assert_eq!(p.id(), None);
+        assert_eq!(p.text(), "hello big world");
+        assert_eq!(p.inner_html(), "hello big world");
+
+        let div = p.parent_element().unwrap();
+        assert_eq!(div.id().unwrap(), "main");
+        assert_eq!(div.classes().collect::<Vec<_>>(), vec!["a", "b"]);
+        assert!(div.parent_element().is_none());
+
+        // 部分木の中だけを探すが、祖先は文書のものを見る
+        let in_div = CompiledSelector::compile("div > p").unwrap();
+        let texts: Vec<String> = div.select(&in_div).map(|el| el.text()).collect();
+        assert_eq!(texts, vec!["hello big world", "xbold"]);
+        assert_eq!(p.select(&in_div).count(), 0);
+        let bold = CompiledSelector::compile("div b").unwrap();
+        assert_eq!(p.select(&bold).count(), 0);
+        let second = doc.query_selector("p:last-child").unwrap().unwrap();
+        assert_eq!(second.inner_html(), "x<b>bold</b>");
+        let found = second.select(&bold).next().unwrap();
+        assert_eq!(found.path(), NodePath(vec![0, 1, 1]));
+    }
+
+    #[test]
+    fn noscript_fixture_with_and_without_scripting() {
+        let path = format!(
+            "{}/tests/fixtures/corpus/noscript.html",
+            env!("CARGO_MANIFEST_DIR")
+        );
+        let html = std::fs::read_to_string(path).unwrap();
+        let pixel = CompiledSelector::compile("noscript img[src]").unwrap();
+        for scripting_enabled in [false, true] {
+            let options = ParserOptions {
+                scripting_enabled,
+                ..ParserOptions::default()
+            };
+            let doc = html::parse_with_options(&html, &options).unwrap();
+            let noscripts = doc.query_selector_all("noscript").unwrap();
+            assert_eq!(noscripts.len(), 3);
+            if scripting_enabled {
+                // 中身はtextなので、中の要素もmetaも見えない
+                assert!(doc.select(&pixel).is_empty());
+                assert!(doc.meta_refresh().is_none());
+                let children = noscripts[1].children();
+                assert_eq!(children.len(), 1);
+                assert!(children[0]
+                    .text_value()
+                    .unwrap()
+                    .starts_with("<img src=\"https://pixel.example.com/t.gif?id=42\""));
+            } else {
+                // scriptingなしでは中身も文書なので、tracking pixelもmetaも見える
+                let pixels = doc.select(&pixel);
+                assert_eq!(pixels.len(), 1);
+                assert_eq!(
+                    pixels[0].attr("src").unwrap(),
+                    "https://pixel.example.com/t.gif?id=42"
+                );
+                assert_eq!(doc.meta_refresh().unwrap().url.as_deref(), Some("/basic"));
+                let notice = doc.query_selector("noscript p.notice").unwrap().unwrap();
+                assert_eq!(
+                    notice.text(),
+                    "Turn on JavaScript to see the full catalogue."
+                );
+            }
+
+            // 同じflagで書き出して読み直すと同じ木になる
+            let serialize = SerializeOptions {
+                scripting_enabled,
+                ..SerializeOptions::default()
+            };
+            let again =
+                html::parse_with_options(&document_html_with(&doc, &serialize), &options).unwrap();
+            assert_eq!(
+                document_tree_test_format(&again),
+                document_tree_test_format(&doc),
+                "scripting_enabled: {}",
+                scripting_enabled
+            );
+        }
+    }
+
+    #[test]
+    fn template_content_is_skipped_by_default() {
+        let html = "<table><tr><td>outside</td></tr></table><template id=\"row\"><tr><td>templated</td><td>2</td></tr><script type=\"application/json\">{}</script></template>";
+        let doc = html::parse(html).unwrap();
+        let td = CompiledSelector::compile("td").unwrap();
+        let texts: Vec<String> = doc.select(&td).iter().map(|el| el.text()).collect();
+        assert_eq!(texts, vec!["outside"]);
+        assert!(doc.script_data().is_empty());
+
+        // 中身はchildrenとは別にある
+        let template = doc.query_selector("template").unwrap().unwrap();
+        assert!(template.children().is_empty());
+        assert_eq!(template.template_content().unwrap().len(), 2);
+
+        // 頼めば中身も探す。兄弟は中身の並びで見る
+        let texts: Vec<String> = doc
+            .select_iter(&td)
+            .include_template_content()
+            .map(|el| el.text())
+            .collect();
+        assert_eq!(texts, vec!["outside", "templated", "2"]);
+        let second = CompiledSelector::compile("td + td").unwrap();
+        let found: Vec<_> = doc
+            .select_iter(&second)
+            .include_template_content()
+            .collect();
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].text(), "2");
+
+        // 書き出すと元に戻る
+        assert_eq!(document_html(&doc), html);
+        assert_eq!(
+            inner_html(template.node()),
+            "<tr><td>templated</td><td>2</td></tr><script type=\"application/json\">{}</script>"
+        );
+    }
+
+    #[test]
+    fn template_content_does_not_close_outer_elements() {
+        let mut parser = Parser::builder()
+            .lenient()
+            .collect_warnings(true)
+            .build()
+            .unwrap();
+        let nodes = parser
+            .parse_str("<div><template><p>a</div></template><span>b</span></div>")
+            .unwrap()
+            .unwrap();
+        let div = &nodes[0];
+        // </div> は中身の外の要素なので閉じない
+        assert_eq!(div.children().len(), 2);
+        assert_eq!(div.children()[1].name().unwrap(), "span");
+        let content = div.children()[0].template_content().unwrap();
+        assert_eq!(content[0].name().unwrap(), "p");
+        assert_eq!(
+            parser.warnings()[0].message,
+            "close tag without an open tag; ignored"
+        );
+    }
+
+    #[test]
+    fn element_ref_try_from_node() {
+        let doc = html::parse("<p>text<!-- c --></p>").unwrap();
+        let p = &doc.children[0];
+        let el = ElementRef::try_from(p).unwrap();
+        assert_eq!(el.name().as_str(), "p");
+        assert!(el.parent_element().is_none());
+        // textやコメントは包まない
+        assert_eq!(
+            ElementRef::try_from(&p.children()[0]).unwrap_err(),
+            NotElementError {
+                kind: NodeKind::Text
+            }
+        );
+        let err = ElementRef::try_from(&p.children()[1]).unwrap_err();
+        assert_eq!(err.kind, NodeKind::Comment);
+        assert_eq!(err.to_string(), "Comment node is not an element");
+    }
+}
@@ -0,0 +1,478 @@
+// WHATWG Encoding Standard のうち、依存なしで読めるもの
+// encoding_rs featureがあれば、残りのencodingはencoding_rsで読む
+use crate::html::document::Document;
+use crate::html::errors::BytesParseError;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Encoding {
+    Utf8,
+    Utf16Le,
+    Utf16Be,
+    // iso-8859-1 や us-ascii もこれとして読む
+    Windows1252,
+    // Shift_JISなど、上にないもの
+    #[cfg(feature = "encoding_rs")]
+    Other(&'static encoding_rs::Encoding),
+}
+
+const LABELS: [(&str, Encoding); 31] = [
+    ("unicode-1-1-utf-8", Encoding::Utf8),
+    ("unicode11utf8", Encoding::Utf8),
+    ("unicode20utf8", Encoding::Utf8),
+    ("utf-8", Encoding::Utf8),
+    ("utf8", Encoding::Utf8),
+    ("x-unicode20utf8", Encoding::Utf8),
+    ("csunicode", Encoding::Utf16Le),
+    ("iso-10646-ucs-2", Encoding::Utf16Le),
+    ("ucs-2", Encoding::Utf16Le),
+    ("unicode", Encoding::Utf16Le),
+    ("unicodefeff", Encoding::Utf16Le),
+    ("utf-16", Encoding::Utf16Le),
+    ("utf-16le", Encoding::Utf16Le),
+    ("unicodefffe", Encoding::Utf16Be),
+    ("utf-16be", Encoding::Utf16Be),
+    ("ansi_x3.4-1968", Encoding::Windows1252),
+    ("ascii", Encoding::Windows1252),
+    ("cp1252", Encoding::Windows1252),
+    ("cp819", Encoding::Windows1252),
+    ("csisolatin1", Encoding::Windows1252),
+    ("ibm819", Encoding::Windows1252),
+    ("iso-8859-1", Encoding::Windows1252),
+    ("iso-ir-100", Encoding::Windows1252),
+    ("iso8859-1", Encoding::Windows1252),
+    ("iso88591", Encoding::Windows1252),
+    ("iso_8859-1", Encoding::Windows1252),
+    ("l1", Encoding::Windows1252),
+    ("latin1", Encoding::Windows1252),
+    ("us-ascii", Encoding::Windows1252),
+    ("windows-1252", Encoding::Windows1252),
+    ("x-cp1252", Encoding::Windows1252),
+];
+
+// 0x80..0x9F。ほかのbyteはそのままU+0000..U+00FF
+const WINDOWS_1252_HIGH: [char; 32] = [
+    '\u{20AC}', '\u{0081}', '\u{201A}', '\u{0192}', '\u{201E}', '\u{2026}', '\u{2020}', '\u{2021}',
+    '\u{02C6}', '\u{2030}', '\u{0160}', '\u{2039}', '\u{0152}', '\u{008D}', '\u{017D}', '\u{008F}',
+    '\u{0090}', '\u{2018}', '\u{2019}', '\u{201C}', '\u{201D}', '\u{2022}', '\u{2013}', '\u{2014}',
+    '\u{02DC}', '\u{2122}', '\u{0161}', '\u{203A}', '\u{0153}', '\u{009D}', '\u{017E}', '\u{0178}',
+];
+
+// metaを探すのは先頭のこれだけ
+const PRESCAN_BYTES: usize = 1024;
+
+// sniffで決めたencoding
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Sniffed {
+    pub encoding: Encoding,
+    // 読み飛ばすBOMの長さ
+    pub bom_len: usize,
+    // 手がかりがなく、仮にUTF-8にした。後ろのmetaと食い違えば読み直す
+    pub tentative: bool,
+}
+
+// html::parse_bytes_with_encoding の結果
+#[derive(Debug)]
+pub struct Decoded {
+    pub document: Document,
+    // 最後にdecodeしたencoding
+    pub encoding: Encoding,
+    // 先頭1024byteより後ろのmetaが仮のencodingと食い違い、読み直した
+    pub redecoded: bool,
+}
+
+impl Encoding {
+    // 前後の空白を除き、大文字小文字を区別しない。知らないlabelや、shift_jisのように読めないものはNone
+    pub fn for_label(label: &str) -> Option<Encoding> {
+        let label = label
+            .trim_matches(|c: char| c.is_ascii_whitespace())
+            .to_ascii_lowercase();
+        let found = LABELS
+            .iter()
+            .find(|(name, _)| *name == label)
+            .map(|(_, encoding)| *encoding);
+        #[cfg(feature = "encoding_rs")]
+        if found.is_none() {
+            return encoding_rs::Encoding::for_label(label.as_bytes()).map(Encoding::Other);
+        }
+        return found;
+    }
+
+    pub fn name(&self) -> &'static str {
+        return match self {
+            Encoding::Utf8 => "UTF-8",
+            Encoding::Utf16Le => "UTF-16LE",
+            Encoding::Utf16Be => "UTF-16BE",
+            Encoding::Windows1252 => "windows-1252",
+            #[cfg(feature = "encoding_rs")]
+            Encoding::Other(encoding) => encoding.name(),
+        };
+    }
+
+    // 読めないbyte列はU+FFFDにする。BOMは呼ぶ側で除く
+    pub fn decode(&self, bytes: &[u8]) -> String {
+        return match self {
+            Encoding::Utf8 => String::from_utf8_lossy(bytes).into_owned(),
+            Encoding::Utf16Le => decode_utf16(bytes, u16::from_le_bytes),
+            Encoding::Utf16Be => decode_utf16(bytes, u16::from_be_bytes),
+            Encoding::Windows1252 => bytes
+                .iter()
+                .map(|&b| match b {
+                    0x80..=0x9F => WINDOWS_1252_HIGH[(b - 0x80) as usize],
+                    _ => b as char,
+                })
+                .collect(),
+            #[cfg(feature = "encoding_rs")]
+            Encoding::Other(encoding) => encoding.decode_without_bom_handling(bytes).0.into_owned(),
+        };
+    }
+}
+
+fn decode_utf16(bytes: &[u8], from_bytes: fn([u8; 2]) -> u16) -> String {
+    let units = bytes.chunks(2).map(|pair| match pair {
+        [a, b] => from_bytes([*a, *b]),
+        // 半端な最後のbyte
+        _ => 0xFFFD,
+    });
+    return char::decode_utf16(units)
+        .map(|c| c.unwrap_or(char::REPLACEMENT_CHARACTER))
+        .collect();
+}
+
+// BOM、transport (Content-Typeのcharset)、先頭1024byteのmetaの順に決める。どれもなければ仮にUTF-8
+// 読めないlabelはエラーにする (別のencodingで読んで文字化けさせない)
+pub fn sniff(bytes: &[u8], transport_charset: Option<&str>) -> Result<Sniffed, BytesParseError> {
+    if let Some((encoding, bom_len)) = sniff_bom(bytes) {
+        return Ok(Sniffed {
+            encoding,
+            bom_len,
+            tentative: false,
+        });
+    }
+    let encoding = match transport_charset {
+        Some(label) => for_label(label)?,
+        None => match prescan_meta(bytes) {
+            Some(label) => from_meta(for_label(&label)?),
+            None => {
+                return Ok(Sniffed {
+                    encoding: Encoding::Utf8,
+                    bom_len: 0,
+                    tentative: true,
+                })
+            }
+        },
+    };
+    return Ok(Sniffed {
+        encoding,
+        bom_len: 0,
+        tentative: false,
+    });
+}
+
+// byteのままmetaが読めたのだからUTF-16ではない
+pub fn from_meta(encoding: Encoding) -> Encoding {
+    return match encoding {
+        Encoding::Utf16Le | Encoding::Utf16Be => Encoding::Utf8,
+        encoding => encoding,
+    };
+}
+
+fn for_label(label: &str) -> Result<Encoding, BytesParseError> {
+    return match Encoding::for_label(label) {
+        Some(encoding) => Ok(encoding),
+        None => Err(BytesParseError::UnsupportedEncoding {
+            label: label.to_string(),
+        }),
+    };
+}
+
+// 先頭のBOMと、その長さ
+pub fn sniff_bom(bytes: &[u8]) -> Option<(Encoding, usize)> {
+    if bytes.starts_with(&[0xEF, 0xBB, 0xBF]) {
+        return Some((Encoding::Utf8, 3));
+    }
+    if bytes.starts_with(&[0xFE, 0xFF]) {
+        return Some((Encoding::Utf16Be, 2));
+    }
+    if bytes.starts_with(&[0xFF, 0xFE]) {
+        return Some((Encoding::Utf16Le, 2));
+    }
+    return None;
+}
+
+// Content-Type: text/html; charset="ISO-8859-1" のcharset
+pub fn charset_from_content_type(content_type: &str) -> Option<&str> {
+    for param in content_type.split(';').skip(1) {
+        let (name, value) = match param.split_once('=') {
+            Some(pair) => pair,
+            None => continue,
+        };
+        if name.trim().eq_ignore_ascii_case("charset") {
+            let value = value.trim().trim_matches(|c| c == '"' || c == '\'');
+            return Some(value);
+        }
+    }
+    return None;
+}
+
+// 先頭の <meta charset> か <meta http-equiv="Content-Type" content="...; charset=..."> のlabel
+// tokenizerを通さずbyteのまま探すので、まだencodingが分からなくてもよい
+pub fn prescan_meta(bytes: &[u8]) -> Option<String> {
+    let head = &bytes[..bytes.len().min(PRESCAN_BYTES)];
+    // ASCII以外は名前や値の区切りにならないので、何に置き換えてもよい
+    let head: String = head
+        .iter()
+        .map(|&b| match b {
+            0x00..=0x7F => (b as char).to_ascii_lowercase(),
+            _ => '\u{FFFD}',
+        })
+        .collect();
+    let mut rest = head.as_str();
+    while let Some(start) = rest.find("<meta") {
+        let tag = &rest[start + "<meta".len()..];
+        let end = tag.find('>').unwrap_or(tag.len());
+        rest = &tag[end..];
+        let tag = &tag[..end];
+        if !tag.starts_with(|c: char| c.is_ascii_whitespace() || c == '/') {
+            continue;
+        }
+
+        let attrs = meta_attributes(tag);
+        let attr = |name: &str| {
+            return attrs
+                .iter()
+                .find(|(attr, _)| *attr == name)
+                .map(|(_, value)| *value);
+        };
+        if let Some(charset) = attr("charset") {
+            return Some(charset.trim().to_string());
+        }
+        let is_content_type = attr("http-equiv").is_some_and(|value| value == "content-type");
+        if let (true, Some(content)) = (is_content_type, attr("content")) {
+            if let Some(charset) = charset_from_content_type(content) {
+                return Some(charset.to_string());
+            }
+        }
+    }
+    return None;
+}
+
+// name="value" / name='value' / name=value / name
+fn meta_attributes(tag: &str) -> Vec<(&str, &str)> {
+    let mut attrs = Vec::new();
+    let mut rest = tag;
+    loop {
+        rest = rest.trim_start_matches(|c: char| c.is_ascii_whitespace() || c == '/');
+        if rest.is_empty() {
+            return attrs;
+        }
+        let name_end = rest
+            .find(|c: char| c.is_ascii_whitespace() || c == '=' || c == '/')
+            .unwrap_or(rest.len());
+        let name = &rest[..name_end];
+        rest = rest[name_end..].trim_start();
+        let value = match rest.strip_prefix('=') {
+            Some(after) => {
+                let after = after.trim_start();
+                match after.chars().next() {
+                    Some(quote @ ('"' | '\'')) => {
+                        let body = &after[1..];
+                        let end = body.find(quote).unwrap_or(body.len());
+                        rest = &body[(end + 1).min(body.len())..];
+                        &body[..end]
+                    }
+                    _ => {
+                        let end = after
+                            .find(|c: char| c.is_ascii_whitespace())
+                            .unwrap_or(after.len());
+                        rest = &after[end..];
+                        &after[..end]
+                    }
+                }
+            }
+            None => "",
+        };
+        attrs.push((name, value));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::html;
+    use crate::html::document::Document;
+    use crate::html::encoding::{charset_from_content_type, prescan_meta, sniff_bom, Encoding};
+    #[cfg(not(feature = "encoding_rs"))]
+    use crate::html::errors::BytesParseError;
+    use std::fs;
+
+    fn fixture(name: &str) -> Vec<u8> {
+        let path = format!(
+            "{}/tests/fixtures/encoding/{}",
+            env!("CARGO_MANIFEST_DIR"),
+            name
+        );
+        return fs::read(path).unwrap();
+    }
+
+    fn text_of(doc: &Document, selector: &str) -> String {
+        let nd = doc.query_selector(selector).unwrap().unwrap();
+        return nd
+            .children()
+            .iter()
+            .filter_map(|child| child.text_value())
+            .map(|s| s.as_str())
+            .collect::<Vec<&str>>()
+            .join(" ");
+    }
+
+    #[test]
+    fn labels_and_decoding() {
+        assert_eq!(Encoding::for_label(" Latin1 "), Some(Encoding::Windows1252));
+        assert_eq!(Encoding::for_label("UTF8"), Some(Encoding::Utf8));
+        #[cfg(not(feature = "encoding_rs"))]
+        assert_eq!(Encoding::for_label("shift_jis"), None);
+        // LABELSにないものはencoding_rsで探す
+        #[cfg(feature = "encoding_rs")]
+        {
+            let sjis = Encoding::for_label("shift_jis").unwrap();
+            assert_eq!(sjis.name(), "Shift_JIS");
+            assert_eq!(sjis.decode(b"\x93\xfa\x96\x7b a"), "\u{65E5}\u{672C} a");
+            assert_eq!(Encoding::for_label("no-such-encoding"), None);
+        }
+
+        assert_eq!(
+            Encoding::Windows1252.decode(b"caf\xe9 \x80 \x93x\x94"),
+            "café € \u{201C}x\u{201D}"
+        );
+        assert_eq!(
+            Encoding::Utf16Be.decode(b"\x00h\x00i\xd8\x3d\xde\x00"),
+            "hi😀"
+        );
+        assert_eq!(Encoding::Utf8.decode(b"a\xffb"), "a\u{FFFD}b");
+        assert_eq!(sniff_bom(b"\xef\xbb\xbfx"), Some((Encoding::Utf8, 3)));
+        assert_eq!(sniff_bom(b"<p>"), None);
+    }
+
+    #[test]
+    fn charset_declarations() {
+        assert_eq!(
+            charset_from_content_type("text/html; charset=\"ISO-8859-1\""),
+            Some("ISO-8859-1")
+        );
+        assert_eq!(charset_from_content_type("text/html"), None);
+
+        assert_eq!(
+            prescan_meta(b"<html><head><META CHARSET='Latin1'>"),
+            Some("latin1".to_string())
+        );
+        assert_eq!(
+            prescan_meta(
+                b"<meta name=x><meta http-equiv=\"Content-Type\" content=\"text/html; charset=utf-8\" />"
+            ),
+            Some("utf-8".to_string())
+        );
+        assert_eq!(prescan_meta(b"<metadata charset=x><p>caf\xe9</p>"), None);
+        // 先頭1024byteより後ろは見ない
+        let mut late = vec![b' '; 1024];
+        late.extend_from_slice(b"<meta charset=latin1>");
+        assert_eq!(prescan_meta(&late), None);
+    }
+
+    #[test]
+    fn parse_bytes_with_bom_or_meta() {
+        // BOMは読み飛ばし、壊れたbyteはU+FFFDにする
+        let doc = html::parse_bytes(b"\xef\xbb\xbf<p>caf\xc3\xa9 \xff</p>").unwrap();
+        assert_eq!(text_of(&doc, "p"), "caf\u{e9} \u{FFFD}");
+        assert!(doc.children[0].is_element());
+
+        let doc = html::parse_bytes(
+            b"<html><head><meta charset=\"iso-8859-1\"/></head><body><p>na\xefve \xa3</p></body></html>",
+        )
+        .unwrap();
+        assert_eq!(text_of(&doc, "p"), "na\u{ef}ve \u{a3}");
+    }
+
+    #[test]
+    fn parse_windows_1252_fixture() {
+        let bytes = fixture("windows-1252.html");
+        let decoded = html::parse_bytes_with_encoding(&bytes, None).unwrap();
+        assert_eq!(decoded.encoding, Encoding::Windows1252);
+        assert!(!decoded.redecoded);
+        assert_eq!(text_of(&decoded.document, "title"), "Caf\u{e9}");
+        assert_eq!(
+            text_of(&decoded.document, "p"),
+            "\u{201C}na\u{ef}ve\u{201D} caf\u{e9} \u{2013} 5 \u{20AC}"
+        );
+
+        // transportのcharsetはmetaより強い
+        let decoded = html::parse_bytes_with_encoding(&bytes, Some("utf-8")).unwrap();
+        assert_eq!(decoded.encoding, Encoding::Utf8);
+        assert_eq!(text_of(&decoded.document, "title"), "Caf\u{FFFD}");
+    }
+
+    #[test]
+    fn redecode_after_late_meta() {
+        // metaが先頭1024byteより後ろにあると、仮にUTF-8で読んでから読み直す
+        let late_meta = |charset: &str, text: &[u8]| {
+            let mut bytes = b"<html><head><!--".to_vec();
+            bytes.extend(vec![b'x'; 1024]);
+            bytes.extend_from_slice(
+                format!("--><meta charset=\"{}\"/></head><p>", charset).as_bytes(),
+            );
+            bytes.extend_from_slice(text);
+            bytes.extend_from_slice(b"</p></html>");
+            return bytes;
+        };
+        let decoded =
+            html::parse_bytes_with_encoding(&late_meta("latin1", b"caf\xe9"), None).unwrap();
+        assert_eq!(decoded.encoding, Encoding::Windows1252);
+        assert!(decoded.redecoded);
+        assert_eq!(text_of(&decoded.document, "p"), "caf\u{e9}");
+
+        // 同じencodingなら読み直さない
+        let decoded =
+            html::parse_bytes_with_encoding(&late_meta("utf-8", b"caf\xc3\xa9"), None).unwrap();
+        assert_eq!(decoded.encoding, Encoding::Utf8);
+        assert!(!decoded.redecoded);
+        assert_eq!(text_of(&decoded.document, "p"), "caf\u{e9}");
+
+        // BOMやtransportで決まっていれば、後ろのmetaは見ない
+        let bytes = [
+            b"\xef\xbb\xbf".as_slice(),
+            &late_meta("latin1", b"\xc3\xa9"),
+        ]
+        .concat();
+        let decoded = html::parse_bytes_with_encoding(&bytes, None).unwrap();
+        assert_eq!(decoded.encoding, Encoding::Utf8);
+        assert!(!decoded.redecoded);
+        assert_eq!(text_of(&decoded.document, "p"), "\u{e9}");
+        let decoded =
+            html::parse_bytes_with_encoding(&late_meta("latin1", b"\xc3\xa9"), Some("utf-8"))
+                .unwrap();
+        assert!(!decoded.redecoded);
+        assert_eq!(text_of(&decoded.document, "p"), "\u{e9}");
+    }
+
+    #[test]
+    fn parse_shift_jis_fixture() {
+        let bytes = fixture("shift_jis.html");
+        let result = html::parse_bytes_with_encoding(&bytes, None);
+        #[cfg(feature = "encoding_rs")]
+        {
+            let decoded = result.unwrap();
+            assert_eq!(decoded.encoding.name(), "Shift_JIS");
+            assert_eq!(
+                text_of(&decoded.document, "title"),
+                "\u{65E5}\u{672C}\u{8A9E}"
+            );
+            assert_eq!(
+                text_of(&decoded.document, "p"),
+                "\u{3053}\u{3093}\u{306B}\u{3061}\u{306F} \u{4E16}\u{754C}"
+            );
+        }
+        #[cfg(not(feature = "encoding_rs"))]
+        assert!(matches!(
+            result,
+            Err(BytesParseError::UnsupportedEncoding { label }) if label == "shift_jis"
+        ));
+    }
+}
@@ -0,0 +1,198 @@
+// 文字参照 (&amp; や &#233;) を文字に戻す
+// 名前はWHATWGの表のすべて。表にない名前はそのまま残す
+use alloc::borrow::Cow;
+use alloc::string::String;
+
+mod table;
+pub use table::NAMED_REFERENCES;
+
+// 属性の値とtextでは、";" のない参照の扱いが違う
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RefContext {
+    Text,
+    // href="?a=1&copy=2" の "&copy" を残すため、";" がなく直後が "=" か英数字なら戻さない
+    Attribute,
+}
+
+// 0x80..0x9f の数値参照は、windows-1252で読んだときの文字にする
+const WINDOWS_1252: [char; 32] = [
+    '\u{20ac}', '\u{81}', '\u{201a}', '\u{192}', '\u{201e}', '\u{2026}', '\u{2020}', '\u{2021}',
+    '\u{2c6}', '\u{2030}', '\u{160}', '\u{2039}', '\u{152}', '\u{8d}', '\u{17d}', '\u{8f}',
+    '\u{90}', '\u{2018}', '\u{2019}', '\u{201c}', '\u{201d}', '\u{2022}', '\u{2013}', '\u{2014}',
+    '\u{2dc}', '\u{2122}', '\u{161}', '\u{203a}', '\u{153}', '\u{9d}', '\u{17e}', '\u{178}',
+];
+
+// "&" がなければコピーしない
+pub fn decode(s: &str, context: RefContext) -> Cow<'_, str> {
+    if !s.contains('&') {
+        return Cow::Borrowed(s);
+    }
+    let mut out = String::with_capacity(s.len());
+    let mut rest = s;
+    while let Some(amp) = rest.find('&') {
+        out += &rest[..amp];
+        rest = &rest[amp + 1..];
+        match reference(rest, context) {
+            Some((value, len)) => {
+                out += &value;
+                rest = &rest[len..];
+            }
+            None => out.push('&'),
+        }
+    }
+    out += rest;
+    return Cow::Owned(out);
+}
+
+// 参照を始めない "&" があるか (a & b)
+pub(crate) fn has_bare_amp(s: &str) -> bool {
+    return s
+        .match_indices('&')
+        .any(|(at, _)| reference(&s[at + 1..], RefContext::Text).is_none());
+}
+
+// "&" の後ろ。戻す文字と、参照として読んだbyteの数
+fn reference(rest: &str, context: RefContext) -> Option<(Cow<'static, str>, usize)> {
+    if let Some(digits) = rest.strip_prefix('#') {
+        return numeric_reference(digits).map(|(c, len)| (Cow::Owned(String::from(c)), len + 1));
+    }
+    return named_reference(rest, context).map(|(value, len)| (Cow::Borrowed(value), len));
+}
+
+// ";" はなくても読む (specではエラーだが、どちらの場所でも戻す)
+fn numeric_reference(rest: &str) -> Option<(char, usize)> {
+    let (radix, prefix) = match rest.as_bytes().first() {
+        Some(b'x') | Some(b'X') => (16, 1),
+        _ => (10, 0),
+    };
+    let digits = rest[prefix..]
+        .bytes()
+        .take_while(|b| (*b as char).is_digit(radix))
+        .count();
+    if digits == 0 {
+        return None;
+    }
+    // 大きすぎる値はu32に入らないので、最大値を超えたものとして扱う
+    let code = u32::from_str_radix(&rest[prefix..prefix + digits], radix).unwrap_or(u32::MAX);
+    let c = match code {
+        0x80..=0x9f => WINDOWS_1252[(code - 0x80) as usize],
+        0 => '\u{fffd}',
+        _ => char::from_u32(code).unwrap_or('\u{fffd}'),
+    };
+    let mut len = prefix + digits;
+    if rest[len..].starts_with(';') {
+        len += 1;
+    }
+    return Some((c, len));
+}
+
+// ";" まで書かれた名前を先に探す
+// なければ ";" のいらないlegacyの名前で、いちばん長く合うもの
+// ("&notin;" は "not" + "in;" でなく "notin;" として読む)
+fn named_reference(rest: &str, context: RefContext) -> Option<(&'static str, usize)> {
+    let len = rest
+        .bytes()
+        .take_while(|b| b.is_ascii_alphanumeric())
+        .count();
+    if rest[len..].starts_with(';') {
+        if let Ok(i) = NAMED_REFERENCES.binary_search_by(|(name, _, _)| name.cmp(&&rest[..len])) {
+            return Some((NAMED_REFERENCES[i].2, len + 1));
+        }
+    }
+    let (name, _, value) = NAMED_REFERENCES
+        .iter()
+        .filter(|(name, legacy, _)| *legacy && rest.starts_with(name))
+        .max_by_key(|(name, _, _)| name.len())?;
+    let len = name.len();
+    if context == RefContext::Attribute {
+        let next = rest[len..].chars().next();
+        if next.is_some_and(|next| next == '=' || next.is_ascii_alphanumeric()) {
+            return None;
+        }
+    }
+    return Some((value, len));
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use crate::html;
+    use crate::html::entity::{decode, RefContext};
+
+    fn href(html: &str) -> String {
+        let doc = html::parse(html).unwrap();
+        let a = doc.query_selector("a").unwrap().unwrap();
+        return a.attr("href").unwrap().to_string();
+    }
+
+    #[test]
+    fn query_strings_in_href_are_kept() {
+        assert_eq!(
+            href("<a href=\"?page=2&sort=name\"></a>"),
+            "?page=2&sort=name"
+        );
+        // ";" がなく直後が "=" なので参照にしない
+        assert_eq!(href("<a href=\"?x=1&copy=true\"></a>"), "?x=1&copy=true");
+        assert_eq!(href("<a href=\"?a=1&times=2\"></a>"), "?a=1&times=2");
+        // 直後が英数字でも同じ
+        assert_eq!(href("<a href=\"?q=&ampx\"></a>"), "?q=&ampx");
+    }
+
+    #[test]
+    fn terminated_references_in_href_are_decoded() {
+        assert_eq!(href("<a href=\"a &amp; b\"></a>"), "a & b");
+        assert_eq!(href("<a href=\"?a=1&amp;b=2\"></a>"), "?a=1&b=2");
+        assert_eq!(href("<a href=\"?x&copy;\"></a>"), "?x\u{a9}");
+        // ";" がなくても、直後が区切りなら戻す
+        assert_eq!(href("<a href=\"x &amp y\"></a>"), "x & y");
+        assert_eq!(href("<a href=\"&#47;a&#x2F;b\"></a>"), "/a/b");
+    }
+
+    #[test]
+    fn text_rules_differ_from_attribute_rules() {
+        for (input, text, attribute) in [
+            ("&copy=true", "\u{a9}=true", "&copy=true"),
+            ("&amp;", "&", "&"),
+            ("&ampx", "&x", "&ampx"),
+            ("&times=", "\u{d7}=", "&times="),
+            ("&sort=name", "&sort=name", "&sort=name"),
+        ] {
+            assert_eq!(decode(input, RefContext::Text), text, "{}", input);
+            assert_eq!(decode(input, RefContext::Attribute), attribute, "{}", input);
+        }
+    }
+
+    #[test]
+    fn numeric_and_longest_match() {
+        assert_eq!(
+            decode("&#233;&#xE9&#X41;", RefContext::Text),
+            "\u{e9}\u{e9}A"
+        );
+        assert_eq!(
+            decode("&#0;&#x110000;&#128;", RefContext::Text),
+            "\u{fffd}\u{fffd}\u{20ac}"
+        );
+        assert_eq!(decode("&#;&#x;", RefContext::Text), "&#;&#x;");
+        assert_eq!(decode("&notit;", RefContext::Text), "\u{ac}it;");
+        assert_eq!(decode("&notit;", RefContext::Attribute), "&notit;");
+        // 表にある長い名前が先
+        assert_eq!(
+            decode("&notin; &not ", RefContext::Text),
+            "\u{2209} \u{ac} "
+        );
+        assert_eq!(decode("&notin;", RefContext::Attribute), "\u{2209}");
+        // 二文字になる参照と、表にない名前
+        assert_eq!(
+            decode("&acE;&bogus;", RefContext::Text),
+            "\u{223e}\u{333}&bogus;"
+        );
+        // ";" が必須の名前
+        assert_eq!(
+            decode("&hellip &hellip;", RefContext::Text),
+            "&hellip \u{2026}"
+        );
+        assert!(matches!(
+            decode("plain", RefContext::Text),
+            std::borrow::Cow::Borrowed(_)
+        ));
+    }
+}
@@ -0,0 +1,2131 @@
+// WHATWGの名前付き文字参照の表 (https://html.spec.whatwg.org/entities.json)
+// markup5ever 0.12.1 (MIT/Apache-2.0) が持つ同じ表から作った。手で直さない
+
+// (名前, ";" がなくても参照になるか, 文字)。名前のbyte順に並ぶ
+pub static NAMED_REFERENCES: &[(&str, bool, &str)] = &[
+    ("AElig", true, "\u{c6}"),
+    ("AMP", true, "&"),
+    ("Aacute", true, "\u{c1}"),
+    ("Abreve", false, "\u{102}"),
+    ("Acirc", true, "\u{c2}"),
+    ("Acy", false, "\u{410}"),
+    ("Afr", false, "\u{1d504}"),
+    ("Agrave", true, "\u{c0}"),
+    ("Alpha", false, "\u{391}"),
+    ("Amacr", false, "\u{100}"),
+    ("And", false, "\u{2a53}"),
+    ("Aogon", false, "\u{104}"),
+    ("Aopf", false, "\u{1d538}"),
+    ("ApplyFunction", false, "\u{2061}"),
+    ("Aring", true, "\u{c5}"),
+    ("Ascr", false, "\u{1d49c}"),
+    ("Assign", false, "\u{2254}"),
+    ("Atilde", true, "\u{c3}"),
+    ("Auml", true, "\u{c4}"),
+    ("Backslash", false, "\u{2216}"),
+    ("Barv", false, "\u{2ae7}"),
+    ("Barwed", false, "\u{2306}"),
+    ("Bcy", false, "\u{411}"),
+    ("Because", false, "\u{2235}"),
+    ("Bernoullis", false, "\u{212c}"),
+    ("Beta", false, "\u{392}"),
+    ("Bfr", false, "\u{1d505}"),
+    ("Bopf", false, "\u{1d539}"),
+    ("Breve", false, "\u{2d8}"),
+    ("Bscr", false, "\u{212c}"),
+    ("Bumpeq", false, "\u{224e}"),
+    ("CHcy", false, "\u{427}"),
+    ("COPY", true, "\u{a9}"),
+    ("Cacute", false, "\u{106}"),
+    ("Cap", false, "\u{22d2}"),
+    ("CapitalDifferentialD", false, "\u{2145}"),
+    ("Cayleys", false, "\u{212d}"),
+    ("Ccaron", false, "\u{10c}"),
+    ("Ccedil", true, "\u{c7}"),
+    ("Ccirc", false, "\u{108}"),
+    ("Cconint", false, "\u{2230}"),
+    ("Cdot", false, "\u{10a}"),
+    ("Cedilla", false, "\u{b8}"),
+    ("CenterDot", false, "\u{b7}"),
+    ("Cfr", false, "\u{212d}"),
+    ("Chi", false, "\u{3a7}"),
+    ("CircleDot", false, "\u{2299}"),
+    ("CircleMinus", false, "\u{2296}"),
+    ("CirclePlus", false, "\u{2295}"),
+    ("CircleTimes", false, "\u{2297}"),
+    ("ClockwiseContourIntegral", false, "\u{2232}"),
+    ("CloseCurlyDoubleQuote", false, "\u{201d}"),
+    ("CloseCurlyQuote", false, "\u{2019}"),
+    ("Colon", false, "\u{2237}"),
+    ("Colone", false, "\u{2a74}"),
+    ("Congruent", false, "\u{2261}"),
+    ("Conint", false, "\u{222f}"),
+    ("ContourIntegral", false, "\u{222e}"),
+    ("Copf", false, "\u{2102}"),
+    ("Coproduct", false, "\u{2210}"),
+    ("CounterClockwiseContourIntegral", false, "\u{2233}"),
+    ("Cross", false, "\u{2a2f}"),
+    ("Cscr", false, "\u{1d49e}"),
+    ("Cup", false, "\u{22d3}"),
+    ("CupCap", false, "\u{224d}"),
+    ("DD", false, "\u{2145}"),
+    ("DDotrahd", false, "\u{2911}"),
+    ("DJcy", false, "\u{402}"),
+    ("DScy", false, "\u{405}"),
+    ("DZcy", false, "\u{40f}"),
+    ("Dagger", false, "\u{2021}"),
+    ("Darr", false, "\u{21a1}"),
+    ("Dashv", false, "\u{2ae4}"),
+    ("Dcaron", false, "\u{10e}"),
+    ("Dcy", false, "\u{414}"),
+    ("Del", false, "\u{2207}"),
+    ("Delta", false, "\u{394}"),
+    ("Dfr", false, "\u{1d507}"),
+    ("DiacriticalAcute", false, "\u{b4}"),
+    ("DiacriticalDot", false, "\u{2d9}"),
+    ("DiacriticalDoubleAcute", false, "\u{2dd}"),
+    ("DiacriticalGrave", false, "`"),
+    ("DiacriticalTilde", false, "\u{2dc}"),
+    ("Diamond", false, "\u{22c4}"),
+    ("DifferentialD", false, "\u{2146}"),
+    ("Dopf", false, "\u{1d53b}"),
+    ("Dot", false, "\u{a8}"),
+    ("DotDot", false, "\u{20dc}"),
+    ("DotEqual", false, "\u{2250}"),
+    ("DoubleContourIntegral", false, "\u{222f}"),
+    ("DoubleDot", false, "\u{a8}"),
+    ("DoubleDownArrow", false, "\u{21d3}"),
+    ("DoubleLeftArrow", false, "\u{21d0}"),
+    ("DoubleLeftRightArrow", false, "\u{21d4}"),
+    ("DoubleLeftTee", false, "\u{2ae4}"),
+    ("DoubleLongLeftArrow", false, "\u{27f8}"),
+    ("DoubleLongLeftRightArrow", false, "\u{27fa}"),
+    ("DoubleLongRightArrow", false, "\u{27f9}"),
+    ("DoubleRightArrow", false, "\u{21d2}"),
+    ("DoubleRightTee", false, "\u{22a8}"),
+    ("DoubleUpArrow", false, "\u{21d1}"),
+    ("DoubleUpDownArrow", false, "\u{21d5}"),
+    ("DoubleVerticalBar", false, "\u{2225}"),
+    ("DownArrow", false, "\u{2193}"),
+    ("DownArrowBar", false, "\u{2913}"),
+    ("DownArrowUpArrow", false, "\u{21f5}"),
+    ("DownBreve", false, "\u{311}"),
+    ("DownLeftRightVector", false, "\u{2950}"),
+    ("DownLeftTeeVector", false, "\u{295e}"),
+    ("DownLeftVector", false, "\u{21bd}"),
+    ("DownLeftVectorBar", false, "\u{2956}"),
+    ("DownRightTeeVector", false, "\u{295f}"),
+    ("DownRightVector", false, "\u{21c1}"),
+    ("DownRightVectorBar", false, "\u{2957}"),
+    ("DownTee", false, "\u{22a4}"),
+    ("DownTeeArrow", false, "\u{21a7}"),
+    ("Downarrow", false, "\u{21d3}"),
+    ("Dscr", false, "\u{1d49f}"),
+    ("Dstrok", false, "\u{110}"),
+    ("ENG", false, "\u{14a}"),
+    ("ETH", true, "\u{d0}"),
+    ("Eacute", true, "\u{c9}"),
+    ("Ecaron", false, "\u{11a}"),
+    ("Ecirc", true, "\u{ca}"),
+    ("Ecy", false, "\u{42d}"),
+    ("Edot", false, "\u{116}"),
+    ("Efr", false, "\u{1d508}"),
+    ("Egrave", true, "\u{c8}"),
+    ("Element", false, "\u{2208}"),
+    ("Emacr", false, "\u{112}"),
+    ("EmptySmallSquare", false, "\u{25fb}"),
+    ("EmptyVerySmallSquare", false, "\u{25ab}"),
+    ("Eogon", false, "\u{118}"),
+    ("Eopf", false, "\u{1d53c}"),
+    ("Epsilon", false, "\u{395}"),
+    ("Equal", false, "\u{2a75}"),
+    ("EqualTilde", false, "\u{2242}"),
+    ("Equilibrium", false, "\u{21cc}"),
+    ("Escr", false, "\u{2130}"),
+    ("Esim", false, "\u{2a73}"),
+    ("Eta", false, "\u{397}"),
+    ("Euml", true, "\u{cb}"),
+    ("Exists", false, "\u{2203}"),
+    ("ExponentialE", false, "\u{2147}"),
+    ("Fcy", false, "\u{424}"),
+    ("Ffr", false, "\u{1d509}"),
+    ("FilledSmallSquare", false, "\u{25fc}"),
+    ("FilledVerySmallSquare", false, "\u{25aa}"),
+    ("Fopf", false, "\u{1d53d}"),
+    ("ForAll", false, "\u{2200}"),
+    ("Fouriertrf", false, "\u{2131}"),
+    ("Fscr", false, "\u{2131}"),
+    ("GJcy", false, "\u{403}"),
+    ("GT", true, ">"),
+    ("Gamma", false, "\u{393}"),
+    ("Gammad", false, "\u{3dc}"),
+    ("Gbreve", false, "\u{11e}"),
+    ("Gcedil", false, "\u{122}"),
+    ("Gcirc", false, "\u{11c}"),
+    ("Gcy", false, "\u{413}"),
+    ("Gdot", false, "\u{120}"),
+    ("Gfr", false, "\u{1d50a}"),
+    ("Gg", false, "\u{22d9}"),
+    ("Gopf", false, "\u{1d53e}"),
+    ("GreaterEqual", false, "\u{2265}"),
+    ("GreaterEqualLess", false, "\u{22db}"),
+    ("GreaterFullEqual", false, "\u{2267}"),
+    ("GreaterGreater", false, "\u{2aa2}"),
+    ("GreaterLess", false, "\u{2277}"),
+    ("GreaterSlantEqual", false, "\u{2a7e}"),
+    ("GreaterTilde", false, "\u{2273}"),
+    ("Gscr", false, "\u{1d4a2}"),
+    ("Gt", false, "\u{226b}"),
+    ("HARDcy", false, "\u{42a}"),
+    ("Hacek", false, "\u{2c7}"),
+    ("Hat", false, "^"),
+    ("Hcirc", false, "\u{124}"),
+    ("Hfr", false, "\u{210c}"),
+    ("HilbertSpace", false, "\u{210b}"),
+    ("Hopf", false, "\u{210d}"),
+    ("HorizontalLine", false, "\u{2500}"),
+    ("Hscr", false, "\u{210b}"),
+    ("Hstrok", false, "\u{126}"),
+    ("HumpDownHump", false, "\u{224e}"),
+    ("HumpEqual", false, "\u{224f}"),
+    ("IEcy", false, "\u{415}"),
+    ("IJlig", false, "\u{132}"),
+    ("IOcy", false, "\u{401}"),
+    ("Iacute", true, "\u{cd}"),
+    ("Icirc", true, "\u{ce}"),
+    ("Icy", false, "\u{418}"),
+    ("Idot", false, "\u{130}"),
+    ("Ifr", false, "\u{2111}"),
+    ("Igrave", true, "\u{cc}"),
+    ("Im", false, "\u{2111}"),
+    ("Imacr", false, "\u{12a}"),
+    ("ImaginaryI", false, "\u{2148}"),
+    ("Implies", false, "\u{21d2}"),
+    ("Int", false, "\u{222c}"),
+    ("Integral", false, "\u{222b}"),
+    ("Intersection", false, "\u{22c2}"),
+    ("InvisibleComma", false, "\u{2063}"),
+    ("InvisibleTimes", false, "\u{2062}"),
+    ("Iogon", false, "\u{12e}"),
+    ("Iopf", false, "\u{1d540}"),
+    ("Iota", false, "\u{399}"),
+    ("Iscr", false, "\u{2110}"),
+    ("Itilde", false, "\u{128}"),
+    ("Iukcy", false, "\u{406}"),
+    ("Iuml", true, "\u{cf}"),
+    ("Jcirc", false, "\u{134}"),
+    ("Jcy", false, "\u{419}"),
+    ("Jfr", false, "\u{1d50d}"),
+    ("Jopf", false, "\u{1d541}"),
+    ("Jscr", false, "\u{1d4a5}"),
+    ("Jsercy", false, "\u{408}"),
+    ("Jukcy", false, "\u{404}"),
+    ("KHcy", false, "\u{425}"),
+    ("KJcy", false, "\u{40c}"),
+    ("Kappa", false, "\u{39a}"),
+    ("Kcedil", false, "\u{136}"),
+    ("Kcy", false, "\u{41a}"),
+    ("Kfr", false, "\u{1d50e}"),
+    ("Kopf", false, "\u{1d542}"),
+    ("Kscr", false, "\u{1d4a6}"),
+    ("LJcy", false, "\u{409}"),
+    ("LT", true, "<"),
+    ("Lacute", false, "\u{139}"),
+    ("Lambda", false, "\u{39b}"),
+    ("Lang", false, "\u{27ea}"),
+    ("Laplacetrf", false, "\u{2112}"),
+    ("Larr", false, "\u{219e}"),
+    ("Lcaron", false, "\u{13d}"),
+    ("Lcedil", false, "\u{13b}"),
+    ("Lcy", false, "\u{41b}"),
+    ("LeftAngleBracket", false, "\u{27e8}"),
+    ("LeftArrow", false, "\u{2190}"),
+    ("LeftArrowBar", false, "\u{21e4}"),
+    ("LeftArrowRightArrow", false, "\u{21c6}"),
+    ("LeftCeiling", false, "\u{2308}"),
+    ("LeftDoubleBracket", false, "\u{27e6}"),
+    ("LeftDownTeeVector", false, "\u{2961}"),
+    ("LeftDownVector", false, "\u{21c3}"),
+    ("LeftDownVectorBar", false, "\u{2959}"),
+    ("LeftFloor", false, "\u{230a}"),
+    ("LeftRightArrow", false, "\u{2194}"),
+    ("LeftRightVector", false, "\u{294e}"),
+    ("LeftTee", false, "\u{22a3}"),
+    ("LeftTeeArrow", false, "\u{21a4}"),
+    ("LeftTeeVector", false, "\u{295a}"),
+    ("LeftTriangle", false, "\u{22b2}"),
+    ("LeftTriangleBar", false, "\u{29cf}"),
+    ("LeftTriangleEqual", false, "\u{22b4}"),
+    ("LeftUpDownVector", false, "\u{2951}"),
+    ("LeftUpTeeVector", false, "\u{2960}"),
+    ("LeftUpVector", false, "\u{21bf}"),
+    ("LeftUpVectorBar", false, "\u{2958}"),
+    ("LeftVector", false, "\u{21bc}"),
+    ("LeftVectorBar", false, "\u{2952}"),
+    ("Leftarrow", false, "\u{21d0}"),
+    ("Leftrightarrow", false, "\u{21d4}"),
+    ("LessEqualGreater", false, "\u{22da}"),
+    ("LessFullEqual", false, "\u{2266}"),
+    ("LessGreater", false, "\u{2276}"),
+    ("LessLess", false, "\u{2aa1}"),
+    ("LessSlantEqual", false, "\u{2a7d}"),
+    ("LessTilde", false, "\u{2272}"),
+    ("Lfr", false, "\u{1d50f}"),
+    ("Ll", false, "\u{22d8}"),
+    ("Lleftarrow", false, "\u{21da}"),
+    ("Lmidot", false, "\u{13f}"),
+    ("LongLeftArrow", false, "\u{27f5}"),
+    ("LongLeftRightArrow", false, "\u{27f7}"),
+    ("LongRightArrow", false, "\u{27f6}"),
+    ("Longleftarrow", false, "\u{27f8}"),
+    ("Longleftrightarrow", false, "\u{27fa}"),
+    ("Longrightarrow", false, "\u{27f9}"),
+    ("Lopf", false, "\u{1d543}"),
+    ("LowerLeftArrow", false, "\u{2199}"),
+    ("LowerRightArrow", false, "\u{2198}"),
+    ("Lscr", false, "\u{2112}"),
+    ("Lsh", false, "\u{21b0}"),
+    ("Lstrok", false, "\u{141}"),
+    ("Lt", false, "\u{226a}"),
+    ("Map", false, "\u{2905}"),
+    ("Mcy", false, "\u{41c}"),
+    ("MediumSpace", false, "\u{205f}"),
+    ("Mellintrf", false, "\u{2133}"),
+    ("Mfr", false, "\u{1d510}"),
+    ("MinusPlus", false, "\u{2213}"),
+    ("Mopf", false, "\u{1d544}"),
+    ("Mscr", false, "\u{2133}"),
+    ("Mu", false, "\u{39c}"),
+    ("NJcy", false, "\u{40a}"),
+    ("Nacute", false, "\u{143}"),
+    ("Ncaron", false, "\u{147}"),
+    ("Ncedil", false, "\u{145}"),
+    ("Ncy", false, "\u{41d}"),
+    ("NegativeMediumSpace", false, "\u{200b}"),
+    ("NegativeThickSpace", false, "\u{200b}"),
+    ("NegativeThinSpace", false, "\u{200b}"),
+    ("NegativeVeryThinSpace", false, "\u{200b}"),
+    ("NestedGreaterGreater", false, "\u{226b}"),
+    ("NestedLessLess", false, "\u{226a}"),
+    ("NewLine", false, "\u{a}"),
+    ("Nfr", false, "\u{1d511}"),
+    ("NoBreak", false, "\u{2060}"),
+    ("NonBreakingSpace", false, "\u{a0}"),
+    ("Nopf", false, "\u{2115}"),
+    ("Not", false, "\u{2aec}"),
+    ("NotCongruent", false, "\u{2262}"),
+    ("NotCupCap", false, "\u{226d}"),
+    ("NotDoubleVerticalBar", false, "\u{2226}"),
+    ("NotElement", false, "\u{2209}"),
+    ("NotEqual", false, "\u{2260}"),
+    ("NotEqualTilde", false, "\u{2242}\u{338}"),
+    ("NotExists", false, "\u{2204}"),
+    ("NotGreater", false, "\u{226f}"),
+    ("NotGreaterEqual", false, "\u{2271}"),
+    ("NotGreaterFullEqual", false, "\u{2267}\u{338}"),
+    ("NotGreaterGreater", false, "\u{226b}\u{338}"),
+    ("NotGreaterLess", false, "\u{2279}"),
+    ("NotGreaterSlantEqual", false, "\u{2a7e}\u{338}"),
+    ("NotGreaterTilde", false, "\u{2275}"),
+    ("NotHumpDownHump", false, "\u{224e}\u{338}"),
+    ("NotHumpEqual", false, "\u{224f}\u{338}"),
+    ("NotLeftTriangle", false, "\u{22ea}"),
+    ("NotLeftTriangleBar", false, "\u{29cf}\u{338}"),
+    ("NotLeftTriangleEqual", false, "\u{22ec}"),
+    ("NotLess", false, "\u{226e}"),
+    ("NotLessEqual", false, "\u{2270}"),
+    ("NotLessGreater", false, "\u{2278}"),
+    ("NotLessLess", false, "\u{226a}\u{338}"),
+    ("NotLessSlantEqual", false, "\u{2a7d}\u{338}"),
+    ("NotLessTilde", false, "\u{2274}"),
+    ("NotNestedGreaterGreater", false, "\u{2aa2}\u{338}"),
+    ("NotNestedLessLess", false, "\u{2aa1}\u{338}"),
+    ("NotPrecedes", false, "\u{2280}"),
+    ("NotPrecedesEqual", false, "\u{2aaf}\u{338}"),
+    ("NotPrecedesSlantEqual", false, "\u{22e0}"),
+    ("NotReverseElement", false, "\u{220c}"),
+    ("NotRightTriangle", false, "\u{22eb}"),
+    ("NotRightTriangleBar", false, "\u{29d0}\u{338}"),
+    ("NotRightTriangleEqual", false, "\u{22ed}"),
+    ("NotSquareSubset", false, "\u{228f}\u{338}"),
+    ("NotSquareSubsetEqual", false, "\u{22e2}"),
+    ("NotSquareSuperset", false, "\u{2290}\u{338}"),
+    ("NotSquareSupersetEqual", false, "\u{22e3}"),
+    ("NotSubset", false, "\u{2282}\u{20d2}"),
+    ("NotSubsetEqual", false, "\u{2288}"),
+    ("NotSucceeds", false, "\u{2281}"),
+    ("NotSucceedsEqual", false, "\u{2ab0}\u{338}"),
+    ("NotSucceedsSlantEqual", false, "\u{22e1}"),
+    ("NotSucceedsTilde", false, "\u{227f}\u{338}"),
+    ("NotSuperset", false, "\u{2283}\u{20d2}"),
+    ("NotSupersetEqual", false, "\u{2289}"),
+    ("NotTilde", false, "\u{2241}"),
+    ("NotTildeEqual", false, "\u{2244}"),
+    ("NotTildeFullEqual", false, "\u{2247}"),
+    ("NotTildeTilde", false, "\u{2249}"),
+    ("NotVerticalBar", false, "\u{2224}"),
+    ("Nscr", false, "\u{1d4a9}"),
+    ("Ntilde", true, "\u{d1}"),
+    ("Nu", false, "\u{39d}"),
+    ("OElig", false, "\u{152}"),
+    ("Oacute", true, "\u{d3}"),
+    ("Ocirc", true, "\u{d4}"),
+    ("Ocy", false, "\u{41e}"),
+    ("Odblac", false, "\u{150}"),
+    ("Ofr", false, "\u{1d512}"),
+    ("Ograve", true, "\u{d2}"),
+    ("Omacr", false, "\u{14c}"),
+    ("Omega", false, "\u{3a9}"),
+    ("Omicron", false, "\u{39f}"),
+    ("Oopf", false, "\u{1d546}"),
+    ("OpenCurlyDoubleQuote", false, "\u{201c}"),
+    ("OpenCurlyQuote", false, "\u{2018}"),
+    ("Or", false, "\u{2a54}"),
+    ("Oscr", false, "\u{1d4aa}"),
+    ("Oslash", true, "\u{d8}"),
+    ("Otilde", true, "\u{d5}"),
+    ("Otimes", false, "\u{2a37}"),
+    ("Ouml", true, "\u{d6}"),
+    ("OverBar", false, "\u{203e}"),
+    ("OverBrace", false, "\u{23de}"),
+    ("OverBracket", false, "\u{23b4}"),
+    ("OverParenthesis", false, "\u{23dc}"),
+    ("PartialD", false, "\u{2202}"),
+    ("Pcy", false, "\u{41f}"),
+    ("Pfr", false, "\u{1d513}"),
+    ("Phi", false, "\u{3a6}"),
+    ("Pi", false, "\u{3a0}"),
+    ("PlusMinus", false, "\u{b1}"),
+    ("Poincareplane", false, "\u{210c}"),
+    ("Popf", false, "\u{2119}"),
+    ("Pr", false, "\u{2abb}"),
+    ("Precedes", false, "\u{227a}"),
+    ("PrecedesEqual", false, "\u{2aaf}"),
+    ("PrecedesSlantEqual", false, "\u{227c}"),
+    ("PrecedesTilde", false, "\u{227e}"),
+    ("Prime", false, "\u{2033}"),
+    ("Product", false, "\u{220f}"),
+    ("Proportion", false, "\u{2237}"),
+    ("Proportional", false, "\u{221d}"),
+    ("Pscr", false, "\u{1d4ab}"),
+    ("Psi", false, "\u{3a8}"),
+    ("QUOT", true, "\""),
+    ("Qfr", false, "\u{1d514}"),
+    ("Qopf", false, "\u{211a}"),
+    ("Qscr", false, "\u{1d4ac}"),
+    ("RBarr", false, "\u{2910}"),
+    ("REG", true, "\u{ae}"),
+    ("Racute", false, "\u{154}"),
+    ("Rang", false, "\u{27eb}"),
+    ("Rarr", false, "\u{21a0}"),
+    ("Rarrtl", false, "\u{2916}"),
+    ("Rcaron", false, "\u{158}"),
+    ("Rcedil", false, "\u{156}"),
+    ("Rcy", false, "\u{420}"),
+    ("Re", false, "\u{211c}"),
+    ("ReverseElement", false, "\u{220b}"),
+    ("ReverseEquilibrium", false, "\u{21cb}"),
+    ("ReverseUpEquilibrium", false, "\u{296f}"),
+    ("Rfr", false, "\u{211c}"),
+    ("Rho", false, "\u{3a1}"),
+    ("RightAngleBracket", false, "\u{27e9}"),
+    ("RightArrow", false, "\u{2192}"),
+    ("RightArrowBar", false, "\u{21e5}"),
+    ("RightArrowLeftArrow", false, "\u{21c4}"),
+    ("RightCeiling", false, "\u{2309}"),
+    ("RightDoubleBracket", false, "\u{27e7}"),
+    ("RightDownTeeVector", false, "\u{295d}"),
+    ("RightDownVector", false, "\u{21c2}"),
+    ("RightDownVectorBar", false, "\u{2955}"),
+    ("RightFloor", false, "\u{230b}"),
+    ("RightTee", false, "\u{22a2}"),
+    ("RightTeeArrow", false, "\u{21a6}"),
+    ("RightTeeVector", false, "\u{295b}"),
+    ("RightTriangle", false, "\u{22b3}"),
+    ("RightTriangleBar", false, "\u{29d0}"),
+    ("RightTriangleEqual", false, "\u{22b5}"),
+    ("RightUpDownVector", false, "\u{294f}"),
+    ("RightUpTeeVector", false, "\u{295c}"),
+    ("RightUpVector", false, "\u{21be}"),
+    ("RightUpVectorBar", false, "\u{2954}"),
+    ("RightVector", false, "\u{21c0}"),
+    ("RightVectorBar", false, "\u{2953}"),
+    ("Rightarrow", false, "\u{21d2}"),
+    ("Ropf", false, "\u{211d}"),
+    ("RoundImplies", false, "\u{2970}"),
+    ("Rrightarrow", false, "\u{21db}"),
+    ("Rscr", false, "\u{211b}"),
+    ("Rsh", false, "\u{21b1}"),
+    ("RuleDelayed", false, "\u{29f4}"),
+    ("SHCHcy", false, "\u{429}"),
+    ("SHcy", false, "\u{428}"),
+    ("SOFTcy", false, "\u{42c}"),
+    ("Sacute", false, "\u{15a}"),
+    ("Sc", false, "\u{2abc}"),
+    ("Scaron", false, "\u{160}"),
+    ("Scedil", false, "\u{15e}"),
+    ("Scirc", false, "\u{15c}"),
+    ("Scy", false, "\u{421}"),
+    ("Sfr", false, "\u{1d516}"),
+    ("ShortDownArrow", false, "\u{2193}"),
+    ("ShortLeftArrow", false, "\u{2190}"),
+    ("ShortRightArrow", false, "\u{2192}"),
+    ("ShortUpArrow", false, "\u{2191}"),
+    ("Sigma", false, "\u{3a3}"),
+    ("SmallCircle", false, "\u{2218}"),
+    ("Sopf", false, "\u{1d54a}"),
+    ("Sqrt", false, "\u{221a}"),
+    ("Square", false, "\u{25a1}"),
+    ("SquareIntersection", false, "\u{2293}"),
+    ("SquareSubset", false, "\u{228f}"),
+    ("SquareSubsetEqual", false, "\u{2291}"),
+    ("SquareSuperset", false, "\u{2290}"),
+    ("SquareSupersetEqual", false, "\u{2292}"),
+    ("SquareUnion", false, "\u{2294}"),
+    ("Sscr", false, "\u{1d4ae}"),
+    ("Star", false, "\u{22c6}"),
+    ("Sub", false, "\u{22d0}"),
+    ("Subset", false, "\u{22d0}"),
+    ("SubsetEqual", false, "\u{2286}"),
+    ("Succeeds", false, "\u{227b}"),
+    ("SucceedsEqual", false, "\u{2ab0}"),
+    ("SucceedsSlantEqual", false, "\u{227d}"),
+    ("SucceedsTilde", false, "\u{227f}"),
+    ("SuchThat", false, "\u{220b}"),
+    ("Sum", false, "\u{2211}"),
+    ("Sup", false, "\u{22d1}"),
+    ("Superset", false, "\u{2283}"),
+    ("SupersetEqual", false, "\u{2287}"),
+    ("Supset", false, "\u{22d1}"),
+    ("THORN", true, "\u{de}"),
+    ("TRADE", false, "\u{2122}"),
+    ("TSHcy", false, "\u{40b}"),
+    ("TScy", false, "\u{426}"),
+    ("Tab", false, "\u{9}"),
+    ("Tau", false, "\u{3a4}"),
+    ("Tcaron", false, "\u{164}"),
+    ("Tcedil", false, "\u{162}"),
+    ("Tcy", false, "\u{422}"),
+    ("Tfr", false, "\u{1d517}"),
+    ("Therefore", false, "\u{2234}"),
+    ("Theta", false, "\u{398}"),
+    ("ThickSpace", false, "\u{205f}\u{200a}"),
+    ("ThinSpace", false, "\u{2009}"),
+    ("Tilde", false, "\u{223c}"),
+    ("TildeEqual", false, "\u{2243}"),
+    ("TildeFullEqual", false, "\u{2245}"),
+    ("TildeTilde", false, "\u{2248}"),
+    ("Topf", false, "\u{1d54b}"),
+    ("TripleDot", false, "\u{20db}"),
+    ("Tscr", false, "\u{1d4af}"),
+    ("Tstrok", false, "\u{166}"),
+    ("Uacute", true, "\u{da}"),
+    ("Uarr", false, "\u{219f}"),
+    ("Uarrocir", false, "\u{2949}"),
+    ("Ubrcy", false, "\u{40e}"),
+    ("Ubreve", false, "\u{16c}"),
+    ("Ucirc", true, "\u{db}"),
+    ("Ucy", false, "\u{423}"),
+    ("Udblac", false, "\u{170}"),
+    ("Ufr", false, "\u{1d518}"),
+    ("Ugrave", true, "\u{d9}"),
+    ("Umacr", false, "\u{16a}"),
+    ("UnderBar", false, "_"),
+    ("UnderBrace", false, "\u{23df}"),
+    ("UnderBracket", false, "\u{23b5}"),
+    ("UnderParenthesis", false, "\u{23dd}"),
+    ("Union", false, "\u{22c3}"),
+    ("UnionPlus", false, "\u{228e}"),
+    ("Uogon", false, "\u{172}"),
+    ("Uopf", false, "\u{1d54c}"),
+    ("UpArrow", false, "\u{2191}"),
+    ("UpArrowBar", false, "\u{2912}"),
+    ("UpArrowDownArrow", false, "\u{21c5}"),
+    ("UpDownArrow", false, "\u{2195}"),
+    ("UpEquilibrium", false, "\u{296e}"),
+    ("UpTee", false, "\u{22a5}"),
+    ("UpTeeArrow", false, "\u{21a5}"),
+    ("Uparrow", false, "\u{21d1}"),
+    ("Updownarrow", false, "\u{21d5}"),
+    ("UpperLeftArrow", false, "\u{2196}"),
+    ("UpperRightArrow", false, "\u{2197}"),
+    ("Upsi", false, "\u{3d2}"),
+    ("Upsilon", false, "\u{3a5}"),
+    ("Uring", false, "\u{16e}"),
+    ("Uscr", false, "\u{1d4b0}"),
+    ("Utilde", false, "\u{168}"),
+    ("Uuml", true, "\u{dc}"),
+    ("VDash", false, "\u{22ab}"),
+    ("Vbar", false, "\u{2aeb}"),
+    ("Vcy", false, "\u{412}"),
+    ("Vdash", false, "\u{22a9}"),
+    ("Vdashl", false, "\u{2ae6}"),
+    ("Vee", false, "\u{22c1}"),
+    ("Verbar", false, "\u{2016}"),
+    ("Vert", false, "\u{2016}"),
+    ("VerticalBar", false, "\u{2223}"),
+    ("VerticalLine", false, "|"),
+    ("VerticalSeparator", false, "\u{2758}"),
+    ("VerticalTilde", false, "\u{2240}"),
+    ("VeryThinSpace", false, "\u{200a}"),
+    ("Vfr", false, "\u{1d519}"),
+    ("Vopf", false, "\u{1d54d}"),
+    ("Vscr", false, "\u{1d4b1}"),
+    ("Vvdash", false, "\u{22aa}"),
+    ("Wcirc", false, "\u{174}"),
+    ("Wedge", false, "\u{22c0}"),
+    ("Wfr", false, "\u{1d51a}"),
+    ("Wopf", false, "\u{1d54e}"),
+    ("Wscr", false, "\u{1d4b2}"),
+    ("Xfr", false, "\u{1d51b}"),
+    ("Xi", false, "\u{39e}"),
+    ("Xopf", false, "\u{1d54f}"),
+    ("Xscr", false, "\u{1d4b3}"),
+    ("YAcy", false, "\u{42f}"),
+    ("YIcy", false, "\u{407}"),
+    ("YUcy", false, "\u{42e}"),
+    ("Yacute", true, "\u{dd}"),
+    ("Ycirc", false, "\u{176}"),
+    ("Ycy", false, "\u{42b}"),
+    ("Yfr", false, "\u{1d51c}"),
+    ("Yopf", false, "\u{1d550}"),
+    ("Yscr", false, "\u{1d4b4}"),
+    ("Yuml", false, "\u{178}"),
+    ("ZHcy", false, "\u{416}"),
+    ("Zacute", false, "\u{179}"),
+    ("Zcaron", false, "\u{17d}"),
+    ("Zcy", false, "\u{417}"),
+    ("Zdot", false, "\u{17b}"),
+    ("ZeroWidthSpace", false, "\u{200b}"),
+    ("Zeta", false, "\u{396}"),
+    ("Zfr", false, "\u{2128}"),
+    ("Zopf", false, "\u{2124}"),
+    ("Zscr", false, "\u{1d4b5}"),
+    ("aacute", true, "\u{e1}"),
+    ("abreve", false, "\u{103}"),
+    ("ac", false, "\u{223e}"),
+    ("acE", false, "\u{223e}\u{333}"),
+    ("acd", false, "\u{223f}"),
+    ("acirc", true, "\u{e2}"),
+    ("acute", true, "\u{b4}"),
+    ("acy", false, "\u{430}"),
+    ("aelig", true, "\u{e6}"),
+    ("af", false, "\u{2061}"),
+    ("afr", false, "\u{1d51e}"),
+    ("agrave", true, "\u{e0}"),
+    ("alefsym", false, "\u{2135}"),
+    ("aleph", false, "\u{2135}"),
+    ("alpha", false, "\u{3b1}"),
+    ("amacr", false, "\u{101}"),
+    ("amalg", false, "\u{2a3f}"),
+    ("amp", true, "&"),
+    ("and", false, "\u{2227}"),
+    ("andand", false, "\u{2a55}"),
+    ("andd", false, "\u{2a5c}"),
+    ("andslope", false, "\u{2a58}"),
+    ("andv", false, "\u{2a5a}"),
+    ("ang", false, "\u{2220}"),
+    ("ange", false, "\u{29a4}"),
+    ("angle", false, "\u{2220}"),
+    ("angmsd", false, "\u{2221}"),
+    ("angmsdaa", false, "\u{29a8}"),
+    ("angmsdab", false, "\u{29a9}"),
+    ("angmsdac", false, "\u{29aa}"),
+    ("angmsdad", false, "\u{29ab}"),
+    ("angmsdae", false, "\u{29ac}"),
+    ("angmsdaf", false, "\u{29ad}"),
+    ("angmsdag", false, "\u{29ae}"),
+    ("angmsdah", false, "\u{29af}"),
+    ("angrt", false, "\u{221f}"),
+    ("angrtvb", false, "\u{22be}"),
+    ("angrtvbd", false, "\u{299d}"),
+    ("angsph", false, "\u{2222}"),
+    ("angst", false, "\u{c5}"),
+    ("angzarr", false, "\u{237c}"),
+    ("aogon", false, "\u{105}"),
+    ("aopf", false, "\u{1d552}"),
+    ("ap", false, "\u{2248}"),
+    ("apE", false, "\u{2a70}"),
+    ("apacir", false, "\u{2a6f}"),
+    ("ape", false, "\u{224a}"),
+    ("apid", false, "\u{224b}"),
+    ("apos", false, "'"),
+    ("approx", false, "\u{2248}"),
+    ("approxeq", false, "\u{224a}"),
+    ("aring", true, "\u{e5}"),
+    ("ascr", false, "\u{1d4b6}"),
+    ("ast", false, "*"),
+    ("asymp", false, "\u{2248}"),
+    ("asympeq", false, "\u{224d}"),
+    ("atilde", true, "\u{e3}"),
+    ("auml", true, "\u{e4}"),
+    ("awconint", false, "\u{2233}"),
+    ("awint", false, "\u{2a11}"),
+    ("bNot", false, "\u{2aed}"),
+    ("backcong", false, "\u{224c}"),
+    ("backepsilon", false, "\u{3f6}"),
+    ("backprime", false, "\u{2035}"),
+    ("backsim", false, "\u{223d}"),
+    ("backsimeq", false, "\u{22cd}"),
+    ("barvee", false, "\u{22bd}"),
+    ("barwed", false, "\u{2305}"),
+    ("barwedge", false, "\u{2305}"),
+    ("bbrk", false, "\u{23b5}"),
+    ("bbrktbrk", false, "\u{23b6}"),
+    ("bcong", false, "\u{224c}"),
+    ("bcy", false, "\u{431}"),
+    ("bdquo", false, "\u{201e}"),
+    ("becaus", false, "\u{2235}"),
+    ("because", false, "\u{2235}"),
+    ("bemptyv", false, "\u{29b0}"),
+    ("bepsi", false, "\u{3f6}"),
+    ("bernou", false, "\u{212c}"),
+    ("beta", false, "\u{3b2}"),
+    ("beth", false, "\u{2136}"),
+    ("between", false, "\u{226c}"),
+    ("bfr", false, "\u{1d51f}"),
+    ("bigcap", false, "\u{22c2}"),
+    ("bigcirc", false, "\u{25ef}"),
+    ("bigcup", false, "\u{22c3}"),
+    ("bigodot", false, "\u{2a00}"),
+    ("bigoplus", false, "\u{2a01}"),
+    ("bigotimes", false, "\u{2a02}"),
+    ("bigsqcup", false, "\u{2a06}"),
+    ("bigstar", false, "\u{2605}"),
+    ("bigtriangledown", false, "\u{25bd}"),
+    ("bigtriangleup", false, "\u{25b3}"),
+    ("biguplus", false, "\u{2a04}"),
+    ("bigvee", false, "\u{22c1}"),
+    ("bigwedge", false, "\u{22c0}"),
+    ("bkarow", false, "\u{290d}"),
+    ("blacklozenge", false, "\u{29eb}"),
+    ("blacksquare", false, "\u{25aa}"),
+    ("blacktriangle", false, "\u{25b4}"),
+    ("blacktriangledown", false, "\u{25be}"),
+    ("blacktriangleleft", false, "\u{25c2}"),
+    ("blacktriangleright", false, "\u{25b8}"),
+    ("blank", false, "\u{2423}"),
+    ("blk12", false, "\u{2592}"),
+    ("blk14", false, "\u{2591}"),
+    ("blk34", false, "\u{2593}"),
+    ("block", false, "\u{2588}"),
+    ("bne", false, "=\u{20e5}"),
+    ("bnequiv", false, "\u{2261}\u{20e5}"),
+    ("bnot", false, "\u{2310}"),
+    ("bopf", false, "\u{1d553}"),
+    ("bot", false, "\u{22a5}"),
+    ("bottom", false, "\u{22a5}"),
+    ("bowtie", false, "\u{22c8}"),
+    ("boxDL", false, "\u{2557}"),
+    ("boxDR", false, "\u{2554}"),
+    ("boxDl", false, "\u{2556}"),
+    ("boxDr", false, "\u{2553}"),
+    ("boxH", false, "\u{2550}"),
+    ("boxHD", false, "\u{2566}"),
+    ("boxHU", false, "\u{2569}"),
+    ("boxHd", false, "\u{2564}"),
+    ("boxHu", false, "\u{2567}"),
+    ("boxUL", false, "\u{255d}"),
+    ("boxUR", false, "\u{255a}"),
+    ("boxUl", false, "\u{255c}"),
+    ("boxUr", false, "\u{2559}"),
+    ("boxV", false, "\u{2551}"),
+    ("boxVH", false, "\u{256c}"),
+    ("boxVL", false, "\u{2563}"),
+    ("boxVR", false, "\u{2560}"),
+    ("boxVh", false, "\u{256b}"),
+    ("boxVl", false, "\u{2562}"),
+    ("boxVr", false, "\u{255f}"),
+    ("boxbox", false, "\u{29c9}"),
+    ("boxdL", false, "\u{2555}"),
+    ("boxdR", false, "\u{2552}"),
+    ("boxdl", false, "\u{2510}"),
+    ("boxdr", false, "\u{250c}"),
+    ("boxh", false, "\u{2500}"),
+    ("boxhD", false, "\u{2565}"),
+    ("boxhU", false, "\u{2568}"),
+    ("boxhd", false, "\u{252c}"),
+    ("boxhu", false, "\u{2534}"),
+    ("boxminus", false, "\u{229f}"),
+    ("boxplus", false, "\u{229e}"),
+    ("boxtimes", false, "\u{22a0}"),
+    ("boxuL", false, "\u{255b}"),
+    ("boxuR", false, "\u{2558}"),
+    ("boxul", false, "\u{2518}"),
+    ("boxur", false, "\u{2514}"),
+    ("boxv", false, "\u{2502}"),
+    ("boxvH", false, "\u{256a}"),
+    ("boxvL", false, "\u{2561}"),
+    ("boxvR", false, "\u{255e}"),
+    ("boxvh", false, "\u{253c}"),
+    ("boxvl", false, "\u{2524}"),
+    ("boxvr", false, "\u{251c}"),
+    ("bprime", false, "\u{2035}"),
+    ("breve", false, "\u{2d8}"),
+    ("brvbar", true, "\u{a6}"),
+    ("bscr", false, "\u{1d4b7}"),
+    ("bsemi", false, "\u{204f}"),
+    ("bsim", false, "\u{223d}"),
+    ("bsime", false, "\u{22cd}"),
+    ("bsol", false, "\\"),
+    ("bsolb", false, "\u{29c5}"),
+    ("bsolhsub", false, "\u{27c8}"),
+    ("bull", false, "\u{2022}"),
+    ("bullet", false, "\u{2022}"),
+    ("bump", false, "\u{224e}"),
+    ("bumpE", false, "\u{2aae}"),
+    ("bumpe", false, "\u{224f}"),
+    ("bumpeq", false, "\u{224f}"),
+    ("cacute", false, "\u{107}"),
+    ("cap", false, "\u{2229}"),
+    ("capand", false, "\u{2a44}"),
+    ("capbrcup", false, "\u{2a49}"),
+    ("capcap", false, "\u{2a4b}"),
+    ("capcup", false, "\u{2a47}"),
+    ("capdot", false, "\u{2a40}"),
+    ("caps", false, "\u{2229}\u{fe00}"),
+    ("caret", false, "\u{2041}"),
+    ("caron", false, "\u{2c7}"),
+    ("ccaps", false, "\u{2a4d}"),
+    ("ccaron", false, "\u{10d}"),
+    ("ccedil", true, "\u{e7}"),
+    ("ccirc", false, "\u{109}"),
+    ("ccups", false, "\u{2a4c}"),
+    ("ccupssm", false, "\u{2a50}"),
+    ("cdot", false, "\u{10b}"),
+    ("cedil", true, "\u{b8}"),
+    ("cemptyv", false, "\u{29b2}"),
+    ("cent", true, "\u{a2}"),
+    ("centerdot", false, "\u{b7}"),
+    ("cfr", false, "\u{1d520}"),
+    ("chcy", false, "\u{447}"),
+    ("check", false, "\u{2713}"),
+    ("checkmark", false, "\u{2713}"),
+    ("chi", false, "\u{3c7}"),
+    ("cir", false, "\u{25cb}"),
+    ("cirE", false, "\u{29c3}"),
+    ("circ", false, "\u{2c6}"),
+    ("circeq", false, "\u{2257}"),
+    ("circlearrowleft", false, "\u{21ba}"),
+    ("circlearrowright", false, "\u{21bb}"),
+    ("circledR", false, "\u{ae}"),
+    ("circledS", false, "\u{24c8}"),
+    ("circledast", false, "\u{229b}"),
+    ("circledcirc", false, "\u{229a}"),
+    ("circleddash", false, "\u{229d}"),
+    ("cire", false, "\u{2257}"),
+    ("cirfnint", false, "\u{2a10}"),
+    ("cirmid", false, "\u{2aef}"),
+    ("cirscir", false, "\u{29c2}"),
+    ("clubs", false, "\u{2663}"),
+    ("clubsuit", false, "\u{2663}"),
+    ("colon", false, ":"),
+    ("colone", false, "\u{2254}"),
+    ("coloneq", false, "\u{2254}"),
+    ("comma", false, ","),
+    ("commat", false, "@"),
+    ("comp", false, "\u{2201}"),
+    ("compfn", false, "\u{2218}"),
+    ("complement", false, "\u{2201}"),
+    ("complexes", false, "\u{2102}"),
+    ("cong", false, "\u{2245}"),
+    ("congdot", false, "\u{2a6d}"),
+    ("conint", false, "\u{222e}"),
+    ("copf", false, "\u{1d554}"),
+    ("coprod", false, "\u{2210}"),
+    ("copy", true, "\u{a9}"),
+    ("copysr", false, "\u{2117}"),
+    ("crarr", false, "\u{21b5}"),
+    ("cross", false, "\u{2717}"),
+    ("cscr", false, "\u{1d4b8}"),
+    ("csub", false, "\u{2acf}"),
+    ("csube", false, "\u{2ad1}"),
+    ("csup", false, "\u{2ad0}"),
+    ("csupe", false, "\u{2ad2}"),
+    ("ctdot", false, "\u{22ef}"),
+    ("cudarrl", false, "\u{2938}"),
+    ("cudarrr", false, "\u{2935}"),
+    ("cuepr", false, "\u{22de}"),
+    ("cuesc", false, "\u{22df}"),
+    ("cularr", false, "\u{21b6}"),
+    ("cularrp", false, "\u{293d}"),
+    ("cup", false, "\u{222a}"),
+    ("cupbrcap", false, "\u{2a48}"),
+    ("cupcap", false, "\u{2a46}"),
+    ("cupcup", false, "\u{2a4a}"),
+    ("cupdot", false, "\u{228d}"),
+    ("cupor", false, "\u{2a45}"),
+    ("cups", false, "\u{222a}\u{fe00}"),
+    ("curarr", false, "\u{21b7}"),
+    ("curarrm", false, "\u{293c}"),
+    ("curlyeqprec", false, "\u{22de}"),
+    ("curlyeqsucc", false, "\u{22df}"),
+    ("curlyvee", false, "\u{22ce}"),
+    ("curlywedge", false, "\u{22cf}"),
+    ("curren", true, "\u{a4}"),
+    ("curvearrowleft", false, "\u{21b6}"),
+    ("curvearrowright", false, "\u{21b7}"),
+    ("cuvee", false, "\u{22ce}"),
+    ("cuwed", false, "\u{22cf}"),
+    ("cwconint", false, "\u{2232}"),
+    ("cwint", false, "\u{2231}"),
+    ("cylcty", false, "\u{232d}"),
+    ("dArr", false, "\u{21d3}"),
+    ("dHar", false, "\u{2965}"),
+    ("dagger", false, "\u{2020}"),
+    ("daleth", false, "\u{2138}"),
+    ("darr", false, "\u{2193}"),
+    ("dash", false, "\u{2010}"),
+    ("dashv", false, "\u{22a3}"),
+    ("dbkarow", false, "\u{290f}"),
+    ("dblac", false, "\u{2dd}"),
+    ("dcaron", false, "\u{10f}"),
+    ("dcy", false, "\u{434}"),
+    ("dd", false, "\u{2146}"),
+    ("ddagger", false, "\u{2021}"),
+    ("ddarr", false, "\u{21ca}"),
+    ("ddotseq", false, "\u{2a77}"),
+    ("deg", true, "\u{b0}"),
+    ("delta", false, "\u{3b4}"),
+    ("demptyv", false, "\u{29b1}"),
+    ("dfisht", false, "\u{297f}"),
+    ("dfr", false, "\u{1d521}"),
+    ("dharl", false, "\u{21c3}"),
+    ("dharr", false, "\u{21c2}"),
+    ("diam", false, "\u{22c4}"),
+    ("diamond", false, "\u{22c4}"),
+    ("diamondsuit", false, "\u{2666}"),
+    ("diams", false, "\u{2666}"),
+    ("die", false, "\u{a8}"),
+    ("digamma", false, "\u{3dd}"),
+    ("disin", false, "\u{22f2}"),
+    ("div", false, "\u{f7}"),
+    ("divide", true, "\u{f7}"),
+    ("divideontimes", false, "\u{22c7}"),
+    ("divonx", false, "\u{22c7}"),
+    ("djcy", false, "\u{452}"),
+    ("dlcorn", false, "\u{231e}"),
+    ("dlcrop", false, "\u{230d}"),
+    ("dollar", false, "$"),
+    ("dopf", false, "\u{1d555}"),
+    ("dot", false, "\u{2d9}"),
+    ("doteq", false, "\u{2250}"),
+    ("doteqdot", false, "\u{2251}"),
+    ("dotminus", false, "\u{2238}"),
+    ("dotplus", false, "\u{2214}"),
+    ("dotsquare", false, "\u{22a1}"),
+    ("doublebarwedge", false, "\u{2306}"),
+    ("downarrow", false, "\u{2193}"),
+    ("downdownarrows", false, "\u{21ca}"),
+    ("downharpoonleft", false, "\u{21c3}"),
+    ("downharpoonright", false, "\u{21c2}"),
+    ("drbkarow", false, "\u{2910}"),
+    ("drcorn", false, "\u{231f}"),
+    ("drcrop", false, "\u{230c}"),
+    ("dscr", false, "\u{1d4b9}"),
+    ("dscy", false, "\u{455}"),
+    ("dsol", false, "\u{29f6}"),
+    ("dstrok", false, "\u{111}"),
+    ("dtdot", false, "\u{22f1}"),
+    ("dtri", false, "\u{25bf}"),
+    ("dtrif", false, "\u{25be}"),
+    ("duarr", false, "\u{21f5}"),
+    ("duhar", false, "\u{296f}"),
+    ("dwangle", false, "\u{29a6}"),
+    ("dzcy", false, "\u{45f}"),
+    ("dzigrarr", false, "\u{27ff}"),
+    ("eDDot", false, "\u{2a77}"),
+    ("eDot", false, "\u{2251}"),
+    ("eacute", true, "\u{e9}"),
+    ("easter", false, "\u{2a6e}"),
+    ("ecaron", false, "\u{11b}"),
+    ("ecir", false, "\u{2256}"),
+    ("ecirc", true, "\u{ea}"),
+    ("ecolon", false, "\u{2255}"),
+    ("ecy", false, "\u{44d}"),
+    ("edot", false, "\u{117}"),
+    ("ee", false, "\u{2147}"),
+    ("efDot", false, "\u{2252}"),
+    ("efr", false, "\u{1d522}"),
+    ("eg", false, "\u{2a9a}"),
+    ("egrave", true, "\u{e8}"),
+    ("egs", false, "\u{2a96}"),
+    ("egsdot", false, "\u{2a98}"),
+    ("el", false, "\u{2a99}"),
+    ("elinters", false, "\u{23e7}"),
+    ("ell", false, "\u{2113}"),
+    ("els", false, "\u{2a95}"),
+    ("elsdot", false, "\u{2a97}"),
+    ("emacr", false, "\u{113}"),
+    ("empty", false, "\u{2205}"),
+    ("emptyset", false, "\u{2205}"),
+    ("emptyv", false, "\u{2205}"),
+    ("emsp", false, "\u{2003}"),
+    ("emsp13", false, "\u{2004}"),
+    ("emsp14", false, "\u{2005}"),
+    ("eng", false, "\u{14b}"),
+    ("ensp", false, "\u{2002}"),
+    ("eogon", false, "\u{119}"),
+    ("eopf", false, "\u{1d556}"),
+    ("epar", false, "\u{22d5}"),
+    ("eparsl", false, "\u{29e3}"),
+    ("eplus", false, "\u{2a71}"),
+    ("epsi", false, "\u{3b5}"),
+    ("epsilon", false, "\u{3b5}"),
+    ("epsiv", false, "\u{3f5}"),
+    ("eqcirc", false, "\u{2256}"),
+    ("eqcolon", false, "\u{2255}"),
+    ("eqsim", false, "\u{2242}"),
+    ("eqslantgtr", false, "\u{2a96}"),
+    ("eqslantless", false, "\u{2a95}"),
+    ("equals", false, "="),
+    ("equest", false, "\u{225f}"),
+    ("equiv", false, "\u{2261}"),
+    ("equivDD", false, "\u{2a78}"),
+    ("eqvparsl", false, "\u{29e5}"),
+    ("erDot", false, "\u{2253}"),
+    ("erarr", false, "\u{2971}"),
+    ("escr", false, "\u{212f}"),
+    ("esdot", false, "\u{2250}"),
+    ("esim", false, "\u{2242}"),
+    ("eta", false, "\u{3b7}"),
+    ("eth", true, "\u{f0}"),
+    ("euml", true, "\u{eb}"),
+    ("euro", false, "\u{20ac}"),
+    ("excl", false, "!"),
+    ("exist", false, "\u{2203}"),
+    ("expectation", false, "\u{2130}"),
+    ("exponentiale", false, "\u{2147}"),
+    ("fallingdotseq", false, "\u{2252}"),
+    ("fcy", false, "\u{444}"),
+    ("female", false, "\u{2640}"),
+    ("ffilig", false, "\u{fb03}"),
+    ("fflig", false, "\u{fb00}"),
+    ("ffllig", false, "\u{fb04}"),
+    ("ffr", false, "\u{1d523}"),
+    ("filig", false, "\u{fb01}"),
+    ("fjlig", false, "fj"),
+    ("flat", false, "\u{266d}"),
+    ("fllig", false, "\u{fb02}"),
+    ("fltns", false, "\u{25b1}"),
+    ("fnof", false, "\u{192}"),
+    ("fopf", false, "\u{1d557}"),
+    ("forall", false, "\u{2200}"),
+    ("fork", false, "\u{22d4}"),
+    ("forkv", false, "\u{2ad9}"),
+    ("fpartint", false, "\u{2a0d}"),
+    ("frac12", true, "\u{bd}"),
+    ("frac13", false, "\u{2153}"),
+    ("frac14", true, "\u{bc}"),
+    ("frac15", false, "\u{2155}"),
+    ("frac16", false, "\u{2159}"),
+    ("frac18", false, "\u{215b}"),
+    ("frac23", false, "\u{2154}"),
+    ("frac25", false, "\u{2156}"),
+    ("frac34", true, "\u{be}"),
+    ("frac35", false, "\u{2157}"),
+    ("frac38", false, "\u{215c}"),
+    ("frac45", false, "\u{2158}"),
+    ("frac56", false, "\u{215a}"),
+    ("frac58", false, "\u{215d}"),
+    ("frac78", false, "\u{215e}"),
+    ("frasl", false, "\u{2044}"),
+    ("frown", false, "\u{2322}"),
+    ("fscr", false, "\u{1d4bb}"),
+    ("gE", false, "\u{2267}"),
+    ("gEl", false, "\u{2a8c}"),
+    ("gacute", false, "\u{1f5}"),
+    ("gamma", false, "\u{3b3}"),
+    ("gammad", false, "\u{3dd}"),
+    ("gap", false, "\u{2a86}"),
+    ("gbreve", false, "\u{11f}"),
+    ("gcirc", false, "\u{11d}"),
+    ("gcy", false, "\u{433}"),
+    ("gdot", false, "\u{121}"),
+    ("ge", false, "\u{2265}"),
+    ("gel", false, "\u{22db}"),
+    ("geq", false, "\u{2265}"),
+    ("geqq", false, "\u{2267}"),
+    ("geqslant", false, "\u{2a7e}"),
+    ("ges", false, "\u{2a7e}"),
+    ("gescc", false, "\u{2aa9}"),
+    ("gesdot", false, "\u{2a80}"),
+    ("gesdoto", false, "\u{2a82}"),
+    ("gesdotol", false, "\u{2a84}"),
+    ("gesl", false, "\u{22db}\u{fe00}"),
+    ("gesles", false, "\u{2a94}"),
+    ("gfr", false, "\u{1d524}"),
+    ("gg", false, "\u{226b}"),
+    ("ggg", false, "\u{22d9}"),
+    ("gimel", false, "\u{2137}"),
+    ("gjcy", false, "\u{453}"),
+    ("gl", false, "\u{2277}"),
+    ("glE", false, "\u{2a92}"),
+    ("gla", false, "\u{2aa5}"),
+    ("glj", false, "\u{2aa4}"),
+    ("gnE", false, "\u{2269}"),
+    ("gnap", false, "\u{2a8a}"),
+    ("gnapprox", false, "\u{2a8a}"),
+    ("gne", false, "\u{2a88}"),
+    ("gneq", false, "\u{2a88}"),
+    ("gneqq", false, "\u{2269}"),
+    ("gnsim", false, "\u{22e7}"),
+    ("gopf", false, "\u{1d558}"),
+    ("grave", false, "`"),
+    ("gscr", false, "\u{210a}"),
+    ("gsim", false, "\u{2273}"),
+    ("gsime", false, "\u{2a8e}"),
+    ("gsiml", false, "\u{2a90}"),
+    ("gt", true, ">"),
+    ("gtcc", false, "\u{2aa7}"),
+    ("gtcir", false, "\u{2a7a}"),
+    ("gtdot", false, "\u{22d7}"),
+    ("gtlPar", false, "\u{2995}"),
+    ("gtquest", false, "\u{2a7c}"),
+    ("gtrapprox", false, "\u{2a86}"),
+    ("gtrarr", false, "\u{2978}"),
+    ("gtrdot", false, "\u{22d7}"),
+    ("gtreqless", false, "\u{22db}"),
+    ("gtreqqless", false, "\u{2a8c}"),
+    ("gtrless", false, "\u{2277}"),
+    ("gtrsim", false, "\u{2273}"),
+    ("gvertneqq", false, "\u{2269}\u{fe00}"),
+    ("gvnE", false, "\u{2269}\u{fe00}"),
+    ("hArr", false, "\u{21d4}"),
+    ("hairsp", false, "\u{200a}"),
+    ("half", false, "\u{bd}"),
+    ("hamilt", false, "\u{210b}"),
+    ("hardcy", false, "\u{44a}"),
+    ("harr", false, "\u{2194}"),
+    ("harrcir", false, "\u{2948}"),
+    ("harrw", false, "\u{21ad}"),
+    ("hbar", false, "\u{210f}"),
+    ("hcirc", false, "\u{125}"),
+    ("hearts", false, "\u{2665}"),
+    ("heartsuit", false, "\u{2665}"),
+    ("hellip", false, "\u{2026}"),
+    ("hercon", false, "\u{22b9}"),
+    ("hfr", false, "\u{1d525}"),
+    ("hksearow", false, "\u{2925}"),
+    ("hkswarow", false, "\u{2926}"),
+    ("hoarr", false, "\u{21ff}"),
+    ("homtht", false, "\u{223b}"),
+    ("hookleftarrow", false, "\u{21a9}"),
+    ("hookrightarrow", false, "\u{21aa}"),
+    ("hopf", false, "\u{1d559}"),
+    ("horbar", false, "\u{2015}"),
+    ("hscr", false, "\u{1d4bd}"),
+    ("hslash", false, "\u{210f}"),
+    ("hstrok", false, "\u{127}"),
+    ("hybull", false, "\u{2043}"),
+    ("hyphen", false, "\u{2010}"),
+    ("iacute", true, "\u{ed}"),
+    ("ic", false, "\u{2063}"),
+    ("icirc", true, "\u{ee}"),
+    ("icy", false, "\u{438}"),
+    ("iecy", false, "\u{435}"),
+    ("iexcl", true, "\u{a1}"),
+    ("iff", false, "\u{21d4}"),
+    ("ifr", false, "\u{1d526}"),
+    ("igrave", true, "\u{ec}"),
+    ("ii", false, "\u{2148}"),
+    ("iiiint", false, "\u{2a0c}"),
+    ("iiint", false, "\u{222d}"),
+    ("iinfin", false, "\u{29dc}"),
+    ("iiota", false, "\u{2129}"),
+    ("ijlig", false, "\u{133}"),
+    ("imacr", false, "\u{12b}"),
+    ("image", false, "\u{2111}"),
+    ("imagline", false, "\u{2110}"),
+    ("imagpart", false, "\u{2111}"),
+    ("imath", false, "\u{131}"),
+    ("imof", false, "\u{22b7}"),
+    ("imped", false, "\u{1b5}"),
+    ("in", false, "\u{2208}"),
+    ("incare", false, "\u{2105}"),
+    ("infin", false, "\u{221e}"),
+    ("infintie", false, "\u{29dd}"),
+    ("inodot", false, "\u{131}"),
+    ("int", false, "\u{222b}"),
+    ("intcal", false, "\u{22ba}"),
+    ("integers", false, "\u{2124}"),
+    ("intercal", false, "\u{22ba}"),
+    ("intlarhk", false, "\u{2a17}"),
+    ("intprod", false, "\u{2a3c}"),
+    ("iocy", false, "\u{451}"),
+    ("iogon", false, "\u{12f}"),
+    ("iopf", false, "\u{1d55a}"),
+    ("iota", false, "\u{3b9}"),
+    ("iprod", false, "\u{2a3c}"),
+    ("iquest", true, "\u{bf}"),
+    ("iscr", false, "\u{1d4be}"),
+    ("isin", false, "\u{2208}"),
+    ("isinE", false, "\u{22f9}"),
+    ("isindot", false, "\u{22f5}"),
+    ("isins", false, "\u{22f4}"),
+    ("isinsv", false, "\u{22f3}"),
+    ("isinv", false, "\u{2208}"),
+    ("it", false, "\u{2062}"),
+    ("itilde", false, "\u{129}"),
+    ("iukcy", false, "\u{456}"),
+    ("iuml", true, "\u{ef}"),
+    ("jcirc", false, "\u{135}"),
+    ("jcy", false, "\u{439}"),
+    ("jfr", false, "\u{1d527}"),
+    ("jmath", false, "\u{237}"),
+    ("jopf", false, "\u{1d55b}"),
+    ("jscr", false, "\u{1d4bf}"),
+    ("jsercy", false, "\u{458}"),
+    ("jukcy", false, "\u{454}"),
+    ("kappa", false, "\u{3ba}"),
+    ("kappav", false, "\u{3f0}"),
+    ("kcedil", false, "\u{137}"),
+    ("kcy", false, "\u{43a}"),
+    ("kfr", false, "\u{1d528}"),
+    ("kgreen", false, "\u{138}"),
+    ("khcy", false, "\u{445}"),
+    ("kjcy", false, "\u{45c}"),
+    ("kopf", false, "\u{1d55c}"),
+    ("kscr", false, "\u{1d4c0}"),
+    ("lAarr", false, "\u{21da}"),
+    ("lArr", false, "\u{21d0}"),
+    ("lAtail", false, "\u{291b}"),
+    ("lBarr", false, "\u{290e}"),
+    ("lE", false, "\u{2266}"),
+    ("lEg", false, "\u{2a8b}"),
+    ("lHar", false, "\u{2962}"),
+    ("lacute", false, "\u{13a}"),
+    ("laemptyv", false, "\u{29b4}"),
+    ("lagran", false, "\u{2112}"),
+    ("lambda", false, "\u{3bb}"),
+    ("lang", false, "\u{27e8}"),
+    ("langd", false, "\u{2991}"),
+    ("langle", false, "\u{27e8}"),
+    ("lap", false, "\u{2a85}"),
+    ("laquo", true, "\u{ab}"),
+    ("larr", false, "\u{2190}"),
+    ("larrb", false, "\u{21e4}"),
+    ("larrbfs", false, "\u{291f}"),
+    ("larrfs", false, "\u{291d}"),
+    ("larrhk", false, "\u{21a9}"),
+    ("larrlp", false, "\u{21ab}"),
+    ("larrpl", false, "\u{2939}"),
+    ("larrsim", false, "\u{2973}"),
+    ("larrtl", false, "\u{21a2}"),
+    ("lat", false, "\u{2aab}"),
+    ("latail", false, "\u{2919}"),
+    ("late", false, "\u{2aad}"),
+    ("lates", false, "\u{2aad}\u{fe00}"),
+    ("lbarr", false, "\u{290c}"),
+    ("lbbrk", false, "\u{2772}"),
+    ("lbrace", false, "{"),
+    ("lbrack", false, "["),
+    ("lbrke", false, "\u{298b}"),
+    ("lbrksld", false, "\u{298f}"),
+    ("lbrkslu", false, "\u{298d}"),
+    ("lcaron", false, "\u{13e}"),
+    ("lcedil", false, "\u{13c}"),
+    ("lceil", false, "\u{2308}"),
+    ("lcub", false, "{"),
+    ("lcy", false, "\u{43b}"),
+    ("ldca", false, "\u{2936}"),
+    ("ldquo", false, "\u{201c}"),
+    ("ldquor", false, "\u{201e}"),
+    ("ldrdhar", false, "\u{2967}"),
+    ("ldrushar", false, "\u{294b}"),
+    ("ldsh", false, "\u{21b2}"),
+    ("le", false, "\u{2264}"),
+    ("leftarrow", false, "\u{2190}"),
+    ("leftarrowtail", false, "\u{21a2}"),
+    ("leftharpoondown", false, "\u{21bd}"),
+    ("leftharpoonup", false, "\u{21bc}"),
+    ("leftleftarrows", false, "\u{21c7}"),
+    ("leftrightarrow", false, "\u{2194}"),
+    ("leftrightarrows", false, "\u{21c6}"),
+    ("leftrightharpoons", false, "\u{21cb}"),
+    ("leftrightsquigarrow", false, "\u{21ad}"),
+    ("leftthreetimes", false, "\u{22cb}"),
+    ("leg", false, "\u{22da}"),
+    ("leq", false, "\u{2264}"),
+    ("leqq", false, "\u{2266}"),
+    ("leqslant", false, "\u{2a7d}"),
+    ("les", false, "\u{2a7d}"),
+    ("lescc", false, "\u{2aa8}"),
+    ("lesdot", false, "\u{2a7f}"),
+    ("lesdoto", false, "\u{2a81}"),
+    ("lesdotor", false, "\u{2a83}"),
+    ("lesg", false, "\u{22da}\u{fe00}"),
+    ("lesges", false, "\u{2a93}"),
+    ("lessapprox", false, "\u{2a85}"),
+    ("lessdot", false, "\u{22d6}"),
+    ("lesseqgtr", false, "\u{22da}"),
+    ("lesseqqgtr", false, "\u{2a8b}"),
+    ("lessgtr", false, "\u{2276}"),
+    ("lesssim", false, "\u{2272}"),
+    ("lfisht", false, "\u{297c}"),
+    ("lfloor", false, "\u{230a}"),
+    ("lfr", false, "\u{1d529}"),
+    ("lg", false, "\u{2276}"),
+    ("lgE", false, "\u{2a91}"),
+    ("lhard", false, "\u{21bd}"),
+    ("lharu", false, "\u{21bc}"),
+    ("lharul", false, "\u{296a}"),
+    ("lhblk", false, "\u{2584}"),
+    ("ljcy", false, "\u{459}"),
+    ("ll", false, "\u{226a}"),
+    ("llarr", false, "\u{21c7}"),
+    ("llcorner", false, "\u{231e}"),
+    ("llhard", false, "\u{296b}"),
+    ("lltri", false, "\u{25fa}"),
+    ("lmidot", false, "\u{140}"),
+    ("lmoust", false, "\u{23b0}"),
+    ("lmoustache", false, "\u{23b0}"),
+    ("lnE", false, "\u{2268}"),
+    ("lnap", false, "\u{2a89}"),
+    ("lnapprox", false, "\u{2a89}"),
+    ("lne", false, "\u{2a87}"),
+    ("lneq", false, "\u{2a87}"),
+    ("lneqq", false, "\u{2268}"),
+    ("lnsim", false, "\u{22e6}"),
+    ("loang", false, "\u{27ec}"),
+    ("loarr", false, "\u{21fd}"),
+    ("lobrk", false, "\u{27e6}"),
+    ("longleftarrow", false, "\u{27f5}"),
+    ("longleftrightarrow", false, "\u{27f7}"),
+    ("longmapsto", false, "\u{27fc}"),
+    ("longrightarrow", false, "\u{27f6}"),
+    ("looparrowleft", false, "\u{21ab}"),
+    ("looparrowright", false, "\u{21ac}"),
+    ("lopar", false, "\u{2985}"),
+    ("lopf", false, "\u{1d55d}"),
+    ("loplus", false, "\u{2a2d}"),
+    ("lotimes", false, "\u{2a34}"),
+    ("lowast", false, "\u{2217}"),
+    ("lowbar", false, "_"),
+    ("loz", false, "\u{25ca}"),
+    ("lozenge", false, "\u{25ca}"),
+    ("lozf", false, "\u{29eb}"),
+    ("lpar", false, "("),
+    ("lparlt", false, "\u{2993}"),
+    ("lrarr", false, "\u{21c6}"),
+    ("lrcorner", false, "\u{231f}"),
+    ("lrhar", false, "\u{21cb}"),
+    ("lrhard", false, "\u{296d}"),
+    ("lrm", false, "\u{200e}"),
+    ("lrtri", false, "\u{22bf}"),
+    ("lsaquo", false, "\u{2039}"),
+    ("lscr", false, "\u{1d4c1}"),
+    ("lsh", false, "\u{21b0}"),
+    ("lsim", false, "\u{2272}"),
+    ("lsime", false, "\u{2a8d}"),
+    ("lsimg", false, "\u{2a8f}"),
+    ("lsqb", false, "["),
+    ("lsquo", false, "\u{2018}"),
+    ("lsquor", false, "\u{201a}"),
+    ("lstrok", false, "\u{142}"),
+    ("lt", true, "<"),
+    ("ltcc", false, "\u{2aa6}"),
+    ("ltcir", false, "\u{2a79}"),
+    ("ltdot", false, "\u{22d6}"),
+    ("lthree", false, "\u{22cb}"),
+    ("ltimes", false, "\u{22c9}"),
+    ("ltlarr", false, "\u{2976}"),
+    ("ltquest", false, "\u{2a7b}"),
+    ("ltrPar", false, "\u{2996}"),
+    ("ltri", false, "\u{25c3}"),
+    ("ltrie", false, "\u{22b4}"),
+    ("ltrif", false, "\u{25c2}"),
+    ("lurdshar", false, "\u{294a}"),
+    ("luruhar", false, "\u{2966}"),
+    ("lvertneqq", false, "\u{2268}\u{fe00}"),
+    ("lvnE", false, "\u{2268}\u{fe00}"),
+    ("mDDot", false, "\u{223a}"),
+    ("macr", true, "\u{af}"),
+    ("male", false, "\u{2642}"),
+    ("malt", false, "\u{2720}"),
+    ("maltese", false, "\u{2720}"),
+    ("map", false, "\u{21a6}"),
+    ("mapsto", false, "\u{21a6}"),
+    ("mapstodown", false, "\u{21a7}"),
+    ("mapstoleft", false, "\u{21a4}"),
+    ("mapstoup", false, "\u{21a5}"),
+    ("marker", false, "\u{25ae}"),
+    ("mcomma", false, "\u{2a29}"),
+    ("mcy", false, "\u{43c}"),
+    ("mdash", false, "\u{2014}"),
+    ("measuredangle", false, "\u{2221}"),
+    ("mfr", false, "\u{1d52a}"),
+    ("mho", false, "\u{2127}"),
+    ("micro", true, "\u{b5}"),
+    ("mid", false, "\u{2223}"),
+    ("midast", false, "*"),
+    ("midcir", false, "\u{2af0}"),
+    ("middot", true, "\u{b7}"),
+    ("minus", false, "\u{2212}"),
+    ("minusb", false, "\u{229f}"),
+    ("minusd", false, "\u{2238}"),
+    ("minusdu", false, "\u{2a2a}"),
+    ("mlcp", false, "\u{2adb}"),
+    ("mldr", false, "\u{2026}"),
+    ("mnplus", false, "\u{2213}"),
+    ("models", false, "\u{22a7}"),
+    ("mopf", false, "\u{1d55e}"),
+    ("mp", false, "\u{2213}"),
+    ("mscr", false, "\u{1d4c2}"),
+    ("mstpos", false, "\u{223e}"),
+    ("mu", false, "\u{3bc}"),
+    ("multimap", false, "\u{22b8}"),
+    ("mumap", false, "\u{22b8}"),
+    ("nGg", false, "\u{22d9}\u{338}"),
+    ("nGt", false, "\u{226b}\u{20d2}"),
+    ("nGtv", false, "\u{226b}\u{338}"),
+    ("nLeftarrow", false, "\u{21cd}"),
+    ("nLeftrightarrow", false, "\u{21ce}"),
+    ("nLl", false, "\u{22d8}\u{338}"),
+    ("nLt", false, "\u{226a}\u{20d2}"),
+    ("nLtv", false, "\u{226a}\u{338}"),
+    ("nRightarrow", false, "\u{21cf}"),
+    ("nVDash", false, "\u{22af}"),
+    ("nVdash", false, "\u{22ae}"),
+    ("nabla", false, "\u{2207}"),
+    ("nacute", false, "\u{144}"),
+    ("nang", false, "\u{2220}\u{20d2}"),
+    ("nap", false, "\u{2249}"),
+    ("napE", false, "\u{2a70}\u{338}"),
+    ("napid", false, "\u{224b}\u{338}"),
+    ("napos", false, "\u{149}"),
+    ("napprox", false, "\u{2249}"),
+    ("natur", false, "\u{266e}"),
+    ("natural", false, "\u{266e}"),
+    ("naturals", false, "\u{2115}"),
+    ("nbsp", true, "\u{a0}"),
+    ("nbump", false, "\u{224e}\u{338}"),
+    ("nbumpe", false, "\u{224f}\u{338}"),
+    ("ncap", false, "\u{2a43}"),
+    ("ncaron", false, "\u{148}"),
+    ("ncedil", false, "\u{146}"),
+    ("ncong", false, "\u{2247}"),
+    ("ncongdot", false, "\u{2a6d}\u{338}"),
+    ("ncup", false, "\u{2a42}"),
+    ("ncy", false, "\u{43d}"),
+    ("ndash", false, "\u{2013}"),
+    ("ne", false, "\u{2260}"),
+    ("neArr", false, "\u{21d7}"),
+    ("nearhk", false, "\u{2924}"),
+    ("nearr", false, "\u{2197}"),
+    ("nearrow", false, "\u{2197}"),
+    ("nedot", false, "\u{2250}\u{338}"),
+    ("nequiv", false, "\u{2262}"),
+    ("nesear", false, "\u{2928}"),
+    ("nesim", false, "\u{2242}\u{338}"),
+    ("nexist", false, "\u{2204}"),
+    ("nexists", false, "\u{2204}"),
+    ("nfr", false, "\u{1d52b}"),
+    ("ngE", false, "\u{2267}\u{338}"),
+    ("nge", false, "\u{2271}"),
+    ("ngeq", false, "\u{2271}"),
+    ("ngeqq", false, "\u{2267}\u{338}"),
+    ("ngeqslant", false, "\u{2a7e}\u{338}"),
+    ("nges", false, "\u{2a7e}\u{338}"),
+    ("ngsim", false, "\u{2275}"),
+    ("ngt", false, "\u{226f}"),
+    ("ngtr", false, "\u{226f}"),
+    ("nhArr", false, "\u{21ce}"),
+    ("nharr", false, "\u{21ae}"),
+    ("nhpar", false, "\u{2af2}"),
+    ("ni", false, "\u{220b}"),
+    ("nis", false, "\u{22fc}"),
+    ("nisd", false, "\u{22fa}"),
+    ("niv", false, "\u{220b}"),
+    ("njcy", false, "\u{45a}"),
+    ("nlArr", false, "\u{21cd}"),
+    ("nlE", false, "\u{2266}\u{338}"),
+    ("nlarr", false, "\u{219a}"),
+    ("nldr", false, "\u{2025}"),
+    ("nle", false, "\u{2270}"),
+    ("nleftarrow", false, "\u{219a}"),
+    ("nleftrightarrow", false, "\u{21ae}"),
+    ("nleq", false, "\u{2270}"),
+    ("nleqq", false, "\u{2266}\u{338}"),
+    ("nleqslant", false, "\u{2a7d}\u{338}"),
+    ("nles", false, "\u{2a7d}\u{338}"),
+    ("nless", false, "\u{226e}"),
+    ("nlsim", false, "\u{2274}"),
+    ("nlt", false, "\u{226e}"),
+    ("nltri", false, "\u{22ea}"),
+    ("nltrie", false, "\u{22ec}"),
+    ("nmid", false, "\u{2224}"),
+    ("nopf", false, "\u{1d55f}"),
+    ("not", true, "\u{ac}"),
+    ("notin", false, "\u{2209}"),
+    ("notinE", false, "\u{22f9}\u{338}"),
+    ("notindot", false, "\u{22f5}\u{338}"),
+    ("notinva", false, "\u{2209}"),
+    ("notinvb", false, "\u{22f7}"),
+    ("notinvc", false, "\u{22f6}"),
+    ("notni", false, "\u{220c}"),
+    ("notniva", false, "\u{220c}"),
+    ("notnivb", false, "\u{22fe}"),
+    ("notnivc", false, "\u{22fd}"),
+    ("npar", false, "\u{2226}"),
+    ("nparallel", false, "\u{2226}"),
+    ("nparsl", false, "\u{2afd}\u{20e5}"),
+    ("npart", false, "\u{2202}\u{338}"),
+    ("npolint", false, "\u{2a14}"),
+    ("npr", false, "\u{2280}"),
+    ("nprcue", false, "\u{22e0}"),
+    ("npre", false, "\u{2aaf}\u{338}"),
+    ("nprec", false, "\u{2280}"),
+    ("npreceq", false, "\u{2aaf}\u{338}"),
+    ("nrArr", false, "\u{21cf}"),
+    ("nrarr", false, "\u{219b}"),
+    ("nrarrc", false, "\u{2933}\u{338}"),
+    ("nrarrw", false, "\u{219d}\u{338}"),
+    ("nrightarrow", false, "\u{219b}"),
+    ("nrtri", false, "\u{22eb}"),
+    ("nrtrie", false, "\u{22ed}"),
+    ("nsc", false, "\u{2281}"),
+    ("nsccue", false, "\u{22e1}"),
+    ("nsce", false, "\u{2ab0}\u{338}"),
+    ("nscr", false, "\u{1d4c3}"),
+    ("nshortmid", false, "\u{2224}"),
+    ("nshortparallel", false, "\u{2226}"),
+    ("nsim", false, "\u{2241}"),
+    ("nsime", false, "\u{2244}"),
+    ("nsimeq", false, "\u{2244}"),
+    ("nsmid", false, "\u{2224}"),
+    ("nspar", false, "\u{2226}"),
+    ("nsqsube", false, "\u{22e2}"),
+    ("nsqsupe", false, "\u{22e3}"),
+    ("nsub", false, "\u{2284}"),
+    ("nsubE", false, "\u{2ac5}\u{338}"),
+    ("nsube", false, "\u{2288}"),
+    ("nsubset", false, "\u{2282}\u{20d2}"),
+    ("nsubseteq", false, "\u{2288}"),
+    ("nsubseteqq", false, "\u{2ac5}\u{338}"),
+    ("nsucc", false, "\u{2281}"),
+    ("nsucceq", false, "\u{2ab0}\u{338}"),
+    ("nsup", false, "\u{2285}"),
+    ("nsupE", false, "\u{2ac6}\u{338}"),
+    ("nsupe", false, "\u{2289}"),
+    ("nsupset", false, "\u{2283}\u{20d2}"),
+    ("nsupseteq", false, "\u{2289}"),
+    ("nsupseteqq", false, "\u{2ac6}\u{338}"),
+    ("ntgl", false, "\u{2279}"),
+    ("ntilde", true, "\u{f1}"),
+    ("ntlg", false, "\u{2278}"),
+    ("ntriangleleft", false, "\u{22ea}"),
+    ("ntrianglelefteq", false, "\u{22ec}"),
+    ("ntriangleright", false, "\u{22eb}"),
+    ("ntrianglerighteq", false, "\u{22ed}"),
+    ("nu", false, "\u{3bd}"),
+    ("num", false, "#"),
+    ("numero", false, "\u{2116}"),
+    ("numsp", false, "\u{2007}"),
+    ("nvDash", false, "\u{22ad}"),
+    ("nvHarr", false, "\u{2904}"),
+    ("nvap", false, "\u{224d}\u{20d2}"),
+    ("nvdash", false, "\u{22ac}"),
+    ("nvge", false, "\u{2265}\u{20d2}"),
+    ("nvgt", false, ">\u{20d2}"),
+    ("nvinfin", false, "\u{29de}"),
+    ("nvlArr", false, "\u{2902}"),
+    ("nvle", false, "\u{2264}\u{20d2}"),
+    ("nvlt", false, "<\u{20d2}"),
+    ("nvltrie", false, "\u{22b4}\u{20d2}"),
+    ("nvrArr", false, "\u{2903}"),
+    ("nvrtrie", false, "\u{22b5}\u{20d2}"),
+    ("nvsim", false, "\u{223c}\u{20d2}"),
+    ("nwArr", false, "\u{21d6}"),
+    ("nwarhk", false, "\u{2923}"),
+    ("nwarr", false, "\u{2196}"),
+    ("nwarrow", false, "\u{2196}"),
+    ("nwnear", false, "\u{2927}"),
+    ("oS", false, "\u{24c8}"),
+    ("oacute", true, "\u{f3}"),
+    ("oast", false, "\u{229b}"),
+    ("ocir", false, "\u{229a}"),
+    ("ocirc", true, "\u{f4}"),
+    ("ocy", false, "\u{43e}"),
+    ("odash", false, "\u{229d}"),
+    ("odblac", false, "\u{151}"),
+    ("odiv", false, "\u{2a38}"),
+    ("odot", false, "\u{2299}"),
+    ("odsold", false, "\u{29bc}"),
+    ("oelig", false, "\u{153}"),
+    ("ofcir", false, "\u{29bf}"),
+    ("ofr", false, "\u{1d52c}"),
+    ("ogon", false, "\u{2db}"),
+    ("ograve", true, "\u{f2}"),
+    ("ogt", false, "\u{29c1}"),
+    ("ohbar", false, "\u{29b5}"),
+    ("ohm", false, "\u{3a9}"),
+    ("oint", false, "\u{222e}"),
+    ("olarr", false, "\u{21ba}"),
+    ("olcir", false, "\u{29be}"),
+    ("olcross", false, "\u{29bb}"),
+    ("oline", false, "\u{203e}"),
+    ("olt", false, "\u{29c0}"),
+    ("omacr", false, "\u{14d}"),
+    ("omega", false, "\u{3c9}"),
+    ("omicron", false, "\u{3bf}"),
+    ("omid", false, "\u{29b6}"),
+    ("ominus", false, "\u{2296}"),
+    ("oopf", false, "\u{1d560}"),
+    ("opar", false, "\u{29b7}"),
+    ("operp", false, "\u{29b9}"),
+    ("oplus", false, "\u{2295}"),
+    ("or", false, "\u{2228}"),
+    ("orarr", false, "\u{21bb}"),
+    ("ord", false, "\u{2a5d}"),
+    ("order", false, "\u{2134}"),
+    ("orderof", false, "\u{2134}"),
+    ("ordf", true, "\u{aa}"),
+    ("ordm", true, "\u{ba}"),
+    ("origof", false, "\u{22b6}"),
+    ("oror", false, "\u{2a56}"),
+    ("orslope", false, "\u{2a57}"),
+    ("orv", false, "\u{2a5b}"),
+    ("oscr", false, "\u{2134}"),
+    ("oslash", true, "\u{f8}"),
+    ("osol", false, "\u{2298}"),
+    ("otilde", true, "\u{f5}"),
+    ("otimes", false, "\u{2297}"),
+    ("otimesas", false, "\u{2a36}"),
+    ("ouml", true, "\u{f6}"),
+    ("ovbar", false, "\u{233d}"),
+    ("par", false, "\u{2225}"),
+    ("para", true, "\u{b6}"),
+    ("parallel", false, "\u{2225}"),
+    ("parsim", false, "\u{2af3}"),
+    ("parsl", false, "\u{2afd}"),
+    ("part", false, "\u{2202}"),
+    ("pcy", false, "\u{43f}"),
+    ("percnt", false, "%"),
+    ("period", false, "."),
+    ("permil", false, "\u{2030}"),
+    ("perp", false, "\u{22a5}"),
+    ("pertenk", false, "\u{2031}"),
+    ("pfr", false, "\u{1d52d}"),
+    ("phi", false, "\u{3c6}"),
+    ("phiv", false, "\u{3d5}"),
+    ("phmmat", false, "\u{2133}"),
+    ("phone", false, "\u{260e}"),
+    ("pi", false, "\u{3c0}"),
+    ("pitchfork", false, "\u{22d4}"),
+    ("piv", false, "\u{3d6}"),
+    ("planck", false, "\u{210f}"),
+    ("planckh", false, "\u{210e}"),
+    ("plankv", false, "\u{210f}"),
+    ("plus", false, "+"),
+    ("plusacir", false, "\u{2a23}"),
+    ("plusb", false, "\u{229e}"),
+    ("pluscir", false, "\u{2a22}"),
+    ("plusdo", false, "\u{2214}"),
+    ("plusdu", false, "\u{2a25}"),
+    ("pluse", false, "\u{2a72}"),
+    ("plusmn", true, "\u{b1}"),
+    ("plussim", false, "\u{2a26}"),
+    ("plustwo", false, "\u{2a27}"),
+    ("pm", false, "\u{b1}"),
+    ("pointint", false, "\u{2a15}"),
+    ("popf", false, "\u{1d561}"),
+    ("pound", true, "\u{a3}"),
+    ("pr", false, "\u{227a}"),
+    ("prE", false, "\u{2ab3}"),
+    ("prap", false, "\u{2ab7}"),
+    ("prcue", false, "\u{227c}"),
+    ("pre", false, "\u{2aaf}"),
+    ("prec", false, "\u{227a}"),
+    ("precapprox", false, "\u{2ab7}"),
+    ("preccurlyeq", false, "\u{227c}"),
+    ("preceq", false, "\u{2aaf}"),
+    ("precnapprox", false, "\u{2ab9}"),
+    ("precneqq", false, "\u{2ab5}"),
+    ("precnsim", false, "\u{22e8}"),
+    ("precsim", false, "\u{227e}"),
+    ("prime", false, "\u{2032}"),
+    ("primes", false, "\u{2119}"),
+    ("prnE", false, "\u{2ab5}"),
+    ("prnap", false, "\u{2ab9}"),
+    ("prnsim", false, "\u{22e8}"),
+    ("prod", false, "\u{220f}"),
+    ("profalar", false, "\u{232e}"),
+    ("profline", false, "\u{2312}"),
+    ("profsurf", false, "\u{2313}"),
+    ("prop", false, "\u{221d}"),
+    ("propto", false, "\u{221d}"),
+    ("prsim", false, "\u{227e}"),
+    ("prurel", false, "\u{22b0}"),
+    ("pscr", false, "\u{1d4c5}"),
+    ("psi", false, "\u{3c8}"),
+    ("puncsp", false, "\u{2008}"),
+    ("qfr", false, "\u{1d52e}"),
+    ("qint", false, "\u{2a0c}"),
+    ("qopf", false, "\u{1d562}"),
+    ("qprime", false, "\u{2057}"),
+    ("qscr", false, "\u{1d4c6}"),
+    ("quaternions", false, "\u{210d}"),
+    ("quatint", false, "\u{2a16}"),
+    ("quest", false, "?"),
+    ("questeq", false, "\u{225f}"),
+    ("quot", true, "\""),
+    ("rAarr", false, "\u{21db}"),
+    ("rArr", false, "\u{21d2}"),
+    ("rAtail", false, "\u{291c}"),
+    ("rBarr", false, "\u{290f}"),
+    ("rHar", false, "\u{2964}"),
+    ("race", false, "\u{223d}\u{331}"),
+    ("racute", false, "\u{155}"),
+    ("radic", false, "\u{221a}"),
+    ("raemptyv", false, "\u{29b3}"),
+    ("rang", false, "\u{27e9}"),
+    ("rangd", false, "\u{2992}"),
+    ("range", false, "\u{29a5}"),
+    ("rangle", false, "\u{27e9}"),
+    ("raquo", true, "\u{bb}"),
+    ("rarr", false, "\u{2192}"),
+    ("rarrap", false, "\u{2975}"),
+    ("rarrb", false, "\u{21e5}"),
+    ("rarrbfs", false, "\u{2920}"),
+    ("rarrc", false, "\u{2933}"),
+    ("rarrfs", false, "\u{291e}"),
+    ("rarrhk", false, "\u{21aa}"),
+    ("rarrlp", false, "\u{21ac}"),
+    ("rarrpl", false, "\u{2945}"),
+    ("rarrsim", false, "\u{2974}"),
+    ("rarrtl", false, "\u{21a3}"),
+    ("rarrw", false, "\u{219d}"),
+    ("ratail", false, "\u{291a}"),
+    ("ratio", false, "\u{2236}"),
+    ("rationals", false, "\u{211a}"),
+    ("rbarr", false, "\u{290d}"),
+    ("rbbrk", false, "\u{2773}"),
+    ("rbrace", false, "}"),
+    ("rbrack", false, "]"),
+    ("rbrke", false, "\u{298c}"),
+    ("rbrksld", false, "\u{298e}"),
+    ("rbrkslu", false, "\u{2990}"),
+    ("rcaron", false, "\u{159}"),
+    ("rcedil", false, "\u{157}"),
+    ("rceil", false, "\u{2309}"),
+    ("rcub", false, "}"),
+    ("rcy", false, "\u{440}"),
+    ("rdca", false, "\u{2937}"),
+    ("rdldhar", false, "\u{2969}"),
+    ("rdquo", false, "\u{201d}"),
+    ("rdquor", false, "\u{201d}"),
+    ("rdsh", false, "\u{21b3}"),
+    ("real", false, "\u{211c}"),
+    ("realine", false, "\u{211b}"),
+    ("realpart", false, "\u{211c}"),
+    ("reals", false, "\u{211d}"),
+    ("rect", false, "\u{25ad}"),
+    ("reg", true, "\u{ae}"),
+    ("rfisht", false, "\u{297d}"),
+    ("rfloor", false, "\u{230b}"),
+    ("rfr", false, "\u{1d52f}"),
+    ("rhard", false, "\u{21c1}"),
+    ("rharu", false, "\u{21c0}"),
+    ("rharul", false, "\u{296c}"),
+    ("rho", false, "\u{3c1}"),
+    ("rhov", false, "\u{3f1}"),
+    ("rightarrow", false, "\u{2192}"),
+    ("rightarrowtail", false, "\u{21a3}"),
+    ("rightharpoondown", false, "\u{21c1}"),
+    ("rightharpoonup", false, "\u{21c0}"),
+    ("rightleftarrows", false, "\u{21c4}"),
+    ("rightleftharpoons", false, "\u{21cc}"),
+    ("rightrightarrows", false, "\u{21c9}"),
+    ("rightsquigarrow", false, "\u{219d}"),
+    ("rightthreetimes", false, "\u{22cc}"),
+    ("ring", false, "\u{2da}"),
+    ("risingdotseq", false, "\u{2253}"),
+    ("rlarr", false, "\u{21c4}"),
+    ("rlhar", false, "\u{21cc}"),
+    ("rlm", false, "\u{200f}"),
+    ("rmoust", false, "\u{23b1}"),
+    ("rmoustache", false, "\u{23b1}"),
+    ("rnmid", false, "\u{2aee}"),
+    ("roang", false, "\u{27ed}"),
+    ("roarr", false, "\u{21fe}"),
+    ("robrk", false, "\u{27e7}"),
+    ("ropar", false, "\u{2986}"),
+    ("ropf", false, "\u{1d563}"),
+    ("roplus", false, "\u{2a2e}"),
+    ("rotimes", false, "\u{2a35}"),
+    ("rpar", false, ")"),
+    ("rpargt", false, "\u{2994}"),
+    ("rppolint", false, "\u{2a12}"),
+    ("rrarr", false, "\u{21c9}"),
+    ("rsaquo", false, "\u{203a}"),
+    ("rscr", false, "\u{1d4c7}"),
+    ("rsh", false, "\u{21b1}"),
+    ("rsqb", false, "]"),
+    ("rsquo", false, "\u{2019}"),
+    ("rsquor", false, "\u{2019}"),
+    ("rthree", false, "\u{22cc}"),
+    ("rtimes", false, "\u{22ca}"),
+    ("rtri", false, "\u{25b9}"),
+    ("rtrie", false, "\u{22b5}"),
+    ("rtrif", false, "\u{25b8}"),
+    ("rtriltri", false, "\u{29ce}"),
+    ("ruluhar", false, "\u{2968}"),
+    ("rx", false, "\u{211e}"),
+    ("sacute", false, "\u{15b}"),
+    ("sbquo", false, "\u{201a}"),
+    ("sc", false, "\u{227b}"),
+    ("scE", false, "\u{2ab4}"),
+    ("scap", false, "\u{2ab8}"),
+    ("scaron", false, "\u{161}"),
+    ("sccue", false, "\u{227d}"),
+    ("sce", false, "\u{2ab0}"),
+    ("scedil", false, "\u{15f}"),
+    ("scirc", false, "\u{15d}"),
+    ("scnE", false, "\u{2ab6}"),
+    ("scnap", false, "\u{2aba}"),
+    ("scnsim", false, "\u{22e9}"),
+    ("scpolint", false, "\u{2a13}"),
+    ("scsim", false, "\u{227f}"),
+    ("scy", false, "\u{441}"),
+    ("sdot", false, "\u{22c5}"),
+    ("sdotb", false, "\u{22a1}"),
+    ("sdote", false, "\u{2a66}"),
+    ("seArr", false, "\u{21d8}"),
+    ("searhk", false, "\u{2925}"),
+    ("searr", false, "\u{2198}"),
+    ("searrow", false, "\u{2198}"),
+    ("sect", true, "\u{a7}"),
+    ("semi", false, ";"),
+    ("seswar", false, "\u{2929}"),
+    ("setminus", false, "\u{2216}"),
+    ("setmn", false, "\u{2216}"),
+    ("sext", false, "\u{2736}"),
+    ("sfr", false, "\u{1d530}"),
+    ("sfrown", false, "\u{2322}"),
+    ("sharp", false, "\u{266f}"),
+    ("shchcy", false, "\u{449}"),
+    ("shcy", false, "\u{448}"),
+    ("shortmid", false, "\u{2223}"),
+    ("shortparallel", false, "\u{2225}"),
+    ("shy", true, "\u{ad}"),
+    ("sigma", false, "\u{3c3}"),
+    ("sigmaf", false, "\u{3c2}"),
+    ("sigmav", false, "\u{3c2}"),
+    ("sim", false, "\u{223c}"),
+    ("simdot", false, "\u{2a6a}"),
+    ("sime", false, "\u{2243}"),
+    ("simeq", false, "\u{2243}"),
+    ("simg", false, "\u{2a9e}"),
+    ("simgE", false, "\u{2aa0}"),
+    ("siml", false, "\u{2a9d}"),
+    ("simlE", false, "\u{2a9f}"),
+    ("simne", false, "\u{2246}"),
+    ("simplus", false, "\u{2a24}"),
+    ("simrarr", false, "\u{2972}"),
+    ("slarr", false, "\u{2190}"),
+    ("smallsetminus", false, "\u{2216}"),
+    ("smashp", false, "\u{2a33}"),
+    ("smeparsl", false, "\u{29e4}"),
+    ("smid", false, "\u{2223}"),
+    ("smile", false, "\u{2323}"),
+    ("smt", false, "\u{2aaa}"),
+    ("smte", false, "\u{2aac}"),
+    ("smtes", false, "\u{2aac}\u{fe00}"),
+    ("softcy", false, "\u{44c}"),
+    ("sol", false, "/"),
+    ("solb", false, "\u{29c4}"),
+    ("solbar", false, "\u{233f}"),
+    ("sopf", false, "\u{1d564}"),
+    ("spades", false, "\u{2660}"),
+    ("spadesuit", false, "\u{2660}"),
+    ("spar", false, "\u{2225}"),
+    ("sqcap", false, "\u{2293}"),
+    ("sqcaps", false, "\u{2293}\u{fe00}"),
+    ("sqcup", false, "\u{2294}"),
+    ("sqcups", false, "\u{2294}\u{fe00}"),
+    ("sqsub", false, "\u{228f}"),
+    ("sqsube", false, "\u{2291}"),
+    ("sqsubset", false, "\u{228f}"),
+    ("sqsubseteq", false, "\u{2291}"),
+    ("sqsup", false, "\u{2290}"),
+    ("sqsupe", false, "\u{2292}"),
+    ("sqsupset", false, "\u{2290}"),
+    ("sqsupseteq", false, "\u{2292}"),
+    ("squ", false, "\u{25a1}"),
+    ("square", false, "\u{25a1}"),
+    ("squarf", false, "\u{25aa}"),
+    ("squf", false, "\u{25aa}"),
+    ("srarr", false, "\u{2192}"),
+    ("sscr", false, "\u{1d4c8}"),
+    ("ssetmn", false, "\u{2216}"),
+    ("ssmile", false, "\u{2323}"),
+    ("sstarf", false, "\u{22c6}"),
+    ("star", false, "\u{2606}"),
+    ("starf", false, "\u{2605}"),
+    ("straightepsilon", false, "\u{3f5}"),
+    ("straightphi", false, "\u{3d5}"),
+    ("strns", false, "\u{af}"),
+    ("sub", false, "\u{2282}"),
+    ("subE", false, "\u{2ac5}"),
+    ("subdot", false, "\u{2abd}"),
+    ("sube", false, "\u{2286}"),
+    ("subedot", false, "\u{2ac3}"),
+    ("submult", false, "\u{2ac1}"),
+    ("subnE", false, "\u{2acb}"),
+    ("subne", false, "\u{228a}"),
+    ("subplus", false, "\u{2abf}"),
+    ("subrarr", false, "\u{2979}"),
+    ("subset", false, "\u{2282}"),
+    ("subseteq", false, "\u{2286}"),
+    ("subseteqq", false, "\u{2ac5}"),
+    ("subsetneq", false, "\u{228a}"),
+    ("subsetneqq", false, "\u{2acb}"),
+    ("subsim", false, "\u{2ac7}"),
+    ("subsub", false, "\u{2ad5}"),
+    ("subsup", false, "\u{2ad3}"),
+    ("succ", false, "\u{227b}"),
+    ("succapprox", false, "\u{2ab8}"),
+    ("succcurlyeq", false, "\u{227d}"),
+    ("succeq", false, "\u{2ab0}"),
+    ("succnapprox", false, "\u{2aba}"),
+    ("succneqq", false, "\u{2ab6}"),
+    ("succnsim", false, "\u{22e9}"),
+    ("succsim", false, "\u{227f}"),
+    ("sum", false, "\u{2211}"),
+    ("sung", false, "\u{266a}"),
+    ("sup", false, "\u{2283}"),
+    ("sup1", true, "\u{b9}"),
+    ("sup2", true, "\u{b2}"),
+    ("sup3", true, "\u{b3}"),
+    ("supE", false, "\u{2ac6}"),
+    ("supdot", false, "\u{2abe}"),
+    ("supdsub", false, "\u{2ad8}"),
+    ("supe", false, "\u{2287}"),
+    ("supedot", false, "\u{2ac4}"),
+    ("suphsol", false, "\u{27c9}"),
+    ("suphsub", false, "\u{2ad7}"),
+    ("suplarr", false, "\u{297b}"),
+    ("supmult", false, "\u{2ac2}"),
+    ("supnE", false, "\u{2acc}"),
+    ("supne", false, "\u{228b}"),
+    ("supplus", false, "\u{2ac0}"),
+    ("supset", false, "\u{2283}"),
+    ("supseteq", false, "\u{2287}"),
+    ("supseteqq", false, "\u{2ac6}"),
+    ("supsetneq", false, "\u{228b}"),
+    ("supsetneqq", false, "\u{2acc}"),
+    ("supsim", false, "\u{2ac8}"),
+    ("supsub", false, "\u{2ad4}"),
+    ("supsup", false, "\u{2ad6}"),
+    ("swArr", false, "\u{21d9}"),
+    ("swarhk", false, "\u{2926}"),
+    ("swarr", false, "\u{2199}"),
+    ("swarrow", false, "\u{2199}"),
+    ("swnwar", false, "\u{292a}"),
+    ("szlig", true, "\u{df}"),
+    ("target", false, "\u{2316}"),
+    ("tau", false, "\u{3c4}"),
+    ("tbrk", false, "\u{23b4}"),
+    ("tcaron", false, "\u{165}"),
+    ("tcedil", false, "\u{163}"),
+    ("tcy", false, "\u{442}"),
+    ("tdot", false, "\u{20db}"),
+    ("telrec", false, "\u{2315}"),
+    ("tfr", false, "\u{1d531}"),
+    ("there4", false, "\u{2234}"),
+    ("therefore", false, "\u{2234}"),
+    ("theta", false, "\u{3b8}"),
+    ("thetasym", false, "\u{3d1}"),
+    ("thetav", false, "\u{3d1}"),
+    ("thickapprox", false, "\u{2248}"),
+    ("thicksim", false, "\u{223c}"),
+    ("thinsp", false, "\u{2009}"),
+    ("thkap", false, "\u{2248}"),
+    ("thksim", false, "\u{223c}"),
+    ("thorn", true, "\u{fe}"),
+    ("tilde", false, "\u{2dc}"),
+    ("times", true, "\u{d7}"),
+    ("timesb", false, "\u{22a0}"),
+    ("timesbar", false, "\u{2a31}"),
+    ("timesd", false, "\u{2a30}"),
+    ("tint", false, "\u{222d}"),
+    ("toea", false, "\u{2928}"),
+    ("top", false, "\u{22a4}"),
+    ("topbot", false, "\u{2336}"),
+    ("topcir", false, "\u{2af1}"),
+    ("topf", false, "\u{1d565}"),
+    ("topfork", false, "\u{2ada}"),
+    ("tosa", false, "\u{2929}"),
+    ("tprime", false, "\u{2034}"),
+    ("trade", false, "\u{2122}"),
+    ("triangle", false, "\u{25b5}"),
+    ("triangledown", false, "\u{25bf}"),
+    ("triangleleft", false, "\u{25c3}"),
+    ("trianglelefteq", false, "\u{22b4}"),
+    ("triangleq", false, "\u{225c}"),
+    ("triangleright", false, "\u{25b9}"),
+    ("trianglerighteq", false, "\u{22b5}"),
+    ("tridot", false, "\u{25ec}"),
+    ("trie", false, "\u{225c}"),
+    ("triminus", false, "\u{2a3a}"),
+    ("triplus", false, "\u{2a39}"),
+    ("trisb", false, "\u{29cd}"),
+    ("tritime", false, "\u{2a3b}"),
+    ("trpezium", false, "\u{23e2}"),
+    ("tscr", false, "\u{1d4c9}"),
+    ("tscy", false, "\u{446}"),
+    ("tshcy", false, "\u{45b}"),
+    ("tstrok", false, "\u{167}"),
+    ("twixt", false, "\u{226c}"),
+    ("twoheadleftarrow", false, "\u{219e}"),
+    ("twoheadrightarrow", false, "\u{21a0}"),
+    ("uArr", false, "\u{21d1}"),
+    ("uHar", false, "\u{2963}"),
+    ("uacute", true, "\u{fa}"),
+    ("uarr", false, "\u{2191}"),
+    ("ubrcy", false, "\u{45e}"),
+    ("ubreve", false, "\u{16d}"),
+    ("ucirc", true, "\u{fb}"),
+    ("ucy", false, "\u{443}"),
+    ("udarr", false, "\u{21c5}"),
+    ("udblac", false, "\u{171}"),
+    ("udhar", false, "\u{296e}"),
+    ("ufisht", false, "\u{297e}"),
+    ("ufr", false, "\u{1d532}"),
+    ("ugrave", true, "\u{f9}"),
+    ("uharl", false, "\u{21bf}"),
+    ("uharr", false, "\u{21be}"),
+    ("uhblk", false, "\u{2580}"),
+    ("ulcorn", false, "\u{231c}"),
+    ("ulcorner", false, "\u{231c}"),
+    ("ulcrop", false, "\u{230f}"),
+    ("ultri", false, "\u{25f8}"),
+    ("umacr", false, "\u{16b}"),
+    ("uml", true, "\u{a8}"),
+    ("uogon", false, "\u{173}"),
+    ("uopf", false, "\u{1d566}"),
+    ("uparrow", false, "\u{2191}"),
+    ("updownarrow", false, "\u{2195}"),
+    ("upharpoonleft", false, "\u{21bf}"),
+    ("upharpoonright", false, "\u{21be}"),
+    ("uplus", false, "\u{228e}"),
+    ("upsi", false, "\u{3c5}"),
+    ("upsih", false, "\u{3d2}"),
+    ("upsilon", false, "\u{3c5}"),
+    ("upuparrows", false, "\u{21c8}"),
+    ("urcorn", false, "\u{231d}"),
+    ("urcorner", false, "\u{231d}"),
+    ("urcrop", false, "\u{230e}"),
+    ("uring", false, "\u{16f}"),
+    ("urtri", false, "\u{25f9}"),
+    ("uscr", false, "\u{1d4ca}"),
+    ("utdot", false, "\u{22f0}"),
+    ("utilde", false, "\u{169}"),
+    ("utri", false, "\u{25b5}"),
+    ("utrif", false, "\u{25b4}"),
+    ("uuarr", false, "\u{21c8}"),
+    ("uuml", true, "\u{fc}"),
+    ("uwangle", false, "\u{29a7}"),
+    ("vArr", false, "\u{21d5}"),
+    ("vBar", false, "\u{2ae8}"),
+    ("vBarv", false, "\u{2ae9}"),
+    ("vDash", false, "\u{22a8}"),
+    ("vangrt", false, "\u{299c}"),
+    ("varepsilon", false, "\u{3f5}"),
+    ("varkappa", false, "\u{3f0}"),
+    ("varnothing", false, "\u{2205}"),
+    ("varphi", false, "\u{3d5}"),
+    ("varpi", false, "\u{3d6}"),
+    ("varpropto", false, "\u{221d}"),
+    ("varr", false, "\u{2195}"),
+    ("varrho", false, "\u{3f1}"),
+    ("varsigma", false, "\u{3c2}"),
+    ("varsubsetneq", false, "\u{228a}\u{fe00}"),
+    ("varsubsetneqq", false, "\u{2acb}\u{fe00}"),
+    ("varsupsetneq", false, "\u{228b}\u{fe00}"),
+    ("varsupsetneqq", false, "\u{2acc}\u{fe00}"),
+    ("vartheta", false, "\u{3d1}"),
+    ("vartriangleleft", false, "\u{22b2}"),
+    ("vartriangleright", false, "\u{22b3}"),
+    ("vcy", false, "\u{432}"),
+    ("vdash", false, "\u{22a2}"),
+    ("vee", false, "\u{2228}"),
+    ("veebar", false, "\u{22bb}"),
+    ("veeeq", false, "\u{225a}"),
+    ("vellip", false, "\u{22ee}"),
+    ("verbar", false, "|"),
+    ("vert", false, "|"),
+    ("vfr", false, "\u{1d533}"),
+    ("vltri", false, "\u{22b2}"),
+    ("vnsub", false, "\u{2282}\u{20d2}"),
+    ("vnsup", false, "\u{2283}\u{20d2}"),
+    ("vopf", false, "\u{1d567}"),
+    ("vprop", false, "\u{221d}"),
+    ("vrtri", false, "\u{22b3}"),
+    ("vscr", false, "\u{1d4cb}"),
+    ("vsubnE", false, "\u{2acb}\u{fe00}"),
+    ("vsubne", false, "\u{228a}\u{fe00}"),
+    ("vsupnE", false, "\u{2acc}\u{fe00}"),
+    ("vsupne", false, "\u{228b}\u{fe00}"),
+    ("vzigzag", false, "\u{299a}"),
+    ("wcirc", false, "\u{175}"),
+    ("wedbar", false, "\u{2a5f}"),
+    ("wedge", false, "\u{2227}"),
+    ("wedgeq", false, "\u{2259}"),
+    ("weierp", false, "\u{2118}"),
+    ("wfr", false, "\u{1d534}"),
+    ("wopf", false, "\u{1d568}"),
+    ("wp", false, "\u{2118}"),
+    ("wr", false, "\u{2240}"),
+    ("wreath", false, "\u{2240}"),
+    ("wscr", false, "\u{1d4cc}"),
+    ("xcap", false, "\u{22c2}"),
+    ("xcirc", false, "\u{25ef}"),
+    ("xcup", false, "\u{22c3}"),
+    ("xdtri", false, "\u{25bd}"),
+    ("xfr", false, "\u{1d535}"),
+    ("xhArr", false, "\u{27fa}"),
+    ("xharr", false, "\u{27f7}"),
+    ("xi", false, "\u{3be}"),
+    ("xlArr", false, "\u{27f8}"),
+    ("xlarr", false, "\u{27f5}"),
+    ("xmap", false, "\u{27fc}"),
+    ("xnis", false, "\u{22fb}"),
+    ("xodot", false, "\u{2a00}"),
+    ("xopf", false, "\u{1d569}"),
+    ("xoplus", false, "\u{2a01}"),
+    ("xotime", false, "\u{2a02}"),
+    ("xrArr", false, "\u{27f9}"),
+    ("xrarr", false, "\u{27f6}"),
+    ("xscr", false, "\u{1d4cd}"),
+    ("xsqcup", false, "\u{2a06}"),
+    ("xuplus", false, "\u{2a04}"),
+    ("xutri", false, "\u{25b3}"),
+    ("xvee", false, "\u{22c1}"),
+    ("xwedge", false, "\u{22c0}"),
+    ("yacute", true, "\u{fd}"),
+    ("yacy", false, "\u{44f}"),
+    ("ycirc", false, "\u{177}"),
+    ("ycy", false, "\u{44b}"),
+    ("yen", true, "\u{a5}"),
+    ("yfr", false, "\u{1d536}"),
+    ("yicy", false, "\u{457}"),
+    ("yopf", false, "\u{1d56a}"),
+    ("yscr", false, "\u{1d4ce}"),
+    ("yucy", false, "\u{44e}"),
+    ("yuml", true, "\u{ff}"),
+    ("zacute", false, "\u{17a}"),
+    ("zcaron", false, "\u{17e}"),
+    ("zcy", false, "\u{437}"),
+    ("zdot", false, "\u{17c}"),
+    ("zeetrf", false, "\u{2128}"),
+    ("zeta", false, "\u{3b6}"),
+    ("zfr", false, "\u{1d537}"),
+    ("zhcy", false, "\u{436}"),
+    ("zigrarr", false, "\u{21dd}"),
+    ("zopf", false, "\u{1d56b}"),
+    ("zscr", false, "\u{1d4cf}"),
+    ("zwj", false, "\u{200d}"),
+    ("zwnj", false, "\u{200c}"),
+];
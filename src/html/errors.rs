@@ -1,20 +1,469 @@
+use crate::html::arena::NodeId;
+use crate::html::lsp::{LineMap, LspRange};
+#[cfg(feature = "std")]
+use crate::html::parser::NodeKind;
+use crate::html::position::Position;
 use crate::html::tokenizer::{Token, TokenKind};
+#[cfg(feature = "std")]
+use crate::net::errors::FetchError;
+use alloc::boxed::Box;
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::fmt;
+#[cfg(feature = "std")]
+use std::path::{Path, PathBuf};
+#[cfg(feature = "std")]
 use thiserror::Error;
 
-#[derive(Debug, Error)]
+// no_stdでも使うので、thiserrorではなくDisplayを書く
+#[derive(Debug)]
 pub enum ParseError {
-    #[error("open & close tag name miss matched (open: {open:?}, close: {close:?})")]
-    TagMissMatch { open: String, close: String },
-    #[error("unexpected token: (expected: {expected:?}, found: {found:?})")]
+    // 範囲は "<" から ">" まで
+    TagMissMatch {
+        open: String,
+        close: String,
+        open_range: (Position, Position),
+        close_range: (Position, Position),
+    },
+    // startはfoundの始まり。found.posが終わり
     UnexpectedToken {
         expected: TokenKind,
-        found: Token,
+        found: Box<Token>,
+        start: Position,
+    },
+    UnexpectedTokenOneOf {
+        expected: Vec<TokenKind>,
+        found: Box<Token>,
+        start: Position,
     },
-    #[error("unexpected text: (expected: {expected:?}, found: {found:?})")]
     UnexpectedText {
         expected: String,
         found: Option<Box<Token>>,
+        start: Position,
+    },
+    Internal {
+        detail: &'static str,
+        pos: Position,
+    },
+    // ParserBuilder::max_depthより深い要素
+    // startはその要素のタグの始まり
+    TooDeep {
+        max_depth: usize,
+        start: Position,
+        pos: Position,
+    },
+    // lenientで、直したエラーがParserOptions::max_errorsより多くなった
+    TooManyErrors {
+        count: usize,
+    },
+    // ParserBuilder::parseで、buildできなかった
+    InvalidOptions(OptionsError),
+    // <iframe srcdoc> のように属性の値を文書として読んだときのエラー
+    // errorの位置は属性の値の中の位置
+    InAttribute {
+        element: &'static str,
+        attribute: &'static str,
+        error: Box<ParseError>,
     },
-    #[error("unknown parse error")]
     Unknown,
 }
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        return match self {
+            ParseError::TagMissMatch { open, close, .. } => write!(
+                f,
+                "open & close tag name miss matched (open: {:?}, close: {:?})",
+                open, close
+            ),
+            ParseError::UnexpectedToken {
+                expected, found, ..
+            } => write!(
+                f,
+                "unexpected token: (expected: {:?}, found: {:?})",
+                expected, found
+            ),
+            ParseError::UnexpectedTokenOneOf {
+                expected, found, ..
+            } => write!(
+                f,
+                "unexpected token: (expected one of: {:?}, found: {:?})",
+                expected, found
+            ),
+            ParseError::UnexpectedText {
+                expected, found, ..
+            } => write!(
+                f,
+                "unexpected text: (expected: {:?}, found: {:?})",
+                expected, found
+            ),
+            ParseError::Internal { detail, pos } => {
+                write!(f, "internal parser error: {} (at {:?})", detail, pos)
+            }
+            ParseError::TooDeep { max_depth, .. } => {
+                write!(f, "elements nested deeper than max_depth {}", max_depth)
+            }
+            ParseError::TooManyErrors { count } => {
+                write!(f, "stopped after {} recovered errors", count)
+            }
+            ParseError::InvalidOptions(err) => write!(f, "{}", err),
+            ParseError::InAttribute {
+                element,
+                attribute,
+                error,
+            } => write!(
+                f,
+                "{} (in the {} attribute of <{}>)",
+                error, attribute, element
+            ),
+            ParseError::Unknown => write!(f, "unknown parse error"),
+        };
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for ParseError {}
+
+impl From<OptionsError> for ParseError {
+    fn from(err: OptionsError) -> ParseError {
+        return ParseError::InvalidOptions(err);
+    }
+}
+
+// ParserBuilder::build
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum OptionsError {
+    // 一緒には使えない組み合わせ
+    Incompatible {
+        option: &'static str,
+        conflicts_with: &'static str,
+        reason: &'static str,
+    },
+    ZeroMaxDepth,
+}
+
+impl fmt::Display for OptionsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        return match self {
+            OptionsError::Incompatible {
+                option,
+                conflicts_with,
+                reason,
+            } => write!(
+                f,
+                "parser option {} cannot be combined with {}: {}",
+                option, conflicts_with, reason
+            ),
+            OptionsError::ZeroMaxDepth => {
+                write!(f, "max_depth must be at least 1 (0 rejects every element)")
+            }
+        };
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for OptionsError {}
+
+// Arena::replace_with, wrap, unwrap
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArenaError {
+    // 親がない (最上位か、どこにもつながっていない)。最上位の並びはArenaが持っていない
+    Detached(NodeId),
+    // 入れるnodeが、もうどこかの子になっている
+    Attached(NodeId),
+    // 要素でないものをwrapperにしたり、unwrapしたりした
+    NotElement(NodeId),
+    // 自分や先祖を、自分の下に入れることになる
+    Cycle(NodeId),
+}
+
+impl fmt::Display for ArenaError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        return match self {
+            ArenaError::Detached(id) => write!(f, "node {} has no parent", id.index()),
+            ArenaError::Attached(id) => {
+                write!(f, "node {} is already attached to a parent", id.index())
+            }
+            ArenaError::NotElement(id) => write!(f, "node {} is not an element", id.index()),
+            ArenaError::Cycle(id) => {
+                write!(f, "node {} would become its own descendant", id.index())
+            }
+        };
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for ArenaError {}
+
+// ResourceLoaderが返す。io::ErrorなどはCloneできないので文字列にして持つ
+#[cfg(feature = "std")]
+#[derive(Debug, Clone, PartialEq, Error)]
+pub enum LoadError {
+    #[error("resource not found: {url}")]
+    NotFound { url: String },
+    #[error("failed to load {url}: {detail}")]
+    Failed { url: String, detail: String },
+}
+
+// html::fetch_and_parse
+#[cfg(feature = "std")]
+#[derive(Debug, Error)]
+pub enum FetchParseError {
+    #[error(transparent)]
+    Fetch(#[from] FetchError),
+    #[error("unexpected status {status} from {url}")]
+    Status { url: String, status: u16 },
+    #[error("too many redirects (at {url})")]
+    TooManyRedirects { url: String },
+    #[error("unsupported encoding: {label}")]
+    UnsupportedEncoding { label: String },
+    #[error(transparent)]
+    Parse(#[from] ParseError),
+}
+
+// html::parse_bytes_with_encoding
+#[cfg(feature = "std")]
+#[derive(Debug, Error)]
+pub enum BytesParseError {
+    #[error("unsupported encoding: {label}")]
+    UnsupportedEncoding { label: String },
+    #[error(transparent)]
+    Parse(#[from] ParseError),
+}
+
+#[cfg(feature = "std")]
+impl From<BytesParseError> for FetchParseError {
+    fn from(err: BytesParseError) -> FetchParseError {
+        return match err {
+            BytesParseError::UnsupportedEncoding { label } => {
+                FetchParseError::UnsupportedEncoding { label }
+            }
+            BytesParseError::Parse(err) => FetchParseError::Parse(err),
+        };
+    }
+}
+
+// Document::retokenize
+#[cfg(feature = "std")]
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum RetokenizeError {
+    #[error("only elements can be retokenized")]
+    NotElement,
+    #[error("node is not part of this document")]
+    NotInDocument,
+    // 要素の数が木と合わない。この文書を読んだsourceではない
+    #[error("source does not match the document")]
+    SourceMismatch,
+    // 開き直した要素や、閉じタグなしで閉じた要素
+    #[error("element has no span of its own in the source")]
+    NoSpan,
+}
+
+// Edit::apply。rangeがsourceの外か、文字の途中
+#[cfg(feature = "std")]
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+#[error("edit range {start}..{end} does not fit a source of {len} bytes")]
+pub struct EditRangeError {
+    pub start: usize,
+    pub end: usize,
+    pub len: usize,
+}
+
+// Document::reparse_edit
+#[cfg(feature = "std")]
+#[derive(Debug, Error)]
+pub enum ReparseError {
+    #[error(transparent)]
+    InvalidRange(#[from] EditRangeError),
+    #[error(transparent)]
+    Parse(#[from] ParseError),
+}
+
+// ElementRefのTryFrom<&Node>。textやコメントは包まない
+#[cfg(feature = "std")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Error)]
+#[error("{kind:?} node is not an element")]
+pub struct NotElementError {
+    pub kind: NodeKind,
+}
+
+// NodePathのfrom_str
+#[cfg(feature = "std")]
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+#[error("invalid node path: {path:?}")]
+pub struct NodePathParseError {
+    pub path: String,
+}
+
+// html::parse_file。どのfileのエラーかを持つ
+#[cfg(feature = "std")]
+#[derive(Debug, Error)]
+pub enum FileParseError {
+    #[error("failed to read {}: {source}", path.display())]
+    Io {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    #[error("unsupported encoding in {}: {label}", path.display())]
+    UnsupportedEncoding { path: PathBuf, label: String },
+    // error in pages/index.html:12:8: ...
+    #[error("error in {}: {source}", file_location(path, source))]
+    Parse { path: PathBuf, source: ParseError },
+}
+
+// path:行:列。列はtokenの終わり (ParseError::renderと同じ)
+#[cfg(feature = "std")]
+fn file_location(path: &Path, err: &ParseError) -> String {
+    return match err.position() {
+        Some(pos) => format!("{}:{}:{}", path.display(), pos.line_no, pos.at_line.max(1)),
+        None => path.display().to_string(),
+    };
+}
+
+impl ParseError {
+    pub fn position(&self) -> Option<&Position> {
+        return match self {
+            ParseError::UnexpectedToken { found, .. } => Some(&found.pos),
+            ParseError::UnexpectedTokenOneOf { found, .. } => Some(&found.pos),
+            ParseError::UnexpectedText { found, .. } => match found {
+                Some(tok) => Some(&tok.pos),
+                None => None,
+            },
+            ParseError::Internal { pos, .. } => Some(pos),
+            ParseError::TooDeep { pos, .. } => Some(pos),
+            ParseError::TagMissMatch { close_range, .. } => Some(&close_range.1),
+            ParseError::TooManyErrors { .. } => None,
+            ParseError::InvalidOptions(_) => None,
+            ParseError::InAttribute { error, .. } => error.position(),
+            ParseError::Unknown => None,
+        };
+    }
+
+    // エラーになったところの始まりと終わり。TagMissMatchは閉じタグ
+    pub fn range(&self) -> Option<(Position, Position)> {
+        return match self {
+            ParseError::UnexpectedToken { found, start, .. }
+            | ParseError::UnexpectedTokenOneOf { found, start, .. } => {
+                Some((start.clone(), found.pos.clone()))
+            }
+            ParseError::UnexpectedText {
+                found: Some(tok),
+                start,
+                ..
+            } => Some((start.clone(), tok.pos.clone())),
+            ParseError::TooDeep { start, pos, .. } => Some((start.clone(), pos.clone())),
+            ParseError::TagMissMatch { close_range, .. } => Some(close_range.clone()),
+            ParseError::InAttribute { error, .. } => error.range(),
+            _ => self.position().map(|pos| (pos.clone(), pos.clone())),
+        };
+    }
+
+    // 位置を持たないエラーは文書の先頭を指す
+    pub fn lsp_range(&self, line_map: &LineMap) -> LspRange {
+        let (start, end) = match self.range() {
+            Some(range) => range,
+            None => (Position::new(1, 0, 0), Position::new(1, 0, 0)),
+        };
+        return line_map.lsp_range(&start, &end);
+    }
+
+    // 元の行と一緒に表示する
+    // error: ...
+    //  --> file.html:3:5
+    //   |
+    // 3 | <p></div>
+    //   |     ^
+    pub fn render(&self, name: &str, source: &str) -> String {
+        let mut out = format!("error: {}\n", self);
+        let pos = match self.position() {
+            Some(pos) => pos,
+            None => return out + &format!(" --> {}\n", name),
+        };
+        // at_lineは1始まりの列 (tokenの終わり)。改行のtokenは次の行の0になる
+        let column = pos.at_line.max(1);
+        let line_no = pos.line_no.to_string();
+        let gutter = " ".repeat(line_no.len());
+        let line = source
+            .split('\n')
+            .nth(pos.line_no.saturating_sub(1) as usize)
+            .unwrap_or("")
+            .trim_end_matches('\r');
+        out += &format!("{} --> {}:{}:{}\n", gutter, name, pos.line_no, column);
+        out += &format!("{} |\n", gutter);
+        out += &format!("{} | {}\n", line_no, line);
+        out += &format!("{} | {}^\n", gutter, " ".repeat(column as usize - 1));
+        return out;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::html::errors::ParseError;
+    use crate::html::lsp::{LineMap, LspPosition, LspRange};
+    use crate::html::parser::Parser;
+    use crate::html::tokenizer::Tokenizer;
+
+    fn range(start: (u32, u32), end: (u32, u32)) -> LspRange {
+        return LspRange {
+            start: LspPosition {
+                line: start.0,
+                character: start.1,
+            },
+            end: LspPosition {
+                line: end.0,
+                character: end.1,
+            },
+        };
+    }
+
+    #[test]
+    fn lsp_range_after_astral_character() {
+        let html = "<p>\n😀é<div foo></div></p>";
+        let mut tokenizer = Tokenizer::new(html);
+        let mut parser = Parser::new();
+        let err = parser.parse(tokenizer.tokenize()).unwrap_err();
+
+        // 属性の名前の後の ">": char単位では10列目から, UTF-16では11から12まで
+        assert_eq!(err.lsp_range(&LineMap::new(html)), range((1, 11), (1, 12)));
+    }
+
+    #[test]
+    fn tag_miss_match_ranges_on_later_lines() {
+        let html = "<div>\n  <p>\n  😀</div>";
+        let err = Parser::new().parse_str(html).unwrap_err();
+        let line_map = LineMap::new(html);
+        let open_range = match &err {
+            ParseError::TagMissMatch { open_range, .. } => open_range,
+            _ => panic!("unexpected error: {:?}", err),
+        };
+        // <p> は2行目の2..5
+        assert_eq!(
+            line_map.lsp_range(&open_range.0, &open_range.1),
+            range((1, 2), (1, 5))
+        );
+        // </div> は3行目の "😀" (2 code unit) の後
+        assert_eq!(err.lsp_range(&line_map), range((2, 4), (2, 10)));
+        assert_eq!(
+            err.render("a.html", html).lines().nth(1),
+            Some("  --> a.html:3:9")
+        );
+    }
+
+    #[test]
+    fn warning_ranges_cover_the_tag() {
+        let html = "<p>\n😀</b></p>";
+        let mut parser = Parser::builder()
+            .lenient()
+            .collect_warnings(true)
+            .build()
+            .unwrap();
+        parser.parse_str(html).unwrap();
+        let warning = &parser.warnings()[0];
+        assert_eq!(warning.message, "close tag without an open tag; ignored");
+        assert_eq!(
+            warning.lsp_range(&LineMap::new(html)),
+            range((1, 2), (1, 6))
+        );
+    }
+}
@@ -0,0 +1,149 @@
+use crate::html::errors::FetchParseError;
+use crate::html::url;
+use crate::net::{Response, Transport};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FetchOptions {
+    // 3xxのLocationを辿る回数
+    pub max_redirects: usize,
+}
+
+impl Default for FetchOptions {
+    fn default() -> FetchOptions {
+        return FetchOptions { max_redirects: 5 };
+    }
+}
+
+// redirectを辿って取ってきた、2xxのresponse
+pub(crate) fn fetch(
+    url: &str,
+    transport: &impl Transport,
+    options: &FetchOptions,
+) -> Result<Response, FetchParseError> {
+    let mut url = url.to_string();
+    let mut redirects = 0;
+    loop {
+        let response = transport.get(&url)?;
+        if response.is_redirect() {
+            let location = match response.header("location") {
+                Some(location) => location,
+                None => {
+                    return Err(FetchParseError::Status {
+                        url,
+                        status: response.status,
+                    })
+                }
+            };
+            if redirects == options.max_redirects {
+                return Err(FetchParseError::TooManyRedirects { url });
+            }
+            redirects += 1;
+            url = url::resolve(&url, location);
+            continue;
+        }
+        if !response.is_success() {
+            return Err(FetchParseError::Status {
+                url,
+                status: response.status,
+            });
+        }
+        return Ok(response);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::html;
+    use crate::html::errors::FetchParseError;
+    use crate::html::fetch::FetchOptions;
+    use crate::net::mock::MockTransport;
+    use crate::net::Response;
+
+    fn text_of(doc: &html::document::Document, selector: &str) -> String {
+        let node = doc.query_selector(selector).unwrap().unwrap();
+        return node
+            .children()
+            .iter()
+            .filter_map(|child| child.text_value())
+            .map(|s| s.as_str())
+            .collect::<Vec<&str>>()
+            .join(" ");
+    }
+
+    #[test]
+    fn fetch_latin1_document() {
+        let mut transport = MockTransport::new();
+        transport.insert(
+            "http://example.com/",
+            Response::new(301, b"").with_header("Location", "/latin1.html"),
+        );
+        transport.insert(
+            "http://example.com/latin1.html",
+            Response::new(200, b"<html><body><p>caf\xe9 \x80 5</p></body></html>")
+                .with_header("Content-Type", "text/html; charset=ISO-8859-1"),
+        );
+        let doc =
+            html::fetch_and_parse("http://example.com/", &transport, &FetchOptions::default())
+                .unwrap();
+        assert_eq!(text_of(&doc, "p"), "caf\u{e9} \u{20ac} 5");
+        assert_eq!(
+            transport.requests(),
+            vec!["http://example.com/", "http://example.com/latin1.html"]
+        );
+    }
+
+    #[test]
+    fn charset_from_meta_and_bom() {
+        let mut transport = MockTransport::new();
+        // headerにcharsetがなければmetaを見る
+        transport.insert(
+            "http://example.com/meta",
+            Response::new(
+                200,
+                b"<html><head><meta charset=\"latin1\" /></head><body><p>\xe9</p></body></html>",
+            )
+            .with_header("Content-Type", "text/html"),
+        );
+        // BOMはheaderより強い
+        transport.insert(
+            "http://example.com/bom",
+            Response::new(200, b"\xef\xbb\xbf<p>\xc3\xa9</p>")
+                .with_header("Content-Type", "text/html; charset=latin1"),
+        );
+        transport.insert(
+            "http://example.com/sjis",
+            Response::new(200, b"<p>x</p>")
+                .with_header("Content-Type", "text/html; charset=Shift_JIS"),
+        );
+        let options = FetchOptions::default();
+
+        let doc = html::fetch_and_parse("http://example.com/meta", &transport, &options).unwrap();
+        assert_eq!(text_of(&doc, "p"), "\u{e9}");
+        let doc = html::fetch_and_parse("http://example.com/bom", &transport, &options).unwrap();
+        assert_eq!(text_of(&doc, "p"), "\u{e9}");
+        #[cfg(not(feature = "encoding_rs"))]
+        assert!(matches!(
+            html::fetch_and_parse("http://example.com/sjis", &transport, &options),
+            Err(FetchParseError::UnsupportedEncoding { label }) if label == "Shift_JIS"
+        ));
+    }
+
+    #[test]
+    fn status_and_redirect_errors() {
+        let mut transport = MockTransport::new();
+        transport.insert(
+            "http://example.com/loop",
+            Response::new(302, b"").with_header("Location", "loop"),
+        );
+        let options = FetchOptions { max_redirects: 3 };
+        assert!(matches!(
+            html::fetch_and_parse("http://example.com/loop", &transport, &options),
+            Err(FetchParseError::TooManyRedirects { .. })
+        ));
+        assert_eq!(transport.requests().len(), 4);
+        assert!(matches!(
+            html::fetch_and_parse("http://example.com/missing", &transport, &options),
+            Err(FetchParseError::Status { status: 404, .. })
+        ));
+    }
+}
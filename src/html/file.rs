@@ -0,0 +1,93 @@
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FileOptions {
+    // このlabelのencodingとして読む (transportのcharsetと同じ扱い)。NoneならBOMとmetaから決める
+    pub charset: Option<String>,
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::html;
+    use crate::html::errors::FileParseError;
+    use crate::html::file::FileOptions;
+    use std::error::Error;
+    use std::fs;
+    use std::io::ErrorKind;
+    use std::path::PathBuf;
+    use std::process;
+
+    // testごとに別のdirを作る。終わったら消す
+    struct TempDir(PathBuf);
+
+    impl TempDir {
+        fn new(name: &str) -> TempDir {
+            let dir = std::env::temp_dir().join(format!("browser-{}-{}", name, process::id()));
+            fs::create_dir_all(&dir).unwrap();
+            return TempDir(dir);
+        }
+    }
+
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    #[test]
+    fn parse_file_with_charset() {
+        let dir = TempDir::new("parse-file");
+        let path = dir.0.join("index.html");
+        fs::write(&path, b"<html><body><p>caf\xe9</p></body></html>").unwrap();
+
+        let options = FileOptions {
+            charset: Some("latin1".to_string()),
+        };
+        let doc = html::parse_file(&path, &options).unwrap();
+        let p = doc.query_selector("p").unwrap().unwrap();
+        assert_eq!(p.children()[0].text_value().unwrap().as_str(), "caf\u{e9}");
+
+        // BOMはoptionsより強い
+        fs::write(&path, b"\xef\xbb\xbf<p>\xc3\xa9</p>").unwrap();
+        let doc = html::parse_file(&path, &options).unwrap();
+        let p = doc.query_selector("p").unwrap().unwrap();
+        assert_eq!(p.children()[0].text_value().unwrap().as_str(), "\u{e9}");
+    }
+
+    #[test]
+    fn parse_error_names_the_file() {
+        let dir = TempDir::new("parse-file-error");
+        let path = dir.0.join("broken.html");
+        fs::write(&path, "<div>\n  <p class=x>one</p>\n</div>\n").unwrap();
+
+        let err = html::parse_file(&path, &FileOptions::default()).unwrap_err();
+        assert!(matches!(err, FileParseError::Parse { .. }));
+        assert_eq!(
+            err.to_string(),
+            format!(
+                "error in {}:2:12: {}",
+                path.display(),
+                err.source().unwrap()
+            )
+        );
+    }
+
+    #[test]
+    fn missing_file() {
+        let path = std::env::temp_dir().join("browser-missing-file/none.html");
+        let err = html::parse_file(&path, &FileOptions::default()).unwrap_err();
+        let io = match &err {
+            FileParseError::Io {
+                path: err_path,
+                source,
+            } => {
+                assert_eq!(*err_path, path);
+                source
+            }
+            _ => panic!("unexpected error: {}", err),
+        };
+        assert_eq!(io.kind(), ErrorKind::NotFound);
+        assert!(err
+            .to_string()
+            .starts_with(&format!("failed to read {}: ", path.display())));
+        assert!(err.source().is_some());
+    }
+}
@@ -0,0 +1,51 @@
+use crate::html::shared_str::SharedStr;
+use alloc::collections::BTreeSet;
+use core::mem::size_of;
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct MemoryFootprint {
+    // 要素、テキスト、コメント、doctype (属性は数えない)
+    pub nodes: usize,
+    pub text_bytes: usize,
+    pub attribute_bytes: usize,
+    // 確保している領域の見積もり。lenではなくcapacityで数える
+    pub heap_bytes: usize,
+}
+
+// sourceのbufは多くのnodeから共有されているので、同じbufは一度だけ数える
+#[derive(Default)]
+pub(crate) struct FootprintCounter {
+    footprint: MemoryFootprint,
+    buffers: BTreeSet<usize>,
+}
+
+impl FootprintCounter {
+    pub(crate) fn node(&mut self) {
+        self.footprint.nodes += 1;
+    }
+
+    pub(crate) fn text(&mut self, s: &SharedStr) {
+        self.footprint.text_bytes += s.len();
+    }
+
+    pub(crate) fn attribute(&mut self, value: &SharedStr) {
+        self.footprint.attribute_bytes += value.len();
+    }
+
+    pub(crate) fn heap(&mut self, bytes: usize) {
+        self.footprint.heap_bytes += bytes;
+    }
+
+    pub(crate) fn string(&mut self, s: &SharedStr) {
+        if let Some((ptr, len)) = s.heap_buffer() {
+            if self.buffers.insert(ptr) {
+                // Arc<str>は参照カウント2つ分の頭がつく
+                self.footprint.heap_bytes += len + 2 * size_of::<usize>();
+            }
+        }
+    }
+
+    pub(crate) fn finish(self) -> MemoryFootprint {
+        return self.footprint;
+    }
+}
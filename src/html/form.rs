@@ -0,0 +1,376 @@
+use crate::html::parser::{Node, NodeData};
+use crate::html::url;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum FieldKind {
+    // text, email, search などの一行の入力
+    Text,
+    Hidden,
+    Password,
+    Checkbox,
+    Radio,
+    // <input type="submit"> と <button>
+    Submit,
+    Textarea,
+    Select,
+    // file, image, reset, button。送らない
+    Other,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FormField {
+    pub name: String,
+    pub value: String,
+    pub kind: FieldKind,
+    // checkbox, radio, 選ばれたoption
+    pub checked: bool,
+    pub disabled: bool,
+}
+
+// <form> とその中の入力。書かれた順に並ぶ
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FormData {
+    pub action: Option<String>,
+    // 小文字。書かれていなければget
+    pub method: String,
+    pub fields: Vec<FormField>,
+}
+
+impl FormData {
+    pub fn from_form(form: &Node) -> FormData {
+        let method = match form.attr("method") {
+            Some(method) if method.eq_ignore_ascii_case("post") => "post",
+            Some(method) if method.eq_ignore_ascii_case("dialog") => "dialog",
+            _ => "get",
+        };
+        let mut fields = Vec::new();
+        let mut stack: Vec<&Node> = form.children().iter().rev().collect();
+        while let Some(nd) = stack.pop() {
+            let name = match nd.name() {
+                Some(name) => name.as_str(),
+                None => continue,
+            };
+            match name {
+                "input" => fields.push(input_field(nd)),
+                "button" => fields.push(FormField {
+                    // typeのないbuttonはsubmit
+                    kind: match nd.attr("type") {
+                        Some(ty) if !ty.eq_ignore_ascii_case("submit") => FieldKind::Other,
+                        _ => FieldKind::Submit,
+                    },
+                    ..field(nd, attr_or(nd, "value", ""))
+                }),
+                "textarea" => fields.push(FormField {
+                    kind: FieldKind::Textarea,
+                    ..field(nd, text_content(nd))
+                }),
+                "select" => fields.extend(select_fields(nd)),
+                _ => stack.extend(nd.children().iter().rev()),
+            }
+        }
+        return FormData {
+            action: form.attr("action").map(|action| action.to_string()),
+            method: method.to_string(),
+            fields,
+        };
+    }
+
+    // 送り先。actionが空ならbaseそのもの
+    pub fn action_url(&self, base: &str) -> String {
+        return match &self.action {
+            Some(action) if !action.trim().is_empty() => url::resolve(base, action),
+            _ => base.to_string(),
+        };
+    }
+
+    // 送る (name, value) を順に並べる
+    // overridesは同じnameの最初の入力の値を置き換える。checkboxやradioは選んだことにする
+    // checkboxは選ばれていれば自分のvalue (なければ "on") を送り、選ばれていなければ送らない
+    // submitは押したものとしてoverridesに名前があるときだけ送る
+    // formにないnameは最後に足す
+    pub fn entries(&self, overrides: &[(&str, &str)]) -> Vec<(String, String)> {
+        let mut entries = Vec::new();
+        let mut used = vec![false; overrides.len()];
+        for f in &self.fields {
+            if f.disabled || f.name.is_empty() {
+                continue;
+            }
+            let overridden = overrides.iter().position(|(name, _)| *name == f.name);
+            let value = match (f.kind, overridden) {
+                (FieldKind::Other, _) => continue,
+                // radioはvalueが合うものを選ぶ
+                (FieldKind::Radio, Some(i)) => {
+                    if overrides[i].1 != f.value {
+                        continue;
+                    }
+                    f.value.clone()
+                }
+                (FieldKind::Checkbox, Some(_)) => f.value.clone(),
+                (FieldKind::Submit, Some(i)) => {
+                    if overrides[i].1.is_empty() {
+                        f.value.clone()
+                    } else {
+                        overrides[i].1.to_string()
+                    }
+                }
+                (FieldKind::Submit, None) => continue,
+                (FieldKind::Checkbox | FieldKind::Radio, None) if !f.checked => continue,
+                (_, Some(i)) if used[i] => f.value.clone(),
+                (_, Some(i)) => overrides[i].1.to_string(),
+                (_, None) => f.value.clone(),
+            };
+            if let Some(i) = overridden {
+                used[i] = true;
+            }
+            entries.push((f.name.clone(), value));
+        }
+        for (i, (name, value)) in overrides.iter().enumerate() {
+            if !used[i] && !self.fields.iter().any(|f| f.name == *name) {
+                entries.push((name.to_string(), value.to_string()));
+            }
+        }
+        return entries;
+    }
+
+    // application/x-www-form-urlencoded
+    pub fn to_urlencoded(&self, overrides: &[(&str, &str)]) -> String {
+        return self
+            .entries(overrides)
+            .iter()
+            .map(|(name, value)| format!("{}={}", urlencode(name), urlencode(value)))
+            .collect::<Vec<String>>()
+            .join("&");
+    }
+}
+
+fn attr_or(nd: &Node, name: &str, default: &str) -> String {
+    return match nd.attr(name) {
+        Some(value) => value.to_string(),
+        None => default.to_string(),
+    };
+}
+
+fn field(nd: &Node, value: String) -> FormField {
+    return FormField {
+        name: attr_or(nd, "name", ""),
+        value,
+        kind: FieldKind::Text,
+        checked: false,
+        disabled: nd.attr("disabled").is_some(),
+    };
+}
+
+fn input_field(nd: &Node) -> FormField {
+    let ty = attr_or(nd, "type", "text").to_ascii_lowercase();
+    let kind = match ty.as_str() {
+        "hidden" => FieldKind::Hidden,
+        "password" => FieldKind::Password,
+        "checkbox" => FieldKind::Checkbox,
+        "radio" => FieldKind::Radio,
+        "submit" => FieldKind::Submit,
+        "file" | "image" | "reset" | "button" => FieldKind::Other,
+        _ => FieldKind::Text,
+    };
+    // valueのないcheckboxは "on" を送る
+    let default = match kind {
+        FieldKind::Checkbox | FieldKind::Radio => "on",
+        _ => "",
+    };
+    return FormField {
+        kind,
+        checked: nd.attr("checked").is_some(),
+        ..field(nd, attr_or(nd, "value", default))
+    };
+}
+
+// 選ばれたoptionごとに一つ。multipleでなく、どれも選ばれていなければ最初のもの
+fn select_fields(select: &Node) -> Vec<FormField> {
+    let mut options = Vec::new();
+    let mut stack: Vec<&Node> = select.children().iter().rev().collect();
+    while let Some(nd) = stack.pop() {
+        if nd.name().is_some_and(|name| *name == "option") {
+            options.push(nd);
+        } else {
+            stack.extend(nd.children().iter().rev());
+        }
+    }
+    let multiple = select.attr("multiple").is_some();
+    let mut selected: Vec<&Node> = options
+        .iter()
+        .copied()
+        .filter(|option| option.attr("selected").is_some())
+        .collect();
+    if !multiple {
+        selected.truncate(1);
+        if selected.is_empty() {
+            selected.extend(options.first());
+        }
+    }
+    return selected
+        .into_iter()
+        .map(|option| FormField {
+            kind: FieldKind::Select,
+            checked: true,
+            disabled: select.attr("disabled").is_some() || option.attr("disabled").is_some(),
+            ..field(
+                select,
+                match option.attr("value") {
+                    Some(value) => value.to_string(),
+                    None => text_content(option),
+                },
+            )
+        })
+        .collect();
+}
+
+// parserがtextを空白で区切るので、一つの空白でつなぎ直す
+fn text_content(nd: &Node) -> String {
+    let mut words = Vec::new();
+    let mut stack: Vec<&Node> = nd.children().iter().rev().collect();
+    while let Some(child) = stack.pop() {
+        match &child.data {
+            NodeData::Text(s) => words.push(s.as_str()),
+            _ => stack.extend(child.children().iter().rev()),
+        }
+    }
+    return words.join(" ");
+}
+
+// 英数字と *-._ 以外をUTF-8の%XXにし、空白は+にする。改行はCRLFにそろえる
+pub fn urlencode(s: &str) -> String {
+    let s = s
+        .replace("\r\n", "\n")
+        .replace('\r', "\n")
+        .replace('\n', "\r\n");
+    let mut out = String::with_capacity(s.len());
+    for b in s.bytes() {
+        match b {
+            b'a'..=b'z' | b'A'..=b'Z' | b'0'..=b'9' | b'*' | b'-' | b'.' | b'_' => {
+                out.push(b as char)
+            }
+            b' ' => out.push('+'),
+            _ => out += &format!("%{:02X}", b),
+        }
+    }
+    return out;
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::html;
+    use crate::html::form::{urlencode, FieldKind};
+
+    const LOGIN: &str = "<form action=\"/session?next=1\" method=\"POST\">\
+        <input type=\"hidden\" name=\"csrf\" value=\"t0k+n/=\" />\
+        <label>User <input name=\"user\" /></label>\
+        <input type=\"password\" name=\"pass\" />\
+        <input type=\"checkbox\" name=\"remember\" />\
+        <input type=\"checkbox\" name=\"terms\" value=\"yes\" checked=\"\" />\
+        <input type=\"radio\" name=\"lang\" value=\"en\" checked=\"\" />\
+        <input type=\"radio\" name=\"lang\" value=\"ja\" />\
+        <select name=\"plan\"><option value=\"free\">Free</option><option selected=\"\">Pro plan</option></select>\
+        <textarea name=\"bio\">hello world</textarea>\
+        <input name=\"old\" value=\"x\" disabled=\"\" />\
+        <input type=\"file\" name=\"avatar\" />\
+        <button name=\"login\" value=\"1\">Log in</button>\
+        <input type=\"submit\" name=\"register\" value=\"Register\" />\
+        </form>";
+
+    #[test]
+    fn extract_fields() {
+        let doc = html::parse(LOGIN).unwrap();
+        let forms = doc.forms();
+        assert_eq!(forms.len(), 1);
+        let form = &forms[0];
+        assert_eq!(form.method, "post");
+        let fields: Vec<(&str, FieldKind)> = form
+            .fields
+            .iter()
+            .map(|f| (f.name.as_str(), f.kind))
+            .collect();
+        assert_eq!(
+            fields,
+            vec![
+                ("csrf", FieldKind::Hidden),
+                ("user", FieldKind::Text),
+                ("pass", FieldKind::Password),
+                ("remember", FieldKind::Checkbox),
+                ("terms", FieldKind::Checkbox),
+                ("lang", FieldKind::Radio),
+                ("lang", FieldKind::Radio),
+                ("plan", FieldKind::Select),
+                ("bio", FieldKind::Textarea),
+                ("old", FieldKind::Text),
+                ("avatar", FieldKind::Other),
+                ("login", FieldKind::Submit),
+                ("register", FieldKind::Submit),
+            ]
+        );
+        assert_eq!(
+            form.action_url("https://example.com/login"),
+            "https://example.com/session?next=1"
+        );
+    }
+
+    #[test]
+    fn urlencoded_submission() {
+        let doc = html::parse(LOGIN).unwrap();
+        let form = &doc.forms()[0];
+        assert_eq!(
+            form.to_urlencoded(&[]),
+            "csrf=t0k%2Bn%2F%3D&user=&pass=&terms=yes&lang=en&plan=Pro+plan&bio=hello+world"
+        );
+        assert_eq!(
+            form.to_urlencoded(&[
+                ("user", "山田 太郎"),
+                ("pass", "p@ss w0rd&="),
+                ("remember", ""),
+                ("lang", "ja"),
+                ("login", ""),
+                ("extra", "a~b*c"),
+            ]),
+            "csrf=t0k%2Bn%2F%3D\
+             &user=%E5%B1%B1%E7%94%B0+%E5%A4%AA%E9%83%8E\
+             &pass=p%40ss+w0rd%26%3D\
+             &remember=on&terms=yes&lang=ja&plan=Pro+plan&bio=hello+world\
+             &login=1&extra=a%7Eb*c"
+        );
+    }
+
+    #[test]
+    fn checkbox_submission() {
+        let doc = html::parse(
+            "<form>\
+            <input type=\"checkbox\" name=\"a\" checked=\"\" />\
+            <input type=\"checkbox\" name=\"b\" value=\"yes\" checked=\"\" />\
+            <input type=\"checkbox\" name=\"c\" value=\"yes\" />\
+            </form>",
+        )
+        .unwrap();
+        let form = &doc.forms()[0];
+        let pair = |name: &str, value: &str| (name.to_string(), value.to_string());
+
+        // valueのないものは "on"、あるものはそのvalue、選ばれていないものは送らない
+        assert_eq!(form.entries(&[]), vec![pair("a", "on"), pair("b", "yes")]);
+        // 選んだことにしても、送るのは自分のvalue
+        assert_eq!(
+            form.entries(&[("c", "other")]),
+            vec![pair("a", "on"), pair("b", "yes"), pair("c", "yes")]
+        );
+    }
+
+    #[test]
+    fn encode_values() {
+        assert_eq!(urlencode("a b\nc"), "a+b%0D%0Ac");
+        assert_eq!(urlencode("-._*~!'()"), "-._*%7E%21%27%28%29");
+        assert_eq!(urlencode("é😀"), "%C3%A9%F0%9F%98%80");
+
+        let doc = html::parse("<form><input name=\"q\" /></form>").unwrap();
+        let form = &doc.forms()[0];
+        assert_eq!(form.method, "get");
+        assert_eq!(
+            form.action_url("https://example.com/a?b"),
+            "https://example.com/a?b"
+        );
+    }
+}
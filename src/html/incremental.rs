@@ -0,0 +1,646 @@
+// 文書の一部を書き換えたとき、書き換えを含むいちばん内側の要素だけを読み直す
+// nodeは位置を持たないので、要素の範囲はparserに残させ、Documentに持って書き換えのたびにずらす
+use crate::html;
+use crate::html::document::Document;
+use crate::html::errors::{EditRangeError, ParseError, ReparseError, RetokenizeError};
+use crate::html::parser::{
+    children_namespace, ElementSpan, Namespace, Node, Parser, ParserOptions,
+};
+use crate::html::path::{self, NodePath};
+use crate::html::position::Position;
+use crate::html::tokenizer::{Token, Tokenizer, TokenizerOptions};
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::ops::Range;
+
+// 書き換える前のsourceのrangeをtextで置き換える
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Edit {
+    pub range: Range<usize>,
+    pub text: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ReparseResult {
+    // この要素だけを読み直した
+    Element { path: NodePath },
+    // 文書全体を読み直した
+    Full,
+}
+
+impl Edit {
+    // rangeがsourceの外か文字の途中ならエラー
+    pub fn apply(&self, source: &str) -> Result<String, EditRangeError> {
+        self.check(source)?;
+        let mut out = source.to_string();
+        out.replace_range(self.range.clone(), &self.text);
+        return Ok(out);
+    }
+
+    fn check(&self, source: &str) -> Result<(), EditRangeError> {
+        let Range { start, end } = self.range;
+        if start <= end
+            && end <= source.len()
+            && source.is_char_boundary(start)
+            && source.is_char_boundary(end)
+        {
+            return Ok(());
+        }
+        return Err(EditRangeError {
+            start,
+            end,
+            len: source.len(),
+        });
+    }
+
+    // タグの区切りを消したり足したりすると、要素の範囲が変わる
+    // rangeがsourceに合わなければ、変わるものとして扱う
+    fn touches_delimiter(&self, source: &str) -> bool {
+        let is_delimiter = |c: char| c == '<' || c == '>';
+        return match source.get(self.range.clone()) {
+            Some(old) => old.contains(is_delimiter) || self.text.contains(is_delimiter),
+            None => true,
+        };
+    }
+
+    // 書き換えた後のsourceでの位置
+    fn shift(&self, at: usize) -> usize {
+        return at + self.text.len() - self.range.len();
+    }
+}
+
+// あるsourceでの要素の範囲 (byte)。木の要素と同じく開きタグの順に並ぶ
+// 範囲のない要素 (開き直したものや、閉じタグなしで閉じたもの) はNone
+type Spans = Vec<Option<Range<usize>>>;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct SourceSpans {
+    source_hash: u64,
+    spans: Spans,
+}
+
+fn source_hash(source: &str) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    source.hash(&mut hasher);
+    return hasher.finish();
+}
+
+pub(crate) fn reparse_edit(
+    doc: &mut Document,
+    edit: &Edit,
+    source: &str,
+    options: &ParserOptions,
+) -> Result<ReparseResult, ReparseError> {
+    let new_source = edit.apply(source)?;
+    // 前の書き換えで残した範囲が使えなければ、書き換える前のsourceから一度だけ求める
+    let spans = match doc.take_source_spans() {
+        Some(cached) if cached.source_hash == source_hash(source) => Some(cached.spans),
+        _ => parse_with_spans(source, options)
+            .ok()
+            .map(|(_, spans)| spans),
+    };
+    if let Some(spans) = spans {
+        if let Some((path, spans)) =
+            reparse_element(doc, edit, source, &new_source, &spans, options)
+        {
+            doc.set_source_spans(SourceSpans {
+                source_hash: source_hash(&new_source),
+                spans,
+            });
+            return Ok(ReparseResult::Element { path });
+        }
+    }
+    let (new_doc, spans) = parse_with_spans(&new_source, options)?;
+    *doc = new_doc;
+    doc.set_source_spans(SourceSpans {
+        source_hash: source_hash(&new_source),
+        spans,
+    });
+    return Ok(ReparseResult::Full);
+}
+
+// 読み直せたら、その要素のpathと、書き換えた後のsourceでの範囲。Noneなら全体を読み直す
+fn reparse_element(
+    doc: &mut Document,
+    edit: &Edit,
+    source: &str,
+    new_source: &str,
+    spans: &[Option<Range<usize>>],
+    options: &ParserOptions,
+) -> Option<(NodePath, Spans)> {
+    if edit.touches_delimiter(source) {
+        return None;
+    }
+    let elements = elements(&doc.children);
+    // 数えかたが食い違っていたら、どの要素か分からない
+    if spans.len() != elements.len() {
+        return None;
+    }
+    // 要素の順は開きタグの順なので、書き換えを中に含む最後のものがいちばん内側
+    let index = spans.iter().rposition(|span| {
+        span.as_ref()
+            .is_some_and(|span| span.start < edit.range.start && edit.range.end < span.end)
+    })?;
+    let span = spans[index].clone()?;
+    let path = elements[index].0.clone();
+    // 子孫の要素は、この要素のすぐ後ろに並ぶ
+    let subtree_len = elements[index..]
+        .iter()
+        .take_while(|(descendant, _)| descendant.starts_with(&path))
+        .count();
+    // 親の中と同じ名前空間で読む。最上位ならHTML
+    let namespace = match path.split_last() {
+        Some((_, [])) | None => Namespace::Html,
+        Some((_, parent)) => {
+            children_namespace(path::node_at(&doc.children, &NodePath(parent.to_vec()))?)
+        }
+    };
+
+    let fragment = &new_source[span.start..edit.shift(span.end)];
+    let (mut nodes, fragment_spans) = parse_fragment(fragment, options, namespace, path.len() - 1)?;
+    if nodes.len() != 1 || !nodes[0].is_element() {
+        return None;
+    }
+
+    // 外側の要素は終わりだけ、後ろの要素は全体をずらす
+    let mut new_spans: Spans = spans[..index]
+        .iter()
+        .map(|span| {
+            span.clone().map(|span| match span.end >= edit.range.end {
+                true => span.start..edit.shift(span.end),
+                false => span,
+            })
+        })
+        .collect();
+    new_spans.extend(
+        fragment_spans
+            .into_iter()
+            .map(|inner| inner.map(|inner| inner.start + span.start..inner.end + span.start)),
+    );
+    new_spans.extend(spans[index + subtree_len..].iter().map(|span| {
+        span.clone()
+            .map(|span| edit.shift(span.start)..edit.shift(span.end))
+    }));
+
+    let target = node_at_mut(&mut doc.children, &path)?;
+    *target = nodes.remove(0);
+    return Some((NodePath(path), new_spans));
+}
+
+// 範囲を残しながら、sourceの全体を読む
+fn parse_with_spans(
+    source: &str,
+    options: &ParserOptions,
+) -> Result<(Document, Spans), ParseError> {
+    let mut parser = Parser::builder().options(options.clone()).build()?;
+    parser.record_element_spans();
+    let result = parser.parse_str(source);
+    let doc = html::to_document(&parser, result)?;
+    return Ok((doc, byte_spans(source, &parser.take_element_spans())));
+}
+
+// 切り出した要素を、親の中と同じ名前空間と深さで読む
+// 直したエラーがあれば、全体を読んだときとは閉じ方が違いうるのでNone
+fn parse_fragment(
+    fragment: &str,
+    options: &ParserOptions,
+    namespace: Namespace,
+    depth: usize,
+) -> Option<(Vec<Node>, Spans)> {
+    let mut options = options.clone();
+    if let Some(max_depth) = options.max_depth {
+        options.max_depth = Some(max_depth.checked_sub(depth).filter(|rest| *rest > 0)?);
+    }
+    let mut parser = Parser::builder().options(options).build().ok()?;
+    parser.record_element_spans();
+    let nodes = parser.parse_fragment(fragment, namespace).ok()??;
+    if parser.error_count() > 0 {
+        return None;
+    }
+    return Some((nodes, byte_spans(fragment, &parser.take_element_spans())));
+}
+
+// parserの位置は文字の数なので、byteに直す
+fn byte_spans(source: &str, spans: &[ElementSpan]) -> Spans {
+    let bytes: Vec<usize> = source
+        .char_indices()
+        .map(|(i, _)| i)
+        .chain([source.len()])
+        .collect();
+    let byte = |pos: &Position| bytes.get(pos.at_whole as usize).copied();
+    return spans
+        .iter()
+        .map(|span| match (&span.start, &span.end) {
+            (Some(start), Some(end)) => Some(byte(start)?..byte(end)?),
+            _ => None,
+        })
+        .collect();
+}
+
+pub(crate) fn retokenize(
+    doc: &Document,
+    nd: &Node,
+    source: &str,
+) -> Result<Option<Box<Token>>, RetokenizeError> {
+    if !nd.is_element() {
+        return Err(RetokenizeError::NotElement);
+    }
+    let elements = elements(&doc.children);
+    let index = elements
+        .iter()
+        .position(|(_, el)| core::ptr::eq(*el, nd))
+        .ok_or(RetokenizeError::NotInDocument)?;
+    let spans = match doc.source_spans() {
+        Some(cached) if cached.source_hash == source_hash(source) => cached.spans.clone(),
+        // どう読んだ文書か分からないので、直しながら読んで範囲を求める
+        _ => {
+            let options = ParserOptions {
+                lenient: true,
+                ..ParserOptions::default()
+            };
+            match parse_with_spans(source, &options) {
+                Ok((_, spans)) => spans,
+                Err(_) => return Err(RetokenizeError::SourceMismatch),
+            }
+        }
+    };
+    if spans.len() != elements.len() {
+        return Err(RetokenizeError::SourceMismatch);
+    }
+    let span = spans[index].clone().ok_or(RetokenizeError::NoSpan)?;
+    let mut tokenizer = Tokenizer::starting_at(
+        &source[span.clone()],
+        position_at(source, span.start),
+        TokenizerOptions::default(),
+    );
+    return Ok(tokenizer.tokenize());
+}
+
+// byteの位置を、tokenizerと同じ数えかたのPositionにする
+fn position_at(source: &str, byte: usize) -> Position {
+    let before = &source[..byte];
+    let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+    return Position::new(
+        before.matches('\n').count() as u32 + 1,
+        before[line_start..].chars().count() as u32,
+        before.chars().count() as u32,
+    );
+}
+
+// 要素と、最上位からの子の番号の列。文書の順 (開きタグの順) に並べる
+fn elements(roots: &[Node]) -> Vec<(Vec<usize>, &Node)> {
+    let mut found = Vec::new();
+    let mut stack: Vec<(&Node, Vec<usize>)> = roots
+        .iter()
+        .enumerate()
+        .rev()
+        .map(|(i, nd)| (nd, vec![i]))
+        .collect();
+    while let Some((nd, path)) = stack.pop() {
+        if !nd.is_element() {
+            continue;
+        }
+        for (i, child) in nd.children().iter().enumerate().rev() {
+            let mut child_path = path.clone();
+            child_path.push(i);
+            stack.push((child, child_path));
+        }
+        found.push((path, nd));
+    }
+    return found;
+}
+
+fn node_at_mut<'a>(roots: &'a mut [Node], path: &[usize]) -> Option<&'a mut Node> {
+    let (first, rest) = path.split_first()?;
+    let mut nd = roots.get_mut(*first)?;
+    for i in rest {
+        nd = nd.children_mut()?.get_mut(*i)?;
+    }
+    return Some(nd);
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::html;
+    use crate::html::document::Document;
+    use crate::html::errors::{EditRangeError, ReparseError, RetokenizeError};
+    use crate::html::incremental::{Edit, ReparseResult};
+    use crate::html::parser::ParserOptions;
+    use crate::html::path::NodePath;
+    use crate::html::position::Position;
+    use crate::html::serializer::to_tree_test_format;
+    use crate::html::tokenizer::{Token, TokenKind, Tokenizer};
+
+    const SOURCE: &str = "<!DOCTYPE html>\n<html>\n<body>\n<!-- nav -->\n<div id=\"main\">\n  <p class=\"lead\">hello world</p>\n  <ul><li>one</li><li>two</li></ul>\n</div>\n<p>tail</p>\n</body>\n</html>\n";
+
+    // 書き換えたあとの木が、はじめから読んだものと同じか
+    fn reparse(find: &str, replace: &str) -> (Document, ReparseResult) {
+        return reparse_in(SOURCE, find, replace, &ParserOptions::default());
+    }
+
+    fn reparse_in(
+        source: &str,
+        find: &str,
+        replace: &str,
+        options: &ParserOptions,
+    ) -> (Document, ReparseResult) {
+        let mut doc = html::parse_with_options(source, options).unwrap();
+        let result = edit_and_compare(&mut doc, source, find, replace, options);
+        return (doc, result);
+    }
+
+    // docはsourceを読んだもの。書き換えた後のsourceを全部読んだ木と比べる
+    fn edit_and_compare(
+        doc: &mut Document,
+        source: &str,
+        find: &str,
+        replace: &str,
+        options: &ParserOptions,
+    ) -> ReparseResult {
+        let start = source.find(find).unwrap();
+        let edit = Edit {
+            range: start..start + find.len(),
+            text: replace.to_string(),
+        };
+        let result = doc
+            .reparse_edit_with_options(edit.clone(), source, options)
+            .unwrap();
+        let expected = html::parse_with_options(&edit.apply(source).unwrap(), options).unwrap();
+        assert_eq!(
+            to_tree_test_format(&doc.children),
+            to_tree_test_format(&expected.children)
+        );
+        return result;
+    }
+
+    #[test]
+    fn edit_text_in_paragraph() {
+        let (doc, result) = reparse("world", "big wide world");
+        // html > body > div > p
+        assert_eq!(
+            result,
+            ReparseResult::Element {
+                path: NodePath(vec![0, 0, 1, 0])
+            }
+        );
+        let p = doc.query_selector("p.lead").unwrap().unwrap();
+        assert_eq!(p.children().len(), 4);
+    }
+
+    #[test]
+    fn edit_attribute_value() {
+        let (doc, result) = reparse("lead", "intro");
+        // 属性はpの開きタグにあるので、p自身を読み直す
+        assert_eq!(
+            result,
+            ReparseResult::Element {
+                path: NodePath(vec![0, 0, 1, 0])
+            }
+        );
+        assert!(doc.query_selector("p.intro").unwrap().is_some());
+
+        let (_, result) = reparse("two", "2");
+        assert_eq!(
+            result,
+            ReparseResult::Element {
+                path: NodePath(vec![0, 0, 1, 1, 1])
+            }
+        );
+    }
+
+    #[test]
+    fn editing_tags_reparses_everything() {
+        let (doc, result) = reparse("world", "<b>world</b>");
+        assert_eq!(result, ReparseResult::Full);
+        assert!(doc.query_selector("p.lead > b").unwrap().is_some());
+
+        // ">" を消して読めなくなった文書はエラーにして、木はそのまま
+        let mut doc = html::parse(SOURCE).unwrap();
+        let start = SOURCE.find("<ul>").unwrap() + 3;
+        let edit = Edit {
+            range: start..start + 1,
+            text: String::new(),
+        };
+        assert!(doc.reparse_edit(edit, SOURCE).is_err());
+        assert!(doc.query_selector("ul").unwrap().is_some());
+    }
+
+    #[test]
+    fn edit_text_next_to_elements() {
+        // 直接の子のtextやコメントの中なら、その親を読み直す
+        let (_, result) = reparse("tail", "end");
+        assert_eq!(
+            result,
+            ReparseResult::Element {
+                path: NodePath(vec![0, 0, 2])
+            }
+        );
+        let (_, result) = reparse("nav", "menu");
+        assert_eq!(
+            result,
+            ReparseResult::Element {
+                path: NodePath(vec![0, 0])
+            }
+        );
+    }
+
+    #[test]
+    fn edit_inside_svg_and_mathml() {
+        let source = "<body><svg viewBox=\"0 0 10 10\"><g><text>label</text><foreignObject><p>html text</p></foreignObject></g></svg><math><mi>var</mi></math></body>";
+        let options = ParserOptions::default();
+        // <text> はsvgの中なので、svgの要素として読み直す
+        let (doc, result) = reparse_in(source, "label", "new label", &options);
+        assert_eq!(
+            result,
+            ReparseResult::Element {
+                path: NodePath(vec![0, 0, 0, 0])
+            }
+        );
+        let text = doc.node_at(&NodePath(vec![0, 0, 0, 0])).unwrap();
+        assert_eq!(text.namespace(), Some(html::parser::Namespace::Svg));
+        // <foreignObject> の中はHTMLに戻る
+        let (_, result) = reparse_in(source, "html text", "more html", &options);
+        assert_eq!(
+            result,
+            ReparseResult::Element {
+                path: NodePath(vec![0, 0, 0, 1, 0])
+            }
+        );
+        let (doc, result) = reparse_in(source, "var", "value", &options);
+        assert_eq!(
+            result,
+            ReparseResult::Element {
+                path: NodePath(vec![0, 1, 0])
+            }
+        );
+        let mi = doc.node_at(&NodePath(vec![0, 1, 0])).unwrap();
+        assert_eq!(mi.namespace(), Some(html::parser::Namespace::MathMl));
+    }
+
+    #[test]
+    fn slash_does_not_close_html_elements() {
+        // "<div/>" は閉じないので、<p> はdivの子
+        let source = "<main><div/><p>text</p></div><svg><rect/><circle r=\"1\"/></svg></main>";
+        let (_, result) = reparse_in(source, "text", "more text", &ParserOptions::default());
+        assert_eq!(
+            result,
+            ReparseResult::Element {
+                path: NodePath(vec![0, 0, 0])
+            }
+        );
+        // svgの中の "/>" は閉じる
+        let (_, result) = reparse_in(source, "r=\"1\"", "r=\"2\"", &ParserOptions::default());
+        assert_eq!(
+            result,
+            ReparseResult::Element {
+                path: NodePath(vec![0, 1, 1])
+            }
+        );
+    }
+
+    #[test]
+    fn edit_in_lenient_document() {
+        let options = ParserOptions {
+            lenient: true,
+            ..ParserOptions::default()
+        };
+        // 直して読んだところから離れた要素は、その要素だけ読み直す
+        let source = "<div><p>one</span></p><ul><li>two</li></ul></div><b>unclosed";
+        let (_, result) = reparse_in(source, "two", "2", &options);
+        assert_eq!(
+            result,
+            ReparseResult::Element {
+                path: NodePath(vec![0, 1, 0])
+            }
+        );
+        // 直したところを含む要素は、全体で読んだときと違いうるので全体を読み直す
+        let (_, result) = reparse_in(source, "one", "1", &options);
+        assert_eq!(result, ReparseResult::Full);
+        let (_, result) = reparse_in(source, "unclosed", "open", &options);
+        assert_eq!(result, ReparseResult::Full);
+
+        // 書き換えのあとも、ずらした範囲で続けて読み直せる
+        let mut doc = html::parse_with_options(source, &options).unwrap();
+        let result = edit_and_compare(&mut doc, source, "two", "second", &options);
+        assert!(matches!(result, ReparseResult::Element { .. }));
+        let source = source.replace("two", "second");
+        let result = edit_and_compare(&mut doc, &source, "second", "2nd", &options);
+        assert!(matches!(result, ReparseResult::Element { .. }));
+        let source = source.replace("second", "2nd");
+        let result = edit_and_compare(&mut doc, &source, "one", "first", &options);
+        assert_eq!(result, ReparseResult::Full);
+    }
+
+    #[test]
+    fn edit_ranges_outside_the_source_are_errors() {
+        let mut doc = html::parse(SOURCE).unwrap();
+        let before = to_tree_test_format(&doc.children);
+        let start = SOURCE.find("world").unwrap();
+        let len = SOURCE.len();
+        for range in [start..len + 1, start + 2..start, len + 1..len + 2] {
+            let edit = Edit {
+                range: range.clone(),
+                text: "x".to_string(),
+            };
+            let expected = EditRangeError {
+                start: range.start,
+                end: range.end,
+                len,
+            };
+            assert_eq!(edit.apply(SOURCE), Err(expected.clone()));
+            match doc.reparse_edit(edit, SOURCE) {
+                Err(ReparseError::InvalidRange(err)) => assert_eq!(err, expected),
+                other => panic!("unexpected result: {:?}", other),
+            }
+        }
+        // 文字の途中
+        let source = "<p>caf\u{e9}</p>";
+        let mut doc = html::parse(source).unwrap();
+        let e = source.find('\u{e9}').unwrap();
+        let edit = Edit {
+            range: e + 1..e + 2,
+            text: "e".to_string(),
+        };
+        assert!(edit.apply(source).is_err());
+        assert!(matches!(
+            doc.reparse_edit(edit, source),
+            Err(ReparseError::InvalidRange(_))
+        ));
+        assert_eq!(
+            to_tree_test_format(&html::parse(SOURCE).unwrap().children),
+            before
+        );
+    }
+
+    fn token_list(head: Option<Box<Token>>) -> Vec<(TokenKind, String, Position)> {
+        let mut list = Vec::new();
+        let mut tok = head.as_deref();
+        while let Some(t) = tok {
+            list.push((t.kind.clone(), t.s.to_string(), t.pos.clone()));
+            tok = t.next.as_deref();
+        }
+        return list;
+    }
+
+    // 要素の部分を読み直したtoken列は、全体のtoken列のその範囲と同じ (最後のEofを除く)
+    fn assert_retokenized(source: &str, selector: &str, open: &str, close: &str) {
+        let doc = html::parse(source).unwrap();
+        let el = doc.query_selector(selector).unwrap().unwrap();
+        let mut part = token_list(doc.retokenize(&el, source).unwrap());
+        assert_eq!(part.pop().map(|(kind, _, _)| kind), Some(TokenKind::Eof));
+
+        let begin = source.find(open).unwrap();
+        let end = begin + source[begin..].find(close).unwrap() + close.len();
+        let (begin, end) = (
+            source[..begin].chars().count() as u32,
+            source[..end].chars().count() as u32,
+        );
+        let full: Vec<_> = token_list(Tokenizer::new(source).tokenize())
+            .into_iter()
+            .filter(|(_, _, pos)| begin < pos.at_whole && pos.at_whole <= end)
+            .collect();
+        assert!(!full.is_empty());
+        assert_eq!(part, full, "{}", selector);
+    }
+
+    #[test]
+    fn retokenize_element_keeps_absolute_positions() {
+        assert_retokenized(SOURCE, "p.lead", "<p class", "</p>");
+        assert_retokenized(SOURCE, "ul", "<ul>", "</ul>");
+        assert_retokenized(SOURCE, "div", "<div", "</div>");
+        // 行をまたぎ、2byteの文字のあと
+        let source =
+            "<html><body>\n<p>caf\u{e9}</p>\n<div>\n  <b>\u{fc}ber</b>\n</div></body></html>";
+        assert_retokenized(source, "div", "<div>", "</div>");
+        assert_retokenized(source, "b", "<b>", "</b>");
+        // 値の中の改行で行を数え損ねると、その後の位置がずれる
+        let source = "<main>\n<div title=\"first line\nsecond line\"\n  class=\"x\">text</div>\n<p>after</p></main>";
+        assert_retokenized(source, "div", "<div", "</div>");
+        assert_retokenized(source, "p", "<p>", "</p>");
+        let doc = html::parse(source).unwrap();
+        let div = doc.query_selector("div").unwrap().unwrap();
+        let tokens = token_list(doc.retokenize(&div, source).unwrap());
+        // 閉じタグの ">" の行
+        assert_eq!(tokens[tokens.len() - 2].2.line_no, 4);
+    }
+
+    #[test]
+    fn retokenize_rejects_other_nodes_and_sources() {
+        let doc = html::parse(SOURCE).unwrap();
+        let p = doc.query_selector("p.lead").unwrap().unwrap();
+        assert_eq!(
+            doc.retokenize(&p.children()[0], SOURCE).unwrap_err(),
+            RetokenizeError::NotElement
+        );
+        let other = html::parse(SOURCE).unwrap();
+        let other_p = other.query_selector("p.lead").unwrap().unwrap();
+        assert_eq!(
+            doc.retokenize(&other_p, SOURCE).unwrap_err(),
+            RetokenizeError::NotInDocument
+        );
+        assert_eq!(
+            doc.retokenize(&p, "<p>other</p>").unwrap_err(),
+            RetokenizeError::SourceMismatch
+        );
+    }
+}
@@ -0,0 +1,34 @@
+use crate::html::errors::LoadError;
+use std::collections::HashMap;
+
+// 外部のresourceを取ってくるところ。HTTPやfilesystemは使う側が用意する
+pub trait ResourceLoader {
+    fn load(&self, url: &str) -> Result<String, LoadError>;
+}
+
+// 決まったURLにだけ中身を返す。テスト用
+#[derive(Debug, Clone, Default)]
+pub struct MapLoader {
+    pub resources: HashMap<String, String>,
+}
+
+impl MapLoader {
+    pub fn new() -> MapLoader {
+        return MapLoader::default();
+    }
+
+    pub fn insert(&mut self, url: &str, body: &str) {
+        self.resources.insert(url.to_string(), body.to_string());
+    }
+}
+
+impl ResourceLoader for MapLoader {
+    fn load(&self, url: &str) -> Result<String, LoadError> {
+        return match self.resources.get(url) {
+            Some(body) => Ok(body.clone()),
+            None => Err(LoadError::NotFound {
+                url: url.to_string(),
+            }),
+        };
+    }
+}
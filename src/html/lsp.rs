@@ -0,0 +1,98 @@
+use crate::html::position::Position;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+
+// LSPの位置は0始まりの行と、UTF-16のcode unit単位の列
+#[derive(Debug, Clone, PartialEq)]
+pub struct LspPosition {
+    pub line: u32,
+    pub character: u32,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct LspRange {
+    pub start: LspPosition,
+    pub end: LspPosition,
+}
+
+pub struct LineMap {
+    lines: Vec<String>,
+}
+
+impl LineMap {
+    pub fn new(source: &str) -> LineMap {
+        return LineMap {
+            lines: source.split('\n').map(|l| l.to_string()).collect(),
+        };
+    }
+
+    pub fn lsp_position(&self, pos: &Position) -> LspPosition {
+        let line = pos.line_no.saturating_sub(1);
+        let text = match self.lines.get(line as usize) {
+            Some(text) => text.as_str(),
+            None => "",
+        };
+        // Positionの列はchar単位なので、UTF-16に数え直す
+        let character: usize = text
+            .chars()
+            .take(pos.at_line as usize)
+            .map(|c| c.len_utf16())
+            .sum();
+        return LspPosition {
+            line,
+            character: character as u32,
+        };
+    }
+
+    pub fn lsp_range(&self, start: &Position, end: &Position) -> LspRange {
+        return LspRange {
+            start: self.lsp_position(start),
+            end: self.lsp_position(end),
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::html::lsp::{LineMap, LspPosition};
+    use crate::html::position::Position;
+
+    #[test]
+    fn lsp_position_ascii() {
+        let line_map = LineMap::new("<html>\n<body>");
+        assert_eq!(
+            line_map.lsp_position(&Position::new(2, 3, 10)),
+            LspPosition {
+                line: 1,
+                character: 3
+            }
+        );
+    }
+
+    #[test]
+    fn lsp_position_multibyte_and_astral() {
+        // "é" は1 code unit, "😀" は2 code unit
+        let line_map = LineMap::new("<p>\né😀<b>");
+        assert_eq!(
+            line_map.lsp_position(&Position::new(2, 1, 5)),
+            LspPosition {
+                line: 1,
+                character: 1
+            }
+        );
+        assert_eq!(
+            line_map.lsp_position(&Position::new(2, 2, 6)),
+            LspPosition {
+                line: 1,
+                character: 3
+            }
+        );
+        assert_eq!(
+            line_map.lsp_position(&Position::new(2, 5, 9)),
+            LspPosition {
+                line: 1,
+                character: 6
+            }
+        );
+    }
+}
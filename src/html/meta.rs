@@ -0,0 +1,141 @@
+use crate::html::url;
+
+// <meta http-equiv="refresh" content="5; url=/next">
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MetaRefresh {
+    // 秒
+    pub delay: u64,
+    // 書かれたままのURL。Noneなら同じページを読み直す
+    pub url: Option<String>,
+}
+
+impl MetaRefresh {
+    // 次に読むURL。読み直しならbaseそのもの
+    // <base href> を考えるなら Document::base_url を渡す
+    pub fn target(&self, base: &str) -> String {
+        return match &self.url {
+            Some(url) => url::resolve(base, url),
+            None => base.to_string(),
+        };
+    }
+}
+
+fn is_whitespace(c: char) -> bool {
+    return matches!(c, ' ' | '\t' | '\n' | '\x0c' | '\r');
+}
+
+// HTML Standard の shared declarative refresh steps のうち、contentを読むところ
+// 読めなければNone
+pub fn parse_refresh(content: &str) -> Option<MetaRefresh> {
+    let mut rest = content.trim_start_matches(is_whitespace);
+    let digits = rest.len() - rest.trim_start_matches(|c: char| c.is_ascii_digit()).len();
+    if digits == 0 && !rest.starts_with('.') {
+        return None;
+    }
+    // 大きすぎる値は読めるところまでにする
+    let delay = rest[..digits].bytes().fold(0u64, |n, b| {
+        n.saturating_mul(10).saturating_add((b - b'0') as u64)
+    });
+    // 小数点以下は捨てる
+    rest = rest.trim_start_matches(|c: char| c.is_ascii_digit() || c == '.');
+    if rest.is_empty() {
+        return Some(MetaRefresh { delay, url: None });
+    }
+    if !rest.starts_with(|c: char| c == ';' || c == ',' || is_whitespace(c)) {
+        return None;
+    }
+    rest = rest.trim_start_matches(is_whitespace);
+    if let Some(after) = rest.strip_prefix([';', ',']) {
+        rest = after.trim_start_matches(is_whitespace);
+    }
+    if rest.is_empty() {
+        return Some(MetaRefresh { delay, url: None });
+    }
+
+    // "url =" は大文字小文字を区別しない。途中で合わなくなったら、そこからをURLとする
+    for expected in ['u', 'r', 'l'] {
+        match rest.strip_prefix(|c: char| c.to_ascii_lowercase() == expected) {
+            Some(after) => rest = after,
+            None => return Some(quoted_url(delay, rest)),
+        }
+    }
+    rest = rest.trim_start_matches(is_whitespace);
+    match rest.strip_prefix('=') {
+        Some(after) => rest = after.trim_start_matches(is_whitespace),
+        None => return Some(quoted_url(delay, rest)),
+    }
+    return Some(quoted_url(delay, rest));
+}
+
+// 'next.html' や "next.html" の引用符を外す。閉じていなければ最後まで
+fn quoted_url(delay: u64, rest: &str) -> MetaRefresh {
+    let url = match rest.chars().next() {
+        Some(quote @ ('\'' | '"')) => {
+            let body = &rest[1..];
+            match body.find(quote) {
+                Some(end) => &body[..end],
+                None => body,
+            }
+        }
+        _ => rest,
+    };
+    // 空のURLは今のページを指す
+    if url.trim().is_empty() {
+        return MetaRefresh { delay, url: None };
+    }
+    return MetaRefresh {
+        delay,
+        url: Some(url.to_string()),
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::html::meta::{parse_refresh, MetaRefresh};
+
+    #[test]
+    fn refresh_content_variants() {
+        let refresh = |delay: u64, url: Option<&str>| MetaRefresh {
+            delay,
+            url: url.map(|url| url.to_string()),
+        };
+        let table = [
+            ("5; url=/next", Some(refresh(5, Some("/next")))),
+            ("0;URL='next.html'", Some(refresh(0, Some("next.html")))),
+            (
+                "  3 ,  Url = \"a b.html\" trailing",
+                Some(refresh(3, Some("a b.html"))),
+            ),
+            (
+                "1; url='unterminated",
+                Some(refresh(1, Some("unterminated"))),
+            ),
+            ("10", Some(refresh(10, None))),
+            ("2.5;", Some(refresh(2, None))),
+            (".5; url=x", Some(refresh(0, Some("x")))),
+            ("0; next.html", Some(refresh(0, Some("next.html")))),
+            // url= がないときは残り全部
+            ("0; 'quoted.html'", Some(refresh(0, Some("quoted.html")))),
+            // "u" や "ur" までしか合わなければ、その後ろから
+            ("0; ux.html", Some(refresh(0, Some("x.html")))),
+            ("0;url", Some(refresh(0, None))),
+            ("99999999999999999999999", Some(refresh(u64::MAX, None))),
+            ("", None),
+            ("soon", None),
+            ("5x; url=/next", None),
+        ];
+        for (content, expected) in table {
+            assert_eq!(parse_refresh(content), expected, "{:?}", content);
+        }
+    }
+
+    #[test]
+    fn refresh_target() {
+        let base = "https://example.com/a/index.html";
+        assert_eq!(
+            parse_refresh("0; url=../b.html").unwrap().target(base),
+            "https://example.com/b.html"
+        );
+        assert_eq!(parse_refresh("30").unwrap().target(base), base);
+    }
+}
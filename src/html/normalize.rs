@@ -0,0 +1,171 @@
+// 値が決まった語から選ぶ属性を、比べやすい形にそろえる
+// type="TEXT" と type=" text " を同じ "text" にする。href や data-* などの自由な値は触らない
+use crate::html::parser::{Node, NodeData};
+use crate::html::shared_str::SharedStr;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ValueKind {
+    // 1つの語。前後の空白を取って小文字にする
+    Keyword,
+    // 空白で区切った語の並び (rel="Stylesheet  Preload")。語の間の空白も1つにする
+    TokenList,
+}
+
+// (要素, 属性, 値の種類)
+pub static ENUMERATED_ATTRIBUTES: &[(&str, &str, ValueKind)] = &[
+    ("input", "type", ValueKind::Keyword),
+    ("button", "type", ValueKind::Keyword),
+    ("form", "method", ValueKind::Keyword),
+    ("form", "enctype", ValueKind::Keyword),
+    ("form", "autocomplete", ValueKind::Keyword),
+    ("link", "rel", ValueKind::TokenList),
+    ("a", "rel", ValueKind::TokenList),
+    ("img", "loading", ValueKind::Keyword),
+    ("img", "decoding", ValueKind::Keyword),
+    ("iframe", "loading", ValueKind::Keyword),
+    // "module" や "text/javascript"。MIMEの型も大文字小文字を区別しない
+    ("script", "type", ValueKind::Keyword),
+];
+
+pub fn value_kind(element: &str, attribute: &str) -> Option<ValueKind> {
+    return ENUMERATED_ATTRIBUTES
+        .iter()
+        .find(|(el, attr, _)| {
+            el.eq_ignore_ascii_case(element) && attr.eq_ignore_ascii_case(attribute)
+        })
+        .map(|(_, _, kind)| *kind);
+}
+
+pub fn normalize_value(value: &str, kind: ValueKind) -> String {
+    let value = value.trim_matches(|c: char| c.is_ascii_whitespace());
+    return match kind {
+        ValueKind::Keyword => value.to_ascii_lowercase(),
+        ValueKind::TokenList => value
+            .split_ascii_whitespace()
+            .map(|token| token.to_ascii_lowercase())
+            .collect::<Vec<String>>()
+            .join(" "),
+    };
+}
+
+// 書き換えた値の数
+pub(crate) fn normalize_attributes(roots: &mut [Node]) -> usize {
+    let mut changed = 0;
+    let mut stack: Vec<&mut Node> = roots.iter_mut().collect();
+    while let Some(nd) = stack.pop() {
+        if let NodeData::Element {
+            name,
+            attrs,
+            children,
+            ..
+        } = &mut nd.data
+        {
+            for (attr_name, attr_value) in attrs.iter_mut() {
+                let kind = match value_kind(name.as_str(), attr_name) {
+                    Some(kind) => kind,
+                    None => continue,
+                };
+                let value = normalize_value(attr_value, kind);
+                // 元からそろっていればコピーしない
+                if value != attr_value.as_str() {
+                    *attr_value = SharedStr::from(value);
+                    changed += 1;
+                }
+            }
+            stack.extend(children.iter_mut());
+        }
+    }
+    return changed;
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::html;
+    use crate::html::normalize::{normalize_value, value_kind, ValueKind};
+
+    fn normalized(html: &str, selector: &str, attr: &str) -> String {
+        let mut doc = html::parse(html).unwrap();
+        doc.normalize_attributes();
+        let nd = doc.query_selector(selector).unwrap().unwrap();
+        return nd.attr(attr).unwrap().to_string();
+    }
+
+    #[test]
+    fn keywords_are_trimmed_and_lowercased() {
+        assert_eq!(
+            normalized("<input type=\" TEXT \"/>", "input", "type"),
+            "text"
+        );
+        assert_eq!(
+            normalized("<button TYPE=\"Submit\"></button>", "button", "type"),
+            "submit"
+        );
+        assert_eq!(
+            normalized("<form method=\"POST\"></form>", "form", "method"),
+            "post"
+        );
+        assert_eq!(
+            normalized(
+                "<img loading=\"Lazy\" decoding=\"\tASYNC\n\"/>",
+                "img",
+                "loading"
+            ),
+            "lazy"
+        );
+        assert_eq!(
+            normalized("<img decoding=\"\tASYNC\n\"/>", "img", "decoding"),
+            "async"
+        );
+        assert_eq!(
+            normalized("<script type=\"Module\"></script>", "script", "type"),
+            "module"
+        );
+        assert_eq!(
+            normalized(
+                "<script type=\"Text/JavaScript\"></script>",
+                "script",
+                "type"
+            ),
+            "text/javascript"
+        );
+    }
+
+    #[test]
+    fn rel_tokens_are_collapsed() {
+        assert_eq!(
+            normalized(
+                "<link rel=\"  StyleSheet \n PRELOAD \" href=\"a.css\"/>",
+                "link",
+                "rel"
+            ),
+            "stylesheet preload"
+        );
+    }
+
+    #[test]
+    fn free_form_attributes_are_untouched() {
+        let html = "<a href=\"/A B\" title=\" Hello \" data-Foo=\"Bar\" rel=\"NoOpener\"><img alt=\" Cat \"/></a>";
+        let mut doc = html::parse(html).unwrap();
+        assert_eq!(doc.normalize_attributes(), 1);
+        let a = doc.query_selector("a").unwrap().unwrap();
+        assert_eq!(a.attr("href").unwrap(), "/A B");
+        assert_eq!(a.attr("title").unwrap(), " Hello ");
+        assert_eq!(a.attr("data-Foo").unwrap(), "Bar");
+        assert_eq!(a.attr("rel").unwrap(), "noopener");
+        let img = doc.query_selector("img").unwrap().unwrap();
+        assert_eq!(img.attr("alt").unwrap(), " Cat ");
+        // 2回目は何も変えない
+        assert_eq!(doc.normalize_attributes(), 0);
+    }
+
+    #[test]
+    fn table_lookup() {
+        assert_eq!(value_kind("INPUT", "Type"), Some(ValueKind::Keyword));
+        assert_eq!(value_kind("div", "type"), None);
+        assert_eq!(value_kind("input", "value"), None);
+        assert_eq!(
+            normalize_value("\u{a0}text", ValueKind::Keyword),
+            "\u{a0}text"
+        );
+    }
+}
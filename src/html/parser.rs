@@ -1,435 +1,3465 @@
-use crate::html::errors::ParseError;
-use crate::html::tokenizer::{Token, TokenKind};
+use crate::html::arena::{Arena, ArenaBuilder, NodeId};
+use crate::html::atom::Atom;
+use crate::html::attributes::Attributes;
+use crate::html::entity::{self, RefContext};
+use crate::html::errors::{OptionsError, ParseError};
+use crate::html::lsp::{LineMap, LspRange};
+use crate::html::position::Position;
+use crate::html::shared_str::SharedStr;
+use crate::html::tokenizer::{Token, TokenKind, Tokenizer, TokenizerOptions};
+use crate::html::trace::{enter_span, recovery};
+use alloc::borrow::Cow;
+use alloc::boxed::Box;
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::sync::Arc;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt;
+use core::ops::ControlFlow;
+use core::time::Duration;
+#[cfg(feature = "std")]
+use std::sync::mpsc::Receiver;
+#[cfg(feature = "std")]
+use std::time::Instant;
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
 pub enum NodeKind {
-    Tag,
-    SoloTag,
-    CommentTag,
-    DoctypeTag,
+    Element,
     Text,
-    Parameters,
-    Parameter,
-    Identifier,
-    String,
+    Comment,
+    Doctype,
+    Template,
+}
+
+// 属性1つ分
+#[derive(Debug, Clone, PartialEq)]
+pub struct Attribute {
+    pub name: SharedStr,
+    pub value: SharedStr,
+}
+
+// 子はNodeのままVecに並べる (1つずつBoxにしない)
+pub type NodeList = Vec<Node>;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Doctype {
+    pub name: SharedStr,
+}
+
+// 要素の名前空間。<svg> と <math> の中の要素はHTMLとは別の決まりで読む
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Namespace {
+    #[default]
+    Html,
+    Svg,
+    MathMl,
 }
 
 #[derive(Debug, Clone)]
+pub enum NodeData {
+    Element {
+        name: Atom,
+        attrs: Attributes,
+        children: NodeList,
+        // <img/> のように書かれていた
+        self_closing: bool,
+        namespace: Namespace,
+        // parserが読んだ <template> で、childrenはその中身。中身は文書の一部ではないので、
+        // children() など木を辿るAPIからは見えず、template_content() で取る
+        // (Nodeが大きくならないように、別のVecは持たない)
+        holds_template_content: bool,
+    },
+    Text(SharedStr),
+    Comment(SharedStr),
+    Doctype(Doctype),
+    // template_passthroughで読んだ <?php ... ?> など。区切りも含めてそのまま持つ
+    Template(SharedStr),
+}
+
+// Debugは手で書く (大きな木でも画面を埋めないように)
+#[derive(Clone)]
 pub struct Node {
-    pub kind: NodeKind,
-    pub s: String,
-    pub params: Option<Box<Node>>,
-    pub lhs: Option<Box<Node>>,
-    pub rhs: Option<Box<Node>>,
-    pub children: Option<Vec<Option<Box<Node>>>>,
+    pub data: NodeData,
 }
 
-impl Node {
-    pub fn new(
-        kind: NodeKind,
-        params: Option<Box<Node>>,
-        lhs: Option<Box<Node>>,
-        rhs: Option<Box<Node>>,
-        children: Option<Vec<Option<Box<Node>>>>,
-        s: String
-    ) -> Node {
-        return Node {
-            kind,
-            params,
-            lhs,
-            rhs,
-            children,
-            s
+// Debugで見せるtextやコメントの長さ
+const DEBUG_PREVIEW_CHARS: usize = 40;
+
+// 深い木でも再帰しないように、子を自前のスタックに移してから捨てる
+impl Drop for Node {
+    fn drop(&mut self) {
+        // templateの中身も子と同じく移す
+        let mut stack: Vec<Node> = match &mut self.data {
+            NodeData::Element { children, .. } => core::mem::take(children),
+            _ => return,
         };
+        while let Some(mut nd) = stack.pop() {
+            if let NodeData::Element { children, .. } = &mut nd.data {
+                stack.append(children);
+            }
+        }
     }
 }
 
-pub struct Parser {
-    token: Option<Box<Token>>,
+// {:?} は1行の要約 Element("div", 3 attrs, 5 children)
+// {:#?} は子を字下げして並べた木
+impl fmt::Debug for Node {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if !f.alternate() {
+            return f.write_str(&self.debug_summary());
+        }
+        return f.write_str(&debug_tree(core::slice::from_ref(self)));
+    }
 }
 
-impl Parser {
-    pub fn new() -> Parser {
-        return Parser { token: None };
+// 1行に1つずつ、深さに合わせて字下げする
+pub(crate) fn debug_tree(roots: &[Node]) -> String {
+    let mut out = String::new();
+    let mut stack: Vec<(&Node, usize)> = roots.iter().rev().map(|nd| (nd, 0)).collect();
+    while let Some((nd, depth)) = stack.pop() {
+        if !out.is_empty() {
+            out.push('\n');
+        }
+        out += &"  ".repeat(depth);
+        out += &nd.debug_summary();
+        for child in nd.children().iter().rev() {
+            stack.push((child, depth + 1));
+        }
     }
+    return out;
+}
+
+fn count(n: usize, one: &str, many: &str) -> String {
+    return format!("{} {}", n, if n == 1 { one } else { many });
+}
 
-    fn current_token(&self) -> Box<Token> {
-        return self.token.clone().unwrap();
+// 長ければ切って … を付ける
+fn preview(s: &str) -> String {
+    let mut out: String = s
+        .chars()
+        .take(DEBUG_PREVIEW_CHARS)
+        .flat_map(char::escape_debug)
+        .collect();
+    if s.chars().nth(DEBUG_PREVIEW_CHARS).is_some() {
+        out.push('…');
     }
+    return out;
+}
 
-    fn is_eof(&self) -> bool {
-        return self.current_token().kind == TokenKind::Eof;
+impl Node {
+    pub fn new(data: NodeData) -> Node {
+        return Node { data };
     }
 
-    fn consume(&mut self) -> Option<Box<Token>> {
-        let tok = self.current_token();
-        self.token = self.current_token().next;
-        return Some(tok);
+    pub fn element(name: Atom, attrs: Attributes, children: NodeList) -> Node {
+        return Node::new(NodeData::Element {
+            name,
+            attrs,
+            children,
+            self_closing: false,
+            namespace: Namespace::Html,
+            holds_template_content: false,
+        });
     }
 
-    fn consume_kind(&mut self, kind: TokenKind) -> Option<Box<Token>> {
-        if self.current_token().kind == kind {
-            let tok = self.current_token();
-            self.token = self.current_token().next;
-            return Some(tok);
-        }
-        return None;
+    pub fn text(s: SharedStr) -> Node {
+        return Node::new(NodeData::Text(s));
     }
 
-    fn expect_kind(&mut self, kind: TokenKind) -> Result<Option<Box<Token>>, ParseError> {
-        if self.current_token().kind == kind {
-            let cur = self.current_token();
-            self.token = cur.next.clone();
-            return Ok(Some(cur));
-        }
-        return Err(ParseError::UnexpectedToken {
-            expected: kind,
-            found: *self.current_token(),
-        });
+    pub fn comment(s: SharedStr) -> Node {
+        return Node::new(NodeData::Comment(s));
     }
 
-    fn expect_text(&mut self, text: String, case_sensitive: bool) -> Result<(), ParseError> {
-        return match self.expect_kind(TokenKind::Text) {
-            Err(error) => Err(error),
-            Ok(tok) => {
-                if *&case_sensitive && (tok.clone().unwrap().s == text) {
-                    return Ok(());
-                }
-                if *&!case_sensitive && (tok.clone().unwrap().s.to_lowercase() == text) {
-                    return Ok(());
-                }
-                return Err(ParseError::UnexpectedText {
-                    expected: text,
-                    found: tok,
-                });
-            }
-        };
+    pub fn doctype(name: SharedStr) -> Node {
+        return Node::new(NodeData::Doctype(Doctype { name }));
     }
 
-    fn parse_text(&mut self) -> Result<Option<Box<Node>>, ParseError> {
-        let mut text: String = "".to_string();
+    pub fn template(s: SharedStr) -> Node {
+        return Node::new(NodeData::Template(s));
+    }
 
-        while !self.is_eof() {
-            match self.consume_kind(TokenKind::Text) {
-                None => break,
-                Some(tok) => text += &*tok.s,
-            }
-        }
+    pub fn kind(&self) -> NodeKind {
+        return match &self.data {
+            NodeData::Element { .. } => NodeKind::Element,
+            NodeData::Text(_) => NodeKind::Text,
+            NodeData::Comment(_) => NodeKind::Comment,
+            NodeData::Doctype(_) => NodeKind::Doctype,
+            NodeData::Template(_) => NodeKind::Template,
+        };
+    }
 
-        let nd = Node::new(NodeKind::Text, None, None, None, None, text.to_string());
-        return Ok(Some(Box::from(nd)));
+    pub fn is_element(&self) -> bool {
+        return matches!(self.data, NodeData::Element { .. });
     }
 
-    fn parse_decl_tag(&mut self) -> Result<Option<Box<Node>>, ParseError> {
-        // doctype or comment
+    pub fn is_text(&self) -> bool {
+        return matches!(self.data, NodeData::Text(_));
+    }
 
-        // comment
-        if self.consume_kind(TokenKind::Hyphen) != None {
-            match self.expect_kind(TokenKind::Hyphen) {
-                Err(error) => return Err(error),
-                Ok(_) => {
-                    let mut comment: String = "".to_string();
-                    while !self.is_eof() {
-                        if self.consume_kind(TokenKind::Hyphen) != None {
-                            if self.consume_kind(TokenKind::Hyphen) != None {
-                                if self.consume_kind(TokenKind::TagEnd) != None {
-                                    // 終わり
-                                    return Ok(Some(Box::from(Node::new(
-                                        NodeKind::CommentTag,
-                                        None,
-                                        None,
-                                        None,
-                                        None,
-                                        comment,
-                                    ))));
-                                } else {
-                                    comment += "--";
-                                    continue;
-                                }
-                            } else {
-                                comment += "-";
-                                continue;
-                            }
-                        }
+    // 空白だけのtext (preserve_whitespaceで残した改行や字下げ)
+    // 文字のあるtextは最初の文字で止まるので、読み飛ばすために何度呼んでもよい
+    pub fn is_blank(&self) -> bool {
+        return match &self.data {
+            NodeData::Text(s) => s.bytes().all(|b| b.is_ascii_whitespace()),
+            _ => false,
+        };
+    }
 
-                        if self.consume_kind(TokenKind::Whitespace) != None {
-                            comment += " ";
-                            continue;
-                        }
+    // 子のうち要素だけ。間の空白やコメントは飛ばす
+    pub fn element_children(&self) -> impl Iterator<Item = &Node> {
+        return self.children().iter().filter(|nd| nd.is_element());
+    }
 
-                        comment += &*self.consume().unwrap().s
+    // 子孫のtextを文書の順につなぎ、空白を1つにまとめて前後を落とす
+    // 空白だけのtextは中を見ずに区切りとして扱う
+    pub fn text_content_normalized(&self) -> String {
+        let mut text = String::new();
+        // (node, 直前の兄弟もtextか)。語ごとに分けたtextの間には空白を戻す
+        let mut stack: Vec<(&Node, bool)> = vec![(self, false)];
+        while let Some((nd, joined)) = stack.pop() {
+            match &nd.data {
+                NodeData::Text(_) if nd.is_blank() => text.push(' '),
+                NodeData::Text(s) => {
+                    if joined {
+                        text.push(' ');
+                    }
+                    text += s;
+                }
+                NodeData::Element { .. } => {
+                    let children = nd.children();
+                    for (i, child) in children.iter().enumerate().rev() {
+                        stack.push((child, i > 0 && child.is_text() && children[i - 1].is_text()));
                     }
                 }
+                _ => {}
             }
         }
+        return text
+            .split_ascii_whitespace()
+            .collect::<Vec<&str>>()
+            .join(" ");
+    }
 
-        // consume doctype
-        match self.expect_text("doctype".to_string(), false) {
-            Ok(_) => (),
-            Err(err) => return Err(err),
-        }
-        // consume ws
-        match self.expect_kind(TokenKind::Whitespace) {
-            Ok(_) => (),
-            Err(err) => return Err(err),
-        }
+    // 要素の名前。要素でなければNone
+    pub fn name(&self) -> Option<&Atom> {
+        return match &self.data {
+            NodeData::Element { name, .. } => Some(name),
+            _ => None,
+        };
+    }
 
-        // type: eg. html
-        let doctype_node = match self.expect_kind(TokenKind::Text) {
-            Ok(tok) => Node::new(
-                NodeKind::DoctypeTag,
-                None,
-                None,
-                None,
-                None,
-                tok.unwrap().s.to_string().to_lowercase(),
-            ),
-            Err(err) => return Err(err),
+    // テキストとコメントの中身
+    pub fn text_value(&self) -> Option<&SharedStr> {
+        return match &self.data {
+            NodeData::Text(s) | NodeData::Comment(s) => Some(s),
+            _ => None,
         };
+    }
 
-        // consume ">"
-        match self.expect_kind(TokenKind::TagEnd) {
-            Ok(_) => (),
-            Err(err) => return Err(err),
+    pub fn attrs(&self) -> &[Attribute] {
+        return match &self.data {
+            NodeData::Element { attrs, .. } => attrs,
+            _ => &[],
+        };
+    }
+
+    // 属性名は大文字小文字を区別しない
+    pub fn attr(&self, name: &str) -> Option<&SharedStr> {
+        return match &self.data {
+            NodeData::Element { attrs, .. } => attrs.get_ignore_case(name),
+            _ => None,
         };
+    }
 
-        return Ok(Some(Box::from(doctype_node)));
+    // 要素でなければNone
+    pub fn namespace(&self) -> Option<Namespace> {
+        return match &self.data {
+            NodeData::Element { namespace, .. } => Some(*namespace),
+            _ => None,
+        };
     }
 
-    fn parse_tag_parameters(&mut self) -> Result<Option<Box<Node>>, ParseError> {
-        let mut children: Vec<Option<Box<Node>>> = vec![];
+    // parserが読んだ <template> の中身。children() には入っていない
+    pub fn template_content(&self) -> Option<&[Node]> {
+        return match &self.data {
+            NodeData::Element {
+                children,
+                holds_template_content: true,
+                ..
+            } => Some(children),
+            _ => None,
+        };
+    }
 
-        while !self.is_eof() {
-            self.consume_kind(TokenKind::Whitespace);
-            // ">" or "/" がきたら中止
-            // 最後の処理はtag_bodyに任せるので、consumeしない
-            if self.current_token().kind == TokenKind::TagEnd || self.current_token().kind == TokenKind::Slash {
-                break;
-            }
-            // whitespace あるかも
-            self.consume_kind(TokenKind::Whitespace);
+    pub fn children(&self) -> &[Node] {
+        return match &self.data {
+            NodeData::Element {
+                children,
+                holds_template_content: false,
+                ..
+            } => children,
+            _ => &[],
+        };
+    }
 
-            // param = value
-            // param
-            let param_name = match self.consume_kind(TokenKind::Text) {
-                Some(tok) => tok,
+    pub fn children_mut(&mut self) -> Option<&mut NodeList> {
+        return match &mut self.data {
+            NodeData::Element {
+                children,
+                holds_template_content: false,
+                ..
+            } => Some(children),
+            _ => None,
+        };
+    }
+
+    pub(crate) fn debug_summary(&self) -> String {
+        return match &self.data {
+            NodeData::Element {
+                name,
+                attrs,
+                children,
+                ..
+            } => format!(
+                "Element({:?}, {}, {})",
+                name.as_str(),
+                count(attrs.len(), "attr", "attrs"),
+                count(children.len(), "child", "children")
+            ),
+            NodeData::Text(s) => format!("Text(\"{}\")", preview(s)),
+            NodeData::Comment(s) => format!("Comment(\"{}\")", preview(s)),
+            NodeData::Doctype(doctype) => format!("Doctype({:?})", doctype.name.as_str()),
+            NodeData::Template(s) => format!("Template(\"{}\")", preview(s)),
+        };
+    }
+
+    // parserを直すとき用。属性もtextもすべて省かずに書く
+    pub fn debug_full(&self) -> String {
+        let mut out = String::new();
+        // (node, 深さ) か、要素の閉じ
+        let mut stack: Vec<(Option<&Node>, usize)> = vec![(Some(self), 0)];
+        while let Some((nd, depth)) = stack.pop() {
+            let indent = "    ".repeat(depth);
+            let nd = match nd {
+                Some(nd) => nd,
                 None => {
-                    return Err(ParseError::UnexpectedToken {
-                        expected: TokenKind::Text,
-                        found: *self.current_token(),
-                    })
+                    out += &format!("{}    ],\n{}}},\n", indent, indent);
+                    continue;
                 }
             };
-            // =
-            match self.expect_kind(TokenKind::Assign) {
-                Ok(_) => {}
-                Err(err) => return Err(err),
-            }
-            // value maybe string
-            let value: Token;
-            match self.expect_kind(TokenKind::String) {
-                Ok(v) => value = *v.unwrap(),
-                Err(err) => return Err(err),
+            match &nd.data {
+                NodeData::Element {
+                    name,
+                    attrs,
+                    children,
+                    self_closing,
+                    namespace,
+                    holds_template_content,
+                } => {
+                    out += &format!("{}Element {{\n", indent);
+                    out += &format!("{}    name: {:?},\n", indent, name.as_str());
+                    out += &format!("{}    namespace: {:?},\n", indent, namespace);
+                    out += &format!("{}    attrs: [\n", indent);
+                    for attr in attrs {
+                        out += &format!(
+                            "{}        {:?}: {:?},\n",
+                            indent,
+                            attr.name.as_str(),
+                            attr.value.as_str()
+                        );
+                    }
+                    out += &format!("{}    ],\n", indent);
+                    out += &format!("{}    self_closing: {},\n", indent, self_closing);
+                    let field = match holds_template_content {
+                        true => "template_content",
+                        false => "children",
+                    };
+                    out += &format!("{}    {}: [\n", indent, field);
+                    stack.push((None, depth));
+                    for child in children.iter().rev() {
+                        stack.push((Some(child), depth + 2));
+                    }
+                }
+                NodeData::Text(s) => out += &format!("{}Text({:?}),\n", indent, s.as_str()),
+                NodeData::Comment(s) => out += &format!("{}Comment({:?}),\n", indent, s.as_str()),
+                NodeData::Doctype(doctype) => {
+                    out += &format!("{}Doctype({:?}),\n", indent, doctype.name.as_str())
+                }
+                NodeData::Template(s) => out += &format!("{}Template({:?}),\n", indent, s.as_str()),
             }
-
-            let lhs = Node::new(NodeKind::Identifier, None, None, None, None, param_name.s);
-            let rhs = Node::new(NodeKind::String, None, None, None, None, value.s);
-
-            children.push(Some(Box::from(Node::new(
-                NodeKind::Parameter,
-                Some(Box::from(lhs)),
-                Some(Box::from(rhs)),
-                None,
-                None,
-                "".to_string(),
-            ))));
-
-            self.consume_kind(TokenKind::Whitespace);
         }
-
-        if children.len() == 0 {
-            return Ok(None);
+        // 一番外側の後ろには "," を付けない
+        if out.ends_with(",\n") {
+            out.truncate(out.len() - 2);
         }
-
-        return Ok(Some(Box::from(Node::new(
-            NodeKind::Parameters,
-            None,
-            None,
-            None,
-            Some(children),
-            "".to_string(),
-        ))));
+        return out;
     }
 
-    fn parse_tag(&mut self) -> Result<Option<Box<Node>>, ParseError> {
-        if self.consume_kind(TokenKind::Excl) != None {
-            return self.parse_decl_tag();
+    // 自分と子孫の数 (属性は数えない。<template> の中身は数える)
+    pub fn count_nodes(&self) -> usize {
+        let mut count = 0;
+        let mut stack: Vec<&Node> = vec![self];
+        while let Some(nd) = stack.pop() {
+            count += 1;
+            stack.extend(nd.template_content().unwrap_or(nd.children()));
         }
+        return count;
+    }
+}
 
-        if self.current_token().kind == TokenKind::Slash {
-            return Ok(None);
-        }
+// parse_tagなどが読んだ1つ分。木のどこに置くかはSinkが決める
+pub(crate) enum Item {
+    Open(Atom, Attributes, Namespace),
+    Solo(Atom, Attributes, Namespace),
+    Text(SharedStr),
+    Comment(SharedStr),
+    Doctype(SharedStr),
+    Template(SharedStr),
+}
 
-        let tag_name = match self.expect_kind(TokenKind::Text) {
-            Ok(tok) => tok.unwrap().s.to_lowercase(),
-            Err(err) => return Err(err),
-        };
+// 木の作り方。parse_はnodeを読む順番と親子関係だけを決める
+pub(crate) trait Sink {
+    type Handle;
+    fn create(&mut self, item: Item) -> Self::Handle;
+    // 閉じた要素やテキストを、開いている一番内側のタグ (なければ最上位) に付ける
+    fn append(
+        &mut self,
+        open_tags: &mut [(Atom, Self::Handle)],
+        child: Self::Handle,
+    ) -> ControlFlow<()>;
+}
 
-        // wsが入っている確率が高いので消しておく
-        self.consume_kind(TokenKind::Whitespace);
+// HTMLの <template> なら、子を中身として持つ
+fn is_template(name: &Atom, namespace: Namespace) -> bool {
+    return namespace == Namespace::Html && *name == "template";
+}
 
-        // parameters
-        let params = match self.parse_tag_parameters() {
-            Ok(nd) => nd,
-            Err(err) => return Err(err),
-        };
+// 読んだnodeを親に付ける。<template> の中身もchildrenのVecに入れる
+fn append_parsed(parent: &mut Node, child: Node) {
+    if let NodeData::Element { children, .. } = &mut parent.data {
+        children.push(child);
+    }
+}
 
-        // wsが入っている確率が高いので消しておく
-        self.consume_kind(TokenKind::Whitespace);
+fn item_to_node(item: Item) -> Node {
+    return match item {
+        Item::Open(name, attrs, namespace) => Node::new(NodeData::Element {
+            holds_template_content: is_template(&name, namespace),
+            name,
+            attrs,
+            children: NodeList::new(),
+            self_closing: false,
+            namespace,
+        }),
+        Item::Solo(name, attrs, namespace) => Node::new(NodeData::Element {
+            holds_template_content: is_template(&name, namespace),
+            name,
+            attrs,
+            children: NodeList::new(),
+            self_closing: true,
+            namespace,
+        }),
+        Item::Text(s) => Node::text(s),
+        Item::Comment(s) => Node::comment(s),
+        Item::Doctype(s) => Node::doctype(s),
+        Item::Template(s) => Node::template(s),
+    };
+}
 
-        // Solo tag
-        if self.consume_kind(TokenKind::Slash) != None {
-            return match self.expect_kind(TokenKind::TagEnd) {
-                Ok(_) => Ok(Some(Box::from(Node::new(
-                    NodeKind::SoloTag, params, None, None, None, tag_name,
-                )))),
-                Err(err) => Err(err),
-            };
-        }
+// parserが読んだものから、好きな形の木を作る。Parser::parse_into_sinkに渡す
+// parserはnodeを読んだ順に作り、要素は閉じたときに親へappendする
+pub trait TreeSink {
+    type Handle;
+    // self_closingは <br/> のように "/>" で閉じていたか
+    fn create_element(&mut self, name: Atom, attrs: Attributes, self_closing: bool)
+        -> Self::Handle;
+    // <svg> や <math> の中の要素。既定では名前空間を捨ててcreate_elementに渡す
+    fn create_foreign_element(
+        &mut self,
+        name: Atom,
+        attrs: Attributes,
+        self_closing: bool,
+        _namespace: Namespace,
+    ) -> Self::Handle {
+        return self.create_element(name, attrs, self_closing);
+    }
+    fn create_text(&mut self, text: SharedStr) -> Self::Handle;
+    fn create_comment(&mut self, text: SharedStr) -> Self::Handle;
+    // template_passthroughで区切りごと読んだもの。既定ではtextとして扱う
+    fn create_template(&mut self, source: SharedStr) -> Self::Handle {
+        return self.create_text(source);
+    }
+    // <!DOCTYPE name>。木に置くならhandleを返す。ほかのnodeと同じくappendに渡る
+    fn set_doctype(&mut self, name: SharedStr) -> Option<Self::Handle>;
+    // parentがNoneなら最上位。Breakを返せば、そこで読むのをやめる
+    fn append(&mut self, parent: Option<&mut Self::Handle>, child: Self::Handle)
+        -> ControlFlow<()>;
+    // 読めないところを直したり飛ばしたりしたこと。parseの終わりに、見つけた順に渡す
+    fn parse_error(&mut self, _warning: &ParseWarning) {}
+}
 
-        // ">"
-        match self.expect_kind(TokenKind::TagEnd) {
-            Ok(_) => {}
-            Err(err) => return Err(err),
-        }
+// TreeSinkをparse_に渡すための包み。doctypeを木に置かないsinkではhandleがない
+struct TreeSinkAdapter<'a, T: TreeSink>(&'a mut T);
 
-        let children: Option<Vec<Option<Box<Node>>>> = match self.parse_() {
-            Ok(c) => c,
-            Err(err) => return Err(err),
-        };
+impl<'a, T: TreeSink> Sink for TreeSinkAdapter<'a, T> {
+    type Handle = Option<T::Handle>;
 
-        // "/" of close tag
-        match self.expect_kind(TokenKind::Slash) {
-            Ok(_) => {}
-            Err(err) => return Err(err),
+    fn create(&mut self, item: Item) -> Option<T::Handle> {
+        return match item {
+            Item::Open(name, attrs, Namespace::Html) => {
+                Some(self.0.create_element(name, attrs, false))
+            }
+            Item::Solo(name, attrs, Namespace::Html) => {
+                Some(self.0.create_element(name, attrs, true))
+            }
+            Item::Open(name, attrs, namespace) => {
+                Some(self.0.create_foreign_element(name, attrs, false, namespace))
+            }
+            Item::Solo(name, attrs, namespace) => {
+                Some(self.0.create_foreign_element(name, attrs, true, namespace))
+            }
+            Item::Text(s) => Some(self.0.create_text(s)),
+            Item::Comment(s) => Some(self.0.create_comment(s)),
+            Item::Doctype(s) => self.0.set_doctype(s),
+            Item::Template(s) => Some(self.0.create_template(s)),
         };
+    }
 
-        // closing tag name
-        let close_tag_name = match self.expect_kind(TokenKind::Text) {
-            Ok(tok) => tok.unwrap().s.to_lowercase(),
-            Err(err) => return Err(err),
+    fn append(
+        &mut self,
+        open_tags: &mut [(Atom, Option<T::Handle>)],
+        child: Option<T::Handle>,
+    ) -> ControlFlow<()> {
+        let child = match child {
+            Some(child) => child,
+            None => return ControlFlow::Continue(()),
         };
+        let parent = open_tags.last_mut().and_then(|(_, parent)| parent.as_mut());
+        return self.0.append(parent, child);
+    }
+}
 
-        match self.expect_kind(TokenKind::TagEnd) {
-            Ok(_) => {}
-            Err(err) => return Err(err),
-        }
+// parseで使う、Nodeの木
+struct Tree {
+    nodes: Vec<Node>,
+}
 
-        // tag miss match: eg. <xxx></yyy>
-        if tag_name.clone() != close_tag_name.clone() {
-            return Err(ParseError::TagMissMatch {
-                open: tag_name,
-                close: close_tag_name,
-            });
-        }
+impl TreeSink for Tree {
+    type Handle = Node;
 
-        return Ok(Some(Box::from(Node::new(
-            NodeKind::Tag,
-            params,
-            None,
-            None,
-            children,
-            tag_name.to_string(),
-        ))));
+    fn create_element(&mut self, name: Atom, attrs: Attributes, self_closing: bool) -> Node {
+        return self.create_foreign_element(name, attrs, self_closing, Namespace::Html);
     }
 
-    fn parse_(&mut self) -> Result<Option<Vec<Option<Box<Node>>>>, ParseError> {
-        let mut nodes: Vec<Option<Box<Node>>> = Vec::new();
-        while !self.is_eof() {
-            self.consume_kind(TokenKind::Whitespace);
-            let nd_result = match self.consume_kind(TokenKind::TagBegin) {
-                Some(_) => self.parse_tag(),
-                None => self.parse_text(),
-            };
-            // テキスト、あるいはタグのパースは成功しましたか?
-            match nd_result {
-                Ok(nd) => match nd {
-                    Some(n) => nodes.push(Some(n)),
-                    None => break,
-                },
-                Err(err) => return Err(err),
-            }
-            self.consume_kind(TokenKind::Whitespace);
-        }
+    fn create_foreign_element(
+        &mut self,
+        name: Atom,
+        attrs: Attributes,
+        self_closing: bool,
+        namespace: Namespace,
+    ) -> Node {
+        return Node::new(NodeData::Element {
+            holds_template_content: is_template(&name, namespace),
+            name,
+            attrs,
+            children: NodeList::new(),
+            self_closing,
+            namespace,
+        });
+    }
 
-        if nodes.len() == 0 {
-            return Ok(None);
-        }
+    fn create_text(&mut self, text: SharedStr) -> Node {
+        return Node::text(text);
+    }
 
-        return Ok(Some(nodes));
+    fn create_comment(&mut self, text: SharedStr) -> Node {
+        return Node::comment(text);
     }
 
-    pub fn parse(
-        &mut self,
-        token: Option<Box<Token>>,
-    ) -> Result<Option<Vec<Option<Box<Node>>>>, ParseError> {
-        self.token = Some(token.unwrap());
-        match self.parse_() {
-            Ok(n) => return Ok(n),
-            Err(err) => return Err(err),
+    fn create_template(&mut self, source: SharedStr) -> Node {
+        return Node::template(source);
+    }
+
+    // Documentにするときに、最上位の最初のものをdoctypeに移す
+    fn set_doctype(&mut self, name: SharedStr) -> Option<Node> {
+        return Some(Node::doctype(name));
+    }
+
+    fn append(&mut self, parent: Option<&mut Node>, child: Node) -> ControlFlow<()> {
+        match parent {
+            Some(parent) => append_parsed(parent, child),
+            None => self.nodes.push(child),
         }
+        return ControlFlow::Continue(());
     }
 }
 
-#[cfg(test)]
-mod test {
-    use crate::html::parser::Parser;
-    use crate::html::tokenizer;
-    #[test]
-    fn parse_only_decl() {
-        let mut tokenizer_ = tokenizer::Tokenizer::new("<!doctype html><!-- hello, w--orld -->");
-        let tok = tokenizer_.tokenize();
+// parse_streamingでどのnodeを渡すか
+pub enum StreamTarget {
+    // <body>直下のnode (テキストやコメントも含む)
+    BodyChildren,
+    // この名前の要素。入れ子になっている場合は一番外側だけ
+    Elements(Atom),
+}
 
-        let mut parser_ = Parser::new();
-        let nodes = parser_.parse(tok);
-        println!("{:#?}", nodes)
-    }
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct StreamStats {
+    pub emitted: usize,
+    // parserが同時に抱えていたnodeの数の最大
+    pub peak_retained: usize,
+}
 
-    #[test]
-    fn parse_html_tag() {
-        let mut tokenizer_ = tokenizer::Tokenizer::new("<html></html>");
-        let tok = tokenizer_.tokenize();
+struct Stream<'a> {
+    target: &'a StreamTarget,
+    f: &'a mut dyn FnMut(Node) -> ControlFlow<()>,
+    retained: usize,
+    stats: StreamStats,
+}
 
-        let mut parser_ = Parser::new();
-        let nodes = parser_.parse(tok);
-        println!("{:#?}", nodes)
+impl<'a> Stream<'a> {
+    fn retain(&mut self, n: usize) {
+        self.retained += n;
+        if self.retained > self.stats.peak_retained {
+            self.stats.peak_retained = self.retained;
+        }
     }
 
-    #[test]
-    fn parse_html_body() {
-        let mut tokenizer_ = tokenizer::Tokenizer::new("<html><body></body></html>");
-        let tok = tokenizer_.tokenize();
-
-        let mut parser_ = Parser::new();
-        let nodes = parser_.parse(tok);
-        println!("{:#?}", nodes)
+    fn wants(&self, open_tags: &[(Atom, Node)], nd: &Node) -> bool {
+        return match self.target {
+            StreamTarget::BodyChildren => match open_tags.last() {
+                Some((parent, _)) => *parent == "body",
+                None => false,
+            },
+            StreamTarget::Elements(name) => {
+                nd.name() == Some(name) && !open_tags.iter().any(|(open, _)| open == name)
+            }
+        };
     }
+}
 
-    #[test]
-    fn parse_html_body_h1_img() {
-        let html = "<!DOCTYPE html> \
-            <html>\
-            <body>\
-            <h1>hello</h1>\
-            <img src=\"https://google.com\"/>\
-            </body>\
-            </html>";
+impl<'a> Sink for Stream<'a> {
+    type Handle = Node;
 
-        let mut tokenizer_ = tokenizer::Tokenizer::new(html);
-        let tok = tokenizer_.tokenize();
+    fn create(&mut self, item: Item) -> Node {
+        self.retain(1);
+        return item_to_node(item);
+    }
+
+    fn append(&mut self, open_tags: &mut [(Atom, Node)], child: Node) -> ControlFlow<()> {
+        // 渡したnodeはここで手放す
+        if self.wants(open_tags, &child) {
+            self.retained -= child.count_nodes();
+            self.stats.emitted += 1;
+            return (self.f)(child);
+        }
+        match open_tags.last_mut() {
+            Some((_, parent)) => append_parsed(parent, child),
+            // 最上位に残ったものは誰も見ないので捨てる
+            None => self.retained -= child.count_nodes(),
+        }
+        return ControlFlow::Continue(());
+    }
+}
+
+// lenientで直してよい数の既定。ふつうの崩れたページはここまで行かない
+pub const DEFAULT_MAX_ERRORS: usize = 200;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParserOptions {
+    // 閉じタグの食い違いや閉じ忘れで止めずに、閉じて読み進める
+    pub lenient: bool,
+    // 空白もtextに残す。tokenizerのkeep_whitespaceが要る
+    pub preserve_whitespace: bool,
+    // これより深い要素があればエラーにする
+    pub max_depth: Option<usize>,
+    // 読めないところを飛ばしたことをwarningsに残す
+    pub collect_warnings: bool,
+    // scriptを実行するブラウザと同じく、<noscript> の中身をtextとして読む
+    // 既定ではJSを動かさないので、中身を要素として読む (tokenizerのraw_noscriptが要る)
+    pub scripting_enabled: bool,
+    // <acronym> のような古い名前を、LEGACY_NAMESの今の名前に置き換える
+    pub map_legacy_names: bool,
+    // 読んだ量とかかった時間をParser::metricsに残す
+    pub collect_metrics: bool,
+    // 属性の名前を書かれたままにする。既定ではHTMLの要素の属性だけ小文字にする
+    // (<svg> と <math> の中は viewBox のように大文字を区別するので、もとから残す)
+    pub preserve_attribute_case: bool,
+    // <my-widget> のような自作の要素の開きタグで呼び、中身の読み方を決める
+    pub on_element: Option<ElementHook>,
+    // <div/> をXMLのように中身のない要素として閉じる
+    // 既定ではブラウザと同じく "/" を無視し、<div> として後ろを子にする
+    // (voidの要素と、<svg> <math> の中は、どちらでも "/>" で閉じる)
+    pub xml_self_closing: bool,
+    // lenientで、直したエラーがこれより多くなったら読むのをやめてTooManyErrorsにする
+    // 古い名前や "<div/>" のような、誤りではないwarningは数えない
+    // HTMLとして送られたbinaryのような入力で、直しが延々と続かないように。Noneなら止めない
+    pub max_errors: Option<usize>,
+}
+
+impl Default for ParserOptions {
+    fn default() -> ParserOptions {
+        return ParserOptions {
+            lenient: false,
+            preserve_whitespace: false,
+            max_depth: None,
+            collect_warnings: false,
+            scripting_enabled: false,
+            map_legacy_names: false,
+            collect_metrics: false,
+            preserve_attribute_case: false,
+            on_element: None,
+            xml_self_closing: false,
+            max_errors: Some(DEFAULT_MAX_ERRORS),
+        };
+    }
+}
+
+// on_elementが返す、自作の要素の読み方
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ElementDisposition {
+    // ふつうの要素として子要素を読む
+    Normal,
+    // <script> と同じく、閉じタグまでを一つのtextとして読む
+    RawText,
+    // <br> と同じく、閉じタグを待たない
+    Void,
+}
+
+type ElementHookFn = dyn Fn(&str, &[Attribute]) -> ElementDisposition + Send + Sync;
+
+// 要素の名前と属性を受け取る関数。ParserOptionsを複製しても同じ関数を指す
+#[derive(Clone)]
+pub struct ElementHook(Arc<ElementHookFn>);
+
+impl ElementHook {
+    pub fn new<F>(hook: F) -> ElementHook
+    where
+        F: Fn(&str, &[Attribute]) -> ElementDisposition + Send + Sync + 'static,
+    {
+        return ElementHook(Arc::new(hook));
+    }
+
+    pub fn call(&self, name: &str, attrs: &[Attribute]) -> ElementDisposition {
+        return (self.0)(name, attrs);
+    }
+}
+
+impl fmt::Debug for ElementHook {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        return f.write_str("ElementHook(..)");
+    }
+}
+
+// 同じ関数を指しているときだけ等しい
+impl PartialEq for ElementHook {
+    fn eq(&self, other: &ElementHook) -> bool {
+        return Arc::ptr_eq(&self.0, &other.0);
+    }
+}
+
+impl Eq for ElementHook {}
+
+// map_legacy_namesで置き換える名前。意味の変わらないものだけにする
+// (<image> は img の古い綴り、<acronym> は abbr、<strike> は s に置き換えられた)
+// (古い名前, 今の名前, warning)
+pub static LEGACY_NAMES: &[(&str, &str, &str)] = &[
+    ("image", "img", "legacy element <image> renamed to <img>"),
+    (
+        "acronym",
+        "abbr",
+        "legacy element <acronym> renamed to <abbr>",
+    ),
+    ("strike", "s", "legacy element <strike> renamed to <s>"),
+];
+
+fn legacy_name(name: &str) -> Option<(&'static str, &'static str)> {
+    return LEGACY_NAMES
+        .iter()
+        .find(|(legacy, _, _)| legacy.eq_ignore_ascii_case(name))
+        .map(|(_, modern, message)| (*modern, *message));
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParseWarning {
+    pub message: &'static str,
+    // 読んでいたタグやテキストの始まり
+    pub start: Position,
+    // 気づいたところ
+    pub pos: Position,
+}
+
+impl ParseWarning {
+    pub fn lsp_range(&self, line_map: &LineMap) -> LspRange {
+        return line_map.lsp_range(&self.start, &self.pos);
+    }
+}
+
+// collect_metricsのときの、直前のparseの数字
+// parse_strで読んだときだけinput_bytesとtokenize_durationが入る (token列を渡されたときは0)
+// 時間はstdのときだけ測る。no_stdでは0
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ParseMetrics {
+    pub input_bytes: usize,
+    // Eofも数える
+    pub token_count: usize,
+    // 最上位のdoctypeも数える
+    pub node_count: usize,
+    // 要素がいちばん深く入れ子になったところ。最上位の要素が1
+    pub max_depth: usize,
+    // collect_warningsでなくても数える
+    pub warning_count: usize,
+    pub tokenize_duration: Duration,
+    pub parse_duration: Duration,
+}
+
+#[cfg(feature = "std")]
+struct Timer(Instant);
+
+#[cfg(feature = "std")]
+impl Timer {
+    fn start() -> Timer {
+        return Timer(Instant::now());
+    }
+
+    fn elapsed(&self) -> Duration {
+        return self.0.elapsed();
+    }
+}
+
+#[cfg(not(feature = "std"))]
+struct Timer;
+
+#[cfg(not(feature = "std"))]
+impl Timer {
+    fn start() -> Timer {
+        return Timer;
+    }
+
+    fn elapsed(&self) -> Duration {
+        return Duration::ZERO;
+    }
+}
+
+fn count_tokens(token: &Option<Box<Token>>) -> usize {
+    let mut count = 0;
+    let mut cur = token.as_deref();
+    while let Some(tok) = cur {
+        count += 1;
+        cur = tok.next.as_deref();
+    }
+    return count;
+}
+
+// (nodeの数, 要素のいちばん深い入れ子)
+fn tree_shape(roots: &[Node]) -> (usize, usize) {
+    let mut count = 0;
+    let mut max_depth = 0;
+    let mut stack: Vec<(&Node, usize)> = roots.iter().map(|nd| (nd, 1)).collect();
+    while let Some((nd, depth)) = stack.pop() {
+        count += 1;
+        if nd.is_element() {
+            max_depth = max_depth.max(depth);
+        }
+        stack.extend(nd.children().iter().map(|child| (child, depth + 1)));
+    }
+    return (count, max_depth);
+}
+
+fn is_foreign_root(name: &str) -> bool {
+    return name.eq_ignore_ascii_case("svg") || name.eq_ignore_ascii_case("math");
+}
+
+// ndの子を読むときの名前空間。文書の途中から読み直すときに使う
+#[cfg(feature = "std")]
+pub(crate) fn children_namespace(nd: &Node) -> Namespace {
+    let namespace = nd.namespace().unwrap_or(Namespace::Html);
+    return match nd.name() {
+        Some(name) => foreign_boundary(name.as_str(), namespace).unwrap_or(namespace),
+        None => namespace,
+    };
+}
+
+// parentの中に開いた要素の名前空間。<svg> と <math> から外はHTMLに戻らない
+fn element_namespace(name: &str, parent: Namespace) -> Namespace {
+    if parent != Namespace::Html {
+        return parent;
+    }
+    if name.eq_ignore_ascii_case("svg") {
+        return Namespace::Svg;
+    }
+    if name.eq_ignore_ascii_case("math") {
+        return Namespace::MathMl;
+    }
+    return Namespace::Html;
+}
+
+// 中の名前空間が変わる要素なら、中の名前空間。<foreignObject> の中はまたHTMLになる
+fn foreign_boundary(name: &str, namespace: Namespace) -> Option<Namespace> {
+    if namespace == Namespace::Svg && name.eq_ignore_ascii_case("foreignObject") {
+        return Some(Namespace::Html);
+    }
+    if namespace != Namespace::Html && is_foreign_root(name) {
+        return Some(namespace);
+    }
+    return None;
+}
+
+// 閉じタグは大文字小文字を区別せずに開きタグと対応させる (foreignの名前は書かれたまま持つので)
+fn same_tag(open: &Atom, close: &Atom) -> bool {
+    if open == close {
+        return true;
+    }
+    if open.is_known() && close.is_known() {
+        return false;
+    }
+    return open.as_str().eq_ignore_ascii_case(close.as_str());
+}
+
+// lenientで、閉じタグが内側の要素を追い越したときに開き直す要素
+// specのformatting elementsに、よく同じ使われ方をするspanを足したもの
+const FORMATTING_ELEMENTS: [&str; 15] = [
+    "a", "b", "big", "code", "em", "font", "i", "nobr", "s", "small", "span", "strike", "strong",
+    "tt", "u",
+];
+
+fn is_formatting(name: &str) -> bool {
+    return FORMATTING_ELEMENTS.contains(&name);
+}
+
+// </b> が閉じる <b> より内側にある書式の要素と、その属性 (外側から順に)
+// specのadoption agencyを簡単にしたもの。<b><i>x</b>y</i> は <b><i>x</i></b><i>y</i> になる
+// 閉じタグに対応する開きタグの位置。<template> の中は断片として読むので、外の要素は閉じない
+fn matching_open<H>(open_tags: &[(Atom, H)], close_tag_name: &Atom) -> Option<usize> {
+    for (at, (name, _)) in open_tags.iter().enumerate().rev() {
+        if same_tag(name, close_tag_name) {
+            return Some(at);
+        }
+        if *name == "template" {
+            return None;
+        }
+    }
+    return None;
+}
+
+fn misnested<H>(
+    open_tags: &[(Atom, H)],
+    formatting: &[(usize, Attributes)],
+    close_tag_name: &Atom,
+) -> Vec<(Atom, Attributes)> {
+    let at = match matching_open(open_tags, close_tag_name) {
+        Some(at) => at,
+        None => return Vec::new(),
+    };
+    return formatting
+        .iter()
+        .filter(|(i, _)| *i > at)
+        .map(|(i, attrs)| (open_tags[*i].0.clone(), attrs.clone()))
+        .collect();
+}
+
+// Parser::builder().lenient().max_depth(256).parse(html)
+#[derive(Debug, Clone, Default)]
+pub struct ParserBuilder {
+    options: ParserOptions,
+    // 指定されなければ、parserのoptionsに合わせる
+    tokenizer: Option<TokenizerOptions>,
+}
+
+impl ParserBuilder {
+    pub fn lenient(mut self) -> ParserBuilder {
+        self.options.lenient = true;
+        return self;
+    }
+
+    pub fn preserve_whitespace(mut self) -> ParserBuilder {
+        self.options.preserve_whitespace = true;
+        return self;
+    }
+
+    // まとめて置き換える。ParserOptionsを持ち回すとき用
+    pub fn options(mut self, options: ParserOptions) -> ParserBuilder {
+        self.options = options;
+        return self;
+    }
+
+    pub fn scripting_enabled(mut self, enabled: bool) -> ParserBuilder {
+        self.options.scripting_enabled = enabled;
+        return self;
+    }
+
+    pub fn max_depth(mut self, max_depth: usize) -> ParserBuilder {
+        self.options.max_depth = Some(max_depth);
+        return self;
+    }
+
+    pub fn max_errors(mut self, max_errors: usize) -> ParserBuilder {
+        self.options.max_errors = Some(max_errors);
+        return self;
+    }
+
+    pub fn map_legacy_names(mut self) -> ParserBuilder {
+        self.options.map_legacy_names = true;
+        return self;
+    }
+
+    pub fn preserve_attribute_case(mut self) -> ParserBuilder {
+        self.options.preserve_attribute_case = true;
+        return self;
+    }
+
+    pub fn xml_self_closing(mut self) -> ParserBuilder {
+        self.options.xml_self_closing = true;
+        return self;
+    }
+
+    pub fn on_element<F>(mut self, hook: F) -> ParserBuilder
+    where
+        F: Fn(&str, &[Attribute]) -> ElementDisposition + Send + Sync + 'static,
+    {
+        self.options.on_element = Some(ElementHook::new(hook));
+        return self;
+    }
+
+    pub fn collect_warnings(mut self, collect: bool) -> ParserBuilder {
+        self.options.collect_warnings = collect;
+        return self;
+    }
+
+    pub fn collect_metrics(mut self, collect: bool) -> ParserBuilder {
+        self.options.collect_metrics = collect;
+        return self;
+    }
+
+    pub fn tokenizer(mut self, options: TokenizerOptions) -> ParserBuilder {
+        self.tokenizer = Some(options);
+        return self;
+    }
+
+    pub fn build(self) -> Result<Parser, OptionsError> {
+        if self.options.max_depth == Some(0) {
+            return Err(OptionsError::ZeroMaxDepth);
+        }
+        let tokenizer_options = match self.tokenizer {
+            Some(tokenizer) if self.options.preserve_whitespace && !tokenizer.keep_whitespace => {
+                return Err(OptionsError::Incompatible {
+                    option: "preserve_whitespace",
+                    conflicts_with: "a tokenizer without keep_whitespace",
+                    reason: "the tokenizer drops the whitespace the parser would keep",
+                });
+            }
+            Some(tokenizer) if self.options.scripting_enabled && !tokenizer.raw_noscript => {
+                return Err(OptionsError::Incompatible {
+                    option: "scripting_enabled",
+                    conflicts_with: "a tokenizer without raw_noscript",
+                    reason: "the tokenizer reads markup inside <noscript>",
+                });
+            }
+            Some(tokenizer) => tokenizer,
+            None => TokenizerOptions {
+                keep_whitespace: self.options.preserve_whitespace,
+                raw_noscript: self.options.scripting_enabled,
+                ..TokenizerOptions::default()
+            },
+        };
+        let mut parser = Parser::new();
+        parser.options = self.options;
+        parser.tokenizer_options = tokenizer_options;
+        return Ok(parser);
+    }
+
+    pub fn parse(self, html: &str) -> Result<Option<Vec<Node>>, ParseError> {
+        let mut parser = self.build()?;
+        return parser.parse_str(html);
+    }
+}
+
+// <!-- の後のcommentの読み方 (specの comment start state から comment end bang state まで)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum CommentState {
+    Start,
+    StartDash,
+    Comment,
+    EndDash,
+    End,
+    EndBang,
+}
+
+enum CommentStep {
+    // pushを足し、keep_tokenならtokenの中身も足す
+    Next {
+        state: CommentState,
+        push: &'static str,
+        keep_token: bool,
+    },
+    // commentが終わった。specでparse errorになる終わり方ならwarningがある
+    Close {
+        warning: Option<&'static str>,
+    },
+}
+
+// tokenを1つ読んだあと。incrementalも同じ終わりを見つけるのに使う
+fn comment_step(state: CommentState, kind: &TokenKind) -> CommentStep {
+    use CommentState::*;
+    let next = |state, push, keep_token| CommentStep::Next {
+        state,
+        push,
+        keep_token,
+    };
+    return match (state, kind) {
+        // <!--> と <!--->
+        (Start | StartDash, TokenKind::TagEnd) => CommentStep::Close {
+            warning: Some("abrupt closing of empty comment"),
+        },
+        (Start, TokenKind::Hyphen) => next(StartDash, "", false),
+        (StartDash, TokenKind::Hyphen) => next(End, "", false),
+        (StartDash, _) => next(Comment, "-", true),
+        (Comment, TokenKind::Hyphen) => next(EndDash, "", false),
+        (EndDash, TokenKind::Hyphen) => next(End, "", false),
+        (EndDash, _) => next(Comment, "-", true),
+        (End, TokenKind::TagEnd) => CommentStep::Close { warning: None },
+        (End, TokenKind::Excl) => next(EndBang, "", false),
+        // ---> の最初の - は中身
+        (End, TokenKind::Hyphen) => next(End, "-", false),
+        // 途中の -- はそのまま中身
+        (End, _) => next(Comment, "--", true),
+        (EndBang, TokenKind::Hyphen) => next(EndDash, "--!", false),
+        // --!> でも閉じる
+        (EndBang, TokenKind::TagEnd) => CommentStep::Close {
+            warning: Some("incorrectly closed comment"),
+        },
+        (EndBang, _) => next(Comment, "--!", true),
+        (Start | Comment, _) => next(Comment, "", true),
+    };
+}
+
+fn push_comment_token(comment: &mut String, tok: &Token) {
+    match tok.kind.symbol() {
+        Some(c) => comment.push(c),
+        // 空白は1つにまとめる (keep_whitespaceならそのまま)
+        None if tok.kind == TokenKind::Whitespace && tok.s.is_empty() => comment.push(' '),
+        None => *comment += &tok.s,
+    }
+}
+
+// lenientのとき、タグの外でtextとして読むtoken。"<" はタグを始めるので入れない
+fn is_stray_in_text(kind: &TokenKind) -> bool {
+    return *kind == TokenKind::String || (kind.symbol().is_some() && *kind != TokenKind::TagBegin);
+}
+
+// コメントと違い、文字列は引用符ごと戻す
+fn push_raw_token(text: &mut String, tok: &Token) {
+    if tok.kind != TokenKind::String {
+        push_comment_token(text, tok);
+        return;
+    }
+    let quote = if tok.s.contains('"') { '\'' } else { '"' };
+    text.push(quote);
+    *text += &tok.s;
+    text.push(quote);
+}
+
+// 閉じタグを読んだ結果
+enum Closed<H> {
+    Element(H),
+    // lenientで、対応する開きタグがなかった
+    Ignored,
+    // Sinkがもう要らないと言った
+    Stopped,
+}
+
+// 要素の開きタグの "<" から閉じタグの ">" まで。開きタグの順に並べる
+// 開き直した要素にはstartがなく、閉じタグなしで閉じた要素にはendがない
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct ElementSpan {
+    // 開いたときのopen_tagsの長さ
+    depth: usize,
+    pub(crate) start: Option<Position>,
+    pub(crate) end: Option<Position>,
+}
+
+pub struct Parser {
+    token: Option<Box<Token>>,
+    pos: Position,
+    // parse_nodesで今読んでいるタグやテキストの始まり。warningの範囲に使う
+    item_start: Position,
+    // 直前のコメントの長さ。次のコメントのバッファの大きさの目安にする
+    comment_hint: usize,
+    // parse_pipelinedのとき、tokenizerのthreadから届く続きのtoken列
+    #[cfg(feature = "std")]
+    batches: Option<Receiver<Box<Token>>>,
+    options: ParserOptions,
+    tokenizer_options: TokenizerOptions,
+    warnings: Vec<ParseWarning>,
+    warning_count: usize,
+    // warningのうち、入力の誤りを直して読んだもの。max_errorsと比べる
+    error_count: usize,
+    metrics: Option<ParseMetrics>,
+    // 最上位の最初のdoctypeの "<" と ">" の位置。Documentに移したあとも場所を示せるように
+    doctype_span: Option<(Position, Position)>,
+    // 最上位のnodeを、この名前空間の要素の中として読む
+    context_namespace: Namespace,
+    // record_element_spansのときだけ残す
+    element_spans: Option<Vec<ElementSpan>>,
+}
+
+impl Default for Parser {
+    fn default() -> Parser {
+        return Parser::new();
+    }
+}
+
+impl Parser {
+    pub fn new() -> Parser {
+        return Parser {
+            token: None,
+            pos: Position::new(1, 0, 0),
+            item_start: Position::new(1, 0, 0),
+            comment_hint: 0,
+            #[cfg(feature = "std")]
+            batches: None,
+            options: ParserOptions::default(),
+            tokenizer_options: TokenizerOptions::default(),
+            warnings: Vec::new(),
+            warning_count: 0,
+            error_count: 0,
+            metrics: None,
+            doctype_span: None,
+            context_namespace: Namespace::Html,
+            element_spans: None,
+        };
+    }
+
+    pub fn builder() -> ParserBuilder {
+        return ParserBuilder::default();
+    }
+
+    pub fn options(&self) -> &ParserOptions {
+        return &self.options;
+    }
+
+    // 直前のparseで飛ばしたところ。collect_warningsのときだけ残す
+    pub fn warnings(&self) -> &[ParseWarning] {
+        return &self.warnings;
+    }
+
+    // 直前のparseかparse_strの数字。collect_metricsでないか、エラーで止まったときはNone
+    pub fn metrics(&self) -> Option<&ParseMetrics> {
+        return self.metrics.as_ref();
+    }
+
+    // Documentはstdのときだけある
+    #[cfg(feature = "std")]
+    pub(crate) fn doctype_span(&self) -> Option<(Position, Position)> {
+        return self.doctype_span.clone();
+    }
+
+    // 次のparseから要素の範囲を残す
+    #[cfg(feature = "std")]
+    pub(crate) fn record_element_spans(&mut self) {
+        self.element_spans = Some(Vec::new());
+    }
+
+    #[cfg(feature = "std")]
+    pub(crate) fn take_element_spans(&mut self) -> Vec<ElementSpan> {
+        return self.element_spans.take().unwrap_or_default();
+    }
+
+    // 直前のparseで、直して読んだエラーの数
+    #[cfg(feature = "std")]
+    pub(crate) fn error_count(&self) -> usize {
+        return self.error_count;
+    }
+
+    fn open_span(&mut self, depth: usize, start: Option<Position>, end: Option<Position>) {
+        if let Some(spans) = &mut self.element_spans {
+            spans.push(ElementSpan { depth, start, end });
+        }
+    }
+
+    // depthで開いている要素は、そのdepthで最後に開いたもの
+    fn close_span(&mut self, depth: usize) {
+        if let Some(spans) = &mut self.element_spans {
+            if let Some(span) = spans.iter_mut().rev().find(|span| span.depth == depth) {
+                span.end = Some(self.pos.clone());
+            }
+        }
+    }
+
+    // 入力の誤りを直して読んだ。warningとして残し、max_errorsの分として数える
+    fn recover(&mut self, message: &'static str) {
+        // 上限を超えた1つ目までは残す
+        let over_budget = self.over_error_budget();
+        self.error_count += 1;
+        self.push_warning(message, over_budget);
+    }
+
+    // 古い名前や自己終了の書き方のように、誤りではないが知らせておくもの
+    fn warn(&mut self, message: &'static str) {
+        let over_budget = self.over_error_budget();
+        self.push_warning(message, over_budget);
+    }
+
+    // 上限を超えた後は、読むのをやめるまで数えるだけ
+    fn push_warning(&mut self, message: &'static str, over_budget: bool) {
+        recovery!(
+            line = self.pos.line_no,
+            column = self.pos.at_line,
+            "{}",
+            message
+        );
+        self.warning_count += 1;
+        if self.options.collect_warnings && !over_budget {
+            self.warnings.push(ParseWarning {
+                message,
+                start: self.item_start.clone(),
+                pos: self.pos.clone(),
+            });
+        }
+    }
+
+    // 開きタグのときだけwarningを残す
+    fn map_legacy_name(&mut self, name: Atom, open: bool) -> Atom {
+        if !self.options.map_legacy_names {
+            return name;
+        }
+        let (modern, message) = match legacy_name(name.as_str()) {
+            Some(found) => found,
+            None => return name,
+        };
+        if open {
+            self.warn(message);
+        }
+        return Atom::new(modern);
+    }
+
+    // strictは最初のエラーで止まるので、lenientのときだけ数える
+    fn over_error_budget(&self) -> bool {
+        return self.options.lenient
+            && self
+                .options
+                .max_errors
+                .is_some_and(|max_errors| self.error_count > max_errors);
+    }
+
+    fn check_depth(&self, depth: usize) -> Result<(), ParseError> {
+        return match self.options.max_depth {
+            Some(max_depth) if depth > max_depth => Err(ParseError::TooDeep {
+                max_depth,
+                start: self.item_start.clone(),
+                pos: self.pos.clone(),
+            }),
+            _ => Ok(()),
+        };
+    }
+
+    fn current_token(&self) -> Result<Box<Token>, ParseError> {
+        return match &self.token {
+            // nextは辿らない (残り全部をcloneしてしまうので)
+            Some(tok) => Ok(Box::new(Token::new(
+                tok.kind.clone(),
+                tok.pos.clone(),
+                tok.s.clone(),
+            ))),
+            // tokenizerは必ずEofで終わるので、ここには来ないはず
+            None => Err(ParseError::Internal {
+                detail: "token stream ended without an Eof token",
+                pos: self.pos.clone(),
+            }),
+        };
+    }
+
+    fn current_kind(&self) -> TokenKind {
+        return match &self.token {
+            Some(tok) => tok.kind.clone(),
+            None => TokenKind::Eof,
+        };
+    }
+
+    fn is_eof(&self) -> bool {
+        return self.current_kind() == TokenKind::Eof;
+    }
+
+    fn consume(&mut self) -> Option<Box<Token>> {
+        return match self.token.take() {
+            Some(mut tok) => {
+                self.token = tok.next.take();
+                if self.token.is_none() {
+                    self.token = self.next_batch();
+                }
+                self.pos = tok.pos.clone();
+                Some(tok)
+            }
+            None => None,
+        };
+    }
+
+    #[cfg(feature = "std")]
+    fn next_batch(&mut self) -> Option<Box<Token>> {
+        return match &self.batches {
+            // 送る側が終わっていればNone
+            Some(rx) => rx.recv().ok(),
+            None => None,
+        };
+    }
+
+    // threadがないので、token列は最初に渡されたものだけ
+    #[cfg(not(feature = "std"))]
+    fn next_batch(&mut self) -> Option<Box<Token>> {
+        return None;
+    }
+
+    fn consume_kind(&mut self, kind: TokenKind) -> Option<Box<Token>> {
+        if self.current_kind() == kind {
+            return self.consume();
+        }
+        return None;
+    }
+
+    fn expect_kind(&mut self, kind: TokenKind) -> Result<Box<Token>, ParseError> {
+        if self.current_kind() == kind {
+            if let Some(tok) = self.consume() {
+                return Ok(tok);
+            }
+        }
+        return match self.current_token() {
+            Ok(tok) => Err(ParseError::UnexpectedToken {
+                expected: kind,
+                found: tok,
+                start: self.pos.clone(),
+            }),
+            Err(err) => Err(err),
+        };
+    }
+
+    fn expect_one_of(&mut self, kinds: &[TokenKind]) -> Result<Box<Token>, ParseError> {
+        if kinds.contains(&self.current_kind()) {
+            if let Some(tok) = self.consume() {
+                return Ok(tok);
+            }
+        }
+        return match self.current_token() {
+            Ok(tok) => Err(ParseError::UnexpectedTokenOneOf {
+                expected: kinds.to_vec(),
+                found: tok,
+                start: self.pos.clone(),
+            }),
+            Err(err) => Err(err),
+        };
+    }
+
+    fn expect_text(&mut self, text: String, case_sensitive: bool) -> Result<(), ParseError> {
+        let start = self.pos.clone();
+        return match self.expect_kind(TokenKind::Text) {
+            Err(error) => Err(error),
+            Ok(tok) => {
+                if case_sensitive && (tok.s == text) {
+                    return Ok(());
+                }
+                if !case_sensitive && tok.s.eq_ignore_ascii_case(&text) {
+                    return Ok(());
+                }
+                return Err(ParseError::UnexpectedText {
+                    expected: text,
+                    found: Some(tok),
+                    start,
+                });
+            }
+        };
+    }
+
+    fn parse_text(&mut self) -> Result<Option<Item>, ParseError> {
+        let mut text: SharedStr = SharedStr::default();
+        // lenientで、記号をtextに戻したか。戻したら文字参照もここで戻す
+        let mut stray = false;
+        // & のほかの記号や引用符があったか。&amp; のような参照だけなら知らせない
+        let mut noted = false;
+
+        while !self.is_eof() {
+            let tok = match self.consume_kind(TokenKind::Text) {
+                Some(tok) => tok,
+                None if self.options.lenient && is_stray_in_text(&self.current_kind()) => {
+                    let tok = match self.consume() {
+                        Some(tok) => tok,
+                        None => break,
+                    };
+                    let mut raw = String::new();
+                    push_raw_token(&mut raw, &tok);
+                    text = text.concat(&SharedStr::from(raw));
+                    stray = true;
+                    noted |= tok.kind != TokenKind::Amp;
+                    continue;
+                }
+                None if self.options.preserve_whitespace => {
+                    match self.consume_kind(TokenKind::Whitespace) {
+                        Some(tok) => tok,
+                        None => break,
+                    }
+                }
+                None => break,
+            };
+            // タグの外の引用符は、tokenizerが1文字のtextにしている
+            noted |= self.options.lenient && (tok.s == "'" || tok.s == "\"");
+            text = text.concat(&tok.s);
+        }
+
+        if noted || (stray && entity::has_bare_amp(&text)) {
+            self.warn("markup character in text; read it as text");
+        }
+        if stray {
+            if let Cow::Owned(decoded) = entity::decode(&text, RefContext::Text) {
+                text = SharedStr::from(decoded);
+            }
+        }
+
+        return Ok(Some(Item::Text(text)));
+    }
+
+    fn parse_decl_tag(&mut self) -> Result<Option<Item>, ParseError> {
+        // doctype or comment
+
+        // comment
+        if self.consume_kind(TokenKind::Hyphen).is_some() {
+            return match self.expect_kind(TokenKind::Hyphen) {
+                Err(error) => Err(error),
+                Ok(_) => self.parse_comment(),
+            };
+        }
+
+        // consume doctype
+        match self.expect_text("doctype".to_string(), false) {
+            Ok(_) => (),
+            Err(err) => return Err(err),
+        }
+        // consume ws
+        match self.expect_kind(TokenKind::Whitespace) {
+            Ok(_) => (),
+            Err(err) => return Err(err),
+        }
+
+        // type: eg. html
+        let doctype: SharedStr = match self.expect_kind(TokenKind::Text) {
+            Ok(tok) => tok.s.to_ascii_lowercase(),
+            Err(err) => return Err(err),
+        };
+
+        // consume ">"
+        match self.expect_kind(TokenKind::TagEnd) {
+            Ok(_) => (),
+            Err(err) => return Err(err),
+        };
+
+        return Ok(Some(Item::Doctype(doctype)));
+    }
+
+    // <!-- の後。終わりはspecのcomment stateと同じに決める
+    fn parse_comment(&mut self) -> Result<Option<Item>, ParseError> {
+        let mut comment: String = String::with_capacity(self.comment_hint);
+        let mut state = CommentState::Start;
+        loop {
+            if self.is_eof() {
+                self.recover("end of input in comment");
+                break;
+            }
+            let tok = match self.consume() {
+                Some(tok) => tok,
+                None => break,
+            };
+            match comment_step(state, &tok.kind) {
+                CommentStep::Next {
+                    state: next,
+                    push,
+                    keep_token,
+                } => {
+                    comment += push;
+                    if keep_token {
+                        push_comment_token(&mut comment, &tok);
+                    }
+                    state = next;
+                }
+                CommentStep::Close { warning } => {
+                    if let Some(message) = warning {
+                        self.recover(message);
+                    }
+                    break;
+                }
+            }
+        }
+        self.comment_hint = comment.len();
+        return Ok(Some(Item::Comment(comment.into())));
+    }
+
+    // http-equiv や my-widget のように - でつながった名前
+    fn hyphenated(&mut self, first: &SharedStr) -> SharedStr {
+        let mut name = first.clone();
+        while self.consume_kind(TokenKind::Hyphen).is_some() {
+            let mut joined = name.as_str().to_string();
+            joined.push('-');
+            if let Some(tok) = self.consume_kind(TokenKind::Text) {
+                joined += &tok.s;
+            }
+            name = SharedStr::from(joined);
+        }
+        return name;
+    }
+
+    fn parse_tag_parameters(&mut self, lowercase: bool) -> Result<Attributes, ParseError> {
+        let mut attrs: Attributes = Attributes::new();
+
+        while !self.is_eof() {
+            self.consume_kind(TokenKind::Whitespace);
+            // ">" or "/" がきたら中止
+            // 最後の処理はtag_bodyに任せるので、consumeしない
+            if self.current_kind() == TokenKind::TagEnd || self.current_kind() == TokenKind::Slash {
+                break;
+            }
+            // whitespace あるかも
+            self.consume_kind(TokenKind::Whitespace);
+
+            // <div class="a""b"> や <img "src"="x"> のように、名前のところに来た文字列
+            // lenientなら捨てて次の属性を読む。"=値" が続けばそれも捨てる
+            if self.options.lenient && self.current_kind() == TokenKind::String {
+                self.consume();
+                self.recover("stray quoted text in tag; skipped");
+                if self.consume_kind(TokenKind::Assign).is_some() {
+                    self.consume_kind(TokenKind::String);
+                }
+                continue;
+            }
+
+            // param = value
+            // param
+            let param_name = match self.consume_kind(TokenKind::Text) {
+                Some(tok) => tok,
+                None => {
+                    // 名前の代わりにタグの終わりが来てもよい
+                    return match self.current_token() {
+                        Ok(tok) => Err(ParseError::UnexpectedTokenOneOf {
+                            expected: vec![TokenKind::Text, TokenKind::TagEnd, TokenKind::Slash],
+                            found: tok,
+                            start: self.pos.clone(),
+                        }),
+                        Err(err) => Err(err),
+                    };
+                }
+            };
+            let mut name = self.hyphenated(&param_name.s);
+            if lowercase {
+                name = name.to_ascii_lowercase();
+            }
+            // = の前後には空白があってもよい
+            self.consume_kind(TokenKind::Whitespace);
+            // <input disabled> の値のない属性。lenientなら空の値にする
+            if self.options.lenient
+                && self.current_kind() != TokenKind::Assign
+                && self.current_kind() != TokenKind::String
+            {
+                self.warn("attribute without a value; read it as empty");
+                attrs.push(Attribute {
+                    name,
+                    value: SharedStr::default(),
+                });
+                continue;
+            }
+            match self.expect_one_of(&[TokenKind::Whitespace, TokenKind::Assign]) {
+                Ok(_) => {}
+                Err(err) => return Err(err),
+            }
+            self.consume_kind(TokenKind::Whitespace);
+            // value maybe string
+            let value = match self.current_kind() {
+                TokenKind::String => self.expect_kind(TokenKind::String)?.s.clone(),
+                _ if self.options.lenient => self.unquoted_value(),
+                _ => self.expect_kind(TokenKind::String)?.s.clone(),
+            };
+
+            // 文字参照を戻す。なければtokenの文字列をそのまま使う
+            let value = match entity::decode(&value, RefContext::Attribute) {
+                Cow::Borrowed(_) => value,
+                Cow::Owned(decoded) => SharedStr::from(decoded),
+            };
+            attrs.push(Attribute { name, value });
+
+            self.consume_kind(TokenKind::Whitespace);
+        }
+
+        return Ok(attrs);
+    }
+
+    // <img src=x> の引用符のない値。空白か ">" の手前までを戻してつなぐ
+    fn unquoted_value(&mut self) -> SharedStr {
+        self.warn("unquoted attribute value");
+        let mut value = String::new();
+        while !self.is_eof()
+            && self.current_kind() != TokenKind::Whitespace
+            && self.current_kind() != TokenKind::TagEnd
+        {
+            match self.consume() {
+                Some(tok) => push_raw_token(&mut value, &tok),
+                None => break,
+            }
+        }
+        return SharedStr::from(value);
+    }
+
+    // parentは、開いているタグから見た今の名前空間
+    fn parse_tag(&mut self, parent: Namespace) -> Result<Option<Item>, ParseError> {
+        enter_span!(
+            TRACE,
+            "parse_tag",
+            name = %self.token.as_ref().map_or("", |tok| tok.s.as_str()),
+            line = self.pos.line_no,
+            column = self.pos.at_line
+        );
+        if self.consume_kind(TokenKind::Excl).is_some() {
+            if parent != Namespace::Html && self.at_cdata() {
+                return self.parse_cdata();
+            }
+            return self.parse_decl_tag();
+        }
+
+        if self.current_kind() == TokenKind::Slash {
+            return Ok(None);
+        }
+
+        let in_foreign = parent != Namespace::Html;
+        let tag_name = match self.expect_kind(TokenKind::Text) {
+            Ok(tok) if in_foreign => Atom::from_shared_exact(&self.hyphenated(&tok.s)),
+            Ok(tok) => Atom::from_shared(&self.hyphenated(&tok.s)),
+            Err(err) => return Err(err),
+        };
+        // <svg> の中の <image> はSVGの要素なので置き換えない
+        let tag_name = match in_foreign {
+            true => tag_name,
+            false => self.map_legacy_name(tag_name, true),
+        };
+
+        // wsが入っている確率が高いので消しておく
+        self.consume_kind(TokenKind::Whitespace);
+
+        // parameters
+        let namespace = element_namespace(tag_name.as_str(), parent);
+        let foreign = namespace != Namespace::Html;
+        let lowercase = !foreign && !self.options.preserve_attribute_case;
+        let attrs = self.parse_tag_parameters(lowercase)?;
+
+        // wsが入っている確率が高いので消しておく
+        self.consume_kind(TokenKind::Whitespace);
+
+        // "/" or ">"
+        let tag_close = self.expect_one_of(&[TokenKind::Slash, TokenKind::TagEnd])?;
+
+        // Solo tag
+        if tag_close.kind == TokenKind::Slash {
+            self.expect_kind(TokenKind::TagEnd)?;
+            if foreign || tag_name.is_void() {
+                return Ok(Some(Item::Solo(tag_name, attrs, namespace)));
+            }
+            if self.options.xml_self_closing {
+                self.warn("self-closing syntax on a non-void element; closed it");
+                return Ok(Some(Item::Solo(tag_name, attrs, namespace)));
+            }
+            self.warn("self-closing syntax on a non-void element; ignored the slash");
+            return Ok(Some(Item::Open(tag_name, attrs, namespace)));
+        }
+
+        // <br> のように "/" のない空要素。閉じタグは来ないので、ここで閉じる
+        if self.options.lenient && !foreign && tag_name.is_void() {
+            self.warn("void element without a closing slash; closed it");
+            return Ok(Some(Item::Solo(tag_name, attrs, namespace)));
+        }
+
+        // 子要素と閉じタグはparse_で読む
+        return Ok(Some(Item::Open(tag_name, attrs, namespace)));
+    }
+
+    // "<!" の後ろが "[" か。"[CDATA[" はtokenが分かれるので、続きはparse_cdataで見る
+    fn at_cdata(&self) -> bool {
+        return self
+            .token
+            .as_ref()
+            .is_some_and(|tok| tok.kind == TokenKind::Text && tok.s == "[");
+    }
+
+    // <svg> や <math> の中の <![CDATA[ ... ]]>。中身をそのままtextにする
+    // "[CDATA[" でなければ、specと同じく ">" までをコメントにする
+    fn parse_cdata(&mut self) -> Result<Option<Item>, ParseError> {
+        const OPEN: &str = "[CDATA[";
+        let mut text = String::new();
+        while !self.is_eof() {
+            let tok = match self.consume() {
+                Some(tok) => tok,
+                None => break,
+            };
+            push_raw_token(&mut text, &tok);
+            if tok.kind != TokenKind::TagEnd {
+                continue;
+            }
+            if !text.starts_with(OPEN) {
+                self.recover("malformed CDATA section; read it as a comment");
+                text.pop();
+                return Ok(Some(Item::Comment(text.into())));
+            }
+            if text.ends_with("]]>") {
+                text.truncate(text.len() - "]]>".len());
+                return Ok(Some(Item::Text(text[OPEN.len()..].into())));
+            }
+        }
+        self.recover("end of input in CDATA section");
+        let body = text.get(OPEN.len()..).unwrap_or("");
+        return Ok(Some(Item::Text(body.into())));
+    }
+
+    // foreignなら、開きタグと同じく名前を書かれたまま読む
+    fn parse_close_tag(&mut self, foreign: bool) -> Result<Atom, ParseError> {
+        // "/" of close tag
+        match self.expect_kind(TokenKind::Slash) {
+            Ok(_) => {}
+            Err(err) => return Err(err),
+        };
+
+        // closing tag name
+        let close_tag_name = match self.expect_kind(TokenKind::Text) {
+            Ok(tok) if foreign => Atom::from_shared_exact(&self.hyphenated(&tok.s)),
+            Ok(tok) => Atom::from_shared(&self.hyphenated(&tok.s)),
+            Err(err) => return Err(err),
+        };
+        // 開きタグと同じ名前にしないと対応が取れない
+        let close_tag_name = match foreign {
+            true => close_tag_name,
+            false => self.map_legacy_name(close_tag_name, false),
+        };
+
+        match self.expect_kind(TokenKind::TagEnd) {
+            Ok(_) => {}
+            Err(err) => return Err(err),
+        }
+
+        return Ok(close_tag_name);
+    }
+
+    fn parse_<S: Sink>(&mut self, sink: &mut S) -> Result<(), ParseError> {
+        enter_span!(DEBUG, "parse");
+        self.warnings.clear();
+        self.warning_count = 0;
+        self.error_count = 0;
+        self.metrics = None;
+        self.doctype_span = None;
+        if let Some(spans) = &mut self.element_spans {
+            spans.clear();
+        }
+        let result = self.parse_nodes(sink);
+        #[cfg(feature = "tracing")]
+        if let Err(err) = &result {
+            tracing::warn!(error = %err, "parse stopped");
+        }
+        return result;
+    }
+
+    // 深い文書でもスタックを使い切らないように、開いているタグは自前のスタックで持つ
+    fn parse_nodes<S: Sink>(&mut self, sink: &mut S) -> Result<(), ParseError> {
+        let mut open_tags: Vec<(Atom, S::Handle)> = Vec::new();
+        // 開いているforeign_boundaryの要素の、open_tagsでの位置と中の名前空間
+        // 深い文書で毎回open_tagsをたどらないように、別に持つ
+        let mut boundaries: Vec<(usize, Namespace)> = Vec::new();
+        // lenientのとき、開いている書式の要素 (<b> など) の位置と属性。開き直すときに使う
+        let mut formatting: Vec<(usize, Attributes)> = Vec::new();
+        // <b><i>x</b>y</i> の </b> で閉じた <i>。次に中身が来たら開き直す
+        let mut reopen: Vec<(Atom, Attributes)> = Vec::new();
+        // 開きタグの、open_tagsでの位置と "<" から ">" までの範囲。閉じタグと合わないときに示す
+        let mut open_ranges: Vec<(usize, (Position, Position))> = Vec::new();
+        let preserve_whitespace = self.options.preserve_whitespace;
+
+        while !self.is_eof() {
+            if self.over_error_budget() {
+                break;
+            }
+            if !preserve_whitespace {
+                self.consume_kind(TokenKind::Whitespace);
+            }
+            if self.is_eof() {
+                break;
+            }
+            self.item_start = self.pos.clone();
+            // 閉じたものを除く。次にpushするまで、open_tagsは短くならない
+            while boundaries
+                .last()
+                .is_some_and(|(at, _)| *at >= open_tags.len())
+            {
+                boundaries.pop();
+            }
+            while formatting
+                .last()
+                .is_some_and(|(at, _)| *at >= open_tags.len())
+            {
+                formatting.pop();
+            }
+            while open_ranges
+                .last()
+                .is_some_and(|(at, _)| *at >= open_tags.len())
+            {
+                open_ranges.pop();
+            }
+            if !reopen.is_empty() && self.current_kind() != TokenKind::TagBegin {
+                self.reopen_formatting(sink, &mut open_tags, &mut formatting, &mut reopen)?;
+            }
+
+            let namespace = boundaries
+                .last()
+                .map_or(self.context_namespace, |(_, namespace)| *namespace);
+            let nd_result = match self.consume_kind(TokenKind::TagBegin) {
+                Some(begin) => {
+                    if self.current_kind() == TokenKind::Slash {
+                        // 対応する開きタグがない閉じタグは読まずに終わる
+                        if open_tags.is_empty() && !self.options.lenient {
+                            self.recover("close tag without an open tag; stop reading");
+                            break;
+                        }
+                        let foreign = namespace != Namespace::Html;
+                        let close_tag_name = self.parse_close_tag(foreign)?;
+                        // まだ開き直していない要素の閉じタグは、開き直さずに済ませる
+                        if let Some(i) =
+                            reopen.iter().rposition(|(name, _)| *name == close_tag_name)
+                        {
+                            reopen.remove(i);
+                            continue;
+                        }
+                        reopen.clear();
+                        if self.options.lenient && is_formatting(close_tag_name.as_str()) {
+                            reopen = misnested(&open_tags, &formatting, &close_tag_name);
+                        }
+                        match self.close_tag(sink, &mut open_tags, &open_ranges, close_tag_name) {
+                            Ok(Closed::Element(open)) => Ok(Some(open)),
+                            Ok(Closed::Ignored) => continue,
+                            Ok(Closed::Stopped) => return Ok(()),
+                            Err(err) => return Err(err),
+                        }
+                    } else {
+                        if !reopen.is_empty() {
+                            self.reopen_formatting(
+                                sink,
+                                &mut open_tags,
+                                &mut formatting,
+                                &mut reopen,
+                            )?;
+                        }
+                        match self.parse_tag(namespace) {
+                            Ok(Some(Item::Open(name, attrs, namespace))) => {
+                                self.check_depth(open_tags.len() + 1)?;
+                                let disposition = self.element_disposition(&name, &attrs);
+                                let depth = open_tags.len();
+                                let start = Some(self.item_start.clone());
+                                if disposition == ElementDisposition::Void {
+                                    self.open_span(depth, start, Some(self.pos.clone()));
+                                    Ok(Some(sink.create(Item::Solo(name, attrs, namespace))))
+                                } else {
+                                    if let Some(inner) = foreign_boundary(name.as_str(), namespace)
+                                    {
+                                        boundaries.push((open_tags.len(), inner));
+                                    }
+                                    if self.options.lenient
+                                        && namespace == Namespace::Html
+                                        && is_formatting(name.as_str())
+                                    {
+                                        formatting.push((open_tags.len(), attrs.clone()));
+                                    }
+                                    let open =
+                                        sink.create(Item::Open(name.clone(), attrs, namespace));
+                                    self.open_span(depth, start, None);
+                                    open_ranges.push((
+                                        open_tags.len(),
+                                        (self.item_start.clone(), self.pos.clone()),
+                                    ));
+                                    open_tags.push((name.clone(), open));
+                                    if disposition != ElementDisposition::RawText {
+                                        continue;
+                                    }
+                                    let (text, closed) = self.read_raw_element(&name);
+                                    if !text.is_empty() {
+                                        let text = sink.create(Item::Text(text.into()));
+                                        if sink.append(&mut open_tags, text).is_break() {
+                                            return Ok(());
+                                        }
+                                    }
+                                    // 閉じタグがないまま終わったら、開いたままにしておく
+                                    if !closed {
+                                        continue;
+                                    }
+                                    match self.close_tag(sink, &mut open_tags, &open_ranges, name) {
+                                        Ok(Closed::Element(open)) => Ok(Some(open)),
+                                        Ok(Closed::Ignored) => continue,
+                                        Ok(Closed::Stopped) => return Ok(()),
+                                        Err(err) => return Err(err),
+                                    }
+                                }
+                            }
+                            Ok(Some(item)) => {
+                                if let Item::Solo(..) = item {
+                                    self.check_depth(open_tags.len() + 1)?;
+                                    self.open_span(
+                                        open_tags.len(),
+                                        Some(self.item_start.clone()),
+                                        Some(self.pos.clone()),
+                                    );
+                                }
+                                if let Item::Doctype(_) = item {
+                                    if open_tags.is_empty() && self.doctype_span.is_none() {
+                                        self.doctype_span =
+                                            Some((begin.pos.clone(), self.pos.clone()));
+                                    }
+                                }
+                                Ok(Some(sink.create(item)))
+                            }
+                            Ok(None) => Ok(None),
+                            Err(err) => Err(err),
+                        }
+                    }
+                }
+                None if self.current_kind() == TokenKind::Template => match self.consume() {
+                    Some(tok) => Ok(Some(sink.create(Item::Template(tok.s.clone())))),
+                    None => Ok(None),
+                },
+                None => {
+                    // Text以外の記号は読めないので、止まらずにエラーにする
+                    let kind = self.current_kind();
+                    if kind != TokenKind::Text
+                        && !(preserve_whitespace && kind == TokenKind::Whitespace)
+                        && !(self.options.lenient && is_stray_in_text(&kind))
+                    {
+                        return match self.current_token() {
+                            Ok(tok) => Err(ParseError::UnexpectedToken {
+                                expected: TokenKind::Text,
+                                found: tok,
+                                start: self.pos.clone(),
+                            }),
+                            Err(err) => Err(err),
+                        };
+                    }
+                    match self.parse_text() {
+                        Ok(Some(item)) => Ok(Some(sink.create(item))),
+                        Ok(None) => Ok(None),
+                        Err(err) => Err(err),
+                    }
+                }
+            };
+            // テキスト、あるいはタグのパースは成功しましたか?
+            let nd = match nd_result {
+                Ok(Some(n)) => n,
+                Ok(None) => break,
+                Err(err) => return Err(err),
+            };
+            if sink.append(&mut open_tags, nd).is_break() {
+                return Ok(());
+            }
+            if !preserve_whitespace {
+                self.consume_kind(TokenKind::Whitespace);
+            }
+        }
+
+        // 直しきれない入力。それまでに読んだところは閉じて木に残す
+        if self.over_error_budget() {
+            while let Some((_, open)) = open_tags.pop() {
+                if sink.append(&mut open_tags, open).is_break() {
+                    break;
+                }
+            }
+            return Err(ParseError::TooManyErrors {
+                count: self.error_count,
+            });
+        }
+
+        // 閉じられていないタグがある
+        if !open_tags.is_empty() && self.options.lenient {
+            self.item_start = self.pos.clone();
+            self.recover("element not closed before the end; closed it");
+            while let Some((_, open)) = open_tags.pop() {
+                if sink.append(&mut open_tags, open).is_break() {
+                    return Ok(());
+                }
+            }
+        }
+        if !open_tags.is_empty() {
+            return match self.current_token() {
+                Ok(tok) => Err(ParseError::UnexpectedToken {
+                    expected: TokenKind::Slash,
+                    found: tok,
+                    start: self.pos.clone(),
+                }),
+                Err(err) => Err(err),
+            };
+        }
+
+        return Ok(());
+    }
+
+    // misnestedで閉じた書式の要素を、同じ名前と属性で開き直す
+    fn reopen_formatting<S: Sink>(
+        &mut self,
+        sink: &mut S,
+        open_tags: &mut Vec<(Atom, S::Handle)>,
+        formatting: &mut Vec<(usize, Attributes)>,
+        reopen: &mut Vec<(Atom, Attributes)>,
+    ) -> Result<(), ParseError> {
+        self.recover("misnested formatting element; reopened it");
+        for (name, attrs) in reopen.drain(..) {
+            self.check_depth(open_tags.len() + 1)?;
+            formatting.push((open_tags.len(), attrs.clone()));
+            self.open_span(open_tags.len(), None, None);
+            let open = sink.create(Item::Open(name.clone(), attrs, Namespace::Html));
+            open_tags.push((name, open));
+        }
+        return Ok(());
+    }
+
+    // 自作の要素だけon_elementに聞く。ほかの要素は名前で決まっている
+    fn element_disposition(&self, name: &Atom, attrs: &Attributes) -> ElementDisposition {
+        return match &self.options.on_element {
+            Some(hook) if name.is_custom_element() => hook.call(name.as_str(), attrs),
+            _ => ElementDisposition::Normal,
+        };
+    }
+
+    // on_elementでRawTextにした要素の中身を、</name> の手前まで読む
+    // tokenizerはタグとして区切っているので、tokenをつなぎ直す
+    // (中身, 閉じタグまで読んだか)
+    fn read_raw_element(&mut self, name: &Atom) -> (String, bool) {
+        let mut text = String::new();
+        while !self.is_eof() {
+            let tok = match self.consume() {
+                Some(tok) => tok,
+                None => break,
+            };
+            if tok.kind != TokenKind::TagBegin {
+                push_raw_token(&mut text, &tok);
+                continue;
+            }
+            // 閉じタグでなければ、読んだところまでを中身に戻す
+            let mut pending = String::from("<");
+            if self.consume_kind(TokenKind::Slash).is_none() {
+                text += &pending;
+                continue;
+            }
+            pending.push('/');
+            let close_name = match self.consume_kind(TokenKind::Text) {
+                Some(tok) => tok,
+                None => {
+                    text += &pending;
+                    continue;
+                }
+            };
+            let close_name = self.hyphenated(&close_name.s);
+            pending += &close_name;
+            if !close_name.eq_ignore_ascii_case(name.as_str()) {
+                text += &pending;
+                continue;
+            }
+            if let Some(ws) = self.consume_kind(TokenKind::Whitespace) {
+                push_raw_token(&mut pending, &ws);
+            }
+            if self.consume_kind(TokenKind::TagEnd).is_some() {
+                return (text, true);
+            }
+            text += &pending;
+        }
+        return (text, false);
+    }
+
+    // 閉じタグに対応する開きタグを外す
+    // lenientなら、間で閉じていないタグも閉じ、対応する開きタグがなければ読み飛ばす
+    fn close_tag<S: Sink>(
+        &mut self,
+        sink: &mut S,
+        open_tags: &mut Vec<(Atom, S::Handle)>,
+        open_ranges: &[(usize, (Position, Position))],
+        close_tag_name: Atom,
+    ) -> Result<Closed<S::Handle>, ParseError> {
+        let at = match (matching_open(open_tags, &close_tag_name), open_tags.last()) {
+            (Some(at), _) if at + 1 == open_tags.len() => {
+                self.close_span(at);
+                at
+            }
+            (Some(at), _) if self.options.lenient => {
+                self.recover("close tag for an outer element; closed the inner ones");
+                at
+            }
+            (None, _) if self.options.lenient => {
+                self.recover("close tag without an open tag; ignored");
+                return Ok(Closed::Ignored);
+            }
+            // tag miss match: eg. <xxx></yyy>
+            (_, Some((open_tag_name, _))) => {
+                let close_range = (self.item_start.clone(), self.pos.clone());
+                let open_range = match open_ranges.last() {
+                    Some((at, range)) if *at + 1 == open_tags.len() => range.clone(),
+                    // 開き直した要素には、書かれた場所がない
+                    _ => close_range.clone(),
+                };
+                return Err(ParseError::TagMissMatch {
+                    open: open_tag_name.to_string(),
+                    close: close_tag_name.to_string(),
+                    open_range,
+                    close_range,
+                });
+            }
+            (_, None) => {
+                return Err(ParseError::Internal {
+                    detail: "close tag with no open tags",
+                    pos: self.pos.clone(),
+                });
+            }
+        };
+        while open_tags.len() > at + 1 {
+            if let Some((_, inner)) = open_tags.pop() {
+                if sink.append(open_tags, inner).is_break() {
+                    return Ok(Closed::Stopped);
+                }
+            }
+        }
+        return match open_tags.pop() {
+            Some((_, open)) => Ok(Closed::Element(open)),
+            None => Err(ParseError::Internal {
+                detail: "open tag disappeared while closing",
+                pos: self.pos.clone(),
+            }),
+        };
+    }
+
+    // optionsに合わせてtokenizeしてから読む
+    pub fn parse_str(&mut self, html: &str) -> Result<Option<Vec<Node>>, ParseError> {
+        let mut tokenizer = Tokenizer::with_options(html, self.tokenizer_options.clone());
+        let timer = Timer::start();
+        let tokens = tokenizer.tokenize();
+        let tokenize_duration = timer.elapsed();
+        let result = self.parse(tokens);
+        if let Some(metrics) = &mut self.metrics {
+            metrics.input_bytes = html.len();
+            metrics.tokenize_duration = tokenize_duration;
+        }
+        return result;
+    }
+
+    // 文書から切り出した要素を、namespaceの要素の中として読む。位置は切り出したところから数える
+    #[cfg(feature = "std")]
+    pub(crate) fn parse_fragment(
+        &mut self,
+        html: &str,
+        namespace: Namespace,
+    ) -> Result<Option<Vec<Node>>, ParseError> {
+        let mut tokenizer = Tokenizer::with_options(html, self.tokenizer_options.clone());
+        self.context_namespace = namespace;
+        let result = self.parse(tokenizer.tokenize());
+        self.context_namespace = Namespace::Html;
+        return result;
+    }
+
+    pub fn parse(&mut self, token: Option<Box<Token>>) -> Result<Option<Vec<Node>>, ParseError> {
+        let token_count = match self.options.collect_metrics {
+            true => count_tokens(&token),
+            false => 0,
+        };
+        let timer = Timer::start();
+        self.token = token;
+        let mut tree = Tree { nodes: Vec::new() };
+        match self.parse_(&mut TreeSinkAdapter(&mut tree)) {
+            Ok(_) => {}
+            Err(err) => return Err(err),
+        }
+        if self.options.collect_metrics {
+            let parse_duration = timer.elapsed();
+            let (node_count, max_depth) = tree_shape(&tree.nodes);
+            self.metrics = Some(ParseMetrics {
+                token_count,
+                node_count,
+                max_depth,
+                warning_count: self.warning_count,
+                parse_duration,
+                ..ParseMetrics::default()
+            });
+        }
+
+        if tree.nodes.is_empty() {
+            return Ok(None);
+        }
+
+        return Ok(Some(tree.nodes));
+    }
+
+    // エラーで止まっても、それまでに読んだ木とwarningsを返す。warningsはcollect_warningsによらず全部
+    // TooManyErrorsのときは開いていた要素も閉じて残す。ほかのエラーでは、閉じ終わった最上位のnodeだけ
+    pub fn parse_recovering(
+        &mut self,
+        html: &str,
+    ) -> (Vec<Node>, Vec<ParseWarning>, Option<ParseError>) {
+        let mut tokenizer = Tokenizer::with_options(html, self.tokenizer_options.clone());
+        self.token = tokenizer.tokenize();
+        let collect_warnings = self.options.collect_warnings;
+        self.options.collect_warnings = true;
+        let mut tree = Tree { nodes: Vec::new() };
+        let result = self.parse_(&mut TreeSinkAdapter(&mut tree));
+        self.options.collect_warnings = collect_warnings;
+        let warnings = match collect_warnings {
+            true => self.warnings.clone(),
+            false => core::mem::take(&mut self.warnings),
+        };
+        return (tree.nodes, warnings, result.err());
+    }
+
+    // token列を少しずつrxから受け取りながら読む
+    #[cfg(feature = "std")]
+    pub(crate) fn parse_batches(
+        &mut self,
+        rx: Receiver<Box<Token>>,
+    ) -> Result<Option<Vec<Node>>, ParseError> {
+        self.batches = Some(rx);
+        let first = self.next_batch();
+        let result = self.parse(first);
+        // 途中で止まったときに、送る側が待ち続けないようにする
+        self.batches = None;
+        return result;
+    }
+
+    // optionsに合わせてtokenizeし、sinkに木を作らせる
+    // warningsはcollect_warningsによらずsinkに渡す。Parser::warningsにはcollect_warningsのときだけ残す
+    pub fn parse_into_sink<T: TreeSink>(
+        &mut self,
+        html: &str,
+        sink: &mut T,
+    ) -> Result<(), ParseError> {
+        let mut tokenizer = Tokenizer::with_options(html, self.tokenizer_options.clone());
+        self.token = tokenizer.tokenize();
+        let collect_warnings = self.options.collect_warnings;
+        self.options.collect_warnings = true;
+        let result = self.parse_(&mut TreeSinkAdapter(sink));
+        self.options.collect_warnings = collect_warnings;
+        for warning in &self.warnings {
+            sink.parse_error(warning);
+        }
+        if !collect_warnings {
+            self.warnings.clear();
+        }
+        return result;
+    }
+
+    // 木をarenaの中に作る。返すのはこの文書の最上位のnode
+    // エラーのときはarenaを呼ぶ前の状態に戻す
+    pub fn parse_in(
+        &mut self,
+        arena: &mut Arena,
+        token: Option<Box<Token>>,
+    ) -> Result<Vec<NodeId>, ParseError> {
+        self.token = token;
+        let mut builder = ArenaBuilder::new(arena);
+        return match self.parse_(&mut builder) {
+            Ok(_) => Ok(builder.finish()),
+            Err(err) => {
+                builder.rollback();
+                Err(err)
+            }
+        };
+    }
+
+    // 部分木ができあがるたびにfへ渡して、木全体は持たない
+    pub fn parse_streaming<F: FnMut(Node) -> ControlFlow<()>>(
+        &mut self,
+        token: Option<Box<Token>>,
+        target: &StreamTarget,
+        mut f: F,
+    ) -> Result<StreamStats, ParseError> {
+        self.token = token;
+        let mut stream = Stream {
+            target,
+            f: &mut f,
+            retained: 0,
+            stats: StreamStats::default(),
+        };
+        return match self.parse_(&mut stream) {
+            Ok(_) => Ok(stream.stats),
+            Err(err) => Err(err),
+        };
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod test {
+    use crate::html;
+    use crate::html::atom::Atom;
+    use crate::html::attributes::Attributes;
+    use crate::html::compare::{assert_tree_eq, CompareOptions};
+    use crate::html::errors::{OptionsError, ParseError};
+    use crate::html::parser::{
+        Attribute, ElementDisposition, Namespace, Node, NodeData, NodeKind, ParseWarning, Parser,
+        ParserOptions, StreamTarget, TreeSink, DEFAULT_MAX_ERRORS,
+    };
+    use crate::html::position::Position;
+    use crate::html::serializer::{
+        document_ascii_tree, document_html, outer_html, to_ascii_tree, to_tree_test_format,
+    };
+    use crate::html::shared_str::SharedStr;
+    use crate::html::snapshot::assert_snapshot;
+    use crate::html::tokenizer;
+    use crate::html::tokenizer::{TokenKind, TokenizerOptions};
+    use std::mem::size_of;
+    use std::ops::ControlFlow;
+
+    // 木か、エラーなら元の行つきの表示をsnapshotと比べる
+    fn assert_parse_snapshot(name: &str, html: &str) {
+        let mut parser_ = Parser::new();
+        let out = match parser_.parse(tokenizer::Tokenizer::new(html).tokenize()) {
+            Ok(Some(nodes)) => to_ascii_tree(&nodes),
+            Ok(None) => String::new(),
+            Err(err) => err.render("input", html),
+        };
+        assert_snapshot(name, &out);
+    }
+
+    #[test]
+    fn parse_only_decl() {
+        // doctypeはchildrenではなくDocument::doctypeに入る。書き出すときは先頭に戻す
+        let doc = html::parse("<!doctype html><!-- hello, w--orld -->").unwrap();
+        assert_eq!(doc.doctype.as_ref().map(|d| d.name.as_str()), Some("html"));
+        assert_eq!(doc.children.len(), 1);
+        assert!(matches!(&doc.children[0].data, NodeData::Comment(s) if s == " hello, w--orld "));
+        assert_snapshot("parser__parse_only_decl", &document_ascii_tree(&doc));
+    }
+
+    #[test]
+    fn doctype_after_leading_comment() {
+        let html = "<!-- c -->\n<!DOCTYPE html><html></html>";
+        let doc = html::parse(html).unwrap();
+        assert_eq!(doc.doctype.as_ref().map(|d| d.name.as_str()), Some("html"));
+        assert_eq!(doc.children.len(), 2);
+        assert!(doc.children.iter().all(|nd| nd.kind() != NodeKind::Doctype));
+        // 位置は "<" と ">" のtoken。最上位で2番目 (コメントの次) にあった
+        let span = doc.doctype_node_span().unwrap();
+        assert_eq!(span.index, 1);
+        assert_eq!(
+            span.range,
+            Some((Position::new(2, 1, 12), Position::new(2, 15, 26)))
+        );
+        assert_eq!(
+            document_html(&doc),
+            "<!DOCTYPE html><!-- c --><html></html>"
+        );
+
+        // 2つ目のdoctypeや、要素の中のdoctypeはchildrenに残す
+        let doc = html::parse("<!DOCTYPE html><!DOCTYPE x><p><!DOCTYPE y></p>").unwrap();
+        assert_eq!(doc.doctype.as_ref().map(|d| d.name.as_str()), Some("html"));
+        assert_eq!(doc.children[0].kind(), NodeKind::Doctype);
+        assert_eq!(doc.children[1].children()[0].kind(), NodeKind::Doctype);
+        assert_eq!(doc.doctype_node_span().unwrap().index, 0);
+
+        let doc = html::parse("<p>x</p>").unwrap();
+        assert!(doc.doctype.is_none());
+        assert!(doc.doctype_node_span().is_none());
+    }
+
+    #[test]
+    fn parse_html_tag() {
+        assert_parse_snapshot("parser__parse_html_tag", "<html></html>");
+    }
+
+    #[test]
+    fn parse_html_body() {
+        assert_parse_snapshot("parser__parse_html_body", "<html><body></body></html>");
+    }
+
+    #[test]
+    fn parse_html_body_h1_img() {
+        let html = "<!DOCTYPE html> \
+            <html>\
+            <body>\
+            <h1>hello</h1>\
+            <img src=\"https://google.com\"/>\
+            </body>\
+            </html>";
+
+        assert_parse_snapshot("parser__parse_html_body_h1_img", html);
+    }
+
+    #[test]
+    fn parse_mixed_case_tag_pair() {
+        let mut tokenizer_ =
+            tokenizer::Tokenizer::new("<!DocType HTML><DiV><MyCard>x</mYcArD></dIv>");
+        let tok = tokenizer_.tokenize();
 
         let mut parser_ = Parser::new();
-        let nodes = parser_.parse(tok);
-        println!("{:#?}", nodes)
+        let nodes = parser_.parse(tok).unwrap().unwrap();
+        match &nodes[0].data {
+            NodeData::Doctype(doctype) => assert_eq!(doctype.name, "html"),
+            data => panic!("unexpected node: {:?}", data),
+        }
+        let div = &nodes[1];
+        assert_eq!(div.name().unwrap(), "div");
+        assert_eq!(div.children()[0].name().unwrap(), "mycard");
+        let expected = Parser::new()
+            .parse_str("<!DOCTYPE html><div><mycard>x</mycard></div>")
+            .unwrap()
+            .unwrap();
+        assert_tree_eq!(nodes, expected);
+    }
+
+    #[test]
+    fn node_sizes() {
+        // 以前のNodeは種類に関係なく96バイトで、1つずつBoxに入っていた
+        // 属性1つにもParameter, Identifier, Stringの3つのnodeを使っていた
+        #[cfg(not(feature = "smallvec"))]
+        assert!(size_of::<Node>() <= 96, "{}", size_of::<Node>());
+        #[cfg(feature = "smallvec")]
+        assert!(
+            size_of::<Node>() <= 96 + size_of::<Attribute>(),
+            "{}",
+            size_of::<Node>()
+        );
+        assert!(size_of::<Attribute>() < 96, "{}", size_of::<Attribute>());
+    }
+
+    #[test]
+    fn parse_stray_string_in_tag() {
+        let mut tokenizer_ = tokenizer::Tokenizer::new("<div foo=\"a\" \"b\"></div>");
+        let tok = tokenizer_.tokenize();
+
+        let mut parser_ = Parser::new();
+        let err = parser_.parse(tok).unwrap_err();
+        match &err {
+            ParseError::UnexpectedTokenOneOf {
+                expected, found, ..
+            } => {
+                assert_eq!(
+                    expected,
+                    &vec![TokenKind::Text, TokenKind::TagEnd, TokenKind::Slash]
+                );
+                assert_eq!(found.kind, TokenKind::String);
+            }
+            _ => panic!("unexpected error: {:?}", err),
+        }
+        assert!(err
+            .to_string()
+            .starts_with("unexpected token: (expected one of: [Text, TagEnd, Slash], found: "));
+    }
+
+    #[test]
+    fn parse_attribute_without_value() {
+        let mut tokenizer_ = tokenizer::Tokenizer::new("<div foo bar></div>");
+        let err = Parser::new().parse(tokenizer_.tokenize()).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "unexpected token: (expected one of: [Whitespace, Assign], found: Token { kind: Text, pos: Position { line_no: 1, at_line: 12, at_whole: 12 }, s: \"bar\", next: None })"
+        );
+
+        let nodes = Parser::new()
+            .parse_str("<div foo = \"a\"></div>")
+            .unwrap()
+            .unwrap();
+        assert_eq!(nodes[0].attr("foo").unwrap(), "a");
+    }
+
+    #[test]
+    fn parse_hyphenated_attribute_names() {
+        let mut tokenizer_ = tokenizer::Tokenizer::new(
+            "<meta http-equiv=\"refresh\" data-a-b-=\"1\" content=\"0\" />",
+        );
+        let nodes = Parser::new().parse(tokenizer_.tokenize()).unwrap().unwrap();
+        let names: Vec<&str> = nodes[0]
+            .attrs()
+            .iter()
+            .map(|attr| attr.name.as_str())
+            .collect();
+        assert_eq!(names, vec!["http-equiv", "data-a-b-", "content"]);
+        assert_eq!(nodes[0].attr("HTTP-EQUIV").unwrap(), "refresh");
+    }
+
+    #[test]
+    fn parse_unclosed_open_tag() {
+        let mut tokenizer_ = tokenizer::Tokenizer::new("<div foo=\"a\"");
+        let tok = tokenizer_.tokenize();
+
+        let mut parser_ = Parser::new();
+        let err = parser_.parse(tok).unwrap_err();
+        assert!(err
+            .to_string()
+            .starts_with("unexpected token: (expected one of: [Slash, TagEnd], found: "));
+    }
+
+    #[test]
+    fn parse_empty_token_stream() {
+        let mut parser_ = Parser::new();
+        let nodes = parser_.parse(None);
+        assert!(matches!(nodes, Ok(None)));
+    }
+
+    #[test]
+    fn parse_and_drop_large_trees_on_small_stack() {
+        let handle = std::thread::Builder::new()
+            .stack_size(256 * 1024)
+            .spawn(|| {
+                // 浅くて大きい: p + text で50万node
+                let html = "<p>x</p>".repeat(250_000);
+                let mut tokenizer_ = tokenizer::Tokenizer::new(&html);
+                let nodes = Parser::new().parse(tokenizer_.tokenize()).unwrap();
+                assert_eq!(nodes.unwrap().len(), 250_000);
+
+                // 深い
+                let depth = 100_000;
+                let html = "<div>".repeat(depth) + &"</div>".repeat(depth);
+                let mut tokenizer_ = tokenizer::Tokenizer::new(&html);
+                let nodes = Parser::new().parse(tokenizer_.tokenize()).unwrap();
+                assert_eq!(nodes.unwrap().len(), 1);
+            })
+            .unwrap();
+        handle.join().unwrap();
+    }
+
+    #[test]
+    fn parse_stray_symbol_in_text() {
+        let mut tokenizer_ = tokenizer::Tokenizer::new("<p>a = b</p>");
+        let tok = tokenizer_.tokenize();
+
+        let mut parser_ = Parser::new();
+        let err = parser_.parse(tok).unwrap_err();
+        assert!(matches!(
+            err,
+            ParseError::UnexpectedToken {
+                expected: TokenKind::Text,
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn parse_streaming_body_children() {
+        let mut html = "<html><body>".to_string();
+        for i in 0..10_000 {
+            html += &format!("<item id=\"{}\"><name>item {}</name></item>\n", i, i);
+        }
+        html += "</body></html>";
+
+        let mut tokenizer_ = tokenizer::Tokenizer::new(&html);
+        let mut items = 0;
+        let stats = Parser::new()
+            .parse_streaming(tokenizer_.tokenize(), &StreamTarget::BodyChildren, |nd| {
+                assert_eq!(nd.name().unwrap(), "item");
+                items += 1;
+                ControlFlow::Continue(())
+            })
+            .unwrap();
+
+        assert_eq!(items, 10_000);
+        assert_eq!(stats.emitted, 10_000);
+        // html, body と item 1つ分 (item, name, text x2) しか持たない
+        assert!(stats.peak_retained <= 6, "{:?}", stats);
+    }
+
+    #[test]
+    fn parse_streaming_outermost_elements() {
+        let html = "<main><article><p>a</p><article><p>b</p></article></article><article>c</article></main>";
+        let mut tokenizer_ = tokenizer::Tokenizer::new(html);
+        let mut sizes = vec![];
+        let stats = Parser::new()
+            .parse_streaming(
+                tokenizer_.tokenize(),
+                &StreamTarget::Elements(Atom::new("article")),
+                |nd| {
+                    sizes.push(nd.count_nodes());
+                    ControlFlow::Continue(())
+                },
+            )
+            .unwrap();
+
+        // 入れ子のarticleは外側と一緒に渡される
+        assert_eq!(sizes, vec![6, 2]);
+        assert_eq!(stats.emitted, 2);
+    }
+
+    #[test]
+    fn parse_streaming_break() {
+        let html = "<body><p>1</p><p>2</p><p>3</p></body>";
+        let mut tokenizer_ = tokenizer::Tokenizer::new(html);
+        let mut seen = 0;
+        let stats = Parser::new()
+            .parse_streaming(tokenizer_.tokenize(), &StreamTarget::BodyChildren, |_| {
+                seen += 1;
+                if seen == 2 {
+                    return ControlFlow::Break(());
+                }
+                ControlFlow::Continue(())
+            })
+            .unwrap();
+
+        assert_eq!(seen, 2);
+        assert_eq!(stats.emitted, 2);
+    }
+
+    #[test]
+    fn builder_lenient_with_warnings() {
+        let mut parser_ = Parser::builder()
+            .lenient()
+            .collect_warnings(true)
+            .max_depth(8)
+            .build()
+            .unwrap();
+        let nodes = parser_
+            .parse_str("<div><p>a</div></i><b>x</b><ul><li>y")
+            .unwrap()
+            .unwrap();
+        let expected = Parser::new()
+            .parse_str("<div><p>a</p></div><b>x</b><ul><li>y</li></ul>")
+            .unwrap()
+            .unwrap();
+        assert_tree_eq!(nodes, expected);
+        let messages: Vec<&str> = parser_.warnings().iter().map(|w| w.message).collect();
+        assert_eq!(
+            messages,
+            vec![
+                "close tag for an outer element; closed the inner ones",
+                "close tag without an open tag; ignored",
+                "element not closed before the end; closed it",
+            ]
+        );
+        assert_eq!(parser_.warnings()[0].pos.at_whole, 15);
+
+        // 深すぎる文書はlenientでもエラー
+        let err = parser_.parse_str(&"<i>".repeat(9)).unwrap_err();
+        assert!(matches!(err, ParseError::TooDeep { max_depth: 8, .. }));
+    }
+
+    #[test]
+    fn error_budget_stops_runaway_recovery() {
+        // 開きタグのない閉じタグを1000個。lenientでは1つずつ読み飛ばす
+        let html = format!("<div><p>ok</p>{}<p>never</p></div>", "</i>".repeat(1000));
+        let mut parser = Parser::builder().lenient().build().unwrap();
+        let (nodes, warnings, err) = parser.parse_recovering(&html);
+        assert!(
+            matches!(err, Some(ParseError::TooManyErrors { count: 201 })),
+            "{:?}",
+            err
+        );
+        assert_eq!(warnings.len(), DEFAULT_MAX_ERRORS + 1);
+        assert!(warnings
+            .iter()
+            .all(|w| w.message == "close tag without an open tag; ignored"));
+        // 止まるまでに読んだところは閉じて残る
+        assert_tree_eq!(nodes, html::parse("<div><p>ok</p></div>").unwrap().children);
+        assert!(parser.warnings().is_empty());
+
+        let mut parser = Parser::builder().lenient().max_errors(10).build().unwrap();
+        assert!(matches!(
+            parser.parse_str(&html),
+            Err(ParseError::TooManyErrors { count: 11 })
+        ));
+        // Noneなら最後まで読む
+        let options = ParserOptions {
+            lenient: true,
+            max_errors: None,
+            ..ParserOptions::default()
+        };
+        let mut parser = Parser::builder().options(options).build().unwrap();
+        let (nodes, warnings, err) = parser.parse_recovering(&html);
+        assert!(err.is_none(), "{:?}", err);
+        assert_eq!(warnings.len(), 1000);
+        assert_eq!(nodes[0].children().len(), 2);
+    }
+
+    #[test]
+    fn messy_pages_stay_under_the_error_budget() {
+        for name in ["broken.html", "corpus/broken.html", "corpus/news.html"] {
+            let path = format!("{}/tests/fixtures/{}", env!("CARGO_MANIFEST_DIR"), name);
+            let html = std::fs::read_to_string(path).unwrap();
+            let mut parser = Parser::builder().lenient().build().unwrap();
+            let (nodes, warnings, err) = parser.parse_recovering(&html);
+            assert!(err.is_none(), "{}: {:?}", name, err);
+            assert!(!nodes.is_empty(), "{}", name);
+            assert!(warnings.len() < DEFAULT_MAX_ERRORS, "{}", name);
+        }
+        // strictのエラーでも、それまでに閉じた最上位のnodeは返す
+        let (nodes, warnings, err) = Parser::new().parse_recovering("<p>a</p><div><b>x</p>");
+        assert_eq!(nodes.len(), 1);
+        assert!(warnings.is_empty());
+        assert!(matches!(err, Some(ParseError::TagMissMatch { .. })));
+    }
+
+    #[test]
+    fn warnings_do_not_count_against_the_error_budget() {
+        // 古い名前と、voidでない要素の "/>" は誤りではないので、いくつあっても止めない
+        let html = "<p><acronym>a</acronym><span/>b</span></p>".repeat(300);
+        let mut parser = Parser::builder()
+            .lenient()
+            .map_legacy_names()
+            .build()
+            .unwrap();
+        let (nodes, warnings, err) = parser.parse_recovering(&html);
+        assert!(err.is_none(), "{:?}", err);
+        assert_eq!(nodes.len(), 300);
+        assert_eq!(warnings.len(), 600);
+    }
+
+    // lenientで読んで、htmlと警告の文を返す
+    fn parse_lenient(html: &str) -> (String, Vec<&'static str>) {
+        let mut parser = Parser::builder().lenient().build().unwrap();
+        let (nodes, warnings, err) = parser.parse_recovering(html);
+        assert!(err.is_none(), "{}: {:?}", html, err);
+        let html = nodes.iter().map(outer_html).collect();
+        return (
+            html,
+            warnings.iter().map(|warning| warning.message).collect(),
+        );
+    }
+
+    #[test]
+    fn lenient_reads_quotes_in_text() {
+        // タグの外の引用符は値の文字列を始めない。</p> まで読み込まない
+        for html in ["<p>it's</p><p>b</p>", "<p>say \"hi\"</p>"] {
+            let (out, warnings) = parse_lenient(html);
+            assert_eq!(out, html);
+            assert_eq!(warnings, ["markup character in text; read it as text"]);
+        }
+    }
+
+    #[test]
+    fn lenient_reads_symbols_in_text() {
+        for (html, want) in [
+            ("<p>a & b</p>", "<p>a &amp; b</p>"),
+            ("<p>a - b</p>", "<p>a - b</p>"),
+            ("<p>a/b</p>", "<p>a/b</p>"),
+            ("<p>hi!</p>", "<p>hi!</p>"),
+            ("<p>a=b</p>", "<p>a=b</p>"),
+        ] {
+            let (out, warnings) = parse_lenient(html);
+            assert_eq!(out, want);
+            assert_eq!(warnings, ["markup character in text; read it as text"]);
+        }
+        assert!(Parser::builder().parse("<p>a & b</p>").is_err());
+    }
+
+    #[test]
+    fn lenient_text_decodes_references_without_warning() {
+        let (out, warnings) = parse_lenient("<p>a &amp; b &lt;c&gt;</p>");
+        assert_eq!(out, "<p>a &amp; b &lt;c&gt;</p>");
+        assert!(warnings.is_empty(), "{:?}", warnings);
+    }
+
+    #[test]
+    fn lenient_closes_void_elements_without_slash() {
+        let (out, warnings) = parse_lenient("<p>x<br>y</p>");
+        assert_eq!(out, "<p>x<br/>y</p>");
+        assert_eq!(
+            warnings,
+            ["void element without a closing slash; closed it"]
+        );
+    }
+
+    #[test]
+    fn lenient_reads_boolean_attributes() {
+        let (out, warnings) = parse_lenient("<p><input disabled checked/></p>");
+        assert_eq!(out, r#"<p><input disabled="" checked=""/></p>"#);
+        assert_eq!(
+            warnings,
+            [
+                "attribute without a value; read it as empty",
+                "attribute without a value; read it as empty"
+            ]
+        );
+        assert!(Parser::builder().parse("<input disabled/>").is_err());
+    }
+
+    #[test]
+    fn lenient_reads_unquoted_attribute_values() {
+        let (out, warnings) = parse_lenient(r#"<a href=/x/y?a=1&amp;b=2 title="t">t</a>"#);
+        assert_eq!(out, r#"<a href="/x/y?a=1&amp;b=2" title="t">t</a>"#);
+        assert_eq!(warnings, ["unquoted attribute value"]);
+
+        let (out, _) = parse_lenient("<img src=x alt=a-b.png>");
+        assert_eq!(out, r#"<img src="x" alt="a-b.png"/>"#);
+        assert!(Parser::builder().parse("<img src=x/>").is_err());
+    }
+
+    #[test]
+    fn builder_preserve_whitespace() {
+        let nodes = Parser::builder()
+            .preserve_whitespace()
+            .parse("<p>a  b\n c</p>\n<br/>")
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            to_ascii_tree(&nodes),
+            "<p>\n└── \"a  b\\n c\"\n\"\\n\"\n<br>\n"
+        );
+
+        // 空白だけのtextを除けば、字下げしていない文書と同じ
+        let nodes = Parser::builder()
+            .preserve_whitespace()
+            .parse("<ul>\n  <li>a</li>\n  <li>b</li>\n</ul>\n")
+            .unwrap()
+            .unwrap();
+        let expected = Parser::builder()
+            .preserve_whitespace()
+            .parse("<ul><li>a</li><li>b</li></ul>")
+            .unwrap()
+            .unwrap();
+        let opts = CompareOptions {
+            ignore_whitespace_text: true,
+            ..CompareOptions::default()
+        };
+        assert_tree_eq!(nodes, expected, opts);
+        assert!(!nodes[0].equals_ignoring(&expected[0], CompareOptions::default()));
+
+        // 既定では語ごとに分け、空白は残さない
+        let nodes = Parser::builder().parse("<p>a  b</p>").unwrap().unwrap();
+        assert_eq!(nodes[0].children().len(), 2);
+        assert!(Parser::new().warnings().is_empty());
+    }
+
+    #[test]
+    fn builder_rejects_incompatible_options() {
+        let err = Parser::builder()
+            .preserve_whitespace()
+            .tokenizer(TokenizerOptions::default())
+            .build()
+            .err()
+            .unwrap();
+        assert!(matches!(
+            err,
+            OptionsError::Incompatible {
+                option: "preserve_whitespace",
+                ..
+            }
+        ));
+        assert_eq!(
+            err.to_string(),
+            "parser option preserve_whitespace cannot be combined with a tokenizer without keep_whitespace: the tokenizer drops the whitespace the parser would keep"
+        );
+        let err = Parser::builder().max_depth(0).parse("<p></p>").unwrap_err();
+        assert!(matches!(
+            err,
+            ParseError::InvalidOptions(OptionsError::ZeroMaxDepth)
+        ));
+    }
+
+    #[test]
+    fn noscript_depends_on_scripting() {
+        let html = "<noscript><img src=\"x\"/><p>hi</p></noscript>";
+        // 既定 (scriptingなし) では中身を要素として読む
+        let nodes = Parser::builder().parse(html).unwrap().unwrap();
+        let names: Vec<&str> = nodes[0]
+            .children()
+            .iter()
+            .filter_map(|nd| nd.name().map(|name| name.as_str()))
+            .collect();
+        assert_eq!(names, vec!["img", "p"]);
+
+        // scriptingが有効なら中身は1つのtext
+        let nodes = Parser::builder()
+            .scripting_enabled(true)
+            .parse(html)
+            .unwrap()
+            .unwrap();
+        let children = nodes[0].children();
+        assert_eq!(children.len(), 1);
+        assert_eq!(
+            children[0].text_value().unwrap(),
+            "<img src=\"x\"/><p>hi</p>"
+        );
+
+        let err = Parser::builder()
+            .scripting_enabled(true)
+            .tokenizer(TokenizerOptions::default())
+            .build()
+            .err()
+            .unwrap();
+        assert!(matches!(
+            err,
+            OptionsError::Incompatible {
+                option: "scripting_enabled",
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn attribute_names_are_lowercased_outside_foreign_content() {
+        let html = "<div CLASS=\"x\" Data-Id=\"1\"><svg viewBox=\"0 0 1 1\"><path strokeWidth=\"2\"/><foreignObject><p ID=\"y\">a</p></foreignObject></svg></div>";
+        let doc = html::parse(html).unwrap();
+        let div = doc.query_selector("div").unwrap().unwrap();
+        assert_eq!(div.attr("class").unwrap(), "x");
+        let names: Vec<&str> = div.attrs().iter().map(|attr| attr.name.as_str()).collect();
+        assert_eq!(names, vec!["class", "data-id"]);
+        // svgの中は大文字を区別する名前があるので残す
+        let svg = &div.children()[0];
+        assert_eq!(svg.attrs()[0].name, "viewBox");
+        assert_eq!(svg.children()[0].attrs()[0].name, "strokeWidth");
+        // foreignObjectの中はHTML
+        let p = doc.query_selector("p").unwrap().unwrap();
+        assert_eq!(p.attrs()[0].name, "id");
+        assert_eq!(
+            outer_html(&div),
+            "<div class=\"x\" data-id=\"1\"><svg viewBox=\"0 0 1 1\"><path strokeWidth=\"2\"/><foreignObject><p id=\"y\">a</p></foreignObject></svg></div>"
+        );
+
+        let nodes = Parser::builder()
+            .preserve_attribute_case()
+            .parse(html)
+            .unwrap()
+            .unwrap();
+        // 書かれたままでも、attrは大文字小文字を区別せずに引ける
+        assert_eq!(nodes[0].attr("class").unwrap(), "x");
+        assert_eq!(outer_html(&nodes[0]), html);
+    }
+
+    // nodeを作らずに数えるだけのsink
+    #[derive(Default)]
+    struct CountingSink {
+        elements: usize,
+        texts: usize,
+        comments: usize,
+        doctype: Option<String>,
+        appended: usize,
+        warnings: Vec<&'static str>,
+    }
+
+    impl TreeSink for CountingSink {
+        type Handle = ();
+
+        fn create_element(&mut self, _: Atom, _: Attributes, _: bool) {
+            self.elements += 1;
+        }
+
+        fn create_text(&mut self, _: SharedStr) {
+            self.texts += 1;
+        }
+
+        fn create_comment(&mut self, _: SharedStr) {
+            self.comments += 1;
+        }
+
+        fn set_doctype(&mut self, name: SharedStr) -> Option<()> {
+            self.doctype = Some(name.to_string());
+            return None;
+        }
+
+        fn append(&mut self, _: Option<&mut ()>, _: ()) -> ControlFlow<()> {
+            self.appended += 1;
+            return ControlFlow::Continue(());
+        }
+
+        fn parse_error(&mut self, warning: &ParseWarning) {
+            self.warnings.push(warning.message);
+        }
+    }
+
+    #[test]
+    fn tree_sink_counts_match_the_default_tree() {
+        let html = "<!DOCTYPE html><html><body><h1 class=\"t\">Hello world</h1><!-- c --><ul><li>one</li><li>two <b>2</b></li></ul><br/><p>unclosed</body></html>";
+        let mut parser = Parser::builder().lenient().build().unwrap();
+        let mut sink = CountingSink::default();
+        parser.parse_into_sink(html, &mut sink).unwrap();
+
+        let doc = html::parse_with_options(html, parser.options()).unwrap();
+        let (mut elements, mut texts, mut comments, mut total) = (0, 0, 0, 0);
+        let mut stack: Vec<&Node> = doc.children.iter().collect();
+        while let Some(nd) = stack.pop() {
+            total += 1;
+            match nd.kind() {
+                NodeKind::Element => elements += 1,
+                NodeKind::Text => texts += 1,
+                NodeKind::Comment => comments += 1,
+                _ => {}
+            }
+            stack.extend(nd.children());
+        }
+        assert_eq!(sink.elements, elements);
+        assert_eq!(sink.texts, texts);
+        assert_eq!(sink.comments, comments);
+        // doctypeは木に置かなかったので、appendされていない
+        assert_eq!(sink.appended, total);
+        assert_eq!(sink.doctype.as_deref(), Some("html"));
+        // collect_warningsでなくても、sinkには渡す
+        assert_eq!(
+            sink.warnings,
+            vec!["close tag for an outer element; closed the inner ones"]
+        );
+        assert!(parser.warnings().is_empty());
+    }
+
+    #[test]
+    fn misnested_formatting_elements_are_reopened() {
+        let mut parser = Parser::builder()
+            .lenient()
+            .collect_warnings(true)
+            .build()
+            .unwrap();
+        let html = "<p><b>bold <i class=\"x\">both</b> italic</i> plain</p>";
+        let nodes = parser.parse_str(html).unwrap().unwrap();
+        assert_eq!(
+            outer_html(&nodes[0]),
+            "<p><b>bold<i class=\"x\">both</i></b><i class=\"x\">italic</i>plain</p>"
+        );
+        // 字は一つも落ちていない
+        let mut texts = Vec::new();
+        let mut stack = vec![&nodes[0]];
+        while let Some(nd) = stack.pop() {
+            texts.extend(nd.text_value().map(|s| s.as_str()));
+            stack.extend(nd.children().iter().rev());
+        }
+        assert_eq!(texts, vec!["bold", "both", "italic", "plain"]);
+        assert!(parser
+            .warnings()
+            .iter()
+            .any(|w| w.message == "misnested formatting element; reopened it"));
+        // strictではエラーのまま
+        assert!(matches!(
+            html::parse(html),
+            Err(ParseError::TagMissMatch { .. })
+        ));
+
+        // 中身が来なければ開き直さない。閉じタグだけなら、それで済ませる
+        for (html, expected) in [
+            ("<p><b><i>x</b></i></p>", "<p><b><i>x</i></b></p>"),
+            ("<p><b><em><i>x</b></p>", "<p><b><em><i>x</i></em></b></p>"),
+            (
+                "<div><b>1<em>2<span>3</b>4</span>5</em>6</div>",
+                "<div><b>1<em>2<span>3</span></em></b><em><span>4</span>5</em>6</div>",
+            ),
+        ] {
+            let nodes = parser.parse_str(html).unwrap().unwrap();
+            assert_tree_eq!(nodes, Parser::new().parse_str(expected).unwrap().unwrap());
+        }
+    }
+
+    #[test]
+    fn lone_less_than_is_text() {
+        let text = |html: &str| {
+            let nodes = html::parse(html).unwrap().children;
+            return nodes[0].text_content_normalized();
+        };
+        assert_eq!(text("<p>5 < 10 is true</p>"), "5 < 10 is true");
+        assert_eq!(text("<p>1<2 and 3 <4</p>"), "1<2 and 3 <4");
+        // 空白の後ろに文字があっても、< の直後でなければタグではない
+        assert_eq!(text("<p>x < y</p>"), "x < y");
+        // 入力の終わりの <
+        let nodes = html::parse("<p>end</p><").unwrap().children;
+        assert_eq!(nodes.len(), 2);
+        assert_eq!(nodes[1].text_value().unwrap(), "<");
+        // 文字のすぐ後ろの <b> はタグ
+        let nodes = html::parse("<p>x<b>bold</b></p>").unwrap().children;
+        assert_eq!(nodes[0].children()[1].name().unwrap(), "b");
+        assert_eq!(
+            outer_html(&html::parse("<p>5 < 10</p>").unwrap().children[0]),
+            "<p>5 &lt; 10</p>"
+        );
+    }
+
+    #[test]
+    fn self_closing_non_void_elements() {
+        let html = "<div/>text</div>";
+        // 既定ではブラウザと同じく "/" を無視し、後ろのtextを子にする
+        let mut parser = Parser::builder().collect_warnings(true).build().unwrap();
+        let nodes = parser.parse_str(html).unwrap().unwrap();
+        assert_tree_eq!(nodes, html::parse("<div>text</div>").unwrap().children);
+        let warnings = parser.warnings();
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(
+            warnings[0].message,
+            "self-closing syntax on a non-void element; ignored the slash"
+        );
+        assert_eq!(warnings[0].pos.at_whole, 6);
+
+        // XMLのように閉じると、textは兄弟になり "</div>" は余る
+        let mut parser = Parser::builder()
+            .xml_self_closing()
+            .lenient()
+            .collect_warnings(true)
+            .build()
+            .unwrap();
+        let nodes = parser.parse_str(html).unwrap().unwrap();
+        assert_eq!(nodes.len(), 2);
+        assert!(nodes[0].children().is_empty());
+        assert_eq!(nodes[1].text_value().unwrap(), "text");
+        let warnings = parser.warnings();
+        assert_eq!(
+            warnings[0].message,
+            "self-closing syntax on a non-void element; closed it"
+        );
+
+        // voidの要素と <svg> の中はどちらでも閉じる
+        let html = "<p><br/><svg><rect/></svg></p>";
+        let mut parser = Parser::builder().collect_warnings(true).build().unwrap();
+        let nodes = parser.parse_str(html).unwrap().unwrap();
+        assert_eq!(outer_html(&nodes[0]), html);
+        assert!(parser.warnings().is_empty());
+    }
+
+    #[test]
+    fn stray_quotes_in_tags_are_skipped_when_lenient() {
+        for (html, expected, at_whole) in [
+            (
+                "<div class=\"a\"\"b\">x</div>",
+                vec![("class", "a")],
+                vec![17],
+            ),
+            (
+                "<a href=\"x\" \"y\">text</a>",
+                vec![("href", "x")],
+                vec![15],
+            ),
+            ("<img \"src\"=\"x\" alt=\"\"/>", vec![("alt", "")], vec![10]),
+            (
+                "<p \"a\" \"b\" id=\"c\">x</p>",
+                vec![("id", "c")],
+                vec![6, 10],
+            ),
+        ] {
+            let mut parser = Parser::builder()
+                .lenient()
+                .collect_warnings(true)
+                .build()
+                .unwrap();
+            let nodes = parser.parse_str(html).unwrap().unwrap();
+            let attrs: Vec<(&str, &str)> = nodes[0]
+                .attrs()
+                .iter()
+                .map(|attr| (attr.name.as_str(), attr.value.as_str()))
+                .collect();
+            assert_eq!(attrs, expected, "{}", html);
+            // タグは閉じていて、中身も読めている
+            assert_eq!(nodes.len(), 1, "{}", html);
+            // 位置はほかのtokenと同じく、捨てた文字列の終わり
+            let warnings: Vec<u32> = parser
+                .warnings()
+                .iter()
+                .map(|warning| {
+                    assert_eq!(warning.message, "stray quoted text in tag; skipped");
+                    return warning.pos.at_whole;
+                })
+                .collect();
+            assert_eq!(warnings, at_whole, "{}", html);
+
+            // strictではエラーのまま
+            assert!(html::parse(html).is_err(), "{}", html);
+        }
+    }
+
+    #[test]
+    fn custom_elements_parse_like_normal_elements() {
+        let html = "<my-widget data-x=\"1\"><p>a</p><x-item>b</x-item></my-widget><script-x><b>c</b></script-x>";
+        let nodes = html::parse(html).unwrap().children;
+        assert_eq!(nodes[0].name().unwrap(), "my-widget");
+        assert!(nodes[0].name().unwrap().is_custom_element());
+        assert_eq!(nodes[0].children().len(), 2);
+        // - が続くので <script> ではない
+        assert_eq!(nodes[1].children()[0].name().unwrap(), "b");
+        assert_eq!(
+            outer_html(&nodes[0]),
+            html[..html.find("<script-x>").unwrap()]
+        );
+    }
+
+    #[test]
+    fn on_element_reads_custom_elements_as_raw_text_or_void() {
+        let html = "<x-code lang=\"html\"><b class=\"a\">bold</b> </x-codes></X-CODE ><x-icon name=\"star\"><p>after</p><div-x>d</div-x>";
+        let nodes = Parser::builder()
+            .on_element(|name, attrs| match name {
+                "x-code" if attrs.iter().any(|attr| attr.name == "lang") => {
+                    ElementDisposition::RawText
+                }
+                "x-icon" => ElementDisposition::Void,
+                // 自作の要素でなければ呼ばれない
+                "div" | "p" => panic!("called for <{}>", name),
+                _ => ElementDisposition::Normal,
+            })
+            .parse(html)
+            .unwrap()
+            .unwrap();
+        // 中の <b> はtextのまま。違う名前の閉じタグも中身になる
+        let code = &nodes[0];
+        assert_eq!(code.children().len(), 1);
+        assert_eq!(
+            code.children()[0].text_value().unwrap(),
+            "<b class=\"a\">bold</b> </x-codes>"
+        );
+        let icon = &nodes[1];
+        assert_eq!(icon.name().unwrap(), "x-icon");
+        assert!(icon.children().is_empty());
+        assert_eq!(nodes[2].name().unwrap(), "p");
+        assert_eq!(nodes[3].children()[0].text_value().unwrap(), "d");
+    }
+
+    #[test]
+    fn legacy_names_are_mapped_on_request() {
+        let html = "<p><image src=\"x\"/><ACRONYM title=\"a\">HTML <b>5</b></acronym><strike>old</strike><blink>b</blink></p>";
+        let mut parser = Parser::builder()
+            .map_legacy_names()
+            .collect_warnings(true)
+            .build()
+            .unwrap();
+        let nodes = parser.parse_str(html).unwrap().unwrap();
+        let p = &nodes[0];
+        let names: Vec<&str> = p
+            .children()
+            .iter()
+            .map(|nd| nd.name().unwrap().as_str())
+            .collect();
+        // 表にない名前はそのまま
+        assert_eq!(names, vec!["img", "abbr", "s", "blink"]);
+        let img = &p.children()[0];
+        assert!(matches!(
+            img.data,
+            NodeData::Element {
+                self_closing: true,
+                ..
+            }
+        ));
+        assert_eq!(img.attr("src").unwrap(), "x");
+        let abbr = &p.children()[1];
+        assert_eq!(abbr.attr("title").unwrap(), "a");
+        assert_eq!(abbr.children().len(), 2);
+        assert_eq!(abbr.children()[1].name().unwrap(), "b");
+
+        // 開きタグごとに1つ。位置は名前のtoken
+        let warnings = parser.warnings();
+        assert_eq!(warnings.len(), 3);
+        assert_eq!(
+            warnings[0].message,
+            "legacy element <image> renamed to <img>"
+        );
+        assert_eq!(warnings[0].pos.at_line, 9);
+        assert_eq!(
+            warnings[1].message,
+            "legacy element <acronym> renamed to <abbr>"
+        );
+
+        // 既定では置き換えない (<image> はvoidでないので閉じタグを書く)
+        let html = html.replace("/>", "></image>");
+        let nodes = Parser::new().parse_str(&html).unwrap().unwrap();
+        assert_eq!(nodes[0].children()[1].name().unwrap().as_str(), "acronym");
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn inline_svg_is_foreign_content() {
+        let html = "<div><svg viewBox=\"0 0 10 10\"><defs><linearGradient id=\"g\"><stop offset=\"0\"/></linearGradient></defs><path d=\"M0 0L10 10\"/><image href=\"a.png\"/></svg><P>after</P></div>";
+        let mut parser = Parser::builder()
+            .map_legacy_names()
+            .collect_warnings(true)
+            .build()
+            .unwrap();
+        let nodes = parser.parse_str(html).unwrap().unwrap();
+        assert!(parser.warnings().is_empty());
+        let div = &nodes[0];
+        let svg = &div.children()[0];
+        assert_eq!(svg.namespace(), Some(Namespace::Svg));
+        assert_eq!(svg.attrs()[0].name, "viewBox");
+        // 名前は書かれたまま。"/>" はそこで閉じる
+        let gradient = &svg.children()[0].children()[0];
+        assert_eq!(gradient.name().unwrap().as_str(), "linearGradient");
+        assert_eq!(gradient.namespace(), Some(Namespace::Svg));
+        let path = &svg.children()[1];
+        assert!(path.children().is_empty());
+        assert!(matches!(
+            path.data,
+            NodeData::Element {
+                self_closing: true,
+                ..
+            }
+        ));
+        // <svg> の中の <image> はimgにしない
+        assert_eq!(svg.children()[2].name().unwrap().as_str(), "image");
+        // </svg> の後ろはHTMLに戻る
+        let p = &div.children()[1];
+        assert_eq!(p.name().unwrap().as_str(), "p");
+        assert_eq!(p.namespace(), Some(Namespace::Html));
+        assert_eq!(div.namespace(), Some(Namespace::Html));
+
+        assert_eq!(outer_html(div), html.replace("P>", "p>"));
+        let again = html::parse(&outer_html(div)).unwrap();
+        assert_eq!(outer_html(&again.children[0]), outer_html(div));
+        assert_eq!(
+            to_tree_test_format(&nodes[..1]).lines().nth(1).unwrap(),
+            "|   <svg svg>"
+        );
+
+        // CDATAは中身をtextにする。HTMLでは名前を小文字にする
+        let nodes = html::parse("<math><mi>x</mi><![CDATA[a < b]]></math><B>c</B>")
+            .unwrap()
+            .children;
+        assert_eq!(nodes[0].namespace(), Some(Namespace::MathMl));
+        assert_eq!(nodes[0].children()[0].namespace(), Some(Namespace::MathMl));
+        assert_eq!(nodes[0].children()[1].text_value().unwrap(), "a < b");
+        assert_eq!(nodes[1].name().unwrap().as_str(), "b");
+    }
+
+    #[test]
+    fn debug_is_compact() {
+        let html = "<div id=\"a\" class=\"b\"><p>the quick brown fox jumps over the lazy dog again</p><br/><!--\ttab--></div>";
+        // 語ごとに分けないように、空白を残して読む
+        let nodes = Parser::builder()
+            .preserve_whitespace()
+            .parse(html)
+            .unwrap()
+            .unwrap();
+        let div = &nodes[0];
+        assert_eq!(
+            format!("{:?}", div),
+            "Element(\"div\", 2 attrs, 3 children)"
+        );
+        assert_eq!(
+            format!("{:#?}", div),
+            "Element(\"div\", 2 attrs, 3 children)\n\
+             \x20 Element(\"p\", 0 attrs, 1 child)\n\
+             \x20   Text(\"the quick brown fox jumps over the lazy …\")\n\
+             \x20 Element(\"br\", 0 attrs, 0 children)\n\
+             \x20 Comment(\"\\ttab\")"
+        );
+        // textは切らずに、属性もすべて書く
+        let br = &div.children()[1];
+        assert_eq!(
+            br.debug_full(),
+            "Element {\n    name: \"br\",\n    namespace: Html,\n    attrs: [\n    ],\n    self_closing: true,\n    children: [\n    ],\n}"
+        );
+        assert!(div
+            .debug_full()
+            .contains("Text(\"the quick brown fox jumps over the lazy dog again\")"));
+        assert!(div.debug_full().contains("\"id\": \"a\","));
+    }
+
+    #[test]
+    fn comment_termination() {
+        // (入力, commentの中身, warning)
+        let cases: [(&str, &str, Option<&str>); 9] = [
+            ("<!-- foo -->", " foo ", None),
+            ("<!-- foo --!>", " foo ", Some("incorrectly closed comment")),
+            ("<!-->", "", Some("abrupt closing of empty comment")),
+            ("<!--->", "", Some("abrupt closing of empty comment")),
+            ("<!---->", "", None),
+            ("<!-- a -- b -->", " a -- b ", None),
+            ("<!-- a --->", " a -", None),
+            ("<!--a--!-->", "a--!", None),
+            ("<!-- x = y/z -->", " x = y/z ", None),
+        ];
+        for (html, expected, warning) in cases {
+            let mut parser_ = Parser::builder().collect_warnings(true).build().unwrap();
+            let nodes = parser_.parse_str(html).unwrap().unwrap();
+            assert_eq!(nodes.len(), 1, "{}", html);
+            match &nodes[0].data {
+                NodeData::Comment(s) => assert_eq!(s, expected, "{}", html),
+                data => panic!("{}: unexpected node: {:?}", html, data),
+            }
+            let warnings: Vec<&str> = parser_.warnings().iter().map(|w| w.message).collect();
+            assert_eq!(
+                warnings,
+                warning.into_iter().collect::<Vec<&str>>(),
+                "{}",
+                html
+            );
+        }
+
+        // 終わらないcommentも、最後までを中身にする
+        let mut parser_ = Parser::builder().collect_warnings(true).build().unwrap();
+        let nodes = parser_.parse_str("<!-- open").unwrap().unwrap();
+        assert_eq!(nodes[0].text_value().unwrap(), " open");
+        assert_eq!(parser_.warnings()[0].message, "end of input in comment");
+        // 閉じたあとは続けて読む
+        let nodes = Parser::new()
+            .parse_str("<!-- a --!><p>x</p>")
+            .unwrap()
+            .unwrap();
+        assert_eq!(nodes[1].name().unwrap(), "p");
+    }
+}
@@ -0,0 +1,188 @@
+// 最上位からの子の番号の列で、文書の中のnodeを指す
+// 番号はparseしたままの木で数える。textやコメントも1つと数える
+// (最上位の最初のdoctypeはDocument::doctypeに移るので、childrenには数えない)
+// (html::parseはtextを語ごとに分けるので、"a b" は2つのnodeになる)
+use crate::html::errors::NodePathParseError;
+use crate::html::parser::Node;
+use std::fmt;
+use std::str::FromStr;
+
+// "0.2.1" のように "." でつないで書く。空の列は文書そのもの (nodeではない)
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct NodePath(pub Vec<usize>);
+
+impl NodePath {
+    pub fn indices(&self) -> &[usize] {
+        return &self.0;
+    }
+
+    pub fn depth(&self) -> usize {
+        return self.0.len();
+    }
+
+    pub fn parent(&self) -> Option<NodePath> {
+        let (_, parent) = self.0.split_last()?;
+        return Some(NodePath(parent.to_vec()));
+    }
+
+    pub fn child(&self, index: usize) -> NodePath {
+        let mut indices = self.0.clone();
+        indices.push(index);
+        return NodePath(indices);
+    }
+}
+
+impl From<Vec<usize>> for NodePath {
+    fn from(indices: Vec<usize>) -> NodePath {
+        return NodePath(indices);
+    }
+}
+
+impl fmt::Display for NodePath {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, index) in self.0.iter().enumerate() {
+            if i > 0 {
+                f.write_str(".")?;
+            }
+            write!(f, "{}", index)?;
+        }
+        return Ok(());
+    }
+}
+
+impl FromStr for NodePath {
+    type Err = NodePathParseError;
+
+    fn from_str(s: &str) -> Result<NodePath, NodePathParseError> {
+        if s.is_empty() {
+            return Ok(NodePath::default());
+        }
+        let mut indices = Vec::new();
+        for part in s.split('.') {
+            // "+1" や " 1" はusizeとして読めてしまうので、数字だけを許す
+            if part.is_empty() || !part.bytes().all(|b| b.is_ascii_digit()) {
+                return Err(NodePathParseError {
+                    path: s.to_string(),
+                });
+            }
+            match part.parse() {
+                Ok(index) => indices.push(index),
+                Err(_) => {
+                    return Err(NodePathParseError {
+                        path: s.to_string(),
+                    })
+                }
+            }
+        }
+        return Ok(NodePath(indices));
+    }
+}
+
+// 子の番号を順に辿る。深さの分だけしか見ない
+pub(crate) fn node_at<'a>(roots: &'a [Node], path: &NodePath) -> Option<&'a Node> {
+    let (first, rest) = path.0.split_first()?;
+    let mut nd = roots.get(*first)?;
+    for index in rest {
+        nd = nd.children().get(*index)?;
+    }
+    return Some(nd);
+}
+
+// nodeは同じ場所にあるもの (参照が同じもの) を探す。値が同じだけの別のnodeは見つけない
+pub(crate) fn path_to(roots: &[Node], target: &Node) -> Option<NodePath> {
+    // (node, 親の中の番号, 深さ)。pathは辿っている途中のものを1つだけ持つ
+    let mut stack: Vec<(&Node, usize, usize)> = Vec::new();
+    let mut indices: Vec<usize> = Vec::new();
+    for (i, nd) in roots.iter().enumerate().rev() {
+        stack.push((nd, i, 0));
+    }
+    while let Some((nd, index, depth)) = stack.pop() {
+        indices.truncate(depth);
+        indices.push(index);
+        if std::ptr::eq(nd, target) {
+            return Some(NodePath(indices));
+        }
+        for (i, child) in nd.children().iter().enumerate().rev() {
+            stack.push((child, i, depth + 1));
+        }
+    }
+    return None;
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::html;
+    use crate::html::parser::Node;
+    use crate::html::path::NodePath;
+    use std::fs;
+
+    fn all_nodes(roots: &[Node]) -> Vec<&Node> {
+        let mut out = Vec::new();
+        let mut stack: Vec<&Node> = roots.iter().rev().collect();
+        while let Some(nd) = stack.pop() {
+            out.push(nd);
+            stack.extend(nd.children().iter().rev());
+        }
+        return out;
+    }
+
+    #[test]
+    fn round_trip_every_node_of_fixture() {
+        let path = format!("{}/tests/fixtures/article.html", env!("CARGO_MANIFEST_DIR"));
+        let doc = html::parse(&fs::read_to_string(path).unwrap()).unwrap();
+        let nodes = all_nodes(&doc.children);
+        assert!(nodes.len() > 50);
+        for nd in nodes {
+            let path = doc.path_to(nd).unwrap();
+            assert!(std::ptr::eq(doc.node_at(&path).unwrap(), nd), "{}", path);
+            let parsed: NodePath = path.to_string().parse().unwrap();
+            assert_eq!(parsed, path);
+        }
+        // 別の文書のnodeは見つからない
+        let other = html::parse("<p>x</p>").unwrap();
+        assert_eq!(doc.path_to(&other.children[0]), None);
+    }
+
+    #[test]
+    fn paths_count_every_node_kind() {
+        let doc = html::parse("<!DOCTYPE html><!--c--><div>a b<p>x</p></div>").unwrap();
+        // コメントとtextの語はそれぞれ数え、doctypeは数えない
+        let p = doc.query_selector("p").unwrap().unwrap();
+        assert_eq!(doc.path_to(&p).unwrap().to_string(), "1.2");
+        assert_eq!(NodePath::from(vec![1, 1]).to_string(), "1.1");
+        assert!(doc.node_at(&NodePath::from(vec![1, 1])).unwrap().is_text());
+        assert_eq!(doc.node_at(&NodePath::default()).map(|nd| nd.kind()), None);
+    }
+
+    #[test]
+    fn stale_path_returns_none() {
+        let mut doc = html::parse("<ul><li>a</li><li>b</li></ul>").unwrap();
+        let path: NodePath = "0.1.0".parse().unwrap();
+        assert!(doc.node_at(&path).is_some());
+
+        doc.children[0].children_mut().unwrap().truncate(1);
+        assert!(doc.node_at(&path).is_none());
+        // textの下を指すpath
+        assert!(doc.node_at(&"0.0.0.0".parse().unwrap()).is_none());
+        assert!(doc.node_at(&"9".parse().unwrap()).is_none());
+    }
+
+    #[test]
+    fn parse_dotted_string() {
+        assert_eq!("0.2.1".parse::<NodePath>().unwrap().indices(), &[0, 2, 1]);
+        assert_eq!("".parse::<NodePath>().unwrap(), NodePath::default());
+        for bad in [
+            "1.",
+            ".1",
+            "1..2",
+            "a",
+            "+1",
+            "1. 2",
+            "99999999999999999999999",
+        ] {
+            let err = bad.parse::<NodePath>().unwrap_err();
+            assert_eq!(err.to_string(), format!("invalid node path: {:?}", bad));
+        }
+    }
+}
@@ -0,0 +1,87 @@
+// <script> のうち、実行せずにデータとして埋め込まれているもの
+// <script type="application/json" id="config">{"a": 1}</script> や importmap
+use crate::html::path::NodePath;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ScriptData {
+    pub id: Option<String>,
+    // 書かれたままのtype属性
+    pub mime_type: String,
+    // 中身のtext。文字参照も戻さない
+    pub text: String,
+    // nodeは位置を持たないので、sourceの範囲の代わりに文書の中の場所を返す
+    pub path: NodePath,
+}
+
+// typeがデータのものか。typeがない、module、text/javascriptなどの実行するものはfalse
+pub fn is_data_type(mime_type: &str) -> bool {
+    let mime_type = mime_type
+        .trim_matches(|c: char| c.is_ascii_whitespace())
+        .to_ascii_lowercase();
+    return mime_type == "importmap"
+        || mime_type == "speculationrules"
+        // application/json, application/ld+json, application/json; charset=utf-8 など
+        || mime_type.contains("json");
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::html;
+    use crate::html::path::NodePath;
+    use crate::html::script::is_data_type;
+
+    #[test]
+    fn only_data_scripts_are_returned() {
+        let html = "<html><head>\
+            <script>var a = 1;</script>\
+            <script type=\"module\">import x from \"./x.js\";</script>\
+            <script type=\"text/javascript\">go();</script>\
+            <script type=\"application/json\" id=\"config\">{\"page\": 2, \"tags\": [\"a b\"]}</script>\
+            <script type=\"importmap\">{\"imports\": {}}</script>\
+            </head><body>\
+            <script type=\"Application/LD+JSON\">{\"@type\": \"Article\"}</script>\
+            <script type=\"speculationrules\"></script>\
+            <script type=\"text/template\"><p>x</p></script>\
+            </body></html>";
+        let doc = html::parse(html).unwrap();
+        let scripts = doc.script_data();
+        let types: Vec<&str> = scripts.iter().map(|s| s.mime_type.as_str()).collect();
+        assert_eq!(
+            types,
+            vec![
+                "application/json",
+                "importmap",
+                "Application/LD+JSON",
+                "speculationrules"
+            ]
+        );
+
+        let config = &scripts[0];
+        assert_eq!(config.id.as_deref(), Some("config"));
+        assert_eq!(config.text, "{\"page\": 2, \"tags\": [\"a b\"]}");
+        assert_eq!(config.path, NodePath(vec![0, 0, 3]));
+        assert!(std::ptr::eq(
+            doc.node_at(&config.path).unwrap(),
+            doc.query_selector("#config").unwrap().unwrap().node()
+        ));
+        assert_eq!(scripts[1].id, None);
+        assert_eq!(scripts[3].text, "");
+    }
+
+    #[test]
+    fn data_types() {
+        for data in [
+            "application/json",
+            " application/ld+json ",
+            "IMPORTMAP",
+            "speculationrules",
+            "application/vnd.api+json",
+            "application/json; charset=utf-8",
+        ] {
+            assert!(is_data_type(data), "{}", data);
+        }
+        for executable in ["", "module", "text/javascript", "application/ecmascript"] {
+            assert!(!is_data_type(executable), "{}", executable);
+        }
+    }
+}
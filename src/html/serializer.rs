@@ -0,0 +1,558 @@
+use crate::html::document::Document;
+use crate::html::parser::{Attribute, Namespace, Node, NodeData};
+
+// 中身をそのまま書く要素
+const RAW_TEXT_ELEMENTS: [&str; 2] = ["script", "style"];
+// 空白も中身として扱う要素。字下げし直したり空白を消したりしない
+const PREFORMATTED_ELEMENTS: [&str; 4] = ["pre", "textarea", "script", "style"];
+// 値によらず、あるかないかだけに意味がある属性
+const BOOLEAN_ATTRIBUTES: [&str; 20] = [
+    "allowfullscreen",
+    "async",
+    "autofocus",
+    "autoplay",
+    "checked",
+    "controls",
+    "default",
+    "defer",
+    "disabled",
+    "formnovalidate",
+    "hidden",
+    "ismap",
+    "loop",
+    "multiple",
+    "muted",
+    "novalidate",
+    "open",
+    "readonly",
+    "required",
+    "selected",
+];
+
+// 書き方の細かい選び方。既定はouter_htmlと同じ
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SerializeOptions {
+    // 属性を書かれた順でなく、名前の順に並べる
+    pub sort_attributes: bool,
+    // disabled="" のような真偽の属性を名前だけで書く
+    // (このcrateのparserは値のない属性を読めないので、読み直すものには使わない)
+    pub bare_boolean_attributes: bool,
+    // 読んだときの "/>" によらず、void要素だけを <br/> と書き、ほかは閉じタグを書く
+    pub normalize_self_closing: bool,
+    // 最後に改行を書く
+    pub trailing_newline: bool,
+    // scripting_enabledで読んだ木では <noscript> の中身が1つのtextなので、escapeせずに書く
+    pub scripting_enabled: bool,
+}
+
+impl SerializeOptions {
+    // 同じ形の木なら、元の書き方によらず同じ文字列になる。生成したHTMLを比べるとき用
+    // 文字参照は読んだときに戻っているので、& < > と属性の " だけを書き直す
+    pub fn canonical() -> SerializeOptions {
+        return SerializeOptions {
+            sort_attributes: true,
+            bare_boolean_attributes: true,
+            normalize_self_closing: true,
+            trailing_newline: true,
+            scripting_enabled: false,
+        };
+    }
+}
+
+pub(crate) fn is_preformatted(nd: &Node) -> bool {
+    return nd.name().is_some_and(|name| {
+        PREFORMATTED_ELEMENTS
+            .iter()
+            .any(|pre| name.as_str().eq_ignore_ascii_case(pre))
+    });
+}
+
+enum Step<'a> {
+    Node(&'a Node, bool),
+    Close(&'a str),
+    // parserは空白で区切った語ごとにtextを作るので、並んだtextの間に空白を戻す
+    Space,
+}
+
+// 要素自身を含めたHTML。このcrateのparserでもう一度読める形で書く
+// (子のない <br/> などはself-closingのまま書く)
+pub fn outer_html(nd: &Node) -> String {
+    return write_steps(vec![Step::Node(nd, false)], &SerializeOptions::default());
+}
+
+// optionsに合わせて、並んだnodeを書く
+pub fn serialize(roots: &[Node], options: &SerializeOptions) -> String {
+    let mut stack = Vec::new();
+    push_children(&mut stack, roots, false);
+    let mut out = write_steps(stack, options);
+    if options.trailing_newline {
+        out.push('\n');
+    }
+    return out;
+}
+
+// 要素自身のタグを含めない、子のHTML。<template> は中身も書く
+pub fn inner_html(nd: &Node) -> String {
+    let raw = nd
+        .name()
+        .is_some_and(|name| RAW_TEXT_ELEMENTS.contains(&name.as_str()));
+    let mut stack = Vec::new();
+    let children = nd.template_content().unwrap_or(nd.children());
+    push_children(&mut stack, children, raw);
+    return write_steps(stack, &SerializeOptions::default());
+}
+
+fn push_children<'a>(stack: &mut Vec<Step<'a>>, children: &'a [Node], raw: bool) {
+    for (i, child) in children.iter().enumerate().rev() {
+        stack.push(Step::Node(child, raw));
+        if i > 0 && child.is_text() && children[i - 1].is_text() {
+            stack.push(Step::Space);
+        }
+    }
+}
+
+fn write_steps(mut stack: Vec<Step>, options: &SerializeOptions) -> String {
+    let mut out = String::new();
+    while let Some(step) = stack.pop() {
+        let (nd, raw) = match step {
+            Step::Node(nd, raw) => (nd, raw),
+            Step::Close(name) => {
+                out += &format!("</{}>", name);
+                continue;
+            }
+            Step::Space => {
+                out.push(' ');
+                continue;
+            }
+        };
+        match &nd.data {
+            NodeData::Element {
+                name,
+                attrs,
+                children,
+                self_closing,
+                ..
+            } => {
+                write_start_tag(&mut out, name.as_str(), attrs, options);
+                let self_closing = match options.normalize_self_closing {
+                    true => name.is_void(),
+                    false => *self_closing,
+                };
+                if self_closing && children.is_empty() {
+                    out += "/>";
+                    continue;
+                }
+                out.push('>');
+                stack.push(Step::Close(name.as_str()));
+                let raw = RAW_TEXT_ELEMENTS.contains(&name.as_str())
+                    || (options.scripting_enabled && *name == "noscript");
+                push_children(&mut stack, children, raw);
+            }
+            NodeData::Text(s) if raw => out += s.as_str(),
+            NodeData::Text(s) => out += &escape(s, false),
+            NodeData::Comment(s) => out += &format!("<!--{}-->", s),
+            NodeData::Doctype(doctype) => out += &format!("<!DOCTYPE {}>", doctype.name),
+            // 読んだときの区切りも含めて持っているので、そのまま書けば元に戻る
+            NodeData::Template(s) => out += s.as_str(),
+        }
+    }
+    return out;
+}
+
+// 文書全体のHTML。doctypeがあれば、元の場所によらず先頭に書く
+pub fn document_html(doc: &Document) -> String {
+    return document_html_with(doc, &SerializeOptions::default());
+}
+
+// optionsに合わせたdocument_html
+pub fn document_html_with(doc: &Document, options: &SerializeOptions) -> String {
+    let mut out = String::new();
+    if let Some(doctype) = &doc.doctype {
+        out += &format!("<!DOCTYPE {}>", doctype.name);
+    }
+    out += &serialize(&doc.children, options);
+    return out;
+}
+
+enum PrettyStep<'a> {
+    // (node, 深さ, 直前の兄弟も空白でないtextか)
+    Node(&'a Node, usize, bool),
+    Close(&'a str, usize),
+}
+
+// 1行に1つのnodeを書き、深さに合わせて字下げする。空白だけのtextは書かない
+// 語ごとに分けたtextは1行につなぐ。preなどの中は空白も中身なので、outer_htmlのまま書く
+pub fn pretty_html(roots: &[Node]) -> String {
+    let mut out = String::new();
+    let mut stack: Vec<PrettyStep> = Vec::new();
+    push_pretty_children(&mut stack, roots, 0);
+    while let Some(step) = stack.pop() {
+        let (nd, depth, joined) = match step {
+            PrettyStep::Node(nd, depth, joined) => (nd, depth, joined),
+            PrettyStep::Close(name, depth) => {
+                out += &format!("{}</{}>\n", "  ".repeat(depth), name);
+                continue;
+            }
+        };
+        let indent = "  ".repeat(depth);
+        match &nd.data {
+            NodeData::Element {
+                name,
+                attrs,
+                children,
+                ..
+            } if !children.is_empty() && !is_preformatted(nd) => {
+                out += &indent;
+                write_start_tag(&mut out, name.as_str(), attrs, &SerializeOptions::default());
+                out += ">\n";
+                stack.push(PrettyStep::Close(name.as_str(), depth));
+                push_pretty_children(&mut stack, children, depth + 1);
+            }
+            NodeData::Text(s) if joined => {
+                out.pop();
+                out += &format!(" {}\n", escape(s.trim(), false));
+            }
+            NodeData::Text(s) => out += &format!("{}{}\n", indent, escape(s.trim(), false)),
+            _ => out += &format!("{}{}\n", indent, outer_html(nd)),
+        }
+    }
+    return out;
+}
+
+fn push_pretty_children<'a>(stack: &mut Vec<PrettyStep<'a>>, children: &'a [Node], depth: usize) {
+    let mut visible: Vec<&Node> = children.iter().filter(|nd| !nd.is_blank()).collect();
+    visible.reverse();
+    for (i, child) in visible.iter().enumerate() {
+        let joined = child.is_text() && visible.get(i + 1).is_some_and(|prev| prev.is_text());
+        stack.push(PrettyStep::Node(child, depth, joined));
+    }
+}
+
+// "<name" と属性。">" や "/>" は呼ぶ側で書く
+fn write_start_tag(out: &mut String, name: &str, attrs: &[Attribute], options: &SerializeOptions) {
+    *out += &format!("<{}", name);
+    let mut attrs: Vec<&Attribute> = attrs.iter().collect();
+    if options.sort_attributes {
+        attrs.sort_by(|a, b| a.name.as_str().cmp(b.name.as_str()));
+    }
+    for attr in attrs {
+        let name = attr.name.as_str();
+        if options.bare_boolean_attributes
+            && BOOLEAN_ATTRIBUTES
+                .iter()
+                .any(|known| name.eq_ignore_ascii_case(known))
+        {
+            *out += &format!(" {}", attr.name);
+            continue;
+        }
+        *out += &format!(" {}=\"{}\"", attr.name, escape(&attr.value, true));
+    }
+}
+
+// html5lib-testsのtree-constructionの #document の形
+// | <html>
+// |   <body>
+// |     <p>
+// |       class="a"
+// |       "text"
+// 属性は名前の順に並べ、textはそのまま引用符で囲む
+pub fn to_tree_test_format(nodes: &[Node]) -> String {
+    let mut out = String::new();
+    // (node, 深さ, 直前の兄弟もtextか)
+    let mut stack: Vec<(&Node, usize, bool)> = Vec::new();
+    push_tree_children(&mut stack, nodes, 0);
+    while let Some((nd, depth, joined)) = stack.pop() {
+        let indent = "  ".repeat(depth);
+        match &nd.data {
+            NodeData::Element {
+                name,
+                attrs,
+                children,
+                namespace,
+                ..
+            } => {
+                // html5libと同じく、foreignの要素には名前空間を前に付ける
+                let prefix = match namespace {
+                    Namespace::Html => "",
+                    Namespace::Svg => "svg ",
+                    Namespace::MathMl => "math ",
+                };
+                out += &format!("| {}<{}{}>\n", indent, prefix, name);
+                let mut attrs: Vec<_> = attrs.iter().collect();
+                attrs.sort_by(|a, b| a.name.as_str().cmp(b.name.as_str()));
+                for attr in attrs {
+                    out += &format!("| {}  {}=\"{}\"\n", indent, attr.name, attr.value);
+                }
+                // templateの中身は "content" の下に書く
+                match nd.template_content() {
+                    Some(content) => {
+                        out += &format!("| {}  content\n", indent);
+                        push_tree_children(&mut stack, content, depth + 2);
+                    }
+                    None => push_tree_children(&mut stack, children, depth + 1),
+                }
+            }
+            // 前のtextに続けて、語の間の空白を戻す
+            NodeData::Text(s) if joined => {
+                out.truncate(out.len() - "\"\n".len());
+                out += &format!(" {}\"\n", s);
+            }
+            NodeData::Text(s) => out += &format!("| {}\"{}\"\n", indent, s),
+            NodeData::Comment(s) => out += &format!("| {}<!-- {} -->\n", indent, s),
+            NodeData::Doctype(doctype) => {
+                out += &format!("| {}<!DOCTYPE {}>\n", indent, doctype.name)
+            }
+            NodeData::Template(s) => out += &format!("| {}{}\n", indent, s),
+        }
+    }
+    return out;
+}
+
+// to_tree_test_formatの先頭にdoctypeを足す
+pub fn document_tree_test_format(doc: &Document) -> String {
+    let mut out = String::new();
+    if let Some(doctype) = &doc.doctype {
+        out += &format!("| <!DOCTYPE {}>\n", doctype.name);
+    }
+    out += &to_tree_test_format(&doc.children);
+    return out;
+}
+
+fn push_tree_children<'a>(
+    stack: &mut Vec<(&'a Node, usize, bool)>,
+    children: &'a [Node],
+    depth: usize,
+) {
+    for (i, child) in children.iter().enumerate().rev() {
+        let joined = i > 0 && child.is_text() && children[i - 1].is_text();
+        stack.push((child, depth, joined));
+    }
+}
+
+// 木を罫線で描く。属性は書かれた順、textは引用符で囲む
+// <p class="a">
+// ├── "hello"
+// └── <b>
+//     └── "world"
+pub fn to_ascii_tree(roots: &[Node]) -> String {
+    let mut out = String::new();
+    // (node, 前に付ける罫線, 自分の罫線)
+    let mut stack: Vec<(&Node, String, &str)> = roots
+        .iter()
+        .rev()
+        .map(|nd| (nd, String::new(), ""))
+        .collect();
+    while let Some((nd, prefix, branch)) = stack.pop() {
+        out += &format!("{}{}{}\n", prefix, branch, tree_label(nd));
+        let prefix = match branch {
+            "├── " => prefix + "│   ",
+            "└── " => prefix + "    ",
+            _ => prefix,
+        };
+        let children = nd.children();
+        for (i, child) in children.iter().enumerate().rev() {
+            let branch = if i + 1 == children.len() {
+                "└── "
+            } else {
+                "├── "
+            };
+            stack.push((child, prefix.clone(), branch));
+        }
+    }
+    return out;
+}
+
+// to_ascii_treeの先頭にdoctypeを足す
+pub fn document_ascii_tree(doc: &Document) -> String {
+    let mut out = String::new();
+    if let Some(doctype) = &doc.doctype {
+        out += &format!("<!DOCTYPE {}>\n", doctype.name);
+    }
+    out += &to_ascii_tree(&doc.children);
+    return out;
+}
+
+fn tree_label(nd: &Node) -> String {
+    return match &nd.data {
+        NodeData::Element { name, attrs, .. } => {
+            let attrs: String = attrs
+                .iter()
+                .map(|attr| format!(" {}={:?}", attr.name, attr.value.as_str()))
+                .collect();
+            format!("<{}{}>", name, attrs)
+        }
+        NodeData::Text(s) => format!("{:?}", s.as_str()),
+        NodeData::Comment(s) => format!("<!--{}-->", s),
+        NodeData::Doctype(doctype) => format!("<!DOCTYPE {}>", doctype.name),
+        NodeData::Template(s) => s.to_string(),
+    };
+}
+
+fn escape(s: &str, attr: bool) -> String {
+    let mut out = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '&' => out += "&amp;",
+            '<' if !attr => out += "&lt;",
+            '>' if !attr => out += "&gt;",
+            '"' if attr => out += "&quot;",
+            _ => out.push(c),
+        }
+    }
+    return out;
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::html;
+    use crate::html::atom::Atom;
+    use crate::html::attributes::Attributes;
+    use crate::html::parser::{Attribute, Node, NodeData, NodeKind, Parser};
+    use crate::html::serializer::{
+        document_html_with, document_tree_test_format, outer_html, pretty_html, serialize,
+        to_tree_test_format, SerializeOptions,
+    };
+    use crate::html::shared_str::SharedStr;
+    use crate::html::tokenizer::{TemplateDelimiter, TokenizerOptions};
+
+    #[test]
+    fn serialize_elements() {
+        let doc = html::parse(
+            "<div id=\"a\" class=\"b c\"><p>hello big world</p><br/><!-- note --><script>if (a) {}</script></div>",
+        )
+        .unwrap();
+        assert_eq!(
+            outer_html(&doc.children[0]),
+            "<div id=\"a\" class=\"b c\"><p>hello big world</p><br/><!-- note --><script>if (a) {}</script></div>"
+        );
+        let p = doc.query_selector("p").unwrap().unwrap();
+        assert_eq!(outer_html(&p), "<p>hello big world</p>");
+    }
+
+    #[test]
+    fn templates_round_trip() {
+        let parser = || {
+            return Parser::builder().tokenizer(TokenizerOptions {
+                template_passthrough: TemplateDelimiter::builtin(),
+                ..TokenizerOptions::default()
+            });
+        };
+        // 値の中のPHPは値の文字列の一部になる
+        let php = "<a href=\"<?php echo $base ?>/item/<?= $id ?>\" class=\"<?php if ($n > 1) echo $cls ?>\">link</a>";
+        let nodes = parser().parse(php).unwrap().unwrap();
+        assert_eq!(
+            nodes[0].attr("href").unwrap(),
+            "<?php echo $base ?>/item/<?= $id ?>"
+        );
+        assert_eq!(outer_html(&nodes[0]), php);
+
+        // 要素の間のERBはTemplateのnodeになる
+        let erb = "<ul><li>a</li><% items.each do |item| %><li><%= item.name %></li><% end %></ul>";
+        let nodes = parser().parse(erb).unwrap().unwrap();
+        let ul = &nodes[0];
+        assert_eq!(ul.children()[1].kind(), NodeKind::Template);
+        assert!(matches!(
+            &ul.children()[1].data,
+            NodeData::Template(s) if s == "<% items.each do |item| %>"
+        ));
+        assert_eq!(ul.children()[2].children()[0].kind(), NodeKind::Template);
+        assert_eq!(outer_html(ul), erb);
+        assert_eq!(
+            to_tree_test_format(&nodes),
+            "| <ul>\n\
+             |   <li>\n\
+             |     \"a\"\n\
+             |   <% items.each do |item| %>\n\
+             |   <li>\n\
+             |     <%= item.name %>\n\
+             |   <% end %>\n"
+        );
+    }
+
+    #[test]
+    fn tree_test_format() {
+        let doc = html::parse(
+            "<!DOCTYPE html><html><body><p id=\"x\" class=\"a\">one two</p><!--c--></body></html>",
+        )
+        .unwrap();
+        assert_eq!(
+            document_tree_test_format(&doc),
+            "| <!DOCTYPE html>\n\
+             | <html>\n\
+             |   <body>\n\
+             |     <p>\n\
+             |       class=\"a\"\n\
+             |       id=\"x\"\n\
+             |       \"one two\"\n\
+             |     <!-- c -->\n"
+        );
+    }
+
+    #[test]
+    fn pretty_html_skips_blank_text() {
+        let html = "<div id=\"a\">\n  <p>hello <b>big</b> world</p>\n\n  <br/><pre>  keep\n  me </pre>\n</div>";
+        let expected = "<div id=\"a\">\n  <p>\n    hello\n    <b>\n      big\n    </b>\n    world\n  </p>\n  <br/>\n  <pre>  keep\n  me </pre>\n</div>\n";
+        let nodes = Parser::builder()
+            .preserve_whitespace()
+            .parse(html)
+            .unwrap()
+            .unwrap();
+        assert_eq!(pretty_html(&nodes), expected);
+        // 語ごとに分けたtextも1行につなぐ
+        let doc = html::parse("<p>one two <i>three</i></p>").unwrap();
+        assert_eq!(
+            pretty_html(&doc.children),
+            "<p>\n  one two\n  <i>\n    three\n  </i>\n</p>\n"
+        );
+    }
+
+    #[test]
+    fn escape_text_and_attributes() {
+        let mut attrs = Attributes::new();
+        attrs.push(Attribute {
+            name: SharedStr::from("title"),
+            value: SharedStr::from("a \"b\" & <c>"),
+        });
+        let nd = Node::element(
+            Atom::from("span"),
+            attrs,
+            vec![Node::text(SharedStr::from("1 < 2 & 3 > 2"))],
+        );
+        assert_eq!(
+            outer_html(&nd),
+            "<span title=\"a &quot;b&quot; &amp; <c>\">1 &lt; 2 &amp; 3 &gt; 2</span>"
+        );
+    }
+
+    #[test]
+    fn canonical_output_ignores_formatting() {
+        let a = "<!DOCTYPE html><div id=\"a\" class=\"x\"><input type=\"checkbox\" checked=\"checked\" disabled=\"\"/><p title=\"a &amp; b\">hello world</p><span></span></div>";
+        let b = "<!DOCTYPE html>\n<div class='x'   id=\"a\">\n  <input disabled=\"disabled\" type=\"checkbox\" checked=\"\"></input>\n  <p title=\"a &#38; b\">hello\n    world</p><span></span>\n</div>\n";
+        let canonical = SerializeOptions::canonical();
+        let a = document_html_with(&html::parse(a).unwrap(), &canonical);
+        let b = document_html_with(&html::parse(b).unwrap(), &canonical);
+        assert_eq!(a, b);
+        assert_eq!(
+            a,
+            "<!DOCTYPE html><div class=\"x\" id=\"a\"><input checked disabled type=\"checkbox\"/><p title=\"a &amp; b\">hello world</p><span></span></div>\n"
+        );
+    }
+
+    #[test]
+    fn canonical_output_is_stable() {
+        let html = "<ul data-b=\"2\" data-a=\"1\"><li>one<br/>two</li><!-- note --><li hidden=\"\">three</li></ul>";
+        let nodes = html::parse(html).unwrap().children;
+        let canonical = SerializeOptions::canonical();
+        let first = serialize(&nodes, &canonical);
+        assert_eq!(first, serialize(&nodes, &canonical));
+        assert_eq!(
+            first,
+            "<ul data-a=\"1\" data-b=\"2\"><li>one<br/>two</li><!-- note --><li hidden>three</li></ul>\n"
+        );
+        // 既定はouter_htmlと同じ
+        assert_eq!(
+            serialize(&nodes, &SerializeOptions::default()),
+            outer_html(&nodes[0])
+        );
+    }
+}
@@ -0,0 +1,209 @@
+use alloc::string::{String, ToString};
+use alloc::sync::Arc;
+use core::fmt;
+use core::ops::Deref;
+
+#[derive(Clone)]
+enum Buf {
+    Static(&'static str),
+    Shared(Arc<str>),
+}
+
+// 元の文字列を共有したまま、その一部分を指す文字列
+// tokenからnodeへ渡すときはポインタのコピーだけで済む
+#[derive(Clone)]
+pub struct SharedStr {
+    buf: Buf,
+    start: usize,
+    end: usize,
+}
+
+impl Default for SharedStr {
+    fn default() -> SharedStr {
+        return SharedStr::from_static("");
+    }
+}
+
+impl SharedStr {
+    pub fn new(buf: Arc<str>, start: usize, end: usize) -> SharedStr {
+        return SharedStr {
+            buf: Buf::Shared(buf),
+            start,
+            end,
+        };
+    }
+
+    pub fn from_static(s: &'static str) -> SharedStr {
+        return SharedStr {
+            buf: Buf::Static(s),
+            start: 0,
+            end: s.len(),
+        };
+    }
+
+    // 隣り合った部分文字列ならコピーせずにつなげる
+    pub fn concat(&self, other: &SharedStr) -> SharedStr {
+        if self.is_empty() {
+            return other.clone();
+        }
+        if other.is_empty() {
+            return self.clone();
+        }
+        if let (Buf::Shared(a), Buf::Shared(b)) = (&self.buf, &other.buf) {
+            if Arc::ptr_eq(a, b) && self.end == other.start {
+                return SharedStr::new(a.clone(), self.start, other.end);
+            }
+        }
+        return SharedStr::from(self.as_str().to_string() + other.as_str());
+    }
+
+    // もともと小文字ならコピーしない
+    pub fn to_ascii_lowercase(&self) -> SharedStr {
+        if !self.bytes().any(|b| b.is_ascii_uppercase()) {
+            return self.clone();
+        }
+        let mut owned = self.as_str().to_string();
+        owned.make_ascii_lowercase();
+        return SharedStr::from(owned);
+    }
+
+    // 指しているbufの場所と大きさ。staticな文字列はheapにないのでNone
+    #[cfg(feature = "parser")]
+    pub(crate) fn heap_buffer(&self) -> Option<(usize, usize)> {
+        return match &self.buf {
+            Buf::Static(_) => None,
+            Buf::Shared(buf) => Some((Arc::as_ptr(buf) as *const u8 as usize, buf.len())),
+        };
+    }
+
+    pub fn as_str(&self) -> &str {
+        return match &self.buf {
+            Buf::Static(s) => &s[self.start..self.end],
+            Buf::Shared(buf) => &buf[self.start..self.end],
+        };
+    }
+}
+
+impl Deref for SharedStr {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        return self.as_str();
+    }
+}
+
+impl From<String> for SharedStr {
+    fn from(s: String) -> SharedStr {
+        // 空文字列のためにわざわざ確保しない
+        if s.is_empty() {
+            return SharedStr::default();
+        }
+        let end = s.len();
+        return SharedStr::new(Arc::from(s), 0, end);
+    }
+}
+
+impl From<&str> for SharedStr {
+    fn from(s: &str) -> SharedStr {
+        return SharedStr::from(s.to_string());
+    }
+}
+
+impl PartialEq for SharedStr {
+    fn eq(&self, other: &SharedStr) -> bool {
+        return self.as_str() == other.as_str();
+    }
+}
+
+impl PartialEq<str> for SharedStr {
+    fn eq(&self, other: &str) -> bool {
+        return self.as_str() == other;
+    }
+}
+
+impl PartialEq<&str> for SharedStr {
+    fn eq(&self, other: &&str) -> bool {
+        return self.as_str() == *other;
+    }
+}
+
+impl PartialEq<String> for SharedStr {
+    fn eq(&self, other: &String) -> bool {
+        return self.as_str() == other.as_str();
+    }
+}
+
+impl fmt::Debug for SharedStr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        return fmt::Debug::fmt(self.as_str(), f);
+    }
+}
+
+impl fmt::Display for SharedStr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        return fmt::Display::fmt(self.as_str(), f);
+    }
+}
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for SharedStr {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        return serializer.serialize_str(self.as_str());
+    }
+}
+
+// 元の文字列はもうないので、自分の分だけを持つ
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for SharedStr {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<SharedStr, D::Error> {
+        let s: String = serde::Deserialize::deserialize(deserializer)?;
+        return Ok(SharedStr::from(s));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::html::shared_str::SharedStr;
+    use std::sync::Arc;
+
+    #[test]
+    fn shared_str_slice() {
+        let buf: Arc<str> = Arc::from("<p>hello</p>");
+        let s = SharedStr::new(buf.clone(), 3, 8);
+        assert_eq!(s, "hello");
+        assert_eq!(Arc::strong_count(&buf), 2);
+        assert_eq!(SharedStr::from(""), SharedStr::default());
+    }
+
+    #[test]
+    fn shared_str_concat() {
+        let buf: Arc<str> = Arc::from("hello, world");
+        let a = SharedStr::new(buf.clone(), 0, 5);
+        let b = SharedStr::new(buf.clone(), 5, 6);
+        let c = SharedStr::new(buf.clone(), 7, 12);
+
+        // 隣り合っていれば同じbufを指したまま
+        let ab = a.concat(&b);
+        assert_eq!(ab, "hello,");
+        assert_eq!(Arc::strong_count(&buf), 5);
+
+        // 離れていればコピーする
+        let ac = a.concat(&c);
+        assert_eq!(ac, "helloworld");
+        assert_eq!(Arc::strong_count(&buf), 5);
+    }
+
+    #[test]
+    fn shared_str_to_ascii_lowercase() {
+        let buf: Arc<str> = Arc::from("<Div><span>");
+        let upper = SharedStr::new(buf.clone(), 1, 4);
+        let lower = SharedStr::new(buf.clone(), 6, 10);
+
+        assert_eq!(upper.to_ascii_lowercase(), "div");
+        assert_eq!(Arc::strong_count(&buf), 3);
+        // 小文字だけならbufを指したまま
+        let same = lower.to_ascii_lowercase();
+        assert_eq!(same, "span");
+        assert_eq!(Arc::strong_count(&buf), 4);
+    }
+}
@@ -0,0 +1,46 @@
+// テストの出力を tests/snapshots/<name>.snap と比べる
+// UPDATE_SNAPSHOTS=1 で実行すると、比べずに書き直す。fileがなければ作って失敗する
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+
+pub fn assert_snapshot(name: &str, actual: &str) {
+    let path: PathBuf = [env!("CARGO_MANIFEST_DIR"), "tests", "snapshots"]
+        .iter()
+        .collect::<PathBuf>()
+        .join(format!("{}.snap", name));
+    let update = env::var("UPDATE_SNAPSHOTS").is_ok_and(|v| v == "1");
+    let expected = fs::read_to_string(&path).ok();
+    if update || expected.is_none() {
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(&path, actual).unwrap();
+        assert!(update, "new snapshot written: {}", path.display());
+        return;
+    }
+    let expected = expected.unwrap();
+    if expected != actual {
+        let diff: Vec<String> = diff_lines(&expected, actual);
+        panic!(
+            "snapshot {} does not match (rerun with UPDATE_SNAPSHOTS=1 to accept)\n{}",
+            path.display(),
+            diff.join("\n")
+        );
+    }
+}
+
+// 先頭と末尾の同じ行を除いた、違う部分だけ
+fn diff_lines(expected: &str, actual: &str) -> Vec<String> {
+    let a: Vec<&str> = expected.lines().collect();
+    let b: Vec<&str> = actual.lines().collect();
+    let head = a.iter().zip(&b).take_while(|(x, y)| x == y).count();
+    let tail = a[head..]
+        .iter()
+        .rev()
+        .zip(b[head..].iter().rev())
+        .take_while(|(x, y)| x == y)
+        .count();
+    let mut out = vec![format!("@@ line {} @@", head + 1)];
+    out.extend(a[head..a.len() - tail].iter().map(|l| format!("-{}", l)));
+    out.extend(b[head..b.len() - tail].iter().map(|l| format!("+{}", l)));
+    return out;
+}
@@ -0,0 +1,114 @@
+// Document::statsで返す、木の形の数字。クローラの監視などで、parseの後に安く見るためのもの
+use crate::html::parser::{Node, NodeData, NodeKind};
+use alloc::collections::BTreeMap;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct DocumentStats {
+    // doctypeも含めたnodeの数 (属性は数えない)
+    pub nodes: usize,
+    pub nodes_by_kind: BTreeMap<NodeKind, usize>,
+    // 名前ごとの要素の数。<svg> の中の名前は書かれたまま
+    pub elements_by_name: BTreeMap<String, usize>,
+    // 要素のいちばん深い入れ子。最上位の要素が1
+    pub max_depth: usize,
+    // textの長さ (byte)
+    pub text_bytes: usize,
+    pub attributes: usize,
+    pub comments: usize,
+}
+
+impl DocumentStats {
+    pub fn count(&self, kind: NodeKind) -> usize {
+        return self.nodes_by_kind.get(&kind).copied().unwrap_or(0);
+    }
+
+    fn add(&mut self, kind: NodeKind) {
+        self.nodes += 1;
+        *self.nodes_by_kind.entry(kind).or_default() += 1;
+    }
+
+    // 同じ名前の2つ目からは文字列を作らない
+    fn add_element(&mut self, name: &str) {
+        match self.elements_by_name.get_mut(name) {
+            Some(count) => *count += 1,
+            None => {
+                self.elements_by_name.insert(name.to_string(), 1);
+            }
+        }
+    }
+}
+
+// 深い木でも再帰しないように、自前のスタックで一度だけ辿る
+// <template> の中身も、読んだものとして数える
+pub(crate) fn collect(doctype: bool, roots: &[Node]) -> DocumentStats {
+    let mut stats = DocumentStats::default();
+    if doctype {
+        stats.add(NodeKind::Doctype);
+    }
+    let mut stack: Vec<(&Node, usize)> = roots.iter().map(|nd| (nd, 1)).collect();
+    while let Some((nd, depth)) = stack.pop() {
+        stats.add(nd.kind());
+        match &nd.data {
+            NodeData::Element { name, attrs, .. } => {
+                stats.max_depth = stats.max_depth.max(depth);
+                stats.attributes += attrs.len();
+                stats.add_element(name.as_str());
+            }
+            NodeData::Text(s) => stats.text_bytes += s.len(),
+            NodeData::Comment(_) => stats.comments += 1,
+            _ => {}
+        }
+        let children = nd.template_content().unwrap_or(nd.children());
+        stack.extend(children.iter().map(|child| (child, depth + 1)));
+    }
+    return stats;
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use crate::html;
+    use crate::html::parser::NodeKind;
+
+    #[test]
+    fn login_fixture_stats() {
+        let path = format!("{}/tests/fixtures/login.html", env!("CARGO_MANIFEST_DIR"));
+        let html = std::fs::read_to_string(path).unwrap();
+        let stats = html::parse(&html).unwrap().stats();
+        // textは語ごとに分かれる ("Keep me signed in" は4つ)。<title> と <style> と <script> の中は1つ
+        assert_eq!(stats.count(NodeKind::Element), 15);
+        assert_eq!(stats.count(NodeKind::Text), 11);
+        assert_eq!(stats.count(NodeKind::Doctype), 1);
+        assert_eq!(stats.count(NodeKind::Comment), 0);
+        assert_eq!(stats.nodes, 27);
+        assert_eq!(stats.comments, 0);
+        // html > body > form > label > input
+        assert_eq!(stats.max_depth, 5);
+        assert_eq!(stats.attributes, 15);
+        assert_eq!(stats.elements_by_name["input"], 4);
+        assert_eq!(stats.elements_by_name["label"], 3);
+        assert_eq!(stats.elements_by_name.len(), 10);
+        assert!(!stats.elements_by_name.contains_key("p"));
+        assert_eq!(stats.text_bytes, 250);
+    }
+
+    #[test]
+    fn deep_and_flat_documents() {
+        let depth = 50_000;
+        let html = format!("{}x{}", "<div>".repeat(depth), "</div>".repeat(depth));
+        let stats = html::parse(&html).unwrap().stats();
+        assert_eq!(stats.max_depth, depth);
+        assert_eq!(stats.nodes, depth + 1);
+        assert_eq!(stats.elements_by_name["div"], depth);
+
+        let width = 50_000;
+        let html = "<p id=\"a\"><!--c--></p>".repeat(width);
+        let stats = html::parse(&html).unwrap().stats();
+        assert_eq!(stats.max_depth, 1);
+        assert_eq!(stats.nodes, width * 2);
+        assert_eq!(stats.comments, width);
+        assert_eq!(stats.attributes, width);
+        assert_eq!(stats.text_bytes, 0);
+    }
+}
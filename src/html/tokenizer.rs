@@ -1,8 +1,17 @@
+use crate::html::atom::Atom;
 use crate::html::position::Position;
-use crate::html::tokenizer::TokenKind::{Eof,  Text, Whitespace};
-use std::str::Chars;
+use crate::html::shared_str::SharedStr;
+use crate::html::tokenizer::TokenKind::{Eof, Text, Whitespace};
+use crate::html::trace::{enter_span, recovery};
+use alloc::boxed::Box;
+use alloc::string::String;
+use alloc::sync::Arc;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::ops::ControlFlow;
 
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum TokenKind {
     Illegal,
     Eof,
@@ -17,19 +26,21 @@ pub enum TokenKind {
     Amp,
 
     String,
-    Text
+    Text,
+    // テンプレートの区切りから区切りまで。区切りも含めてそのまま持つ
+    Template,
 }
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct Token {
     pub kind: TokenKind,
     pub pos: Position,
-    pub s: String,
+    pub s: SharedStr,
     pub next: Option<Box<Token>>,
 }
 
 impl Token {
-    pub fn new(kind: TokenKind, pos: Position, s: String) -> Token {
+    pub fn new(kind: TokenKind, pos: Position, s: SharedStr) -> Token {
         return Token {
             kind,
             pos,
@@ -39,21 +50,18 @@ impl Token {
     }
 }
 
-fn is_alphanum_(c: char) -> bool {
-    return c.is_alphanumeric() || c == '_';
+// 長いtoken列でも再帰しないように、nextを順に外してから捨てる
+impl Drop for Token {
+    fn drop(&mut self) {
+        let mut next = self.next.take();
+        while let Some(mut tok) = next {
+            next = tok.next.take();
+        }
+    }
 }
 
-fn is_number(c: char) -> bool {
-    return c == '0'
-        || c == '1'
-        || c == '2'
-        || c == '3'
-        || c == '4'
-        || c == '5'
-        || c == '6'
-        || c == '7'
-        || c == '8'
-        || c == '9';
+fn is_alphanum_(c: char) -> bool {
+    return c.is_alphanumeric() || c == '_';
 }
 
 fn is_ws(c: char) -> bool {
@@ -61,251 +69,646 @@ fn is_ws(c: char) -> bool {
 }
 
 fn is_reserved_symbol(c: char) -> bool {
-    let symbols: Vec<&str> = vec!["<", ">", "!", "=", "-", "/", "&"];
-    for s in symbols {
-        if s == c.to_string() {
-            return true;
-        }
+    return matches!(c, '<' | '>' | '!' | '=' | '-' | '/' | '&');
+}
+
+impl TokenKind {
+    // 記号のtokenは中身 (s) が空なので、元の文字はここから戻す
+    pub fn symbol(&self) -> Option<char> {
+        return match self {
+            TokenKind::TagBegin => Some('<'),
+            TokenKind::TagEnd => Some('>'),
+            TokenKind::Excl => Some('!'),
+            TokenKind::Assign => Some('='),
+            TokenKind::Hyphen => Some('-'),
+            TokenKind::Slash => Some('/'),
+            TokenKind::Amp => Some('&'),
+            _ => None,
+        };
     }
-    return false;
 }
 
-fn str_to_symbol_kind(s: String) -> TokenKind {
-    return match s.as_str() {
-        "<" => TokenKind::TagBegin,
-        ">" => TokenKind::TagEnd,
-        "!" => TokenKind::Excl,
-        "=" => TokenKind::Assign,
-        "-" => TokenKind::Hyphen,
-        "/" => TokenKind::Slash,
-        "&" => TokenKind::Amp,
-        _ => TokenKind::Illegal
+fn char_to_symbol_kind(c: char) -> TokenKind {
+    return match c {
+        '<' => TokenKind::TagBegin,
+        '>' => TokenKind::TagEnd,
+        '!' => TokenKind::Excl,
+        '=' => TokenKind::Assign,
+        '-' => TokenKind::Hyphen,
+        '/' => TokenKind::Slash,
+        '&' => TokenKind::Amp,
+        _ => TokenKind::Illegal,
+    };
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TokenizerOptions {
+    // Whitespaceのtokenに空白そのものを持たせる (ふつうは空)
+    pub keep_whitespace: bool,
+    // <noscript> の中身もscriptと同じく1つのtextとして読む
+    pub raw_noscript: bool,
+    // <?php ... ?> のようなサーバー側のテンプレートを、タグとして読まずにTemplateのtokenにする
+    // 空ならテンプレートを区別しない
+    pub template_passthrough: Vec<TemplateDelimiter>,
+}
+
+// テンプレートの開きと閉じの区切り
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TemplateDelimiter {
+    pub open: String,
+    pub close: String,
+}
+
+impl TemplateDelimiter {
+    pub fn new(open: &str, close: &str) -> TemplateDelimiter {
+        return TemplateDelimiter {
+            open: String::from(open),
+            close: String::from(close),
+        };
+    }
+
+    // PHPの <? ... ?> とERBやASPの <% ... %>
+    // {{ ... }} はタグを壊さないので入れない。要るならnewで足す
+    pub fn builtin() -> Vec<TemplateDelimiter> {
+        return vec![
+            TemplateDelimiter::new("<?", "?>"),
+            TemplateDelimiter::new("<%", "%>"),
+        ];
     }
 }
 
-pub struct Tokenizer {
-    target: String,
+pub struct Tokenizer<'a> {
+    // まだ読んでいない部分。from_chunksなら、読みかけのtokenと次のchunkだけを持つ
+    target: Arc<str>,
+    byte: usize,
+    pos: Position,
+    last_kind: TokenKind,
+    // scriptなどの開きタグの名前。そのタグの">"まで覚えておく
+    raw_tag: Option<SharedStr>,
+    // 直前のtokenがタグの名前。script-x のように - が続けば別の要素
+    after_tag_name: bool,
+    // < から > までの中。引用符で値の文字列を始めるのはこの中だけ (it's はただのtext)
+    in_tag: bool,
+    // 次は</raw_until までを一つのテキストとして読む
+    raw_until: Option<SharedStr>,
+    // まだ読んでいないchunk。使い切ったらNone
+    chunks: Option<Box<dyn Iterator<Item = String> + 'a>>,
+    options: TokenizerOptions,
+    // 読みかけのtokenを新しい列に写したバイト数の合計
+    #[cfg(test)]
+    carried_bytes: usize,
+}
+
+// tokenを読み直すときに戻す状態
+struct Mark {
+    byte: usize,
     pos: Position,
+    last_kind: TokenKind,
+    raw_tag: Option<SharedStr>,
+    after_tag_name: bool,
+    in_tag: bool,
+    raw_until: Option<SharedStr>,
 }
 
-impl Tokenizer {
-    pub fn new(target: &str) -> Tokenizer {
+impl<'a> Tokenizer<'a> {
+    pub fn new(target: &str) -> Tokenizer<'a> {
+        return Tokenizer::from_shared(Arc::from(target));
+    }
+
+    pub fn with_options(target: &str, options: TokenizerOptions) -> Tokenizer<'a> {
+        let mut tokenizer = Tokenizer::new(target);
+        tokenizer.options = options;
+        return tokenizer;
+    }
+
+    pub(crate) fn from_shared(target: Arc<str>) -> Tokenizer<'a> {
         return Tokenizer {
-            target: target.to_string(),
+            target,
+            byte: 0,
             pos: Position::new(1, 0, 0),
+            last_kind: TokenKind::Illegal,
+            raw_tag: None,
+            after_tag_name: false,
+            in_tag: false,
+            raw_until: None,
+            chunks: None,
+            options: TokenizerOptions::default(),
+            #[cfg(test)]
+            carried_bytes: 0,
         };
     }
 
-    fn is_eof(&self) -> bool {
-        return self.pos.at_whole >= self.target.len() as u32;
+    // 文書の途中から切り出した部分を読む。位置はposから数えるので、元の文書での位置になる
+    #[cfg(feature = "std")]
+    pub(crate) fn starting_at(
+        target: &str,
+        pos: Position,
+        options: TokenizerOptions,
+    ) -> Tokenizer<'a> {
+        let mut tokenizer = Tokenizer::with_options(target, options);
+        tokenizer.pos = pos;
+        return tokenizer;
+    }
+
+    // 分けて届く入力を、読み進めるのに合わせて1つずつ取り出す
+    // 位置はつなげた1つの文字列として数える
+    pub fn from_chunks<I>(chunks: I) -> Tokenizer<'a>
+    where
+        I: IntoIterator,
+        I::IntoIter: 'a,
+        I::Item: AsRef<str>,
+    {
+        let mut tokenizer = Tokenizer::new("");
+        let chunks = chunks.into_iter().map(|chunk| String::from(chunk.as_ref()));
+        tokenizer.chunks = Some(Box::new(chunks));
+        return tokenizer;
     }
 
-    fn move_horizon(&mut self, n: u32) {
+    // 読み終えた部分を捨てて、次のchunkを後ろにつなぐ。もうなければfalse
+    // 読みかけのtokenがあるときだけ、それを新しい列に写す。写す分と同じだけは読み足すので、
+    // 多くのchunkにまたがる長いtokenでも、写すのは合わせて入力の長さの数倍で済む
+    fn pull_chunk(&mut self) -> bool {
+        let carried = self.target.len() - self.byte;
+        let mut added = String::new();
+        let mut pulled = false;
+        while !pulled || added.len() < carried {
+            match self.chunks.as_mut().and_then(|chunks| chunks.next()) {
+                Some(chunk) => added.push_str(&chunk),
+                None => {
+                    self.chunks = None;
+                    break;
+                }
+            }
+            pulled = true;
+        }
+        if !pulled {
+            return false;
+        }
+        if carried > 0 {
+            let mut target = String::with_capacity(carried + added.len());
+            target.push_str(&self.target[self.byte..]);
+            target.push_str(&added);
+            added = target;
+            #[cfg(test)]
+            {
+                self.carried_bytes += carried;
+            }
+        }
+        self.target = Arc::from(added);
+        self.byte = 0;
+        return true;
+    }
+
+    fn mark(&self) -> Mark {
+        return Mark {
+            byte: self.byte,
+            pos: self.pos.clone(),
+            last_kind: self.last_kind.clone(),
+            raw_tag: self.raw_tag.clone(),
+            after_tag_name: self.after_tag_name,
+            in_tag: self.in_tag,
+            raw_until: self.raw_until.clone(),
+        };
+    }
+
+    fn rewind(&mut self, mark: Mark) {
+        self.byte = mark.byte;
+        self.pos = mark.pos;
+        self.last_kind = mark.last_kind;
+        self.raw_tag = mark.raw_tag;
+        self.after_tag_name = mark.after_tag_name;
+        self.in_tag = mark.in_tag;
+        self.raw_until = mark.raw_until;
+    }
+
+    fn move_horizon(&mut self, c: char) {
+        self.pos.at_line += 1;
+        self.pos.at_whole += 1;
+        self.byte += c.len_utf8();
+    }
+
+    // 改行を含まないlenバイトをまとめて進める
+    fn move_horizon_by(&mut self, len: usize) {
+        let n = self.target[self.byte..self.byte + len].chars().count() as u32;
         self.pos.at_line += n;
         self.pos.at_whole += n;
+        self.byte += len;
+    }
+
+    // 改行を含むかもしれないlenバイトを進める
+    fn move_over(&mut self, len: usize) {
+        let text = &self.target[self.byte..self.byte + len];
+        match text.rfind('\n') {
+            None => self.move_horizon_by(len),
+            Some(i) => {
+                let lines = text.matches('\n').count() as u32;
+                self.pos.line_no += lines;
+                self.pos.at_whole += text[..=i].chars().count() as u32;
+                self.pos.at_line = 0;
+                self.byte += i + 1;
+                self.move_horizon_by(len - i - 1);
+            }
+        }
     }
 
     fn next_line(&mut self) {
         self.pos.at_whole += 1;
         self.pos.line_no += 1;
         self.pos.at_line = 0;
+        self.byte += 1;
     }
 
-    fn current_char(&self) -> char {
-        return self.target.chars().nth(self.pos.at_whole as usize).unwrap();
+    fn current_char(&self) -> Option<char> {
+        return self.target[self.byte..].chars().next();
     }
 
-    fn peek(&self, n: u32) -> char {
-        return self
-            .target
-            .chars()
-            .nth((self.pos.at_whole + n) as usize)
-            .unwrap();
+    // 条件を満たさなくなる位置までのバイト数
+    fn run_len<P: Fn(char) -> bool>(&self, pred: P) -> usize {
+        let rest = &self.target[self.byte..];
+        return match rest.find(|c: char| !pred(c)) {
+            Some(i) => i,
+            None => rest.len(),
+        };
     }
 
-    fn start_with(&self, word: String) -> bool {
-        let chars: Chars = word.chars();
-        for (i, c) in chars.enumerate() {
-            if self.peek(i as u32) != c {
-                return false;
-            }
-        }
-        return true;
+    fn span(&self, start: usize) -> SharedStr {
+        return SharedStr::new(self.target.clone(), start, self.byte);
     }
 
-    fn consume_string(&mut self, is_single: bool) -> String {
-        let mut s: String = "".to_string();
-
+    fn consume_string(&mut self, quote: char) -> SharedStr {
         // consume start single/double quotation
-        self.move_horizon(1);
-
-        while !self.is_eof() {
-            let cur = self.current_char();
-            if cur == '\'' && is_single {
-                break;
-            }
-            if cur == '"' && !is_single {
-                break;
-            }
-            s += &*cur.to_string();
-            self.move_horizon(1);
+        self.move_horizon(quote);
+        let start = self.byte;
+
+        // 値は改行を含むことがある (title="1行目\n2行目")
+        if self.options.template_passthrough.is_empty() {
+            let len = self.run_len(|c| c != quote);
+            self.move_over(len);
+        } else {
+            // 値の中のテンプレートは引用符を含んでいてもそのまま値の一部にする
+            let len = self.string_len_with_templates(quote);
+            self.move_over(len);
         }
+        let s = self.span(start);
 
         // consume end single/double quotation
-        self.move_horizon(1);
+        if self.current_char() == Some(quote) {
+            self.move_horizon(quote);
+        } else if self.chunks.is_none() {
+            recovery!(
+                line = self.pos.line_no,
+                column = self.pos.at_line,
+                "unterminated string; read to the end"
+            );
+        }
 
         return s;
     }
 
-    fn consume_numeric(&mut self) -> (f64, bool) {
-        let mut s: String = "".to_string();
-        let mut include_dot: bool = false;
-
-        while !self.is_eof() {
-            if is_number(self.current_char()) {
-                s += &*self.current_char().to_string()
-            } else if self.current_char() == '.' {
-                s += &*self.current_char().to_string();
-                include_dot = true;
-            } else {
+    fn string_len_with_templates(&self, quote: char) -> usize {
+        let rest = &self.target[self.byte..];
+        let mut len = 0;
+        while let Some(c) = rest[len..].chars().next() {
+            if let Some(template) = self.template_len(self.byte + len) {
+                len += template;
+                continue;
+            }
+            if c == quote {
                 break;
             }
-            self.move_horizon(1);
+            len += c.len_utf8();
         }
+        return len;
+    }
+
+    // atから始まるテンプレートの長さ。閉じの区切りがなければ最後まで
+    fn template_len(&self, at: usize) -> Option<usize> {
+        let rest = &self.target[at..];
+        let delimiter =
+            self.options.template_passthrough.iter().find(|delimiter| {
+                !delimiter.open.is_empty() && rest.starts_with(&delimiter.open)
+            })?;
+        let body = &rest[delimiter.open.len()..];
+        return match body.find(&delimiter.close) {
+            Some(i) => Some(delimiter.open.len() + i + delimiter.close.len()),
+            None => Some(rest.len()),
+        };
+    }
 
-        return (s.parse().unwrap(), include_dot);
+    fn consume_template(&mut self, len: usize) -> SharedStr {
+        let start = self.byte;
+        if len == self.target.len() - start && self.chunks.is_none() {
+            recovery!(
+                line = self.pos.line_no,
+                column = self.pos.at_line,
+                "unterminated template; read to the end"
+            );
+        }
+        self.move_over(len);
+        return self.span(start);
     }
 
-    fn consume_ws(&mut self) -> String {
-        let mut s: String = "".to_string();
+    fn consume_ws(&mut self) -> SharedStr {
+        let start = self.byte;
 
-        while !self.is_eof() {
-            if is_ws(self.current_char()) && self.current_char() != '\n' {
-                s += &*self.current_char().to_string();
-                self.move_horizon(1);
-            } else if self.current_char() == '\n' {
-                s += &*self.current_char().to_string();
-                self.next_line();
-            } else {
+        loop {
+            // 改行までの空白はまとめて進める
+            let len = self.run_len(|c| is_ws(c) && c != '\n');
+            self.move_horizon_by(len);
+            if self.current_char() != Some('\n') {
                 break;
             }
+            self.next_line();
         }
 
-        return s;
-    }
-
-    fn consume_symbol(&mut self) -> String {
-        let s: String = self.current_char().to_string();
-        self.move_horizon(1);
-        return s;
+        return self.span(start);
     }
 
-    fn consume_text(&mut self) -> String {
-        let mut s: String = "".to_string();
+    // </name までをそのまま読む。見つからなければ最後まで
+    fn consume_raw_text(&mut self, name: &str) -> SharedStr {
+        let start = self.byte;
+        let rest = &self.target[start..];
+        let mut len = rest.len();
+        let mut from = 0;
+        while let Some(i) = rest[from..].find("</") {
+            let at = from + i;
+            let after = &rest.as_bytes()[at + 2..];
+            let same_name = after.len() >= name.len()
+                && after[..name.len()].eq_ignore_ascii_case(name.as_bytes());
+            // </scripts のような別の名前は閉じタグではない
+            if same_name
+                && matches!(
+                    after.get(name.len()),
+                    None | Some(b'>' | b'/' | b' ' | b'\t' | b'\n')
+                )
+            {
+                len = at;
+                break;
+            }
+            from = at + 2;
+        }
 
-        if !is_alphanum_(self.current_char()) {
-            s = self.current_char().to_string();
-            self.move_horizon(1);
-            return s;
+        if len == rest.len() && self.chunks.is_none() {
+            recovery!(
+                line = self.pos.line_no,
+                column = self.pos.at_line,
+                tag = name,
+                "raw text without a close tag; read to the end"
+            );
         }
+        self.move_over(len);
+        return self.span(start);
+    }
 
-        while !self.is_eof() {
-            if is_alphanum_(self.current_char()) {
-                s += &*self.current_char().to_string();
-                self.move_horizon(1);
-            } else {
-                break;
+    // scriptなどの開きタグを閉じる">"のあとは、中身をそのまま一つのテキストとして読む
+    fn track(&mut self, kind: TokenKind, s: &SharedStr) {
+        let after_tag_name = self.after_tag_name;
+        self.after_tag_name = false;
+        match kind {
+            TokenKind::TagBegin => {
+                self.raw_tag = None;
+                self.in_tag = true;
+            }
+            // <!-- の中はコメントの本文なので、引用符があっても文字列にしない
+            TokenKind::Hyphen if self.last_kind == TokenKind::Excl => self.in_tag = false,
+            TokenKind::Text if self.last_kind == TokenKind::TagBegin => {
+                let name = Atom::from_shared(s);
+                let raw_noscript = self.options.raw_noscript && name == "noscript";
+                if name.is_raw_text() || name.is_escapable_raw_text() || raw_noscript {
+                    self.raw_tag = Some(s.clone());
+                }
+                self.after_tag_name = true;
+            }
+            TokenKind::Hyphen if after_tag_name => self.raw_tag = None,
+            TokenKind::TagEnd => {
+                self.in_tag = false;
+                let raw_tag = self.raw_tag.take();
+                // <script/> には中身がない
+                if self.last_kind != TokenKind::Slash {
+                    self.raw_until = raw_tag;
+                }
             }
+            _ => {}
         }
+        self.last_kind = kind;
+    }
 
-        return s;
+    // 今の < の次の文字がタグらしいか。入力の終わりならタグにしない
+    fn opens_tag(&self) -> bool {
+        return self.target[self.byte + 1..]
+            .chars()
+            .next()
+            .is_some_and(|next| next.is_ascii_alphabetic() || matches!(next, '/' | '!' | '?'));
     }
 
-    fn link_ws_token<'a>(&self, cur: &'a mut Token, pos: Position) -> &'a mut Box<Token> {
-        let tok: Token = Token::new(Whitespace, pos, "".to_string());
-        cur.next = Some(Box::from(tok.clone()));
-        return cur.next.as_mut().unwrap();
+    fn consume_symbol(&mut self, c: char) -> char {
+        self.move_horizon(c);
+        return c;
     }
 
-    fn link_symbol_token<'a>(
-        &self,
-        cur: &'a mut Token,
-        pos: Position,
-        symbol: String,
-    ) -> &'a mut Box<Token> {
-        let tok: Token = Token::new(str_to_symbol_kind(symbol), pos, "".to_string());
-        cur.next = Some(Box::from(tok.clone()));
-        return cur.next.as_mut().unwrap();
+    fn consume_text(&mut self, first: char) -> SharedStr {
+        let start = self.byte;
+
+        if !is_alphanum_(first) {
+            self.move_horizon(first);
+            return self.span(start);
+        }
+
+        let len = self.run_len(is_alphanum_);
+        self.move_horizon_by(len);
+
+        return self.span(start);
     }
 
-    fn link_string_token<'a>(
+    fn link_token<'t>(
         &self,
-        cur: &'a mut Token,
-        pos: Position,
-        s: String,
-    ) -> &'a mut Box<Token> {
-        let tok: Token = Token::new(TokenKind::String, pos, s);
-        cur.next = Some(Box::from(tok.clone()));
-        return cur.next.as_mut().unwrap();
+        cur: &'t mut Token,
+        kind: TokenKind,
+        s: SharedStr,
+    ) -> &'t mut Box<Token> {
+        let tok: Token = Token::new(kind, self.pos.clone(), s);
+        return cur.next.insert(Box::from(tok));
     }
 
-    fn link_text_token<'a>(
-        &self,
-        cur: &'a mut Token,
-        pos: Position,
-        s: String,
-    ) -> &'a mut Box<Token> {
-        let tok: Token = Token::new(Text, pos, s);
-        cur.next = Some(Box::from(tok.clone()));
-        return cur.next.as_mut().unwrap();
+    pub fn tokenize(&mut self) -> Option<Box<Token>> {
+        enter_span!(DEBUG, "tokenize");
+        let mut head = Token::new(TokenKind::Illegal, self.pos.clone(), SharedStr::default());
+        self.tokenize_into(&mut head, usize::MAX);
+        return head.next.take();
     }
 
-    fn link_eof_token<'a>(&self, cur: &'a mut Token, pos: Position) -> &'a mut Box<Token> {
-        let tok: Token = Token::new(Eof, pos, "".to_string());
-        cur.next = Some(Box::from(tok.clone()));
-        return cur.next.as_mut().unwrap();
+    // batch_len個ずつの列にしてfへ渡す。最後の列はEofで終わる
+    pub fn tokenize_in_batches<F: FnMut(Box<Token>) -> ControlFlow<()>>(
+        &mut self,
+        batch_len: usize,
+        mut f: F,
+    ) {
+        enter_span!(DEBUG, "tokenize", batch_len);
+        loop {
+            let mut head = Token::new(TokenKind::Illegal, self.pos.clone(), SharedStr::default());
+            let done = self.tokenize_into(&mut head, batch_len.max(1));
+            if let Some(batch) = head.next.take() {
+                if f(batch).is_break() {
+                    return;
+                }
+            }
+            if done {
+                return;
+            }
+        }
     }
 
-    pub(crate) fn tokenize(&mut self) -> Option<Box<Token>> {
-        let mut head = Token::new(TokenKind::Illegal, self.pos.clone(), "".to_string());
-        let mut cur = &mut head;
+    // curの後ろに最大limit個つなげる。最後まで読んでEofをつないだらtrue
+    fn tokenize_into(&mut self, head: &mut Token, limit: usize) -> bool {
+        let mut cur = head;
+        let mut n = 0;
+
+        loop {
+            let c = match self.current_char() {
+                Some(c) => c,
+                None if self.pull_chunk() => continue,
+                None => break,
+            };
+            if n == limit {
+                return false;
+            }
 
-        while !self.is_eof() {
-            if is_ws(self.current_char()) {
-                let _ws = self.consume_ws();
-                cur = self.link_ws_token(cur, self.pos.clone());
+            // </script の後ろの1文字まで見えていないと、閉じタグか決められない
+            // テンプレートの開きの区切りも、全部見えていないと決められない
+            let lookahead = match &self.raw_until {
+                Some(name) => name.len() + "</>".len(),
+                None => self
+                    .options
+                    .template_passthrough
+                    .iter()
+                    .map(|delimiter| delimiter.open.len())
+                    .fold(1, usize::max),
+            };
+            let mark = self.mark();
+            let read = self.read_token(c);
+            // chunkの終わりで切れたかもしれないtokenは、次のchunkをつないで読み直す
+            if self.chunks.is_some() && self.target.len() - self.byte < lookahead {
+                self.rewind(mark);
+                self.pull_chunk();
                 continue;
             }
+            n += 1;
 
-            if is_reserved_symbol(self.current_char()) {
-                let sym = self.consume_symbol();
-                cur = self.link_symbol_token(cur, self.pos.clone(), sym);
-                continue;
+            if let Some((kind, s)) = read {
+                cur = self.link_token(cur, kind, s);
             }
+        }
 
-            if self.current_char() == '\'' {
-                let s = self.consume_string(true);
-                cur = self.link_string_token(cur, self.pos.clone(), s);
-                continue;
-            } else if self.current_char() == '"' {
-                let s = self.consume_string(false);
-                cur = self.link_string_token(cur, self.pos.clone(), s);
-                continue;
+        if n == limit {
+            return false;
+        }
+        let _cur = self.link_token(cur, Eof, SharedStr::default());
+        return true;
+    }
+
+    // 1つ読む。中身のないraw textならNone
+    fn read_token(&mut self, c: char) -> Option<(TokenKind, SharedStr)> {
+        if let Some(name) = self.raw_until.take() {
+            let t = self.consume_raw_text(&name);
+            if t.is_empty() {
+                return None;
+            }
+            self.track(Text, &t);
+            return Some((Text, t));
+        }
+
+        if let Some(len) = self.template_len(self.byte) {
+            let t = self.consume_template(len);
+            self.track(TokenKind::Template, &t);
+            return Some((TokenKind::Template, t));
+        }
+
+        if is_ws(c) {
+            let ws = self.consume_ws();
+            self.track(Whitespace, &ws);
+            if self.options.keep_whitespace {
+                return Some((Whitespace, ws));
             }
+            return Some((Whitespace, SharedStr::default()));
+        }
+
+        // 後ろが名前や / ! ? でない < はタグを始めない (<p>5 < 10</p>)
+        if c == '<' && !self.opens_tag() {
+            let t = self.consume_text(c);
+            self.track(Text, &t);
+            return Some((Text, t));
+        }
+
+        if is_reserved_symbol(c) {
+            let kind = char_to_symbol_kind(self.consume_symbol(c));
+            self.track(kind.clone(), &SharedStr::default());
+            return Some((kind, SharedStr::default()));
+        }
 
-            let t = self.consume_text();
-            cur = self.link_text_token(cur, self.pos.clone(), t);
-            continue;
+        if self.in_tag && (c == '\'' || c == '"') {
+            let s = self.consume_string(c);
+            self.track(TokenKind::String, &s);
+            return Some((TokenKind::String, s));
         }
 
-        let _cur = self.link_eof_token(cur, self.pos.clone());
-        return head.next;
+        let t = self.consume_text(c);
+        self.track(Text, &t);
+        return Some((Text, t));
+    }
+}
+
+// nextの鎖は入れ子にせず、[{kind, pos, s}, ...] の平らな列にする
+#[cfg(feature = "serde")]
+#[derive(serde::Serialize, serde::Deserialize)]
+struct TokenEntry {
+    kind: TokenKind,
+    pos: Position,
+    s: SharedStr,
+}
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for Token {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use serde::ser::SerializeSeq;
+
+        let mut seq = serializer.serialize_seq(None)?;
+        let mut cur = Some(self);
+        while let Some(tok) = cur {
+            seq.serialize_element(&TokenEntry {
+                kind: tok.kind.clone(),
+                pos: tok.pos.clone(),
+                s: tok.s.clone(),
+            })?;
+            cur = tok.next.as_deref();
+        }
+        return seq.end();
+    }
+}
+
+// 平らな列を読んで、nextでつなぎ直す。空の列は読めない
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for Token {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Token, D::Error> {
+        use alloc::vec::Vec;
+
+        let entries: Vec<TokenEntry> = serde::Deserialize::deserialize(deserializer)?;
+        let mut next: Option<Box<Token>> = None;
+        for entry in entries.into_iter().rev() {
+            let mut tok = Box::new(Token::new(entry.kind, entry.pos, entry.s));
+            tok.next = next;
+            next = Some(tok);
+        }
+        return match next {
+            Some(head) => Ok(*head),
+            None => Err(serde::de::Error::invalid_length(0, &"at least one token")),
+        };
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::html::tokenizer::Tokenizer;
+    use crate::html::position::Position;
+    use crate::html::tokenizer::{TemplateDelimiter, TokenKind, Tokenizer, TokenizerOptions};
     #[test]
     fn tokenize() {
         let input = "<h1>hello, world</h1>";
@@ -313,4 +716,294 @@ mod tests {
         let token = tokenizer.tokenize();
         println!("{:#?}", token)
     }
+
+    #[test]
+    fn multi_line_tags_keep_line_numbers() {
+        let input = "<div title=\"first line\nsecond line\"\n  class=\"x\">text</div>";
+        let mut tokenizer = Tokenizer::new(input);
+        let mut cur = tokenizer.tokenize();
+        let mut tokens = vec![];
+        while let Some(mut tok) = cur {
+            tokens.push((tok.kind.clone(), tok.s.to_string(), tok.pos.clone()));
+            cur = tok.next.take();
+        }
+        let title = tokens
+            .iter()
+            .find(|(kind, _, _)| *kind == TokenKind::String);
+        assert_eq!(
+            title,
+            Some(&(
+                TokenKind::String,
+                "first line\nsecond line".to_string(),
+                Position::new(2, 12, 35)
+            ))
+        );
+        // 開きタグの ">" と、その後のtext
+        let text = tokens.iter().position(|(_, s, _)| s == "text").unwrap();
+        assert_eq!(tokens[text - 1].2, Position::new(3, 12, 48));
+        assert_eq!(tokens[text].2, Position::new(3, 16, 52));
+        assert_eq!(tokens.last().unwrap().2, Position::new(3, 22, 58));
+    }
+
+    #[test]
+    fn tokenize_multibyte() {
+        let input = "<p>こんにちは、世界</p>";
+        let mut tokenizer = Tokenizer::new(input);
+        let token = tokenizer.tokenize();
+        assert!(token.is_some());
+    }
+
+    #[test]
+    fn tokenize_text_runs() {
+        let input = "héllo wörld_1 \n\t 'a b'";
+        let mut tokenizer = Tokenizer::new(input);
+        let mut cur = tokenizer.tokenize();
+
+        let mut tokens = vec![];
+        while let Some(mut tok) = cur {
+            tokens.push((tok.kind.clone(), tok.s.to_string(), tok.pos.clone()));
+            cur = tok.next.take();
+        }
+        assert_eq!(
+            tokens,
+            vec![
+                (TokenKind::Text, "héllo".to_string(), Position::new(1, 5, 5)),
+                (
+                    TokenKind::Whitespace,
+                    "".to_string(),
+                    Position::new(1, 6, 6)
+                ),
+                (
+                    TokenKind::Text,
+                    "wörld_1".to_string(),
+                    Position::new(1, 13, 13)
+                ),
+                (
+                    TokenKind::Whitespace,
+                    "".to_string(),
+                    Position::new(2, 2, 17)
+                ),
+                // タグの外の引用符は文字列を始めない
+                (TokenKind::Text, "'".to_string(), Position::new(2, 3, 18)),
+                (TokenKind::Text, "a".to_string(), Position::new(2, 4, 19)),
+                (
+                    TokenKind::Whitespace,
+                    "".to_string(),
+                    Position::new(2, 5, 20)
+                ),
+                (TokenKind::Text, "b".to_string(), Position::new(2, 6, 21)),
+                (TokenKind::Text, "'".to_string(), Position::new(2, 7, 22)),
+                (TokenKind::Eof, "".to_string(), Position::new(2, 7, 22)),
+            ]
+        );
+    }
+
+    #[test]
+    fn tokenize_raw_text() {
+        let input = "<SCRIPT type=\"a\">if (a < b && c) {\n  x = \"</div>\";\n}</Script ><p>";
+        let mut tokenizer = Tokenizer::new(input);
+        let mut cur = tokenizer.tokenize();
+
+        let mut tokens = vec![];
+        while let Some(mut tok) = cur {
+            tokens.push((tok.kind.clone(), tok.s.to_string(), tok.pos.clone()));
+            cur = tok.next.take();
+        }
+        // 中身は一つのText。位置は改行も数える
+        assert_eq!(
+            tokens[7],
+            (
+                TokenKind::Text,
+                "if (a < b && c) {\n  x = \"</div>\";\n}".to_string(),
+                Position::new(3, 1, 52)
+            )
+        );
+        assert_eq!(tokens[8].0, TokenKind::TagBegin);
+        assert_eq!(tokens[9].0, TokenKind::Slash);
+        assert_eq!(tokens[10].1, "Script");
+        assert_eq!(tokens[11].0, TokenKind::Whitespace);
+        assert_eq!(tokens[12].0, TokenKind::TagEnd);
+        // 閉じたあとは普通に読む
+        assert_eq!(tokens[14].1, "p");
+    }
+
+    fn collect(mut tokenizer: Tokenizer) -> Vec<(TokenKind, String, Position)> {
+        let mut cur = tokenizer.tokenize();
+        let mut tokens = vec![];
+        while let Some(mut tok) = cur {
+            tokens.push((tok.kind.clone(), tok.s.to_string(), tok.pos.clone()));
+            cur = tok.next.take();
+        }
+        return tokens;
+    }
+
+    #[test]
+    fn tokenize_chunks_split_anywhere() {
+        let input =
+            "<p class=\"a b\">caf\u{e9} &amp; 語</p>\n<script>x = \"</scripts>\";</script><b>";
+        let expected = collect(Tokenizer::new(input));
+        // 2つに分ける位置をすべて試す (タグ、文字列、&amp;、</script の途中も含む)
+        for (at, _) in input.char_indices().skip(1) {
+            let chunks = [&input[..at], &input[at..]];
+            assert_eq!(
+                collect(Tokenizer::from_chunks(chunks)),
+                expected,
+                "split at {}",
+                at
+            );
+        }
+        let chars: Vec<String> = input.chars().map(|c| c.to_string()).collect();
+        assert_eq!(collect(Tokenizer::from_chunks(&chars)), expected);
+        // 空のchunkは飛ばす
+        assert_eq!(collect(Tokenizer::from_chunks(["", input, ""])), expected);
+        assert_eq!(
+            collect(Tokenizer::from_chunks(Vec::<&str>::new())),
+            collect(Tokenizer::new(""))
+        );
+    }
+
+    #[test]
+    fn long_token_over_small_chunks() {
+        let body = "x".repeat(1 << 20);
+        let input = format!("<script>{}</script><p>", body);
+        let chunks: Vec<&str> = input
+            .as_bytes()
+            .chunks(64)
+            .map(|chunk| core::str::from_utf8(chunk).unwrap())
+            .collect();
+        let mut tokenizer = Tokenizer::from_chunks(chunks);
+        let mut cur = tokenizer.tokenize();
+        let mut texts = vec![];
+        while let Some(mut tok) = cur {
+            if tok.kind == TokenKind::Text {
+                texts.push(tok.s.len());
+            }
+            cur = tok.next.take();
+        }
+        assert_eq!(texts, vec![6, 1 << 20, 6, 1]);
+        // chunkごとに読みかけの全部を写すと、写すのは入力の長さの2乗に近くなる
+        assert!(
+            tokenizer.carried_bytes < 4 * input.len(),
+            "{}",
+            tokenizer.carried_bytes
+        );
+    }
+
+    fn templates() -> TokenizerOptions {
+        return TokenizerOptions {
+            template_passthrough: TemplateDelimiter::builtin(),
+            ..TokenizerOptions::default()
+        };
+    }
+
+    #[test]
+    fn tokenize_template_passthrough() {
+        let input = "<a href=\"<?= \"x\" ?>\"><% if a > 1 %>\n<b>";
+        let tokens = collect(Tokenizer::with_options(input, templates()));
+        // 値の中の引用符はテンプレートの一部
+        assert_eq!(
+            tokens[5],
+            (
+                TokenKind::String,
+                "<?= \"x\" ?>".to_string(),
+                Position::new(1, 20, 20)
+            )
+        );
+        assert_eq!(tokens[7].0, TokenKind::Template);
+        assert_eq!(tokens[7].1, "<% if a > 1 %>");
+        assert_eq!(tokens[8].0, TokenKind::Whitespace);
+        assert_eq!(tokens[9].0, TokenKind::TagBegin);
+
+        // 閉じの区切りがなければ最後まで
+        let tokens = collect(Tokenizer::with_options("<p><?php echo", templates()));
+        assert_eq!(
+            tokens[3],
+            (
+                TokenKind::Template,
+                "<?php echo".to_string(),
+                Position::new(1, 13, 13)
+            )
+        );
+
+        // 区切りの途中でchunkが切れても同じ
+        let expected = collect(Tokenizer::with_options(input, templates()));
+        for (at, _) in input.char_indices().skip(1) {
+            let mut tokenizer = Tokenizer::from_chunks([&input[..at], &input[at..]]);
+            tokenizer.options = templates();
+            assert_eq!(collect(tokenizer), expected, "split at {}", at);
+        }
+
+        // 指定しなければ、% の前の < はタグを始めないただの文字
+        let tokens = collect(Tokenizer::new("<% x %>"));
+        assert_eq!(tokens[0].0, TokenKind::Text);
+        assert_eq!(tokens[0].1, "<");
+    }
+
+    #[test]
+    fn tokenize_self_closing_script_is_not_raw() {
+        let input = "<script src=\"a.js\"/><p>";
+        let mut tokenizer = Tokenizer::new(input);
+        let mut cur = tokenizer.tokenize();
+
+        let mut kinds = vec![];
+        while let Some(mut tok) = cur {
+            kinds.push(tok.kind.clone());
+            cur = tok.next.take();
+        }
+        assert_eq!(
+            kinds[kinds.len() - 4..],
+            [
+                TokenKind::TagBegin,
+                TokenKind::Text,
+                TokenKind::TagEnd,
+                TokenKind::Eof
+            ]
+        );
+    }
+
+    #[test]
+    fn drop_long_token_list_on_small_stack() {
+        let handle = std::thread::Builder::new()
+            .stack_size(256 * 1024)
+            .spawn(|| {
+                // "a" と空白で200万token
+                let input = "a ".repeat(1_000_000);
+                let mut tokenizer = Tokenizer::new(&input);
+                let token = tokenizer.tokenize();
+                assert!(token.is_some());
+                drop(token);
+            })
+            .unwrap();
+        handle.join().unwrap();
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_round_trip() {
+        use crate::html::tokenizer::Token;
+
+        let input =
+            "<!DOCTYPE html>\n<p class=\"a b\">héllo,\n wörld</p>\n<script>if (a < b) {}</script>";
+        let head = Tokenizer::new(input).tokenize().unwrap();
+        let json = serde_json::to_string(&head).unwrap();
+        // 入れ子ではなく平らな列
+        assert!(json.starts_with("[{\"kind\":\"TagBegin\",\"pos\":{\"line_no\":1,"));
+        assert!(!json.contains("next"));
+
+        let back: Token = serde_json::from_str(&json).unwrap();
+        let (mut a, mut b) = (Some(&*head), Some(&back));
+        let mut n = 0;
+        while let (Some(x), Some(y)) = (a, b) {
+            assert_eq!(x.kind, y.kind);
+            assert_eq!(x.pos, y.pos);
+            assert_eq!(x.s, y.s);
+            a = x.next.as_deref();
+            b = y.next.as_deref();
+            n += 1;
+        }
+        assert!(a.is_none() && b.is_none());
+        assert!(n > 20);
+
+        assert!(serde_json::from_str::<Token>("[]").is_err());
+    }
 }
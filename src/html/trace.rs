@@ -0,0 +1,143 @@
+// tracing featureのときだけspanとeventを出す
+// featureがなければ引数ごと消えるので、値を作る手間もかからない
+
+// enter_span!(DEBUG, "parse", name = %name) のように書く。spanはそのblockの終わりまで
+#[cfg(feature = "tracing")]
+macro_rules! enter_span {
+    ($level:ident, $($arg:tt)+) => {
+        let _span = tracing::span!(tracing::Level::$level, $($arg)+).entered();
+    };
+}
+
+#[cfg(not(feature = "tracing"))]
+macro_rules! enter_span {
+    ($level:ident, $($arg:tt)+) => {};
+}
+
+// 読めないところを飛ばしたり、そこで止めたりしたとき
+#[cfg(feature = "tracing")]
+macro_rules! recovery {
+    ($($arg:tt)+) => {
+        tracing::warn!($($arg)+)
+    };
+}
+
+#[cfg(not(feature = "tracing"))]
+macro_rules! recovery {
+    ($($arg:tt)+) => {};
+}
+
+pub(crate) use enter_span;
+pub(crate) use recovery;
+
+#[cfg(all(test, feature = "tracing", feature = "parser", feature = "std"))]
+mod tests {
+    use crate::html;
+    use std::fmt;
+    use std::fs;
+    use std::sync::{Arc, Mutex};
+    use tracing::field::{Field, Visit};
+    use tracing::span::{Attributes, Id, Record};
+    use tracing::{Event, Metadata, Subscriber};
+
+    // spanとeventを "name field=value ..." の1行にして貯める
+    #[derive(Clone, Default)]
+    struct Collect {
+        lines: Arc<Mutex<Vec<String>>>,
+    }
+
+    struct Line(String);
+
+    impl Visit for Line {
+        fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+            self.0 += &format!(" {}={:?}", field.name(), value);
+        }
+    }
+
+    impl Subscriber for Collect {
+        fn enabled(&self, _: &Metadata<'_>) -> bool {
+            return true;
+        }
+
+        fn new_span(&self, span: &Attributes<'_>) -> Id {
+            let mut line = Line(format!("span {}", span.metadata().name()));
+            span.record(&mut line);
+            self.lines.lock().unwrap().push(line.0);
+            return Id::from_u64(1);
+        }
+
+        fn record(&self, _: &Id, _: &Record<'_>) {}
+
+        fn record_follows_from(&self, _: &Id, _: &Id) {}
+
+        fn event(&self, event: &Event<'_>) {
+            let mut line = Line(format!("{}", event.metadata().level()));
+            event.record(&mut line);
+            self.lines.lock().unwrap().push(line.0);
+        }
+
+        fn enter(&self, _: &Id) {}
+
+        fn exit(&self, _: &Id) {}
+    }
+
+    fn collect(html: &str) -> Vec<String> {
+        let collect = Collect::default();
+        tracing::subscriber::with_default(collect.clone(), || {
+            let _ = html::parse(html);
+        });
+        let lines = collect.lines.lock().unwrap().clone();
+        return lines;
+    }
+
+    #[test]
+    fn broken_fixture_emits_recovery_events() {
+        let path = format!("{}/tests/fixtures/broken.html", env!("CARGO_MANIFEST_DIR"));
+        let lines = collect(&fs::read_to_string(path).unwrap());
+        assert!(lines.contains(&"span tokenize".to_string()), "{:?}", lines);
+        assert!(lines.contains(&"span parse".to_string()), "{:?}", lines);
+        assert!(
+            lines
+                .iter()
+                .any(|line| line.starts_with("span parse_tag name=b line=5")),
+            "{:?}",
+            lines
+        );
+        let warnings: Vec<&String> = lines.iter().filter(|l| l.starts_with("WARN")).collect();
+        assert_eq!(warnings.len(), 1, "{:?}", lines);
+        assert!(warnings[0].contains("parse stopped"), "{:?}", warnings);
+        assert!(warnings[0].contains("miss matched"), "{:?}", warnings);
+    }
+
+    #[test]
+    fn recovery_events() {
+        let lines = collect("<p>a</p></div><p>b</p>");
+        assert!(
+            lines
+                .iter()
+                .any(|line| line.starts_with("WARN") && line.contains("close tag without")),
+            "{:?}",
+            lines
+        );
+
+        let lines = collect("<p class=\"a>x</p>");
+        assert!(
+            lines
+                .iter()
+                .any(|line| line.starts_with("WARN") && line.contains("unterminated string")),
+            "{:?}",
+            lines
+        );
+
+        let lines = collect("<script>let a;");
+        assert!(
+            lines
+                .iter()
+                .any(|line| line.starts_with("WARN") && line.contains("raw text")),
+            "{:?}",
+            lines
+        );
+
+        assert!(!collect("<p>ok</p>").iter().any(|l| l.starts_with("WARN")));
+    }
+}
@@ -0,0 +1,212 @@
+// RFC 3986 の参照解決。URLの正規化 (%エンコードや大文字小文字) まではしない
+
+// scheme://authority/path?query#fragment を切り分けたもの
+struct Parts<'a> {
+    scheme: Option<&'a str>,
+    authority: Option<&'a str>,
+    path: &'a str,
+    query: Option<&'a str>,
+    fragment: Option<&'a str>,
+}
+
+fn split(url: &str) -> Parts<'_> {
+    let (rest, fragment) = match url.split_once('#') {
+        Some((rest, fragment)) => (rest, Some(fragment)),
+        None => (url, None),
+    };
+    let (rest, query) = match rest.split_once('?') {
+        Some((rest, query)) => (rest, Some(query)),
+        None => (rest, None),
+    };
+    let (scheme, rest) = match scheme_end(rest) {
+        Some(end) => (Some(&rest[..end]), &rest[end + 1..]),
+        None => (None, rest),
+    };
+    let (authority, path) = match rest.strip_prefix("//") {
+        Some(rest) => {
+            let end = rest.find('/').unwrap_or(rest.len());
+            (Some(&rest[..end]), &rest[end..])
+        }
+        None => (None, rest),
+    };
+    return Parts {
+        scheme,
+        authority,
+        path,
+        query,
+        fragment,
+    };
+}
+
+// "http:" の ':' の位置。schemeは英字で始まり、英数字と +-. が続く
+fn scheme_end(url: &str) -> Option<usize> {
+    let end = url.find(':')?;
+    let scheme = &url[..end];
+    let mut chars = scheme.chars();
+    if !chars.next()?.is_ascii_alphabetic() {
+        return None;
+    }
+    if !chars.all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.')) {
+        return None;
+    }
+    return Some(end);
+}
+
+fn join(parts: &Parts) -> String {
+    let mut out = String::new();
+    if let Some(scheme) = parts.scheme {
+        out.push_str(scheme);
+        out.push(':');
+    }
+    if let Some(authority) = parts.authority {
+        out.push_str("//");
+        out.push_str(authority);
+    }
+    out.push_str(parts.path);
+    if let Some(query) = parts.query {
+        out.push('?');
+        out.push_str(query);
+    }
+    if let Some(fragment) = parts.fragment {
+        out.push('#');
+        out.push_str(fragment);
+    }
+    return out;
+}
+
+// . と .. を消す。先頭を越える .. は捨てる
+fn remove_dot_segments(path: &str) -> String {
+    let absolute = path.starts_with('/');
+    let mut segments: Vec<&str> = Vec::new();
+    let mut iter = path.split('/').peekable();
+    if absolute {
+        iter.next();
+    }
+    while let Some(segment) = iter.next() {
+        let last = iter.peek().is_none();
+        match segment {
+            "." => {
+                if last {
+                    segments.push("");
+                }
+            }
+            ".." => {
+                segments.pop();
+                if last {
+                    segments.push("");
+                }
+            }
+            _ => segments.push(segment),
+        }
+    }
+    let joined = segments.join("/");
+    if absolute {
+        return format!("/{}", joined);
+    }
+    return joined;
+}
+
+// baseの最後の / までにhrefをつなぐ
+fn merge(base: &Parts, path: &str) -> String {
+    if base.authority.is_some() && base.path.is_empty() {
+        return format!("/{}", path);
+    }
+    return match base.path.rfind('/') {
+        Some(i) => format!("{}{}", &base.path[..=i], path),
+        None => path.to_string(),
+    };
+}
+
+// hrefをbaseからの相対として解決する。baseはschemeのない "pages/index.html" のようなpathでもよい
+pub fn resolve(base: &str, href: &str) -> String {
+    // 前後の空白と、途中の改行やtabは無視される
+    let href: String = href
+        .trim_matches(|c: char| c.is_ascii_whitespace() || c.is_control())
+        .chars()
+        .filter(|c| !matches!(c, '\t' | '\n' | '\r'))
+        .collect();
+    let reference = split(&href);
+    if reference.scheme.is_some() {
+        let path = remove_dot_segments(reference.path);
+        return join(&Parts {
+            path: &path,
+            ..reference
+        });
+    }
+
+    let base = split(base);
+    if reference.authority.is_some() {
+        let path = remove_dot_segments(reference.path);
+        return join(&Parts {
+            scheme: base.scheme,
+            path: &path,
+            ..reference
+        });
+    }
+
+    let (path, query) = if reference.path.is_empty() {
+        // "?q" や "#f" だけなら、baseのpathのまま
+        let query = match reference.query {
+            Some(query) => Some(query),
+            None => base.query,
+        };
+        (base.path.to_string(), query)
+    } else if reference.path.starts_with('/') {
+        (remove_dot_segments(reference.path), reference.query)
+    } else {
+        (
+            remove_dot_segments(&merge(&base, reference.path)),
+            reference.query,
+        )
+    };
+    return join(&Parts {
+        scheme: base.scheme,
+        authority: base.authority,
+        path: &path,
+        query,
+        fragment: reference.fragment,
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::html::url::resolve;
+
+    #[test]
+    fn resolve_references() {
+        // RFC 3986 5.4 の例
+        let base = "http://a/b/c/d;p?q";
+        let table = [
+            ("g:h", "g:h"),
+            ("g", "http://a/b/c/g"),
+            ("./g", "http://a/b/c/g"),
+            ("g/", "http://a/b/c/g/"),
+            ("/g", "http://a/g"),
+            ("//g", "http://g"),
+            ("?y", "http://a/b/c/d;p?y"),
+            ("g?y", "http://a/b/c/g?y"),
+            ("#s", "http://a/b/c/d;p?q#s"),
+            ("g#s", "http://a/b/c/g#s"),
+            ("", "http://a/b/c/d;p?q"),
+            (".", "http://a/b/c/"),
+            ("./", "http://a/b/c/"),
+            ("..", "http://a/b/"),
+            ("../g", "http://a/b/g"),
+            ("../..", "http://a/"),
+            ("../../../g", "http://a/g"),
+            ("/./g", "http://a/g"),
+            ("g/../h", "http://a/b/c/h"),
+            (" g\n.css ", "http://a/b/c/g.css"),
+        ];
+        for (href, expected) in table {
+            assert_eq!(resolve(base, href), expected, "{:?}", href);
+        }
+
+        assert_eq!(
+            resolve("https://example.com", "a.css"),
+            "https://example.com/a.css"
+        );
+        assert_eq!(resolve("pages/index.html", "../css/a.css"), "css/a.css");
+        assert_eq!(resolve("/srv/www/index.html", "a.css"), "/srv/www/a.css");
+    }
+}
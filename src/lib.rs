@@ -1 +1,27 @@
-mod html;
+// std featureを外すとno_std + allocで、tokenizerとparserだけを使える
+// テストはstdのharnessで動かすので、そのときはstdを使う
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
+
+extern crate alloc;
+
+#[cfg(all(feature = "parser", feature = "std"))]
+pub mod css;
+pub mod html;
+#[cfg(all(feature = "parser", feature = "std"))]
+pub mod net;
+#[cfg(all(feature = "parser", feature = "std"))]
+pub mod prelude;
+#[cfg(all(feature = "parser", feature = "std"))]
+pub mod render;
+#[cfg(feature = "wasm")]
+pub mod wasm;
+
+// よく使うものはmoduleの場所を知らなくても使えるように
+#[cfg(all(feature = "parser", feature = "std"))]
+pub use crate::html::document::Document;
+#[cfg(feature = "parser")]
+pub use crate::html::errors::ParseError;
+#[cfg(all(feature = "parser", feature = "std"))]
+pub use crate::html::parse;
+#[cfg(feature = "parser")]
+pub use crate::html::parser::{Node, NodeData};
@@ -0,0 +1,57 @@
+pub mod errors;
+#[cfg(feature = "http")]
+pub mod http;
+pub mod mock;
+
+use crate::net::errors::FetchError;
+
+// 取ってきたものをそのまま持つ。bodyはまだdecodeしない
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Response {
+    pub status: u16,
+    // 受け取った順。名前は書かれたまま
+    pub headers: Vec<(String, String)>,
+    pub body: Vec<u8>,
+}
+
+impl Response {
+    pub fn new(status: u16, body: &[u8]) -> Response {
+        return Response {
+            status,
+            headers: Vec::new(),
+            body: body.to_vec(),
+        };
+    }
+
+    pub fn with_header(mut self, name: &str, value: &str) -> Response {
+        self.headers.push((name.to_string(), value.to_string()));
+        return self;
+    }
+
+    // 名前は大文字小文字を区別しない。同じ名前が何度もあれば最初のもの
+    pub fn header(&self, name: &str) -> Option<&str> {
+        return self
+            .headers
+            .iter()
+            .find(|(header, _)| header.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str());
+    }
+
+    pub fn content_type(&self) -> Option<&str> {
+        return self.header("content-type");
+    }
+
+    pub fn is_success(&self) -> bool {
+        return (200..300).contains(&self.status);
+    }
+
+    pub fn is_redirect(&self) -> bool {
+        return matches!(self.status, 301 | 302 | 303 | 307 | 308);
+    }
+}
+
+// HTTPなどの通信をするところ。crateは実装を持たず、使う側が用意する
+// (http featureで、TLSなしのHTTP/1.1だけは使える)
+pub trait Transport {
+    fn get(&self, url: &str) -> Result<Response, FetchError>;
+}
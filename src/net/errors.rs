@@ -0,0 +1,11 @@
+use thiserror::Error;
+
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum FetchError {
+    #[error("unsupported url: {url}")]
+    UnsupportedUrl { url: String },
+    #[error("failed to connect to {url}: {detail}")]
+    Connect { url: String, detail: String },
+    #[error("invalid response from {url}: {detail}")]
+    InvalidResponse { url: String, detail: String },
+}
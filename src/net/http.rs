@@ -0,0 +1,329 @@
+use crate::net::errors::FetchError;
+use crate::net::{Response, Transport};
+use std::io::{Read, Write};
+use std::net::TcpStream;
+use std::time::Duration;
+
+// http:// だけを読む。httpsはTLSの実装が要るので、使う側でTransportを用意する
+#[derive(Debug, Clone)]
+pub struct HttpTransport {
+    // 読み書きそれぞれの待ち時間
+    pub timeout: Option<Duration>,
+    pub user_agent: String,
+    // これより大きい応答 (headerも含む) は読むのをやめてエラーにする
+    pub max_response_bytes: usize,
+}
+
+impl Default for HttpTransport {
+    fn default() -> HttpTransport {
+        return HttpTransport {
+            timeout: Some(Duration::from_secs(30)),
+            user_agent: concat!("browser/", env!("CARGO_PKG_VERSION")).to_string(),
+            max_response_bytes: 64 * 1024 * 1024,
+        };
+    }
+}
+
+impl HttpTransport {
+    pub fn new() -> HttpTransport {
+        return HttpTransport::default();
+    }
+}
+
+// (Host headerに書くもの, 接続先, port, path)
+fn split_url(url: &str) -> Option<(&str, &str, u16, &str)> {
+    let scheme = url.get(..7)?;
+    if !scheme.eq_ignore_ascii_case("http://") {
+        return None;
+    }
+    let rest = &url[7..];
+    let rest = match rest.find('#') {
+        Some(end) => &rest[..end],
+        None => rest,
+    };
+    let end = rest.find(['/', '?']).unwrap_or(rest.len());
+    let (authority, path) = (&rest[..end], &rest[end..]);
+    // userinfoは送らない
+    let authority = match authority.rfind('@') {
+        Some(at) => &authority[at + 1..],
+        None => authority,
+    };
+    // Host headerにそのまま書くので、改行などで別のheaderを足せないようにする
+    if authority
+        .chars()
+        .any(|c| c.is_whitespace() || c.is_control())
+    {
+        return None;
+    }
+    let (host, port) = match authority.rfind(':') {
+        // [::1] の中の : はportの区切りではない
+        Some(colon) if !authority[colon..].contains(']') => {
+            (&authority[..colon], authority[colon + 1..].parse().ok()?)
+        }
+        _ => (authority, 80),
+    };
+    if host.is_empty() {
+        return None;
+    }
+    let path = if path.is_empty() { "/" } else { path };
+    let connect = host.trim_start_matches('[').trim_end_matches(']');
+    return Some((authority, connect, port, path));
+}
+
+// request lineに書くpath。空白と制御文字は区切りや改行として読まれるので、%XXにする
+fn request_target(path: &str) -> String {
+    let mut out = String::with_capacity(path.len());
+    for c in path.chars() {
+        if c.is_whitespace() || c.is_control() {
+            let mut buf = [0; 4];
+            for b in c.encode_utf8(&mut buf).bytes() {
+                out.push_str(&format!("%{:02X}", b));
+            }
+        } else {
+            out.push(c);
+        }
+    }
+    return out;
+}
+
+impl Transport for HttpTransport {
+    fn get(&self, url: &str) -> Result<Response, FetchError> {
+        let (host, connect, port, path) = match split_url(url) {
+            Some(parts) => parts,
+            None => {
+                return Err(FetchError::UnsupportedUrl {
+                    url: url.to_string(),
+                })
+            }
+        };
+        let connect_error = |err: std::io::Error| FetchError::Connect {
+            url: url.to_string(),
+            detail: err.to_string(),
+        };
+
+        let mut stream = TcpStream::connect((connect, port)).map_err(connect_error)?;
+        stream
+            .set_read_timeout(self.timeout)
+            .map_err(connect_error)?;
+        stream
+            .set_write_timeout(self.timeout)
+            .map_err(connect_error)?;
+        // 読み終わりが分かりやすいように、一度で切ってもらう
+        let request = format!(
+            "GET {} HTTP/1.1\r\nHost: {}\r\nUser-Agent: {}\r\nAccept: */*\r\nConnection: close\r\n\r\n",
+            request_target(path),
+            host,
+            self.user_agent
+        );
+        stream
+            .write_all(request.as_bytes())
+            .map_err(connect_error)?;
+        // 1バイト多く読めたら、上限を超えている
+        let limit = self.max_response_bytes as u64;
+        let mut bytes = Vec::new();
+        (&stream)
+            .take(limit.saturating_add(1))
+            .read_to_end(&mut bytes)
+            .map_err(connect_error)?;
+        if bytes.len() as u64 > limit {
+            return Err(FetchError::InvalidResponse {
+                url: url.to_string(),
+                detail: format!("response larger than {} bytes", self.max_response_bytes),
+            });
+        }
+
+        return parse_response(&bytes).map_err(|detail| FetchError::InvalidResponse {
+            url: url.to_string(),
+            detail: detail.to_string(),
+        });
+    }
+}
+
+fn parse_response(bytes: &[u8]) -> Result<Response, &'static str> {
+    let head_end = match bytes.windows(4).position(|w| w == b"\r\n\r\n") {
+        Some(end) => end,
+        None => return Err("missing end of headers"),
+    };
+    // headerはASCIIのはずなので、それ以外はlatin1として読む
+    let head: String = bytes[..head_end].iter().map(|&b| b as char).collect();
+    let mut lines = head.split("\r\n");
+    let status = lines
+        .next()
+        .filter(|line| line.starts_with("HTTP/"))
+        .and_then(|line| line.split_ascii_whitespace().nth(1))
+        .and_then(|code| code.parse().ok());
+    let status = match status {
+        Some(status) => status,
+        None => return Err("invalid status line"),
+    };
+    let mut response = Response::new(status, b"");
+    for line in lines {
+        match line.split_once(':') {
+            Some((name, value)) => response
+                .headers
+                .push((name.trim().to_string(), value.trim().to_string())),
+            None => return Err("invalid header line"),
+        }
+    }
+
+    let body = &bytes[head_end + 4..];
+    let chunked = response
+        .header("transfer-encoding")
+        .is_some_and(|value| value.to_ascii_lowercase().contains("chunked"));
+    response.body = if chunked {
+        dechunk(body)?
+    } else {
+        match response
+            .header("content-length")
+            .and_then(|len| len.parse::<usize>().ok())
+        {
+            Some(len) if len <= body.len() => body[..len].to_vec(),
+            Some(_) => return Err("body shorter than content-length"),
+            None => body.to_vec(),
+        }
+    };
+    return Ok(response);
+}
+
+// Transfer-Encoding: chunked
+fn dechunk(mut body: &[u8]) -> Result<Vec<u8>, &'static str> {
+    let mut out = Vec::new();
+    loop {
+        let line_end = match body.windows(2).position(|w| w == b"\r\n") {
+            Some(end) => end,
+            None => return Err("unterminated chunk size"),
+        };
+        let line = String::from_utf8_lossy(&body[..line_end]);
+        // 12;name=value のような拡張は読まない
+        let size = line.split(';').next().unwrap_or("").trim();
+        let size = match usize::from_str_radix(size, 16) {
+            Ok(size) => size,
+            Err(_) => return Err("invalid chunk size"),
+        };
+        body = &body[line_end + 2..];
+        if size == 0 {
+            return Ok(out);
+        }
+        // ffffffffffffffff のような大きさでも溢れないように
+        match size.checked_add(2) {
+            Some(end) if end <= body.len() => {}
+            Some(_) => return Err("truncated chunk"),
+            None => return Err("invalid chunk size"),
+        }
+        out.extend_from_slice(&body[..size]);
+        body = &body[size + 2..];
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::net::http::{parse_response, request_target, split_url, HttpTransport};
+    use crate::net::Transport;
+    use std::io::{Read, Write};
+    use std::net::TcpListener;
+    use std::thread;
+
+    #[test]
+    fn split_urls() {
+        assert_eq!(
+            split_url("http://example.com"),
+            Some(("example.com", "example.com", 80, "/"))
+        );
+        assert_eq!(
+            split_url("HTTP://user@[::1]:8080/a?b#c"),
+            Some(("[::1]:8080", "::1", 8080, "/a?b"))
+        );
+        assert_eq!(split_url("https://example.com/"), None);
+        assert_eq!(split_url("http://a.com\r\nX-Injected: 1/"), None);
+        assert_eq!(request_target("/a b\r\nX: y"), "/a%20b%0D%0AX:%20y");
+        assert_eq!(request_target("/caf\u{e9}?q=1"), "/caf\u{e9}?q=1");
+    }
+
+    #[test]
+    fn parse_responses() {
+        let response =
+            parse_response(b"HTTP/1.1 200 OK\r\nContent-Length: 5\r\nX-A: b\r\n\r\nhello!!")
+                .unwrap();
+        assert_eq!(response.status, 200);
+        assert_eq!(response.header("x-a"), Some("b"));
+        assert_eq!(response.body, b"hello");
+
+        let response = parse_response(
+            b"HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n3\r\nabc\r\n2;x=y\r\nde\r\n0\r\n\r\n",
+        )
+        .unwrap();
+        assert_eq!(response.body, b"abcde");
+        assert!(parse_response(b"garbage").is_err());
+        assert_eq!(
+            parse_response(
+                b"HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\nffffffffffffffff\r\nabc\r\n"
+            )
+            .unwrap_err(),
+            "invalid chunk size"
+        );
+    }
+
+    #[test]
+    fn get_from_local_server() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let server = thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut request = Vec::new();
+            let mut buf = [0; 256];
+            while !request.ends_with(b"\r\n\r\n") {
+                let n = stream.read(&mut buf).unwrap();
+                request.extend_from_slice(&buf[..n]);
+            }
+            stream
+                .write_all(b"HTTP/1.1 200 OK\r\nContent-Type: text/html\r\nContent-Length: 8\r\n\r\n<p>x</p>")
+                .unwrap();
+            return String::from_utf8(request).unwrap();
+        });
+
+        let url = format!("http://127.0.0.1:{}/index.html", port);
+        let response = HttpTransport::new().get(&url).unwrap();
+        assert_eq!(response.status, 200);
+        assert_eq!(response.content_type(), Some("text/html"));
+        assert_eq!(response.body, b"<p>x</p>");
+
+        let request = server.join().unwrap();
+        assert!(request.starts_with("GET /index.html HTTP/1.1\r\n"));
+        assert!(request.contains(&format!("\r\nHost: 127.0.0.1:{}\r\n", port)));
+    }
+
+    #[test]
+    fn large_responses_are_cut_off() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let server = thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut request = Vec::new();
+            let mut buf = [0; 256];
+            while !request.ends_with(b"\r\n\r\n") {
+                let n = stream.read(&mut buf).unwrap();
+                request.extend_from_slice(&buf[..n]);
+            }
+            // 読むのをやめられても止まらないように、書けなくなったら終える
+            let _ = stream.write_all(b"HTTP/1.1 200 OK\r\n\r\n");
+            let _ = stream.write_all(&vec![b'x'; 1024 * 1024]);
+            return String::from_utf8(request).unwrap();
+        });
+
+        let transport = HttpTransport {
+            max_response_bytes: 1024,
+            ..HttpTransport::new()
+        };
+        let url = format!("http://127.0.0.1:{}/a b.html", port);
+        let err = transport.get(&url).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            format!(
+                "invalid response from {}: response larger than 1024 bytes",
+                url
+            )
+        );
+        let request = server.join().unwrap();
+        assert!(request.starts_with("GET /a%20b.html HTTP/1.1\r\n"));
+    }
+}
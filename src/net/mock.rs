@@ -0,0 +1,37 @@
+use crate::net::errors::FetchError;
+use crate::net::{Response, Transport};
+use std::cell::RefCell;
+use std::collections::HashMap;
+
+// 決まったURLに決まったResponseを返す。テスト用
+// 登録していないURLは404
+#[derive(Debug, Clone, Default)]
+pub struct MockTransport {
+    pub responses: HashMap<String, Response>,
+    // getされたURLを順に残す
+    requests: RefCell<Vec<String>>,
+}
+
+impl MockTransport {
+    pub fn new() -> MockTransport {
+        return MockTransport::default();
+    }
+
+    pub fn insert(&mut self, url: &str, response: Response) {
+        self.responses.insert(url.to_string(), response);
+    }
+
+    pub fn requests(&self) -> Vec<String> {
+        return self.requests.borrow().clone();
+    }
+}
+
+impl Transport for MockTransport {
+    fn get(&self, url: &str) -> Result<Response, FetchError> {
+        self.requests.borrow_mut().push(url.to_string());
+        return match self.responses.get(url) {
+            Some(response) => Ok(response.clone()),
+            None => Ok(Response::new(404, b"")),
+        };
+    }
+}
@@ -0,0 +1,7 @@
+// use browser::prelude::*; で、読み込みと問い合わせに要るものと、実装して渡すtraitをまとめて入れる
+pub use crate::html::document::{Document, ElementRef};
+pub use crate::html::errors::ParseError;
+pub use crate::html::loader::ResourceLoader;
+pub use crate::html::parse;
+pub use crate::html::parser::{Node, NodeData};
+pub use crate::net::Transport;
@@ -0,0 +1,29 @@
+pub mod ansi;
+pub mod tree;
+
+use crate::css;
+use crate::css::computed::ComputedStyles;
+use crate::css::ua;
+use crate::html::document::Document;
+use crate::render::ansi::AnsiOptions;
+use crate::render::tree::RenderTree;
+
+// 表示するboxだけの木を作る。大きさや位置はまだ決めない
+pub fn build_render_tree<'a>(doc: &'a Document, styles: &'a ComputedStyles) -> RenderTree<'a> {
+    return RenderTree::build(doc, styles);
+}
+
+// 端末で読めるように、ANSI escape sequenceで飾った文字列にする
+// 色を使うかはNO_COLORで決める
+pub fn to_ansi(doc: &Document, width: usize) -> String {
+    return to_ansi_with(doc, &AnsiOptions::new(width));
+}
+
+// 既定のstylesheetと文書の <style> でstyleを計算してから書く
+pub fn to_ansi_with(doc: &Document, options: &AnsiOptions) -> String {
+    let mut sheets = vec![ua::stylesheet()];
+    sheets.extend(doc.stylesheets());
+    let styles = css::compute_styles(doc, &sheets);
+    let tree = RenderTree::build(doc, &styles);
+    return ansi::render(&tree, options);
+}
@@ -0,0 +1,533 @@
+use crate::css::computed::{ComputedLength, ComputedStyle, Display, FontStyle, DEFAULT_FONT_SIZE};
+use crate::html::parser::{Node, NodeData};
+use crate::render::tree::{BlockOrInline, RenderNode, RenderTree};
+use std::env;
+use std::ffi::OsStr;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AnsiOptions {
+    // 字下げも含めた一行の幅 (文字数)
+    pub width: usize,
+    // falseならescape sequenceを書かない
+    pub color: bool,
+    // リンクの後にhrefを薄く書く
+    pub show_urls: bool,
+}
+
+impl AnsiOptions {
+    // 色を使うかは環境変数NO_COLORで決める
+    pub fn new(width: usize) -> AnsiOptions {
+        return AnsiOptions {
+            width,
+            color: color_allowed(env::var_os("NO_COLOR").as_deref()),
+            show_urls: false,
+        };
+    }
+}
+
+// NO_COLORが空でなければ色を使わない (https://no-color.org)
+fn color_allowed(no_color: Option<&OsStr>) -> bool {
+    return no_color.is_none_or(|value| value.is_empty());
+}
+
+// SGRで付ける見た目
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+struct Sgr {
+    bold: bool,
+    dim: bool,
+    italic: bool,
+    underline: bool,
+    strike: bool,
+    link: bool,
+}
+
+impl Sgr {
+    fn escape(&self) -> String {
+        let mut codes = Vec::new();
+        for (on, code) in [
+            (self.bold, "1"),
+            (self.dim, "2"),
+            (self.italic, "3"),
+            (self.underline || self.link, "4"),
+            (self.strike, "9"),
+            // リンクは青
+            (self.link, "34"),
+        ] {
+            if on {
+                codes.push(code);
+            }
+        }
+        if codes.is_empty() {
+            return String::new();
+        }
+        return format!("\x1b[{}m", codes.join(";"));
+    }
+}
+
+const RESET: &str = "\x1b[0m";
+
+enum Piece {
+    Word { text: String, sgr: Sgr, glue: bool },
+    // <br>
+    Break,
+}
+
+// 前に空白を入れない記号。parserがtextを空白で区切るので、区切りを詰め直す
+fn closes(text: &str) -> bool {
+    return text.starts_with([',', '.', ';', ':', '!', '?', ')', ']']);
+}
+
+fn opens(text: &str) -> bool {
+    return text.ends_with(['(', '[']);
+}
+
+// ol/ulの中で次のliに付ける番号
+struct ListState {
+    ordered: bool,
+    next: i64,
+}
+
+struct AnsiWriter<'o> {
+    options: &'o AnsiOptions,
+    out: String,
+    // 行の頭に付けるもの (書く文字列, 見た目の幅)
+    prefixes: Vec<(String, usize)>,
+    // まだ一行も書いていないliの印と、それで置き換えるprefixの位置
+    marker: Option<(usize, String)>,
+    lists: Vec<ListState>,
+    // 次に書く前に一行空ける。空けると決めたときのprefixの数だけ行頭に付ける
+    blank: Option<usize>,
+}
+
+impl<'o> AnsiWriter<'o> {
+    fn dim(&self, s: &str) -> String {
+        if !self.options.color {
+            return s.to_string();
+        }
+        return format!("\x1b[2m{}{}", s, RESET);
+    }
+
+    fn prefix_width(&self) -> usize {
+        return self.prefixes.iter().map(|(_, width)| width).sum();
+    }
+
+    fn write_line(&mut self, line: &str) {
+        if let Some(n) = self.blank.take() {
+            if !self.out.is_empty() {
+                let prefix: String = self.prefixes[..n].iter().map(|(s, _)| s.as_str()).collect();
+                self.out += prefix.trim_end();
+                self.out.push('\n');
+            }
+        }
+        for (i, (s, _)) in self.prefixes.iter().enumerate() {
+            match &self.marker {
+                Some((index, marker)) if *index == i => self.out += marker,
+                _ => self.out += s,
+            }
+        }
+        self.marker = None;
+        self.out += line;
+        self.out.push('\n');
+    }
+
+    fn request_blank(&mut self) {
+        let n = self.prefixes.len();
+        self.blank = Some(self.blank.map_or(n, |blank| blank.min(n)));
+    }
+
+    fn push_prefix(&mut self, s: String, width: usize) {
+        self.prefixes.push((s, width));
+    }
+
+    fn block(&mut self, b: &RenderNode) {
+        let name = match b.node_ref.and_then(|nd| nd.name()) {
+            Some(name) => name.as_str(),
+            None => "",
+        };
+        let spaced = is_spaced(b, name);
+        if spaced {
+            self.request_blank();
+        }
+
+        let prefixes = self.prefixes.len();
+        let is_list_item = match b.style_ref {
+            Some(style) if b.node_ref.is_some() => style.display == Display::ListItem,
+            _ => name == "li",
+        };
+        if is_list_item {
+            let marker = match self.lists.last_mut() {
+                Some(list) if list.ordered => {
+                    list.next += 1;
+                    format!("{}. ", list.next - 1)
+                }
+                _ => "• ".to_string(),
+            };
+            let width = marker.chars().count();
+            self.marker = Some((self.prefixes.len(), marker));
+            self.push_prefix(" ".repeat(width), width);
+        }
+        match name {
+            "blockquote" => {
+                let bar = self.dim("│");
+                self.push_prefix(format!("{} ", bar), 2);
+            }
+            "ul" | "ol" => {
+                let start = b
+                    .node_ref
+                    .and_then(|nd| nd.attr("start"))
+                    .and_then(|start| start.trim().parse().ok());
+                self.lists.push(ListState {
+                    ordered: name == "ol",
+                    next: start.unwrap_or(1),
+                });
+            }
+            "hr" => {
+                let width = self
+                    .options
+                    .width
+                    .saturating_sub(self.prefix_width())
+                    .max(1);
+                let rule = self.dim(&"─".repeat(width));
+                self.write_line(&rule);
+            }
+            _ => {}
+        }
+
+        if b.children
+            .iter()
+            .all(|child| child.display == BlockOrInline::Inline)
+        {
+            // 匿名のblockは親のstyleで書く
+            let base = styled(Sgr::default(), b.node_ref, b.style_ref, DEFAULT_FONT_SIZE);
+            let font_size = match b.style_ref {
+                Some(style) => style.font_size,
+                None => DEFAULT_FONT_SIZE,
+            };
+            let mut pieces = Vec::new();
+            self.inline(&b.children, base, font_size, &mut pieces);
+            let lines = self.wrap(&pieces);
+            let longest = lines.iter().map(|(_, width)| *width).max();
+            for (line, _) in &lines {
+                self.write_line(line);
+            }
+            // 見出しの下に線を引く
+            let rule = match name {
+                "h1" => Some("="),
+                "h2" => Some("-"),
+                _ => None,
+            };
+            if let (Some(rule), Some(longest)) = (rule, longest) {
+                self.write_line(&rule.repeat(longest));
+            }
+        } else {
+            for child in &b.children {
+                self.block(child);
+            }
+        }
+
+        if matches!(name, "ul" | "ol") {
+            self.lists.pop();
+        }
+        self.prefixes.truncate(prefixes);
+        if self.marker.as_ref().is_some_and(|(i, _)| *i >= prefixes) {
+            self.marker = None;
+        }
+        if spaced {
+            self.request_blank();
+        }
+    }
+
+    fn inline(&self, boxes: &[RenderNode], base: Sgr, font_size: f64, pieces: &mut Vec<Piece>) {
+        for b in boxes {
+            let nd = match b.node_ref {
+                Some(nd) => nd,
+                None => {
+                    self.inline(&b.children, base, font_size, pieces);
+                    continue;
+                }
+            };
+            match &nd.data {
+                NodeData::Text(s) => {
+                    let glue = closes(s.as_str())
+                        || matches!(pieces.last(), Some(Piece::Word { text, .. }) if opens(text));
+                    pieces.push(Piece::Word {
+                        text: s.as_str().to_string(),
+                        sgr: base,
+                        glue,
+                    });
+                }
+                NodeData::Element { name, .. } if *name == "br" => pieces.push(Piece::Break),
+                NodeData::Element { .. } => {
+                    let sgr = styled(base, Some(nd), b.style_ref, font_size);
+                    self.inline(&b.children, sgr, font_size, pieces);
+                    match nd.attr("href") {
+                        Some(href) if sgr.link && self.options.show_urls => {
+                            pieces.push(Piece::Word {
+                                text: format!("({})", href),
+                                sgr: Sgr {
+                                    dim: true,
+                                    ..Sgr::default()
+                                },
+                                glue: false,
+                            })
+                        }
+                        _ => {}
+                    }
+                }
+                NodeData::Comment(_) | NodeData::Doctype(_) | NodeData::Template(_) => {}
+            }
+        }
+    }
+
+    // 字下げを除いた幅で折り返す。(escape sequence込みの行, 見た目の幅)
+    fn wrap(&self, pieces: &[Piece]) -> Vec<(String, usize)> {
+        let available = self
+            .options
+            .width
+            .saturating_sub(self.prefix_width())
+            .max(1);
+        let mut lines = Vec::new();
+        let mut line = String::new();
+        let mut width = 0;
+        // lineの今の見た目
+        let mut current = Sgr::default();
+
+        let set = |line: &mut String, current: &mut Sgr, sgr: Sgr| {
+            if *current == sgr {
+                return;
+            }
+            if self.options.color {
+                if *current != Sgr::default() {
+                    line.push_str(RESET);
+                }
+                line.push_str(&sgr.escape());
+            }
+            *current = sgr;
+        };
+        let finish = |lines: &mut Vec<(String, usize)>,
+                      line: &mut String,
+                      width: &mut usize,
+                      current: &mut Sgr| {
+            set(line, current, Sgr::default());
+            lines.push((std::mem::take(line), *width));
+            *width = 0;
+        };
+
+        for piece in pieces {
+            match piece {
+                Piece::Break => finish(&mut lines, &mut line, &mut width, &mut current),
+                Piece::Word { text, sgr, glue } => {
+                    let text_width = text.chars().count();
+                    let mut space = width > 0 && !glue;
+                    if width > 0 && width + space as usize + text_width > available {
+                        finish(&mut lines, &mut line, &mut width, &mut current);
+                        space = false;
+                    }
+                    if space {
+                        // 見た目が続くときだけ、空白にも下線などを付ける
+                        if current != *sgr {
+                            set(&mut line, &mut current, Sgr::default());
+                        }
+                        line.push(' ');
+                        width += 1;
+                    }
+                    set(&mut line, &mut current, *sgr);
+                    line.push_str(text);
+                    width += text_width;
+                }
+            }
+        }
+        if width > 0 {
+            finish(&mut lines, &mut line, &mut width, &mut current);
+        }
+        return lines;
+    }
+}
+
+// 要素の見た目をbaseに重ねる。styleがなければタグで決める
+fn styled(base: Sgr, nd: Option<&Node>, style: Option<&ComputedStyle>, font_size: f64) -> Sgr {
+    let mut sgr = base;
+    let name = match nd.and_then(|nd| nd.name()) {
+        Some(name) => name.as_str(),
+        None => "",
+    };
+    match style {
+        Some(style) => {
+            sgr.bold = style.font_weight >= 600;
+            sgr.italic = style.font_style != FontStyle::Normal;
+            // text-decorationは継承しないが、子孫のtextにも線が引かれる
+            sgr.underline |= style.text_decoration.underline;
+            sgr.strike |= style.text_decoration.line_through;
+            // 囲んでいるblockより小さい文字
+            sgr.dim |= style.font_size < font_size * 0.95;
+        }
+        None => match name {
+            "b" | "strong" | "h1" | "h2" | "h3" | "h4" | "h5" | "h6" => sgr.bold = true,
+            "em" | "i" | "cite" => sgr.italic = true,
+            "u" | "ins" => sgr.underline = true,
+            "s" | "strike" | "del" => sgr.strike = true,
+            "small" => sgr.dim = true,
+            _ => {}
+        },
+    }
+    if name == "a" && nd.is_some_and(|nd| nd.attr("href").is_some()) {
+        sgr.link = true;
+    }
+    return sgr;
+}
+
+// 上下にmarginがあるblockは、前後を一行空ける
+fn is_spaced(b: &RenderNode, name: &str) -> bool {
+    // html/bodyのmarginは画面の端との間
+    if b.node_ref.is_none() || matches!(name, "html" | "body") {
+        return false;
+    }
+    return match b.style_ref {
+        Some(style) => [style.margin.top, style.margin.bottom]
+            .iter()
+            .any(|margin| matches!(margin, ComputedLength::Px(px) if *px > 0.0)),
+        None => matches!(
+            name,
+            "p" | "h1"
+                | "h2"
+                | "h3"
+                | "h4"
+                | "h5"
+                | "h6"
+                | "ul"
+                | "ol"
+                | "blockquote"
+                | "pre"
+                | "dl"
+                | "figure"
+        ),
+    };
+}
+
+pub fn render(tree: &RenderTree, options: &AnsiOptions) -> String {
+    let mut writer = AnsiWriter {
+        options,
+        out: String::new(),
+        prefixes: Vec::new(),
+        marker: None,
+        lists: Vec::new(),
+        blank: None,
+    };
+    writer.block(&tree.root);
+    return writer.out;
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::css;
+    use crate::html;
+    use crate::render;
+    use crate::render::ansi::{color_allowed, AnsiOptions};
+    use std::ffi::OsStr;
+
+    const FIXTURE: &str = "<html><head><title>t</title>\
+        <style>.note { font-weight: bold } .plain { font-style: normal }</style></head><body>\
+        <h1>Title</h1>\
+        <p>Some <strong>bold</strong> and <em>italic</em> text, see <a href=\"/docs\">the docs</a>.</p>\
+        <p class=\"note\">Note <small>(fine print)</small></p>\
+        <ul><li>one</li><li>two<ol start=\"3\"><li>three</li><li>four</li></ol></li></ul>\
+        <blockquote><p>quoted <em class=\"plain\">words</em> here</p></blockquote>\
+        </body></html>";
+
+    fn options(color: bool, show_urls: bool) -> AnsiOptions {
+        return AnsiOptions {
+            width: 40,
+            color,
+            show_urls,
+        };
+    }
+
+    #[test]
+    fn styled_fixture() {
+        let doc = html::parse(FIXTURE).unwrap();
+        assert_eq!(
+            render::to_ansi_with(&doc, &options(true, true)),
+            "\x1b[1mTitle\x1b[0m\n\
+             =====\n\
+             \n\
+             Some \x1b[1mbold\x1b[0m and \x1b[3mitalic\x1b[0m text, see \x1b[4;34mthe docs\x1b[0m\n\
+             \x1b[2m(/docs)\x1b[0m.\n\
+             \n\
+             \x1b[1mNote\x1b[0m \x1b[1;2m(fine print)\x1b[0m\n\
+             \n\
+             • one\n\
+             • two\n\
+             \x20 3. three\n\
+             \x20 4. four\n\
+             \n\
+             \x1b[2m│\x1b[0m quoted words here\n"
+        );
+    }
+
+    #[test]
+    fn plain_without_color() {
+        let doc = html::parse(FIXTURE).unwrap();
+        assert_eq!(
+            render::to_ansi_with(&doc, &options(false, false)),
+            "Title\n\
+             =====\n\
+             \n\
+             Some bold and italic text, see the docs.\n\
+             \n\
+             Note (fine print)\n\
+             \n\
+             • one\n\
+             • two\n\
+             \x20 3. three\n\
+             \x20 4. four\n\
+             \n\
+             │ quoted words here\n"
+        );
+    }
+
+    #[test]
+    fn no_color_disables_color() {
+        assert!(color_allowed(None));
+        assert!(color_allowed(Some(OsStr::new(""))));
+        assert!(!color_allowed(Some(OsStr::new("1"))));
+        assert!(!color_allowed(Some(OsStr::new("false"))));
+    }
+
+    #[test]
+    fn wrap_inside_prefixes() {
+        let doc = html::parse(
+            "<ul><li>alpha beta gamma delta</li></ul><hr/><blockquote>a b</blockquote>",
+        )
+        .unwrap();
+        let options = AnsiOptions {
+            width: 14,
+            color: false,
+            show_urls: false,
+        };
+        assert_eq!(
+            render::to_ansi_with(&doc, &options),
+            "• alpha beta\n  gamma delta\n\n──────────────\n\n│ a b\n"
+        );
+    }
+
+    #[test]
+    fn anonymous_blocks_keep_parent_style() {
+        let doc = html::parse("<div style=\"font-weight: bold\">a<p>b</p></div>").unwrap();
+        assert_eq!(
+            render::to_ansi_with(&doc, &options(true, false)),
+            "\x1b[1ma\x1b[0m\n\n\x1b[1mb\x1b[0m\n"
+        );
+    }
+
+    #[test]
+    fn tag_defaults_without_styles() {
+        let doc = html::parse("<p><b>x</b> <a href=\"/\">y</a></p>").unwrap();
+        let other = html::parse("<p></p>").unwrap();
+        let styles = css::compute_styles(&other, &[]);
+        let tree = render::build_render_tree(&doc, &styles);
+        assert_eq!(
+            render::ansi::render(&tree, &options(true, false)),
+            "\x1b[1mx\x1b[0m \x1b[4;34my\x1b[0m\n"
+        );
+    }
+}
@@ -0,0 +1,320 @@
+use crate::css::computed::{ComputedStyle, ComputedStyles, Display};
+use crate::css::ua;
+use crate::html::document::Document;
+use crate::html::parser::{Node, NodeData};
+use std::fmt;
+use std::mem;
+
+// stylesheetでdisplayを変えられても表示しない要素
+const NEVER_RENDERED: [&str; 4] = ["head", "script", "style", "template"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum BlockOrInline {
+    Block,
+    Inline,
+}
+
+#[derive(Debug, Clone)]
+pub struct RenderNode<'a> {
+    // 匿名のboxならNone。それ以外は要素かtext
+    pub node_ref: Option<&'a Node>,
+    pub display: BlockOrInline,
+    // textと匿名のboxは親の要素のstyle。ComputedStylesにない要素ならNone
+    pub style_ref: Option<&'a ComputedStyle>,
+    pub children: Vec<RenderNode<'a>>,
+}
+
+impl<'a> RenderNode<'a> {
+    pub fn is_anonymous(&self) -> bool {
+        return self.node_ref.is_none();
+    }
+
+    // 空白だけのtext。blockの間にあっても何も表示しない
+    fn is_collapsible_whitespace(&self) -> bool {
+        return match self.node_ref.map(|nd| &nd.data) {
+            Some(NodeData::Text(s)) => s
+                .as_str()
+                .chars()
+                .all(|c| matches!(c, ' ' | '\t' | '\n' | '\x0c' | '\r')),
+            _ => false,
+        };
+    }
+}
+
+// 根は文書全体を入れる匿名のblock
+#[derive(Debug, Clone)]
+pub struct RenderTree<'a> {
+    pub root: RenderNode<'a>,
+}
+
+// 子を辿っている途中の要素
+struct Frame<'a> {
+    node: Option<&'a Node>,
+    display: BlockOrInline,
+    // display: contentsならboxを作らず、子を親に渡す
+    contents: bool,
+    style: Option<&'a ComputedStyle>,
+    children: &'a [Node],
+    next: usize,
+    boxes: Vec<RenderNode<'a>>,
+}
+
+impl<'a> RenderTree<'a> {
+    // stylesのない要素はタグごとの既定のdisplayで並べる
+    pub fn build(doc: &'a Document, styles: &'a ComputedStyles) -> RenderTree<'a> {
+        let mut stack = vec![Frame {
+            node: None,
+            display: BlockOrInline::Block,
+            contents: false,
+            style: None,
+            children: &doc.children,
+            next: 0,
+            boxes: Vec::new(),
+        }];
+        // 深い木でも再帰しないように、辿っている途中の要素を自前のスタックに積む
+        while let Some(frame) = stack.last_mut() {
+            let children = frame.children;
+            if let Some(child) = children.get(frame.next) {
+                frame.next += 1;
+                match &child.data {
+                    NodeData::Text(_) => frame.boxes.push(RenderNode {
+                        node_ref: Some(child),
+                        display: BlockOrInline::Inline,
+                        style_ref: frame.style,
+                        children: Vec::new(),
+                    }),
+                    NodeData::Element { name, children, .. } => {
+                        if NEVER_RENDERED.contains(&name.as_str()) {
+                            continue;
+                        }
+                        let style = styles.style(child);
+                        let display = match style {
+                            Some(style) => style.display,
+                            None => ua::default_display(name.as_str()),
+                        };
+                        let outer = match display {
+                            Display::None => continue,
+                            Display::Inline
+                            | Display::InlineBlock
+                            | Display::InlineFlex
+                            | Display::InlineGrid => BlockOrInline::Inline,
+                            _ => BlockOrInline::Block,
+                        };
+                        // 中身を持てる匿名のboxやtextは、親のstyleのままにする
+                        let style = match (style, display) {
+                            (_, Display::Contents) => frame.style,
+                            (Some(style), _) => Some(style),
+                            (None, _) => frame.style,
+                        };
+                        stack.push(Frame {
+                            node: Some(child),
+                            display: outer,
+                            contents: display == Display::Contents,
+                            style,
+                            children,
+                            next: 0,
+                            boxes: Vec::new(),
+                        });
+                    }
+                    // テンプレートは画面に出すものが決まっていない
+                    NodeData::Comment(_) | NodeData::Doctype(_) | NodeData::Template(_) => {}
+                }
+                continue;
+            }
+
+            let frame = match stack.pop() {
+                Some(frame) => frame,
+                None => break,
+            };
+            let parent = match stack.last_mut() {
+                Some(parent) => parent,
+                None => {
+                    return RenderTree {
+                        root: RenderNode {
+                            node_ref: None,
+                            display: BlockOrInline::Block,
+                            style_ref: None,
+                            children: wrap_inline_runs(frame.style, frame.boxes),
+                        },
+                    };
+                }
+            };
+            if frame.contents {
+                // 匿名のboxで包むかは親が決める
+                parent.boxes.extend(frame.boxes);
+                continue;
+            }
+            let children = match frame.display {
+                BlockOrInline::Block => wrap_inline_runs(frame.style, frame.boxes),
+                // inlineの中のblockで inlineを分けることはまだしない
+                BlockOrInline::Inline => frame.boxes,
+            };
+            parent.boxes.push(RenderNode {
+                node_ref: frame.node,
+                display: frame.display,
+                style_ref: frame.style,
+                children,
+            });
+        }
+        return RenderTree {
+            root: RenderNode {
+                node_ref: None,
+                display: BlockOrInline::Block,
+                style_ref: None,
+                children: Vec::new(),
+            },
+        };
+    }
+}
+
+// blockとinlineが混ざっていたら、続いたinlineを匿名のblockで包む
+// 空白だけのinlineの並びは捨てる
+fn wrap_inline_runs<'a>(
+    style: Option<&'a ComputedStyle>,
+    boxes: Vec<RenderNode<'a>>,
+) -> Vec<RenderNode<'a>> {
+    if !boxes.iter().any(|b| b.display == BlockOrInline::Block) {
+        return boxes;
+    }
+    let mut wrapped = Vec::new();
+    let mut run: Vec<RenderNode<'a>> = Vec::new();
+    let flush = |wrapped: &mut Vec<RenderNode<'a>>, run: &mut Vec<RenderNode<'a>>| {
+        if run.iter().all(|b| b.is_collapsible_whitespace()) {
+            run.clear();
+            return;
+        }
+        wrapped.push(RenderNode {
+            node_ref: None,
+            display: BlockOrInline::Block,
+            style_ref: style,
+            children: mem::take(run),
+        });
+    };
+    for b in boxes {
+        match b.display {
+            BlockOrInline::Inline => run.push(b),
+            BlockOrInline::Block => {
+                flush(&mut wrapped, &mut run);
+                wrapped.push(b);
+            }
+        }
+    }
+    flush(&mut wrapped, &mut run);
+    return wrapped;
+}
+
+// 一行に一つのboxを、深さの分だけ字下げして書く。根は書かない
+// block <p> / inline "text" / block (anonymous)
+impl fmt::Display for RenderTree<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut stack: Vec<(&RenderNode, usize)> =
+            self.root.children.iter().rev().map(|b| (b, 0)).collect();
+        while let Some((b, depth)) = stack.pop() {
+            let display = match b.display {
+                BlockOrInline::Block => "block",
+                BlockOrInline::Inline => "inline",
+            };
+            write!(f, "{}{}", "  ".repeat(depth), display)?;
+            match b.node_ref.map(|nd| &nd.data) {
+                None => write!(f, " (anonymous)")?,
+                Some(NodeData::Element { name, .. }) => write!(f, " <{}>", name)?,
+                Some(NodeData::Text(s)) => write!(f, " {:?}", s.as_str())?,
+                Some(_) => {}
+            }
+            writeln!(f)?;
+            stack.extend(b.children.iter().rev().map(|child| (child, depth + 1)));
+        }
+        return Ok(());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::css;
+    use crate::css::ua;
+    use crate::html;
+    use crate::render;
+    use crate::render::tree::BlockOrInline;
+
+    fn render(html: &str) -> String {
+        let doc = html::parse(html).unwrap();
+        let mut sheets = vec![ua::stylesheet()];
+        sheets.extend(doc.stylesheets());
+        let styles = css::compute_styles(&doc, &sheets);
+        return render::build_render_tree(&doc, &styles).to_string();
+    }
+
+    #[test]
+    fn build_box_tree() {
+        let tree = render(
+            "<html><head><title>t</title><style>.hidden { display: none }</style></head><body>\
+             <div class=\"hidden\"><p>gone</p></div>\
+             <div>intro <em>text</em><p>para</p>tail</div>\
+             <!-- comment -->\
+             <ul><li>one</li><li style=\"display: none\">two</li></ul>\
+             <section style=\"display: contents\"><span>a</span><p>b</p></section>\
+             <script>var x;</script>\
+             </body></html>",
+        );
+        assert_eq!(
+            tree,
+            "block <html>
+  block <body>
+    block <div>
+      block (anonymous)
+        inline \"intro\"
+        inline <em>
+          inline \"text\"
+      block <p>
+        inline \"para\"
+      block (anonymous)
+        inline \"tail\"
+    block <ul>
+      block <li>
+        inline \"one\"
+    block (anonymous)
+      inline <span>
+        inline \"a\"
+    block <p>
+      inline \"b\"
+"
+        );
+    }
+
+    #[test]
+    fn whitespace_between_blocks() {
+        let tree = render(
+            "<html><body>\n  <p>a <b>b</b></p>\n  <span> </span>\n  <p>c</p>\n</body></html>",
+        );
+        // blockの間の空白は消えるが、中身のない要素は残る
+        assert_eq!(
+            tree,
+            "block <html>
+  block <body>
+    block <p>
+      inline \"a\"
+      inline <b>
+        inline \"b\"
+    block (anonymous)
+      inline <span>
+    block <p>
+      inline \"c\"
+"
+        );
+    }
+
+    #[test]
+    fn tag_defaults_without_styles() {
+        let doc = html::parse("<div>a<p>b</p></div>").unwrap();
+        // 別の文書のstyleしかなければ、どの要素もstyleを持たない
+        let other = html::parse("<div></div>").unwrap();
+        let styles = css::compute_styles(&other, &[]);
+        let tree = render::build_render_tree(&doc, &styles);
+
+        let div = &tree.root.children[0];
+        assert_eq!(div.display, BlockOrInline::Block);
+        assert!(div.style_ref.is_none());
+        assert!(div.children[0].is_anonymous());
+        assert_eq!(div.children[1].display, BlockOrInline::Block);
+    }
+}
@@ -0,0 +1,207 @@
+// wasm feature。JSからparseと問い合わせをするための入口
+// 返すものはJSONの文字列を作ってからJSON.parseで値にする。JSONを作るところはwasmでなくても試せる
+// panicはwasmではabortになり、JSの側には例外 (RuntimeError) として届く
+use crate::html;
+use crate::html::errors::ParseError;
+use crate::html::lsp::LineMap;
+use crate::html::parser::{Node, NodeData};
+use crate::html::serializer::outer_html;
+use wasm_bindgen::prelude::*;
+
+// {"doctype": {"name": "html"} | null, "children": [{"type": "element", "name": "p", "attrs": [["class", "a"]], "children": [...]}, ...]}
+#[wasm_bindgen]
+pub fn parse(html: &str) -> Result<JsValue, JsError> {
+    return to_js(&tree_json(html)?);
+}
+
+// 合った要素のouter HTMLを文書の順に
+#[wasm_bindgen]
+pub fn query_selector_all(html: &str, selector: &str) -> Result<JsValue, JsError> {
+    return to_js(&query_json(html, selector)?);
+}
+
+// [{"message": "...", "severity": "error", "range": {"start": {"line": 0, "character": 3}, ...}}]
+// html::parse (strict) で読むので、最初のエラーで止まって多くても1つ
+#[wasm_bindgen]
+pub fn validate(html: &str) -> Result<JsValue, JsError> {
+    return to_js(&diagnostics_json(html));
+}
+
+fn to_js(json: &str) -> Result<JsValue, JsError> {
+    return js_sys::JSON::parse(json).map_err(|_| JsError::new("failed to build result"));
+}
+
+fn tree_json(html: &str) -> Result<String, JsError> {
+    let doc = html::parse(html).map_err(|err| JsError::new(&err.to_string()))?;
+    let doctype = match &doc.doctype {
+        Some(doctype) => format!("{{\"name\":{}}}", json_string(&doctype.name)),
+        None => "null".to_string(),
+    };
+    return Ok(format!(
+        "{{\"doctype\":{},\"children\":{}}}",
+        doctype,
+        nodes_json(&doc.children)
+    ));
+}
+
+fn query_json(html: &str, selector: &str) -> Result<String, JsError> {
+    let doc = html::parse(html).map_err(|err| JsError::new(&err.to_string()))?;
+    let found = doc
+        .query_selector_all(selector)
+        .map_err(|err| JsError::new(&err.to_string()))?;
+    let items: Vec<String> = found
+        .iter()
+        .map(|nd| json_string(&outer_html(nd)))
+        .collect();
+    return Ok(format!("[{}]", items.join(",")));
+}
+
+fn diagnostics_json(html: &str) -> String {
+    return match html::parse(html) {
+        Ok(_) => "[]".to_string(),
+        Err(err) => format!("[{}]", diagnostic_json(&err, &LineMap::new(html))),
+    };
+}
+
+fn diagnostic_json(err: &ParseError, line_map: &LineMap) -> String {
+    let range = err.lsp_range(line_map);
+    return format!(
+        "{{\"message\":{},\"severity\":\"error\",\"range\":{{\"start\":{{\"line\":{},\"character\":{}}},\"end\":{{\"line\":{},\"character\":{}}}}}}}",
+        json_string(&err.to_string()),
+        range.start.line,
+        range.start.character,
+        range.end.line,
+        range.end.character
+    );
+}
+
+enum Step<'a> {
+    Node(&'a Node),
+    Str(&'static str),
+}
+
+// 深い木でも再帰しないように、閉じ括弧と区切りもスタックに積む
+fn nodes_json(nodes: &[Node]) -> String {
+    let mut out = String::from("[");
+    let mut stack = vec![Step::Str("]")];
+    push_children(&mut stack, nodes);
+    while let Some(step) = stack.pop() {
+        let nd = match step {
+            Step::Node(nd) => nd,
+            Step::Str(s) => {
+                out += s;
+                continue;
+            }
+        };
+        match &nd.data {
+            NodeData::Element {
+                name,
+                attrs,
+                children,
+                ..
+            } => {
+                let attrs: Vec<String> = attrs
+                    .iter()
+                    .map(|attr| {
+                        format!("[{},{}]", json_string(&attr.name), json_string(&attr.value))
+                    })
+                    .collect();
+                out += &format!(
+                    "{{\"type\":\"element\",\"name\":{},\"attrs\":[{}],\"children\":[",
+                    json_string(name.as_str()),
+                    attrs.join(",")
+                );
+                stack.push(Step::Str("]}"));
+                push_children(&mut stack, children);
+            }
+            NodeData::Text(s) => {
+                out += &format!("{{\"type\":\"text\",\"text\":{}}}", json_string(s))
+            }
+            NodeData::Comment(s) => {
+                out += &format!("{{\"type\":\"comment\",\"text\":{}}}", json_string(s))
+            }
+            NodeData::Doctype(doctype) => {
+                out += &format!(
+                    "{{\"type\":\"doctype\",\"name\":{}}}",
+                    json_string(&doctype.name)
+                )
+            }
+            NodeData::Template(s) => {
+                out += &format!("{{\"type\":\"template\",\"text\":{}}}", json_string(s))
+            }
+        }
+    }
+    return out;
+}
+
+fn push_children<'a>(stack: &mut Vec<Step<'a>>, children: &'a [Node]) {
+    for (i, child) in children.iter().enumerate().rev() {
+        stack.push(Step::Node(child));
+        if i > 0 {
+            stack.push(Step::Str(","));
+        }
+    }
+}
+
+fn json_string(s: &str) -> String {
+    let mut out = String::with_capacity(s.len() + 2);
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out += "\\\"",
+            '\\' => out += "\\\\",
+            '\n' => out += "\\n",
+            '\r' => out += "\\r",
+            '\t' => out += "\\t",
+            c if (c as u32) < 0x20 => out += &format!("\\u{:04x}", c as u32),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    return out;
+}
+
+// JsErrorやJsValueはwasmの外では作れないので、JSONを作るところだけを試す
+#[cfg(test)]
+mod tests {
+    use crate::html;
+    use crate::html::lsp::LineMap;
+    use crate::wasm::{diagnostic_json, diagnostics_json, json_string, nodes_json, query_json};
+
+    #[test]
+    fn tree_shape() {
+        let doc = html::parse("<!-- c --><div id=\"a\"><p>hi</p><br/></div>").unwrap();
+        assert_eq!(
+            nodes_json(&doc.children),
+            "[{\"type\":\"comment\",\"text\":\" c \"},\
+             {\"type\":\"element\",\"name\":\"div\",\"attrs\":[[\"id\",\"a\"]],\"children\":[\
+             {\"type\":\"element\",\"name\":\"p\",\"attrs\":[],\"children\":[{\"type\":\"text\",\"text\":\"hi\"}]},\
+             {\"type\":\"element\",\"name\":\"br\",\"attrs\":[],\"children\":[]}]}]"
+        );
+        assert_eq!(nodes_json(&[]), "[]");
+        assert_eq!(json_string("a\"b\\\n\u{1}"), "\"a\\\"b\\\\\\n\\u0001\"");
+    }
+
+    #[test]
+    fn query_outer_html() {
+        let found = match query_json("<ul><li class=\"a\">one</li><li>two three</li></ul>", "li") {
+            Ok(found) => found,
+            Err(_) => panic!("query failed"),
+        };
+        assert_eq!(
+            found,
+            "[\"<li class=\\\"a\\\">one</li>\",\"<li>two three</li>\"]"
+        );
+    }
+
+    #[test]
+    fn diagnostics() {
+        assert_eq!(diagnostics_json("<p>ok</p>"), "[]");
+        let source = "<p>\n<b>x</b <i>";
+        let err = html::parse(source).unwrap_err();
+        let expected = format!("[{}]", diagnostic_json(&err, &LineMap::new(source)));
+        assert_eq!(diagnostics_json(source), expected);
+        assert!(expected.starts_with("[{\"message\":\"unexpected token"));
+        assert!(expected.contains("\"range\":{\"start\":{\"line\":1,\"character\":"));
+    }
+}
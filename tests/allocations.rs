@@ -0,0 +1,126 @@
+#![cfg(all(feature = "parser", feature = "std"))]
+// tokenizerとparserが何回メモリを確保するかを数える
+// #[global_allocator] はそのtest binaryの全部に効くので、ほかのテストとは別のファイルにしている
+use browser::html::parser::Parser;
+use browser::html::tokenizer::Tokenizer;
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::Cell;
+
+// テスト中のスレッドだけ数える
+struct CountingAlloc;
+
+thread_local! {
+    static ALLOCATIONS: Cell<Option<usize>> = const { Cell::new(None) };
+}
+
+unsafe impl GlobalAlloc for CountingAlloc {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let _ = ALLOCATIONS.try_with(|c| c.set(c.get().map(|n| n + 1)));
+        return System.alloc(layout);
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout);
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        let _ = ALLOCATIONS.try_with(|c| c.set(c.get().map(|n| n + 1)));
+        return System.realloc(ptr, layout, new_size);
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAlloc = CountingAlloc;
+
+fn count_allocations<F: FnOnce()>(f: F) -> usize {
+    ALLOCATIONS.with(|c| c.set(Some(0)));
+    f();
+    return ALLOCATIONS.with(|c| c.replace(None)).unwrap_or(0);
+}
+
+const FIXTURE: &str = "<!DOCTYPE html>\n\
+    <html lang=\"en\">\n\
+    <head><title>example</title></head>\n\
+    <body>\n\
+    <h1 class=\"title\">Lorem ipsum dolor sit amet, consectetur adipiscing elit</h1>\n\
+    <p>Sed_do_eiusmod_tempor incididunt ut labore et dolore magna aliqua.</p>\n\
+    <img src=\"https://example.com/image.png\" alt=\"an example image\"/>\n\
+    </body>\n\
+    </html>";
+
+fn count_tokens() -> usize {
+    let mut tokenizer = Tokenizer::new(FIXTURE);
+    let mut n = 0;
+    let mut cur = tokenizer.tokenize();
+    while let Some(mut tok) = cur {
+        n += 1;
+        cur = tok.next.take();
+    }
+    return n;
+}
+
+#[test]
+fn tokenize_and_parse_allocations() {
+    let tokens = count_tokens();
+    let allocations = count_allocations(|| {
+        let mut tokenizer = Tokenizer::new(FIXTURE);
+        let mut parser = Parser::new();
+        let nodes = parser.parse(tokenizer.tokenize());
+        assert!(nodes.is_ok());
+    });
+    // 文字列をsourceと共有する前は1 tokenあたり10回ほど確保していた
+    assert!(
+        allocations < tokens * 2,
+        "{} tokens, {} allocations",
+        tokens,
+        allocations
+    );
+}
+
+#[test]
+fn element_allocations() {
+    // 子も属性も数個しかない、よくある形の要素 (li, a, b の3つ)
+    let html =
+        "<li class=\"item\" id=\"x\"><a href=\"/\">link</a> <b>bold</b> text</li>".repeat(100);
+    let elements = 300;
+    let mut tokenizer = Tokenizer::new(&html);
+    let tokens = tokenizer.tokenize();
+
+    let allocations = count_allocations(|| {
+        let mut parser = Parser::new();
+        let nodes = parser.parse(tokens);
+        assert!(nodes.is_ok());
+    });
+    // 子はVecにそのまま並ぶので、確保はほぼ子と属性のリストの分だけ
+    // smallvecなら属性が1つのときの確保がなくなる
+    #[cfg(not(feature = "smallvec"))]
+    let limit = elements * 2;
+    #[cfg(feature = "smallvec")]
+    let limit = elements * 3 / 2;
+    assert!(
+        allocations < limit,
+        "{} allocations for {} elements",
+        allocations,
+        elements
+    );
+}
+
+fn count_parse_allocations(html: &str) -> usize {
+    let mut tokenizer = Tokenizer::new(html);
+    let tokens = tokenizer.tokenize();
+    return count_allocations(|| {
+        let nodes = Parser::new().parse(tokens);
+        assert!(nodes.is_ok());
+    });
+}
+
+#[test]
+fn tag_name_case_allocations() {
+    // 表にない名前でも、小文字ならsourceを指すだけで確保しない
+    let known = count_parse_allocations(&"<div>x</div>".repeat(100));
+    let unknown = count_parse_allocations(&"<card>x</card>".repeat(100));
+    assert_eq!(known, unknown);
+    // 大文字の既知の名前は表を引くだけ
+    let upper = count_parse_allocations(&"<DIV>x</Div>".repeat(100));
+    assert_eq!(known, upper);
+}
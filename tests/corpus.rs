@@ -0,0 +1,277 @@
+#![cfg(all(feature = "parser", feature = "std"))]
+// tests/fixtures と tests/fixtures/corpus の実際にありそうな文書をlenientで読み、
+// 木の形そのものではなく、どの文書でも成り立つはずのことを確かめる
+// この2つは手で書いたもの。保存した実際のページは tests/fixtures/corpus/real に分けて、別に確かめる
+// corpusの文書はdifftestの対象にしていない (html5everとの違いを調べていないので)
+use browser::html;
+use browser::html::document::Document;
+use browser::html::parser::ParserOptions;
+use browser::html::serializer::{document_html, document_tree_test_format};
+use browser::html::tokenizer::Tokenizer;
+use std::fs;
+use std::ops::ControlFlow;
+use std::panic;
+
+// 中にtextがあるはずの要素。pは画像だけのこともあるので入れない
+const HAS_TEXT: [&str; 16] = [
+    "title",
+    "h1",
+    "h2",
+    "h3",
+    "li",
+    "th",
+    "td",
+    "dt",
+    "dd",
+    "label",
+    "legend",
+    "button",
+    "option",
+    "caption",
+    "figcaption",
+    "summary",
+];
+
+// (tests/fixtures からのpath, 中身)
+fn fixtures() -> Vec<(String, String)> {
+    let root = format!("{}/tests/fixtures", env!("CARGO_MANIFEST_DIR"));
+    let mut found = Vec::new();
+    for dir in ["", "corpus/"] {
+        for entry in fs::read_dir(format!("{}/{}", root, dir)).unwrap() {
+            let name = entry.unwrap().file_name().to_string_lossy().into_owned();
+            if !name.ends_with(".html") {
+                continue;
+            }
+            let name = format!("{}{}", dir, name);
+            let html = fs::read_to_string(format!("{}/{}", root, name)).unwrap();
+            found.push((name, html));
+        }
+    }
+    found.sort();
+    assert!(found.len() >= 10, "{} fixtures", found.len());
+    return found;
+}
+
+// tests/fixtures/corpus/real の保存した実際のページ (出どころとlicenseは同じdirのREADME.md)
+// 手で書いたfixtureと違って、どれも & ' " - / ! = や閉じていないタグを含む
+fn real_pages() -> Vec<(String, String)> {
+    let dir = format!("{}/tests/fixtures/corpus/real", env!("CARGO_MANIFEST_DIR"));
+    let mut found = Vec::new();
+    for entry in fs::read_dir(&dir).unwrap() {
+        let name = entry.unwrap().file_name().to_string_lossy().into_owned();
+        if !name.ends_with(".html") {
+            continue;
+        }
+        let html = fs::read_to_string(format!("{}/{}", dir, name)).unwrap();
+        found.push((name, html));
+    }
+    found.sort();
+    assert!(found.len() >= 5, "{} real pages", found.len());
+    return found;
+}
+
+// 実際のページのうち、今は通らないもの (file, 理由)。直ったらここから消す
+const REAL_PAGE_KNOWN_FAILURES: [(&str, &str); 3] = [
+    (
+        "daringfireball-1.html",
+        "DOCTYPEのpublic idとsystem idを読まない",
+    ),
+    (
+        "heise.html",
+        "<title> の中の &amp; を戻さないのに、書き出すときに & をescapeするので読み直すたびに増える",
+    ),
+    (
+        "salon-1.html",
+        "引用符を含む属性の名前 (title=\"a \" b=\"\") で、値の区切りがずれる",
+    ),
+];
+
+fn parse_lenient(html: &str) -> Document {
+    let options = ParserOptions {
+        lenient: true,
+        collect_metrics: true,
+        ..ParserOptions::default()
+    };
+    return html::parse_with_options(html, &options).unwrap();
+}
+
+fn warning_count(doc: &Document) -> usize {
+    return doc.metrics().map_or(0, |metrics| metrics.warning_count);
+}
+
+#[test]
+fn lenient_parse_reads_every_fixture() {
+    for (name, html) in fixtures() {
+        let options = ParserOptions {
+            lenient: true,
+            ..ParserOptions::default()
+        };
+        let result = html::parse_with_options(&html, &options);
+        assert!(result.is_ok(), "{}: {}", name, result.unwrap_err());
+        assert!(!result.unwrap().children.is_empty(), "{}", name);
+    }
+}
+
+#[test]
+fn serialized_tree_reparses_to_the_same_tree() {
+    for (name, html) in fixtures() {
+        let doc = parse_lenient(&html);
+        let serialized = document_html(&doc);
+        let again = parse_lenient(&serialized);
+        assert_eq!(
+            document_tree_test_format(&again),
+            document_tree_test_format(&doc),
+            "{}\n{}",
+            name,
+            serialized
+        );
+        // 書き出したものは閉じ忘れがないので、もう何も直さない
+        assert_eq!(warning_count(&again), 0, "{}\n{}", name, serialized);
+    }
+}
+
+// nodeは位置を持たないので、tokenとdoctypeの位置で確かめる
+#[test]
+fn positions_lie_within_the_input() {
+    for (name, html) in fixtures() {
+        let lines: Vec<usize> = html.split('\n').map(|line| line.chars().count()).collect();
+        let total = html.chars().count();
+        let within = |line_no: u32, at_line: u32, at_whole: u32| {
+            let line = lines.get(line_no as usize - 1);
+            return line.is_some_and(|len| at_line as usize <= *len) && at_whole as usize <= total;
+        };
+
+        let mut tokenizer = Tokenizer::new(&html);
+        let mut cur = tokenizer.tokenize();
+        while let Some(mut tok) = cur {
+            let pos = &tok.pos;
+            assert!(
+                within(pos.line_no, pos.at_line, pos.at_whole),
+                "{}: {:?} {:?}",
+                name,
+                tok.kind,
+                pos
+            );
+            cur = tok.next.take();
+        }
+
+        let doc = parse_lenient(&html);
+        let span = doc.doctype_node_span().and_then(|span| span.range.clone());
+        if let Some((begin, end)) = span {
+            assert!(begin.at_whole <= end.at_whole, "{}", name);
+            assert!(within(end.line_no, end.at_line, end.at_whole), "{}", name);
+        }
+    }
+}
+
+// lenientで閉じて読み進めたなら、warningが残っている
+#[test]
+fn unclosed_elements_are_closed_or_warned() {
+    for (name, html) in fixtures() {
+        let lenient = parse_lenient(&html);
+        let same_as_strict = match html::parse(&html) {
+            Ok(strict) => document_tree_test_format(&strict) == document_tree_test_format(&lenient),
+            Err(_) => false,
+        };
+        assert!(
+            same_as_strict || warning_count(&lenient) > 0,
+            "{}: lenient tree differs from strict without warnings",
+            name
+        );
+        if name.contains("broken") {
+            assert!(warning_count(&lenient) > 0, "{}", name);
+        }
+    }
+}
+
+#[test]
+fn text_content_where_expected() {
+    let mut checked = 0;
+    for (name, html) in fixtures() {
+        let doc = parse_lenient(&html);
+        doc.walk_elements(|el| {
+            let nd = el.node();
+            let expected = nd
+                .name()
+                .is_some_and(|tag| HAS_TEXT.contains(&tag.as_str()));
+            if expected {
+                checked += 1;
+                assert!(
+                    !nd.text_content_normalized().is_empty(),
+                    "{}: <{}> at {:?} has no text",
+                    name,
+                    nd.name().unwrap(),
+                    el.path()
+                );
+            }
+            return ControlFlow::Continue(());
+        });
+    }
+    assert!(checked > 100, "{} elements", checked);
+}
+
+// 実際のページでは木の形を決めきれないので、どのページでも成り立つことだけ見る
+// panicしない、木が空でない、書き出して読み直しても同じ木になる
+#[test]
+fn real_pages_parse_and_reparse() {
+    let mut failures = Vec::new();
+    let mut fixed = Vec::new();
+    for (name, html) in real_pages() {
+        let outcome = match panic::catch_unwind(|| check_real_page(&html)) {
+            Ok(outcome) => outcome,
+            Err(_) => Err(String::from("panicked")),
+        };
+        let known = REAL_PAGE_KNOWN_FAILURES
+            .iter()
+            .any(|(file, _)| *file == name);
+        match (outcome, known) {
+            (Ok(()), true) => fixed.push(name),
+            (Err(detail), false) => failures.push(format!("{}: {}", name, detail)),
+            _ => {}
+        }
+    }
+    assert!(
+        failures.is_empty(),
+        "unexpected failures:\n{}",
+        failures.join("\n")
+    );
+    assert!(
+        fixed.is_empty(),
+        "remove from REAL_PAGE_KNOWN_FAILURES: {:?}",
+        fixed
+    );
+}
+
+fn check_real_page(html: &str) -> Result<(), String> {
+    let options = ParserOptions {
+        lenient: true,
+        ..ParserOptions::default()
+    };
+    let doc = html::parse_with_options(html, &options).map_err(|err| err.to_string())?;
+    if doc.children.is_empty() {
+        return Err(String::from("empty tree"));
+    }
+    let serialized = document_html(&doc);
+    let again = html::parse_with_options(&serialized, &options)
+        .map_err(|err| format!("reparse: {}", err))?;
+    let (tree, tree_again) = (
+        document_tree_test_format(&doc),
+        document_tree_test_format(&again),
+    );
+    if tree_again != tree {
+        // 最初に違う行だけ示す
+        let at = tree
+            .lines()
+            .zip(tree_again.lines())
+            .position(|(a, b)| a != b)
+            .unwrap_or(0);
+        let line = |tree: &str| String::from(tree.lines().nth(at).unwrap_or(""));
+        return Err(format!(
+            "reparse changed the tree at line {}: {:?} -> {:?}",
+            at + 1,
+            line(&tree),
+            line(&tree_again)
+        ));
+    }
+    return Ok(());
+}
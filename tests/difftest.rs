@@ -0,0 +1,247 @@
+#![cfg(feature = "difftest")]
+// tests/fixtures/*.html をこのcrateとscraper (html5ever) の両方で読み、木を比べる
+// cargo test --features difftest --test difftest -- --nocapture で、今ある違いを一覧にする
+// 認めている違いは tests/difftest.allow に「fixture 最初に食い違うpath」で書く
+use browser::NodeData;
+use std::fs;
+
+// 比べる形。コメントとdoctypeは除き、textは空白をまとめる
+// html5everが補うhtml, head, bodyは、どちらの木でも子をその場に出して比べる
+#[derive(Debug, Clone, PartialEq)]
+enum Item {
+    Element {
+        name: String,
+        attrs: Vec<(String, String)>,
+        children: Vec<Item>,
+    },
+    Text(String),
+}
+
+const TRANSPARENT: [&str; 3] = ["html", "head", "body"];
+
+#[test]
+fn difftest_against_html5ever() {
+    let dir = format!("{}/tests/fixtures", env!("CARGO_MANIFEST_DIR"));
+    let allow = read_allow_list();
+    let mut known = Vec::new();
+    let mut failures = Vec::new();
+    let mut fixtures: Vec<String> = fs::read_dir(&dir)
+        .unwrap()
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.file_name().to_string_lossy().into_owned())
+        .filter(|name| name.ends_with(".html"))
+        .collect();
+    fixtures.sort();
+
+    for name in &fixtures {
+        let source = fs::read_to_string(format!("{}/{}", dir, name)).unwrap();
+        let expected = normalize(from_html5ever(&source));
+        let diff = match browser::parse(&source) {
+            Ok(doc) => {
+                let actual = normalize(doc.children.iter().flat_map(from_node).collect());
+                first_difference(&expected, &actual, "")
+            }
+            Err(err) => Some(("/".to_string(), format!("parse error: {}", err))),
+        };
+        match diff {
+            None => {}
+            Some((path, detail)) => {
+                let line = format!("{} {}", name, path);
+                if allow.contains(&line) {
+                    known.push((line, detail));
+                } else {
+                    failures.push(format!("{}: {}", line, detail));
+                }
+            }
+        }
+    }
+
+    // 直ったものはallow listから消す
+    let stale: Vec<&String> = allow
+        .iter()
+        .filter(|line| {
+            let name = line.split(' ').next().unwrap_or("");
+            let listed = known.iter().any(|(known, _)| known == *line);
+            return fixtures.iter().any(|f| f == name) && !listed;
+        })
+        .collect();
+
+    println!(
+        "difftest: {} fixtures, {} known differences",
+        fixtures.len(),
+        known.len()
+    );
+    for (line, detail) in &known {
+        println!("  {}: {}", line, detail);
+    }
+    assert!(
+        failures.is_empty(),
+        "differences not in tests/difftest.allow:\n{}",
+        failures.join("\n")
+    );
+    assert!(
+        stale.is_empty(),
+        "no longer differ, remove from tests/difftest.allow:\n{:?}",
+        stale
+    );
+}
+
+fn read_allow_list() -> Vec<String> {
+    let path = format!("{}/tests/difftest.allow", env!("CARGO_MANIFEST_DIR"));
+    return fs::read_to_string(path)
+        .unwrap_or_default()
+        .lines()
+        .map(|line| line.split('#').next().unwrap_or("").trim().to_string())
+        .filter(|line| !line.is_empty())
+        .collect();
+}
+
+fn from_html5ever(source: &str) -> Vec<Item> {
+    let html = scraper::Html::parse_document(source);
+    return vec![from_element(html.root_element())];
+}
+
+fn from_element(el: scraper::ElementRef) -> Item {
+    let mut children = Vec::new();
+    for child in el.children() {
+        match child.value() {
+            scraper::Node::Element(_) => {
+                children.extend(scraper::ElementRef::wrap(child).map(from_element))
+            }
+            scraper::Node::Text(text) => children.push(Item::Text(text.to_string())),
+            _ => {}
+        }
+    }
+    return Item::Element {
+        name: el.value().name().to_string(),
+        attrs: el
+            .value()
+            .attrs()
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .collect(),
+        children,
+    };
+}
+
+fn from_node(nd: &browser::Node) -> Vec<Item> {
+    return match &nd.data {
+        NodeData::Element {
+            name,
+            attrs,
+            children,
+            ..
+        } => vec![Item::Element {
+            name: name.to_string(),
+            attrs: attrs
+                .iter()
+                .map(|attr| (attr.name.to_string(), attr.value.to_string()))
+                .collect(),
+            children: children.iter().flat_map(from_node).collect(),
+        }],
+        NodeData::Text(s) => vec![Item::Text(s.to_string())],
+        _ => Vec::new(),
+    };
+}
+
+// html, head, bodyを外し、属性を名前の順にし、隣り合ったtextをつないで空白をまとめる
+fn normalize(items: Vec<Item>) -> Vec<Item> {
+    let mut out: Vec<Item> = Vec::new();
+    for item in items {
+        match item {
+            Item::Element { name, children, .. } if TRANSPARENT.contains(&name.as_str()) => {
+                for child in normalize(children) {
+                    push_item(&mut out, child);
+                }
+            }
+            Item::Element {
+                name,
+                mut attrs,
+                children,
+            } => {
+                attrs.sort();
+                push_item(
+                    &mut out,
+                    Item::Element {
+                        name,
+                        attrs,
+                        children: normalize(children),
+                    },
+                );
+            }
+            Item::Text(s) => push_item(&mut out, Item::Text(s)),
+        }
+    }
+    for item in out.iter_mut() {
+        if let Item::Text(s) = item {
+            *s = s.split_whitespace().collect::<Vec<&str>>().join(" ");
+        }
+    }
+    out.retain(|item| !matches!(item, Item::Text(s) if s.is_empty()));
+    return out;
+}
+
+fn push_item(out: &mut Vec<Item>, item: Item) {
+    if let (Some(Item::Text(last)), Item::Text(s)) = (out.last_mut(), &item) {
+        last.push(' ');
+        last.push_str(s);
+        return;
+    }
+    out.push(item);
+}
+
+// 最初に食い違うところの /div[0]/p[1] のようなpathと、その中身
+fn first_difference(expected: &[Item], actual: &[Item], path: &str) -> Option<(String, String)> {
+    for i in 0..expected.len().max(actual.len()) {
+        let (e, a) = match (expected.get(i), actual.get(i)) {
+            (Some(e), Some(a)) => (e, a),
+            (e, a) => {
+                return Some((
+                    format!("{}/[{}]", path, i),
+                    format!("expected {}, found {}", describe(e), describe(a)),
+                ))
+            }
+        };
+        match (e, a) {
+            (
+                Item::Element {
+                    name: en,
+                    attrs: ea,
+                    children: ec,
+                },
+                Item::Element {
+                    name: an,
+                    attrs: aa,
+                    children: ac,
+                },
+            ) if en == an => {
+                let here = format!("{}/{}[{}]", path, en, i);
+                if ea != aa {
+                    return Some((here, format!("attributes {:?} != {:?}", ea, aa)));
+                }
+                if let Some(diff) = first_difference(ec, ac, &here) {
+                    return Some(diff);
+                }
+            }
+            _ if e == a => {}
+            _ => {
+                return Some((
+                    format!("{}/[{}]", path, i),
+                    format!(
+                        "expected {}, found {}",
+                        describe(Some(e)),
+                        describe(Some(a))
+                    ),
+                ))
+            }
+        }
+    }
+    return None;
+}
+
+fn describe(item: Option<&Item>) -> String {
+    return match item {
+        Some(Item::Element { name, .. }) => format!("<{}>", name),
+        Some(Item::Text(s)) => format!("{:?}", s),
+        None => "nothing".to_string(),
+    };
+}
@@ -0,0 +1,133 @@
+#![cfg(all(feature = "parser", feature = "std"))]
+// html5lib-testsのtree-constructionのテスト (.dat) を、このcrateのparserで動かす
+// tests/html5lib/ は元のsuiteのfileをそのまま置いたもの (出どころとlicenseは tests/html5lib/README.md)
+use browser::html::serializer::document_tree_test_format;
+use std::collections::BTreeSet;
+use std::fs;
+
+// 今は通らないcase。1行に「file "#data"」。直ったらここから消す
+// 同じ #data のcaseが複数あれば、1つでも通らないうちは残す
+const KNOWN_FAILURES: &str = include_str!("html5lib_tree.known_failures");
+
+struct Case {
+    data: String,
+    document: String,
+    fragment: bool,
+}
+
+#[test]
+fn html5lib_tree_construction() {
+    let known: BTreeSet<&str> = KNOWN_FAILURES
+        .lines()
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .collect();
+    let dir = format!(
+        "{}/tests/html5lib/tree-construction",
+        env!("CARGO_MANIFEST_DIR")
+    );
+    let mut files: Vec<String> = fs::read_dir(&dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+        .filter(|name| name.ends_with(".dat"))
+        .collect();
+    files.sort();
+
+    let mut failing = BTreeSet::new();
+    let mut failures = Vec::new();
+    let (mut passed, mut skipped) = (0, 0);
+    for file in &files {
+        let source = fs::read_to_string(format!("{}/{}", dir, file)).unwrap();
+        for case in read_cases(&source) {
+            // 断片のparseはまだない
+            if case.fragment {
+                skipped += 1;
+                continue;
+            }
+            let actual = match browser::parse(&case.data) {
+                Ok(doc) => document_tree_test_format(&doc),
+                Err(err) => format!("parse error: {}\n", err),
+            };
+            if actual == case.document {
+                passed += 1;
+                continue;
+            }
+            let key = format!("{} {:?}", file, case.data);
+            if !known.contains(key.as_str()) {
+                failures.push(format!(
+                    "{}\n--- expected\n{}--- actual\n{}",
+                    key, case.document, actual
+                ));
+            }
+            failing.insert(key);
+        }
+    }
+    let fixed: Vec<&&str> = known
+        .iter()
+        .filter(|key| !failing.contains(**key))
+        .collect();
+    println!(
+        "html5lib tree-construction: {} passed, {} known failures, {} skipped",
+        passed,
+        failing.len(),
+        skipped
+    );
+    assert!(
+        failures.is_empty(),
+        "unexpected failures:\n{}",
+        failures.join("\n")
+    );
+    assert!(
+        fixed.is_empty(),
+        "remove from tests/html5lib_tree.known_failures:\n{:?}",
+        fixed
+    );
+}
+
+// #data, #errors, #document などの節に分ける。caseは空行と次の #data で区切られる
+fn read_cases(source: &str) -> Vec<Case> {
+    let mut cases = Vec::new();
+    let mut section = "";
+    let mut data: Vec<&str> = Vec::new();
+    let mut document = String::new();
+    let mut fragment = false;
+    let mut lines = source.lines().peekable();
+    while let Some(line) = lines.next() {
+        if line == "#data" && !section.is_empty() {
+            cases.push(finish_case(&mut data, &mut document, &mut fragment));
+        }
+        if line.starts_with('#') {
+            section = line;
+            if section == "#document-fragment" {
+                fragment = true;
+            }
+            continue;
+        }
+        match section {
+            "#data" => data.push(line),
+            "#document" => {
+                // 次のcaseの前の空行はdocumentに含めない
+                if line.is_empty() && lines.peek().is_none_or(|next| *next == "#data") {
+                    continue;
+                }
+                document += line;
+                document.push('\n');
+            }
+            _ => {}
+        }
+    }
+    if !section.is_empty() {
+        cases.push(finish_case(&mut data, &mut document, &mut fragment));
+    }
+    return cases;
+}
+
+fn finish_case(data: &mut Vec<&str>, document: &mut String, fragment: &mut bool) -> Case {
+    let case = Case {
+        data: data.join("\n"),
+        document: std::mem::take(document),
+        fragment: *fragment,
+    };
+    data.clear();
+    *fragment = false;
+    return case;
+}
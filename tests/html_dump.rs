@@ -0,0 +1,89 @@
+#![cfg(all(feature = "parser", feature = "std"))]
+// html-dumpを実際に起動して、終了コードと出力の要所を見る
+use std::io::Write;
+use std::process::{Command, Output, Stdio};
+
+fn run(args: &[&str], stdin: &str) -> Output {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_html-dump"))
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(stdin.as_bytes())
+        .unwrap();
+    return child.wait_with_output().unwrap();
+}
+
+#[test]
+fn tree_from_stdin() {
+    let out = run(&["tree"], "<div id=\"a\"><p>hi</p><br/></div>");
+    assert!(out.status.success());
+    assert_eq!(
+        String::from_utf8(out.stdout).unwrap(),
+        "<div id=\"a\">\n├── <p>\n│   └── \"hi\"\n└── <br>\n"
+    );
+}
+
+#[test]
+fn tokens_with_positions() {
+    let out = run(&["tokens", "--strict"], "<p>\nhi</p>");
+    assert!(out.status.success());
+    let stdout = String::from_utf8(out.stdout).unwrap();
+    assert!(stdout.starts_with("1:1\tTagBegin\n1:2\tText\t\"p\"\n1:3\tTagEnd\n"));
+    assert!(stdout.contains("\n2:2\tText\t\"hi\"\n"));
+    assert!(stdout.ends_with("Eof\n"));
+}
+
+#[test]
+fn check_exit_codes() {
+    let out = run(&["check"], "<p>ok</p>");
+    assert!(out.status.success());
+    assert_eq!(String::from_utf8(out.stdout).unwrap(), "<stdin>: ok\n");
+
+    let out = run(&["check"], "<p>\n<b>x</b <i>\n</p>");
+    assert_eq!(out.status.code(), Some(1));
+    let stdout = String::from_utf8(out.stdout).unwrap();
+    assert!(stdout.starts_with("error: unexpected token"), "{}", stdout);
+    assert!(
+        stdout.ends_with(" --> <stdin>:2:8\n  |\n2 | <b>x</b <i>\n  |        ^\n"),
+        "{}",
+        stdout
+    );
+
+    let out = run(&["frobnicate"], "");
+    assert_eq!(out.status.code(), Some(2));
+    assert!(String::from_utf8(out.stderr).unwrap().starts_with("usage:"));
+}
+
+#[test]
+fn lenient_repairs_and_warns() {
+    let html = "<div><b>x</p></div>";
+    let out = run(&["tree", "--lenient"], html);
+    assert!(out.status.success());
+    assert_eq!(
+        String::from_utf8(out.stdout).unwrap(),
+        "<div>\n└── <b>\n    └── \"x\"\n"
+    );
+
+    let out = run(&["check", "--lenient"], html);
+    assert!(out.status.success());
+    assert_eq!(
+        String::from_utf8(out.stdout).unwrap(),
+        "warning: close tag without an open tag; ignored\n --> <stdin>:1:13\n\
+         warning: close tag for an outer element; closed the inner ones\n --> <stdin>:1:19\n\
+         <stdin>: 2 warnings\n"
+    );
+
+    // 後に書いたほうが勝つ
+    let out = run(&["check", "--lenient", "--strict"], html);
+    assert_eq!(out.status.code(), Some(1));
+    assert!(String::from_utf8(out.stdout)
+        .unwrap()
+        .starts_with("error: "));
+}
@@ -0,0 +1,39 @@
+// std featureを外したtokenizerとparserが、stdなしでbuildできることを確かめる
+// 手元ではhostのtargetでbuildする (#![no_std] なのでstdを使えばここで落ちる)
+// NO_STD_TARGET=thumbv7em-none-eabihf のように指定すると、そのtargetでbuildする
+use std::env;
+use std::process::Command;
+
+fn build(features: &str) {
+    let mut cmd = Command::new(env!("CARGO"));
+    cmd.arg("build")
+        .arg("--lib")
+        .arg("--manifest-path")
+        .arg(format!("{}/Cargo.toml", env!("CARGO_MANIFEST_DIR")))
+        .arg("--no-default-features")
+        .arg("--features")
+        .arg(features)
+        // 外側のcargoと同じtarget dirを使うと、lockを待ち続けてしまう
+        .arg("--target-dir")
+        .arg(format!("{}/no_std", env!("CARGO_TARGET_TMPDIR")));
+    if let Ok(target) = env::var("NO_STD_TARGET") {
+        cmd.arg("--target").arg(target);
+    }
+    let output = cmd.output().unwrap();
+    assert!(
+        output.status.success(),
+        "no_std build with features {:?} failed:\n{}",
+        features,
+        String::from_utf8_lossy(&output.stderr)
+    );
+}
+
+#[test]
+fn tokenizer_builds_without_std() {
+    build("");
+}
+
+#[test]
+fn parser_builds_without_std() {
+    build("parser");
+}
@@ -0,0 +1,32 @@
+#![cfg(all(feature = "parser", feature = "std"))]
+// crateの外から、rootとpreludeだけで使えることを確かめる
+use browser::prelude::*;
+use std::ops::ControlFlow;
+
+#[test]
+fn parse_and_query_from_root() {
+    let doc: browser::Document =
+        browser::parse("<ul><li class=\"a\">one</li><li>two</li></ul>").unwrap();
+    let items = doc.query_selector_all("li").unwrap();
+    assert_eq!(items.len(), 2);
+    let first: &browser::Node = items[0].node();
+    assert_eq!(first.attr("class").map(|class| class.as_str()), Some("a"));
+    match &first.children()[0].data {
+        browser::NodeData::Text(s) => assert_eq!(s.as_str(), "one"),
+        data => panic!("unexpected {:?}", data),
+    }
+
+    let err: browser::ParseError = browser::parse("<p></div>").unwrap_err();
+    assert!(!err.to_string().is_empty());
+}
+
+#[test]
+fn prelude_is_enough() {
+    let doc: Document = parse("<div><p>a</p><p>b</p></div>").unwrap();
+    let mut depths = Vec::new();
+    doc.walk_elements(|el| {
+        depths.push(el.depth());
+        return ControlFlow::Continue(());
+    });
+    assert_eq!(depths, vec![0, 1, 1]);
+}
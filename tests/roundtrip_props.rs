@@ -0,0 +1,300 @@
+#![cfg(all(feature = "parser", feature = "std"))]
+// 乱数で作った小さな文書で、serialize→parseの往復とtokenの切り方を確かめる
+// 失敗したら、失敗を保ったまま小さくした文書と、その乱数の種を表示する
+// 種を tests/roundtrip.seeds に足すと、次からは毎回その文書も試す
+use browser::html::atom::Atom;
+use browser::html::attributes::Attributes;
+use browser::html::parser::{Attribute, Node};
+use browser::html::serializer::{outer_html, to_tree_test_format};
+use browser::html::shared_str::SharedStr;
+use browser::html::tokenizer::{TokenKind, Tokenizer};
+use std::fs;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const CASES: usize = 256;
+const MAX_DEPTH: usize = 6;
+const TAGS: [&str; 10] = [
+    "div", "p", "span", "a", "b", "i", "ul", "li", "em", "section",
+];
+const ATTRS: [&str; 5] = ["id", "class", "href", "title", "data-x"];
+const WORDS: [&str; 8] = [
+    "a",
+    "hello",
+    "world",
+    "x1",
+    "foo_bar",
+    "42",
+    "caf\u{e9}",
+    "\u{65e5}\u{672c}",
+];
+
+// xorshift64*。同じ種からは同じ文書ができる
+struct Rng(u64);
+
+impl Rng {
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 >> 12;
+        self.0 ^= self.0 << 25;
+        self.0 ^= self.0 >> 27;
+        return self.0.wrapping_mul(0x2545_F491_4F6C_DD1D);
+    }
+
+    fn below(&mut self, n: usize) -> usize {
+        return (self.next() % n as u64) as usize;
+    }
+
+    fn pick<'a>(&mut self, items: &[&'a str]) -> &'a str {
+        return items[self.below(items.len())];
+    }
+}
+
+#[derive(Debug, Clone)]
+enum Gen {
+    Element {
+        name: String,
+        attrs: Vec<(String, String)>,
+        children: Vec<Gen>,
+    },
+    // 空白1つで区切った語
+    Text(Vec<String>),
+}
+
+fn gen_element(rng: &mut Rng, depth: usize) -> Gen {
+    let mut attrs: Vec<(String, String)> = Vec::new();
+    for _ in 0..rng.below(3) {
+        let name = rng.pick(&ATTRS).to_string();
+        if attrs.iter().any(|(n, _)| *n == name) {
+            continue;
+        }
+        let value: Vec<&str> = (0..rng.below(3) + 1).map(|_| rng.pick(&WORDS)).collect();
+        attrs.push((name, value.join(" ")));
+    }
+    let mut children = Vec::new();
+    let n = if depth >= MAX_DEPTH { 0 } else { rng.below(4) };
+    for _ in 0..n {
+        // textは続けて置かない (parseすると区別できない)
+        let after_text = matches!(children.last(), Some(Gen::Text(_)));
+        if !after_text && rng.below(3) == 0 {
+            let words = (0..rng.below(3) + 1)
+                .map(|_| rng.pick(&WORDS).to_string())
+                .collect();
+            children.push(Gen::Text(words));
+        } else {
+            children.push(gen_element(rng, depth + 1));
+        }
+    }
+    return Gen::Element {
+        name: rng.pick(&TAGS).to_string(),
+        attrs,
+        children,
+    };
+}
+
+fn to_node(g: &Gen) -> Vec<Node> {
+    return match g {
+        Gen::Element {
+            name,
+            attrs,
+            children,
+        } => {
+            let mut list = Attributes::new();
+            for (name, value) in attrs {
+                list.push(Attribute {
+                    name: SharedStr::from(name.as_str()),
+                    value: SharedStr::from(value.as_str()),
+                });
+            }
+            let children = children.iter().flat_map(to_node).collect();
+            vec![Node::element(Atom::from(name.as_str()), list, children)]
+        }
+        // parserと同じく語ごとにtext nodeにする
+        Gen::Text(words) => words
+            .iter()
+            .map(|w| Node::text(SharedStr::from(w.as_str())))
+            .collect(),
+    };
+}
+
+// 失敗を保つ、1段小さい候補
+fn shrink(g: &Gen) -> Vec<Gen> {
+    let mut out = Vec::new();
+    match g {
+        Gen::Text(words) if words.len() > 1 => {
+            out.push(Gen::Text(words[..words.len() - 1].to_vec()));
+            out.push(Gen::Text(words[1..].to_vec()));
+        }
+        Gen::Text(_) => {}
+        Gen::Element {
+            name,
+            attrs,
+            children,
+        } => {
+            for child in children {
+                if let Gen::Element { .. } = child {
+                    out.push(child.clone());
+                }
+            }
+            for i in 0..children.len() {
+                let mut fewer = children.clone();
+                fewer.remove(i);
+                out.push(Gen::Element {
+                    name: name.clone(),
+                    attrs: attrs.clone(),
+                    children: fewer,
+                });
+            }
+            for i in 0..attrs.len() {
+                let mut fewer = attrs.clone();
+                fewer.remove(i);
+                out.push(Gen::Element {
+                    name: name.clone(),
+                    attrs: fewer,
+                    children: children.clone(),
+                });
+            }
+            for (i, child) in children.iter().enumerate() {
+                for smaller in shrink(child) {
+                    let mut replaced = children.clone();
+                    replaced[i] = smaller;
+                    out.push(Gen::Element {
+                        name: name.clone(),
+                        attrs: attrs.clone(),
+                        children: replaced,
+                    });
+                }
+            }
+        }
+    }
+    return out;
+}
+
+fn minimize(mut g: Gen, fails: &dyn Fn(&Gen) -> Option<String>) -> (Gen, String) {
+    let mut reason = fails(&g).unwrap_or_default();
+    'outer: loop {
+        for candidate in shrink(&g) {
+            if let Some(r) = fails(&candidate) {
+                g = candidate;
+                reason = r;
+                continue 'outer;
+            }
+        }
+        return (g, reason);
+    }
+}
+
+// 種の一覧。tests/roundtrip.seeds の種と、今回の乱数から作るもの
+fn seeds() -> Vec<u64> {
+    let path = format!("{}/tests/roundtrip.seeds", env!("CARGO_MANIFEST_DIR"));
+    let mut seeds: Vec<u64> = fs::read_to_string(path)
+        .unwrap_or_default()
+        .lines()
+        .map(|line| line.trim())
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(|line| u64::from_str_radix(line.trim_start_matches("0x"), 16).unwrap())
+        .collect();
+    let mut base = match std::env::var("ROUNDTRIP_SEED") {
+        Ok(seed) => u64::from_str_radix(seed.trim_start_matches("0x"), 16).unwrap(),
+        Err(_) => SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(1, |d| d.as_nanos() as u64),
+    };
+    for _ in 0..CASES {
+        base = base
+            .wrapping_mul(6364136223846793005)
+            .wrapping_add(1442695040888963407);
+        seeds.push(base | 1);
+    }
+    return seeds;
+}
+
+fn check(property: &str, fails: &dyn Fn(&Gen) -> Option<String>) {
+    for seed in seeds() {
+        let g = gen_element(&mut Rng(seed), 0);
+        if fails(&g).is_some() {
+            let (small, reason) = minimize(g, fails);
+            let html = outer_html(&to_node(&small)[0]);
+            panic!(
+                "{} failed (seed {:#x}): {}\nminimal input: {}",
+                property, seed, reason, html
+            );
+        }
+    }
+}
+
+#[test]
+fn serialize_then_parse_round_trips() {
+    check("round trip", &|g| {
+        let nodes = to_node(g);
+        let html = outer_html(&nodes[0]);
+        let doc = match browser::parse(&html) {
+            Ok(doc) => doc,
+            Err(err) => return Some(format!("parse error: {}", err)),
+        };
+        let (expected, actual) = (
+            to_tree_test_format(&nodes),
+            to_tree_test_format(&doc.children),
+        );
+        if expected != actual {
+            return Some(format!("expected\n{}actual\n{}", expected, actual));
+        }
+        return None;
+    });
+}
+
+#[test]
+fn parse_is_deterministic() {
+    check("determinism", &|g| {
+        let html = outer_html(&to_node(g)[0]);
+        let first = browser::parse(&html).map(|doc| to_tree_test_format(&doc.children));
+        let second = browser::parse(&html).map(|doc| to_tree_test_format(&doc.children));
+        return match (first, second) {
+            (Ok(a), Ok(b)) if a == b => None,
+            (Err(a), Err(b)) if a.to_string() == b.to_string() => None,
+            _ => Some("two parses differ".to_string()),
+        };
+    });
+}
+
+// tokenの位置は終わりの文字の数。前のtokenの終わりからの文字列が、tokenの中身と合う
+#[test]
+fn token_lexemes_reproduce_input() {
+    check("token lexemes", &|g| {
+        let html = outer_html(&to_node(g)[0]);
+        let chars: Vec<char> = html.chars().collect();
+        let mut rebuilt = String::new();
+        let mut start = 0;
+        let head = Tokenizer::new(&html).tokenize();
+        let mut tok = head.as_deref();
+        while let Some(t) = tok {
+            let end = t.pos.at_whole as usize;
+            if end < start || end > chars.len() {
+                return Some(format!("{:?} at {} is out of order", t.kind, end));
+            }
+            let lexeme: String = chars[start..end].iter().collect();
+            let expected = match t.kind {
+                TokenKind::Text => t.s.to_string(),
+                TokenKind::String => format!("\"{}\"", t.s),
+                TokenKind::Whitespace if lexeme.chars().all(char::is_whitespace) => lexeme.clone(),
+                TokenKind::Eof => String::new(),
+                TokenKind::TagBegin => "<".to_string(),
+                TokenKind::TagEnd => ">".to_string(),
+                TokenKind::Slash => "/".to_string(),
+                TokenKind::Assign => "=".to_string(),
+                TokenKind::Hyphen => "-".to_string(),
+                TokenKind::Excl => "!".to_string(),
+                TokenKind::Amp => "&".to_string(),
+                _ => return Some(format!("unexpected {:?} {:?}", t.kind, lexeme)),
+            };
+            if lexeme != expected {
+                return Some(format!("{:?}: {:?} != {:?}", t.kind, lexeme, expected));
+            }
+            rebuilt += &lexeme;
+            start = end;
+            tok = t.next.as_deref();
+        }
+        if rebuilt != html {
+            return Some(format!("rebuilt {:?}", rebuilt));
+        }
+        return None;
+    });
+}